@@ -0,0 +1,22 @@
+// Closures: 200 mil chamadas a um contador e fib(22) recursivo
+function contador() {
+    let n = 0;
+    function soma() {
+        n = n + 1;
+        return n;
+    }
+    return soma;
+}
+
+let conta = contador();
+let total = 0;
+for (let i = 0; i < 200000; i++) {
+    total = conta();
+}
+console.log(total);
+
+function fib(n) {
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+console.log(fib(22));
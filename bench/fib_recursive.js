@@ -0,0 +1,7 @@
+// Fibonacci recursivo de 30: um monte de chamadas pequenas
+function fib(n) {
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}
+
+console.log(fib(30));
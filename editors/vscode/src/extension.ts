@@ -17,6 +17,10 @@ export function activate(context: vscode.ExtensionContext) {
 
     const clientOptions: LanguageClientOptions = {
         documentSelector: [{ scheme: 'file', language: 'mano' }],
+        initializationOptions: config,
+        synchronize: {
+            configurationSection: 'mano',
+        },
     };
 
     client = new LanguageClient(
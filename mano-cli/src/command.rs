@@ -0,0 +1,154 @@
+//! REPL meta-commands, lines starting with `:`
+
+use mano::Runner;
+
+use crate::report::report_error;
+
+#[derive(Debug, PartialEq)]
+pub enum ReplCommand {
+    /// `:desmonta [nome]` - show the bytecode of a function or the last entry
+    Disassemble(Option<String>),
+    /// `:salvaSessao arquivo` - write the globals to a script
+    SaveSession(String),
+    /// `:carregaSessao arquivo` - run a saved session script
+    LoadSession(String),
+}
+
+impl ReplCommand {
+    /// Parse a `:comando` line. Returns `None` for regular code.
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let rest = line.trim().strip_prefix(':')?;
+        let mut parts = rest.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let args: Vec<&str> = parts.collect();
+
+        Some(match (name, args.as_slice()) {
+            ("desmonta", []) => Ok(Self::Disassemble(None)),
+            ("desmonta", [fita]) => Ok(Self::Disassemble(Some(fita.to_string()))),
+            ("desmonta", _) => Err("Uso: :desmonta [nomeDaFita]".to_string()),
+            ("salvaSessao", [path]) => Ok(Self::SaveSession(path.to_string())),
+            ("salvaSessao", _) => Err("Uso: :salvaSessao arquivo".to_string()),
+            ("carregaSessao", [path]) => Ok(Self::LoadSession(path.to_string())),
+            ("carregaSessao", _) => Err("Uso: :carregaSessao arquivo".to_string()),
+            _ => Err(format!("Comando ':{}' não existe, mano!", name)),
+        })
+    }
+
+    /// Run the command, returning what to print
+    pub fn execute<R: Runner>(&self, runner: &mut R) -> Result<String, String> {
+        match self {
+            Self::Disassemble(name) => runner.disassemble(name.as_deref()),
+            Self::SaveSession(path) => {
+                let script = runner.save_session()?;
+                std::fs::write(path, script)
+                    .map_err(|e| format!("Não rolou salvar em '{}': {}", path, e))?;
+                Ok(format!("Sessão salva em '{}', mano!\n", path))
+            }
+            Self::LoadSession(path) => {
+                let source = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Não rolou abrir '{}': {}", path, e))?;
+                if let Err(errors) = runner.run(&source, std::io::stdout()) {
+                    for error in &errors {
+                        report_error(error, &source, Some(path), std::io::stderr());
+                    }
+                    return Err(format!("Deu ruim carregando a sessão de '{}'", path));
+                }
+                Ok(format!("Sessão de '{}' carregada, mano!\n", path))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mano::Mano;
+
+    #[test]
+    fn parse_regular_code_is_none() {
+        assert_eq!(ReplCommand::parse("salve 1;"), None);
+    }
+
+    #[test]
+    fn parse_desmonta_without_name() {
+        assert_eq!(
+            ReplCommand::parse(":desmonta"),
+            Some(Ok(ReplCommand::Disassemble(None)))
+        );
+    }
+
+    #[test]
+    fn parse_desmonta_with_name() {
+        assert_eq!(
+            ReplCommand::parse("  :desmonta soma "),
+            Some(Ok(ReplCommand::Disassemble(Some("soma".to_string()))))
+        );
+    }
+
+    #[test]
+    fn parse_desmonta_with_extra_args_errors() {
+        assert!(matches!(
+            ReplCommand::parse(":desmonta a b"),
+            Some(Err(msg)) if msg.contains("Uso")
+        ));
+    }
+
+    #[test]
+    fn parse_unknown_command_errors() {
+        assert!(matches!(
+            ReplCommand::parse(":xpto"),
+            Some(Err(msg)) if msg.contains(":xpto")
+        ));
+    }
+
+    #[test]
+    fn execute_desmonta_on_interpreter_errors() {
+        let mut mano = Mano::new();
+        let result = ReplCommand::Disassemble(None).execute(&mut mano);
+        assert!(result.unwrap_err().contains("--vm"));
+    }
+
+    #[test]
+    fn parse_session_commands() {
+        assert_eq!(
+            ReplCommand::parse(":salvaSessao sessao.mano"),
+            Some(Ok(ReplCommand::SaveSession("sessao.mano".to_string())))
+        );
+        assert_eq!(
+            ReplCommand::parse(":carregaSessao sessao.mano"),
+            Some(Ok(ReplCommand::LoadSession("sessao.mano".to_string())))
+        );
+        assert!(matches!(
+            ReplCommand::parse(":salvaSessao"),
+            Some(Err(msg)) if msg.contains("Uso")
+        ));
+    }
+
+    #[test]
+    fn saved_session_loads_in_a_new_runner() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessao.mano").display().to_string();
+
+        let mut before = Mano::new();
+        before.run(
+            "seLiga x = 2; olhaEssaFita dobro(n) { toma n * 2; }",
+            Vec::new(),
+        );
+        ReplCommand::SaveSession(path.clone())
+            .execute(&mut before)
+            .unwrap();
+
+        let mut after = Mano::new();
+        ReplCommand::LoadSession(path).execute(&mut after).unwrap();
+        let mut output = Vec::new();
+        after.run("salve dobro(x);", &mut output);
+        assert_eq!(String::from_utf8(output).unwrap(), "4\n");
+    }
+
+    #[test]
+    fn load_missing_session_errors() {
+        let mut mano = Mano::new();
+        let result = ReplCommand::LoadSession("/nao/existe.mano".to_string()).execute(&mut mano);
+        assert!(result.unwrap_err().contains("/nao/existe.mano"));
+    }
+}
@@ -5,37 +5,176 @@ use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
 use rustyline::validate::Validator;
 use std::cell::RefCell;
+use std::collections::HashMap;
+
+use mano::{Symbol, TokenType};
+
+/// Where the word being completed sits, going by the tokens before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// Start of a statement: any keyword but `vacilou` and `seDeuRuim` fits
+    Statement,
+    /// Start of a statement right after the block of a `sePá`, where
+    /// `vacilou` fits too
+    AfterIf,
+    /// Right after the block of a `tentaAi`, where `seDeuRuim` fits too
+    AfterTry,
+    /// Inside an expression: only keywords that are values or operators
+    Expression,
+}
+
+/// A bracket still open before the word being completed
+enum Open {
+    /// `(`, with the keyword whose condition it holds, if any
+    Paren(Option<TokenType>),
+    /// `{`, with the keyword whose block it is when that's `sePá` or `tentaAi`
+    Brace(Option<TokenType>),
+}
+
+/// Keywords that only make sense at the start of a statement
+fn starts_statement(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Var
+            | TokenType::Print
+            | TokenType::If
+            | TokenType::Else
+            | TokenType::While
+            | TokenType::For
+            | TokenType::Return
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::Class
+            | TokenType::Enum
+            | TokenType::Import
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Throw
+    )
+}
 
 /// Rustyline helper that provides auto-completion for mano REPL
 pub struct ManoHelper {
-    variables: RefCell<Vec<String>>,
+    symbols: RefCell<Vec<Symbol>>,
+    /// Fields and methods of each name, as the runner saw them after the last run
+    members: RefCell<HashMap<String, Vec<Symbol>>>,
 }
 
 impl ManoHelper {
     pub fn new() -> Self {
         Self {
-            variables: RefCell::new(Vec::new()),
+            symbols: RefCell::new(Vec::new()),
+            members: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_symbols(&self, symbols: Vec<Symbol>) {
+        *self.symbols.borrow_mut() = symbols;
+    }
+
+    pub fn set_members(&self, members: HashMap<String, Vec<Symbol>>) {
+        *self.members.borrow_mut() = members;
+    }
+
+    /// The receiver when the word being completed follows `receiver.`
+    ///
+    /// Only plain names are supported; chains like `a.b.` return `None`.
+    fn find_receiver(line: &str, word_start: usize) -> Option<&str> {
+        let before_dot = line[..word_start].strip_suffix('.')?;
+        let receiver_start = Self::find_word_start(before_dot, before_dot.len());
+        let receiver = &before_dot[receiver_start..];
+        if receiver.is_empty() || before_dot[..receiver_start].ends_with('.') {
+            return None;
         }
+        Some(receiver)
     }
 
-    pub fn set_variables(&self, vars: Vec<String>) {
-        *self.variables.borrow_mut() = vars;
+    /// Members of the receiver matching the prefix (an empty prefix lists all)
+    fn get_member_completions(prefix: &str, members: &[Symbol]) -> Vec<Pair> {
+        members
+            .iter()
+            .filter(|member| member.name.starts_with(prefix))
+            .map(|member| Pair {
+                display: format!("{} ({})", member.name, member.kind),
+                replacement: member.name.clone(),
+            })
+            .collect()
     }
 
     /// Find the start position of the current word being typed
     fn find_word_start(line: &str, pos: usize) -> usize {
-        let before_cursor = &line[..pos];
-        // Search backwards for a non-identifier character
-        for (i, c) in before_cursor.char_indices().rev() {
-            if !c.is_alphanumeric() && c != '_' {
-                return i + c.len_utf8();
+        mano::word_at(&line[..pos], pos).start
+    }
+
+    /// Whether code ending in `before` is at the start of a statement.
+    ///
+    /// Only looks at the brackets and the last token, so it can be wrong in
+    /// code that doesn't parse yet, which is most code being typed.
+    fn position(before: &str) -> Position {
+        let mut open = Vec::new();
+        let mut previous = None;
+        // Keyword of the condition the last token closed, `sePá` for `sePá (x)`
+        let mut closed_condition = None;
+        // Keyword of the block the last token closed, `tentaAi` for `tentaAi { }`
+        let mut closed_block = None;
+
+        for token in mano::Scanner::new(before).filter_map(Result::ok) {
+            let token_type = token.token_type;
+            let (mut condition, mut block) = (None, None);
+            match token_type {
+                TokenType::Eof => break,
+                TokenType::LeftParen => {
+                    let keyword = previous.filter(|keyword| {
+                        matches!(keyword, TokenType::If | TokenType::While | TokenType::For)
+                    });
+                    open.push(Open::Paren(keyword));
+                }
+                TokenType::RightParen => {
+                    if let Some(Open::Paren(keyword)) = open.pop() {
+                        condition = keyword;
+                    }
+                }
+                TokenType::LeftBrace => {
+                    let keyword = match (closed_condition, previous) {
+                        (Some(TokenType::If), _) => Some(TokenType::If),
+                        (_, Some(TokenType::Try)) => Some(TokenType::Try),
+                        _ => None,
+                    };
+                    open.push(Open::Brace(keyword));
+                }
+                TokenType::RightBrace => {
+                    if let Some(Open::Brace(keyword)) = open.pop() {
+                        block = keyword;
+                    }
+                }
+                _ => {}
+            }
+            previous = Some(token_type);
+            closed_condition = condition;
+            closed_block = block;
+        }
+
+        if let Some(Open::Paren(_)) = open.last() {
+            return Position::Expression;
+        }
+        match previous {
+            None | Some(TokenType::Semicolon | TokenType::LeftBrace | TokenType::Else) => {
+                Position::Statement
             }
+            Some(TokenType::RightBrace) => match closed_block {
+                Some(TokenType::If) => Position::AfterIf,
+                Some(TokenType::Try) => Position::AfterTry,
+                _ => Position::Statement,
+            },
+            Some(TokenType::RightParen) if closed_condition.is_some() => Position::Statement,
+            _ => Position::Expression,
         }
-        0
     }
 
-    /// Get completion candidates for the given prefix
-    fn get_completions(prefix: &str, variables: &[String]) -> Vec<String> {
+    /// Get completion candidates for the given prefix.
+    /// Symbols show their kind next to the name, e.g. `soma (fita)`.
+    /// Keywords are only offered where they fit.
+    fn get_completions(prefix: &str, position: Position, symbols: &[Symbol]) -> Vec<Pair> {
         if prefix.is_empty() {
             return Vec::new();
         }
@@ -43,16 +182,28 @@ impl ManoHelper {
         let mut completions = Vec::new();
 
         // Add matching keywords
-        for (keyword, _) in mano::KEYWORDS.entries() {
-            if keyword.starts_with(prefix) {
-                completions.push((*keyword).to_string());
+        for (keyword, &token_type) in mano::KEYWORDS.entries() {
+            let fits = match position {
+                Position::Statement => !matches!(token_type, TokenType::Else | TokenType::Catch),
+                Position::AfterIf => token_type != TokenType::Catch,
+                Position::AfterTry => token_type != TokenType::Else,
+                Position::Expression => !starts_statement(token_type),
+            };
+            if fits && keyword.starts_with(prefix) {
+                completions.push(Pair {
+                    display: (*keyword).to_string(),
+                    replacement: (*keyword).to_string(),
+                });
             }
         }
 
-        // Add matching variables
-        for var in variables {
-            if var.starts_with(prefix) {
-                completions.push(var.clone());
+        // Add matching symbols
+        for symbol in symbols {
+            if symbol.name.starts_with(prefix) {
+                completions.push(Pair {
+                    display: format!("{} ({})", symbol.name, symbol.kind),
+                    replacement: symbol.name.clone(),
+                });
             }
         }
 
@@ -93,12 +244,13 @@ impl ManoHelper {
                     let color = match token.token_type {
                         mano::TokenType::Comment => Some(COMMENT),
                         mano::TokenType::String
+                        | mano::TokenType::Bytes
                         | mano::TokenType::StringStart
                         | mano::TokenType::StringMiddle
                         | mano::TokenType::StringEnd => Some(STRING),
                         mano::TokenType::Number => Some(NUMBER),
                         mano::TokenType::Identifier => {
-                            if variables.contains(&token.lexeme) {
+                            if variables.iter().any(|v| **v == *token.lexeme) {
                                 Some(VARIABLE)
                             } else {
                                 None
@@ -107,6 +259,7 @@ impl ManoHelper {
                         // Keywords
                         mano::TokenType::And
                         | mano::TokenType::Class
+                        | mano::TokenType::Enum
                         | mano::TokenType::Else
                         | mano::TokenType::False
                         | mano::TokenType::Fun
@@ -121,7 +274,12 @@ impl ManoHelper {
                         | mano::TokenType::True
                         | mano::TokenType::Var
                         | mano::TokenType::While
-                        | mano::TokenType::Break => Some(KEYWORD),
+                        | mano::TokenType::Break
+                        | mano::TokenType::Continue
+                        | mano::TokenType::Import
+                        | mano::TokenType::Try
+                        | mano::TokenType::Catch
+                        | mano::TokenType::Throw => Some(KEYWORD),
                         // Operators and punctuation - no highlighting
                         _ => None,
                     };
@@ -156,7 +314,12 @@ impl Helper for ManoHelper {}
 
 impl Highlighter for ManoHelper {
     fn highlight<'l>(&self, line: &'l str, _pos: usize) -> std::borrow::Cow<'l, str> {
-        let variables = self.variables.borrow();
+        let variables: Vec<String> = self
+            .symbols
+            .borrow()
+            .iter()
+            .map(|symbol| symbol.name.clone())
+            .collect();
         std::borrow::Cow::Owned(Self::highlight_line(line, &variables))
     }
 
@@ -186,24 +349,40 @@ impl Completer for ManoHelper {
     ) -> rustyline::Result<(usize, Vec<Pair>)> {
         let start = Self::find_word_start(line, pos);
         let prefix = &line[start..pos];
-        let variables = self.variables.borrow();
-        let completions = Self::get_completions(prefix, &variables);
-
-        let pairs: Vec<Pair> = completions
-            .into_iter()
-            .map(|s| Pair {
-                display: s.clone(),
-                replacement: s,
-            })
-            .collect();
 
-        Ok((start, pairs))
+        if let Some(receiver) = Self::find_receiver(line, start) {
+            let members = self.members.borrow();
+            let pairs = members
+                .get(receiver)
+                .map(|members| Self::get_member_completions(prefix, members))
+                .unwrap_or_default();
+            return Ok((start, pairs));
+        }
+
+        let symbols = self.symbols.borrow();
+        let position = Self::position(&line[..start]);
+        Ok((start, Self::get_completions(prefix, position, &symbols)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use mano::SymbolKind;
+
+    fn variables(names: &[&str]) -> Vec<Symbol> {
+        names
+            .iter()
+            .map(|name| Symbol {
+                name: name.to_string(),
+                kind: SymbolKind::Variable,
+            })
+            .collect()
+    }
+
+    fn replacements(pairs: Vec<Pair>) -> Vec<String> {
+        pairs.into_iter().map(|pair| pair.replacement).collect()
+    }
 
     // ManoHelper integration tests
 
@@ -239,7 +418,7 @@ mod tests {
     #[test]
     fn helper_completes_variables() {
         let helper = ManoHelper::new();
-        helper.set_variables(vec!["contador".to_string()]);
+        helper.set_symbols(variables(&["contador"]));
         let (start, pairs) = helper
             .complete(
                 "salve con",
@@ -254,8 +433,8 @@ mod tests {
     #[test]
     fn helper_updates_variables() {
         let helper = ManoHelper::new();
-        helper.set_variables(vec!["x".to_string()]);
-        helper.set_variables(vec!["y".to_string()]);
+        helper.set_symbols(variables(&["x"]));
+        helper.set_symbols(variables(&["y"]));
         let (_, pairs) = helper
             .complete(
                 "y",
@@ -274,8 +453,62 @@ mod tests {
         assert!(pairs.is_empty());
     }
 
+    fn member(name: &str, kind: SymbolKind) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn helper_completes_members_after_dot() {
+        let helper = ManoHelper::new();
+        helper.set_members(HashMap::from([(
+            "rex".to_string(),
+            vec![
+                member("nome", SymbolKind::Field),
+                member("latir", SymbolKind::Method),
+            ],
+        )]));
+        let history = rustyline::history::DefaultHistory::new();
+        let ctx = Context::new(&history);
+
+        let (start, pairs) = helper.complete("rex.", 4, &ctx).unwrap();
+        assert_eq!(start, 4);
+        assert_eq!(replacements(pairs), vec!["nome", "latir"]);
+
+        let (start, pairs) = helper.complete("salve rex.la", 12, &ctx).unwrap();
+        assert_eq!(start, 10);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].display, "latir (método)");
+    }
+
+    #[test]
+    fn helper_dot_on_unknown_receiver_completes_nothing() {
+        let helper = ManoHelper::new();
+        helper.set_symbols(variables(&["salario"]));
+        let history = rustyline::history::DefaultHistory::new();
+        let ctx = Context::new(&history);
+        let (_, pairs) = helper.complete("x.sal", 5, &ctx).unwrap();
+        assert!(pairs.is_empty());
+    }
+
     // Unit tests for helper methods
 
+    #[test]
+    fn find_receiver_after_dot() {
+        assert_eq!(ManoHelper::find_receiver("rex.", 4), Some("rex"));
+        assert_eq!(ManoHelper::find_receiver("salve ação.x", 13), Some("ação"));
+        assert_eq!(ManoHelper::find_receiver("_2.", 3), Some("_2"));
+    }
+
+    #[test]
+    fn find_receiver_none_without_dot_or_for_chains() {
+        assert_eq!(ManoHelper::find_receiver("salve rex", 6), None);
+        assert_eq!(ManoHelper::find_receiver(".x", 1), None);
+        assert_eq!(ManoHelper::find_receiver("a.b.", 4), None);
+    }
+
     #[test]
     fn find_word_start_at_beginning() {
         assert_eq!(ManoHelper::find_word_start("sal", 3), 0);
@@ -298,13 +531,14 @@ mod tests {
 
     #[test]
     fn get_completions_matches_keywords() {
-        let completions = ManoHelper::get_completions("sal", &[]);
+        let completions =
+            replacements(ManoHelper::get_completions("sal", Position::Statement, &[]));
         assert!(completions.contains(&"salve".to_string()));
     }
 
     #[test]
     fn get_completions_matches_multiple_keywords() {
-        let completions = ManoHelper::get_completions("se", &[]);
+        let completions = replacements(ManoHelper::get_completions("se", Position::Statement, &[]));
         assert!(completions.contains(&"seLiga".to_string()));
         assert!(completions.contains(&"sePá".to_string()));
         assert!(completions.contains(&"seVira".to_string()));
@@ -313,40 +547,203 @@ mod tests {
 
     #[test]
     fn get_completions_matches_variables() {
-        let vars = vec!["contador".to_string(), "nome".to_string()];
-        let completions = ManoHelper::get_completions("con", &vars);
+        let vars = variables(&["contador", "nome"]);
+        let completions = replacements(ManoHelper::get_completions(
+            "con",
+            Position::Statement,
+            &vars,
+        ));
         assert!(completions.contains(&"contador".to_string()));
         assert!(!completions.contains(&"nome".to_string()));
     }
 
     #[test]
     fn get_completions_matches_both_keywords_and_variables() {
-        let vars = vec!["salário".to_string()];
-        let completions = ManoHelper::get_completions("sal", &vars);
+        let vars = variables(&["salário"]);
+        let completions = replacements(ManoHelper::get_completions(
+            "sal",
+            Position::Statement,
+            &vars,
+        ));
         assert!(completions.contains(&"salve".to_string()));
         assert!(completions.contains(&"salário".to_string()));
     }
 
     #[test]
     fn get_completions_empty_prefix_returns_empty() {
-        let completions = ManoHelper::get_completions("", &["x".to_string()]);
+        let completions = replacements(ManoHelper::get_completions(
+            "",
+            Position::Statement,
+            &variables(&["x"]),
+        ));
         assert!(completions.is_empty());
     }
 
     #[test]
     fn get_completions_no_match_returns_empty() {
-        let completions = ManoHelper::get_completions("xyz", &[]);
+        let completions =
+            replacements(ManoHelper::get_completions("xyz", Position::Statement, &[]));
         assert!(completions.is_empty());
     }
 
     #[test]
     fn get_completions_unicode_prefix_matches() {
-        let vars = vec!["salário".to_string(), "salame".to_string()];
-        let completions = ManoHelper::get_completions("salá", &vars);
+        let vars = variables(&["salário", "salame"]);
+        let completions = replacements(ManoHelper::get_completions(
+            "salá",
+            Position::Statement,
+            &vars,
+        ));
         assert!(completions.contains(&"salário".to_string()));
         assert!(!completions.contains(&"salame".to_string()));
     }
 
+    #[test]
+    fn position_is_a_statement_at_the_start_and_after_blocks() {
+        for before in [
+            "",
+            "salve 1; ",
+            "{ ",
+            "segueOFluxo (x < 3) ",
+            "seVira (seLiga i = 0; i < 3; i = i + 1) ",
+            "sePá (x) salve 1; vacilou ",
+            "segueOFluxo (firmeza) { } ",
+        ] {
+            assert_eq!(
+                ManoHelper::position(before),
+                Position::Statement,
+                "{before:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn position_is_an_expression_after_operators_and_inside_parens() {
+        for before in [
+            "salve ",
+            "seLiga x = ",
+            "1 + ",
+            "sePá (",
+            "seVira (seLiga i = 0; ",
+            "f(olhaEssaFita () { toma 1; }, ",
+            "salve \"oi {",
+        ] {
+            assert_eq!(
+                ManoHelper::position(before),
+                Position::Expression,
+                "{before:?}"
+            );
+        }
+        // Inside a lambda's block statements start again
+        assert_eq!(
+            ManoHelper::position("f(olhaEssaFita () { "),
+            Position::Statement
+        );
+    }
+
+    #[test]
+    fn position_after_the_block_of_a_se_pa_takes_vacilou() {
+        assert_eq!(
+            ManoHelper::position("sePá (x) { salve 1; } "),
+            Position::AfterIf
+        );
+        assert_eq!(
+            ManoHelper::position("sePá (x) { sePá (y) { } } "),
+            Position::AfterIf
+        );
+        assert_eq!(
+            ManoHelper::position("sePá (x) { } vacilou { } "),
+            Position::Statement
+        );
+    }
+
+    #[test]
+    fn position_after_the_block_of_a_tenta_ai_takes_se_deu_ruim() {
+        assert_eq!(
+            ManoHelper::position("tentaAi { f(); } "),
+            Position::AfterTry
+        );
+        assert_eq!(
+            ManoHelper::position("tentaAi { sePá (x) { } } "),
+            Position::AfterTry
+        );
+        assert_eq!(
+            ManoHelper::position("tentaAi { } seDeuRuim (e) { } "),
+            Position::Statement
+        );
+        // Only the keyword right before the brace opens a tentaAi block
+        assert_eq!(ManoHelper::position("tentaAi { { } "), Position::Statement);
+    }
+
+    #[test]
+    fn get_completions_skips_statement_keywords_in_expressions() {
+        let completions = replacements(ManoHelper::get_completions("s", Position::Expression, &[]));
+        assert!(!completions.contains(&"salve".to_string()));
+        assert!(!completions.contains(&"seLiga".to_string()));
+        let completions = replacements(ManoHelper::get_completions("o", Position::Expression, &[]));
+        assert!(completions.contains(&"oCara".to_string()));
+        assert!(completions.contains(&"olhaEssaFita".to_string()));
+    }
+
+    #[test]
+    fn get_completions_offers_vacilou_only_after_a_se_pa_block() {
+        for (position, offered) in [
+            (Position::AfterIf, true),
+            (Position::Statement, false),
+            (Position::Expression, false),
+        ] {
+            let completions = replacements(ManoHelper::get_completions("vac", position, &[]));
+            assert_eq!(completions.contains(&"vacilou".to_string()), offered);
+        }
+    }
+
+    #[test]
+    fn get_completions_offers_se_deu_ruim_only_after_a_tenta_ai_block() {
+        for (position, offered) in [
+            (Position::AfterTry, true),
+            (Position::AfterIf, false),
+            (Position::Statement, false),
+            (Position::Expression, false),
+        ] {
+            let completions = replacements(ManoHelper::get_completions("seD", position, &[]));
+            assert_eq!(completions.contains(&"seDeuRuim".to_string()), offered);
+        }
+    }
+
+    #[test]
+    fn helper_completes_keywords_that_fit_where_the_cursor_is() {
+        let helper = ManoHelper::new();
+        let history = rustyline::history::DefaultHistory::new();
+        let ctx = Context::new(&history);
+
+        let (_, pairs) = helper.complete("salve se", 8, &ctx).unwrap();
+        assert!(pairs.is_empty(), "{:?}", replacements(pairs));
+
+        let (_, pairs) = helper.complete("sePá (x) { } vac", 17, &ctx).unwrap();
+        assert_eq!(replacements(pairs), vec!["vacilou"]);
+
+        let (_, pairs) = helper.complete("tentaAi { f(); } seD", 20, &ctx).unwrap();
+        assert_eq!(replacements(pairs), vec!["seDeuRuim"]);
+    }
+
+    #[test]
+    fn get_completions_shows_symbol_kind() {
+        let symbols = vec![
+            Symbol {
+                name: "soma".to_string(),
+                kind: SymbolKind::Function,
+            },
+            Symbol {
+                name: "Sonho".to_string(),
+                kind: SymbolKind::Class,
+            },
+        ];
+        let pairs = ManoHelper::get_completions("so", Position::Statement, &symbols);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].display, "soma (fita)");
+        assert_eq!(pairs[0].replacement, "soma");
+    }
+
     // Highlighter tests
 
     #[test]
@@ -439,7 +836,7 @@ mod tests {
         use rustyline::highlight::Highlighter;
 
         let helper = ManoHelper::new();
-        helper.set_variables(vec!["meuVar".to_string()]);
+        helper.set_symbols(variables(&["meuVar"]));
 
         let result = helper.highlight("salve meuVar", 0);
         // Should highlight both keyword and variable
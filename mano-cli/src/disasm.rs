@@ -0,0 +1,135 @@
+//! `mano disasm`: the bytecode the VM would run for a script, with its
+//! constants and the source line of each instruction, without running it
+
+use std::io::{self, Write};
+
+use mano::LineIndex;
+use mano_vm::{Chunk, Operand, decode_chunk, disassemble_instruction};
+
+use crate::json::json_string;
+
+/// Writes the listing of `chunk`, compiled from `source`, to `out`
+pub fn dump<W: Write>(
+    chunk: &Chunk,
+    source: &str,
+    name: &str,
+    json: bool,
+    mut out: W,
+) -> io::Result<()> {
+    let index = LineIndex::new(source);
+    if json {
+        return writeln!(out, "{}", json_listing(chunk, &index));
+    }
+
+    writeln!(out, "== {name} ==")?;
+    writeln!(out, "-- constantes --")?;
+    for (i, value) in chunk.constants().iter().enumerate() {
+        writeln!(out, "{:>4} {:<8} '{}'", i, value.kind_name(), value)?;
+    }
+    writeln!(out, "-- código --")?;
+    let mut last_line = None;
+    for instruction in decode_chunk(chunk) {
+        // Only the first instruction of each line shows it, like clox
+        let line = index.line_col(instruction.span.start).line + 1;
+        let line_str = if last_line == Some(line) {
+            "   |".to_string()
+        } else {
+            format!("{line:>4}")
+        };
+        last_line = Some(line);
+        let (text, _) = disassemble_instruction(chunk, instruction.offset);
+        write!(out, "{line_str} {text}")?;
+    }
+    Ok(())
+}
+
+fn json_listing(chunk: &Chunk, index: &LineIndex) -> String {
+    let constants: Vec<String> = chunk
+        .constants()
+        .iter()
+        .map(|value| {
+            format!(
+                r#"{{"kind":{},"value":{}}}"#,
+                json_string(value.kind_name()),
+                json_string(&value.to_string())
+            )
+        })
+        .collect();
+    let code: Vec<String> = decode_chunk(chunk)
+        .iter()
+        .map(|instruction| {
+            let op = match instruction.opcode {
+                Some(opcode) => json_string(opcode.info().name),
+                None => "null".to_string(),
+            };
+            let operand = match instruction.operand {
+                Operand::None => String::new(),
+                Operand::Constant(i) => format!(r#","constant":{i}"#),
+                Operand::Target(target) => format!(r#","target":{target}"#),
+            };
+            format!(
+                r#"{{"offset":{},"op":{},"byte":{}{},"span":[{},{}],"line":{}}}"#,
+                instruction.offset,
+                op,
+                instruction.byte,
+                operand,
+                instruction.span.start,
+                instruction.span.end,
+                index.line_col(instruction.span.start).line + 1,
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"constants":[{}],"code":[{}]}}"#,
+        constants.join(","),
+        code.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mano_vm::CompileOptions;
+
+    fn dump_to_string(source: &str, json: bool) -> String {
+        let chunk = mano_vm::compile(source, &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        let mut out = Vec::new();
+        dump(&chunk, source, "teste", json, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn dump_lists_constants_and_code_with_lines() {
+        let out = dump_to_string("1 +\n\"oi\"", false);
+
+        assert_eq!(
+            out,
+            "== teste ==\n\
+             -- constantes --\n\
+             \x20  0 número   '1'\n\
+             \x20  1 texto    'oi'\n\
+             -- código --\n\
+             \x20  1 0000 0..1 OP_CONSTANT         0 '1'\n\
+             \x20  2 0002 4..8 OP_CONSTANT         1 'oi'\n\
+             \x20  | 0004    | OP_ADD\n\
+             \x20  | 0005 8..8 OP_RETURN\n"
+        );
+    }
+
+    #[test]
+    fn dump_as_json_resolves_operands() {
+        let out = dump_to_string("1", true);
+
+        assert_eq!(
+            out,
+            concat!(
+                r#"{"constants":[{"kind":"número","value":"1"}],"code":["#,
+                r#"{"offset":0,"op":"OP_CONSTANT","byte":1,"constant":0,"span":[0,1],"line":1},"#,
+                r#"{"offset":2,"op":"OP_RETURN","byte":0,"span":[1,1],"line":1}]}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Ctrl+C while code runs
+//!
+//! A script stuck in a loop stops at its next statement instead of taking
+//! the whole process down with it, so what it printed so far stays and the
+//! REPL keeps going. Outside of a run, or when the code didn't stop after the
+//! first Ctrl+C (a native waiting on something), Ctrl+C quits right away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Exit status for a process stopped by SIGINT, like shells report it
+pub const EXIT_CODE: u8 = 130;
+
+/// Whether some code is running now, to be interrupted instead of quitting
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// What the handler sets for the runner to notice, once installed
+static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Installs the Ctrl+C handler, giving the flag it sets for the runner
+pub fn install() -> Arc<AtomicBool> {
+    let flag = Arc::clone(FLAG.get_or_init(Arc::default));
+    let handler_flag = Arc::clone(&flag);
+    let installed = ctrlc::set_handler(move || {
+        // A second Ctrl+C before the first one was noticed quits too
+        if !RUNNING.load(Ordering::SeqCst) || handler_flag.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_CODE.into());
+        }
+    });
+    if let Err(error) = installed {
+        eprintln!("Não deu pra cuidar do Ctrl+C, mano: {error}");
+    }
+    flag
+}
+
+/// Runs `f` as running code, so Ctrl+C interrupts it instead of quitting
+pub fn while_running<T>(f: impl FnOnce() -> T) -> T {
+    RUNNING.store(true, Ordering::SeqCst);
+    let result = f();
+    RUNNING.store(false, Ordering::SeqCst);
+    // A Ctrl+C after the last statement has nothing left to stop
+    if let Some(flag) = FLAG.get() {
+        flag.store(false, Ordering::SeqCst);
+    }
+    result
+}
@@ -1,30 +1,57 @@
+mod command;
 mod completer;
+mod disasm;
+mod interrupt;
+mod json;
+mod plugin;
 mod report;
 mod state;
+mod tokens;
 mod vm;
 
+use std::ffi::OsString;
 use std::fs;
-use std::io::{self, IsTerminal, Read};
+use std::io::{self, BufRead, IsTerminal, Read};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Instant;
 
-use clap::Parser;
-use mano::{Mano, ManoError, Runner};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use mano::{Dialect, FormatOptions, Mano, ManoError, Runner, SandboxPolicy, SourceMap};
 use rustyline::Editor;
 use rustyline::error::ReadlineError;
 
+use command::ReplCommand;
 use completer::ManoHelper;
 use report::report_error;
-use state::ReplState;
+use state::{Interrupt, ReplState};
 use vm::Vm;
 
 #[derive(Parser)]
 #[command(name = "mano")]
 #[command(about = "Interpretador da linguagem mano - a linguagem dos cria", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Script file to execute
     script: Option<PathBuf>,
 
+    /// Execute a code string directly instead of a script file
+    #[arg(
+        short = 'e',
+        long = "eval",
+        value_name = "CODE",
+        conflicts_with = "script"
+    )]
+    eval: Option<String>,
+
+    /// Auto-print the value of the last expression (with -e)
+    #[arg(short = 'p', long = "print", requires = "eval")]
+    print: bool,
+
     /// Use the bytecode VM instead of the tree-walk interpreter
     #[arg(long)]
     vm: bool,
@@ -32,22 +59,237 @@ struct Args {
     /// Enable debug tracing (VM mode only)
     #[arg(long)]
     debug: bool,
+
+    /// Trace only some events: calls, stack, ops, comma-separated (VM mode only)
+    #[arg(long, value_name = "O_QUE")]
+    trace: Option<mano_vm::Trace>,
+
+    /// Keyword set to accept besides mano's (a `// dialeto:` line in the code wins)
+    #[arg(long, value_name = "NOME", default_value_t = Dialect::Mano)]
+    dialect: Dialect,
+
+    /// Turn on `#se NOME` blocks in the code (repeatable)
+    #[arg(long = "define", short = 'D', value_name = "NOME")]
+    defines: Vec<String>,
+
+    /// Refuse to run code with warnings, like a fita declared twice in a script
+    #[arg(long = "estrito")]
+    strict: bool,
+
+    /// Show the values involved when an operator fails (interpreter only)
+    #[arg(long = "explica", conflicts_with = "vm")]
+    explain: bool,
+
+    /// Seed for aleatorio and aleatorioEntre, so runs repeat the same numbers (interpreter only)
+    #[arg(long = "semente", value_name = "N", conflicts_with = "vm")]
+    seed: Option<u64>,
+
+    /// Let the script run shell commands with rodaComando (interpreter only)
+    #[arg(long = "libera-comandos", conflicts_with = "vm")]
+    allow_commands: bool,
+
+    /// Let `+` join texto with a number, lógico or nadaNão, turning it into texto (interpreter only)
+    #[arg(long = "junta-texto", conflicts_with = "vm")]
+    text_coercion: bool,
+
+    /// Leave out the prelude's fitas (maior, repete, ...) (interpreter only)
+    #[arg(long = "sem-prelude", conflicts_with = "vm")]
+    no_prelude: bool,
+
+    /// Refuse scripts bigger than this many bytes, instead of loading them whole
+    #[arg(long = "limite-tamanho", value_name = "BYTES", default_value_t = MAX_SCRIPT_SIZE)]
+    max_size: u64,
+
+    /// Arguments for the script, after `--` (read with `pegaOpcao`)
+    #[arg(last = true, value_name = "ARGS")]
+    script_args: Vec<String>,
+
+    /// Start the REPL even when stdin isn't a terminal
+    #[arg(long, conflicts_with_all = ["script", "eval", "no_repl"])]
+    repl: bool,
+
+    /// Run stdin as one script even when it's a terminal
+    #[arg(long = "no-repl", conflicts_with_all = ["script", "eval"])]
+    no_repl: bool,
+
+    /// Run stdin line by line like the REPL, printing values but no prompts
+    #[arg(
+        long = "interactive-output",
+        conflicts_with_all = ["script", "eval", "repl", "no_repl"]
+    )]
+    interactive_output: bool,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two scripts by their syntax, ignoring formatting
+    #[command(name = "diff-ast")]
+    DiffAst { old: PathBuf, new: PathBuf },
+
+    /// Re-indent scripts in place
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Only list the files that would change, exiting with 1 if any would
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Print the tokens the scanner makes of a script or `-e` code
+    Tokens {
+        #[arg(required_unless_present = "eval")]
+        script: Option<PathBuf>,
+
+        /// Code to scan instead of a script file
+        #[arg(
+            short = 'e',
+            long = "eval",
+            value_name = "CODE",
+            conflicts_with = "script"
+        )]
+        eval: Option<String>,
+
+        /// Print the tokens as a JSON array
+        #[arg(long)]
+        json: bool,
+
+        /// Keyword set to accept besides mano's (a `// dialeto:` line in the code wins)
+        #[arg(long, value_name = "NOME", default_value_t = Dialect::Mano)]
+        dialect: Dialect,
+    },
+
+    /// Print the bytecode the VM would run for a script, without running it
+    Disasm {
+        #[arg(required_unless_present = "eval")]
+        script: Option<PathBuf>,
+
+        /// Code to compile instead of a script file
+        #[arg(
+            short = 'e',
+            long = "eval",
+            value_name = "CODE",
+            conflicts_with = "script"
+        )]
+        eval: Option<String>,
+
+        /// Print the constants and instructions as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Keyword set to accept besides mano's (a `// dialeto:` line in the code wins)
+        #[arg(long, value_name = "NOME", default_value_t = Dialect::Mano)]
+        dialect: Dialect,
+
+        /// Turn on `#se NOME` blocks in the code (repeatable)
+        #[arg(long = "define", short = 'D', value_name = "NOME")]
+        defines: Vec<String>,
+    },
+}
+
+/// Runs everything on a thread with the stack the interpreter measures deep
+/// code against, so it stops with an error instead of overflowing
 fn main() -> ExitCode {
-    let args = Args::parse();
+    std::thread::Builder::new()
+        .stack_size(mano::STACK_SIZE)
+        .spawn(run)
+        .expect("Failed to start the main thread")
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn run() -> ExitCode {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    if let Some(path) = argv.get(1).and_then(plugin_for) {
+        return plugin::run(&path, &argv[2..]);
+    }
 
+    // Only scan PATH for plugins when there's help to list them in
+    let matches = match Args::command().try_get_matches_from(&argv) {
+        Ok(matches) => matches,
+        Err(e) if e.kind() == ErrorKind::DisplayHelp => {
+            with_plugins(Args::command()).get_matches_from(argv)
+        }
+        Err(e) => e.exit(),
+    };
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    if let Some(command) = &args.command {
+        let result = match command {
+            Command::DiffAst { old, new } => diff_ast(old, new),
+            Command::Fmt { files, check } => format_files(files, *check),
+            Command::Tokens {
+                script,
+                eval,
+                json,
+                dialect,
+            } => print_tokens(script.as_deref(), eval.as_deref(), *dialect, *json),
+            Command::Disasm {
+                script,
+                eval,
+                json,
+                dialect,
+                defines,
+            } => {
+                let options = mano_vm::CompileOptions {
+                    dialect: *dialect,
+                    defines: defines.clone(),
+                    ..Default::default()
+                };
+                print_disassembly(script.as_deref(), eval.as_deref(), &options, *json)
+            }
+        };
+        return match result {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::from(1),
+            Err(e) => {
+                let msg = e.to_string();
+                if !msg.is_empty() {
+                    eprintln!("{e}");
+                }
+                ExitCode::from(65)
+            }
+        };
+    }
+
+    let interrupt = interrupt::install();
     let result = if args.vm {
         let mut vm = Vm::new();
         vm.set_debug(args.debug);
-        run_mode(&mut vm, args.script.as_deref())
+        vm.set_trace(args.trace.unwrap_or_default());
+        vm.set_dialect(args.dialect);
+        vm.set_defines(args.defines.clone());
+        vm.set_strict(args.strict);
+        vm.set_interrupt(interrupt);
+        run_mode(&mut vm, &args)
     } else {
-        let mut mano = Mano::new();
-        run_mode(&mut mano, args.script.as_deref())
+        let policy = SandboxPolicy {
+            seed: args.seed,
+            process: args.allow_commands,
+            ..SandboxPolicy::default()
+        };
+        let mut mano = if args.no_prelude {
+            Mano::without_prelude(policy)
+        } else {
+            Mano::new_with_policy(policy)
+        };
+        mano.set_stack_size(mano::STACK_SIZE);
+        mano.set_explain(args.explain);
+        mano.set_text_coercion(args.text_coercion);
+        mano.set_dialect(args.dialect);
+        mano.set_defines(args.defines.clone());
+        mano.set_strict(args.strict);
+        mano.set_args(args.script_args.clone());
+        if let Some(dir) = args.script.as_deref().and_then(Path::parent) {
+            mano.set_script_dir(dir);
+        }
+        mano.set_interrupt(interrupt);
+        run_mode(&mut mano, &args)
     };
 
     match result {
         Ok(()) => ExitCode::SUCCESS,
+        Err(ManoError::Interrupted { .. }) => ExitCode::from(interrupt::EXIT_CODE),
         Err(e) => {
             let msg = e.to_string();
             if !msg.is_empty() {
@@ -58,45 +300,322 @@ fn main() -> ExitCode {
     }
 }
 
-fn run_mode<R: Runner>(runner: &mut R, script: Option<&Path>) -> Result<(), ManoError> {
-    match script {
-        Some(path) => run_file(runner, path),
-        None => {
-            if io::stdin().is_terminal() {
-                run_repl(runner)
-            } else {
-                run_stdin(runner)
-            }
-        }
+/// Lists the plugins on PATH at the end of the help
+fn with_plugins(command: clap::Command) -> clap::Command {
+    let plugins = plugin::discover();
+    if plugins.is_empty() {
+        return command;
+    }
+    command.after_help(format!(
+        "Plugins (mano-<nome> no PATH):\n  {}",
+        plugins.join("\n  ")
+    ))
+}
+
+/// The plugin to run for `mano <arg>`: scripts and built-in subcommands win
+fn plugin_for(arg: &OsString) -> Option<PathBuf> {
+    let name = arg.to_str()?;
+    if name.starts_with('-')
+        || Path::new(name).exists()
+        || Args::command().find_subcommand(name).is_some()
+    {
+        return None;
     }
+    plugin::find(name)
 }
 
-fn run_file<R: Runner>(runner: &mut R, path: &Path) -> Result<(), ManoError> {
-    let source = fs::read_to_string(path)?; // IO errors propagate (will be printed)
+/// Default for `--limite-tamanho`: far above any handwritten script, low
+/// enough that a runaway generated file fails fast instead of eating memory
+const MAX_SCRIPT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Source name used in error reports for `-e` code
+const CMDLINE_SOURCE_NAME: &str = "<cmdline>";
+
+fn run_mode<R: Runner>(runner: &mut R, args: &Args) -> Result<(), ManoError> {
+    if let Some(code) = &args.eval {
+        return run_eval(runner, code, args.print);
+    }
+
+    match args.script.as_deref() {
+        Some(path) => run_file(runner, path, args.max_size),
+        None if args.interactive_output => run_transcript(runner, io::stdin().lock()),
+        None if args.repl || (!args.no_repl && io::stdin().is_terminal()) => run_repl(runner),
+        None => run_stdin(runner, args.max_size),
+    }
+}
+
+/// Reads a script, refusing one over `limit` bytes. The file's reported
+/// size can't be trusted (pipes and `/dev/stdin` say 0), so it's read.
+fn read_script(path: &Path, limit: u64) -> Result<String, ManoError> {
+    read_limited(fs::File::open(path)?, limit)
+}
+
+/// Reads all of `reader`, stopping with an error once it passes `limit` bytes
+fn read_limited(reader: impl Read, limit: u64) -> Result<String, ManoError> {
+    let mut source = String::new();
+    // One byte past the limit is enough to know it's too big
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_string(&mut source)?;
+    if source.len() as u64 > limit {
+        return Err(ManoError::TooLarge { limit });
+    }
+    Ok(source)
+}
+
+fn run_file<R: Runner>(runner: &mut R, path: &Path, limit: u64) -> Result<(), ManoError> {
+    let source = read_script(path, limit)?; // IO errors propagate (will be printed)
     let filename = path.to_string_lossy();
-    match runner.run(&source, std::io::stdout()) {
+    run_script(runner, &source, |error| {
+        report_error(&error, &source, Some(&filename), std::io::stderr())
+    })
+}
+
+/// Runs a whole script with its output on stdout, handing each error to
+/// `report`. A closed stdout ends it quietly.
+fn run_script<R: Runner>(
+    runner: &mut R,
+    source: &str,
+    report: impl FnMut(ManoError),
+) -> Result<(), ManoError> {
+    match interrupt::while_running(|| runner.run(source, std::io::stdout())) {
         Ok(()) => Ok(()),
+        // Whoever reads the output stopped (`mano x.mano | head`): quit
+        // quietly, like other Unix tools
+        Err(errors) if errors.iter().any(ManoError::is_broken_pipe) => Ok(()),
         Err(errors) => {
+            let failure = script_failure(&errors);
+            errors.into_iter().for_each(report);
+            Err(failure)
+        }
+    }
+}
+
+/// What a script that failed with `errors` ends with, once they're reported:
+/// interrupted scripts exit like a Ctrl+C, the rest as failed
+fn script_failure(errors: &[ManoError]) -> ManoError {
+    match errors
+        .iter()
+        .map(ManoError::primary)
+        .find_map(|error| match error {
+            ManoError::Interrupted { span } => Some(span.clone()),
+            _ => None,
+        }) {
+        Some(span) => ManoError::Interrupted { span },
+        None => ManoError::ScriptFailed,
+    }
+}
+
+/// Prints the semantic changes between two scripts, returns whether they match
+fn diff_ast(old: &Path, new: &Path) -> Result<bool, ManoError> {
+    let parse = |path: &Path| -> Result<Vec<mano::Stmt>, ManoError> {
+        let source = fs::read_to_string(path)?;
+        mano::parse(&source).map_err(|errors| {
+            let filename = path.to_string_lossy();
             for error in &errors {
                 report_error(error, &source, Some(&filename), std::io::stderr());
             }
-            Err(ManoError::ScriptFailed)
+            ManoError::ScriptFailed
+        })
+    };
+    let changes = mano::diff_ast(&parse(old)?, &parse(new)?);
+    for change in &changes {
+        println!("{change}");
+    }
+    Ok(changes.is_empty())
+}
+
+/// Formats each file (or checks it's formatted), returns whether all were already
+fn format_files(files: &[PathBuf], check: bool) -> Result<bool, ManoError> {
+    let mut unchanged = true;
+    let mut failed = false;
+    for path in files {
+        let source = fs::read_to_string(path)?;
+        match mano::format_checked(&source, &FormatOptions::default()) {
+            Ok(formatted) if formatted == source => {}
+            Ok(formatted) => {
+                unchanged = false;
+                if check {
+                    println!("{}", path.display());
+                } else {
+                    fs::write(path, formatted)?;
+                }
+            }
+            Err(errors) => {
+                failed = true;
+                let filename = path.to_string_lossy();
+                for error in &errors {
+                    report_error(error, &source, Some(&filename), std::io::stderr());
+                }
+            }
         }
     }
+    if failed {
+        return Err(ManoError::ScriptFailed);
+    }
+    Ok(unchanged || !check)
 }
 
-fn run_stdin<R: Runner>(runner: &mut R) -> Result<(), ManoError> {
-    let mut source = String::new();
-    io::stdin().read_to_string(&mut source)?; // IO errors propagate (will be printed)
-    match runner.run(&source, std::io::stdout()) {
-        Ok(()) => Ok(()),
+/// The code of a debug subcommand and its name for reports, from `-e` or a file
+fn read_source(script: Option<&Path>, eval: Option<&str>) -> Result<(String, String), ManoError> {
+    Ok(match (eval, script) {
+        (Some(code), _) => (code.to_string(), CMDLINE_SOURCE_NAME.to_string()),
+        (None, Some(path)) => (fs::read_to_string(path)?, path.display().to_string()),
+        (None, None) => unreachable!("clap requires a script or -e"),
+    })
+}
+
+/// Prints the token stream of a script or `-e` code, reporting scan errors
+fn print_tokens(
+    script: Option<&Path>,
+    eval: Option<&str>,
+    dialect: Dialect,
+    json: bool,
+) -> Result<bool, ManoError> {
+    let (source, filename) = read_source(script, eval)?;
+    let errors = tokens::dump(&source, dialect, json, io::stdout().lock())?;
+    for error in &errors {
+        report_error(error, &source, Some(&filename), std::io::stderr());
+    }
+    if !errors.is_empty() {
+        return Err(ManoError::ScriptFailed);
+    }
+    Ok(true)
+}
+
+/// Compiles a script or `-e` code and prints its bytecode, without running it
+fn print_disassembly(
+    script: Option<&Path>,
+    eval: Option<&str>,
+    options: &mano_vm::CompileOptions,
+    json: bool,
+) -> Result<bool, ManoError> {
+    let (source, filename) = read_source(script, eval)?;
+    let compiled = mano_vm::compile(&source, options).map_err(|errors| {
+        for error in &errors {
+            report_error(error, &source, Some(&filename), std::io::stderr());
+        }
+        ManoError::ScriptFailed
+    })?;
+    for warning in &compiled.warnings {
+        report_error(warning, &source, Some(&filename), std::io::stderr());
+    }
+    disasm::dump(
+        &compiled.chunk,
+        &source,
+        &filename,
+        json,
+        io::stdout().lock(),
+    )?;
+    Ok(true)
+}
+
+fn run_eval<R: Runner>(runner: &mut R, code: &str, print: bool) -> Result<(), ManoError> {
+    let source = if print && runner.supports_auto_print() {
+        ReplState::wrap_last_for_print(code)
+    } else {
+        SourceMap::identity(code)
+    };
+    run_script(runner, source.text(), |error| {
+        let error = source.error(error);
+        report_error(&error, code, Some(CMDLINE_SOURCE_NAME), std::io::stderr());
+    })
+}
+
+fn run_stdin<R: Runner>(runner: &mut R, limit: u64) -> Result<(), ManoError> {
+    let source = read_limited(io::stdin(), limit)?; // IO errors propagate
+    run_script(runner, &source, |error| {
+        report_error(&error, &source, None, std::io::stderr())
+    })
+}
+
+/// Update completions with the runner's current symbols and their members
+fn refresh_completions<R: Runner>(
+    rl: &Editor<ManoHelper, rustyline::history::DefaultHistory>,
+    runner: &R,
+) {
+    if let Some(helper) = rl.helper() {
+        let symbols = runner.symbols(true);
+        let members = symbols
+            .iter()
+            .map(|symbol| (symbol.name.clone(), runner.members(&symbol.name)))
+            .filter(|(_, members)| !members.is_empty())
+            .collect();
+        helper.set_symbols(symbols);
+        helper.set_members(members);
+    }
+}
+
+/// Feeds a line to the REPL: runs it as a `:comando`, or adds it to the buffer
+/// and runs the buffer once it's complete, auto-printing a lone expression.
+///
+/// `None` while a block is still open, otherwise whether it went without errors.
+fn repl_line<R: Runner>(runner: &mut R, state: &mut ReplState, line: &str) -> Option<bool> {
+    if state.is_empty()
+        && let Some(command) = ReplCommand::parse(line)
+    {
+        return Some(
+            match command.and_then(|command| command.execute(&mut *runner)) {
+                Ok(output) => {
+                    print!("{output}");
+                    true
+                }
+                Err(message) => {
+                    eprintln!("{message}");
+                    false
+                }
+            },
+        );
+    }
+
+    if !state.process_line(line) {
+        return None;
+    }
+    let buffer = state.take_buffer();
+    let auto_print = runner.supports_auto_print() && ReplState::should_auto_print(&buffer);
+    let source = if auto_print {
+        state.wrap_for_history(&buffer)
+    } else {
+        SourceMap::identity(&buffer)
+    };
+    match interrupt::while_running(|| runner.run(source.text(), std::io::stdout())) {
+        Ok(()) => {
+            if auto_print {
+                state.keep_result();
+            }
+            Some(true)
+        }
         Err(errors) => {
+            for error in errors {
+                report_error(&source.error(error), &buffer, None, std::io::stderr());
+            }
+            Some(false)
+        }
+    }
+}
+
+/// Runs input the way the REPL would, without prompts, for transcripts of
+/// piped sessions. Keeps going after errors but fails at the end if any.
+fn run_transcript<R: Runner>(runner: &mut R, input: impl BufRead) -> Result<(), ManoError> {
+    let mut state = ReplState::new();
+    let mut failed = false;
+    for line in input.lines() {
+        failed |= repl_line(runner, &mut state, &line?) == Some(false);
+    }
+    // A block left open at the end still runs, to report what's missing
+    if !state.is_empty() {
+        let source = state.take_buffer();
+        if let Err(errors) = interrupt::while_running(|| runner.run(&source, std::io::stdout())) {
             for error in &errors {
                 report_error(error, &source, None, std::io::stderr());
             }
-            Err(ManoError::ScriptFailed)
+            failed = true;
         }
     }
+    if failed {
+        return Err(ManoError::ScriptFailed);
+    }
+    Ok(())
 }
 
 fn run_repl<R: Runner>(runner: &mut R) -> Result<(), ManoError> {
@@ -111,33 +630,14 @@ fn run_repl<R: Runner>(runner: &mut R) -> Result<(), ManoError> {
             Ok(line) => {
                 let _ = rl.add_history_entry(&line);
 
-                if state.process_line(&line) {
-                    let buffer = state.take_buffer();
-                    let source =
-                        if runner.supports_auto_print() && ReplState::should_auto_print(&buffer) {
-                            ReplState::wrap_for_print(&buffer)
-                        } else {
-                            buffer
-                        };
-                    if let Err(errors) = runner.run(&source, std::io::stdout()) {
-                        for error in &errors {
-                            report_error(error, &source, None, std::io::stderr());
-                        }
-                    }
-
-                    // Update completions with current variables
-                    if let Some(helper) = rl.helper() {
-                        helper.set_variables(runner.variable_names());
-                    }
-                }
-            }
-            Err(ReadlineError::Interrupted) => {
-                if state.is_empty() {
-                    break;
+                if repl_line(runner, &mut state, &line).is_some() {
+                    refresh_completions(&rl, runner);
                 }
-                state.cancel();
-                println!();
             }
+            Err(ReadlineError::Interrupted) => match state.interrupt(Instant::now()) {
+                Interrupt::Exit => break,
+                Interrupt::Cancel => println!("(Ctrl+C de novo pra vazar, ou Ctrl+D)"),
+            },
             Err(ReadlineError::Eof) => {
                 break;
             }
@@ -163,7 +663,7 @@ mod tests {
         writeln!(file, "@").unwrap();
 
         let mut mano = Mano::new();
-        let result = run_file(&mut mano, file.path());
+        let result = run_file(&mut mano, file.path(), MAX_SCRIPT_SIZE);
 
         assert!(result.is_err());
         assert!(
@@ -171,4 +671,23 @@ mod tests {
             "Expected ManoError::ScriptFailed"
         );
     }
+
+    #[test]
+    fn run_file_refuses_files_over_the_limit() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "salve 1;").unwrap();
+
+        let mut mano = Mano::new();
+        let result = run_file(&mut mano, file.path(), 4);
+
+        assert!(matches!(result, Err(ManoError::TooLarge { limit: 4 })));
+    }
+
+    #[test]
+    fn run_eval_with_error_returns_script_failed() {
+        let mut mano = Mano::new();
+        let result = run_eval(&mut mano, "@", false);
+
+        assert!(matches!(result, Err(ManoError::ScriptFailed)));
+    }
 }
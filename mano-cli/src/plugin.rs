@@ -0,0 +1,70 @@
+//! Plugins: `mano nome` runs a `mano-nome` executable from PATH, like cargo
+
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const PREFIX: &str = "mano-";
+
+/// The executable for plugin `name`, the first one found on PATH
+pub fn find(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(std::path::is_separator) {
+        return None;
+    }
+    let file = format!("{PREFIX}{name}{}", env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+/// Names of the plugins on PATH, sorted and without duplicates
+pub fn discover() -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = env::split_paths(&path)
+        .filter_map(|dir| dir.read_dir().ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file = entry.file_name().into_string().ok()?;
+            let name = file
+                .strip_prefix(PREFIX)?
+                .strip_suffix(env::consts::EXE_SUFFIX)?;
+            (!name.is_empty() && is_executable(&entry.path())).then(|| name.to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Runs the plugin with the rest of the arguments, exiting with its code.
+/// `MANO` tells it which mano called it.
+pub fn run(path: &Path, args: &[OsString]) -> ExitCode {
+    let mut command = std::process::Command::new(path);
+    command.args(args);
+    if let Ok(mano) = env::current_exe() {
+        command.env("MANO", mano);
+    }
+    match command.status() {
+        Ok(status) => ExitCode::from(status.code().unwrap_or(1) as u8),
+        Err(e) => {
+            eprintln!("Não rolou rodar '{}': {}", path.display(), e);
+            ExitCode::from(65)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -1,14 +1,7 @@
 use ariadne::{Color, Label, Report, ReportKind, Source};
-use mano::ManoError;
+use mano::{Frame, LineIndex, ManoError};
+use std::borrow::Cow;
 use std::io::Write;
-use std::ops::Range;
-
-/// Converts a byte span to a character span for ariadne
-fn byte_to_char_span(source: &str, byte_span: &Range<usize>) -> Range<usize> {
-    let start = source[..byte_span.start].chars().count();
-    let end = source[..byte_span.end.min(source.len())].chars().count();
-    start..end
-}
 
 /// Renders a ManoError using ariadne for beautiful error output
 pub fn report_error<W: Write>(
@@ -20,65 +13,94 @@ pub fn report_error<W: Write>(
     // Use filename or empty string for source ID
     let name = filename.unwrap_or("");
     let src = (name, Source::from(source));
+    // ariadne indexes by char, our spans are bytes
+    let index = LineIndex::new(source);
+
+    let (kind, color) = match error.primary() {
+        ManoError::Warning { .. } => (ReportKind::Warning, Color::Yellow),
+        _ => (ReportKind::Error, Color::Red),
+    };
 
-    match error {
-        ManoError::Io(_) => {
+    let (span, message) = match error.primary() {
+        ManoError::Io(_) | ManoError::TooLarge { .. } => {
             writeln!(writer, "{}", error).ok();
+            return;
         }
-        ManoError::Scan { span, message } => {
-            let char_span = byte_to_char_span(source, span);
-            Report::build(ReportKind::Error, (name, char_span.clone()))
-                .with_message(error.to_string())
-                .with_label(
-                    Label::new((name, char_span))
-                        .with_message(message)
-                        .with_color(Color::Red),
-                )
-                .finish()
-                .write(src, &mut writer)
-                .ok();
+        ManoError::Scan { span, message }
+        | ManoError::Parse { span, message }
+        | ManoError::Runtime { span, message }
+        | ManoError::Resolution { span, message }
+        | ManoError::Warning { span, message } => (span, Cow::Borrowed(message.as_str())),
+        ManoError::Output { source, span } => (span, Cow::Owned(source.to_string())),
+        ManoError::Interrupted { span } => (span, Cow::Borrowed("interrompido pelo usuário")),
+        ManoError::Internal(message) => {
+            writeln!(writer, "{} {}", error, message).ok();
+            return;
         }
-        ManoError::Parse { span, message } => {
-            let char_span = byte_to_char_span(source, span);
-            Report::build(ReportKind::Error, (name, char_span.clone()))
-                .with_message(error.to_string())
-                .with_label(
-                    Label::new((name, char_span))
-                        .with_message(message)
-                        .with_color(Color::Red),
-                )
-                .finish()
-                .write(src, &mut writer)
-                .ok();
+        ManoError::Detailed { .. }
+        | ManoError::Break
+        | ManoError::Continue
+        | ManoError::Return(_)
+        | ManoError::Throw { .. }
+        | ManoError::ScriptFailed => {
+            // Internal control flow or already reported, should not be reported to users
+            return;
         }
-        ManoError::Runtime { span, message } => {
-            let char_span = byte_to_char_span(source, span);
-            Report::build(ReportKind::Error, (name, char_span.clone()))
-                .with_message(error.to_string())
-                .with_label(
-                    Label::new((name, char_span))
-                        .with_message(message)
-                        .with_color(Color::Red),
-                )
-                .finish()
-                .write(src, &mut writer)
-                .ok();
+    };
+
+    let char_span = index.char_span(span);
+    // Other places the error points at, like where a fita was declared
+    let related = error.related().iter().map(|related| {
+        Label::new((name, index.char_span(&related.span)))
+            .with_message(&related.message)
+            .with_color(Color::Blue)
+    });
+    let mut report = Report::build(kind, (name, char_span.clone()))
+        .with_message(error.to_string())
+        .with_label(
+            Label::new((name, char_span))
+                .with_message(message)
+                .with_color(color),
+        )
+        .with_labels(related);
+    if let Some(help) = error.help() {
+        report = report.with_help(help);
+    }
+    report.finish().write(src, &mut writer).ok();
+    write_stack(error.stack(), &index, filename, &mut writer);
+}
+
+/// The calls a runtime error happened inside of, one line each, with a
+/// recursion's repeated calls shown once
+fn write_stack<W: Write>(
+    stack: &[Frame],
+    index: &LineIndex,
+    filename: Option<&str>,
+    writer: &mut W,
+) {
+    if stack.is_empty() {
+        return;
+    }
+    let index = match filename {
+        Some(name) => index.clone().named(name),
+        None => index.clone(),
+    };
+    writeln!(writer, "Rastro das chamadas, a mais funda primeiro:").ok();
+    let mut frames = stack.iter().peekable();
+    while let Some(frame) = frames.next() {
+        writeln!(
+            writer,
+            "  em {}, chamada em {}",
+            frame.name,
+            index.locate(&frame.span)
+        )
+        .ok();
+        let mut repeats = 0;
+        while frames.next_if_eq(&frame).is_some() {
+            repeats += 1;
         }
-        ManoError::Resolution { span, message } => {
-            let char_span = byte_to_char_span(source, span);
-            Report::build(ReportKind::Error, (name, char_span.clone()))
-                .with_message(error.to_string())
-                .with_label(
-                    Label::new((name, char_span))
-                        .with_message(message)
-                        .with_color(Color::Red),
-                )
-                .finish()
-                .write(src, &mut writer)
-                .ok();
-        }
-        ManoError::Break | ManoError::Return(_) | ManoError::ScriptFailed => {
-            // Internal control flow or already reported, should not be reported to users
+        if repeats > 0 {
+            writeln!(writer, "  ... e de novo, mais {} vezes", repeats).ok();
         }
     }
 }
@@ -86,6 +108,11 @@ pub fn report_error<W: Write>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ops::Range;
+
+    fn byte_to_char_span(source: &str, byte_span: &Range<usize>) -> Range<usize> {
+        LineIndex::new(source).char_span(byte_span)
+    }
 
     #[test]
     fn byte_to_char_span_ascii_unchanged() {
@@ -148,6 +175,19 @@ mod tests {
         assert!(result.contains("Tá moscando"));
     }
 
+    #[test]
+    fn report_warning_is_not_an_error() {
+        let error = ManoError::Warning {
+            message: "Essa condição é sempre firmeza".to_string(),
+            span: 0..7,
+        };
+        let mut output = Vec::new();
+        report_error(&error, "firmeza ? 1 : 2", None, &mut output);
+        let result = strip_ansi(&String::from_utf8(output).unwrap());
+        assert!(result.starts_with("Warning:"), "got: {result}");
+        assert!(result.contains("Se liga, mano!"));
+    }
+
     #[test]
     fn report_parse_error_shows_span() {
         let error = ManoError::Parse {
@@ -245,6 +285,77 @@ mod tests {
         insta::assert_snapshot!(result);
     }
 
+    #[test]
+    fn snapshot_arity_error_points_at_declaration() {
+        let source = "olhaEssaFita soma(a, b) { toma a + b; }\nsoma(1);";
+        let error = ManoError::Runtime {
+            message: "Essa fita espera 2 argumentos, mas tu passou 1, maluco!".to_string(),
+            span: 46..47,
+        }
+        .with_related(13..17, "declarada aqui");
+        let mut output = Vec::new();
+        report_error(&error, source, None, &mut output);
+        let result = strip_ansi(&String::from_utf8(output).unwrap());
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn report_shows_help() {
+        let error = ManoError::Resolution {
+            message: "Já tem uma 'x' aqui, chapa!".to_string(),
+            span: 17..18,
+        }
+        .with_related(7..8, "a primeira tá aqui")
+        .with_help("dá outro nome pra segunda");
+        let mut output = Vec::new();
+        report_error(&error, "seLiga x; seLiga x;", None, &mut output);
+        let result = strip_ansi(&String::from_utf8(output).unwrap());
+        assert!(result.contains("a primeira tá aqui"), "got: {result}");
+        assert!(
+            result.contains("Help: dá outro nome pra segunda"),
+            "got: {result}"
+        );
+    }
+
+    #[test]
+    fn report_shows_the_calls_the_error_is_in() {
+        let source = "olhaEssaFita a() { b(); }\nolhaEssaFita b() { b(); }\na();";
+        let recursion = Frame {
+            name: "b".to_string(),
+            span: 45..46,
+        };
+        let error = ManoError::Runtime {
+            message: "Tá fundo demais, mano!".to_string(),
+            span: 45..46,
+        }
+        .with_stack(vec![
+            recursion.clone(),
+            recursion.clone(),
+            recursion,
+            Frame {
+                name: "b".to_string(),
+                span: 19..20,
+            },
+            Frame {
+                name: "a".to_string(),
+                span: 52..53,
+            },
+        ]);
+        let mut output = Vec::new();
+        report_error(&error, source, Some("fundo.mano"), &mut output);
+        let result = strip_ansi(&String::from_utf8(output).unwrap());
+        assert!(
+            result.ends_with(
+                "Rastro das chamadas, a mais funda primeiro:\n\
+                 \x20 em b, chamada em fundo.mano:2:20\n\
+                 \x20 ... e de novo, mais 2 vezes\n\
+                 \x20 em b, chamada em fundo.mano:1:20\n\
+                 \x20 em a, chamada em fundo.mano:3:1\n"
+            ),
+            "got: {result}"
+        );
+    }
+
     #[test]
     fn report_error_renders_multibyte_utf8_spans() {
         // Byte 25 = ", byte 36 = EOF (after final \n)
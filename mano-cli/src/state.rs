@@ -1,8 +1,28 @@
-use mano::KEYWORDS;
+use std::time::{Duration, Instant};
+
+use mano::{KEYWORDS, Scanner, SourceMap, TokenType};
+
+/// How soon a second Ctrl+C has to come to quit the REPL
+pub const DOUBLE_INTERRUPT: Duration = Duration::from_secs(1);
+
+/// What a Ctrl+C at the prompt does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Drop what was typed and show a fresh prompt
+    Cancel,
+    /// Quit the REPL
+    Exit,
+}
 
 pub struct ReplState {
     buffer: String,
     brace_depth: usize,
+    /// Whether a string, block comment or interpolation is still open
+    open: bool,
+    /// How many auto-printed results were kept in `_1`, `_2`, ...
+    results: usize,
+    /// When Ctrl+C last cancelled the input, if nothing was entered since
+    last_interrupt: Option<Instant>,
 }
 
 impl ReplState {
@@ -10,41 +30,90 @@ impl ReplState {
         Self {
             buffer: String::new(),
             brace_depth: 0,
+            open: false,
+            results: 0,
+            last_interrupt: None,
         }
     }
 
     pub fn prompt(&self) -> String {
-        if self.brace_depth == 0 {
-            "> ".to_string()
-        } else {
+        if self.brace_depth > 0 {
             format!("..{} ", self.brace_depth)
+        } else if self.open {
+            ".. ".to_string()
+        } else {
+            "> ".to_string()
         }
     }
 
-    /// Returns true if ready to execute (braces balanced)
+    /// Returns true if ready to execute: braces balanced, and no string or
+    /// block comment left open
     pub fn process_line(&mut self, line: &str) -> bool {
-        for ch in line.chars() {
-            match ch {
-                '{' => self.brace_depth += 1,
-                '}' => self.brace_depth = self.brace_depth.saturating_sub(1),
-                _ => {}
-            }
-        }
-
+        self.last_interrupt = None;
         self.buffer.push_str(line);
         self.buffer.push('\n');
 
-        self.brace_depth == 0
+        (self.brace_depth, self.open) = Self::unfinished(&self.buffer);
+        self.brace_depth == 0 && !self.open
+    }
+
+    /// Braces still open at the end of the input, and whether a string, block
+    /// comment or interpolation is too. Scanned, so braces in strings and
+    /// comments don't count.
+    fn unfinished(input: &str) -> (usize, bool) {
+        let mut depth = 0usize;
+        let mut interpolations = 0usize;
+        for token in Scanner::new(input) {
+            let token = match token {
+                Ok(token) => token,
+                // A string or comment running to the end goes on in the next
+                // line. A string opened inside another one's `{}` is more
+                // likely a `{` meant literally, so it runs and gets reported.
+                Err(error) => match error.span() {
+                    Some(span) if span.end == input.len() => {
+                        let string = input[span.start..].starts_with('"');
+                        return (depth, !(string && interpolations > 0));
+                    }
+                    _ => continue,
+                },
+            };
+            match token.token_type {
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace => depth = depth.saturating_sub(1),
+                TokenType::StringStart => interpolations += 1,
+                TokenType::StringEnd => interpolations = interpolations.saturating_sub(1),
+                _ => {}
+            }
+        }
+        (depth, interpolations > 0)
     }
 
     pub fn take_buffer(&mut self) -> String {
         self.brace_depth = 0;
+        self.open = false;
         std::mem::take(&mut self.buffer)
     }
 
     pub fn cancel(&mut self) {
         self.buffer.clear();
         self.brace_depth = 0;
+        self.open = false;
+    }
+
+    /// Ctrl+C at the prompt: the first one drops the input, even when it's
+    /// only the line being typed, and a second one right after quits
+    pub fn interrupt(&mut self, now: Instant) -> Interrupt {
+        let again = self
+            .last_interrupt
+            .is_some_and(|last| now.duration_since(last) < DOUBLE_INTERRUPT);
+        self.cancel();
+        if again {
+            self.last_interrupt = None;
+            Interrupt::Exit
+        } else {
+            self.last_interrupt = Some(now);
+            Interrupt::Cancel
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -112,10 +181,80 @@ impl ReplState {
     }
 
     /// Wrap input in a print statement for auto-printing
-    pub fn wrap_for_print(input: &str) -> String {
-        format!("salve {};", input.trim())
+    pub fn wrap_for_print(input: &str) -> SourceMap {
+        let mut map = SourceMap::new();
+        map.push_generated("salve ");
+        push_trimmed(&mut map, input, 0);
+        map.push_generated(";");
+        map
     }
+
+    /// Wrap input so its value is printed and kept for later inputs.
+    ///
+    /// `1 + 2` becomes `seLiga _1 = 1 + 2; seLiga _ = _1; salve _1;`, so the
+    /// result lives in the globals as `_` and `_1`. Call [`Self::keep_result`]
+    /// once it ran, otherwise the next result reuses the number.
+    pub fn wrap_for_history(&self, input: &str) -> SourceMap {
+        let name = format!("_{}", self.results + 1);
+        let mut map = SourceMap::new();
+        map.push_generated(&format!("seLiga {name} = "));
+        push_trimmed(&mut map, input, 0);
+        map.push_generated(&format!(";\nseLiga _ = {name};\nsalve {name};"));
+        map
+    }
+
+    /// Move on to the next history variable after a wrapped input succeeded
+    pub fn keep_result(&mut self) {
+        self.results += 1;
+    }
+
+    /// Wrap only the trailing expression of a multi-statement input for auto-printing.
+    ///
+    /// `seLiga x = 2; x * 3` becomes `seLiga x = 2; salve x * 3;`. Input whose last
+    /// statement is already terminated is returned unchanged.
+    pub fn wrap_last_for_print(input: &str) -> SourceMap {
+        let mut depth = 0usize;
+        let mut boundary = 0;
+        for token in Scanner::new(input) {
+            let Ok(token) = token else {
+                return SourceMap::identity(input);
+            };
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket => depth = depth.saturating_sub(1),
+                TokenType::RightBrace => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        boundary = token.span.end;
+                    }
+                }
+                TokenType::Semicolon if depth == 0 => boundary = token.span.end,
+                _ => {}
+            }
+        }
+
+        let (head, tail) = input.split_at(boundary);
+        if !Self::should_auto_print(tail) {
+            return SourceMap::identity(input);
+        }
+        let mut map = SourceMap::new();
+        let code = head.trim_start();
+        if !code.is_empty() {
+            map.push_original(code, head.len() - code.len());
+            map.push_generated(" ");
+        }
+        map.push_map(&Self::wrap_for_print(tail), boundary);
+        map
+    }
+}
+
+/// Adds `text` without its surrounding whitespace, `text` being at `offset` in
+/// what the user typed
+fn push_trimmed(map: &mut SourceMap, text: &str, offset: usize) {
+    let start = text.len() - text.trim_start().len();
+    map.push_original(text.trim(), offset + start);
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,6 +312,46 @@ mod tests {
         assert!(state.process_line("}"));
     }
 
+    #[test]
+    fn braces_in_strings_and_comments_do_not_count() {
+        let mut state = ReplState::new();
+        assert!(state.process_line("salve \"}}\"; // {"));
+        state.take_buffer();
+        assert!(!state.process_line("{ salve \"}\";"));
+        assert_eq!(state.prompt(), "..1 ");
+        assert!(state.process_line("/* } */ }"));
+    }
+
+    #[test]
+    fn waits_for_strings_and_block_comments_to_close() {
+        let mut state = ReplState::new();
+        assert!(!state.process_line("salve \"linha 1"));
+        assert_eq!(state.prompt(), ".. ");
+        assert!(state.process_line("linha 2\";"));
+        state.take_buffer();
+
+        assert!(!state.process_line("/* comentário"));
+        assert!(state.process_line("*/ salve 1;"));
+        state.take_buffer();
+
+        assert!(!state.process_line("salve \"a {1 +"));
+        assert!(state.process_line("2} b\";"));
+    }
+
+    #[test]
+    fn a_brace_opening_a_string_inside_another_runs() {
+        // "{" opens an interpolation, and the string in it never closes
+        let mut state = ReplState::new();
+        assert!(state.process_line("salve \"{\";"));
+        assert_eq!(state.prompt(), "> ");
+    }
+
+    #[test]
+    fn other_scan_errors_run_to_be_reported() {
+        let mut state = ReplState::new();
+        assert!(state.process_line("salve @;"));
+    }
+
     #[test]
     fn take_buffer_returns_accumulated_lines() {
         let mut state = ReplState::new();
@@ -206,6 +385,52 @@ mod tests {
         assert_eq!(state.prompt(), "> ");
     }
 
+    #[test]
+    fn interrupt_cancels_pending_input_first() {
+        let mut state = ReplState::new();
+        state.process_line("{");
+        assert_eq!(state.interrupt(Instant::now()), Interrupt::Cancel);
+        assert!(state.is_empty());
+        assert_eq!(state.prompt(), "> ");
+    }
+
+    #[test]
+    fn interrupt_on_an_empty_prompt_only_cancels() {
+        let mut state = ReplState::new();
+        assert_eq!(state.interrupt(Instant::now()), Interrupt::Cancel);
+    }
+
+    #[test]
+    fn second_interrupt_right_after_exits() {
+        let mut state = ReplState::new();
+        let first = Instant::now();
+        state.process_line("{");
+        assert_eq!(state.interrupt(first), Interrupt::Cancel);
+        let second = first + DOUBLE_INTERRUPT / 2;
+        assert_eq!(state.interrupt(second), Interrupt::Exit);
+    }
+
+    #[test]
+    fn second_interrupt_after_the_window_cancels_again() {
+        let mut state = ReplState::new();
+        let first = Instant::now();
+        assert_eq!(state.interrupt(first), Interrupt::Cancel);
+        let late = first + DOUBLE_INTERRUPT;
+        assert_eq!(state.interrupt(late), Interrupt::Cancel);
+        // The late one starts a new window
+        assert_eq!(state.interrupt(late), Interrupt::Exit);
+    }
+
+    #[test]
+    fn entering_a_line_resets_the_interrupt() {
+        let mut state = ReplState::new();
+        let now = Instant::now();
+        assert_eq!(state.interrupt(now), Interrupt::Cancel);
+        state.process_line("salve 1;");
+        state.take_buffer();
+        assert_eq!(state.interrupt(now), Interrupt::Cancel);
+    }
+
     #[test]
     fn is_empty_false_when_has_content() {
         let mut state = ReplState::new();
@@ -291,7 +516,83 @@ mod tests {
 
     #[test]
     fn wrap_for_print_adds_salve() {
-        assert_eq!(ReplState::wrap_for_print("a"), "salve a;");
-        assert_eq!(ReplState::wrap_for_print("\"mano\""), "salve \"mano\";");
+        assert_eq!(ReplState::wrap_for_print("a").text(), "salve a;");
+        assert_eq!(
+            ReplState::wrap_for_print("\"mano\"").text(),
+            "salve \"mano\";"
+        );
+    }
+
+    #[test]
+    fn wrap_for_history_binds_numbered_result() {
+        let mut state = ReplState::new();
+        assert_eq!(
+            state.wrap_for_history("1 + 2\n").text(),
+            "seLiga _1 = 1 + 2;\nseLiga _ = _1;\nsalve _1;"
+        );
+        state.keep_result();
+        assert!(
+            state
+                .wrap_for_history("_ * 2")
+                .text()
+                .starts_with("seLiga _2 = _ * 2;")
+        );
+    }
+
+    #[test]
+    fn history_results_build_on_each_other() {
+        let mut mano = mano::Mano::new();
+        let mut state = ReplState::new();
+        let mut output = Vec::new();
+        for input in ["20 + 1", "_ * 2", "_1 + _2"] {
+            let errors = mano.run(state.wrap_for_history(input).text(), &mut output);
+            assert!(errors.is_empty(), "{:?}", errors);
+            state.keep_result();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "21\n42\n63\n");
+    }
+
+    #[test]
+    fn wrap_last_for_print_wraps_single_expression() {
+        assert_eq!(
+            ReplState::wrap_last_for_print("1 + 2").text(),
+            "salve 1 + 2;"
+        );
+    }
+
+    #[test]
+    fn wrap_last_for_print_wraps_only_trailing_expression() {
+        assert_eq!(
+            ReplState::wrap_last_for_print("seLiga x = 2; x * 3").text(),
+            "seLiga x = 2; salve x * 3;"
+        );
+    }
+
+    #[test]
+    fn wrap_last_for_print_ignores_semicolons_inside_parens() {
+        assert_eq!(
+            ReplState::wrap_last_for_print("seVira (seLiga i = 0; i < 1; i = i + 1) {} 7").text(),
+            "seVira (seLiga i = 0; i < 1; i = i + 1) {} salve 7;"
+        );
+    }
+
+    #[test]
+    fn wrapped_spans_map_back_to_the_input() {
+        let input = "seLiga x = 2;  x * oi";
+        let map = ReplState::wrap_last_for_print(input);
+        let wrapped = map.text().find("oi").unwrap();
+        assert_eq!(map.span(&(wrapped..wrapped + 2)), 19..21);
+
+        let map = ReplState::new().wrap_for_history("  oi\n");
+        let wrapped = map.text().find("oi").unwrap();
+        assert_eq!(map.span(&(wrapped..wrapped + 2)), 2..4);
+    }
+
+    #[test]
+    fn wrap_last_for_print_keeps_terminated_input() {
+        assert_eq!(
+            ReplState::wrap_last_for_print("salve 1; salve 2;").text(),
+            "salve 1; salve 2;"
+        );
     }
 }
@@ -0,0 +1,106 @@
+//! `mano tokens`: prints what the scanner makes of some code, one token per
+//! line or as JSON, for bug reports and for seeing how the scanner works
+
+use std::io::{self, Write};
+
+use mano::{Dialect, LineIndex, ManoError, Scanner, Token};
+
+use crate::json::json_string;
+
+/// Writes the tokens of `source` to `out`, returns the scan errors in between
+pub fn dump<W: Write>(
+    source: &str,
+    dialect: Dialect,
+    json: bool,
+    mut out: W,
+) -> io::Result<Vec<ManoError>> {
+    let index = LineIndex::new(source);
+    let mut errors = Vec::new();
+    let mut tokens = Vec::new();
+    for result in Scanner::new(source).with_dialect(dialect) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if json {
+        let entries: Vec<String> = tokens.iter().map(|t| json_entry(t, &index)).collect();
+        writeln!(out, "[{}]", entries.join(","))?;
+    } else {
+        for token in &tokens {
+            writeln!(out, "{}", text_entry(token, &index))?;
+        }
+    }
+    Ok(errors)
+}
+
+/// `line:col  span  Type  "lexeme"`, counting lines and columns from 1
+fn text_entry(token: &Token, index: &LineIndex) -> String {
+    let pos = index.line_col(token.span.start);
+    format!(
+        "{:<8}{:<12}{:<14}{:?}",
+        format!("{}:{}", pos.line + 1, pos.col + 1),
+        format!("{}..{}", token.span.start, token.span.end),
+        format!("{:?}", token.token_type),
+        &*token.lexeme,
+    )
+}
+
+fn json_entry(token: &Token, index: &LineIndex) -> String {
+    let pos = index.line_col(token.span.start);
+    format!(
+        r#"{{"type":"{:?}","lexeme":{},"span":[{},{}],"line":{},"col":{}}}"#,
+        token.token_type,
+        json_string(&token.lexeme),
+        token.span.start,
+        token.span.end,
+        pos.line + 1,
+        pos.col + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump_to_string(source: &str, json: bool) -> (String, Vec<ManoError>) {
+        let mut out = Vec::new();
+        let errors = dump(source, Dialect::Mano, json, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), errors)
+    }
+
+    #[test]
+    fn dump_lists_one_token_per_line() {
+        let (out, errors) = dump_to_string("salve 1;\nx", false);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            out,
+            "1:1     0..5        Print         \"salve\"\n\
+             1:7     6..7        Number        \"1\"\n\
+             1:8     7..8        Semicolon     \";\"\n\
+             2:1     9..10       Identifier    \"x\"\n\
+             2:2     10..10      Eof           \"\"\n"
+        );
+    }
+
+    #[test]
+    fn dump_as_json_escapes_lexemes() {
+        let (out, _) = dump_to_string("\"a\\\"b\"", true);
+
+        assert_eq!(
+            out,
+            "[{\"type\":\"String\",\"lexeme\":\"\\\"a\\\\\\\"b\\\"\",\"span\":[0,6],\"line\":1,\"col\":1},\
+             {\"type\":\"Eof\",\"lexeme\":\"\",\"span\":[6,6],\"line\":1,\"col\":7}]\n"
+        );
+    }
+
+    #[test]
+    fn dump_keeps_going_after_scan_errors() {
+        let (out, errors) = dump_to_string("1 @ 2", false);
+
+        assert_eq!(errors.len(), 1);
+        assert!(out.contains("\"1\"") && out.contains("\"2\""));
+    }
+}
@@ -1,24 +1,68 @@
 //! VM wrapper that implements the Runner trait
 
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use mano::{ManoError, Runner};
+use mano::{Dialect, ManoError, Runner};
+use mano_vm::{Chunk, CompileOptions, Trace, VM};
+
+use crate::report::report_error;
 
 /// Bytecode VM wrapper that implements the Runner trait.
 pub struct Vm {
     debug: bool,
+    /// What to trace when not debugging, which traces everything
+    trace: Trace,
+    dialect: Dialect,
+    /// Flags for `#se NOME` blocks
+    defines: Vec<String>,
+    /// Whether warnings are errors
+    strict: bool,
+    /// Chunk of the last code that compiled, kept for `:desmonta`
+    last_chunk: Option<Chunk>,
+    /// Set on Ctrl+C, stops loops
+    interrupt: Option<Arc<AtomicBool>>,
 }
 
 impl Vm {
     /// Create a new VM.
     pub fn new() -> Self {
-        Self { debug: false }
+        Self {
+            debug: false,
+            trace: Trace::default(),
+            dialect: Dialect::default(),
+            defines: Vec::new(),
+            strict: false,
+            last_chunk: None,
+            interrupt: None,
+        }
     }
 
     /// Enable debug tracing during execution.
     pub fn set_debug(&mut self, debug: bool) {
         self.debug = debug;
     }
+
+    /// Trace only some events during execution, like just the calls.
+    pub fn set_trace(&mut self, trace: Trace) {
+        self.trace = trace;
+    }
+
+    /// Accept the dialect's keywords in sources without a pragma.
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+    }
+
+    /// Flags that turn on `#se NOME` blocks.
+    pub fn set_defines(&mut self, defines: Vec<String>) {
+        self.defines = defines;
+    }
+
+    /// Treat warnings as errors.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
 }
 
 impl Default for Vm {
@@ -29,7 +73,33 @@ impl Default for Vm {
 
 impl Runner for Vm {
     fn run<W: Write>(&mut self, source: &str, mut stdout: W) -> Result<(), Vec<ManoError>> {
-        mano_vm::run(source, &mut stdout, self.debug)
+        let options = CompileOptions {
+            dialect: self.dialect,
+            defines: self.defines.clone(),
+            strict: self.strict,
+            ..Default::default()
+        };
+        let compiled = mano_vm::compile(source, &options)?;
+        for warning in &compiled.warnings {
+            report_error(warning, source, None, std::io::stderr());
+        }
+        let chunk = compiled.chunk;
+        if self.debug {
+            write!(stdout, "{}", mano_vm::disassemble_chunk(&chunk, "code")).unwrap();
+        }
+        let mut vm = VM::new(&chunk, &mut stdout);
+        vm.set_trace_options(if self.debug { Trace::ALL } else { self.trace });
+        vm.set_source(source);
+        if let Some(flag) = &self.interrupt {
+            vm.set_interrupt(Arc::clone(flag));
+        }
+        let result = vm.interpret();
+        self.last_chunk = Some(chunk);
+        result
+    }
+
+    fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
     }
 
     fn variable_names(&self) -> Vec<String> {
@@ -38,7 +108,19 @@ impl Runner for Vm {
     }
 
     fn supports_auto_print(&self) -> bool {
-        false // VM only handles expressions, no print statement yet
+        false // A trailing expression already prints itself
+    }
+
+    fn disassemble(&self, name: Option<&str>) -> Result<String, String> {
+        // The VM has no functions yet, so the only chunk is the last script
+        match name {
+            Some(name) => Err(format!("Fita '{}' não existe, mano!", name)),
+            None => self
+                .last_chunk
+                .as_ref()
+                .map(|chunk| mano_vm::disassemble_chunk(chunk, "script"))
+                .ok_or_else(|| "Roda alguma coisa antes de desmontar, mano!".to_string()),
+        }
     }
 }
 
@@ -95,9 +177,47 @@ mod tests {
         assert!(out.contains("== trace =="));
     }
 
+    #[test]
+    fn vm_run_with_trace_shows_only_what_was_asked() {
+        let mut vm = Vm::new();
+        vm.set_trace(Trace {
+            calls: true,
+            ..Trace::default()
+        });
+        let mut output = Vec::new();
+        Runner::run(&mut vm, "42", &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("<- script = 42"));
+        assert!(!out.contains("== code =="));
+        assert!(!out.contains("OP_CONSTANT"));
+    }
+
     #[test]
     fn vm_supports_auto_print_returns_false() {
         let vm = Vm::new();
         assert!(!Runner::supports_auto_print(&vm));
     }
+
+    #[test]
+    fn vm_disassemble_before_running_errors() {
+        let vm = Vm::new();
+        assert!(Runner::disassemble(&vm, None).is_err());
+    }
+
+    #[test]
+    fn vm_disassemble_shows_last_chunk() {
+        let mut vm = Vm::new();
+        Runner::run(&mut vm, "1 + 2", &mut Vec::new()).unwrap();
+        let listing = Runner::disassemble(&vm, None).unwrap();
+        assert!(listing.starts_with("== script =="));
+        assert!(listing.contains("OP_ADD"));
+    }
+
+    #[test]
+    fn vm_disassemble_unknown_function_errors() {
+        let mut vm = Vm::new();
+        Runner::run(&mut vm, "1", &mut Vec::new()).unwrap();
+        let err = Runner::disassemble(&vm, Some("soma")).unwrap_err();
+        assert!(err.contains("soma"));
+    }
 }
@@ -1,4 +1,5 @@
 use assert_cmd::Command;
+use predicates::prelude::PredicateBooleanExt;
 use std::io::Write;
 
 fn mano() -> Command {
@@ -43,6 +44,109 @@ fn exits_with_error_for_missing_file() {
         .stderr(predicates::str::contains("Cadê o arquivo"));
 }
 
+#[test]
+fn refuses_scripts_over_the_size_limit() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "salve \"oi mano\";").unwrap();
+
+    mano()
+        .args(["--limite-tamanho", "8"])
+        .arg(file.path())
+        .assert()
+        .code(65)
+        .stdout("")
+        .stderr(predicates::str::contains("grande demais"));
+}
+
+#[test]
+fn refuses_stdin_over_the_size_limit() {
+    mano()
+        .args(["--no-repl", "--limite-tamanho", "8"])
+        .write_stdin("salve \"oi mano\";\n")
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("grande demais"));
+}
+
+#[test]
+#[cfg(unix)]
+fn refuses_scripts_from_pipes_over_the_size_limit() {
+    // A pipe reports a size of 0, so the limit has to hold while reading
+    mano()
+        .args(["--limite-tamanho", "8", "/dev/stdin"])
+        .write_stdin("salve \"oi mano\";\n")
+        .assert()
+        .code(65)
+        .stdout("")
+        .stderr(predicates::str::contains("grande demais"));
+}
+
+#[test]
+fn size_limit_can_be_the_largest_number() {
+    mano()
+        .args(["--no-repl", "--limite-tamanho", &u64::MAX.to_string()])
+        .write_stdin("salve 1;")
+        .assert()
+        .success()
+        .stdout("1\n");
+}
+
+#[test]
+fn le_ai_reads_lines_from_stdin() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "seLiga nome = leAi(); salve \"oi, {{nome}}!\";").unwrap();
+
+    mano()
+        .arg(file.path())
+        .write_stdin("Zé\n")
+        .assert()
+        .success()
+        .stdout("oi, Zé!\n");
+}
+
+#[test]
+fn chama_finds_modules_next_to_the_script() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("util.mano"),
+        "olhaEssaFita dobro(n) { toma n * 2; }",
+    )
+    .unwrap();
+    let script = dir.path().join("main.mano");
+    std::fs::write(&script, "chama \"util\"; salve dobro(21);").unwrap();
+
+    // Run from elsewhere, so the working directory can't be what finds it
+    mano()
+        .arg(&script)
+        .current_dir(std::env::temp_dir())
+        .assert()
+        .success()
+        .stdout("42\n");
+}
+
+#[test]
+fn chama_finds_installed_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let fitas = dir.path().join(".mano_modules/fitas");
+    std::fs::create_dir_all(&fitas).unwrap();
+    std::fs::write(
+        fitas.join("fitas.mano"),
+        "olhaEssaFita grita(s) { toma s + \"!\"; }",
+    )
+    .unwrap();
+    let src = dir.path().join("src");
+    std::fs::create_dir(&src).unwrap();
+    let script = src.join("main.mano");
+    std::fs::write(&script, "chama \"fitas\"; salve grita(\"salve\");").unwrap();
+
+    mano()
+        .arg(&script)
+        .current_dir(std::env::temp_dir())
+        .assert()
+        .success()
+        .stdout("salve!\n");
+}
+
 #[test]
 fn repl_exits_on_eof() {
     // When stdin is piped and empty, rustyline returns EOF immediately
@@ -178,6 +282,59 @@ fn piped_input_does_not_auto_print_expressions() {
         .stderr(predicates::str::contains("Deu mole"));
 }
 
+#[test]
+fn no_repl_flag_runs_stdin_as_one_script() {
+    mano()
+        .args(["--no-repl"])
+        .write_stdin("seLiga a = 1;\nsalve a + 1;\n")
+        .assert()
+        .success()
+        .stdout("2\n");
+}
+
+#[test]
+fn repl_flag_forces_repl_on_piped_input() {
+    mano()
+        .args(["--repl"])
+        .write_stdin("seLiga a = 20;\na + 1\n")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("21"));
+}
+
+#[test]
+fn repl_flags_conflict() {
+    mano().args(["--repl", "--no-repl"]).assert().code(2);
+    mano()
+        .args(["--interactive-output", "--repl"])
+        .assert()
+        .code(2);
+    mano().args(["--repl", "-e", "1"]).assert().code(2);
+}
+
+#[test]
+fn interactive_output_auto_prints_without_prompts() {
+    mano()
+        .args(["--interactive-output"])
+        .write_stdin("seLiga a = 20;\na + 1\n{\nsalve a;\n}\n_ * 2\n")
+        .assert()
+        .success()
+        .stdout("21\n20\n42\n");
+}
+
+#[test]
+fn interactive_output_keeps_going_but_fails_after_errors() {
+    mano()
+        .args(["--interactive-output"])
+        .write_stdin("@\n1 + 1\n{\n")
+        .assert()
+        .code(65)
+        .stdout("2\n")
+        .stderr(
+            predicates::str::contains("Tá moscando").and(predicates::str::contains("Deu mole")),
+        );
+}
+
 #[test]
 fn errors_are_not_printed_twice() {
     // Errors should only be printed once, not duplicated
@@ -225,6 +382,24 @@ fn vm_debug_flag_traces_execution() {
         .stdout(predicates::str::contains("OP_CONSTANT"));
 }
 
+#[test]
+fn vm_trace_flag_shows_only_the_chosen_events() {
+    mano()
+        .args(["--vm", "--trace=calls", "-e", "1 + 2"])
+        .assert()
+        .success()
+        .stdout("== trace ==\n-> script\n<- script = 3\n3\n");
+}
+
+#[test]
+fn vm_trace_flag_rejects_unknown_events() {
+    mano()
+        .args(["--vm", "--trace=tudo", "-e", "1"])
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("'tudo'"));
+}
+
 #[test]
 fn vm_file_mode_works() {
     let mut file = tempfile::NamedTempFile::new().unwrap();
@@ -248,6 +423,16 @@ fn vm_reports_errors() {
         .stderr(predicates::str::contains("Deu mole"));
 }
 
+#[test]
+fn vm_runs_control_flow() {
+    let code = "segueOFluxo (firmeza) {\n  sePá (nadaNão ow 1) salve 1; vacilou salve 2;\n  saiFora;\n}\nsalve treta tamoJunto 3;";
+    mano()
+        .args(["--vm", "-e", code])
+        .assert()
+        .success()
+        .stdout("1\ntreta\n");
+}
+
 #[test]
 fn help_flag_shows_usage() {
     mano()
@@ -256,3 +441,591 @@ fn help_flag_shows_usage() {
         .success()
         .stdout(predicates::str::contains("--vm"));
 }
+
+/// A directory with a `mano-oi` plugin that echoes its arguments and exits with 3
+#[cfg(unix)]
+fn plugin_dir() -> tempfile::TempDir {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mano-oi");
+    std::fs::write(&path, "#!/bin/sh\necho \"oi $*\"\nexit 3\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+#[cfg(unix)]
+fn path_with(dir: &std::path::Path) -> std::ffi::OsString {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::join_paths(std::iter::once(dir.to_path_buf()).chain(std::env::split_paths(&path)))
+        .unwrap()
+}
+
+#[test]
+#[cfg(unix)]
+fn unknown_subcommand_runs_plugin_from_path() {
+    let dir = plugin_dir();
+    mano()
+        .env("PATH", path_with(dir.path()))
+        .args(["oi", "--tudo", "bem"])
+        .assert()
+        .code(3)
+        .stdout("oi --tudo bem\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn help_lists_plugins_on_path() {
+    let dir = plugin_dir();
+    mano()
+        .env("PATH", path_with(dir.path()))
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Plugins").and(predicates::str::contains("  oi")));
+}
+
+#[test]
+#[cfg(unix)]
+fn script_files_win_over_plugins() {
+    let dir = plugin_dir();
+    let script = tempfile::tempdir().unwrap();
+    std::fs::write(script.path().join("oi"), "salve \"script\";").unwrap();
+    mano()
+        .env("PATH", path_with(dir.path()))
+        .current_dir(script.path())
+        .arg("oi")
+        .assert()
+        .success()
+        .stdout("script\n");
+}
+
+#[test]
+fn arguments_after_double_dash_go_to_the_script() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(
+        file,
+        "salve pegaOpcao(\"nome\"); salve pegaOpcao(\"seco\");"
+    )
+    .unwrap();
+
+    mano()
+        .arg(file.path())
+        .args(["--", "--nome", "Zé", "--seco"])
+        .assert()
+        .success()
+        .stdout("Zé\nfirmeza\n");
+}
+
+#[test]
+fn semente_repeats_the_same_random_numbers() {
+    let run = || {
+        mano()
+            .args([
+                "--semente",
+                "42",
+                "-e",
+                "salve aleatorio(); salve aleatorioEntre(1, 6);",
+            ])
+            .output()
+            .unwrap()
+    };
+    let first = run();
+    assert!(first.status.success());
+    assert_eq!(first.stdout, run().stdout);
+}
+
+#[test]
+fn roda_comando_needs_libera_comandos() {
+    let code = "salve rodaComando(\"echo oi\").saida;";
+    mano()
+        .args(["-e", code])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("sandbox"));
+    mano()
+        .args(["--libera-comandos", "-e", code])
+        .assert()
+        .success()
+        .stdout("oi\n\n");
+}
+
+#[test]
+fn eval_flag_runs_code_string() {
+    mano()
+        .args(["-e", "salve 1 + 2;"])
+        .assert()
+        .success()
+        .stdout("3\n");
+}
+
+#[test]
+fn eval_print_flag_prints_last_expression() {
+    mano()
+        .args(["-e", "seLiga x = 2; x * 3", "-p"])
+        .assert()
+        .success()
+        .stdout("6\n");
+}
+
+#[test]
+fn eval_without_print_does_not_auto_print() {
+    mano()
+        .args(["-e", "1 + 2"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("Deu mole"));
+}
+
+#[test]
+fn eval_errors_are_attributed_to_cmdline() {
+    mano()
+        .args(["-e", "salve @;"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("<cmdline>"));
+}
+
+#[test]
+fn eval_conflicts_with_script() {
+    mano()
+        .args(["-e", "salve 1;", "script.mano"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn runs_script_with_shebang() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "#!/usr/bin/env mano\nsalve 42;").unwrap();
+
+    mano().arg(file.path()).assert().success().stdout("42\n");
+}
+
+#[test]
+fn prints_functions_with_name_and_arity() {
+    mano()
+        .args([
+            "-e",
+            "olhaEssaFita soma(a, b) { toma a + b; } salve soma; salve olhaEssaFita (x) { toma x; };",
+        ])
+        .assert()
+        .success()
+        .stdout("<fita soma/2>\n<fita anônima/1>\n");
+}
+
+#[test]
+fn explica_shows_operand_values() {
+    mano()
+        .args(["--explica", "-e", "seLiga nome = \"mano\"; salve nome + 3;"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains(
+            "esquerda era \"mano\" (texto), direita era 3 (número)",
+        ));
+}
+
+#[test]
+fn explica_shows_operand_values_of_number_operators() {
+    for operator in ["-", "<", "&"] {
+        let code = format!("seLiga n = \"a\"; salve n {} 1;", operator);
+        mano()
+            .args(["--explica", "-e", &code])
+            .assert()
+            .code(65)
+            .stderr(predicates::str::contains(
+                "esquerda era \"a\" (texto), direita era 1 (número)",
+            ));
+    }
+}
+
+#[test]
+fn runtime_errors_skip_values_without_explica() {
+    mano()
+        .args(["-e", "salve \"mano\" + 3;"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("esquerda era").not());
+}
+
+#[test]
+fn junta_texto_lets_plus_join_texto_and_numbers() {
+    mano()
+        .args(["--junta-texto", "-e", "salve \"idade: \" + 30;"])
+        .assert()
+        .success()
+        .stdout("idade: 30\n");
+    mano()
+        .args(["-e", "salve \"idade: \" + 30;"])
+        .assert()
+        .code(65);
+}
+
+#[test]
+fn deep_but_legal_recursion_runs() {
+    mano()
+        .args([
+            "-e",
+            "olhaEssaFita soma(n) { sePá (n == 0) toma 0; toma n + soma(n - 1); } salve soma(2000);",
+        ])
+        .assert()
+        .success()
+        .stdout("2001000\n");
+}
+
+#[test]
+fn endless_recursion_is_an_error_not_a_crash() {
+    mano()
+        .args(["-e", "olhaEssaFita f(n) { toma f(n + 1); } salve f(0);"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("Afundou demais"));
+}
+
+#[test]
+fn diff_ast_ignores_formatting() {
+    let mut old = tempfile::NamedTempFile::new().unwrap();
+    writeln!(old, "olhaEssaFita soma(a, b) {{ toma a + b; }}").unwrap();
+    let mut new = tempfile::NamedTempFile::new().unwrap();
+    writeln!(new, "// soma\nolhaEssaFita soma(a,b)\n{{\n  toma a+b;\n}}").unwrap();
+
+    mano()
+        .arg("diff-ast")
+        .args([old.path(), new.path()])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn diff_ast_reports_semantic_changes() {
+    let mut old = tempfile::NamedTempFile::new().unwrap();
+    writeln!(old, "olhaEssaFita soma(a, b) {{ toma a + b; }}").unwrap();
+    let mut new = tempfile::NamedTempFile::new().unwrap();
+    writeln!(
+        new,
+        "olhaEssaFita soma(a, b, c) {{ toma a + b + c; }} seLiga x = 1;"
+    )
+    .unwrap();
+
+    mano()
+        .arg("diff-ast")
+        .args([old.path(), new.path()])
+        .assert()
+        .code(1)
+        .stdout("~ fita 'soma' foi de 2 pra 3 lances\n+ variável 'x'\n");
+}
+
+#[test]
+fn fmt_reindents_file_in_place() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, "sePá (firmeza) {{\nsalve 1;   \n}}").unwrap();
+
+    mano().arg("fmt").arg(file.path()).assert().success();
+    let formatted = std::fs::read_to_string(file.path()).unwrap();
+    assert_eq!(formatted, "sePá (firmeza) {\n    salve 1;\n}\n");
+}
+
+#[test]
+fn fmt_check_lists_unformatted_files_without_writing() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "{{\nsalve 1;\n}}").unwrap();
+
+    mano()
+        .args(["fmt", "--check"])
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stdout(predicates::str::contains(file.path().to_str().unwrap()));
+    let source = std::fs::read_to_string(file.path()).unwrap();
+    assert_eq!(source, "{\nsalve 1;\n}\n");
+}
+
+#[test]
+fn fmt_refuses_code_that_does_not_parse() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "salve (1;").unwrap();
+
+    mano().arg("fmt").arg(file.path()).assert().code(65);
+    let source = std::fs::read_to_string(file.path()).unwrap();
+    assert_eq!(source, "salve (1;\n");
+}
+
+#[test]
+fn tokens_prints_the_token_stream() {
+    mano()
+        .args(["tokens", "-e", "salve 1;"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "1:1     0..5        Print         \"salve\"",
+        ))
+        .stdout(predicates::str::contains("Semicolon"));
+}
+
+#[test]
+fn tokens_prints_json() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, "x").unwrap();
+
+    mano()
+        .args(["tokens", "--json"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"[{"type":"Identifier","lexeme":"x","span":[0,1],"line":1,"col":1},"#,
+            r#"{"type":"Eof","lexeme":"","span":[1,1],"line":1,"col":2}]"#,
+            "\n"
+        ));
+}
+
+#[test]
+fn disasm_prints_bytecode_without_running() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, "salve 1 + 2;").unwrap();
+
+    mano()
+        .arg("disasm")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("-- constantes --"))
+        .stdout(predicates::str::contains("OP_ADD"))
+        .stdout(predicates::str::contains("OP_PRINT"))
+        .stdout(predicates::str::contains("3\n").not());
+}
+
+#[test]
+fn disasm_prints_json() {
+    mano()
+        .args(["disasm", "--json", "-e", "1"])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(
+            r#"{"constants":[{"kind":"número","value":"1"}],"code":["#,
+        ));
+}
+
+#[test]
+fn disasm_reports_compile_errors() {
+    mano()
+        .args(["disasm", "-e", "1 +"])
+        .assert()
+        .code(65)
+        .stdout("");
+}
+
+#[test]
+fn tokens_reports_scan_errors() {
+    mano()
+        .args(["tokens", "-e", "1 @"])
+        .assert()
+        .code(65)
+        .stdout(predicates::str::contains("Number"))
+        .stderr(predicates::str::contains("@"));
+}
+
+#[test]
+fn dialect_flag_accepts_formal_keywords() {
+    mano()
+        .args([
+            "--dialect",
+            "formal",
+            "-e",
+            "variavel x = 2; imprima x * 21;",
+        ])
+        .assert()
+        .success()
+        .stdout("42\n");
+}
+
+#[test]
+fn dialect_pragma_selects_formal_keywords() {
+    mano()
+        .args(["-e", "// dialeto: formal\nimprima verdadeiro;"])
+        .assert()
+        .success()
+        .stdout("firmeza\n");
+}
+
+#[test]
+fn lox_dialect_runs_lox_code() {
+    mano()
+        .args([
+            "--dialect=lox",
+            "-e",
+            "var a = \"lox\"; if (a != nil) print a;",
+        ])
+        .assert()
+        .success()
+        .stdout("lox\n");
+}
+
+#[test]
+fn sem_prelude_leaves_the_prelude_out() {
+    mano()
+        .args(["-e", "salve maior(1, 2);"])
+        .assert()
+        .success()
+        .stdout("2\n");
+    mano()
+        .args(["--sem-prelude", "-e", "salve maior(1, 2);"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("maior"));
+}
+
+#[test]
+fn conditional_blocks_follow_the_backend_and_defines() {
+    let code = "#seVm\n1 + 1\n#vacilou\nsalve \"interpretador\";\n#fim\n#se DEBUG\nsalve \"debug\";\n#fim\n";
+    mano()
+        .args(["-e", code])
+        .assert()
+        .success()
+        .stdout("interpretador\n");
+    mano()
+        .args(["--define", "DEBUG", "-e", code])
+        .assert()
+        .success()
+        .stdout("interpretador\ndebug\n");
+    mano()
+        .args(["--vm", "-e", code])
+        .assert()
+        .success()
+        .stdout("2\n");
+}
+
+#[test]
+fn vm_says_lists_are_not_supported_yet() {
+    mano()
+        .args(["--vm", "-e", "salve [1,2][0];"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("Lista ainda não roda no VM"));
+}
+
+#[test]
+fn hashes_in_strings_and_comments_are_not_directives() {
+    mano()
+        .args(["-e", "salve \"itens:\n#1 primeiro\n\";"])
+        .assert()
+        .success()
+        .stdout("itens:\n#1 primeiro\n\n");
+    mano()
+        .args(["-e", "/* notas\n# nota\n*/\nsalve 1;"])
+        .assert()
+        .success()
+        .stdout("1\n");
+}
+
+#[test]
+fn strict_mode_refuses_a_fita_declared_twice() {
+    let code = "olhaEssaFita f() { toma 1; }\nolhaEssaFita f() { toma 2; }\nsalve f();";
+    mano().args(["-e", code]).assert().success().stdout("2\n");
+    mano()
+        .args(["--estrito", "-e", code])
+        .assert()
+        .code(65)
+        .stdout("")
+        .stderr(predicates::str::contains("<cmdline>:2:14"));
+}
+
+#[test]
+fn errors_in_wrapped_input_point_at_what_was_typed() {
+    mano()
+        .args(["-e", "seLiga x = 2;\nsumido * x", "-p"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("<cmdline>:2:"));
+    mano()
+        .args(["--interactive-output"])
+        .write_stdin("sumido + 1\n")
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains(" :1:1 "));
+}
+
+#[test]
+fn stops_quietly_when_the_reader_closes_the_pipe() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("mano"))
+        .args(["-e", "segueOFluxo (firmeza) salve \"sim\";"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut first = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut first)
+        .unwrap();
+    assert_eq!(first, "sim\n");
+
+    // The reader is gone now, like `mano ... | head -1`
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Runs mano until it prints `first_line`, then sends it a SIGINT, returning
+/// how it exited and what it wrote to stderr
+#[cfg(unix)]
+fn interrupt_after_first_line(
+    args: &[&str],
+    first_line: &str,
+) -> (std::process::ExitStatus, String) {
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::Stdio;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("mano"))
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut first = String::new();
+    stdout.read_line(&mut first).unwrap();
+    assert_eq!(first.trim_end(), first_line);
+
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let status = child.wait().unwrap();
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    (status, stderr)
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_stops_the_script_keeping_its_output() {
+    let code = "salve \"comecei\";\nsegueOFluxo (firmeza) {}";
+    let (status, stderr) = interrupt_after_first_line(&["-e", code], "comecei");
+    assert_eq!(status.code(), Some(130));
+    assert!(stderr.contains("interrompido pelo usuário"), "{stderr}");
+    assert!(strip_ansi(&stderr).contains("<cmdline>:2:"), "{stderr}");
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_stops_a_vm_loop() {
+    let code = "salve 1;\nsegueOFluxo (firmeza) {}";
+    let (status, stderr) = interrupt_after_first_line(&["--vm", "-e", code], "1");
+    assert_eq!(status.code(), Some(130));
+    assert!(stderr.contains("interrompido pelo usuário"), "{stderr}");
+    assert!(strip_ansi(&stderr).contains("<cmdline>:2:1"), "{stderr}");
+}
@@ -0,0 +1,395 @@
+//! Runs Lox programs written in the format of the Crafting Interpreters test
+//! suite under both backends, using the lox dialect.
+//!
+//! `tests/lox` holds a handful of programs in that format. Point
+//! `LOX_TEST_SUITE` at the `test` directory of a craftinginterpreters checkout
+//! to run the whole official suite instead.
+//!
+//! Expectations are read from comments: `// expect: output`, `// expect
+//! runtime error: ...` and compile errors (`// Error at ...`, `// [line N]
+//! Error ...`). Only the kind of error is checked, mano has its own messages.
+//! Programs mano can't run the way Lox does are counted as divergences, each
+//! with a reason; anything else that fails fails the test. The VM also skips
+//! programs using a feature it can't compile yet, listed in
+//! `VM_MISSING_FEATURES`.
+//!
+//! CI runs the official suite in the `lox` job, which checks out
+//! craftinginterpreters and sets `LOX_TEST_SUITE`. It doesn't block merges
+//! until a run against the whole suite is green.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mano::{Dialect, Mano, ManoError, Scanner, TokenType};
+use mano_vm::CompileOptions;
+
+/// Programs that rely on Lox behavior mano doesn't have, by path in the suite
+const KNOWN_DIVERGENCES: &[(&str, &str)] = &[
+    ("benchmark/", "benchmarks, not conformance tests"),
+    ("expressions/", "tests for the book's intermediate chapters"),
+    ("scanning/", "tests for the book's intermediate chapters"),
+    ("limit/", "clox implementation limits"),
+    ("inheritance/inherit_init.lox", "bora is not inherited"),
+];
+
+/// Tokens of the features the VM doesn't compile yet, with why it skips them
+const VM_MISSING_FEATURES: &[(TokenType, &str)] = &[
+    (TokenType::Var, "the VM has no variables yet"),
+    (TokenType::Identifier, "the VM has no variables yet"),
+    (TokenType::Fun, "the VM has no functions yet"),
+    (TokenType::Return, "the VM has no functions yet"),
+    (TokenType::Class, "the VM has no classes yet"),
+    (TokenType::This, "the VM has no classes yet"),
+    (TokenType::Super, "the VM has no classes yet"),
+    (TokenType::For, "the VM has no for loops yet"),
+];
+
+#[derive(Debug, Default, PartialEq)]
+struct Expected {
+    output: Vec<String>,
+    runtime_error: bool,
+    compile_error: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Passed,
+    Diverged(&'static str),
+    Failed(String),
+}
+
+impl Expected {
+    fn parse(source: &str) -> Self {
+        let mut expected = Expected::default();
+        for line in source.lines() {
+            let Some((_, comment)) = line.split_once("// ") else {
+                continue;
+            };
+            if let Some(output) = comment.strip_prefix("expect: ") {
+                expected.output.push(mano_output(output));
+            } else if comment.starts_with("expect runtime error:") {
+                expected.runtime_error = true;
+            } else if comment.starts_with("[java line") {
+                // Only for jlox, clox reports these differently
+            } else if comment.starts_with("Error") || comment.starts_with("[line") {
+                expected.compile_error = true;
+            }
+        }
+        expected
+    }
+}
+
+/// How mano prints a value Lox printed as `output`
+fn mano_output(output: &str) -> String {
+    match output {
+        "true" => "firmeza",
+        "false" => "treta",
+        "nil" => "nadaNão",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Whether the program prints one of its classes, which Lox shows by name
+fn prints_class(source: &str) -> bool {
+    let tokens: Vec<_> = Scanner::new(source)
+        .with_dialect(Dialect::Lox)
+        .filter_map(Result::ok)
+        .collect();
+    let classes: Vec<_> = tokens
+        .windows(2)
+        .filter(|pair| pair[0].token_type == TokenType::Class)
+        .map(|pair| &pair[1].lexeme)
+        .collect();
+    tokens.windows(3).any(|window| {
+        window[0].token_type == TokenType::Print
+            && classes.contains(&&window[1].lexeme)
+            && window[2].token_type == TokenType::Semicolon
+    })
+}
+
+/// Why a program can't behave like Lox under mano, if it can't
+fn divergence(path: &str, source: &str, expected: &Expected) -> Option<&'static str> {
+    if let Some((_, reason)) = KNOWN_DIVERGENCES
+        .iter()
+        .find(|(prefix, _)| path.starts_with(prefix))
+    {
+        return Some(reason);
+    }
+    let shows_value = |line: &String| {
+        line.starts_with("<fn ") || line == "<native fn>" || line.ends_with(" instance")
+    };
+    if expected.output.iter().any(shows_value) || prints_class(source) {
+        return Some("mano prints fitas, bagulhos and instances its own way");
+    }
+    if source.lines().any(|line| {
+        line.split('"')
+            .skip(1)
+            .step_by(2)
+            .any(|text| text.contains(['{', '}']))
+    }) {
+        return Some("braces in strings are interpolation in mano");
+    }
+    None
+}
+
+/// The first feature in `source` the VM can't compile, if any
+fn vm_missing_feature(source: &str) -> Option<&'static str> {
+    let tokens: Vec<_> = Scanner::new(source)
+        .with_dialect(Dialect::Lox)
+        .filter_map(Result::ok)
+        .map(|token| token.token_type)
+        .collect();
+    VM_MISSING_FEATURES
+        .iter()
+        .find(|(token_type, _)| tokens.contains(token_type))
+        .map(|(_, reason)| *reason)
+}
+
+/// Compares what ran against the expectations
+fn check(expected: &Expected, output: &[u8], errors: &[ManoError]) -> Result<(), String> {
+    let output: Vec<_> = String::from_utf8_lossy(output)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let runtime_error = errors
+        .iter()
+        .any(|e| matches!(e.primary(), ManoError::Runtime { .. }));
+    let compile_error = errors.iter().any(|e| {
+        matches!(
+            e.primary(),
+            ManoError::Scan { .. } | ManoError::Parse { .. } | ManoError::Resolution { .. }
+        )
+    });
+
+    if compile_error != expected.compile_error {
+        return Err(format!("compile errors: {:?}", errors));
+    }
+    if runtime_error != expected.runtime_error {
+        return Err(format!("runtime errors: {:?}", errors));
+    }
+    if output != expected.output {
+        return Err(format!(
+            "expected output {:?}, got {:?}",
+            expected.output, output
+        ));
+    }
+    Ok(())
+}
+
+/// mano rejects unused locals, Lox doesn't care
+fn only_unused_variables(errors: &[ManoError]) -> bool {
+    !errors.is_empty()
+        && errors.iter().all(|e| {
+            matches!(e, ManoError::Resolution { message, .. } if message.contains("nunca foi usada"))
+        })
+}
+
+/// Reading a variable declared without a value is an error in mano, Lox
+/// gives nil
+fn read_empty_variable(errors: &[ManoError]) -> bool {
+    errors.iter().any(|e| {
+        matches!(e.primary(), ManoError::Runtime { message, .. } if message.contains("tá vazia, chapa"))
+    })
+}
+
+fn run_interpreter(path: &str, source: &str) -> Outcome {
+    let expected = Expected::parse(source);
+    if let Some(reason) = divergence(path, source, &expected) {
+        return Outcome::Diverged(reason);
+    }
+
+    let mut mano = Mano::new();
+    mano.set_dialect(Dialect::Lox);
+    let mut output = Vec::new();
+    let errors = mano.run(source, &mut output);
+    if !expected.compile_error && only_unused_variables(&errors) {
+        return Outcome::Diverged("unused locals are errors in mano");
+    }
+    if !expected.runtime_error && read_empty_variable(&errors) {
+        return Outcome::Diverged("variables without a value can't be read in mano");
+    }
+    let resolution_error = |e: &ManoError| matches!(e, ManoError::Resolution { .. });
+    if expected.runtime_error && !errors.is_empty() && errors.iter().all(resolution_error) {
+        return Outcome::Diverged("mano catches this before running");
+    }
+    match check(&expected, &output, &errors) {
+        Ok(()) => Outcome::Passed,
+        Err(problem) => Outcome::Failed(problem),
+    }
+}
+
+fn run_vm(path: &str, source: &str) -> Outcome {
+    let expected = Expected::parse(source);
+    if let Some(reason) = divergence(path, source, &expected) {
+        return Outcome::Diverged(reason);
+    }
+    if let Some(reason) = vm_missing_feature(source) {
+        return Outcome::Diverged(reason);
+    }
+
+    let options = CompileOptions {
+        dialect: Dialect::Lox,
+        ..Default::default()
+    };
+    let chunk = match mano_vm::compile(source, &options) {
+        Ok(compiled) => compiled.chunk,
+        Err(errors) => {
+            return match check(&expected, &[], &errors) {
+                Ok(()) => Outcome::Passed,
+                Err(problem) => Outcome::Failed(problem),
+            };
+        }
+    };
+    let mut output = Vec::new();
+    let errors = mano_vm::run_chunk(&chunk, source, &mut output, false)
+        .err()
+        .unwrap_or_default();
+    match check(&expected, &output, &errors) {
+        Ok(()) => Outcome::Passed,
+        Err(problem) => Outcome::Failed(problem),
+    }
+}
+
+fn lox_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            lox_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+fn suite() -> (PathBuf, Vec<PathBuf>) {
+    let root = std::env::var_os("LOX_TEST_SUITE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox"));
+    let mut files = Vec::new();
+    lox_files(&root, &mut files);
+    files.sort();
+    (root, files)
+}
+
+/// Runs every program, failing with a report of the ones that didn't pass
+fn conformance(backend: &str, run: fn(&str, &str) -> Outcome) {
+    let (root, files) = suite();
+    assert!(!files.is_empty(), "no .lox files in {}", root.display());
+
+    let (mut passed, mut diverged, mut failures) = (0, 0, Vec::new());
+    for file in &files {
+        let path = file
+            .strip_prefix(&root)
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        let source = fs::read_to_string(file).unwrap();
+        match run(&path, &source) {
+            Outcome::Passed => {
+                if KNOWN_DIVERGENCES.iter().any(|(known, _)| *known == path) {
+                    failures.push(format!(
+                        "{path}: passes now, drop it from KNOWN_DIVERGENCES"
+                    ));
+                }
+                passed += 1;
+            }
+            Outcome::Diverged(_) => diverged += 1,
+            Outcome::Failed(problem) => failures.push(format!("{path}: {problem}")),
+        }
+    }
+
+    eprintln!(
+        "lox ({backend}): {passed} passed, {diverged} diverged, {} failed",
+        failures.len()
+    );
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn interpreter_runs_lox_suite() {
+    conformance("interpreter", run_interpreter);
+}
+
+#[test]
+fn vm_runs_lox_suite() {
+    conformance("vm", run_vm);
+}
+
+#[test]
+fn reads_expectations_from_comments() {
+    let expected = Expected::parse(
+        "print true; // expect: true\n\
+         a = 1; // Error at 'a': Undefined.\n\
+         -nil; // expect runtime error: Operand must be a number.\n\
+         // [java line 3] Error at end: Expect ';'.",
+    );
+    assert_eq!(
+        expected,
+        Expected {
+            output: vec!["firmeza".to_string()],
+            runtime_error: true,
+            compile_error: true,
+        }
+    );
+}
+
+#[test]
+fn categorizes_divergences() {
+    let printed_fn = Expected::parse("print f; // expect: <fn f>");
+    assert!(divergence("function/print.lox", "", &printed_fn).is_some());
+    assert!(divergence("a.lox", "print \"{\";", &Expected::default()).is_some());
+    let source = "class Foo {}\nprint Foo; // expect: Foo";
+    assert!(divergence("class/empty.lox", source, &Expected::parse(source)).is_some());
+    let source = "class A {}\nprint \"A\"; // expect: A";
+    assert_eq!(divergence("a.lox", source, &Expected::parse(source)), None);
+    assert!(divergence("benchmark/fib.lox", "", &Expected::default()).is_some());
+    assert_eq!(divergence("a.lox", "print 1;", &Expected::default()), None);
+}
+
+#[test]
+fn vm_skips_only_missing_features() {
+    assert_eq!(
+        run_vm("a.lox", "var a = 1; print a; // expect: 1"),
+        Outcome::Diverged("the VM has no variables yet")
+    );
+    assert_eq!(
+        run_vm("a.lox", "for (;;) {}"),
+        Outcome::Diverged("the VM has no for loops yet")
+    );
+    assert_eq!(
+        run_vm("a.lox", "print 1 + 2; // expect: 3"),
+        Outcome::Passed
+    );
+    assert!(matches!(run_vm("a.lox", "print 1 +;"), Outcome::Failed(_)));
+    assert_eq!(
+        run_vm("a.lox", "print 1 +; // Error at ';': Expect expression."),
+        Outcome::Passed
+    );
+}
+
+#[test]
+fn sample_programs_cover_each_outcome() {
+    let (root, _) = suite();
+    if std::env::var_os("LOX_TEST_SUITE").is_some() {
+        return;
+    }
+    let outcome = |path: &str| {
+        let source = fs::read_to_string(root.join(path)).unwrap();
+        run_interpreter(path, &source)
+    };
+    assert_eq!(outcome("closure/counter.lox"), Outcome::Passed);
+    assert_eq!(outcome("operator/add_bool_string.lox"), Outcome::Passed);
+    assert_eq!(
+        outcome("function/unused_parameter.lox"),
+        Outcome::Diverged("unused locals are errors in mano")
+    );
+    assert!(matches!(
+        outcome("inheritance/inherit_init.lox"),
+        Outcome::Diverged(_)
+    ));
+    assert_eq!(
+        outcome("variable/redeclare_global.lox"),
+        Outcome::Diverged("variables without a value can't be read in mano")
+    );
+}
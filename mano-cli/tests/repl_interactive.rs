@@ -2,17 +2,22 @@ use rexpect::error::Error;
 use rexpect::spawn;
 
 fn mano_repl() -> Result<rexpect::session::PtySession, Error> {
-    spawn("cargo run --quiet", Some(30_000))
+    spawn(env!("CARGO_BIN_EXE_mano"), Some(30_000))
 }
 
 #[test]
-fn ctrl_c_on_empty_prompt_exits() -> Result<(), Error> {
+fn ctrl_c_twice_on_empty_prompt_exits() -> Result<(), Error> {
     let mut p = mano_repl()?;
 
     // Wait for prompt
     p.exp_string("> ")?;
 
-    // Send Ctrl+C on empty prompt - should exit
+    // First Ctrl+C only says how to quit, the second one quits. It's only
+    // read as a key once the prompt is back; before that it's a signal, and
+    // the handler for running code takes it.
+    p.send_control('c')?;
+    p.exp_string("Ctrl+C de novo")?;
+    p.exp_string("> ")?;
     p.send_control('c')?;
 
     // Process should exit
@@ -43,10 +48,14 @@ fn ctrl_c_mid_block_cancels_and_continues() -> Result<(), Error> {
     // Now we can type a normal statement
     p.send_line("salve 42;")?;
 
-    // Should output 42
+    // Should output 42, then prompt again
     p.exp_string("42")?;
+    p.exp_string("> ")?;
 
-    // Exit cleanly
+    // Exit cleanly, once the prompt is back after the first Ctrl+C
+    p.send_control('c')?;
+    p.exp_string("Ctrl+C de novo")?;
+    p.exp_string("> ")?;
     p.send_control('c')?;
     p.exp_eof()?;
 
@@ -0,0 +1,497 @@
+//! The workspace symbol index: the declarations of every `.mano` file in the
+//! workspace, for `workspace/symbol`, and the modules each one `chama`s, for
+//! renames that reach other files.
+//!
+//! It's saved to a cache file, so after a restart only the files whose size
+//! or modification time changed are read again, and only the ones whose
+//! contents changed are parsed again.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use lsp_types::{SymbolInformation, Uri};
+use mano::{Literal, Stmt};
+use serde_json::{Value, json};
+
+use crate::{get_document_symbols, parse_statements};
+
+/// Bumped when the cache layout changes, so older caches get ignored
+const CACHE_VERSION: u64 = 2;
+
+/// What a file looked like on disk when it was indexed
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stamp {
+    /// Modification time, in seconds and nanoseconds since the epoch
+    modified: (u64, u32),
+    len: u64,
+}
+
+impl Stamp {
+    fn of(metadata: &fs::Metadata) -> Option<Stamp> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Stamp {
+            modified: (modified.as_secs(), modified.subsec_nanos()),
+            len: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// `None` when indexed from an open document, whose unsaved contents the
+    /// file on disk may not have, so it's read again on the next refresh
+    stamp: Option<Stamp>,
+    hash: u64,
+    symbols: Vec<SymbolInformation>,
+    /// The files its `chama`s load
+    imports: Vec<PathBuf>,
+}
+
+impl Entry {
+    fn new(path: &Path, source: &str, uri: Uri, stamp: Option<Stamp>) -> Entry {
+        Entry {
+            stamp,
+            hash: hash(source.as_bytes()),
+            symbols: get_document_symbols(source, uri),
+            imports: imports(path, source),
+        }
+    }
+}
+
+/// Declarations by file, sorted by path so results come in a stable order
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    files: BTreeMap<PathBuf, Entry>,
+    /// Where the index is saved, `None` to keep it in memory only
+    cache: Option<PathBuf>,
+}
+
+impl SymbolIndex {
+    /// The index saved in `cache`, or an empty one saving there when there's
+    /// none yet or it can't be read
+    pub fn load(cache: PathBuf) -> Self {
+        let files = fs::read_to_string(&cache)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .and_then(|value| read_cache(&value))
+            .unwrap_or_default();
+        Self {
+            files,
+            cache: Some(cache),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(cache) = &self.cache else {
+            return Ok(());
+        };
+        if let Some(dir) = cache.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let files: Vec<Value> = self
+            .files
+            .iter()
+            .map(|(path, entry)| {
+                json!({
+                    "path": path.to_string_lossy(),
+                    "modified": entry.stamp.map(|stamp| stamp.modified),
+                    "len": entry.stamp.map(|stamp| stamp.len),
+                    "hash": entry.hash,
+                    "symbols": entry.symbols,
+                    "imports": entry
+                        .imports
+                        .iter()
+                        .map(|path| path.to_string_lossy())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let cache_json = json!({ "version": CACHE_VERSION, "files": files });
+        fs::write(cache, cache_json.to_string())
+    }
+
+    /// Catches up with the `.mano` files under `roots`, forgetting the ones
+    /// that are gone. Files over `max_size` bytes are left out, like they
+    /// are from analysis. Returns how many files were parsed.
+    pub fn refresh(&mut self, roots: &[PathBuf], max_size: usize) -> usize {
+        let mut paths = Vec::new();
+        for root in roots {
+            find_sources(root, &mut paths);
+        }
+
+        let mut parsed = 0;
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.len() > max_size as u64 {
+                continue;
+            }
+            let stamp = Stamp::of(&metadata);
+            let entry = match self.files.remove(&path) {
+                Some(entry) if stamp.is_some() && entry.stamp == stamp => entry,
+                old => {
+                    let Ok(source) = fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    let hash = hash(source.as_bytes());
+                    match old {
+                        // Touched but not changed, like after a checkout
+                        Some(entry) if entry.hash == hash => Entry { stamp, ..entry },
+                        _ => {
+                            let Some(uri) = file_uri(&path) else {
+                                continue;
+                            };
+                            parsed += 1;
+                            Entry::new(&path, &source, uri, stamp)
+                        }
+                    }
+                }
+            };
+            files.insert(path, entry);
+        }
+        self.files = files;
+        parsed
+    }
+
+    /// Indexes an open document's contents, which may not be saved yet
+    pub fn update(&mut self, uri: &Uri, source: &str) {
+        let Some(path) = file_path(uri) else {
+            return;
+        };
+        let entry = Entry::new(&path, source, uri.clone(), None);
+        self.files.insert(normalize(&path), entry);
+    }
+
+    /// Symbols whose name has `query` in it, ignoring case. An empty query
+    /// gets them all.
+    pub fn search(&self, query: &str) -> Vec<SymbolInformation> {
+        let query = query.to_lowercase();
+        self.files
+            .values()
+            .flat_map(|entry| &entry.symbols)
+            .filter(|symbol| symbol.name.to_lowercase().contains(&query))
+            .cloned()
+            .collect()
+    }
+
+    /// The files that `chama` `file`, directly or through other modules, and
+    /// so see its globals
+    pub fn importers(&self, file: &Path) -> Vec<PathBuf> {
+        self.reach(file, |current, _, entry| {
+            entry.imports.iter().any(|import| import == current)
+        })
+    }
+
+    /// The modules `file` `chama`s, directly or through other modules
+    pub fn modules(&self, file: &Path) -> Vec<PathBuf> {
+        self.reach(file, |current, path, _| {
+            self.files
+                .get(current)
+                .is_some_and(|entry| entry.imports.iter().any(|import| import == path))
+        })
+    }
+
+    /// The files other than `file` linked to it, one module at a time, where
+    /// `linked(current, path, entry)` says whether `path` is linked to `current`
+    fn reach(&self, file: &Path, linked: impl Fn(&Path, &Path, &Entry) -> bool) -> Vec<PathBuf> {
+        let file = normalize(file);
+        let mut found = BTreeSet::new();
+        let mut queue = vec![file.clone()];
+        while let Some(current) = queue.pop() {
+            for (path, entry) in &self.files {
+                if *path != file && !found.contains(path) && linked(&current, path, entry) {
+                    found.insert(path.clone());
+                    queue.push(path.clone());
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+}
+
+fn read_cache(cache: &Value) -> Option<BTreeMap<PathBuf, Entry>> {
+    if cache.get("version")?.as_u64()? != CACHE_VERSION {
+        return None;
+    }
+    let mut files = BTreeMap::new();
+    for file in cache.get("files")?.as_array()? {
+        let path = PathBuf::from(file.get("path")?.as_str()?);
+        let stamp = match (file.get("modified")?.as_array(), file.get("len")?.as_u64()) {
+            (Some(modified), Some(len)) => Some(Stamp {
+                modified: (
+                    modified.first()?.as_u64()?,
+                    modified.get(1)?.as_u64()? as u32,
+                ),
+                len,
+            }),
+            _ => None,
+        };
+        let imports = file.get("imports")?.as_array()?;
+        let entry = Entry {
+            stamp,
+            hash: file.get("hash")?.as_u64()?,
+            symbols: serde_json::from_value(file.get("symbols")?.clone()).ok()?,
+            imports: imports
+                .iter()
+                .map(|path| path.as_str().map(PathBuf::from))
+                .collect::<Option<_>>()?,
+        };
+        files.insert(path, entry);
+    }
+    Some(files)
+}
+
+/// Where the index of the workspace with `roots` is saved in `dir`, one
+/// file per workspace
+pub fn cache_file(dir: &Path, roots: &[PathBuf]) -> PathBuf {
+    let mut key = Vec::new();
+    for root in roots {
+        key.extend_from_slice(root.to_string_lossy().as_bytes());
+        key.push(0);
+    }
+    dir.join(format!("{:016x}.json", hash(&key)))
+}
+
+/// The user's cache directory, when there's one to be found
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let cache = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(cache.join("mano-lsp"))
+}
+
+/// Every `.mano` file under `dir`, skipping hidden directories like `.git`
+/// and `.mano_modules`
+fn find_sources(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                find_sources(&path, paths);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "mano") {
+            paths.push(path);
+        }
+    }
+}
+
+/// The files the top-level `chama`s of `source`, in `file`, load: relative to
+/// the file's folder, with `.mano` added when the path has no extension
+fn imports(file: &Path, source: &str) -> Vec<PathBuf> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    parse_statements(source)
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Import { path, .. } => match &path.literal {
+                Some(Literal::String(name)) => {
+                    let mut module = dir.join(name);
+                    if module.extension().is_none() {
+                        module.set_extension("mano");
+                    }
+                    Some(normalize(&module))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The path without `.` and `..` parts, so a module reached through
+/// `../lib/util` is the same file as `lib/util` from the workspace root
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// FNV-1a, which unlike the standard library's hasher gives the same hash
+/// in every build, so hashes saved by one build hold for the next
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The path of a `file:` uri
+pub fn file_path(uri: &Uri) -> Option<PathBuf> {
+    if !uri.scheme()?.as_str().eq_ignore_ascii_case("file") {
+        return None;
+    }
+    let path = uri.path().as_estr().decode().into_string_lossy();
+    Some(PathBuf::from(path.as_ref()))
+}
+
+/// The `file:` uri of an absolute path
+pub fn file_uri(path: &Path) -> Option<Uri> {
+    let path = path.to_str()?.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        // Windows paths, like C:/code
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    fn names(index: &SymbolIndex, query: &str) -> Vec<String> {
+        index
+            .search(query)
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect()
+    }
+
+    #[test]
+    fn refresh_indexes_mano_files_but_not_hidden_folders() {
+        let dir = workspace(&[
+            ("a.mano", "olhaEssaFita soma(a, b) { toma a + b; }"),
+            ("lib/b.mano", "bagulho Pessoa {}"),
+            (".mano_modules/c.mano", "seLiga escondido = 1;"),
+            ("notas.txt", "seLiga texto = 1;"),
+        ]);
+        let mut index = SymbolIndex::default();
+
+        assert_eq!(index.refresh(&[dir.path().to_path_buf()], usize::MAX), 2);
+        assert_eq!(names(&index, ""), vec!["soma", "Pessoa"]);
+        assert_eq!(names(&index, "PES"), vec!["Pessoa"]);
+    }
+
+    #[test]
+    fn refresh_skips_files_over_the_size_limit() {
+        let dir = workspace(&[("a.mano", "seLiga x = 1;")]);
+        let mut index = SymbolIndex::default();
+
+        assert_eq!(index.refresh(&[dir.path().to_path_buf()], 4), 0);
+        assert!(index.search("").is_empty());
+    }
+
+    #[test]
+    fn a_saved_index_only_parses_what_changed() {
+        let dir = workspace(&[("a.mano", "seLiga a = 1;"), ("b.mano", "seLiga b = 1;")]);
+        let roots = [dir.path().to_path_buf()];
+        let cache = dir.path().join(".cache/index.json");
+        let mut index = SymbolIndex::load(cache.clone());
+        assert_eq!(index.refresh(&roots, usize::MAX), 2);
+        index.save().unwrap();
+
+        fs::write(dir.path().join("b.mano"), "seLiga bb = 1;").unwrap();
+        fs::remove_file(dir.path().join("a.mano")).unwrap();
+        let mut index = SymbolIndex::load(cache);
+        assert_eq!(names(&index, ""), vec!["a", "b"]);
+        assert_eq!(index.refresh(&roots, usize::MAX), 1);
+        assert_eq!(names(&index, ""), vec!["bb"]);
+    }
+
+    #[test]
+    fn open_documents_are_read_again_on_the_next_refresh() {
+        let dir = workspace(&[("a.mano", "seLiga a = 1;")]);
+        let roots = [dir.path().to_path_buf()];
+        let mut index = SymbolIndex::default();
+        index.refresh(&roots, usize::MAX);
+
+        let uri = file_uri(&dir.path().join("a.mano")).unwrap();
+        index.update(&uri, "seLiga naoSalvo = 1;");
+        assert_eq!(names(&index, ""), vec!["naoSalvo"]);
+        assert_eq!(index.refresh(&roots, usize::MAX), 1);
+        assert_eq!(names(&index, ""), vec!["a"]);
+    }
+
+    #[test]
+    fn chama_chains_link_importers_and_modules() {
+        let dir = workspace(&[
+            ("util.mano", "seLiga x = 1;"),
+            ("main.mano", "chama \"util\";"),
+            ("app/usa.mano", "chama \"../main.mano\";"),
+            ("solto.mano", ""),
+        ]);
+        let mut index = SymbolIndex::default();
+        index.refresh(&[dir.path().to_path_buf()], usize::MAX);
+        let path = |name: &str| dir.path().join(name);
+
+        assert_eq!(
+            index.importers(&path("util.mano")),
+            vec![path("app/usa.mano"), path("main.mano")]
+        );
+        assert_eq!(
+            index.modules(&path("app/usa.mano")),
+            vec![path("main.mano"), path("util.mano")]
+        );
+        assert!(index.importers(&path("solto.mano")).is_empty());
+    }
+
+    #[test]
+    fn unreadable_caches_start_empty() {
+        let dir = workspace(&[("index.json", "{\"version\": 0, \"files\": []}")]);
+        assert!(
+            SymbolIndex::load(dir.path().join("index.json"))
+                .files
+                .is_empty()
+        );
+        assert!(
+            SymbolIndex::load(dir.path().join("sumiu.json"))
+                .files
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn file_uris_round_trip() {
+        let path = Path::new("/home/zé/meu código/a.mano");
+        let uri = file_uri(path).unwrap();
+        assert_eq!(
+            uri.as_str(),
+            "file:///home/z%C3%A9/meu%20c%C3%B3digo/a.mano"
+        );
+        assert_eq!(file_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn cache_files_differ_by_workspace() {
+        let dir = Path::new("/cache");
+        let a = cache_file(dir, &[PathBuf::from("/a")]);
+        assert_eq!(a, cache_file(dir, &[PathBuf::from("/a")]));
+        assert_ne!(a, cache_file(dir, &[PathBuf::from("/b")]));
+    }
+}
@@ -1,24 +1,48 @@
-use std::collections::HashMap;
-use std::error::Error;
+mod index;
 
-use lsp_server::{Connection, Message, Notification, Request, Response};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use index::SymbolIndex;
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, Diagnostic,
-    DiagnosticSeverity, DocumentSymbolParams, DocumentSymbolResponse, FoldingRange,
-    FoldingRangeParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
-    HoverParams, HoverProviderCapability, InitializeParams, Location, MarkupContent, MarkupKind,
-    OneOf, Position, PublishDiagnosticsParams, Range, ReferenceParams, RenameParams,
-    ServerCapabilities, SymbolInformation, SymbolKind, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
-    notification::{DidChangeTextDocument, DidOpenTextDocument, Notification as _},
+    AnnotatedTextEdit, ChangeAnnotation, ClientCapabilities, CodeLens, CodeLensOptions,
+    CodeLensParams, Command, CompletionItem, CompletionItemKind, CompletionOptions,
+    CompletionParams, Diagnostic, DiagnosticOptions, DiagnosticRelatedInformation,
+    DiagnosticServerCapabilities, DiagnosticSeverity, DiagnosticTag, DocumentChanges,
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticReportResult,
+    DocumentFormattingParams, DocumentLink, DocumentLinkOptions, DocumentLinkParams,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandOptions, ExecuteCommandParams,
+    FoldingRange, FoldingRangeParams, FullDocumentDiagnosticReport, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability,
+    ImplementationProviderCapability, InitializeParams, Location, MarkupContent, MarkupKind,
+    MessageType, OneOf, OptionalVersionedTextDocumentIdentifier, Position,
+    PublishDiagnosticsParams, Range, ReferenceParams, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, RenameParams, ServerCapabilities, ShowMessageParams,
+    SymbolInformation, SymbolKind, TextDocumentEdit, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, TypeDefinitionProviderCapability,
+    UnchangedDocumentDiagnosticReport, Uri, WorkspaceEdit, WorkspaceSymbolParams,
+    WorkspaceSymbolResponse,
+    notification::{
+        DidChangeConfiguration, DidChangeTextDocument, DidOpenTextDocument, Notification as _,
+    },
     request::{
-        Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, HoverRequest,
-        PrepareRenameRequest, References, Rename, Request as _,
+        CodeLensRequest, Completion, DocumentDiagnosticRequest, DocumentLinkRequest,
+        DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting, GotoDefinition,
+        GotoImplementation, GotoTypeDefinition, HoverRequest, PrepareRenameRequest, References,
+        Rename, Request as _, WorkspaceDiagnosticRefresh, WorkspaceSymbolRequest,
     },
 };
 use mano::{
-    Expr, INITIALIZER_NAME, KEYWORDS, ManoError, NATIVE_FUNCTIONS, Parser, Scanner, Stmt,
-    TokenType, is_identifier_char,
+    Analysis, DeclarationKind, Dialect, Expr, FormatOptions, INITIALIZER_NAME, InterpolationPart,
+    KEYWORDS, LineCol, LineIndex, Literal, ManoError, NATIVE_FUNCTIONS, PRELUDE_NAMES, Parser,
+    SandboxPolicy, Scanner, ScopeTree, Stmt, Token, TokenType, word_at,
 };
 
 fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
@@ -30,14 +54,35 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
         text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
         completion_provider: Some(CompletionOptions::default()),
         definition_provider: Some(OneOf::Left(true)),
+        type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
         document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
         references_provider: Some(OneOf::Left(true)),
         rename_provider: Some(OneOf::Right(lsp_types::RenameOptions {
             prepare_provider: Some(true),
             work_done_progress_options: Default::default(),
         })),
         folding_range_provider: Some(lsp_types::FoldingRangeProviderCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+            identifier: Some("mano".to_string()),
+            inter_file_dependencies: false,
+            workspace_diagnostics: false,
+            work_done_progress_options: Default::default(),
+        })),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: Default::default(),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![RUN_COMMAND.to_string()],
+            work_done_progress_options: Default::default(),
+        }),
         ..Default::default()
     })?;
 
@@ -51,30 +96,209 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
         }
     };
 
-    let _: InitializeParams = serde_json::from_value(initialization_params)?;
+    let initialize_params: InitializeParams = serde_json::from_value(initialization_params)?;
+    let mut config = ServerConfig::default();
+    config.read_capabilities(&initialize_params.capabilities);
+    if let Some(options) = &initialize_params.initialization_options {
+        config.update(options);
+    }
     eprintln!("mano-lsp initialized!");
 
-    main_loop(connection)?;
+    let roots = workspace_roots(&initialize_params);
+    let mut index = match config.cache_dir.clone().or_else(index::default_cache_dir) {
+        Some(dir) => SymbolIndex::load(index::cache_file(&dir, &roots)),
+        None => SymbolIndex::default(),
+    };
+    let parsed = index.refresh(&roots, config.max_file_size);
+    eprintln!("mano-lsp indexed the workspace, {parsed} file(s) parsed");
+    if let Err(e) = index.save() {
+        eprintln!("mano-lsp couldn't save the symbol index: {e}");
+    }
+
+    main_loop(connection, config, index)?;
     io_threads.join()?;
 
     eprintln!("mano-lsp shutting down.");
     Ok(())
 }
 
-fn main_loop(connection: Connection) -> Result<(), Box<dyn Error + Sync + Send>> {
+/// The folders of the workspace, for the symbol index
+#[allow(deprecated)] // root_uri is the fallback for clients without workspace folders
+fn workspace_roots(params: &InitializeParams) -> Vec<PathBuf> {
+    match &params.workspace_folders {
+        Some(folders) => folders
+            .iter()
+            .filter_map(|folder| index::file_path(&folder.uri))
+            .collect(),
+        None => params
+            .root_uri
+            .iter()
+            .filter_map(index::file_path)
+            .collect(),
+    }
+}
+
+/// How eval features (like running code from the editor) execute code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Interpreter,
+    Vm,
+}
+
+/// Which code gets a fold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Folding {
+    /// Every statement that spans more than one line
+    Statements,
+    /// Only fitas, bagulhos, opcoes and `{ }` blocks
+    Blocks,
+}
+
+/// Formatting preferences sent by the client
+#[derive(Debug, Clone, PartialEq)]
+struct FormattingConfig {
+    indent_width: usize,
+    use_tabs: bool,
+}
+
+/// Client settings, read from the `mano` section of the editor configuration.
+///
+/// Owned by the main loop and handed to every handler, so a
+/// `workspace/didChangeConfiguration` takes effect on the next request.
+#[derive(Debug, Clone, PartialEq)]
+struct ServerConfig {
+    /// Report resolver checks (unused variables, misplaced `toma`...) as diagnostics
+    lints: bool,
+    max_diagnostics: usize,
+    /// Documents bigger than this many bytes aren't analyzed, so a huge
+    /// generated file doesn't stall the editor
+    max_file_size: usize,
+    backend: Backend,
+    folding: Folding,
+    formatting: FormattingConfig,
+    /// Where the symbol index is saved between runs, instead of the user's
+    /// cache directory. Only read at startup, when the index is loaded.
+    cache_dir: Option<PathBuf>,
+    /// Send renames as annotated edits the user confirms before they're
+    /// applied, for clients that support change annotations
+    confirm_rename: bool,
+    /// The client asks for diagnostics with `textDocument/diagnostic`, so
+    /// they aren't pushed. From its capabilities, not a setting.
+    pull_diagnostics: bool,
+    /// The client can be told to ask for diagnostics again, when settings
+    /// change them
+    refresh_diagnostics: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            lints: true,
+            max_diagnostics: 100,
+            max_file_size: 4 * 1024 * 1024,
+            backend: Backend::Interpreter,
+            folding: Folding::Statements,
+            formatting: FormattingConfig {
+                indent_width: 4,
+                use_tabs: false,
+            },
+            cache_dir: None,
+            confirm_rename: false,
+            pull_diagnostics: false,
+            refresh_diagnostics: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Applies the settings present in `settings`, leaving the rest untouched.
+    /// Accepts either the `mano` section itself or an object containing it.
+    /// Values of the wrong type are ignored.
+    fn update(&mut self, settings: &serde_json::Value) {
+        let settings = settings.get("mano").unwrap_or(settings);
+
+        if let Some(lints) = settings.get("lints").and_then(|v| v.as_bool()) {
+            self.lints = lints;
+        }
+        if let Some(max) = settings.get("maxDiagnostics").and_then(|v| v.as_u64()) {
+            self.max_diagnostics = max as usize;
+        }
+        if let Some(max) = settings.get("maxFileSize").and_then(|v| v.as_u64()) {
+            self.max_file_size = max as usize;
+        }
+        match settings.get("backend").and_then(|v| v.as_str()) {
+            Some("interpreter") => self.backend = Backend::Interpreter,
+            Some("vm") => self.backend = Backend::Vm,
+            _ => {}
+        }
+        match settings.get("folding").and_then(|v| v.as_str()) {
+            Some("statements") => self.folding = Folding::Statements,
+            Some("blocks") => self.folding = Folding::Blocks,
+            _ => {}
+        }
+        if let Some(dir) = settings.get("cacheDir").and_then(|v| v.as_str()) {
+            self.cache_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(confirm) = settings.get("confirmRename").and_then(|v| v.as_bool()) {
+            self.confirm_rename = confirm;
+        }
+        if let Some(formatting) = settings.get("formatting") {
+            if let Some(width) = formatting.get("indentWidth").and_then(|v| v.as_u64()) {
+                self.formatting.indent_width = width as usize;
+            }
+            if let Some(use_tabs) = formatting.get("useTabs").and_then(|v| v.as_bool()) {
+                self.formatting.use_tabs = use_tabs;
+            }
+        }
+    }
+
+    /// Takes what the client can do from the capabilities it sent at startup
+    fn read_capabilities(&mut self, capabilities: &ClientCapabilities) {
+        self.pull_diagnostics = capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.refresh_diagnostics = capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.diagnostic.as_ref())
+            .and_then(|diagnostics| diagnostics.refresh_support)
+            .unwrap_or(false);
+    }
+
+    /// An open document, unless it's too big to analyze
+    fn document<'a>(
+        &self,
+        documents: &'a HashMap<String, String>,
+        uri: &str,
+    ) -> Option<&'a String> {
+        documents
+            .get(uri)
+            .filter(|source| source.len() <= self.max_file_size)
+    }
+}
+
+fn main_loop(
+    connection: Connection,
+    mut config: ServerConfig,
+    mut index: SymbolIndex,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
     let mut documents: HashMap<String, String> = HashMap::new();
 
     for msg in &connection.receiver {
         match msg {
             Message::Request(req) => {
                 if connection.handle_shutdown(&req)? {
+                    if let Err(e) = index.save() {
+                        eprintln!("mano-lsp couldn't save the symbol index: {e}");
+                    }
                     return Ok(());
                 }
-                handle_request(&connection, req, &documents)?;
+                handle_request(&connection, req, &documents, &config, &index)?;
             }
             Message::Response(Response { .. }) => {}
             Message::Notification(not) => {
-                handle_notification(&connection, not, &mut documents)?;
+                handle_notification(&connection, not, &mut documents, &mut config, &mut index)?;
             }
         }
     }
@@ -85,13 +309,15 @@ fn handle_request(
     connection: &Connection,
     req: Request,
     documents: &HashMap<String, String>,
+    config: &ServerConfig,
+    index: &SymbolIndex,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
     if req.method == Completion::METHOD {
         let params: CompletionParams = serde_json::from_value(req.params)?;
         let uri = params.text_document_position.text_document.uri.to_string();
         let position = params.text_document_position.position;
 
-        let completions = if let Some(source) = documents.get(&uri) {
+        let completions = if let Some(source) = config.document(documents, &uri) {
             get_completions_at_position(source, position)
         } else {
             vec![]
@@ -104,13 +330,52 @@ fn handle_request(
         let uri = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
 
-        let result = documents.get(&uri.to_string()).and_then(|source| {
-            find_definition(source, position).map(|range| {
-                GotoDefinitionResponse::Scalar(Location {
-                    uri: uri.clone(),
-                    range,
+        let result = config
+            .document(documents, &uri.to_string())
+            .and_then(|source| {
+                find_definition(source, position).map(|range| {
+                    GotoDefinitionResponse::Scalar(Location {
+                        uri: uri.clone(),
+                        range,
+                    })
                 })
-            })
+            });
+
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == GotoTypeDefinition::METHOD {
+        let params: GotoDefinitionParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let result = config
+            .document(documents, &uri.to_string())
+            .and_then(|source| {
+                find_type_definition(source, position).map(|range| {
+                    GotoDefinitionResponse::Scalar(Location {
+                        uri: uri.clone(),
+                        range,
+                    })
+                })
+            });
+
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == GotoImplementation::METHOD {
+        let params: GotoDefinitionParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let result = config.document(documents, &uri.to_string()).map(|source| {
+            GotoDefinitionResponse::Array(
+                find_implementations(source, position)
+                    .into_iter()
+                    .map(|range| Location {
+                        uri: uri.clone(),
+                        range,
+                    })
+                    .collect(),
+            )
         });
 
         let response = Response::new_ok(req.id, result);
@@ -120,8 +385,8 @@ fn handle_request(
         let uri = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
 
-        let result = documents
-            .get(&uri.to_string())
+        let result = config
+            .document(documents, &uri.to_string())
             .and_then(|source| create_hover_response(source, position));
 
         let response = Response::new_ok(req.id, result);
@@ -130,10 +395,34 @@ fn handle_request(
         let params: DocumentSymbolParams = serde_json::from_value(req.params)?;
         let uri = params.text_document.uri;
 
-        let result = documents
-            .get(&uri.to_string())
+        let result = config
+            .document(documents, &uri.to_string())
             .map(|source| DocumentSymbolResponse::Flat(get_document_symbols(source, uri.clone())));
 
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == DocumentDiagnosticRequest::METHOD {
+        let params: DocumentDiagnosticParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document.uri;
+
+        // Too big to analyze still gets its diagnostic saying so
+        let report = match documents.get(&uri.to_string()) {
+            Some(source) => {
+                diagnostic_report(&uri, source, config, params.previous_result_id.as_deref())
+            }
+            None => DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport::default(),
+            }),
+        };
+
+        let result = DocumentDiagnosticReportResult::Report(report);
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == WorkspaceSymbolRequest::METHOD {
+        let params: WorkspaceSymbolParams = serde_json::from_value(req.params)?;
+        let result = WorkspaceSymbolResponse::Flat(index.search(&params.query));
+
         let response = Response::new_ok(req.id, result);
         connection.sender.send(Message::Response(response))?;
     } else if req.method == References::METHOD {
@@ -141,8 +430,8 @@ fn handle_request(
         let uri = params.text_document_position.text_document.uri;
         let position = params.text_document_position.position;
 
-        let result = documents
-            .get(&uri.to_string())
+        let result = config
+            .document(documents, &uri.to_string())
             .map(|source| find_references(source, position, uri.clone()));
 
         let response = Response::new_ok(req.id, result);
@@ -153,12 +442,42 @@ fn handle_request(
         let position = params.text_document_position.position;
         let new_name = params.new_name;
 
-        let result = documents.get(&uri.to_string()).map(|source| {
-            let edits = get_rename_edits(source, position, &new_name, uri.clone());
-            WorkspaceEdit {
-                changes: Some(HashMap::from([(uri, edits)])),
-                ..Default::default()
+        let source = config.document(documents, &uri.to_string());
+        let rename =
+            source.map(|source| get_rename_edits(source, position, &new_name, uri.clone()));
+        if let Some(warning) = rename.as_ref().and_then(|rename| rename.warning.clone()) {
+            let notification = Notification::new(
+                lsp_types::notification::ShowMessage::METHOD.to_string(),
+                ShowMessageParams {
+                    typ: MessageType::WARNING,
+                    message: warning,
+                },
+            );
+            connection
+                .sender
+                .send(Message::Notification(notification))?;
+        }
+        let result = source.zip(rename).map(|(source, rename)| {
+            let mut files = Vec::new();
+            // The current file's own edits already cover it, unless the name
+            // is only used here and declared in a module
+            let local = !rename.edits.is_empty();
+            if local {
+                files.push((uri.clone(), rename.edits));
             }
+            if let Some(global) = shared_global(source, position, &uri, documents, config, index) {
+                let current = index::file_path(&uri).filter(|_| local);
+                files.extend(shared_global_edits(
+                    &global,
+                    &new_name,
+                    current.as_deref(),
+                    documents,
+                    config,
+                    index,
+                ));
+            }
+            let old_name = get_word_at_position(source, position).unwrap_or_default();
+            rename_workspace_edit(files, &old_name, &new_name, config.confirm_rename)
         });
 
         let response = Response::new_ok(req.id, result);
@@ -168,9 +487,14 @@ fn handle_request(
         let uri = params.text_document.uri;
         let position = params.position;
 
-        let result = documents
-            .get(&uri.to_string())
-            .and_then(|source| prepare_rename(source, position));
+        let result = config
+            .document(documents, &uri.to_string())
+            .and_then(|source| {
+                prepare_rename(source, position).or_else(|| {
+                    shared_global(source, position, &uri, documents, config, index)
+                        .map(|global| global.range)
+                })
+            });
 
         let response = Response::new_ok(req.id, result);
         connection.sender.send(Message::Response(response))?;
@@ -178,39 +502,313 @@ fn handle_request(
         let params: FoldingRangeParams = serde_json::from_value(req.params)?;
         let uri = params.text_document.uri;
 
-        let result = documents
-            .get(&uri.to_string())
-            .map(|source| get_folding_ranges(source));
+        let result = config
+            .document(documents, &uri.to_string())
+            .map(|source| get_folding_ranges(source, config.folding));
+
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == Formatting::METHOD {
+        let params: DocumentFormattingParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document.uri;
+
+        let result = config
+            .document(documents, &uri.to_string())
+            .and_then(|source| format_document(source, &config.formatting));
+
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == CodeLensRequest::METHOD {
+        let params: CodeLensParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document.uri;
+
+        let result = config
+            .document(documents, &uri.to_string())
+            .map(|source| get_code_lenses(source, &uri));
+
+        let response = Response::new_ok(req.id, result);
+        connection.sender.send(Message::Response(response))?;
+    } else if req.method == DocumentLinkRequest::METHOD {
+        let params: DocumentLinkParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document.uri;
+
+        let result = config
+            .document(documents, &uri.to_string())
+            .map(|source| get_document_links(source, &uri));
 
         let response = Response::new_ok(req.id, result);
         connection.sender.send(Message::Response(response))?;
+    } else if req.method == ExecuteCommand::METHOD {
+        let params: ExecuteCommandParams = serde_json::from_value(req.params)?;
+
+        let result = (params.command == RUN_COMMAND)
+            .then(|| run_command(&params.arguments, documents, config.backend))
+            .flatten();
+
+        // Show the output where the user is looking, and return it too
+        if let Some(run) = &result {
+            let (typ, message) = match run {
+                Ok(output) => (MessageType::INFO, output),
+                Err(errors) => (MessageType::ERROR, errors),
+            };
+            let notification = Notification::new(
+                lsp_types::notification::ShowMessage::METHOD.to_string(),
+                ShowMessageParams {
+                    typ,
+                    message: message.clone(),
+                },
+            );
+            connection
+                .sender
+                .send(Message::Notification(notification))?;
+        }
+
+        let output = result.map(|run| run.unwrap_or_else(|errors| errors));
+        let response = Response::new_ok(req.id, output);
+        connection.sender.send(Message::Response(response))?;
     }
     Ok(())
 }
 
+/// One edit replacing the whole document with its formatted version. `None`
+/// when it's already formatted, or can't be: code that doesn't parse is left
+/// alone, as is anything the formatter would change the meaning of.
+fn format_document(source: &str, config: &FormattingConfig) -> Option<Vec<TextEdit>> {
+    let options = FormatOptions {
+        indent_width: config.indent_width,
+        use_tabs: config.use_tabs,
+    };
+    let formatted = mano::format_checked(source, &options).ok()?;
+    if formatted == source {
+        return None;
+    }
+    let index = LineIndex::new(source);
+    Some(vec![TextEdit {
+        range: to_range(&index, &(0..source.len())),
+        new_text: formatted,
+    }])
+}
+
+/// Command behind the "▶ rodar" code lens. Arguments: document uri and,
+/// optionally, the name of a top-level fita to call after loading the file.
+const RUN_COMMAND: &str = "mano.rodar";
+
+/// "▶ rodar" lenses: one for the whole file, and one per top-level fita that
+/// takes no parameters (there's nothing sensible to pass the others)
+fn get_code_lenses(source: &str, uri: &Uri) -> Vec<CodeLens> {
+    let index = LineIndex::new(source);
+    let lens = |range: Range, function: Option<&str>| {
+        let mut arguments = vec![serde_json::json!(uri.to_string())];
+        if let Some(name) = function {
+            arguments.push(serde_json::json!(name));
+        }
+        CodeLens {
+            range,
+            command: Some(Command {
+                title: "▶ rodar".to_string(),
+                command: RUN_COMMAND.to_string(),
+                arguments: Some(arguments),
+            }),
+            data: None,
+        }
+    };
+
+    let mut lenses = vec![lens(Range::default(), None)];
+    for stmt in parse_statements(source) {
+        if let Some((name, params, _)) = stmt.function_declaration()
+            && params.is_empty()
+        {
+            lenses.push(lens(to_range(&index, &name.span), Some(&name.lexeme)));
+        }
+    }
+    lenses
+}
+
+/// Runs the `mano.rodar` command. `None` when the arguments don't point at an
+/// open document.
+fn run_command(
+    arguments: &[serde_json::Value],
+    documents: &HashMap<String, String>,
+    backend: Backend,
+) -> Option<Result<String, String>> {
+    let uri = arguments.first()?.as_str()?;
+    let source = documents.get(uri)?;
+    let function = arguments.get(1).and_then(|name| name.as_str());
+    Some(run_code(source, function, backend, RUN_TIMEOUT))
+}
+
+/// How long "▶ rodar" lets code run before stopping it, so an endless loop
+/// doesn't hang the server
+const RUN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a document (and then calls `function`, if given), returning what it
+/// printed, or the printed output followed by the errors.
+///
+/// The code runs sandboxed, without files, network, environment or other
+/// programs, and is stopped at its next statement after `timeout`. Stdin
+/// carries the client's messages, so `leAi` finds no input instead of waiting
+/// on it. The interpreter gets a thread with the stack it measures deep code
+/// against, like in the CLI.
+fn run_code(
+    source: &str,
+    function: Option<&str>,
+    backend: Backend,
+    timeout: Duration,
+) -> Result<String, String> {
+    let code = match function {
+        Some(name) => format!("{}\n{}();", source, name),
+        None => source.to_string(),
+    };
+    let interrupt = Arc::new(AtomicBool::new(false));
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let flag = Arc::clone(&interrupt);
+        let code = &code;
+        std::thread::Builder::new()
+            .stack_size(mano::STACK_SIZE)
+            .spawn_scoped(scope, move || {
+                let _ = sender.send(run_here(code, backend, flag));
+            })
+            .expect("Failed to start the thread to run on");
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                interrupt.store(true, Ordering::Relaxed);
+                let output = match receiver.recv() {
+                    Ok(Ok(output) | Err(output)) => output,
+                    Err(_) => String::new(),
+                };
+                Err(format!(
+                    "{}Demorou mais de {}s, mano! Parei o código.",
+                    output,
+                    timeout.as_secs_f64()
+                ))
+            }
+            // The thread panicked, the scope passes the panic on
+            Err(RecvTimeoutError::Disconnected) => Err(String::new()),
+        }
+    })
+}
+
+/// [`run_code`] on the current thread, stopping when `interrupt` gets set
+fn run_here(code: &str, backend: Backend, interrupt: Arc<AtomicBool>) -> Result<String, String> {
+    let mut output = Vec::new();
+    let policy = SandboxPolicy {
+        input: true,
+        clock: Some(Duration::ZERO),
+        ..SandboxPolicy::deny_all()
+    };
+    let errors = match backend {
+        Backend::Interpreter => {
+            let mut mano = mano::Mano::new_with_policy(policy);
+            mano.set_stack_size(mano::STACK_SIZE);
+            mano.set_input(std::io::empty());
+            mano.set_interrupt(interrupt);
+            mano.run(code, &mut output)
+        }
+        Backend::Vm => match mano_vm::compile(code, &Default::default()) {
+            Ok(compiled) => {
+                let mut vm = mano_vm::VM::new(&compiled.chunk, &mut output);
+                vm.set_source(code);
+                vm.set_policy(policy);
+                vm.set_interrupt(interrupt);
+                vm.interpret().err().unwrap_or_default()
+            }
+            Err(errors) => errors,
+        },
+    };
+
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    if errors.is_empty() {
+        return Ok(output);
+    }
+
+    let index = LineIndex::new(code);
+    for error in &errors {
+        output.push_str(&describe_error(error, &index));
+        output.push('\n');
+    }
+    Err(output)
+}
+
+/// One-line description of an error: its roast, message and line, then the
+/// lines of the other places it points at
+fn describe_error(error: &ManoError, index: &LineIndex) -> String {
+    let mut description = match (error.primary(), error.locate(index)) {
+        (
+            ManoError::Scan { message, .. }
+            | ManoError::Parse { message, .. }
+            | ManoError::Runtime { message, .. }
+            | ManoError::Resolution { message, .. }
+            | ManoError::Warning { message, .. },
+            Some(location),
+        ) => format!("[linha {}] {} {}", location.line + 1, error, message),
+        _ => error.to_string(),
+    };
+    for related in error.related() {
+        let line = index.locate(&related.span).line + 1;
+        description.push_str(&format!(" ({} na linha {})", related.message, line));
+    }
+    description
+}
+
 fn handle_notification(
     connection: &Connection,
     not: Notification,
     documents: &mut HashMap<String, String>,
+    config: &mut ServerConfig,
+    index: &mut SymbolIndex,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
     match not.method.as_str() {
         DidOpenTextDocument::METHOD => {
             let params: lsp_types::DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
             let uri = params.text_document.uri.to_string();
             let text = params.text_document.text.clone();
+            if text.len() <= config.max_file_size {
+                index.update(&params.text_document.uri, &text);
+            }
             documents.insert(uri, text);
             publish_diagnostics(
                 connection,
                 params.text_document.uri,
                 &params.text_document.text,
+                config,
             )?;
         }
         DidChangeTextDocument::METHOD => {
             let params: lsp_types::DidChangeTextDocumentParams =
                 serde_json::from_value(not.params)?;
             if let Some(change) = params.content_changes.into_iter().next() {
+                if change.text.len() <= config.max_file_size {
+                    index.update(&params.text_document.uri, &change.text);
+                }
                 documents.insert(params.text_document.uri.to_string(), change.text.clone());
-                publish_diagnostics(connection, params.text_document.uri, &change.text)?;
+                publish_diagnostics(connection, params.text_document.uri, &change.text, config)?;
+            }
+        }
+        DidChangeConfiguration::METHOD => {
+            let params: lsp_types::DidChangeConfigurationParams =
+                serde_json::from_value(not.params)?;
+            config.update(&params.settings);
+
+            // Settings like lints change what's reported, so refresh open documents
+            if config.pull_diagnostics {
+                if config.refresh_diagnostics {
+                    static REFRESHES: AtomicU32 = AtomicU32::new(0);
+                    let id = format!("mano-refresh-{}", REFRESHES.fetch_add(1, Ordering::Relaxed));
+                    let request = Request::new(
+                        RequestId::from(id),
+                        WorkspaceDiagnosticRefresh::METHOD.to_string(),
+                        (),
+                    );
+                    connection.sender.send(Message::Request(request))?;
+                }
+            } else {
+                for (uri, source) in documents.iter() {
+                    if let Ok(uri) = uri.parse::<Uri>() {
+                        publish_diagnostics(connection, uri, source, config)?;
+                    }
+                }
             }
         }
         _ => {}
@@ -218,12 +816,17 @@ fn handle_notification(
     Ok(())
 }
 
+/// Pushes the document's diagnostics, unless the client pulls them
 fn publish_diagnostics(
     connection: &Connection,
     uri: Uri,
     source: &str,
+    config: &ServerConfig,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
-    let diagnostics = get_diagnostics(source);
+    if config.pull_diagnostics {
+        return Ok(());
+    }
+    let diagnostics = get_diagnostics(&uri, source, config);
 
     let params = PublishDiagnosticsParams {
         uri,
@@ -242,46 +845,181 @@ fn publish_diagnostics(
     Ok(())
 }
 
-fn byte_offset_to_position(source: &str, byte_offset: usize) -> Position {
-    let prefix = &source[..byte_offset.min(source.len())];
-    let line = prefix.matches('\n').count() as u32;
-    let col = prefix
-        .rfind('\n')
-        .map_or(prefix.len(), |i| prefix.len() - i - 1) as u32;
+/// The diagnostics of a document for a pull, or that they're still the ones
+/// the client got with `previous_result_id`
+fn diagnostic_report(
+    uri: &Uri,
+    source: &str,
+    config: &ServerConfig,
+    previous_result_id: Option<&str>,
+) -> DocumentDiagnosticReport {
+    let result_id = diagnostics_result_id(uri, source, config);
+    if previous_result_id == Some(result_id.as_str()) {
+        return DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        });
+    }
+    DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+        related_documents: None,
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items: get_diagnostics(uri, source, config),
+        },
+    })
+}
+
+/// Tells apart the diagnostics of `source` under the settings that change
+/// them, so a pull for the same code and settings isn't analyzed again. The
+/// modules it `chama`s count too, since they can appear or go away.
+fn diagnostics_result_id(uri: &Uri, source: &str, config: &ServerConfig) -> String {
+    let mut hasher = DefaultHasher::new();
+    let modules: Vec<_> = module_links(uri, &parse_statements(source))
+        .into_iter()
+        .map(|link| link.file)
+        .collect();
+    (
+        source,
+        modules,
+        config.lints,
+        config.max_diagnostics,
+        config.max_file_size,
+    )
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// LSP positions count columns in UTF-16 code units
+fn to_position(index: &LineIndex, byte_offset: usize) -> Position {
+    let LineCol { line, col } = index.line_col_utf16(byte_offset);
     Position::new(line, col)
 }
 
-fn get_diagnostics(source: &str) -> Vec<Diagnostic> {
+fn to_range(index: &LineIndex, span: &std::ops::Range<usize>) -> Range {
+    Range {
+        start: to_position(index, span.start),
+        end: to_position(index, span.end),
+    }
+}
+
+fn to_byte_offset(index: &LineIndex, position: Position) -> usize {
+    index.offset_utf16(LineCol {
+        line: position.line,
+        col: position.character,
+    })
+}
+
+fn get_diagnostics(uri: &Uri, source: &str, config: &ServerConfig) -> Vec<Diagnostic> {
+    if source.len() > config.max_file_size {
+        return vec![Diagnostic {
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            source: Some("mano".to_string()),
+            message: format!(
+                "Arquivo grande demais pra analisar, mano! Tem {} bytes e o limite é {} (mano.maxFileSize).",
+                source.len(),
+                config.max_file_size
+            ),
+            ..Default::default()
+        }];
+    }
+
+    let index = LineIndex::new(source);
     let scanner = Scanner::new(source);
     let results: Vec<_> = scanner.collect();
 
     let mut diagnostics = Vec::new();
 
     for result in &results {
-        if let Err(ManoError::Scan { message, span }) = result {
-            diagnostics.push(to_lsp_diagnostic(message, span, source));
+        if let Err(error) = result {
+            diagnostics.extend(error_diagnostic(error, uri, &index));
         }
     }
 
     let valid_tokens: Vec<_> = results.into_iter().filter_map(|r| r.ok()).collect();
     let mut parser = Parser::new(valid_tokens);
-    let _ = parser.parse();
+    let statements = parser.parse().unwrap_or_default();
 
     for error in parser.take_errors() {
-        if let ManoError::Parse { message, span } = error {
-            diagnostics.push(to_lsp_diagnostic(&message, &span, source));
+        diagnostics.extend(error_diagnostic(&error, uri, &index));
+    }
+
+    // Resolver checks on a half-parsed program would only add noise
+    if config.lints && diagnostics.is_empty() {
+        for error in mano::check(&statements) {
+            diagnostics.extend(error_diagnostic(&error, uri, &index));
+        }
+        for warning in mano::redeclaration_warnings(&statements) {
+            diagnostics.extend(error_diagnostic(&warning, uri, &index));
+        }
+        for branch in mano::dead_branches(&statements) {
+            diagnostics.push(unnecessary_diagnostic(
+                &branch.message(),
+                &branch.span,
+                &index,
+            ));
+        }
+    }
+
+    for link in module_links(uri, &statements) {
+        if link.file.is_none() {
+            diagnostics.push(to_lsp_diagnostic(
+                &format!("Não achei o módulo '{}', mano.", link.name),
+                &link.span,
+                &index,
+            ));
+        }
+    }
+
+    if config.lints {
+        for warning in mano::identifier_warnings(source) {
+            diagnostics.extend(error_diagnostic(&warning, uri, &index));
         }
     }
 
+    diagnostics.truncate(config.max_diagnostics);
     diagnostics
 }
 
-fn to_lsp_diagnostic(message: &str, span: &std::ops::Range<usize>, source: &str) -> Diagnostic {
-    let start = byte_offset_to_position(source, span.start);
-    let end = byte_offset_to_position(source, span.end);
+/// The diagnostic for an error that points at the code, with the other places
+/// it points at as related information and its tip after the message
+fn error_diagnostic(error: &ManoError, uri: &Uri, index: &LineIndex) -> Option<Diagnostic> {
+    let (message, span) = match error.primary() {
+        ManoError::Scan { message, span }
+        | ManoError::Parse { message, span }
+        | ManoError::Runtime { message, span }
+        | ManoError::Resolution { message, span }
+        | ManoError::Warning { message, span } => (message, span),
+        _ => return None,
+    };
+    let mut diagnostic = to_lsp_diagnostic(message, span, index);
+    if matches!(error.primary(), ManoError::Warning { .. }) {
+        diagnostic.severity = Some(DiagnosticSeverity::WARNING);
+    }
+    if let Some(help) = error.help() {
+        diagnostic.message = format!("{}\n{}", diagnostic.message, help);
+    }
+    if !error.related().is_empty() {
+        let related = error
+            .related()
+            .iter()
+            .map(|related| DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), to_range(index, &related.span)),
+                message: related.message.clone(),
+            });
+        diagnostic.related_information = Some(related.collect());
+    }
+    Some(diagnostic)
+}
+
+fn to_lsp_diagnostic(
+    message: &str,
+    span: &std::ops::Range<usize>,
+    index: &LineIndex,
+) -> Diagnostic {
+    let range = to_range(index, span);
 
     Diagnostic {
-        range: Range { start, end },
+        range,
         severity: Some(DiagnosticSeverity::ERROR),
         source: Some("mano".to_string()),
         message: message.to_string(),
@@ -289,25 +1027,94 @@ fn to_lsp_diagnostic(message: &str, span: &std::ops::Range<usize>, source: &str)
     }
 }
 
-fn get_prefix_at_position(source: &str, position: Position) -> String {
-    let lines: Vec<&str> = source.lines().collect();
-    let line_idx = position.line as usize;
-
-    if line_idx >= lines.len() {
-        return String::new();
+/// A hint for code that does nothing, tagged so editors fade it out instead
+/// of underlining it
+fn unnecessary_diagnostic(
+    message: &str,
+    span: &std::ops::Range<usize>,
+    index: &LineIndex,
+) -> Diagnostic {
+    Diagnostic {
+        severity: Some(DiagnosticSeverity::HINT),
+        tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+        ..to_lsp_diagnostic(message, span, index)
     }
+}
+
+/// A `chama` and the file it loads, `None` when there's no such module
+struct ModuleLink {
+    name: String,
+    /// The string with the module's path, quotes included
+    span: std::ops::Range<usize>,
+    file: Option<PathBuf>,
+}
+
+/// Where the `chama`s of a document lead, found like the interpreter finds
+/// them. Empty for documents that aren't files, which have no folder for
+/// paths to start from.
+fn module_links(uri: &Uri, statements: &[Stmt]) -> Vec<ModuleLink> {
+    let Some(file) = index::file_path(uri) else {
+        return vec![];
+    };
+    // chama is only allowed at the top level
+    statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Import {
+                path:
+                    Token {
+                        literal: Some(Literal::String(name)),
+                        span,
+                        ..
+                    },
+                ..
+            } => Some(ModuleLink {
+                name: name.clone(),
+                span: span.clone(),
+                file: mano::locate_module(file.parent(), name).ok(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
 
-    let line = lines[line_idx];
-    let col = position.character as usize;
-    let col = col.min(line.len());
+/// Links from each `chama`'s path to the file it loads
+fn get_document_links(source: &str, uri: &Uri) -> Vec<DocumentLink> {
+    let index = LineIndex::new(source);
+    module_links(uri, &parse_statements(source))
+        .into_iter()
+        .filter_map(|link| {
+            // Only the path, not its quotes
+            let path = link.span.start + 1..link.span.end.saturating_sub(1);
+            Some(DocumentLink {
+                range: to_range(&index, &path),
+                target: Some(index::file_uri(&link.file?)?),
+                tooltip: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+/// Text of the cursor's line up to the cursor
+fn line_before_cursor<'a>(index: &LineIndex<'a>, position: Position) -> Option<&'a str> {
+    let line_start = index.line_start(position.line as usize)?;
+    Some(&index.source()[line_start..to_byte_offset(index, position)])
+}
+
+fn get_prefix_at_position(source: &str, position: Position) -> String {
+    let index = LineIndex::new(source);
+    let Some(before_cursor) = line_before_cursor(&index, position) else {
+        return String::new();
+    };
 
     // Walk backwards from cursor to find start of identifier
-    let prefix_start = line[..col]
+    let prefix_start = before_cursor
         .rfind(|c: char| !c.is_alphanumeric() && c != '_')
         .map(|i| i + 1)
         .unwrap_or(0);
 
-    line[prefix_start..col].to_string()
+    before_cursor[prefix_start..].to_string()
 }
 
 struct CompletionContext {
@@ -317,20 +1124,14 @@ struct CompletionContext {
 }
 
 fn get_completion_context(source: &str, position: Position) -> CompletionContext {
-    let lines: Vec<&str> = source.lines().collect();
-    let line_idx = position.line as usize;
-
-    if line_idx >= lines.len() {
+    let index = LineIndex::new(source);
+    let Some(before_cursor) = line_before_cursor(&index, position) else {
         return CompletionContext {
             is_dot_completion: false,
             receiver: None,
             prefix: String::new(),
         };
-    }
-
-    let line = lines[line_idx];
-    let col = (position.character as usize).min(line.len());
-    let before_cursor = &line[..col];
+    };
 
     // Check if we're right after a dot (e.g., "foo." or "foo.bar")
     // Find the last dot before cursor
@@ -369,6 +1170,32 @@ fn get_completions_at_position(source: &str, position: Position) -> Vec<Completi
 
     if context.is_dot_completion {
         if let Some(receiver) = &context.receiver {
+            // `oCara.` completes the members of the class the cursor is in
+            if is_this_keyword(source, receiver) {
+                let offset = to_byte_offset(&LineIndex::new(source), position);
+                let statements = parse_statements(source);
+                return enclosing_class(&statements, offset)
+                    .map(class_members)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|member| {
+                        member.name != INITIALIZER_NAME && member.name.starts_with(&context.prefix)
+                    })
+                    .map(|member| match member.params {
+                        Some(params) => CompletionItem {
+                            label: member.name,
+                            kind: Some(CompletionItemKind::METHOD),
+                            detail: Some(format!("({})", params.join(", "))),
+                            ..Default::default()
+                        },
+                        None => CompletionItem {
+                            label: member.name,
+                            kind: Some(CompletionItemKind::FIELD),
+                            ..Default::default()
+                        },
+                    })
+                    .collect();
+            }
             // Find the class of the receiver variable
             if let Some(class_name) = find_variable_class(source, receiver) {
                 // Return methods of that class (excluding initializer - it's only called on instantiation)
@@ -391,7 +1218,8 @@ fn get_completions_at_position(source: &str, position: Position) -> Vec<Completi
     }
 
     // Regular completion
-    get_completions(source, &context.prefix)
+    let offset = to_byte_offset(&LineIndex::new(source), position);
+    get_completions(source, &context.prefix, offset)
 }
 
 /// Finds the class name that a variable is an instance of
@@ -408,7 +1236,7 @@ fn find_var_class_in_stmts(stmts: &[Stmt], var_name: &str) -> Option<String> {
     for stmt in stmts {
         // Check if this is a var declaration with a class instantiation
         if let Some((name, initializer)) = stmt.var_declaration()
-            && name.lexeme == var_name
+            && &*name.lexeme == var_name
             && let Some(class_name) = initializer.as_ref().and_then(get_class_from_call)
         {
             return Some(class_name);
@@ -431,7 +1259,7 @@ fn get_class_from_call(expr: &Expr) -> Option<String> {
             if let Expr::Variable { name } = callee.as_ref() {
                 // Check if this is a class name (by convention, classes start with uppercase)
                 // Or we could check against known class declarations
-                Some(name.lexeme.clone())
+                Some(name.lexeme.to_string())
             } else {
                 None
             }
@@ -459,94 +1287,347 @@ fn collect_class_methods(
 ) {
     for stmt in stmts {
         if let Some((name, class_methods)) = stmt.class_declaration()
-            && name.lexeme == class_name
+            && &*name.lexeme == class_name
         {
             for method_stmt in class_methods {
-                if let Some((method_name, params, _)) = method_stmt.function_declaration() {
-                    methods.push((
-                        method_name.lexeme.clone(),
-                        params.iter().map(|t| t.lexeme.clone()).collect(),
-                    ));
+                if let Some((method_name, _, _)) = method_stmt.function_declaration() {
+                    methods.push((method_name.lexeme.to_string(), param_names(method_stmt)));
                 }
             }
         }
     }
 }
 
-fn get_completions(source: &str, prefix: &str) -> Vec<CompletionItem> {
-    let mut completions = Vec::new();
+/// Whether a word is `oCara`, or what the document's dialect calls it
+fn is_this_keyword(source: &str, word: &str) -> bool {
+    let aliases = Dialect::from_pragma(source).unwrap_or_default().aliases();
+    KEYWORDS.get(word).or_else(|| aliases.get(word)) == Some(&TokenType::This)
+}
 
-    // Add keywords
-    for (keyword, _) in KEYWORDS.entries() {
-        if keyword.starts_with(prefix) {
-            completions.push(CompletionItem {
-                label: keyword.to_string(),
-                kind: Some(CompletionItemKind::KEYWORD),
-                ..Default::default()
-            });
-        }
+/// Statements directly inside another, including fita bodies and methods
+fn nested_statements(stmt: &Stmt) -> Vec<&Stmt> {
+    match stmt {
+        Stmt::Function { body, .. } => body.iter().collect(),
+        Stmt::Class { methods, .. } => methods.iter().collect(),
+        _ => stmt.children(),
     }
+}
 
-    // Add native functions
-    for func in NATIVE_FUNCTIONS {
-        if func.starts_with(prefix) {
-            completions.push(CompletionItem {
-                label: func.to_string(),
-                kind: Some(CompletionItemKind::FUNCTION),
-                ..Default::default()
-            });
-        }
-    }
+/// The innermost class declaration around an offset
+fn enclosing_class<'a>(
+    statements: impl IntoIterator<Item = &'a Stmt>,
+    offset: usize,
+) -> Option<&'a Stmt> {
+    let stmt = statements.into_iter().find(|stmt| {
+        let span = stmt.span();
+        span.start <= offset && offset <= span.end
+    })?;
+    enclosing_class(nested_statements(stmt), offset)
+        .or_else(|| matches!(stmt, Stmt::Class { .. }).then_some(stmt))
+}
 
-    // Add user-defined functions from parsed AST (with params in detail)
-    for (name, params, _) in extract_function_info(source) {
-        if name.starts_with(prefix) {
-            let params_str = format!("({})", params.join(", "));
-            completions.push(CompletionItem {
-                label: name,
-                kind: Some(CompletionItemKind::FUNCTION),
-                detail: Some(params_str),
-                ..Default::default()
-            });
-        }
-    }
+/// A method of a class, or a field its methods set on `oCara`
+struct ClassMember {
+    name: String,
+    /// Parameters, for methods called with them (getters have none)
+    params: Option<Vec<String>>,
+    span: std::ops::Range<usize>,
+}
 
-    // Add variables from parsed AST
-    for var in extract_variables(source) {
-        if var.starts_with(prefix) {
-            completions.push(CompletionItem {
-                label: var,
-                kind: Some(CompletionItemKind::VARIABLE),
-                ..Default::default()
+/// The methods of a class declaration, then its fields where each is first set
+fn class_members(class: &Stmt) -> Vec<ClassMember> {
+    let Stmt::Class { methods, .. } = class else {
+        return Vec::new();
+    };
+    let mut members: Vec<_> = methods
+        .iter()
+        .filter_map(|method| match method {
+            Stmt::Function {
+                name, is_getter, ..
+            } => Some(ClassMember {
+                name: name.lexeme.to_string(),
+                params: (!is_getter).then(|| param_names(method)),
+                span: name.span.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    for_each_expr(methods, &mut |expr| {
+        if let Expr::Set { object, name, .. } = expr
+            && matches!(**object, Expr::This { .. })
+            && !members.iter().any(|member| *member.name == *name.lexeme)
+        {
+            members.push(ClassMember {
+                name: name.lexeme.to_string(),
+                params: None,
+                span: name.span.clone(),
             });
         }
+    });
+    members
+}
+
+/// The member named by the `oCara.nome` under the offset, in the class around it
+fn this_member_at(statements: &[Stmt], offset: usize) -> Option<ClassMember> {
+    let class = enclosing_class(statements, offset)?;
+    let Stmt::Class { methods, .. } = class else {
+        return None;
+    };
+    let mut accessed = None;
+    for_each_expr(methods, &mut |expr| {
+        if let Expr::Get { object, name } | Expr::Set { object, name, .. } = expr
+            && matches!(**object, Expr::This { .. })
+            && name.span.start <= offset
+            && offset <= name.span.end
+        {
+            accessed = Some(name.lexeme.clone());
+        }
+    });
+    let accessed = accessed?;
+    class_members(class)
+        .into_iter()
+        .find(|member| *member.name == *accessed)
+}
+
+/// Calls `f` on every expression in the statements, leaving out nested
+/// classes, whose `oCara` is another object
+fn for_each_expr<'a>(statements: &'a [Stmt], f: &mut impl FnMut(&'a Expr)) {
+    for stmt in statements {
+        match stmt {
+            Stmt::Expression { expression, .. } | Stmt::Print { expression, .. } => {
+                walk_expr(expression, f)
+            }
+            Stmt::Var {
+                initializer: Some(expression),
+                ..
+            }
+            | Stmt::Return {
+                value: Some(expression),
+                ..
+            }
+            | Stmt::If {
+                condition: expression,
+                ..
+            }
+            | Stmt::While {
+                condition: expression,
+                ..
+            }
+            | Stmt::Throw {
+                value: expression, ..
+            } => walk_expr(expression, f),
+            _ => {}
+        }
+        match stmt {
+            Stmt::Function { body, .. } => for_each_expr(body, f),
+            Stmt::Class { .. } => {}
+            _ => {
+                for child in stmt.children() {
+                    for_each_expr(std::slice::from_ref(child), f);
+                }
+            }
+        }
     }
+}
 
-    // Add class names from parsed AST
-    for (name, _) in extract_class_declarations(source) {
-        if name.starts_with(prefix) {
-            completions.push(CompletionItem {
-                label: name,
-                kind: Some(CompletionItemKind::CLASS),
-                ..Default::default()
-            });
+fn walk_expr<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a Expr)) {
+    f(expr);
+    match expr {
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+            walk_expr(left, f);
+            walk_expr(right, f);
+        }
+        Expr::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            walk_expr(condition, f);
+            walk_expr(then_branch, f);
+            walk_expr(else_branch, f);
+        }
+        Expr::Unary { right: inner, .. }
+        | Expr::Grouping { expression: inner }
+        | Expr::Assign { value: inner, .. }
+        | Expr::Get { object: inner, .. } => walk_expr(inner, f),
+        Expr::Set { object, value, .. } => {
+            walk_expr(object, f);
+            walk_expr(value, f);
+        }
+        Expr::ListLiteral { elements, .. } => {
+            for element in elements {
+                walk_expr(element, f);
+            }
+        }
+        Expr::Index { object, index, .. } => {
+            walk_expr(object, f);
+            walk_expr(index, f);
+        }
+        Expr::IndexSet {
+            object,
+            index,
+            value,
+            ..
+        } => {
+            walk_expr(object, f);
+            walk_expr(index, f);
+            walk_expr(value, f);
+        }
+        Expr::Call {
+            callee, arguments, ..
+        } => {
+            walk_expr(callee, f);
+            for argument in arguments {
+                walk_expr(argument, f);
+            }
+        }
+        Expr::Lambda { body, .. } => for_each_expr(body, f),
+        Expr::Interpolation { parts } => {
+            for part in parts {
+                if let InterpolationPart::Expr(expression) = part {
+                    walk_expr(expression, f);
+                }
+            }
+        }
+        Expr::Literal { .. }
+        | Expr::Variable { .. }
+        | Expr::This { .. }
+        | Expr::Super { .. }
+        | Expr::Error { .. } => {}
+    }
+}
+
+/// Where a completion comes from. Lower ranks are listed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CompletionRank {
+    Local,
+    Global,
+    Member,
+    Native,
+    Keyword,
+}
+
+fn get_completions(source: &str, prefix: &str, offset: usize) -> Vec<CompletionItem> {
+    let mut completions = Vec::new();
+
+    // Add keywords, with the aliases of the dialect the document asks for
+    let aliases = Dialect::from_pragma(source).unwrap_or_default().aliases();
+    for (keyword, _) in KEYWORDS.entries().chain(aliases.entries()) {
+        if keyword.starts_with(prefix) {
+            completions.push((
+                CompletionRank::Keyword,
+                CompletionItem {
+                    label: keyword.to_string(),
+                    kind: Some(CompletionItemKind::KEYWORD),
+                    ..Default::default()
+                },
+            ));
         }
     }
 
+    // Add native functions and the prelude's fitas
+    for func in NATIVE_FUNCTIONS.iter().chain(PRELUDE_NAMES) {
+        if func.starts_with(prefix) {
+            completions.push((
+                CompletionRank::Native,
+                CompletionItem {
+                    label: func.to_string(),
+                    kind: Some(CompletionItemKind::FUNCTION),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+
+    // Add names visible from the cursor's scope
+    let scope_tree = ScopeTree::build(&parse_statements(source));
+    let functions = extract_function_info(source);
+    let globals = &scope_tree.scopes()[0].declarations;
+    for declaration in scope_tree.visible_at(offset) {
+        if !declaration.name.starts_with(prefix) {
+            continue;
+        }
+        let rank = if globals.contains(declaration) {
+            CompletionRank::Global
+        } else {
+            CompletionRank::Local
+        };
+        let (kind, detail) = match declaration.kind {
+            DeclarationKind::Variable | DeclarationKind::Parameter => {
+                (CompletionItemKind::VARIABLE, None)
+            }
+            DeclarationKind::Class => (CompletionItemKind::CLASS, None),
+            DeclarationKind::Function => {
+                // Show params in detail
+                let params = functions
+                    .iter()
+                    .find(|(_, _, span)| *span == declaration.span)
+                    .map(|(_, params, _)| format!("({})", params.join(", ")));
+                (CompletionItemKind::FUNCTION, params)
+            }
+        };
+        completions.push((
+            rank,
+            CompletionItem {
+                label: declaration.name.clone(),
+                kind: Some(kind),
+                detail,
+                ..Default::default()
+            },
+        ));
+    }
+
     // Add method names from parsed AST
     for (method_name, _class_name, params, _) in extract_method_info(source) {
         if method_name.starts_with(prefix) {
             let params_str = format!("({})", params.join(", "));
-            completions.push(CompletionItem {
-                label: method_name,
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some(params_str),
-                ..Default::default()
-            });
+            completions.push((
+                CompletionRank::Member,
+                CompletionItem {
+                    label: method_name,
+                    kind: Some(CompletionItemKind::METHOD),
+                    detail: Some(params_str),
+                    ..Default::default()
+                },
+            ));
         }
     }
 
+    rank_completions(completions)
+}
+
+/// Sorts completions by rank, drops duplicates with the same label and kind
+/// (keeping the best ranked), and sets `sortText`/`filterText` so editors
+/// keep that order instead of sorting alphabetically.
+fn rank_completions(mut completions: Vec<(CompletionRank, CompletionItem)>) -> Vec<CompletionItem> {
+    completions
+        .sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then_with(|| a.label.cmp(&b.label)));
+
+    let mut seen: Vec<(String, Option<CompletionItemKind>)> = Vec::new();
     completions
+        .into_iter()
+        .filter(|(_, item)| {
+            let key = (item.label.clone(), item.kind);
+            let duplicate = seen.contains(&key);
+            if !duplicate {
+                seen.push(key);
+            }
+            !duplicate
+        })
+        .map(|(rank, item)| CompletionItem {
+            sort_text: Some(format!("{}_{}", rank as u8, item.label)),
+            filter_text: Some(item.label.clone()),
+            ..item
+        })
+        .collect()
+}
+
+/// Parses the source, keeping whatever statements survive parse errors
+fn parse_statements(source: &str) -> Vec<Stmt> {
+    let scanner = Scanner::new(source);
+    let tokens: Vec<_> = scanner.filter_map(|r| r.ok()).collect();
+    let mut parser = Parser::new(tokens);
+    parser.parse().unwrap_or_default()
 }
 
 fn extract_variables(source: &str) -> Vec<String> {
@@ -573,7 +1654,7 @@ fn collect_variable_declarations(
 ) {
     for stmt in statements {
         if let Some((name, _)) = stmt.var_declaration() {
-            declarations.push((name.lexeme.clone(), name.span.clone()));
+            declarations.push((name.lexeme.to_string(), name.span.clone()));
         }
         for child in stmt.children() {
             collect_variable_declarations(std::slice::from_ref(child), declarations);
@@ -605,7 +1686,7 @@ fn collect_class_declarations(
 ) {
     for stmt in statements {
         if let Some((name, _methods)) = stmt.class_declaration() {
-            declarations.push((name.lexeme.clone(), name.span.clone()));
+            declarations.push((name.lexeme.to_string(), name.span.clone()));
         }
         for child in stmt.children() {
             collect_class_declarations(std::slice::from_ref(child), declarations);
@@ -613,6 +1694,37 @@ fn collect_class_declarations(
     }
 }
 
+/// Returns (name, span, options) for each `opcoes` declaration, with the
+/// name and span of each option
+#[allow(clippy::type_complexity)]
+fn extract_enum_declarations(
+    source: &str,
+) -> Vec<(
+    String,
+    std::ops::Range<usize>,
+    Vec<(String, std::ops::Range<usize>)>,
+)> {
+    let scanner = Scanner::new(source);
+    let tokens: Vec<_> = scanner.filter_map(|r| r.ok()).collect();
+    let mut parser = Parser::new(tokens);
+    let statements = parser.parse().unwrap_or_default();
+
+    let mut declarations = Vec::new();
+    let mut pending: Vec<&Stmt> = statements.iter().collect();
+    while let Some(stmt) = pending.pop() {
+        if let Stmt::Enum { name, variants, .. } = stmt {
+            let variants = variants
+                .iter()
+                .map(|variant| (variant.lexeme.to_string(), variant.span.clone()))
+                .collect();
+            declarations.push((name.lexeme.to_string(), name.span.clone(), variants));
+        }
+        pending.extend(stmt.children());
+    }
+    declarations.sort_by_key(|(_, span, _)| span.start);
+    declarations
+}
+
 /// Returns (method_name, class_name, params, span) for each method
 fn extract_method_info(source: &str) -> Vec<(String, String, Vec<String>, std::ops::Range<usize>)> {
     let scanner = Scanner::new(source);
@@ -632,13 +1744,11 @@ fn collect_method_info(
     for stmt in statements {
         if let Some((class_name, class_methods)) = stmt.class_declaration() {
             for method in class_methods {
-                if let Some((method_name, params, _body)) = method.function_declaration() {
-                    let param_names: Vec<String> =
-                        params.iter().map(|t| t.lexeme.clone()).collect();
+                if let Some((method_name, _, _)) = method.function_declaration() {
                     methods.push((
-                        method_name.lexeme.clone(),
-                        class_name.lexeme.clone(),
-                        param_names,
+                        method_name.lexeme.to_string(),
+                        class_name.lexeme.to_string(),
+                        param_names(method),
                         method_name.span.clone(),
                     ));
                 }
@@ -650,6 +1760,24 @@ fn collect_method_info(
     }
 }
 
+/// The parameters of a fita as they're declared, with the `...` of the last
+/// one when it takes the rest of the arguments
+fn param_names(function: &Stmt) -> Vec<String> {
+    let Stmt::Function {
+        params,
+        is_variadic,
+        ..
+    } = function
+    else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = params.iter().map(|t| t.lexeme.to_string()).collect();
+    if *is_variadic && let Some(last) = names.last_mut() {
+        last.insert_str(0, "...");
+    }
+    names
+}
+
 /// Returns (name, params, span) for each function declaration
 fn extract_function_info(source: &str) -> Vec<(String, Vec<String>, std::ops::Range<usize>)> {
     let scanner = Scanner::new(source);
@@ -667,9 +1795,12 @@ fn collect_function_info(
     declarations: &mut Vec<(String, Vec<String>, std::ops::Range<usize>)>,
 ) {
     for stmt in statements {
-        if let Some((name, params, body)) = stmt.function_declaration() {
-            let param_names: Vec<String> = params.iter().map(|t| t.lexeme.clone()).collect();
-            declarations.push((name.lexeme.clone(), param_names, name.span.clone()));
+        if let Some((name, _, body)) = stmt.function_declaration() {
+            declarations.push((
+                name.lexeme.to_string(),
+                param_names(stmt),
+                name.span.clone(),
+            ));
             // Also collect nested functions
             collect_function_info(body, declarations);
         }
@@ -680,113 +1811,176 @@ fn collect_function_info(
 }
 
 fn find_definition(source: &str, position: Position) -> Option<Range> {
-    let word = get_word_at_position(source, position)?;
+    let index = LineIndex::new(source);
+    index.line_start(position.line as usize)?;
+    let offset = to_byte_offset(&index, position);
 
-    // Check variable declarations
-    for (name, span) in extract_variable_declarations(source) {
-        if name == word {
-            let start = byte_offset_to_position(source, span.start);
-            let end = byte_offset_to_position(source, span.end);
-            return Some(Range { start, end });
-        }
+    // Variables, parameters, functions and classes, bound the way the resolver sees them
+    let statements = parse_statements(source);
+    let analysis = Analysis::new(&statements);
+    if let Some(id) = analysis.declaration_at(offset) {
+        return Some(to_range(&index, &analysis.declaration(id).span));
     }
 
-    // Check function declarations
-    for (name, span) in extract_function_declarations(source) {
-        if name == word {
-            let start = byte_offset_to_position(source, span.start);
-            let end = byte_offset_to_position(source, span.end);
-            return Some(Range { start, end });
-        }
+    // `oCara.nome` is a member of the class around it
+    if let Some(member) = this_member_at(&statements, offset) {
+        return Some(to_range(&index, &member.span));
     }
 
-    // Check class declarations
-    for (name, span) in extract_class_declarations(source) {
-        if name == word {
-            let start = byte_offset_to_position(source, span.start);
-            let end = byte_offset_to_position(source, span.end);
-            return Some(Range { start, end });
-        }
-    }
+    // Methods are looked up by name, since properties aren't resolved statically
+    let word = get_word_at_position(source, position)?;
+    extract_method_info(source)
+        .into_iter()
+        .find(|(method_name, ..)| method_name == &word)
+        .map(|(.., span)| to_range(&index, &span))
+}
 
-    // Check method declarations
-    for (method_name, _class_name, _params, span) in extract_method_info(source) {
-        if method_name == word {
-            let start = byte_offset_to_position(source, span.start);
-            let end = byte_offset_to_position(source, span.end);
-            return Some(Range { start, end });
+fn find_type_definition(source: &str, position: Position) -> Option<Range> {
+    let index = LineIndex::new(source);
+    index.line_start(position.line as usize)?;
+    let offset = to_byte_offset(&index, position);
+
+    let statements = parse_statements(source);
+    let analysis = Analysis::new(&statements);
+    let class = match analysis.declaration_at(offset) {
+        // A variable set to `Pessoa()` is a Pessoa
+        Some(id) => instance_class(&statements, &analysis.declaration(id).span)?,
+        // `oCara` is an instance of the class around it
+        None => {
+            let mut this = None;
+            for_each_expr_everywhere(&statements, &mut |expr| {
+                if let Expr::This { keyword } = expr
+                    && keyword.span.start <= offset
+                    && offset <= keyword.span.end
+                {
+                    this = Some(keyword.span.start);
+                }
+            });
+            match enclosing_class(&statements, this?)? {
+                Stmt::Class { name, .. } => name.lexeme.to_string(),
+                _ => return None,
+            }
         }
+    };
+    match class_named(&statements, &class)? {
+        Stmt::Class { name, .. } => Some(to_range(&index, &name.span)),
+        _ => None,
     }
-
-    None
 }
 
-fn get_word_at_position(source: &str, position: Position) -> Option<String> {
-    let lines: Vec<&str> = source.lines().collect();
-    let line_idx = position.line as usize;
-    if line_idx >= lines.len() {
-        return None;
-    }
-
-    let line = lines[line_idx];
-
-    // Convert UTF-16 column to byte offset
-    let mut byte_offset = 0;
-    let mut utf16_offset = 0;
-    for c in line.chars() {
-        if utf16_offset >= position.character as usize {
-            break;
-        }
-        utf16_offset += c.len_utf16();
-        byte_offset += c.len_utf8();
+/// The class a variable declared at `span` is instantiated from
+fn instance_class(program: &[Stmt], span: &std::ops::Range<usize>) -> Option<String> {
+    fn find<'a>(
+        statements: impl IntoIterator<Item = &'a Stmt>,
+        span: &std::ops::Range<usize>,
+    ) -> Option<&'a Expr> {
+        statements
+            .into_iter()
+            .find_map(|stmt| match stmt.var_declaration() {
+                Some((name, initializer)) if name.span == *span => initializer.as_ref(),
+                _ => find(nested_statements(stmt), span),
+            })
     }
-    byte_offset = byte_offset.min(line.len());
+    get_class_from_call(find(program, span)?).filter(|class| class_named(program, class).is_some())
+}
 
-    // Find start of word (walk backwards)
-    let mut start = byte_offset;
-    for (i, c) in line[..byte_offset].char_indices().rev() {
-        if !is_identifier_char(c) {
-            start = i + c.len_utf8();
-            break;
+/// Classes that inherit from `class`, directly or through others
+fn subclasses<'a>(statements: &'a [Stmt], class: &str) -> Vec<&'a Stmt> {
+    let mut classes = Vec::new();
+    collect_classes(statements, &mut classes);
+    let mut found: Vec<&Stmt> = Vec::new();
+    let mut parents = vec![class.to_string()];
+    while let Some(parent) = parents.pop() {
+        for &class in &classes {
+            if superclass_name(class).as_deref() == Some(&*parent)
+                && !found.iter().any(|seen| std::ptr::eq(*seen, class))
+                && let Stmt::Class { name, .. } = class
+            {
+                found.push(class);
+                parents.push(name.lexeme.to_string());
+            }
         }
-        start = i;
     }
+    found.sort_by_key(|class| class.span().start);
+    found
+}
 
-    // Find end of word (walk forwards)
-    let mut end = byte_offset;
-    for (i, c) in line[byte_offset..].char_indices() {
-        if !is_identifier_char(c) {
-            end = byte_offset + i;
-            break;
+/// From a class, the classes extending it; from a method, the subclasses'
+/// overrides of it
+fn find_implementations(source: &str, position: Position) -> Vec<Range> {
+    let index = LineIndex::new(source);
+    if index.line_start(position.line as usize).is_none() {
+        return Vec::new();
+    }
+    let offset = to_byte_offset(&index, position);
+
+    let statements = parse_statements(source);
+    let analysis = Analysis::new(&statements);
+    if let Some(id) = analysis.declaration_at(offset) {
+        let declaration = analysis.declaration(id);
+        if declaration.kind != DeclarationKind::Class {
+            return Vec::new();
         }
-        end = byte_offset + i + c.len_utf8();
+        return subclasses(&statements, &declaration.name)
+            .into_iter()
+            .filter_map(|class| class.class_declaration())
+            .map(|(name, _)| to_range(&index, &name.span))
+            .collect();
     }
 
-    if start >= end {
-        return None;
-    }
+    let Some((class, method)) = method_at(&statements, offset) else {
+        return Vec::new();
+    };
+    subclasses(&statements, &class)
+        .into_iter()
+        .filter_map(|class| class.class_declaration())
+        .flat_map(|(_, methods)| methods)
+        .filter_map(|stmt| stmt.function_declaration())
+        .filter(|(name, ..)| *name.lexeme == *method)
+        .map(|(name, ..)| to_range(&index, &name.span))
+        .collect()
+}
 
-    Some(line[start..end].to_string())
+fn get_word_at_position(source: &str, position: Position) -> Option<String> {
+    let index = LineIndex::new(source);
+    index.line_start(position.line as usize)?;
+    let word = word_at(source, to_byte_offset(&index, position));
+    (!word.is_empty()).then(|| source[word].to_string())
 }
 
-fn get_folding_ranges(source: &str) -> Vec<FoldingRange> {
+fn get_folding_ranges(source: &str, folding: Folding) -> Vec<FoldingRange> {
     let scanner = Scanner::new(source);
     let tokens: Vec<_> = scanner.filter_map(|r| r.ok()).collect();
     let mut parser = Parser::new(tokens);
     let statements = parser.parse().unwrap_or_default();
 
     let mut ranges = Vec::new();
-    collect_folding_ranges(&statements, source, &mut ranges);
+    collect_folding_ranges(&statements, &LineIndex::new(source), folding, &mut ranges);
+    // Editors fold whole lines, so a `sePá (x) {` and its block are one fold
+    let mut seen = HashSet::new();
+    ranges.retain(|range| seen.insert((range.start_line, range.end_line)));
     ranges
 }
 
-fn collect_folding_ranges(statements: &[Stmt], source: &str, ranges: &mut Vec<FoldingRange>) {
+fn collect_folding_ranges(
+    statements: &[Stmt],
+    index: &LineIndex,
+    folding: Folding,
+    ranges: &mut Vec<FoldingRange>,
+) {
     for stmt in statements {
         let span = stmt.span();
-        let start_pos = byte_offset_to_position(source, span.start);
-        let end_pos = byte_offset_to_position(source, span.end);
+        let start_pos = to_position(index, span.start);
+        let end_pos = to_position(index, span.end);
+        let folds = match folding {
+            Folding::Statements => true,
+            Folding::Blocks => matches!(
+                stmt,
+                Stmt::Function { .. } | Stmt::Class { .. } | Stmt::Enum { .. } | Stmt::Block { .. }
+            ),
+        };
 
-        if end_pos.line > start_pos.line {
+        if folds && end_pos.line > start_pos.line {
             ranges.push(FoldingRange {
                 start_line: start_pos.line,
                 start_character: Some(start_pos.character),
@@ -797,94 +1991,484 @@ fn collect_folding_ranges(statements: &[Stmt], source: &str, ranges: &mut Vec<Fo
             });
         }
 
-        for child in stmt.children() {
-            collect_folding_ranges(std::slice::from_ref(child), source, ranges);
+        match stmt {
+            Stmt::Function { body, .. } => collect_folding_ranges(body, index, folding, ranges),
+            Stmt::Class { methods, .. } => collect_folding_ranges(methods, index, folding, ranges),
+            _ => {
+                for child in stmt.children() {
+                    collect_folding_ranges(std::slice::from_ref(child), index, folding, ranges);
+                }
+            }
         }
     }
 }
 
 fn prepare_rename(source: &str, position: Position) -> Option<Range> {
-    let word = get_word_at_position(source, position)?;
+    let index = LineIndex::new(source);
+    index.line_start(position.line as usize)?;
+    let offset = to_byte_offset(&index, position);
+
+    // Only declared names can be renamed; return the occurrence under the cursor
+    let statements = parse_statements(source);
+    let analysis = Analysis::new(&statements);
+    let Some(id) = analysis.declaration_at(offset) else {
+        // Or methods, declared or called on a receiver whose class is known
+        method_at(&statements, offset)?;
+        return Some(to_range(&index, &word_at(source, offset)));
+    };
+    let declaration = &analysis.declaration(id).span;
+    std::iter::once(declaration)
+        .chain(analysis.references_to(id).map(|reference| &reference.span))
+        .find(|span| span.start <= offset && offset <= span.end)
+        .map(|span| to_range(&index, span))
+}
 
-    // Check if it's a declared variable or function (not a keyword)
-    let var_declarations = extract_variable_declarations(source);
-    let func_declarations = extract_function_declarations(source);
-    let is_variable = var_declarations.iter().any(|(name, _)| name == &word);
-    let is_function = func_declarations.iter().any(|(name, _)| name == &word);
+/// The edits for a rename, and a warning for the user when some uses had to
+/// be left alone
+struct RenameEdits {
+    edits: Vec<TextEdit>,
+    warning: Option<String>,
+}
 
-    if !is_variable && !is_function {
-        return None;
+fn get_rename_edits(source: &str, position: Position, new_name: &str, uri: Uri) -> RenameEdits {
+    let edit = |range| TextEdit {
+        range,
+        new_text: new_name.to_string(),
+    };
+    let references = find_references(source, position, uri);
+    if !references.is_empty() {
+        return RenameEdits {
+            edits: references.into_iter().map(|loc| edit(loc.range)).collect(),
+            warning: None,
+        };
+    }
+
+    let index = LineIndex::new(source);
+    let statements = parse_statements(source);
+    let Some((class, method)) = index
+        .line_start(position.line as usize)
+        .and_then(|_| method_at(&statements, to_byte_offset(&index, position)))
+    else {
+        return RenameEdits {
+            edits: Vec::new(),
+            warning: None,
+        };
+    };
+    let rename = method_rename(&statements, &class, &method);
+    RenameEdits {
+        edits: rename
+            .spans
+            .iter()
+            .map(|span| edit(to_range(&index, span)))
+            .collect(),
+        warning: (rename.unsure > 0).then(|| {
+            format!(
+                "Deixei {} uso(s) de '{}' sem renomear, mano: não deu pra saber de que bagulho é quem chama.",
+                rename.unsure, method
+            )
+        }),
     }
+}
+
+/// A global that other files share through `chama`
+struct SharedGlobal {
+    /// The file declaring it
+    file: PathBuf,
+    name: String,
+    /// Where it's named under the cursor
+    range: Range,
+}
 
-    // Find the reference at this position and return its range
-    let refs = find_references(source, position, "file:///dummy".parse().unwrap());
-    refs.into_iter()
-        .find(|loc| {
-            loc.range.start.line == position.line
-                && loc.range.start.character <= position.character
-                && loc.range.end.character >= position.character
+/// The global at the position, when it's declared at the top of this file or
+/// of a module this file `chama`s
+fn shared_global(
+    source: &str,
+    position: Position,
+    uri: &Uri,
+    documents: &HashMap<String, String>,
+    config: &ServerConfig,
+    index: &SymbolIndex,
+) -> Option<SharedGlobal> {
+    let path = index::file_path(uri)?;
+    let line_index = LineIndex::new(source);
+    line_index.line_start(position.line as usize)?;
+    let offset = to_byte_offset(&line_index, position);
+    let range = to_range(&line_index, &word_at(source, offset));
+
+    let analysis = Analysis::new(&parse_statements(source));
+    if let Some(id) = analysis.declaration_at(offset) {
+        return (id.scope == 0).then(|| SharedGlobal {
+            file: path,
+            name: analysis.declaration(id).name.clone(),
+            range,
+        });
+    }
+    // Declared nowhere here, so maybe in a module
+    let name = &analysis
+        .references()
+        .iter()
+        .find(|reference| reference.span.start <= offset && offset <= reference.span.end)?
+        .name;
+    index.modules(&path).into_iter().find_map(|module| {
+        let module_source = workspace_source(&module, documents, config)?;
+        let module_analysis = Analysis::new(&parse_statements(&module_source));
+        module_analysis.scopes().scopes()[0]
+            .declarations
+            .iter()
+            .any(|declaration| declaration.name == *name)
+            .then(|| SharedGlobal {
+                file: module,
+                name: name.clone(),
+                range,
+            })
+    })
+}
+
+/// The edits renaming a shared global in the file declaring it and every
+/// file that `chama`s it, except `skip`
+fn shared_global_edits(
+    global: &SharedGlobal,
+    new_name: &str,
+    skip: Option<&Path>,
+    documents: &HashMap<String, String>,
+    config: &ServerConfig,
+    index: &SymbolIndex,
+) -> Vec<(Uri, Vec<TextEdit>)> {
+    std::iter::once(global.file.clone())
+        .chain(index.importers(&global.file))
+        .filter(|file| Some(file.as_path()) != skip)
+        .filter_map(|file| {
+            let source = workspace_source(&file, documents, config)?;
+            let edits = global_rename_edits(&source, &global.name, new_name);
+            (!edits.is_empty()).then_some((index::file_uri(&file)?, edits))
         })
-        .map(|loc| loc.range)
+        .collect()
 }
 
-fn get_rename_edits(source: &str, position: Position, new_name: &str, uri: Uri) -> Vec<TextEdit> {
-    find_references(source, position, uri)
-        .into_iter()
-        .map(|loc| TextEdit {
-            range: loc.range,
+/// A workspace file's code: the open document's if it's open, or else
+/// what's on disk, unless it's too big to analyze
+fn workspace_source(
+    file: &Path,
+    documents: &HashMap<String, String>,
+    config: &ServerConfig,
+) -> Option<String> {
+    let uri = index::file_uri(file)?;
+    match documents.get(uri.as_str()) {
+        Some(source) => Some(source.clone()),
+        None => std::fs::read_to_string(file).ok(),
+    }
+    .filter(|source| source.len() <= config.max_file_size)
+}
+
+/// Edits renaming every top-level declaration of the global `name` in
+/// `source` and every use of it. Globals are shared by name, so one declared
+/// again here is still the same one.
+fn global_rename_edits(source: &str, name: &str, new_name: &str) -> Vec<TextEdit> {
+    let index = LineIndex::new(source);
+    let analysis = Analysis::new(&parse_statements(source));
+    let mut spans: Vec<_> = analysis.scopes().scopes()[0]
+        .declarations
+        .iter()
+        .filter(|declaration| declaration.name == name)
+        .map(|declaration| declaration.span.clone())
+        .chain(
+            analysis
+                .references()
+                .iter()
+                .filter(|reference| {
+                    reference.name == name && reference.declaration.is_none_or(|id| id.scope == 0)
+                })
+                .map(|reference| reference.span.clone()),
+        )
+        .collect();
+    spans.sort_by_key(|span| span.start);
+    spans
+        .iter()
+        .map(|span| TextEdit {
+            range: to_range(&index, span),
             new_text: new_name.to_string(),
         })
         .collect()
 }
 
-fn find_references(source: &str, position: Position, uri: Uri) -> Vec<Location> {
-    let word = match get_word_at_position(source, position) {
-        Some(w) => w,
-        None => return vec![],
+/// The edits of a rename, by file. With `confirm` each file's edits carry an
+/// annotation the user has to accept before they're applied.
+fn rename_workspace_edit(
+    files: Vec<(Uri, Vec<TextEdit>)>,
+    old_name: &str,
+    new_name: &str,
+    confirm: bool,
+) -> WorkspaceEdit {
+    if !confirm {
+        return WorkspaceEdit {
+            changes: Some(files.into_iter().collect()),
+            ..Default::default()
+        };
+    }
+    let mut annotations = HashMap::new();
+    let document_changes = files
+        .into_iter()
+        .map(|(uri, edits)| {
+            let id = uri.to_string();
+            let label = index::file_path(&uri)
+                .as_deref()
+                .and_then(Path::file_name)
+                .map_or_else(|| id.clone(), |name| name.to_string_lossy().into_owned());
+            annotations.insert(
+                id.clone(),
+                ChangeAnnotation {
+                    label,
+                    needs_confirmation: Some(true),
+                    description: Some(format!(
+                        "'{}' vira '{}' em {} lugar(es)",
+                        old_name,
+                        new_name,
+                        edits.len()
+                    )),
+                },
+            );
+            TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                edits: edits
+                    .into_iter()
+                    .map(|text_edit| {
+                        OneOf::Right(AnnotatedTextEdit {
+                            text_edit,
+                            annotation_id: id.clone(),
+                        })
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Edits(document_changes)),
+        change_annotations: Some(annotations),
+        ..Default::default()
+    }
+}
+
+/// Every class declaration, including the ones inside fitas and methods
+fn collect_classes<'a>(
+    statements: impl IntoIterator<Item = &'a Stmt>,
+    classes: &mut Vec<&'a Stmt>,
+) {
+    for stmt in statements {
+        if matches!(stmt, Stmt::Class { .. }) {
+            classes.push(stmt);
+        }
+        collect_classes(nested_statements(stmt), classes);
+    }
+}
+
+/// Calls `f` on every expression in the program, class bodies included
+fn for_each_expr_everywhere<'a>(statements: &'a [Stmt], f: &mut impl FnMut(&'a Expr)) {
+    for_each_expr(statements, f);
+    let mut classes = Vec::new();
+    collect_classes(statements, &mut classes);
+    for class in classes {
+        if let Stmt::Class { methods, .. } = class {
+            for_each_expr(methods, f);
+        }
+    }
+}
+
+fn class_named<'a>(statements: &'a [Stmt], name: &str) -> Option<&'a Stmt> {
+    let mut classes = Vec::new();
+    collect_classes(statements, &mut classes);
+    classes
+        .into_iter()
+        .find(|class| matches!(class, Stmt::Class { name: class_name, .. } if *class_name.lexeme == *name))
+}
+
+fn superclass_name(class: &Stmt) -> Option<String> {
+    match class {
+        Stmt::Class {
+            superclass: Some(superclass),
+            ..
+        } => match superclass.as_ref() {
+            Expr::Variable { name } => Some(name.lexeme.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The class that declares the method a `class` instance gets, walking up
+/// its superclasses
+fn declaring_class(statements: &[Stmt], class: &str, method: &str) -> Option<String> {
+    let mut class = class.to_string();
+    let mut seen = Vec::new();
+    loop {
+        let declaration = class_named(statements, &class)?;
+        let Stmt::Class { methods, .. } = declaration else {
+            return None;
+        };
+        if methods
+            .iter()
+            .any(|stmt| matches!(stmt, Stmt::Function { name, .. } if *name.lexeme == *method))
+        {
+            return Some(class);
+        }
+        seen.push(class);
+        class = superclass_name(declaration).filter(|superclass| !seen.contains(superclass))?;
+    }
+}
+
+/// The class of the object a property is read from, when it can be told:
+/// `oCara`, `Pessoa()` or a variable set to `Pessoa()`
+fn receiver_class(statements: &[Stmt], object: &Expr, offset: usize) -> Option<String> {
+    let class = match object {
+        Expr::This { .. } => match enclosing_class(statements, offset)? {
+            Stmt::Class { name, .. } => name.lexeme.to_string(),
+            _ => return None,
+        },
+        Expr::Grouping { expression } => return receiver_class(statements, expression, offset),
+        Expr::Variable { name } => find_var_class_in_stmts(statements, &name.lexeme)?,
+        _ => get_class_from_call(object)?,
     };
+    // `faz()` and variables set from fitas aren't instances
+    class_named(statements, &class).map(|_| class)
+}
 
-    // Check if this word is a declared variable, function, or class
-    let var_declarations = extract_variable_declarations(source);
-    let func_declarations = extract_function_declarations(source);
-    let class_declarations = extract_class_declarations(source);
-    let is_variable = var_declarations.iter().any(|(name, _)| name == &word);
-    let is_function = func_declarations.iter().any(|(name, _)| name == &word);
-    let is_class = class_declarations.iter().any(|(name, _)| name == &word);
+/// The class whose method `mestre.method` calls, from inside a class
+fn super_method_class(statements: &[Stmt], keyword_at: usize, method: &str) -> Option<String> {
+    let superclass = superclass_name(enclosing_class(statements, keyword_at)?)?;
+    declaring_class(statements, &superclass, method)
+}
 
-    if !is_variable && !is_function && !is_class {
-        return vec![];
+/// The class and name of the method under the offset: at its declaration, or
+/// where it's used on a receiver whose class can be told
+fn method_at(statements: &[Stmt], offset: usize) -> Option<(String, String)> {
+    let under = |span: &std::ops::Range<usize>| span.start <= offset && offset <= span.end;
+    let mut classes = Vec::new();
+    collect_classes(statements, &mut classes);
+    for class in classes {
+        if let Stmt::Class {
+            name: class,
+            methods,
+            ..
+        } = class
+            && let Some(Stmt::Function { name, .. }) = methods
+                .iter()
+                .find(|stmt| matches!(stmt, Stmt::Function { name, .. } if under(&name.span)))
+        {
+            return Some((class.lexeme.to_string(), name.lexeme.to_string()));
+        }
     }
 
-    // Find all identifier tokens matching this name
-    let scanner = Scanner::new(source);
-    scanner
-        .filter_map(|r| r.ok())
-        .filter(|token| token.token_type == TokenType::Identifier && token.lexeme == word)
-        .map(|token| {
-            let start = byte_offset_to_position(source, token.span.start);
-            let end = byte_offset_to_position(source, token.span.end);
-            Location {
-                uri: uri.clone(),
-                range: Range { start, end },
+    let mut found = None;
+    for_each_expr_everywhere(statements, &mut |expr| {
+        let class = match expr {
+            Expr::Get { object, name } if under(&name.span) => {
+                receiver_class(statements, object, name.span.start)
+                    .and_then(|class| declaring_class(statements, &class, &name.lexeme))
+            }
+            Expr::Super { keyword, method } if under(&method.span) => {
+                super_method_class(statements, keyword.span.start, &method.lexeme)
             }
+            _ => return,
+        };
+        if let (Some(class), Expr::Get { name, .. } | Expr::Super { method: name, .. }) =
+            (class, expr)
+        {
+            found = Some((class, name.lexeme.to_string()));
+        }
+    });
+    found
+}
+
+/// Where a method is named, and how many uses had a receiver of unknown class
+struct MethodRename {
+    spans: Vec<std::ops::Range<usize>>,
+    unsure: usize,
+}
+
+/// The declaration of `class.method` and the uses that reach it: on receivers
+/// of that class or a subclass that doesn't override it, and through `mestre`
+fn method_rename(statements: &[Stmt], class: &str, method: &str) -> MethodRename {
+    let mut rename = MethodRename {
+        spans: Vec::new(),
+        unsure: 0,
+    };
+    if let Some(Stmt::Class { methods, .. }) = class_named(statements, class) {
+        rename
+            .spans
+            .extend(methods.iter().filter_map(|stmt| match stmt {
+                Stmt::Function { name, .. } if *name.lexeme == *method => Some(name.span.clone()),
+                _ => None,
+            }));
+    }
+    for_each_expr_everywhere(statements, &mut |expr| match expr {
+        Expr::Get { object, name } if *name.lexeme == *method => {
+            match receiver_class(statements, object, name.span.start) {
+                Some(receiver) => {
+                    if declaring_class(statements, &receiver, method).as_deref() == Some(class) {
+                        rename.spans.push(name.span.clone());
+                    }
+                }
+                None => rename.unsure += 1,
+            }
+        }
+        Expr::Super {
+            keyword,
+            method: name,
+        } if *name.lexeme == *method
+            && super_method_class(statements, keyword.span.start, method).as_deref()
+                == Some(class) =>
+        {
+            rename.spans.push(name.span.clone());
+        }
+        _ => {}
+    });
+    rename.spans.sort_by_key(|span| span.start);
+    rename
+}
+
+fn find_references(source: &str, position: Position, uri: Uri) -> Vec<Location> {
+    let index = LineIndex::new(source);
+    if index.line_start(position.line as usize).is_none() {
+        return vec![];
+    }
+    let offset = to_byte_offset(&index, position);
+
+    let analysis = Analysis::new(&parse_statements(source));
+    let Some(id) = analysis.declaration_at(offset) else {
+        return vec![];
+    };
+
+    // The declaration plus every use that binds to it (shadowed names excluded)
+    let mut spans: Vec<_> = std::iter::once(analysis.declaration(id).span.clone())
+        .chain(
+            analysis
+                .references_to(id)
+                .map(|reference| reference.span.clone()),
+        )
+        .collect();
+    spans.sort_by_key(|span| span.start);
+    spans
+        .iter()
+        .map(|span| Location {
+            uri: uri.clone(),
+            range: to_range(&index, span),
         })
         .collect()
 }
 
 #[allow(deprecated)] // SymbolInformation is deprecated but DocumentSymbol requires hierarchy
 fn get_document_symbols(source: &str, uri: Uri) -> Vec<SymbolInformation> {
+    let index = LineIndex::new(source);
     let mut symbols: Vec<SymbolInformation> = Vec::new();
 
     // Add variable symbols
     for (name, span) in extract_variable_declarations(source) {
-        let start = byte_offset_to_position(source, span.start);
-        let end = byte_offset_to_position(source, span.end);
+        let range = to_range(&index, &span);
         symbols.push(SymbolInformation {
             name,
             kind: SymbolKind::VARIABLE,
             location: Location {
                 uri: uri.clone(),
-                range: Range { start, end },
+                range,
             },
             tags: None,
             deprecated: None,
@@ -894,14 +2478,13 @@ fn get_document_symbols(source: &str, uri: Uri) -> Vec<SymbolInformation> {
 
     // Add function symbols
     for (name, span) in extract_function_declarations(source) {
-        let start = byte_offset_to_position(source, span.start);
-        let end = byte_offset_to_position(source, span.end);
+        let range = to_range(&index, &span);
         symbols.push(SymbolInformation {
             name,
             kind: SymbolKind::FUNCTION,
             location: Location {
                 uri: uri.clone(),
-                range: Range { start, end },
+                range,
             },
             tags: None,
             deprecated: None,
@@ -911,31 +2494,57 @@ fn get_document_symbols(source: &str, uri: Uri) -> Vec<SymbolInformation> {
 
     // Add class symbols
     for (name, span) in extract_class_declarations(source) {
-        let start = byte_offset_to_position(source, span.start);
-        let end = byte_offset_to_position(source, span.end);
+        let range = to_range(&index, &span);
         symbols.push(SymbolInformation {
             name,
             kind: SymbolKind::CLASS,
             location: Location {
                 uri: uri.clone(),
-                range: Range { start, end },
+                range,
+            },
+            tags: None,
+            deprecated: None,
+            container_name: None,
+        });
+    }
+
+    // Add enum symbols, then their options
+    for (name, span, variants) in extract_enum_declarations(source) {
+        symbols.push(SymbolInformation {
+            name: name.clone(),
+            kind: SymbolKind::ENUM,
+            location: Location {
+                uri: uri.clone(),
+                range: to_range(&index, &span),
             },
             tags: None,
             deprecated: None,
             container_name: None,
         });
+        for (variant, span) in variants {
+            symbols.push(SymbolInformation {
+                name: variant,
+                kind: SymbolKind::ENUM_MEMBER,
+                location: Location {
+                    uri: uri.clone(),
+                    range: to_range(&index, &span),
+                },
+                tags: None,
+                deprecated: None,
+                container_name: Some(name.clone()),
+            });
+        }
     }
 
     // Add method symbols
     for (method_name, class_name, _params, span) in extract_method_info(source) {
-        let start = byte_offset_to_position(source, span.start);
-        let end = byte_offset_to_position(source, span.end);
+        let range = to_range(&index, &span);
         symbols.push(SymbolInformation {
             name: method_name,
             kind: SymbolKind::METHOD,
             location: Location {
                 uri: uri.clone(),
-                range: Range { start, end },
+                range,
             },
             tags: None,
             deprecated: None,
@@ -961,166 +2570,602 @@ fn get_hover(source: &str, position: Position) -> Option<String> {
     let word = get_word_at_position(source, position)?;
 
     // Check if it's a keyword
-    if KEYWORDS.contains_key(&word) {
+    let aliases = Dialect::from_pragma(source).unwrap_or_default().aliases();
+    if KEYWORDS.contains_key(&word) || aliases.contains_key(&word) {
         return Some(format!("`{}` (keyword)", word));
     }
 
-    // Check if it's a function (with parameters)
-    for (name, params, _span) in extract_function_info(source) {
-        if name == word {
-            let params_str = params.join(", ");
-            return Some(format!("`{}({})` (function)", name, params_str));
-        }
+    // Check if it's a function (with parameters)
+    for (name, params, _span) in extract_function_info(source) {
+        if name == word {
+            let params_str = params.join(", ");
+            return Some(format!("`{}({})` (function)", name, params_str));
+        }
+    }
+
+    // Check if it's a class
+    for (name, _span) in extract_class_declarations(source) {
+        if name == word {
+            return Some(format!("`{}` (bagulho)", name));
+        }
+    }
+
+    // Check if it's an enum or one of its options
+    for (name, _span, variants) in extract_enum_declarations(source) {
+        if name == word {
+            let variants: Vec<_> = variants.into_iter().map(|(variant, _)| variant).collect();
+            return Some(format!("`opcoes {} {{ {} }}`", name, variants.join(", ")));
+        }
+        if variants.iter().any(|(variant, _)| *variant == word) {
+            return Some(format!("`{}.{}` (opção)", name, word));
+        }
+    }
+
+    // Check if it's a method
+    for (method_name, class_name, params, _span) in extract_method_info(source) {
+        if method_name == word {
+            let params_str = params.join(", ");
+            return Some(format!(
+                "`{}.{}({})` (method)",
+                class_name, method_name, params_str
+            ));
+        }
+    }
+
+    // Check if it's a variable
+    let variables = extract_variables(source);
+    if variables.contains(&word) {
+        return Some(format!("`{}` (variable)", word));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_offset_at_start_is_line_0_col_0() {
+        let pos = to_position(&LineIndex::new("hello"), 0);
+        assert_eq!(pos.line, 0);
+        assert_eq!(pos.character, 0);
+    }
+
+    #[test]
+    fn byte_offset_in_first_line() {
+        let pos = to_position(&LineIndex::new("hello world"), 6);
+        assert_eq!(pos.line, 0);
+        assert_eq!(pos.character, 6);
+    }
+
+    #[test]
+    fn byte_offset_after_newline_is_next_line() {
+        let pos = to_position(&LineIndex::new("hello\nworld"), 6);
+        assert_eq!(pos.line, 1);
+        assert_eq!(pos.character, 0);
+    }
+
+    #[test]
+    fn byte_offset_middle_of_second_line() {
+        let pos = to_position(&LineIndex::new("hello\nworld"), 8);
+        assert_eq!(pos.line, 1);
+        assert_eq!(pos.character, 2);
+    }
+
+    #[test]
+    fn valid_code_produces_no_diagnostics() {
+        let diagnostics = get_diagnostics(&test_uri(), "salve 42;", &ServerConfig::default());
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn scan_error_produces_diagnostic() {
+        let diagnostics = get_diagnostics(&test_uri(), "@", &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains('@'));
+    }
+
+    #[test]
+    fn parse_error_produces_diagnostic() {
+        let diagnostics = get_diagnostics(&test_uri(), "salve", &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn multiple_scan_errors_produce_multiple_diagnostics() {
+        let diagnostics = get_diagnostics(&test_uri(), "@$", &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn code_lenses_include_file_and_parameterless_functions() {
+        let source = "olhaEssaFita oi() { salve 1; }\nolhaEssaFita soma(a, b) { toma a + b; }";
+        let lenses = get_code_lenses(source, &test_uri());
+        assert_eq!(lenses.len(), 2);
+
+        let file = lenses[0].command.as_ref().unwrap();
+        assert_eq!(file.title, "▶ rodar");
+        assert_eq!(file.command, RUN_COMMAND);
+        assert_eq!(file.arguments.as_ref().unwrap().len(), 1);
+
+        let oi = lenses[1].command.as_ref().unwrap();
+        assert_eq!(oi.arguments.as_ref().unwrap()[1], "oi");
+        assert_eq!(lenses[1].range.start, Position::new(0, 13));
+    }
+
+    #[test]
+    fn code_lenses_skip_nested_functions() {
+        let source = "{ olhaEssaFita dentro() {} dentro(); }";
+        assert_eq!(get_code_lenses(source, &test_uri()).len(), 1);
+    }
+
+    #[test]
+    fn run_code_captures_output() {
+        let output = run_code("salve 1 + 2;", None, Backend::Interpreter, RUN_TIMEOUT);
+        assert_eq!(output, Ok("3\n".to_string()));
+    }
+
+    #[test]
+    fn run_code_calls_function() {
+        let source = "salve \"carregou\"; olhaEssaFita oi() { salve \"oi\"; }";
+        let output = run_code(source, Some("oi"), Backend::Interpreter, RUN_TIMEOUT);
+        assert_eq!(output, Ok("carregou\noi\n".to_string()));
+    }
+
+    #[test]
+    fn run_code_reports_errors_with_line() {
+        let output = run_code(
+            "seLiga x = \"a\"; salve 1;\nsalve -x;",
+            None,
+            Backend::Interpreter,
+            RUN_TIMEOUT,
+        )
+        .unwrap_err();
+        assert!(output.starts_with("1\n[linha 2]"), "got {}", output);
+    }
+
+    #[test]
+    fn run_code_points_arity_errors_at_the_declaration() {
+        let output = run_code(
+            "olhaEssaFita oi(_nome) {}\n\noi();",
+            None,
+            Backend::Interpreter,
+            RUN_TIMEOUT,
+        )
+        .unwrap_err();
+        assert!(
+            output.starts_with("[linha 3]") && output.contains("(declarada aqui na linha 1)"),
+            "got {}",
+            output
+        );
+    }
+
+    #[test]
+    fn run_code_has_no_input_for_le_ai() {
+        let output = run_code("salve leAi();", None, Backend::Interpreter, RUN_TIMEOUT);
+        assert_eq!(output, Ok("nadaNão\n".to_string()));
+    }
+
+    #[test]
+    fn run_code_stops_endless_recursion_with_an_error() {
+        let output = run_code(
+            "olhaEssaFita f(n) { toma f(n + 1); }\nf(0);",
+            None,
+            Backend::Interpreter,
+            RUN_TIMEOUT,
+        )
+        .unwrap_err();
+        assert!(output.contains("Afundou demais"), "got {}", output);
+    }
+
+    #[test]
+    fn run_code_stops_endless_loops_after_the_timeout() {
+        for backend in [Backend::Interpreter, Backend::Vm] {
+            let output = run_code(
+                "salve 1;\nsegueOFluxo (firmeza) {}",
+                None,
+                backend,
+                Duration::from_millis(100),
+            )
+            .unwrap_err();
+            assert!(output.starts_with("1\n"), "got {}", output);
+            assert!(output.contains("Demorou mais de 0.1s"), "got {}", output);
+        }
+    }
+
+    #[test]
+    fn run_code_is_sandboxed() {
+        let output = run_code(
+            "salve leBytes(\"/etc/hostname\");",
+            None,
+            Backend::Interpreter,
+            RUN_TIMEOUT,
+        )
+        .unwrap_err();
+        assert!(output.contains("sandbox"), "got {}", output);
+    }
+
+    #[test]
+    fn run_code_uses_vm_backend() {
+        assert_eq!(
+            run_code("1 + 2", None, Backend::Vm, RUN_TIMEOUT),
+            Ok("3\n".to_string())
+        );
+    }
+
+    #[test]
+    fn run_command_needs_an_open_document() {
+        let documents = HashMap::from([("file:///a.mano".to_string(), "salve 1;".to_string())]);
+        let arguments = [serde_json::json!("file:///a.mano")];
+        assert_eq!(
+            run_command(&arguments, &documents, Backend::Interpreter),
+            Some(Ok("1\n".to_string()))
+        );
+        let missing = [serde_json::json!("file:///b.mano")];
+        assert_eq!(
+            run_command(&missing, &documents, Backend::Interpreter),
+            None
+        );
+    }
+
+    #[test]
+    fn lints_report_resolution_errors() {
+        let diagnostics =
+            get_diagnostics(&test_uri(), "{ seLiga x = 1; }", &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("nunca foi usada"));
+    }
+
+    #[test]
+    fn dead_branches_are_faded_out() {
+        let diagnostics = get_diagnostics(
+            &test_uri(),
+            "sePá (treta) {\n  salve 1;\n} vacilou salve 2;",
+            &ServerConfig::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(diagnostic.tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(0, 13), Position::new(2, 1))
+        );
+        assert!(diagnostic.message.contains("sempre treta"));
+    }
+
+    #[test]
+    fn lints_can_be_disabled() {
+        let config = ServerConfig {
+            lints: false,
+            ..Default::default()
+        };
+        assert!(get_diagnostics(&test_uri(), "{ seLiga x = 1; }", &config).is_empty());
+    }
+
+    #[test]
+    fn lints_warn_about_look_alike_names() {
+        // The 'о' is Cyrillic
+        let diagnostics = get_diagnostics(
+            &test_uri(),
+            "seLiga nоme = 1; salve nоme;",
+            &ServerConfig::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(diagnostics[0].message.contains("mistura alfabetos"));
+    }
+
+    #[test]
+    fn lints_warn_about_fitas_declared_twice() {
+        let diagnostics = get_diagnostics(
+            &test_uri(),
+            "olhaEssaFita f() {}\nolhaEssaFita f() {}",
+            &ServerConfig::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        let related = diagnostics[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.range.start.line, 0);
+    }
+
+    #[test]
+    fn lints_skipped_when_there_are_parse_errors() {
+        let diagnostics = get_diagnostics(
+            &test_uri(),
+            "{ seLiga x = 1; } salve",
+            &ServerConfig::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics[0].message.contains("nunca foi usada"));
     }
 
-    // Check if it's a class
-    for (name, _span) in extract_class_declarations(source) {
-        if name == word {
-            return Some(format!("`{}` (bagulho)", name));
-        }
+    #[test]
+    fn max_diagnostics_caps_the_list() {
+        let config = ServerConfig {
+            max_diagnostics: 1,
+            ..Default::default()
+        };
+        assert_eq!(get_diagnostics(&test_uri(), "@$", &config).len(), 1);
     }
 
-    // Check if it's a method
-    for (method_name, class_name, params, _span) in extract_method_info(source) {
-        if method_name == word {
-            let params_str = params.join(", ");
-            return Some(format!(
-                "`{}.{}({})` (method)",
-                class_name, method_name, params_str
-            ));
-        }
+    #[test]
+    fn config_update_reads_mano_section() {
+        let mut config = ServerConfig::default();
+        config.update(&serde_json::json!({
+            "mano": {
+                "lints": false,
+                "maxDiagnostics": 5,
+                "backend": "vm",
+                "formatting": { "indentWidth": 2, "useTabs": true }
+            }
+        }));
+        assert!(!config.lints);
+        assert_eq!(config.max_diagnostics, 5);
+        assert_eq!(config.backend, Backend::Vm);
+        assert_eq!(
+            config.formatting,
+            FormattingConfig {
+                indent_width: 2,
+                use_tabs: true
+            }
+        );
     }
 
-    // Check if it's a variable
-    let variables = extract_variables(source);
-    if variables.contains(&word) {
-        return Some(format!("`{}` (variable)", word));
+    #[test]
+    fn config_update_reads_max_file_size() {
+        let mut config = ServerConfig::default();
+        config.update(&serde_json::json!({ "maxFileSize": 10 }));
+        assert_eq!(config.max_file_size, 10);
     }
 
-    None
-}
+    #[test]
+    fn config_update_reads_folding() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.folding, Folding::Statements);
+        config.update(&serde_json::json!({ "folding": "blocks" }));
+        assert_eq!(config.folding, Folding::Blocks);
+        config.update(&serde_json::json!({ "folding": "tudo" }));
+        assert_eq!(config.folding, Folding::Blocks);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn config_update_reads_cache_dir() {
+        let mut config = ServerConfig::default();
+        config.update(&serde_json::json!({ "mano": { "cacheDir": "/tmp/mano" } }));
+        assert_eq!(config.cache_dir, Some(PathBuf::from("/tmp/mano")));
+    }
 
     #[test]
-    fn byte_offset_at_start_is_line_0_col_0() {
-        let pos = byte_offset_to_position("hello", 0);
-        assert_eq!(pos.line, 0);
-        assert_eq!(pos.character, 0);
+    fn config_update_reads_confirm_rename() {
+        let mut config = ServerConfig::default();
+        assert!(!config.confirm_rename);
+        config.update(&serde_json::json!({ "confirmRename": true }));
+        assert!(config.confirm_rename);
     }
 
     #[test]
-    fn byte_offset_in_first_line() {
-        let pos = byte_offset_to_position("hello world", 6);
-        assert_eq!(pos.line, 0);
-        assert_eq!(pos.character, 6);
+    fn read_capabilities_notices_pull_diagnostics() {
+        let mut config = ServerConfig::default();
+        config.read_capabilities(&ClientCapabilities::default());
+        assert!(!config.pull_diagnostics);
+        assert!(!config.refresh_diagnostics);
+
+        let capabilities: ClientCapabilities = serde_json::from_value(serde_json::json!({
+            "textDocument": { "diagnostic": {} },
+            "workspace": { "diagnostic": { "refreshSupport": true } },
+        }))
+        .unwrap();
+        config.read_capabilities(&capabilities);
+        assert!(config.pull_diagnostics);
+        assert!(config.refresh_diagnostics);
     }
 
     #[test]
-    fn byte_offset_after_newline_is_next_line() {
-        let pos = byte_offset_to_position("hello\nworld", 6);
-        assert_eq!(pos.line, 1);
-        assert_eq!(pos.character, 0);
+    fn pulled_diagnostics_are_unchanged_for_the_same_result_id() {
+        let config = ServerConfig::default();
+        let report = diagnostic_report(&test_uri(), "@", &config, None);
+        let DocumentDiagnosticReport::Full(full) = report else {
+            panic!("Expected a full report");
+        };
+        let full = full.full_document_diagnostic_report;
+        assert_eq!(full.items.len(), 1);
+        let result_id = full.result_id.unwrap();
+
+        let report = diagnostic_report(&test_uri(), "@", &config, Some(&result_id));
+        assert!(matches!(
+            report,
+            DocumentDiagnosticReport::Unchanged(unchanged)
+                if unchanged.unchanged_document_diagnostic_report.result_id == result_id
+        ));
+
+        let report = diagnostic_report(&test_uri(), "1;", &config, Some(&result_id));
+        assert!(matches!(report, DocumentDiagnosticReport::Full(_)));
     }
 
     #[test]
-    fn byte_offset_middle_of_second_line() {
-        let pos = byte_offset_to_position("hello\nworld", 8);
-        assert_eq!(pos.line, 1);
-        assert_eq!(pos.character, 2);
+    fn result_ids_follow_the_settings() {
+        let mut config = ServerConfig::default();
+        let before = diagnostics_result_id(&test_uri(), "seLiga x = 1;", &config);
+        assert_eq!(
+            before,
+            diagnostics_result_id(&test_uri(), "seLiga x = 1;", &config)
+        );
+
+        config.lints = false;
+        assert_ne!(
+            before,
+            diagnostics_result_id(&test_uri(), "seLiga x = 1;", &config)
+        );
     }
 
     #[test]
-    fn valid_code_produces_no_diagnostics() {
-        let diagnostics = get_diagnostics("salve 42;");
-        assert!(diagnostics.is_empty());
+    fn workspace_roots_prefer_workspace_folders() {
+        let params: InitializeParams = serde_json::from_value(serde_json::json!({
+            "capabilities": {},
+            "rootUri": "file:///raiz",
+            "workspaceFolders": [{ "uri": "file:///a", "name": "a" }, { "uri": "file:///b", "name": "b" }],
+        }))
+        .unwrap();
+        assert_eq!(
+            workspace_roots(&params),
+            vec![PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+
+        let params: InitializeParams = serde_json::from_value(serde_json::json!({
+            "capabilities": {},
+            "rootUri": "file:///raiz",
+        }))
+        .unwrap();
+        assert_eq!(workspace_roots(&params), vec![PathBuf::from("/raiz")]);
     }
 
     #[test]
-    fn scan_error_produces_diagnostic() {
-        let diagnostics = get_diagnostics("@");
+    fn diagnostics_skip_files_over_the_size_limit() {
+        let config = ServerConfig {
+            max_file_size: 4,
+            ..Default::default()
+        };
+        let diagnostics = get_diagnostics(&test_uri(), "@ $ @", &config);
         assert_eq!(diagnostics.len(), 1);
-        assert!(diagnostics[0].message.contains('@'));
+        assert_eq!(
+            diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert!(diagnostics[0].message.contains("grande demais"));
     }
 
     #[test]
-    fn parse_error_produces_diagnostic() {
-        let diagnostics = get_diagnostics("salve");
-        assert_eq!(diagnostics.len(), 1);
+    fn documents_over_the_size_limit_are_not_analyzed() {
+        let config = ServerConfig {
+            max_file_size: 4,
+            ..Default::default()
+        };
+        let documents = HashMap::from([
+            ("file:///a.mano".to_string(), "1;".to_string()),
+            ("file:///b.mano".to_string(), "salve 1;".to_string()),
+        ]);
+        assert!(config.document(&documents, "file:///a.mano").is_some());
+        assert!(config.document(&documents, "file:///b.mano").is_none());
     }
 
     #[test]
-    fn multiple_scan_errors_produce_multiple_diagnostics() {
-        let diagnostics = get_diagnostics("@$");
-        assert_eq!(diagnostics.len(), 2);
+    fn config_update_keeps_missing_and_invalid_settings() {
+        let mut config = ServerConfig::default();
+        config.update(&serde_json::json!({ "maxDiagnostics": "muitos", "backend": "jit" }));
+        assert_eq!(config, ServerConfig::default());
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_first_declaration() {
+        let source = "{ seLiga _x = 1;\nseLiga _x = 2; }";
+        let diagnostics = get_diagnostics(&test_uri(), source, &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        let related = diagnostics[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.uri, test_uri());
+        assert_eq!(related[0].location.range.start, Position::new(0, 9));
+        assert_eq!(related[0].message, "a primeira tá aqui");
     }
 
     #[test]
     fn to_lsp_diagnostic_sets_error_severity() {
-        let diag = to_lsp_diagnostic("test", &(0..1), "x");
+        let diag = to_lsp_diagnostic("test", &(0..1), &LineIndex::new("x"));
         assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
     }
 
     #[test]
     fn to_lsp_diagnostic_sets_source_to_mano() {
-        let diag = to_lsp_diagnostic("test", &(0..1), "x");
+        let diag = to_lsp_diagnostic("test", &(0..1), &LineIndex::new("x"));
         assert_eq!(diag.source, Some("mano".to_string()));
     }
 
     #[test]
     fn to_lsp_diagnostic_preserves_message() {
-        let diag = to_lsp_diagnostic("E esse '@' aí?", &(0..1), "x");
+        let diag = to_lsp_diagnostic("E esse '@' aí?", &(0..1), &LineIndex::new("x"));
         assert_eq!(diag.message, "E esse '@' aí?");
     }
 
     #[test]
     fn to_lsp_diagnostic_converts_span_to_range() {
         let source = "hello\nworld";
-        let diag = to_lsp_diagnostic("err", &(6..11), source);
+        let diag = to_lsp_diagnostic("err", &(6..11), &LineIndex::new(source));
         assert_eq!(diag.range.start, Position::new(1, 0));
         assert_eq!(diag.range.end, Position::new(1, 5));
     }
 
+    #[test]
+    fn to_lsp_diagnostic_counts_columns_in_utf16() {
+        // '🔥' is 4 bytes but 2 UTF-16 units
+        let source = "salve \"🔥\" @";
+        let diag = to_lsp_diagnostic("err", &(13..14), &LineIndex::new(source));
+        assert_eq!(diag.range.start, Position::new(0, 11));
+    }
+
+    #[test]
+    fn get_prefix_at_position_handles_multibyte_chars() {
+        let source = "seLiga é = 1; sal";
+        let prefix = get_prefix_at_position(source, Position::new(0, 17));
+        assert_eq!(prefix, "sal");
+    }
+
+    /// Completions with the cursor at the end of the source
+    fn completions_at_end(source: &str, prefix: &str) -> Vec<CompletionItem> {
+        get_completions(source, prefix, source.len())
+    }
+
+    /// Completions with the cursor where `|` is
+    fn completions_at_cursor(marked: &str, prefix: &str) -> Vec<CompletionItem> {
+        let offset = marked.find('|').unwrap();
+        get_completions(&marked.replace('|', ""), prefix, offset)
+    }
+
     #[test]
     fn get_completions_returns_all_keywords_for_empty_prefix() {
-        let completions = get_completions("", "");
+        let completions = completions_at_end("", "");
         assert!(completions.len() >= 18); // All keywords
     }
 
     #[test]
     fn get_completions_filters_by_prefix() {
-        let completions = get_completions("", "sal");
+        let completions = completions_at_end("", "sal");
         assert!(completions.iter().any(|c| c.label == "salve"));
         assert!(!completions.iter().any(|c| c.label == "seLiga"));
     }
 
     #[test]
     fn get_completions_prefix_se_matches_multiple() {
-        let completions = get_completions("", "se");
+        let completions = completions_at_end("", "se");
         assert!(completions.iter().any(|c| c.label == "seLiga"));
         assert!(completions.iter().any(|c| c.label == "sePá"));
         assert!(completions.iter().any(|c| c.label == "seVira"));
         assert!(completions.iter().any(|c| c.label == "segueOFluxo"));
     }
 
+    #[test]
+    fn completions_follow_the_document_dialect() {
+        let formal = completions_at_end("// dialeto: formal\n", "imp");
+        assert!(formal.iter().any(|c| c.label == "imprima"));
+        assert!(completions_at_end("", "imp").is_empty());
+    }
+
     #[test]
     fn completion_item_has_keyword_kind() {
-        let completions = get_completions("", "");
+        let completions = completions_at_end("", "");
         let salve = completions.iter().find(|c| c.label == "salve").unwrap();
         assert_eq!(salve.kind, Some(lsp_types::CompletionItemKind::KEYWORD));
     }
 
     #[test]
     fn get_completions_includes_native_functions() {
-        let completions = get_completions("", "faz");
+        let completions = completions_at_end("", "faz");
         assert!(completions.iter().any(|c| c.label == "fazTeuCorre"));
     }
 
     #[test]
     fn native_function_has_function_kind() {
-        let completions = get_completions("", "");
+        let completions = completions_at_end("", "");
         let faz = completions
             .iter()
             .find(|c| c.label == "fazTeuCorre")
@@ -1130,49 +3175,144 @@ mod tests {
 
     #[test]
     fn get_completions_includes_variables_from_source() {
-        let completions = get_completions("seLiga meuNome = 42;", "meu");
+        let completions = completions_at_end("seLiga meuNome = 42;", "meu");
         assert!(completions.iter().any(|c| c.label == "meuNome"));
     }
 
     #[test]
     fn get_completions_variable_has_variable_kind() {
-        let completions = get_completions("seLiga x = 1;", "x");
+        let completions = completions_at_end("seLiga x = 1;", "x");
         let x = completions.iter().find(|c| c.label == "x").unwrap();
         assert_eq!(x.kind, Some(lsp_types::CompletionItemKind::VARIABLE));
     }
 
     #[test]
     fn get_completions_excludes_variables_not_matching_prefix() {
-        let completions = get_completions("seLiga foo = 1; seLiga bar = 2;", "fo");
+        let completions = completions_at_end("seLiga foo = 1; seLiga bar = 2;", "fo");
         assert!(completions.iter().any(|c| c.label == "foo"));
         assert!(!completions.iter().any(|c| c.label == "bar"));
     }
 
     #[test]
     fn get_completions_finds_variables_in_blocks() {
-        let completions = get_completions("{ seLiga inner = 1; }", "inn");
+        let completions = completions_at_cursor("{ seLiga inner = 1; | }", "inn");
         assert!(completions.iter().any(|c| c.label == "inner"));
     }
 
     #[test]
     fn get_completions_finds_variables_in_if_then_branch() {
-        let completions = get_completions("sePá (firmeza) { seLiga thenVar = 1; }", "then");
+        let completions = completions_at_cursor("sePá (firmeza) { seLiga thenVar = 1; | }", "then");
         assert!(completions.iter().any(|c| c.label == "thenVar"));
     }
 
     #[test]
     fn get_completions_finds_variables_in_if_else_branch() {
-        let completions =
-            get_completions("sePá (firmeza) { } vacilou { seLiga elseVar = 2; }", "else");
+        let completions = completions_at_cursor(
+            "sePá (firmeza) { } vacilou { seLiga elseVar = 2; | }",
+            "else",
+        );
         assert!(completions.iter().any(|c| c.label == "elseVar"));
     }
 
     #[test]
     fn get_completions_finds_variables_in_while_body() {
-        let completions = get_completions("segueOFluxo (firmeza) { seLiga loopVar = 1; }", "loop");
+        let completions =
+            completions_at_cursor("segueOFluxo (firmeza) { seLiga loopVar = 1; | }", "loop");
         assert!(completions.iter().any(|c| c.label == "loopVar"));
     }
 
+    #[test]
+    fn get_completions_hides_variables_declared_after_cursor() {
+        let completions = completions_at_cursor("seLiga antes = 1; | seLiga depois = 2;", "");
+        assert!(completions.iter().any(|c| c.label == "antes"));
+        assert!(!completions.iter().any(|c| c.label == "depois"));
+    }
+
+    #[test]
+    fn get_completions_hides_locals_of_other_functions() {
+        let source = "olhaEssaFita f() { seLiga daFita = 1; toma daFita; } |";
+        let completions = completions_at_cursor(source, "da");
+        assert!(!completions.iter().any(|c| c.label == "daFita"));
+    }
+
+    #[test]
+    fn get_completions_includes_params_inside_function() {
+        let source = "olhaEssaFita soma(parcela) { toma | ; }";
+        let completions = completions_at_cursor(source, "par");
+        assert!(completions.iter().any(|c| c.label == "parcela"));
+    }
+
+    #[test]
+    fn get_completions_includes_later_globals_inside_function() {
+        let source = "olhaEssaFita f() { | } seLiga depois = 1;";
+        let completions = completions_at_cursor(source, "dep");
+        assert!(completions.iter().any(|c| c.label == "depois"));
+    }
+
+    #[test]
+    fn get_completions_ranks_locals_before_globals_before_keywords() {
+        let source = "seLiga seGlobal = 1; { seLiga seLocal = 2; | }";
+        let labels: Vec<_> = completions_at_cursor(source, "se")
+            .into_iter()
+            .map(|c| c.label)
+            .collect();
+        let position = |label: &str| labels.iter().position(|l| l == label).unwrap();
+        assert!(position("seLocal") < position("seGlobal"));
+        assert!(position("seGlobal") < position("seLiga"));
+    }
+
+    #[test]
+    fn get_completions_sort_text_follows_ranking() {
+        let completions = completions_at_end("seLiga seGlobal = 1;", "se");
+        let sort_texts: Vec<_> = completions
+            .iter()
+            .map(|c| c.sort_text.clone().unwrap())
+            .collect();
+        let mut sorted = sort_texts.clone();
+        sorted.sort();
+        assert_eq!(sort_texts, sorted);
+    }
+
+    #[test]
+    fn get_completions_sets_filter_text_to_label() {
+        let completions = completions_at_end("seLiga x = 1;", "x");
+        let x = completions.iter().find(|c| c.label == "x").unwrap();
+        assert_eq!(x.filter_text.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn get_completions_dedups_same_label_and_kind() {
+        let source = "seLiga x = 1; { seLiga x = 2; | } seLiga x = 3;";
+        let completions = completions_at_cursor(source, "x");
+        let count = completions.iter().filter(|c| c.label == "x").count();
+        assert_eq!(count, 1);
+        // The shadowing local wins
+        assert!(
+            completions[0]
+                .sort_text
+                .as_deref()
+                .unwrap()
+                .starts_with('0')
+        );
+    }
+
+    #[test]
+    fn get_completions_keeps_same_label_with_different_kinds() {
+        let source = "bagulho Bicho { x() {} } seLiga x = 1;";
+        let kinds: Vec<_> = completions_at_end(source, "x")
+            .into_iter()
+            .filter(|c| c.label == "x")
+            .map(|c| c.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(lsp_types::CompletionItemKind::VARIABLE),
+                Some(lsp_types::CompletionItemKind::METHOD)
+            ]
+        );
+    }
+
     #[test]
     fn find_definition_returns_none_for_empty_source() {
         let result = find_definition("", Position::new(0, 0));
@@ -1329,6 +3469,35 @@ mod tests {
         assert_eq!(result.len(), 3);
     }
 
+    #[test]
+    fn get_document_symbols_survives_broken_code() {
+        let source = "seLiga x = ;\nolhaEssaFita f() {\n    salve );\n}";
+        let result = get_document_symbols(source, test_uri());
+        let names: Vec<_> = result.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["x", "f"]);
+    }
+
+    #[test]
+    fn get_folding_ranges_survives_broken_code() {
+        let source = "olhaEssaFita f() {\n    salve );\n}";
+        assert_eq!(get_folding_ranges(source, Folding::Statements).len(), 1);
+    }
+
+    #[test]
+    fn find_references_skips_shadowing_declarations() {
+        let source = "seLiga x = 1;\n{ seLiga x = 2; salve x; }\nsalve x;";
+        let result = find_references(source, Position::new(0, 7), test_uri());
+        let lines: Vec<_> = result.iter().map(|loc| loc.range.start.line).collect();
+        assert_eq!(lines, vec![0, 2]);
+    }
+
+    #[test]
+    fn find_definition_finds_shadowing_local() {
+        let source = "seLiga x = 1;\n{ seLiga x = 2; salve x; }";
+        let result = find_definition(source, Position::new(1, 22)).unwrap();
+        assert_eq!(result.start, Position::new(1, 9));
+    }
+
     #[test]
     fn get_hover_works_with_emoji_variable() {
         let source = "seLiga 🔥 = 42;\nsalve 🔥;";
@@ -1388,21 +3557,21 @@ mod tests {
     #[test]
     fn get_rename_edits_renames_function() {
         let source = "olhaEssaFita foo() { salve 1; }\nfoo();";
-        let result = get_rename_edits(source, Position::new(0, 13), "bar", test_uri());
+        let result = get_rename_edits(source, Position::new(0, 13), "bar", test_uri()).edits;
         assert_eq!(result.len(), 2, "Should rename declaration and call");
         assert!(result.iter().all(|edit| edit.new_text == "bar"));
     }
 
     #[test]
     fn get_rename_edits_returns_empty_for_non_variable() {
-        let result = get_rename_edits("salve 42;", Position::new(0, 0), "bar", test_uri());
+        let result = get_rename_edits("salve 42;", Position::new(0, 0), "bar", test_uri()).edits;
         assert!(result.is_empty());
     }
 
     #[test]
     fn get_rename_edits_returns_edits_for_all_references() {
         let source = "seLiga foo = 42;\nsalve foo;";
-        let result = get_rename_edits(source, Position::new(1, 6), "bar", test_uri());
+        let result = get_rename_edits(source, Position::new(1, 6), "bar", test_uri()).edits;
         assert_eq!(result.len(), 2);
         assert!(result.iter().all(|edit| edit.new_text == "bar"));
     }
@@ -1410,49 +3579,279 @@ mod tests {
     #[test]
     fn get_rename_edits_works_with_emoji_variable() {
         let source = "seLiga 🔥 = 42;\nsalve 🔥;";
-        let result = get_rename_edits(source, Position::new(1, 6), "fire", test_uri());
+        let result = get_rename_edits(source, Position::new(1, 6), "fire", test_uri()).edits;
         assert_eq!(result.len(), 2, "Should find 2 edits for emoji variable");
         assert!(result.iter().all(|edit| edit.new_text == "fire"));
     }
 
+    #[test]
+    fn rename_method_only_touches_its_class() {
+        let source = "bagulho Pessoa { falar() { salve 1; } oi() { oCara.falar(); } }\n\
+                      bagulho Gato { falar() { salve 2; } }\n\
+                      olhaEssaFita falar() { salve 3; }\n\
+                      seLiga p = Pessoa();\n\
+                      p.falar();\n\
+                      Gato().falar();\n\
+                      falar();";
+        let rename = get_rename_edits(source, Position::new(0, 17), "fala", test_uri());
+        let lines: Vec<u32> = rename
+            .edits
+            .iter()
+            .map(|edit| edit.range.start.line)
+            .collect();
+        // Declaration, `oCara.falar()` and `p.falar()`
+        assert_eq!(lines, vec![0, 0, 4]);
+        assert!(rename.warning.is_none());
+    }
+
+    #[test]
+    fn rename_method_from_a_call_follows_inheritance() {
+        let source = "bagulho A { falar() { salve 1; } }\n\
+                      bagulho B < A { falar() { mestre.falar(); } }\n\
+                      bagulho C < A {}\n\
+                      C().falar();\n\
+                      B().falar();";
+        assert!(prepare_rename(source, Position::new(3, 5)).is_some());
+        let rename = get_rename_edits(source, Position::new(3, 5), "fala", test_uri());
+        let lines: Vec<u32> = rename
+            .edits
+            .iter()
+            .map(|edit| edit.range.start.line)
+            .collect();
+        // A's declaration, `mestre.falar()` and `C().falar()`, not B's override
+        assert_eq!(lines, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn rename_method_warns_about_unknown_receivers() {
+        let source = "bagulho Pessoa { falar() { salve 1; } }\n\
+                      olhaEssaFita trata(x) { x.falar(); }";
+        let rename = get_rename_edits(source, Position::new(0, 17), "fala", test_uri());
+        assert_eq!(rename.edits.len(), 1);
+        assert!(rename.warning.unwrap().contains("1 uso(s) de 'falar'"));
+    }
+
+    #[test]
+    fn prepare_rename_skips_unknown_receivers() {
+        let source = "olhaEssaFita trata(x) { x.falar(); }";
+        assert!(prepare_rename(source, Position::new(0, 26)).is_none());
+    }
+
+    /// A workspace where `main.mano` chamas `util.mano` and `app/usa.mano`
+    /// chamas `main.mano`, all indexed
+    fn modules_workspace() -> (tempfile::TempDir, SymbolIndex) {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in [
+            (
+                "util.mano",
+                "olhaEssaFita soma(a, b) { toma a + b; }\nsalve soma(1, 2);",
+            ),
+            ("main.mano", "chama \"util\";\nsalve soma(3, 4);"),
+            ("app/usa.mano", "chama \"../main\";\nsalve soma(5, 6);"),
+            ("solto.mano", "olhaEssaFita f(soma) { toma soma; }"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        let mut index = SymbolIndex::default();
+        index.refresh(&[dir.path().to_path_buf()], usize::MAX);
+        (dir, index)
+    }
+
+    fn edited_files(dir: &Path, files: &[(Uri, Vec<TextEdit>)]) -> Vec<(String, usize)> {
+        let mut edited: Vec<_> = files
+            .iter()
+            .map(|(uri, edits)| {
+                let path = index::file_path(uri).unwrap();
+                let name = path
+                    .strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                (name, edits.len())
+            })
+            .collect();
+        edited.sort();
+        edited
+    }
+
+    #[test]
+    fn chama_paths_link_to_their_modules() {
+        let (dir, _) = modules_workspace();
+        let usa = index::file_uri(&dir.path().join("app/usa.mano")).unwrap();
+        let source = "chama \"../main\";\nchama \"nada\";";
+
+        let links = get_document_links(source, &usa);
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].range,
+            Range::new(Position::new(0, 7), Position::new(0, 14))
+        );
+        let main = dir.path().join("main.mano").canonicalize().unwrap();
+        assert_eq!(links[0].target, index::file_uri(&main));
+        // Without a file there's no folder to start from
+        let untitled: Uri = "untitled:Untitled-1".parse().unwrap();
+        assert!(get_document_links(source, &untitled).is_empty());
+    }
+
+    #[test]
+    fn missing_modules_are_diagnosed() {
+        let (dir, _) = modules_workspace();
+        let main = index::file_uri(&dir.path().join("main.mano")).unwrap();
+        let source = "chama \"util\";\nchama \"nada\";";
+
+        let diagnostics = get_diagnostics(&main, source, &ServerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Não achei o módulo 'nada', mano.");
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(1, 6), Position::new(1, 12))
+        );
+    }
+
+    #[test]
+    fn renaming_a_global_reaches_the_files_that_chama_it() {
+        let (dir, index) = modules_workspace();
+        let config = ServerConfig::default();
+        let documents = HashMap::new();
+        let util = dir.path().join("util.mano");
+        let source = std::fs::read_to_string(&util).unwrap();
+        let uri = index::file_uri(&util).unwrap();
+
+        let global = shared_global(
+            &source,
+            Position::new(0, 14),
+            &uri,
+            &documents,
+            &config,
+            &index,
+        )
+        .unwrap();
+        assert_eq!(global.name, "soma");
+        let edits = shared_global_edits(&global, "mais", Some(&util), &documents, &config, &index);
+        assert_eq!(
+            edited_files(dir.path(), &edits),
+            vec![
+                ("app/usa.mano".to_string(), 1),
+                ("main.mano".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn renaming_a_use_finds_the_module_declaring_it() {
+        let (dir, index) = modules_workspace();
+        let config = ServerConfig::default();
+        // The open document's code counts, not what's saved
+        let main = dir.path().join("main.mano");
+        let uri = index::file_uri(&main).unwrap();
+        let source = "chama \"util\";\nsalve soma(3, 4) + soma(5, 6);";
+        let documents = HashMap::from([(uri.to_string(), source.to_string())]);
+
+        assert!(prepare_rename(source, Position::new(1, 7)).is_none());
+        let global = shared_global(
+            source,
+            Position::new(1, 7),
+            &uri,
+            &documents,
+            &config,
+            &index,
+        )
+        .unwrap();
+        assert_eq!(global.file, dir.path().join("util.mano"));
+        assert_eq!(
+            global.range,
+            Range::new(Position::new(1, 6), Position::new(1, 10))
+        );
+        let edits = shared_global_edits(&global, "mais", None, &documents, &config, &index);
+        assert_eq!(
+            edited_files(dir.path(), &edits),
+            vec![
+                ("app/usa.mano".to_string(), 1),
+                ("main.mano".to_string(), 2),
+                ("util.mano".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn locals_are_not_shared_globals() {
+        let (dir, index) = modules_workspace();
+        let path = dir.path().join("solto.mano");
+        let source = std::fs::read_to_string(&path).unwrap();
+        let global = shared_global(
+            &source,
+            Position::new(0, 16),
+            &index::file_uri(&path).unwrap(),
+            &HashMap::new(),
+            &ServerConfig::default(),
+            &index,
+        );
+        assert!(global.is_none());
+    }
+
+    #[test]
+    fn confirmed_renames_annotate_each_file() {
+        let uri = test_uri();
+        let edit = TextEdit {
+            range: Range::default(),
+            new_text: "y".to_string(),
+        };
+        let files = vec![(uri.clone(), vec![edit.clone()])];
+
+        let plain = rename_workspace_edit(files.clone(), "x", "y", false);
+        assert_eq!(plain.changes.unwrap()[&uri], vec![edit.clone()]);
+
+        let annotated = rename_workspace_edit(files, "x", "y", true);
+        let Some(DocumentChanges::Edits(changes)) = annotated.document_changes else {
+            panic!("expected document edits");
+        };
+        let OneOf::Right(annotated_edit) = &changes[0].edits[0] else {
+            panic!("expected an annotated edit");
+        };
+        assert_eq!(annotated_edit.text_edit, edit);
+        let annotation = &annotated.change_annotations.unwrap()[&annotated_edit.annotation_id];
+        assert_eq!(annotation.label, "test.mano");
+        assert_eq!(annotation.needs_confirmation, Some(true));
+    }
+
     #[test]
     fn get_folding_ranges_returns_empty_for_no_blocks() {
-        let result = get_folding_ranges("salve 42;");
+        let result = get_folding_ranges("salve 42;", Folding::Statements);
         assert!(result.is_empty());
     }
 
     #[test]
     fn get_folding_ranges_returns_range_for_if_block() {
         let source = "sePá (firmeza) {\n    salve 42;\n}";
-        let result = get_folding_ranges(source);
-        // 2 folds: If statement (0-2) and Block inside (0-2)
-        assert_eq!(result.len(), 2);
-        // Both span lines 0-2
-        assert!(result.iter().all(|r| r.start_line == 0 && r.end_line == 2));
+        let result = get_folding_ranges(source, Folding::Statements);
+        // The If statement and its Block both span lines 0-2, so one fold
+        assert_eq!(result.len(), 1);
+        assert_eq!((result[0].start_line, result[0].end_line), (0, 2));
     }
 
     #[test]
     fn get_folding_ranges_returns_range_for_while_block() {
         let source = "segueOFluxo (firmeza) {\n    salve 1;\n}";
-        let result = get_folding_ranges(source);
-        // 2 folds: While statement (0-2) and Block inside (0-2)
-        assert_eq!(result.len(), 2);
-        // Both span lines 0-2
-        assert!(result.iter().all(|r| r.start_line == 0 && r.end_line == 2));
+        let result = get_folding_ranges(source, Folding::Statements);
+        // The While statement and its Block both span lines 0-2, so one fold
+        assert_eq!(result.len(), 1);
+        assert_eq!((result[0].start_line, result[0].end_line), (0, 2));
     }
 
     #[test]
     fn get_folding_ranges_returns_nested_ranges() {
         let source = "sePá (firmeza) {\n    sePá (firmeza) {\n        salve 1;\n    }\n}";
-        let result = get_folding_ranges(source);
-        // 4 folds: outer If, outer Block, inner If, inner Block
-        assert_eq!(result.len(), 4);
+        let result = get_folding_ranges(source, Folding::Statements);
+        // 2 folds: outer If with its Block, inner If with its Block
+        assert_eq!(result.len(), 2);
     }
 
     #[test]
     fn get_folding_ranges_folds_if_without_braces() {
         let source = "sePá (firmeza)\n    salve 42;";
-        let result = get_folding_ranges(source);
+        let result = get_folding_ranges(source, Folding::Statements);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].start_line, 0);
         assert_eq!(result[0].end_line, 1);
@@ -1465,7 +3864,7 @@ mod tests {
         // Line 2: vacilou
         // Line 3:     salve 2;
         let source = "sePá (firmeza)\n    salve 1;\nvacilou\n    salve 2;";
-        let result = get_folding_ranges(source);
+        let result = get_folding_ranges(source, Folding::Statements);
 
         // Should have 2 folds: whole if-else (0-3) and else branch (2-3)
         assert_eq!(result.len(), 2, "Expected 2 folds, got {:?}", result);
@@ -1487,10 +3886,25 @@ mod tests {
         assert_eq!(else_fold.unwrap().end_line, 3);
     }
 
+    #[test]
+    fn get_folding_ranges_can_fold_only_declarations_and_blocks() {
+        let source = "bagulho B {\n    m() {\n        sePá (x)\n            salve 1;\n    }\n}\n\
+                      sePá (x)\n    salve 2;\nvacilou {\n    salve 3;\n}";
+        let lines: Vec<_> = get_folding_ranges(source, Folding::Blocks)
+            .iter()
+            .map(|range| (range.start_line, range.end_line))
+            .collect();
+        assert_eq!(lines, [(0, 5), (1, 4), (8, 10)]);
+
+        // Statements inside fitas and methods fold too
+        let all = get_folding_ranges(source, Folding::Statements);
+        assert!(all.iter().any(|range| range.start_line == 2));
+    }
+
     #[test]
     fn get_folding_ranges_folds_while_without_braces() {
         let source = "segueOFluxo (firmeza)\n    salve 1;";
-        let result = get_folding_ranges(source);
+        let result = get_folding_ranges(source, Folding::Statements);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].start_line, 0);
         assert_eq!(result[0].end_line, 1);
@@ -1583,6 +3997,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_hover_shows_a_rest_parameter() {
+        let source = "olhaEssaFita soma(...nums) { toma nums; }";
+        let hover = get_hover(source, Position::new(0, 13)).unwrap();
+        assert!(hover.contains("soma(...nums)"), "got: {}", hover);
+    }
+
     #[test]
     fn get_hover_returns_function_with_no_params() {
         let source = "olhaEssaFita ping() { salve 1; }";
@@ -1599,14 +4020,14 @@ mod tests {
     #[test]
     fn get_completions_includes_function_names() {
         let source = "olhaEssaFita soma(a, b) { toma a + b; }";
-        let result = get_completions(source, "so");
+        let result = completions_at_end(source, "so");
         assert!(result.iter().any(|c| c.label == "soma"));
     }
 
     #[test]
     fn function_completion_has_function_kind() {
         let source = "olhaEssaFita soma(a, b) { toma a + b; }";
-        let result = get_completions(source, "so");
+        let result = completions_at_end(source, "so");
         let soma = result.iter().find(|c| c.label == "soma");
         assert!(soma.is_some());
         assert_eq!(soma.unwrap().kind, Some(CompletionItemKind::FUNCTION));
@@ -1615,7 +4036,7 @@ mod tests {
     #[test]
     fn function_completion_shows_params_in_detail() {
         let source = "olhaEssaFita soma(a, b) { toma a + b; }";
-        let result = get_completions(source, "so");
+        let result = completions_at_end(source, "so");
         let soma = result.iter().find(|c| c.label == "soma").unwrap();
         assert_eq!(soma.detail, Some("(a, b)".to_string()));
     }
@@ -1623,7 +4044,7 @@ mod tests {
     #[test]
     fn function_completion_shows_empty_params_in_detail() {
         let source = "olhaEssaFita ping() { salve 1; }";
-        let result = get_completions(source, "pi");
+        let result = completions_at_end(source, "pi");
         let ping = result.iter().find(|c| c.label == "ping").unwrap();
         assert_eq!(ping.detail, Some("()".to_string()));
     }
@@ -1647,6 +4068,30 @@ mod tests {
         assert_eq!(result[1].0, "Carro");
     }
 
+    #[test]
+    fn enums_are_symbols_with_their_options() {
+        let source = "opcoes Cor { Vermelho, Verde }";
+        let symbols = get_document_symbols(source, test_uri());
+        let kinds: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("Cor", SymbolKind::ENUM),
+                ("Vermelho", SymbolKind::ENUM_MEMBER),
+                ("Verde", SymbolKind::ENUM_MEMBER)
+            ]
+        );
+        assert_eq!(symbols[1].container_name.as_deref(), Some("Cor"));
+        assert_eq!(
+            get_hover(source, Position::new(0, 8)).unwrap(),
+            "`opcoes Cor { Vermelho, Verde }`"
+        );
+        assert_eq!(
+            get_hover(source, Position::new(0, 24)).unwrap(),
+            "`Cor.Verde` (opção)"
+        );
+    }
+
     #[test]
     fn find_definition_finds_class_declaration() {
         let source = "bagulho Pessoa {}\nseLiga p = Pessoa();";
@@ -1757,21 +4202,21 @@ mod tests {
     #[test]
     fn get_completions_includes_class_names() {
         let source = "bagulho Pessoa {}";
-        let result = get_completions(source, "Pes");
+        let result = completions_at_end(source, "Pes");
         assert!(result.iter().any(|c| c.label == "Pessoa"));
     }
 
     #[test]
     fn get_completions_includes_method_names() {
         let source = "bagulho Pessoa { falar() {} }";
-        let result = get_completions(source, "fal");
+        let result = completions_at_end(source, "fal");
         assert!(result.iter().any(|c| c.label == "falar"));
     }
 
     #[test]
     fn class_completion_has_class_kind() {
         let source = "bagulho Pessoa {}";
-        let result = get_completions(source, "Pes");
+        let result = completions_at_end(source, "Pes");
         let pessoa = result.iter().find(|c| c.label == "Pessoa");
         assert!(pessoa.is_some());
         assert_eq!(pessoa.unwrap().kind, Some(CompletionItemKind::CLASS));
@@ -1780,7 +4225,7 @@ mod tests {
     #[test]
     fn method_completion_has_method_kind() {
         let source = "bagulho Pessoa { falar() {} }";
-        let result = get_completions(source, "fal");
+        let result = completions_at_end(source, "fal");
         let falar = result.iter().find(|c| c.label == "falar");
         assert!(falar.is_some());
         assert_eq!(falar.unwrap().kind, Some(CompletionItemKind::METHOD));
@@ -1857,6 +4302,77 @@ mod tests {
         assert!(completions.is_empty());
     }
 
+    #[test]
+    fn this_completion_lists_the_enclosing_class_members() {
+        let source = "bagulho A { bora(nome) { oCara.nome = nome; } falar(oi) { oCara. } idade { toma 1; } }\nbagulho B { outro() {} }";
+        let position = Position::new(0, 64); // right after "oCara."
+        let completions = get_completions_at_position(source, position);
+        let labels: Vec<_> = completions.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, ["falar", "idade", "nome"]);
+        assert_eq!(completions[0].kind, Some(CompletionItemKind::METHOD));
+        assert_eq!(completions[0].detail.as_deref(), Some("(oi)"));
+        assert_eq!(completions[2].kind, Some(CompletionItemKind::FIELD));
+    }
+
+    #[test]
+    fn this_completion_uses_the_innermost_class_and_prefix() {
+        let source = "bagulho A { a() {} f() { bagulho B { b() {} bb() { oCara.b } } } }";
+        let position = Position::new(0, 58); // after "oCara.b"
+        let completions = get_completions_at_position(source, position);
+        let labels: Vec<_> = completions.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, ["b", "bb"]);
+    }
+
+    #[test]
+    fn this_completion_outside_a_class_is_empty() {
+        let source = "olhaEssaFita f() { oCara. }";
+        assert!(get_completions_at_position(source, Position::new(0, 25)).is_empty());
+    }
+
+    #[test]
+    fn find_definition_follows_this_to_the_enclosing_class() {
+        let source = "bagulho A { falar() {} }\nbagulho B { bora() { oCara.nome = 1; } falar() { salve oCara.nome; toma oCara.falar(); } }";
+        // On "nome" in "salve oCara.nome": the field is first set in bora
+        let field = find_definition(source, Position::new(1, 61)).unwrap();
+        assert_eq!((field.start.line, field.start.character), (1, 27));
+        // On "falar" in "oCara.falar()": B's method, not A's
+        let method = find_definition(source, Position::new(1, 78)).unwrap();
+        assert_eq!((method.start.line, method.start.character), (1, 39));
+    }
+
+    #[test]
+    fn find_type_definition_jumps_to_the_instantiated_class() {
+        let source = "bagulho Pessoa { falar() { salve oCara; } }\n\
+                      olhaEssaFita faz() { seLiga p = Pessoa(); salve p; }\n\
+                      seLiga n = 1;";
+        // On "p" in "salve p", and on "oCara"
+        for position in [Position::new(1, 48), Position::new(0, 34)] {
+            let class = find_type_definition(source, position).unwrap();
+            assert_eq!((class.start.line, class.start.character), (0, 8));
+        }
+        // A number has no class to jump to
+        assert!(find_type_definition(source, Position::new(2, 7)).is_none());
+    }
+
+    #[test]
+    fn find_implementations_lists_subclasses_and_overrides() {
+        let source = "bagulho A { falar() {} }\n\
+                      bagulho B < A { falar() {} }\n\
+                      bagulho C < B {}\n\
+                      bagulho D < C { falar() {} }";
+        let lines = |position| -> Vec<u32> {
+            find_implementations(source, position)
+                .iter()
+                .map(|range| range.start.line)
+                .collect()
+        };
+        // From class A: B, C and D
+        assert_eq!(lines(Position::new(0, 8)), vec![1, 2, 3]);
+        // From A's falar: the overrides in B and D
+        assert_eq!(lines(Position::new(0, 13)), vec![1, 3]);
+        assert!(lines(Position::new(3, 0)).is_empty());
+    }
+
     #[test]
     fn dot_completion_excludes_initializer_method() {
         // bora is the initializer - should NOT appear in dot completions
@@ -1900,14 +4416,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "obj".to_string(),
+                    lexeme: "obj".into(),
                     literal: None,
                     span: 0..3,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "method".to_string(),
+                lexeme: "method".into(),
                 literal: None,
                 span: 4..10,
             },
@@ -1916,7 +4432,7 @@ mod tests {
             callee: Box::new(get_expr),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 11..12,
             },
@@ -1925,4 +4441,23 @@ mod tests {
         // This should return None because callee is Get, not Variable
         assert!(get_class_from_call(&call_expr).is_none());
     }
+
+    #[test]
+    fn format_document_uses_configured_indentation() {
+        let config = FormattingConfig {
+            indent_width: 2,
+            use_tabs: false,
+        };
+        let edits = format_document("{\nsalve 1;\n}\n", &config).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "{\n  salve 1;\n}\n");
+        assert_eq!(edits[0].range.end, Position::new(3, 0));
+    }
+
+    #[test]
+    fn format_document_leaves_formatted_or_broken_code_alone() {
+        let config = ServerConfig::default().formatting;
+        assert!(format_document("salve 1;\n", &config).is_none());
+        assert!(format_document("{\nsalve (1;\n}", &config).is_none());
+    }
 }
@@ -495,3 +495,114 @@ fn lsp_folding_else_branch_without_braces() {
 
     lsp.shutdown();
 }
+
+#[test]
+fn lsp_did_change_configuration_republishes_diagnostics() {
+    let mut lsp = LspClient::spawn();
+    lsp.initialize();
+
+    // Unused local is a lint, reported by default
+    lsp.send(r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///test.mano","languageId":"mano","version":1,"text":"{ seLiga x = 1; }"}}}"#);
+    let diagnostics = lsp.recv();
+    assert!(diagnostics.contains("nunca foi usada"));
+
+    // Turning lints off republishes without it
+    lsp.send(r#"{"jsonrpc":"2.0","method":"workspace/didChangeConfiguration","params":{"settings":{"mano":{"lints":false}}}}"#);
+    let diagnostics = lsp.recv();
+    assert!(diagnostics.contains(r#""diagnostics":[]"#));
+
+    lsp.shutdown();
+}
+
+#[test]
+fn lsp_code_lens_runs_file() {
+    let mut lsp = LspClient::spawn();
+    lsp.initialize();
+
+    lsp.send(r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///test.mano","languageId":"mano","version":1,"text":"salve 40 + 2;"}}}"#);
+    let _ = lsp.recv(); // diagnostics
+
+    lsp.send(r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/codeLens","params":{"textDocument":{"uri":"file:///test.mano"}}}"#);
+    let response = lsp.recv();
+    assert!(response.contains("▶ rodar"));
+    assert!(response.contains("mano.rodar"));
+
+    lsp.send(r#"{"jsonrpc":"2.0","id":3,"method":"workspace/executeCommand","params":{"command":"mano.rodar","arguments":["file:///test.mano"]}}"#);
+    let message = lsp.recv();
+    assert!(message.contains("window/showMessage"));
+    assert!(message.contains(r#"42\n"#));
+    let response = lsp.recv();
+    assert!(response.contains(r#""id":3"#));
+
+    lsp.shutdown();
+}
+
+#[test]
+fn lsp_code_lens_run_does_not_wait_for_input() {
+    let mut lsp = LspClient::spawn();
+    lsp.initialize();
+
+    lsp.send(r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///test.mano","languageId":"mano","version":1,"text":"salve leAi();"}}}"#);
+    let _ = lsp.recv(); // diagnostics
+
+    lsp.send(r#"{"jsonrpc":"2.0","id":2,"method":"workspace/executeCommand","params":{"command":"mano.rodar","arguments":["file:///test.mano"]}}"#);
+    let message = lsp.recv();
+    assert!(message.contains("window/showMessage"));
+    assert!(message.contains(r#"nadaNão\n"#));
+    let response = lsp.recv();
+    assert!(response.contains(r#""id":2"#));
+
+    // Still answering, the run didn't take the next message as input
+    lsp.send(r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/codeLens","params":{"textDocument":{"uri":"file:///test.mano"}}}"#);
+    assert!(lsp.recv().contains(r#""id":3"#));
+
+    lsp.shutdown();
+}
+
+#[test]
+fn lsp_links_chama_paths_to_their_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("util.mano"), "seLiga x = 1;").unwrap();
+    let main = format!("file://{}/main.mano", dir.path().display());
+    let mut lsp = LspClient::spawn();
+    lsp.initialize();
+
+    lsp.send(&format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{main}","languageId":"mano","version":1,"text":"chama \"util\";\nchama \"nada\";"}}}}}}"#
+    ));
+    let diagnostics = lsp.recv();
+    assert!(
+        diagnostics.contains("Não achei o módulo 'nada'"),
+        "{diagnostics}"
+    );
+    assert!(!diagnostics.contains("'util'"), "{diagnostics}");
+
+    lsp.send(&format!(
+        r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/documentLink","params":{{"textDocument":{{"uri":"{main}"}}}}}}"#
+    ));
+    let response = lsp.recv();
+    assert!(response.contains(r#""id":2"#));
+    assert!(response.contains("/util.mano\""), "{response}");
+    assert!(!response.contains("nada"), "{response}");
+
+    lsp.shutdown();
+}
+
+#[test]
+fn lsp_answers_pulled_diagnostics() {
+    let mut lsp = LspClient::spawn();
+    lsp.send(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{"textDocument":{"diagnostic":{}}}}}"#);
+    let response = lsp.recv();
+    assert!(response.contains(r#""diagnosticProvider""#));
+    lsp.send(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#);
+
+    // A client that pulls doesn't get diagnostics pushed on didOpen
+    lsp.send(r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///test.mano","languageId":"mano","version":1,"text":"@"}}}"#);
+    lsp.send(r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/diagnostic","params":{"textDocument":{"uri":"file:///test.mano"}}}"#);
+    let response = lsp.recv();
+    assert!(response.contains(r#""id":2"#));
+    assert!(response.contains(r#""kind":"full""#));
+    assert!(response.contains("@"));
+
+    lsp.shutdown();
+}
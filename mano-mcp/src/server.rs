@@ -7,7 +7,9 @@ use rmcp::{
     schemars, tool, tool_handler, tool_router,
 };
 
-use crate::tools::{get_translation_prompt, run_mano_code};
+use mano::FormatOptions;
+
+use crate::tools::{format_mano_code, get_translation_prompt, lint_mano_code, run_mano_code};
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct RunParams {
@@ -21,6 +23,22 @@ pub struct TranslateParams {
     pub code: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct FormatParams {
+    #[schemars(description = "The mano source code to format")]
+    pub code: String,
+    #[schemars(description = "Spaces per indentation level, 4 when left out")]
+    pub indent_width: Option<usize>,
+    #[schemars(description = "Indent with tabs instead of spaces")]
+    pub use_tabs: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct LintParams {
+    #[schemars(description = "The mano source code to check")]
+    pub code: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ManoMcp {
     mano_bin: PathBuf,
@@ -47,6 +65,27 @@ impl ManoMcp {
     pub fn translate_to_mano(&self, Parameters(params): Parameters<TranslateParams>) -> String {
         get_translation_prompt(&params.code)
     }
+
+    #[tool(
+        name = "mano.format",
+        description = "Format mano code the way `mano fmt` does and return it"
+    )]
+    pub fn format_mano(&self, Parameters(params): Parameters<FormatParams>) -> String {
+        let defaults = FormatOptions::default();
+        let options = FormatOptions {
+            indent_width: params.indent_width.unwrap_or(defaults.indent_width),
+            use_tabs: params.use_tabs.unwrap_or(defaults.use_tabs),
+        };
+        format_mano_code(&params.code, &options)
+    }
+
+    #[tool(
+        name = "mano.lint",
+        description = "Check mano code without running it. Returns a JSON list of problems with severity, message, and 1-based line and column"
+    )]
+    pub fn lint_mano(&self, Parameters(params): Parameters<LintParams>) -> String {
+        serde_json::to_string(&lint_mano_code(&params.code)).unwrap_or_default()
+    }
 }
 
 #[tool_handler]
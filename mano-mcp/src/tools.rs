@@ -1,12 +1,17 @@
+use std::ops::Range;
 use std::path::Path;
 use std::process::Stdio;
+
+use mano::{FormatOptions, LineIndex, ManoError};
+use serde::Serialize;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 /// Executes mano code by shelling out to the mano CLI.
 /// Returns the combined stdout and stderr output.
 pub async fn run_mano_code(mano_bin: &Path, code: &str) -> String {
-    // Run mano CLI with stdin
+    // Run mano CLI with stdin, never with --libera-comandos: code from the
+    // client must not run shell commands
     let mut child = match Command::new(mano_bin)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -42,6 +47,105 @@ pub async fn run_mano_code(mano_bin: &Path, code: &str) -> String {
     }
 }
 
+/// Formats mano code the way `mano fmt` does, or lists why it couldn't.
+/// Code the formatting would change the meaning of is left alone.
+pub fn format_mano_code(code: &str, options: &FormatOptions) -> String {
+    match mano::format_checked(code, options) {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            let index = LineIndex::new(code);
+            let lines: Vec<String> = errors
+                .iter()
+                .map(|error| match error.locate(&index) {
+                    Some(location) => format!("{}: {}", location, message(error)),
+                    None => message(error),
+                })
+                .collect();
+            format!("Error formatting mano:\n{}", lines.join("\n"))
+        }
+    }
+}
+
+/// A problem `lint_mano_code` found. Lines and byte columns count from 1,
+/// like the locations in the CLI's error reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lint {
+    /// `error`, `warning`, or `hint` for code that never runs
+    pub severity: &'static str,
+    pub message: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+/// Everything the editor would point out in mano code without running it:
+/// scan and parse errors, the resolver's checks, redeclarations, odd names
+/// and branches that never run, in the order they appear.
+pub fn lint_mano_code(code: &str) -> Vec<Lint> {
+    let index = LineIndex::new(code);
+    let mut errors = Vec::new();
+    let mut hints = Vec::new();
+    match mano::parse(code) {
+        Ok(statements) => {
+            errors.extend(mano::check(&statements));
+            errors.extend(mano::redeclaration_warnings(&statements));
+            hints.extend(mano::dead_branches(&statements));
+        }
+        // The resolver's checks on a half-parsed program would only add noise
+        Err(parse_errors) => errors.extend(parse_errors),
+    }
+    errors.extend(mano::identifier_warnings(code));
+
+    let mut lints: Vec<Lint> = errors
+        .iter()
+        .filter_map(|error| {
+            let severity = match error.primary() {
+                ManoError::Warning { .. } => "warning",
+                _ => "error",
+            };
+            let span = error.span()?;
+            Some(lint(severity, message(error), span, &index))
+        })
+        .chain(
+            hints
+                .iter()
+                .map(|branch| lint("hint", branch.message(), &branch.span, &index)),
+        )
+        .collect();
+    lints.sort_by_key(|lint| (lint.line, lint.column));
+    lints
+}
+
+fn lint(severity: &'static str, message: String, span: &Range<usize>, index: &LineIndex) -> Lint {
+    let start = index.line_col(span.start);
+    let end = index.line_col(span.end);
+    Lint {
+        severity,
+        message,
+        line: start.line + 1,
+        column: start.col + 1,
+        end_line: end.line + 1,
+        end_column: end.col + 1,
+    }
+}
+
+/// An error's message with its tip, without the location `Display` adds
+fn message(error: &ManoError) -> String {
+    let message = match error.primary() {
+        ManoError::Scan { message, .. }
+        | ManoError::Parse { message, .. }
+        | ManoError::Runtime { message, .. }
+        | ManoError::Resolution { message, .. }
+        | ManoError::Warning { message, .. } => message.clone(),
+        other => other.to_string(),
+    };
+    match error.help() {
+        Some(help) => format!("{}\n{}", message, help),
+        None => message,
+    }
+}
+
 const KEYWORD_REFERENCE: &str = r#"# mano Language Reference
 
 mano is a programming language where all keywords are mano slang.
@@ -60,11 +164,16 @@ mano is a programming language where all keywords are mano slang.
 | while | `segueOFluxo` | `segueOFluxo (x < 10) { x = x + 1; }` |
 | for | `seVira` | `seVira (seLiga i = 0; i < 10; i = i + 1) { salve i; }` |
 | break | `saiFora` | `saiFora;` |
+| continue | `segueReto` | `sePá (i % 2 == 0) segueReto;` |
 | and | `tamoJunto` | `firmeza tamoJunto firmeza` |
 | or | `ow` | `treta ow firmeza` |
 | function | `olhaEssaFita` | `olhaEssaFita soma(a, b) { toma a + b; }` |
 | return | `toma` | `toma x * 2;` |
 | class | `bagulho` | `bagulho Pessoa { }` |
+| enum | `opcoes` | `opcoes Cor { Vermelho, Verde }` |
+| import | `chama` | `chama "util";` |
+| try/catch | `tentaAi`/`seDeuRuim` | `tentaAi { f(); } seDeuRuim (erro) { salve erro; }` |
+| throw | `joga` | `joga "deu ruim";` |
 | this | `oCara` | `oCara.nome = "João";` |
 | super | `mestre` | `mestre.metodo();` |
 
@@ -74,6 +183,10 @@ mano is a programming language where all keywords are mano slang.
 |----------|-------------|---------|
 | `fazTeuCorre()` | Returns current time in seconds | `seLiga tempo = fazTeuCorre();` |
 | `viraTexto(x)` | Converts any value to string | `seLiga s = viraTexto(42);` |
+| `textoPraNumero(t)` | Reads a number from a string, `nadaNão` if it isn't one | `seLiga n = textoPraNumero("42");` |
+| `numeroPraTexto(n)` | Converts a number to string | `salve "idade: " + numeroPraTexto(30);` |
+| `queTipoE(x)` | The kind of value, like `"número"` or `"parada"` | `salve queTipoE([]); // lista` |
+| `leAi()` | Reads a line of input, `nadaNão` when it's over | `seLiga nome = leAi();` |
 
 ## String Interpolation
 
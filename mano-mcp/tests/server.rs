@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use mano_mcp::cli::{parse_binary_path, validate_binary};
-use mano_mcp::server::{ManoMcp, RunParams, TranslateParams};
+use mano_mcp::server::{FormatParams, LintParams, ManoMcp, RunParams, TranslateParams};
 use rmcp::{ServerHandler, handler::server::wrapper::Parameters};
 
 const MANO_BIN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/debug/mano");
@@ -99,6 +99,57 @@ fn translate_to_mano_includes_examples() {
     assert!(result.contains("seVira"));
 }
 
+// mano.format tests
+
+#[test]
+fn format_mano_indents_blocks() {
+    let result = server().format_mano(Parameters(FormatParams {
+        code: "olhaEssaFita f(a) {\ntoma a;\n}".into(),
+        indent_width: Some(2),
+        use_tabs: None,
+    }));
+    assert_eq!(result, "olhaEssaFita f(a) {\n  toma a;\n}\n");
+}
+
+#[test]
+fn format_mano_returns_errors_with_their_location() {
+    let result = server().format_mano(Parameters(FormatParams {
+        code: "seLiga x = ;".into(),
+        indent_width: None,
+        use_tabs: None,
+    }));
+    assert!(result.starts_with("Error formatting mano:\n1:12: "));
+}
+
+// mano.lint tests
+
+#[test]
+fn lint_mano_returns_problems_as_json() {
+    let result = server().lint_mano(Parameters(LintParams {
+        code: "olhaEssaFita f(a) {\n  seLiga b = 1;\n  sePá (treta) salve a;\n}".into(),
+    }));
+    let lints: serde_json::Value = serde_json::from_str(&result).unwrap();
+    let lints = lints.as_array().unwrap();
+
+    assert_eq!(lints.len(), 2);
+    assert_eq!(lints[0]["severity"], "error");
+    assert!(lints[0]["message"].as_str().unwrap().contains("'b'"));
+    assert_eq!(lints[0]["line"], 2);
+    assert_eq!(lints[0]["column"], 10);
+    assert_eq!(lints[1]["severity"], "hint");
+    assert_eq!(lints[1]["line"], 3);
+    // Byte columns, and the á in sePá takes two
+    assert_eq!(lints[1]["column"], 17);
+}
+
+#[test]
+fn lint_mano_returns_an_empty_list_for_clean_code() {
+    let result = server().lint_mano(Parameters(LintParams {
+        code: "seLiga x = 1; salve x;".into(),
+    }));
+    assert_eq!(result, "[]");
+}
+
 // get_info tests
 
 #[test]
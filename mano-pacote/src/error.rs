@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PacoteError {
+    #[error("Pô, véi! Deu ruim mexendo nos arquivos: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Esse {file} tá zoado, mano: {message}")]
+    Invalid { file: &'static str, message: String },
+
+    #[error("Deu ruim no git: {0}")]
+    Git(String),
+}
+
+impl PacoteError {
+    pub fn invalid(file: &'static str, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            file,
+            message: message.into(),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Fetching packages into `.mano_modules/<nome>`
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::PacoteError;
+use crate::lock::{Locked, Lockfile, source_id};
+use crate::manifest::{Dependency, Source};
+
+pub const MODULES_DIR: &str = ".mano_modules";
+
+/// Installs every dependency of the project in `dir`, git ones at their
+/// locked commit when there is one, and returns the new lockfile
+pub fn install(
+    dir: &Path,
+    dependencies: &[Dependency],
+    lockfile: &Lockfile,
+) -> Result<Lockfile, PacoteError> {
+    let modules = dir.join(MODULES_DIR);
+    fs::create_dir_all(&modules)?;
+    let mut packages = Vec::new();
+    for dependency in dependencies {
+        let target = modules.join(&dependency.name);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        let rev = match &dependency.source {
+            Source::Path(path) => {
+                copy_dir(&dir.join(path), &target)?;
+                None
+            }
+            Source::Git { url, rev } => {
+                let rev = lockfile.rev_for(dependency).or(rev.as_deref());
+                Some(clone(url, rev, &target)?)
+            }
+        };
+        packages.push(Locked {
+            name: dependency.name.clone(),
+            source: source_id(&dependency.source),
+            rev,
+        });
+    }
+    Ok(Lockfile { packages })
+}
+
+/// Copies a package directory, leaving out its git data and its own packages
+fn copy_dir(from: &Path, to: &Path) -> Result<(), PacoteError> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" || name == MODULES_DIR {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(&name))?;
+        } else {
+            fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Clones `url` into `target` at `rev`, returning the commit it ended up at
+fn clone(url: &str, rev: Option<&str>, target: &Path) -> Result<String, PacoteError> {
+    // Past `--` git takes the URL as one even when it starts with `-`, but
+    // checkout has no such thing for revisions
+    if let Some(rev) = rev.filter(|rev| rev.starts_with('-')) {
+        return Err(PacoteError::Git(format!("'{}' não é revisão, mano", rev)));
+    }
+    git(Command::new("git")
+        .args(["clone", "--quiet", "--", url])
+        .arg(target))?;
+    if let Some(rev) = rev {
+        git(Command::new("git")
+            .arg("-C")
+            .arg(target)
+            .args(["checkout", "--quiet", rev]))?;
+    }
+    let commit = git(Command::new("git")
+        .arg("-C")
+        .arg(target)
+        .args(["rev-parse", "HEAD"]))?;
+    Ok(commit.trim().to_string())
+}
+
+/// Runs a git command, returning its output or what it complained about
+fn git(command: &mut Command) -> Result<String, PacoteError> {
+    let output = command
+        .output()
+        .map_err(|e| PacoteError::Git(format!("não rolou rodar o git: {}", e)))?;
+    if !output.status.success() {
+        return Err(PacoteError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,160 @@
+//! `mano.lock` - the exact commit each git package was installed at, so
+//! installing again gets the same code
+
+use std::fs;
+use std::path::Path;
+
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::error::PacoteError;
+use crate::manifest::{Dependency, Source};
+
+pub const LOCKFILE: &str = "mano.lock";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locked {
+    pub name: String,
+    /// `path+<dir>`, `git+<url>` or `git+<url>?rev=<rev>`
+    pub source: String,
+    /// The commit, for git packages
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Lockfile {
+    pub packages: Vec<Locked>,
+}
+
+/// How a source is written in the lockfile. The requested rev is part of it,
+/// so changing it in the manifest installs again instead of using the lock.
+pub fn source_id(source: &Source) -> String {
+    match source {
+        Source::Path(path) => format!("path+{}", path.display()),
+        Source::Git { url, rev: None } => format!("git+{}", url),
+        Source::Git {
+            url,
+            rev: Some(rev),
+        } => format!("git+{}?rev={}", url, rev),
+    }
+}
+
+impl Lockfile {
+    /// Reads the lockfile in `dir`, or an empty one if there's none
+    pub fn load(dir: &Path) -> Result<Self, PacoteError> {
+        let path = dir.join(LOCKFILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, PacoteError> {
+        let document = text
+            .parse::<DocumentMut>()
+            .map_err(|e| PacoteError::invalid(LOCKFILE, e.message()))?;
+        let Some(packages) = document.get("package") else {
+            return Ok(Self::default());
+        };
+        let packages = packages
+            .as_array_of_tables()
+            .ok_or_else(|| PacoteError::invalid(LOCKFILE, "[[package]] tem que ser lista"))?;
+        let packages = packages
+            .iter()
+            .map(|table| {
+                let string = |key| table.get(key).and_then(|item| item.as_str());
+                match (string("name"), string("source")) {
+                    (Some(name), Some(source)) => Ok(Locked {
+                        name: name.to_string(),
+                        source: source.to_string(),
+                        rev: string("rev").map(str::to_string),
+                    }),
+                    _ => Err(PacoteError::invalid(
+                        LOCKFILE,
+                        "todo pacote precisa de `name` e `source`",
+                    )),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { packages })
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), PacoteError> {
+        fs::write(dir.join(LOCKFILE), self.to_string())?;
+        Ok(())
+    }
+
+    /// The commit a dependency was locked at, if it still comes from the same place
+    pub fn rev_for(&self, dependency: &Dependency) -> Option<&str> {
+        let source = source_id(&dependency.source);
+        self.packages
+            .iter()
+            .find(|locked| locked.name == dependency.name && locked.source == source)
+            .and_then(|locked| locked.rev.as_deref())
+    }
+}
+
+impl std::fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut packages = ArrayOfTables::new();
+        for locked in &self.packages {
+            let mut table = Table::new();
+            table.insert("name", locked.name.as_str().into());
+            table.insert("source", locked.source.as_str().into());
+            if let Some(rev) = &locked.rev {
+                table.insert("rev", rev.as_str().into());
+            }
+            packages.push(table);
+        }
+        let mut document = DocumentMut::new();
+        document.insert("package", Item::ArrayOfTables(packages));
+        write!(
+            f,
+            "# Gerado pelo `mano pacote`, não mexe na mão\n\n{}",
+            document
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let lockfile = Lockfile {
+            packages: vec![
+                Locked {
+                    name: "fitas".to_string(),
+                    source: "git+https://exemplo/fitas".to_string(),
+                    rev: Some("abc123".to_string()),
+                },
+                Locked {
+                    name: "utils".to_string(),
+                    source: "path+../utils".to_string(),
+                    rev: None,
+                },
+            ],
+        };
+        assert_eq!(Lockfile::parse(&lockfile.to_string()).unwrap(), lockfile);
+    }
+
+    #[test]
+    fn rev_is_only_kept_for_the_same_source() {
+        let lockfile = Lockfile::parse(
+            "[[package]]\nname = \"fitas\"\nsource = \"git+https://exemplo/fitas\"\nrev = \"abc123\"\n",
+        )
+        .unwrap();
+        let dependency = |url: &str| Dependency {
+            name: "fitas".to_string(),
+            source: Source::Git {
+                url: url.to_string(),
+                rev: None,
+            },
+        };
+        assert_eq!(
+            lockfile.rev_for(&dependency("https://exemplo/fitas")),
+            Some("abc123")
+        );
+        assert_eq!(lockfile.rev_for(&dependency("https://outro/fitas")), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! `mano pacote` - a minimal package manager, run by mano as a plugin
+
+mod error;
+mod install;
+mod lock;
+mod manifest;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use error::PacoteError;
+use lock::Lockfile;
+use manifest::{Dependency, Manifest, Source};
+
+#[derive(Parser)]
+#[command(name = "mano pacote", bin_name = "mano pacote")]
+#[command(about = "Gerencia os pacotes de um projeto mano", long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add a package to mano.toml and install everything
+    Add {
+        name: String,
+
+        /// Directory of the package, relative to the project
+        #[arg(long, required_unless_present = "git", conflicts_with = "git")]
+        path: Option<PathBuf>,
+
+        /// Git repository of the package
+        #[arg(long)]
+        git: Option<String>,
+
+        /// Commit, tag or branch to use from the repository
+        #[arg(long, requires = "git")]
+        rev: Option<String>,
+    },
+
+    /// Install the packages in mano.toml, at the versions in mano.lock
+    Instala,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let dir = Path::new(".");
+    let result = match args.command {
+        Command::Add {
+            name,
+            path,
+            git,
+            rev,
+        } => {
+            let source = match (path, git) {
+                (Some(path), _) => Source::Path(path),
+                (None, Some(url)) => Source::Git { url, rev },
+                (None, None) => unreachable!("clap requires --path or --git"),
+            };
+            add(dir, Dependency { name, source })
+        }
+        Command::Instala => install(dir),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(65)
+        }
+    }
+}
+
+fn add(dir: &Path, dependency: Dependency) -> Result<(), PacoteError> {
+    manifest::check_name(&dependency.name)?;
+    let mut manifest = Manifest::load(dir)?;
+    manifest.add(&dependency);
+    manifest.save(dir)?;
+    install(dir)
+}
+
+fn install(dir: &Path) -> Result<(), PacoteError> {
+    let dependencies = Manifest::load(dir)?.dependencies()?;
+    let lockfile = install::install(dir, &dependencies, &Lockfile::load(dir)?)?;
+    lockfile.save(dir)?;
+    for package in &lockfile.packages {
+        println!("Instalado: {}", package.name);
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! `mano.toml` - the packages a project depends on
+//!
+//! ```toml
+//! [dependencies]
+//! utils = { path = "../utils" }
+//! fitas = { git = "https://github.com/fulano/fitas", rev = "v1" }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::error::PacoteError;
+
+pub const MANIFEST: &str = "mano.toml";
+
+/// Where a package comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// A directory, relative to the project
+    Path(PathBuf),
+    /// A git repository, at `rev` (a commit, tag or branch) or its default branch
+    Git { url: String, rev: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub source: Source,
+}
+
+/// The parsed manifest, kept as a document so edits keep the user's formatting
+pub struct Manifest {
+    document: DocumentMut,
+}
+
+/// Package names become directory names, so only plain ones are allowed
+pub fn check_name(name: &str) -> Result<(), PacoteError> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if name.is_empty() || !name.chars().all(plain) {
+        return Err(PacoteError::invalid(
+            MANIFEST,
+            format!("'{}' não é nome de pacote, só letra, número, _ e -", name),
+        ));
+    }
+    Ok(())
+}
+
+impl Manifest {
+    /// Reads the manifest in `dir`, or starts an empty one if there's none
+    pub fn load(dir: &Path) -> Result<Self, PacoteError> {
+        let path = dir.join(MANIFEST);
+        let text = if path.exists() {
+            fs::read_to_string(path)?
+        } else {
+            String::new()
+        };
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, PacoteError> {
+        let document = text
+            .parse::<DocumentMut>()
+            .map_err(|e| PacoteError::invalid(MANIFEST, e.message()))?;
+        Ok(Self { document })
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), PacoteError> {
+        fs::write(dir.join(MANIFEST), self.document.to_string())?;
+        Ok(())
+    }
+
+    pub fn dependencies(&self) -> Result<Vec<Dependency>, PacoteError> {
+        let Some(table) = self.document.get("dependencies") else {
+            return Ok(Vec::new());
+        };
+        let table = table
+            .as_table_like()
+            .ok_or_else(|| PacoteError::invalid(MANIFEST, "[dependencies] tem que ser tabela"))?;
+        table
+            .iter()
+            .map(|(name, item)| {
+                check_name(name)?;
+                let source = source(item).ok_or_else(|| {
+                    PacoteError::invalid(MANIFEST, format!("'{}' precisa de `path` ou `git`", name))
+                })?;
+                Ok(Dependency {
+                    name: name.to_string(),
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Adds a dependency, replacing one with the same name
+    pub fn add(&mut self, dependency: &Dependency) {
+        let mut entry = InlineTable::new();
+        match &dependency.source {
+            Source::Path(path) => {
+                entry.insert("path", path.to_string_lossy().as_ref().into());
+            }
+            Source::Git { url, rev } => {
+                entry.insert("git", url.as_str().into());
+                if let Some(rev) = rev {
+                    entry.insert("rev", rev.as_str().into());
+                }
+            }
+        }
+        let table = self
+            .document
+            .entry("dependencies")
+            .or_insert_with(|| Item::Table(Table::new()));
+        if let Some(table) = table.as_table_like_mut() {
+            table.insert(&dependency.name, Item::Value(Value::InlineTable(entry)));
+        }
+    }
+}
+
+fn source(item: &Item) -> Option<Source> {
+    let table = item.as_table_like()?;
+    let string = |key| table.get(key).and_then(|value| value.as_str());
+    match (string("path"), string("git")) {
+        (Some(path), None) => Some(Source::Path(PathBuf::from(path))),
+        (None, Some(url)) => Some(Source::Git {
+            url: url.to_string(),
+            rev: string("rev").map(str::to_string),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_path_and_git_dependencies() {
+        let manifest = Manifest::parse(
+            "[dependencies]\nutils = { path = \"../utils\" }\n\n[dependencies.fitas]\ngit = \"https://exemplo/fitas\"\nrev = \"v1\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.dependencies().unwrap(),
+            vec![
+                Dependency {
+                    name: "utils".to_string(),
+                    source: Source::Path(PathBuf::from("../utils")),
+                },
+                Dependency {
+                    name: "fitas".to_string(),
+                    source: Source::Git {
+                        url: "https://exemplo/fitas".to_string(),
+                        rev: Some("v1".to_string()),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_dependencies_without_a_source() {
+        let manifest = Manifest::parse("[dependencies]\nutils = { versao = \"1\" }\n").unwrap();
+        assert!(manifest.dependencies().is_err());
+        let manifest = Manifest::parse("[dependencies]\n\"../fora\" = { path = \"x\" }\n").unwrap();
+        assert!(manifest.dependencies().is_err());
+    }
+
+    #[test]
+    fn add_keeps_the_rest_of_the_file() {
+        let mut manifest = Manifest::parse("# meu projeto\nnome = \"app\"\n").unwrap();
+        manifest.add(&Dependency {
+            name: "utils".to_string(),
+            source: Source::Path(PathBuf::from("../utils")),
+        });
+        assert_eq!(
+            manifest.document.to_string(),
+            "# meu projeto\nnome = \"app\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+
+fn pacote(dir: &Path) -> Command {
+    let mut command = Command::new(assert_cmd::cargo::cargo_bin!("mano-pacote"));
+    command.current_dir(dir);
+    command
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=mano", "-c", "user.email=mano@exemplo"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// A git repository with `fitas.mano` saying `versao`
+fn repository(dir: &Path, versao: &str) {
+    fs::create_dir_all(dir).unwrap();
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet"]);
+    }
+    fs::write(dir.join("fitas.mano"), format!("salve \"{}\";", versao)).unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "--quiet", "-m", versao]);
+}
+
+#[test]
+fn add_path_package_copies_it_and_records_it() {
+    let root = tempfile::tempdir().unwrap();
+    let utils = root.path().join("utils");
+    fs::create_dir_all(utils.join("sub")).unwrap();
+    fs::write(utils.join("sub/oi.mano"), "salve \"oi\";").unwrap();
+    let app = root.path().join("app");
+    fs::create_dir(&app).unwrap();
+
+    pacote(&app)
+        .args(["add", "utils", "--path", "../utils"])
+        .assert()
+        .success()
+        .stdout("Instalado: utils\n");
+
+    assert_eq!(
+        fs::read_to_string(app.join("mano.toml")).unwrap(),
+        "[dependencies]\nutils = { path = \"../utils\" }\n"
+    );
+    assert!(app.join(".mano_modules/utils/sub/oi.mano").exists());
+    let lock = fs::read_to_string(app.join("mano.lock")).unwrap();
+    assert!(lock.contains("source = \"path+../utils\""), "{}", lock);
+}
+
+#[test]
+fn git_packages_stay_at_the_locked_commit() {
+    let root = tempfile::tempdir().unwrap();
+    let upstream = root.path().join("fitas");
+    repository(&upstream, "v1");
+    let locked = git(&upstream, &["rev-parse", "HEAD"]);
+    let app = root.path().join("app");
+    fs::create_dir(&app).unwrap();
+
+    let url = upstream.to_str().unwrap();
+    pacote(&app)
+        .args(["add", "fitas", "--git", url])
+        .assert()
+        .success();
+    let lock = fs::read_to_string(app.join("mano.lock")).unwrap();
+    assert!(lock.contains(&format!("rev = \"{}\"", locked)), "{}", lock);
+
+    // A new upstream commit doesn't change what gets installed
+    repository(&upstream, "v2");
+    pacote(&app).arg("instala").assert().success();
+    assert_eq!(
+        fs::read_to_string(app.join(".mano_modules/fitas/fitas.mano")).unwrap(),
+        "salve \"v1\";"
+    );
+}
+
+#[test]
+fn git_urls_and_revs_are_never_options() {
+    let root = tempfile::tempdir().unwrap();
+    repository(&root.path().join("-fitas"), "v1");
+
+    pacote(root.path())
+        .args(["add", "fitas", "--git=-fitas"])
+        .assert()
+        .success();
+    assert!(root.path().join(".mano_modules/fitas/fitas.mano").exists());
+
+    pacote(root.path())
+        .args(["add", "fitas", "--git", ".", "--rev=--orphan=x"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("não é revisão"));
+}
+
+#[test]
+fn add_rejects_names_that_are_not_directories() {
+    let app = tempfile::tempdir().unwrap();
+    pacote(app.path())
+        .args(["add", "../fora", "--path", "x"])
+        .assert()
+        .code(65)
+        .stderr(predicates::str::contains("não é nome de pacote"));
+    assert!(!app.path().join("mano.toml").exists());
+}
+
+#[test]
+fn add_needs_a_source() {
+    let app = tempfile::tempdir().unwrap();
+    pacote(app.path()).args(["add", "utils"]).assert().code(2);
+}
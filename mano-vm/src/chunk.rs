@@ -45,6 +45,27 @@ impl Chunk {
         panic!("Offset {} out of bounds", offset);
     }
 
+    /// Drop code from `len` onwards, keeping spans in sync.
+    pub fn truncate(&mut self, len: usize) {
+        self.code.truncate(len);
+        let mut remaining = len;
+        let mut keep = 0;
+        for (_, count) in self.spans.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            *count = (*count).min(remaining);
+            remaining -= *count;
+            keep += 1;
+        }
+        self.spans.truncate(keep);
+    }
+
+    /// Values the chunk's constant instructions refer to, by index.
+    pub fn constants(&self) -> &[Value] {
+        &self.constants
+    }
+
     pub fn add_constant(&mut self, value: Value) -> usize {
         self.constants.push(value);
         self.constants.len() - 1
@@ -87,23 +108,23 @@ mod tests {
     #[test]
     fn chunk_add_constant_returns_index() {
         let mut chunk = Chunk::new();
-        let idx = chunk.add_constant(1.2);
+        let idx = chunk.add_constant(Value::Number(1.2));
         assert_eq!(idx, 0);
     }
 
     #[test]
     fn chunk_add_constant_stores_value() {
         let mut chunk = Chunk::new();
-        chunk.add_constant(1.2);
-        assert_eq!(chunk.constants[0], 1.2);
+        chunk.add_constant(Value::Number(1.2));
+        assert_eq!(chunk.constants[0], Value::Number(1.2));
     }
 
     #[test]
     fn chunk_add_multiple_constants() {
         let mut chunk = Chunk::new();
-        assert_eq!(chunk.add_constant(1.0), 0);
-        assert_eq!(chunk.add_constant(2.0), 1);
-        assert_eq!(chunk.add_constant(3.0), 2);
+        assert_eq!(chunk.add_constant(Value::Number(1.0)), 0);
+        assert_eq!(chunk.add_constant(Value::Number(2.0)), 1);
+        assert_eq!(chunk.add_constant(Value::Number(3.0)), 2);
     }
 
     #[test]
@@ -174,11 +195,33 @@ mod tests {
         chunk.get_span(5); // only 2 bytes written
     }
 
+    #[test]
+    fn chunk_truncate_trims_code_and_spans() {
+        let mut chunk = Chunk::new();
+        chunk.write(0x00, 0..5);
+        chunk.write(0x01, 0..5);
+        chunk.write(0x02, 10..15);
+        chunk.truncate(1);
+        assert_eq!(chunk.code, vec![0x00]);
+        assert_eq!(chunk.spans, vec![(0..5, 1)]);
+    }
+
+    #[test]
+    fn chunk_truncate_at_run_boundary_drops_later_runs() {
+        let mut chunk = Chunk::new();
+        chunk.write(0x00, 0..5);
+        chunk.write(0x01, 10..15);
+        chunk.truncate(1);
+        assert_eq!(chunk.spans, vec![(0..5, 1)]);
+        chunk.write(0x02, 20..25);
+        assert_eq!(chunk.get_span(1), 20..25);
+    }
+
     #[test]
     fn chunk_write_constant_uses_short_opcode() {
         use crate::OpCode;
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..5);
+        chunk.write_constant(Value::Number(1.2), 0..5);
         assert_eq!(chunk.code[0], OpCode::Constant as u8);
         assert_eq!(chunk.code[1], 0); // index 0
     }
@@ -189,10 +232,10 @@ mod tests {
         let mut chunk = Chunk::new();
         // Add 256 constants to fill up the short range
         for i in 0..256 {
-            chunk.add_constant(i as f64);
+            chunk.add_constant(Value::Number(i as f64));
         }
         // Now write constant 256 - should use long opcode
-        chunk.write_constant(256.0, 0..5);
+        chunk.write_constant(Value::Number(256.0), 0..5);
         assert_eq!(chunk.code[0], OpCode::ConstantLong as u8);
         // 24-bit little-endian: 256 = 0x000100
         assert_eq!(chunk.code[1], 0); // low byte
@@ -1,11 +1,50 @@
 //! Compiler - compiles source code to bytecode
 
-use mano::{ManoError, Scanner, Token, TokenType};
+use mano::{Conditions, Dialect, ManoError, Scanner, Token, TokenType};
 
 use crate::Chunk;
+use crate::value::Value;
+
+/// How hard the compiler tries to simplify the code it emits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    /// Emit code exactly as written.
+    #[default]
+    None,
+    /// Drop branches that can never run.
+    Basic,
+}
+
+/// Options that change how source is compiled.
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    pub opt_level: OptLevel,
+    /// Treat warnings as errors.
+    pub strict: bool,
+    /// Keyword aliases to accept, unless the source has a `// dialeto:` pragma.
+    pub dialect: Dialect,
+    /// Flags that turn on `#se NOME` blocks.
+    pub defines: Vec<String>,
+}
+
+/// A successfully compiled chunk plus any warnings found along the way.
+#[derive(Debug)]
+pub struct CompileOutput {
+    pub chunk: Chunk,
+    pub warnings: Vec<ManoError>,
+}
 
 /// Result type for compilation.
-pub type CompileResult = Result<Chunk, Vec<ManoError>>;
+pub type CompileResult = Result<CompileOutput, Vec<ManoError>>;
+
+/// A literal that was just compiled, so the next operator can tell whether
+/// it's working on a constant.
+#[derive(Debug, Clone, Copy)]
+struct LastLiteral {
+    start: usize,
+    end: usize,
+    is_falsey: bool,
+}
 
 /// Precedence levels from lowest to highest.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -14,10 +53,16 @@ pub type CompileResult = Result<Chunk, Vec<ManoError>>;
 enum Precedence {
     None,
     Assignment, // =
+    Comma,      // ,
+    Ternary,    // ?:
     Or,         // ow
     And,        // tamoJunto
     Equality,   // == !=
     Comparison, // < > <= >=
+    BitOr,      // |
+    BitXor,     // ^
+    BitAnd,     // &
+    Shift,      // << >>
     Term,       // + -
     Factor,     // * / %
     Unary,      // ! -
@@ -29,11 +74,17 @@ impl Precedence {
     fn next(self) -> Self {
         match self {
             Self::None => Self::Assignment,
-            Self::Assignment => Self::Or,
+            Self::Assignment => Self::Comma,
+            Self::Comma => Self::Ternary,
+            Self::Ternary => Self::Or,
             Self::Or => Self::And,
             Self::And => Self::Equality,
             Self::Equality => Self::Comparison,
-            Self::Comparison => Self::Term,
+            Self::Comparison => Self::BitOr,
+            Self::BitOr => Self::BitXor,
+            Self::BitXor => Self::BitAnd,
+            Self::BitAnd => Self::Shift,
+            Self::Shift => Self::Term,
             Self::Term => Self::Factor,
             Self::Factor => Self::Unary,
             Self::Unary => Self::Call,
@@ -50,7 +101,14 @@ enum ParseFn {
     Unary,
     Binary,
     Number,
+    String,
+    Interpolation,
+    Literal,
     Ternary,
+    Comma,
+    And,
+    Or,
+    List,
 }
 
 impl ParseFn {
@@ -60,7 +118,14 @@ impl ParseFn {
             Self::Unary => compiler.unary(),
             Self::Binary => compiler.binary(),
             Self::Number => compiler.number(),
+            Self::String => compiler.string(),
+            Self::Interpolation => compiler.interpolation(),
+            Self::Literal => compiler.literal(),
             Self::Ternary => compiler.ternary(),
+            Self::Comma => compiler.comma(),
+            Self::And => compiler.and(),
+            Self::Or => compiler.or(),
+            Self::List => compiler.list(),
         }
     }
 }
@@ -81,6 +146,12 @@ impl From<TokenType> for ParseRule {
                 infix: None,
                 precedence: Precedence::None,
             },
+            // Literals and indexing, both only so they get a clear error
+            TokenType::LeftBracket => Self {
+                prefix: Some(ParseFn::List),
+                infix: Some(ParseFn::List),
+                precedence: Precedence::Call,
+            },
             TokenType::Minus => Self {
                 prefix: Some(ParseFn::Unary),
                 infix: Some(ParseFn::Binary),
@@ -106,16 +177,84 @@ impl From<TokenType> for ParseRule {
                 infix: Some(ParseFn::Binary),
                 precedence: Precedence::Factor,
             },
+            TokenType::Pipe => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::BitOr,
+            },
+            TokenType::Caret => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::BitXor,
+            },
+            TokenType::Ampersand => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::BitAnd,
+            },
+            TokenType::LessLess | TokenType::GreaterGreater => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::Shift,
+            },
+            TokenType::Bang => Self {
+                prefix: Some(ParseFn::Unary),
+                infix: None,
+                precedence: Precedence::None,
+            },
+            TokenType::BangEqual | TokenType::EqualEqual => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::Equality,
+            },
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => Self {
+                prefix: None,
+                infix: Some(ParseFn::Binary),
+                precedence: Precedence::Comparison,
+            },
+            TokenType::True | TokenType::False | TokenType::Nil => Self {
+                prefix: Some(ParseFn::Literal),
+                infix: None,
+                precedence: Precedence::None,
+            },
             TokenType::Question => Self {
                 prefix: None,
                 infix: Some(ParseFn::Ternary),
-                precedence: Precedence::Assignment,
+                precedence: Precedence::Ternary,
+            },
+            TokenType::Comma => Self {
+                prefix: None,
+                infix: Some(ParseFn::Comma),
+                precedence: Precedence::Comma,
+            },
+            TokenType::And => Self {
+                prefix: None,
+                infix: Some(ParseFn::And),
+                precedence: Precedence::And,
+            },
+            TokenType::Or => Self {
+                prefix: None,
+                infix: Some(ParseFn::Or),
+                precedence: Precedence::Or,
             },
             TokenType::Number => Self {
                 prefix: Some(ParseFn::Number),
                 infix: None,
                 precedence: Precedence::None,
             },
+            TokenType::String => Self {
+                prefix: Some(ParseFn::String),
+                infix: None,
+                precedence: Precedence::None,
+            },
+            TokenType::StringStart => Self {
+                prefix: Some(ParseFn::Interpolation),
+                infix: None,
+                precedence: Precedence::None,
+            },
             _ => Self {
                 prefix: None,
                 infix: None,
@@ -125,6 +264,18 @@ impl From<TokenType> for ParseRule {
     }
 }
 
+/// A loop being compiled, for `saiFora` to jump out of and `segueReto` to
+/// jump back to the start of.
+#[derive(Debug, Default)]
+struct Loop {
+    /// Where the condition is checked
+    start: usize,
+    /// The loop's keyword, where Ctrl+C stops it
+    keyword: std::ops::Range<usize>,
+    /// `saiFora` jumps waiting for the end of the loop
+    breaks: Vec<usize>,
+}
+
 /// The compiler - holds parser state and emits bytecode.
 struct Compiler<'a> {
     scanner: Scanner<'a>,
@@ -132,14 +283,27 @@ struct Compiler<'a> {
     previous: Token,
     chunk: Chunk,
     errors: Vec<ManoError>,
+    warnings: Vec<ManoError>,
+    options: CompileOptions,
+    last_literal: Option<LastLiteral>,
+    /// Set after an error until the next statement, so a mistake isn't
+    /// reported again by everything after it
+    panic_mode: bool,
+    /// Loops around the code being compiled, innermost last
+    loops: Vec<Loop>,
 }
 
 impl<'a> Compiler<'a> {
+    #[cfg(test)]
     fn new(source: &'a str) -> Self {
-        let scanner = Scanner::new(source);
+        Self::with_options(source, CompileOptions::default())
+    }
+
+    fn with_options(source: &'a str, options: CompileOptions) -> Self {
+        let scanner = Scanner::new(source).with_dialect(options.dialect);
         let placeholder = Token {
             token_type: TokenType::Eof,
-            lexeme: String::new(),
+            lexeme: "".into(),
             literal: None,
             span: 0..0,
         };
@@ -149,13 +313,18 @@ impl<'a> Compiler<'a> {
             previous: placeholder,
             chunk: Chunk::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            options,
+            last_literal: None,
+            panic_mode: false,
+            loops: Vec::new(),
         }
     }
 
     fn advance(&mut self) {
         let eof_placeholder = Token {
             token_type: TokenType::Eof,
-            lexeme: String::new(),
+            lexeme: "".into(),
             literal: None,
             span: self.current.span.end..self.current.span.end,
         };
@@ -179,6 +348,10 @@ impl<'a> Compiler<'a> {
     }
 
     fn error_at_current(&mut self, message: &str) {
+        if self.panic_mode {
+            return;
+        }
+        self.panic_mode = true;
         self.errors.push(ManoError::Parse {
             message: message.to_string(),
             span: self.current.span.clone(),
@@ -193,21 +366,93 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    fn check(&self, token_type: TokenType) -> bool {
+        self.current.token_type == token_type
+    }
+
+    fn match_token(&mut self, token_type: TokenType) -> bool {
+        if !self.check(token_type) {
+            return false;
+        }
+        self.advance();
+        true
+    }
+
     fn emit_byte(&mut self, byte: u8) {
         let span = self.previous.span.clone();
         self.chunk.write(byte, span);
     }
 
+    fn emit_bytes(&mut self, first: crate::OpCode, second: crate::OpCode) {
+        self.emit_byte(first as u8);
+        self.emit_byte(second as u8);
+    }
+
+    /// Emits a jump with a placeholder offset, returning where to patch it
+    fn emit_jump(&mut self, op: crate::OpCode) -> usize {
+        self.emit_byte(op as u8);
+        self.emit_byte(0xff);
+        self.emit_byte(0xff);
+        self.chunk.code.len() - 2
+    }
+
+    /// Jumps back to `start`, with the span of the loop's keyword so Ctrl+C
+    /// points at the loop it stopped
+    fn emit_loop(&mut self, start: usize, keyword: std::ops::Range<usize>) {
+        self.chunk.write(crate::OpCode::Loop as u8, keyword.clone());
+        // +2 to jump back over the offset bytes too
+        let offset = self.chunk.code.len() - start + 2;
+        if offset > u16::MAX as usize {
+            self.error_at_previous("Loop grande demais, mano!");
+        }
+        self.chunk.write((offset >> 8) as u8, keyword.clone());
+        self.chunk.write(offset as u8, keyword);
+    }
+
+    fn patch_jump(&mut self, offset: usize) {
+        // -2 to account for the jump offset bytes themselves
+        let jump = self.chunk.code.len() - offset - 2;
+        if jump > u16::MAX as usize {
+            self.error_at_previous("Pulo grande demais, mano!");
+            return;
+        }
+        self.chunk.code[offset] = (jump >> 8) as u8;
+        self.chunk.code[offset + 1] = jump as u8;
+    }
+
     fn emit_return(&mut self) {
         self.emit_byte(crate::OpCode::Return as u8);
     }
 
-    fn emit_constant(&mut self, value: f64) {
+    fn emit_constant(&mut self, value: Value) {
         let span = self.previous.span.clone();
         self.chunk.write_constant(value, span);
     }
 
+    fn warning(&mut self, message: String, span: std::ops::Range<usize>) {
+        self.warnings.push(ManoError::Warning { message, span });
+    }
+
+    /// Remember that the code emitted since `start` is a single literal
+    fn mark_literal(&mut self, start: usize, is_falsey: bool) {
+        self.last_literal = Some(LastLiteral {
+            start,
+            end: self.chunk.code.len(),
+            is_falsey,
+        });
+    }
+
+    /// The literal just compiled, if it's the last thing in the chunk
+    fn trailing_literal(&self) -> Option<LastLiteral> {
+        self.last_literal
+            .filter(|literal| literal.end == self.chunk.code.len())
+    }
+
     fn error_at_previous(&mut self, message: &str) {
+        if self.panic_mode {
+            return;
+        }
+        self.panic_mode = true;
         self.errors.push(ManoError::Parse {
             message: message.to_string(),
             span: self.previous.span.clone(),
@@ -237,20 +482,170 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    /// Lists aren't in the VM yet, so `[` says so instead of asking for an
+    /// expression
+    fn list(&mut self) {
+        self.error_at_previous("Lista ainda não roda no VM, mano! Roda sem --vm.");
+    }
+
     fn expression(&mut self) {
         self.parse_precedence(Precedence::Assignment);
     }
 
+    /// Compiles a statement. At the top level, an expression without a `;`
+    /// at the end of the code is the script's result, printed like in the
+    /// REPL; returns whether that's what it was.
+    fn statement(&mut self, top_level: bool) -> bool {
+        let mut result = false;
+        if self.match_token(TokenType::Print) {
+            self.expression();
+            self.consume(TokenType::Semicolon, "Cadê o ';' depois do salve, mano?");
+            self.emit_byte(crate::OpCode::Print as u8);
+        } else if self.match_token(TokenType::If) {
+            self.if_statement();
+        } else if self.match_token(TokenType::While) {
+            self.while_statement();
+        } else if self.match_token(TokenType::Break) {
+            self.break_statement();
+        } else if self.match_token(TokenType::Continue) {
+            self.continue_statement();
+        } else if self.match_token(TokenType::LeftBrace) {
+            self.block();
+        } else {
+            self.expression();
+            if top_level && self.check(TokenType::Eof) {
+                // Returned at the end of the code, where it's printed
+                self.advance();
+                self.emit_return();
+                result = true;
+            } else {
+                self.consume(TokenType::Semicolon, "Cadê o ';' no final, chapa?");
+                self.emit_byte(crate::OpCode::Pop as u8);
+            }
+        }
+        if self.panic_mode {
+            self.synchronize();
+        }
+        result
+    }
+
+    /// Skips to where the next statement probably starts
+    fn synchronize(&mut self) {
+        self.panic_mode = false;
+        while !self.check(TokenType::Eof) {
+            if self.previous.token_type == TokenType::Semicolon {
+                return;
+            }
+            match self.current.token_type {
+                TokenType::Print
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::LeftBrace
+                | TokenType::RightBrace => return,
+                _ => self.advance(),
+            }
+        }
+    }
+
+    fn block(&mut self) {
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::Eof) {
+            self.statement(false);
+        }
+        self.consume(
+            TokenType::RightBrace,
+            "Cadê o '}' pra fechar o bloco, mano?",
+        );
+    }
+
+    fn if_statement(&mut self) {
+        self.consume(TokenType::LeftParen, "Cadê o '(' depois do sePá, mano?");
+        self.expression();
+        self.consume(TokenType::RightParen, "Cadê o ')' depois da condição, véi?");
+
+        let then_jump = self.emit_jump(crate::OpCode::JumpIfFalse);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.statement(false);
+        let else_jump = self.emit_jump(crate::OpCode::Jump);
+
+        // Both ways drop the condition
+        self.patch_jump(then_jump);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        if self.match_token(TokenType::Else) {
+            self.statement(false);
+        }
+        self.patch_jump(else_jump);
+    }
+
+    fn while_statement(&mut self) {
+        let keyword = self.previous.span.clone();
+        let start = self.chunk.code.len();
+        self.consume(
+            TokenType::LeftParen,
+            "Cadê o '(' depois do segueOFluxo, mano?",
+        );
+        self.expression();
+        self.consume(TokenType::RightParen, "Cadê o ')' depois da condição, véi?");
+
+        let exit_jump = self.emit_jump(crate::OpCode::JumpIfFalse);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.loops.push(Loop {
+            start,
+            keyword: keyword.clone(),
+            breaks: Vec::new(),
+        });
+        self.statement(false);
+        let closed = self.loops.pop().unwrap_or_default();
+        self.emit_loop(start, keyword);
+
+        self.patch_jump(exit_jump);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        // saiFora lands past the condition's pop, the stack is already empty
+        for jump in closed.breaks {
+            self.patch_jump(jump);
+        }
+    }
+
+    fn break_statement(&mut self) {
+        if self.loops.is_empty() {
+            self.error_at_previous("Não pode dar saiFora fora de um loop, mano!");
+        }
+        let jump = self.emit_jump(crate::OpCode::Jump);
+        if let Some(innermost) = self.loops.last_mut() {
+            innermost.breaks.push(jump);
+        }
+        self.consume(TokenType::Semicolon, "Cadê o ';' depois do saiFora, véi?");
+    }
+
+    fn continue_statement(&mut self) {
+        match self.loops.last() {
+            Some(innermost) => {
+                let (start, keyword) = (innermost.start, innermost.keyword.clone());
+                self.emit_loop(start, keyword);
+            }
+            None => self.error_at_previous("Não pode dar segueReto fora de um loop, mano!"),
+        }
+        self.consume(TokenType::Semicolon, "Cadê o ';' depois do segueReto, véi?");
+    }
+
     fn grouping(&mut self) {
         self.expression();
         self.consume(TokenType::RightParen, "Cadê o ')', mano?");
     }
 
     fn unary(&mut self) {
+        let operator_type = self.previous.token_type;
+
         // Parse operand at unary precedence (binds tighter than binary ops)
         self.parse_precedence(Precedence::Unary);
+
         // Then emit the operator
-        self.emit_byte(crate::OpCode::Negate as u8);
+        match operator_type {
+            TokenType::Minus => self.emit_byte(crate::OpCode::Negate as u8),
+            TokenType::Bang => self.emit_byte(crate::OpCode::Not as u8),
+            _ => unreachable!("unary() called with non-unary operator"),
+        }
     }
 
     fn binary(&mut self) {
@@ -267,44 +662,194 @@ impl<'a> Compiler<'a> {
             TokenType::Star => self.emit_byte(crate::OpCode::Multiply as u8),
             TokenType::Slash => self.emit_byte(crate::OpCode::Divide as u8),
             TokenType::Percent => self.emit_byte(crate::OpCode::Modulo as u8),
+            TokenType::EqualEqual => self.emit_byte(crate::OpCode::Equal as u8),
+            TokenType::BangEqual => self.emit_bytes(crate::OpCode::Equal, crate::OpCode::Not),
+            TokenType::Greater => self.emit_byte(crate::OpCode::Greater as u8),
+            TokenType::GreaterEqual => self.emit_byte(crate::OpCode::GreaterEqual as u8),
+            TokenType::Less => self.emit_byte(crate::OpCode::Less as u8),
+            TokenType::LessEqual => self.emit_byte(crate::OpCode::LessEqual as u8),
+            TokenType::Ampersand => self.emit_byte(crate::OpCode::BitAnd as u8),
+            TokenType::Pipe => self.emit_byte(crate::OpCode::BitOr as u8),
+            TokenType::Caret => self.emit_byte(crate::OpCode::BitXor as u8),
+            TokenType::LessLess => self.emit_byte(crate::OpCode::ShiftLeft as u8),
+            TokenType::GreaterGreater => self.emit_byte(crate::OpCode::ShiftRight as u8),
             _ => unreachable!("binary() called with non-binary operator"),
         }
     }
 
     fn number(&mut self) {
         if let Some(mano::Literal::Number(value)) = &self.previous.literal {
-            self.emit_constant(*value);
+            let start = self.chunk.code.len();
+            self.emit_constant(Value::Number(*value));
+            self.mark_literal(start, false);
         }
     }
 
+    fn string(&mut self) {
+        let start = self.chunk.code.len();
+        self.text_part();
+        self.mark_literal(start, false);
+    }
+
+    /// `"oi {nome}!"`: each expression is turned into text and added to the
+    /// text before it. Errors inside the braces point at the expression.
+    fn interpolation(&mut self) {
+        self.text_part();
+        loop {
+            self.expression();
+            self.emit_bytes(crate::OpCode::Stringify, crate::OpCode::Add);
+            if self.match_token(TokenType::StringMiddle) {
+                self.text_part();
+                self.emit_byte(crate::OpCode::Add as u8);
+                continue;
+            }
+            self.consume(
+                TokenType::StringEnd,
+                "String interpolada mal formada, mano!",
+            );
+            if self.previous.token_type == TokenType::StringEnd {
+                self.text_part();
+                self.emit_byte(crate::OpCode::Add as u8);
+            }
+            return;
+        }
+    }
+
+    /// Pushes the text of the string token just consumed
+    fn text_part(&mut self) {
+        if let Some(mano::Literal::String(text)) = &self.previous.literal {
+            let text = Value::String(text.as_str().into());
+            self.emit_constant(text);
+        }
+    }
+
+    fn literal(&mut self) {
+        let start = self.chunk.code.len();
+        match self.previous.token_type {
+            TokenType::True => self.emit_byte(crate::OpCode::True as u8),
+            TokenType::False => self.emit_byte(crate::OpCode::False as u8),
+            TokenType::Nil => self.emit_byte(crate::OpCode::Nil as u8),
+            _ => unreachable!("literal() called with non-literal token"),
+        }
+        let is_falsey = self.previous.token_type != TokenType::True;
+        self.mark_literal(start, is_falsey);
+    }
+
     fn ternary(&mut self) {
-        // Parse then branch
+        if let Some(condition) = self.trailing_literal() {
+            let always = if condition.is_falsey {
+                "treta"
+            } else {
+                "firmeza"
+            };
+            let span = self.chunk.get_span(condition.start);
+            self.warning(format!("Essa condição é sempre {}, mano!", always), span);
+            if self.options.opt_level >= OptLevel::Basic {
+                self.constant_ternary(condition);
+                return;
+            }
+        }
+
+        // Condition is already on the stack
+        let else_jump = self.emit_jump(crate::OpCode::JumpIfFalse);
+
+        // Then branch: drop the condition, evaluate the branch
+        self.emit_byte(crate::OpCode::Pop as u8);
         self.expression();
+        let end_jump = self.emit_jump(crate::OpCode::Jump);
 
-        // Consume ':'
         self.consume(TokenType::Colon, "Cadê o ':' do ternário, chapa?");
 
-        // Parse else branch (right-associative)
-        self.parse_precedence(Precedence::Assignment);
+        // Else branch (right-associative)
+        self.patch_jump(else_jump);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.parse_precedence(Precedence::Ternary);
+
+        self.patch_jump(end_jump);
+    }
+
+    /// Ternary with a literal condition: only the branch that runs is kept
+    fn constant_ternary(&mut self, condition: LastLiteral) {
+        self.chunk.truncate(condition.start);
+
+        let then_start = self.chunk.code.len();
+        self.expression();
+        if condition.is_falsey {
+            self.chunk.truncate(then_start);
+        }
+
+        self.consume(TokenType::Colon, "Cadê o ':' do ternário, chapa?");
+
+        let else_start = self.chunk.code.len();
+        self.parse_precedence(Precedence::Ternary);
+        if !condition.is_falsey {
+            self.chunk.truncate(else_start);
+        }
+    }
+
+    /// `tamoJunto`: a falsey left side is the result, the right side isn't run
+    fn and(&mut self) {
+        let end_jump = self.emit_jump(crate::OpCode::JumpIfFalse);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.parse_precedence(Precedence::And.next());
+        self.patch_jump(end_jump);
+    }
+
+    /// `ow`: a truthy left side is the result, the right side isn't run
+    fn or(&mut self) {
+        let else_jump = self.emit_jump(crate::OpCode::JumpIfFalse);
+        let end_jump = self.emit_jump(crate::OpCode::Jump);
+        self.patch_jump(else_jump);
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.parse_precedence(Precedence::Or.next());
+        self.patch_jump(end_jump);
+    }
 
-        // For now, just error - we need jump instructions to implement this
-        self.error_at_previous("Ternário ainda não suportado na VM, mano!");
+    fn comma(&mut self) {
+        // Left value is discarded, only the last one stays
+        self.emit_byte(crate::OpCode::Pop as u8);
+        self.parse_precedence(Precedence::Comma.next());
     }
 }
 
 /// Compile source code into bytecode.
 ///
-/// Returns `Ok(chunk)` on success, `Err(errors)` on failure.
-pub fn compile(source: &str) -> CompileResult {
-    let mut compiler = Compiler::new(source);
+/// The code is a list of statements, and an expression without a `;` at the
+/// very end is printed, so `1 + 2` alone prints 3.
+/// Returns the chunk and any warnings on success, `Err(errors)` on failure.
+/// In strict mode warnings are returned as errors.
+pub fn compile(source: &str, options: &CompileOptions) -> CompileResult {
+    let conditions = Conditions {
+        vm: true,
+        defines: options.defines.clone(),
+    };
+    let source = conditions.select(source)?;
+    let mut compiler = Compiler::with_options(&source, options.clone());
 
     compiler.advance();
-    compiler.expression();
-    compiler.consume(TokenType::Eof, "Expect end of expression.");
-    compiler.emit_return();
+    if compiler.check(TokenType::Eof) {
+        compiler.error_at_current("Cadê a expressão, jão?");
+    }
+    while !compiler.check(TokenType::Eof) {
+        if compiler.statement(true) {
+            break;
+        }
+    }
+
+    if compiler.options.strict {
+        compiler.errors.append(&mut compiler.warnings);
+    }
 
     if compiler.errors.is_empty() {
-        Ok(compiler.chunk)
+        // Codegen bugs, not user errors - only checked in debug builds
+        #[cfg(debug_assertions)]
+        if let Err(error) = crate::verify_stack(&compiler.chunk) {
+            panic!("Compiler emitted an unbalanced chunk at {}", error);
+        }
+        Ok(CompileOutput {
+            chunk: compiler.chunk,
+            warnings: compiler.warnings,
+        })
     } else {
         Err(compiler.errors)
     }
@@ -367,7 +912,7 @@ mod tests {
         compiler.advance(); // current = 42
         compiler.advance(); // skips @, current = 3
         assert_eq!(compiler.current.token_type, TokenType::Number);
-        assert_eq!(compiler.current.lexeme, "3");
+        assert_eq!(&*compiler.current.lexeme, "3");
     }
 
     // 17.2.1 - error reporting (error_at_current used by consume in 17.2.2)
@@ -424,7 +969,7 @@ mod tests {
     // compile() integration
     #[test]
     fn compile_empty_source_errors() {
-        match compile("") {
+        match compile("", &CompileOptions::default()) {
             Err(errors) => match &errors[0] {
                 ManoError::Parse { message, span } => {
                     assert!(message.contains("expressão"));
@@ -438,32 +983,32 @@ mod tests {
 
     #[test]
     fn compile_emits_return_at_end() {
-        let chunk = compile("42").unwrap();
+        let chunk = compile("42", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(chunk.code.last(), Some(&(crate::OpCode::Return as u8)));
     }
 
     #[test]
     fn compile_number_emits_constant() {
-        let chunk = compile("42").unwrap();
+        let chunk = compile("42", &CompileOptions::default()).unwrap().chunk;
         // OP_CONSTANT, index, OP_RETURN
         assert_eq!(chunk.code.len(), 3);
         assert_eq!(chunk.code[0], crate::OpCode::Constant as u8);
         assert_eq!(chunk.code[1], 0); // constant index
         assert_eq!(chunk.code[2], crate::OpCode::Return as u8);
-        assert_eq!(chunk.constants[0], 42.0);
+        assert_eq!(chunk.constants[0], Value::Number(42.0));
     }
 
     #[test]
     fn compile_scanner_error_returns_err() {
-        let result = compile("@");
+        let result = compile("@", &CompileOptions::default());
         assert!(result.is_err());
     }
 
     // 17.4.2 - Parentheses for grouping
     #[test]
     fn grouping_compiles_inner_expression() {
-        let grouped = compile("(42)").unwrap();
-        let bare = compile("42").unwrap();
+        let grouped = compile("(42)", &CompileOptions::default()).unwrap().chunk;
+        let bare = compile("42", &CompileOptions::default()).unwrap().chunk;
         // Grouping is purely syntactic - same bytecode as bare number
         assert_eq!(grouped.code, bare.code);
         assert_eq!(grouped.constants, bare.constants);
@@ -471,14 +1016,14 @@ mod tests {
 
     #[test]
     fn grouping_nested() {
-        let nested = compile("((42))").unwrap();
-        let bare = compile("42").unwrap();
+        let nested = compile("((42))", &CompileOptions::default()).unwrap().chunk;
+        let bare = compile("42", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(nested.code, bare.code);
     }
 
     #[test]
     fn grouping_missing_right_paren_errors() {
-        match compile("(42") {
+        match compile("(42", &CompileOptions::default()) {
             Err(errors) => match &errors[0] {
                 ManoError::Parse { message, span } => {
                     assert!(message.contains("')'"), "error should mention ')'");
@@ -492,7 +1037,7 @@ mod tests {
 
     #[test]
     fn grouping_empty_errors() {
-        match compile("()") {
+        match compile("()", &CompileOptions::default()) {
             Err(errors) => match &errors[0] {
                 ManoError::Parse { message, span } => {
                     assert!(
@@ -510,7 +1055,7 @@ mod tests {
     // 17.4.3 - Unary negation
     #[test]
     fn unary_negation_emits_negate() {
-        let chunk = compile("-42").unwrap();
+        let chunk = compile("-42", &CompileOptions::default()).unwrap().chunk;
         // OP_CONSTANT, index, OP_NEGATE, OP_RETURN
         assert_eq!(chunk.code.len(), 4);
         assert_eq!(chunk.code[0], crate::OpCode::Constant as u8);
@@ -521,7 +1066,7 @@ mod tests {
 
     #[test]
     fn unary_nested_double_negation() {
-        let chunk = compile("--42").unwrap();
+        let chunk = compile("--42", &CompileOptions::default()).unwrap().chunk;
         // OP_CONSTANT, index, OP_NEGATE, OP_NEGATE, OP_RETURN
         assert_eq!(chunk.code.len(), 5);
         assert_eq!(chunk.code[2], crate::OpCode::Negate as u8);
@@ -530,7 +1075,7 @@ mod tests {
 
     #[test]
     fn unary_with_grouping() {
-        let chunk = compile("-(42)").unwrap();
+        let chunk = compile("-(42)", &CompileOptions::default()).unwrap().chunk;
         // OP_CONSTANT, index, OP_NEGATE, OP_RETURN
         assert_eq!(chunk.code.len(), 4);
         assert_eq!(chunk.code[2], crate::OpCode::Negate as u8);
@@ -539,7 +1084,7 @@ mod tests {
     // 17.5/17.6 - Parsing Infix Expressions
     #[test]
     fn binary_addition() {
-        let chunk = compile("1 + 2").unwrap();
+        let chunk = compile("1 + 2", &CompileOptions::default()).unwrap().chunk;
         // OP_CONSTANT 0, OP_CONSTANT 1, OP_ADD, OP_RETURN
         assert_eq!(chunk.code.len(), 6);
         assert_eq!(chunk.code[0], crate::OpCode::Constant as u8);
@@ -550,50 +1095,151 @@ mod tests {
 
     #[test]
     fn binary_subtraction() {
-        let chunk = compile("5 - 3").unwrap();
+        let chunk = compile("5 - 3", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(chunk.code[4], crate::OpCode::Subtract as u8);
     }
 
     #[test]
     fn binary_multiplication() {
-        let chunk = compile("2 * 3").unwrap();
+        let chunk = compile("2 * 3", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(chunk.code[4], crate::OpCode::Multiply as u8);
     }
 
     #[test]
     fn binary_division() {
-        let chunk = compile("6 / 2").unwrap();
+        let chunk = compile("6 / 2", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(chunk.code[4], crate::OpCode::Divide as u8);
     }
 
     #[test]
     fn binary_modulo() {
-        let chunk = compile("10 % 3").unwrap();
+        let chunk = compile("10 % 3", &CompileOptions::default()).unwrap().chunk;
         assert_eq!(chunk.code[4], crate::OpCode::Modulo as u8);
     }
 
     #[test]
     fn binary_precedence_mul_over_add() {
         // 2 + 3 * 4 = 2 + 12 = 14, not (2 + 3) * 4 = 20
-        let chunk = compile("2 + 3 * 4").unwrap();
+        let chunk = compile("2 + 3 * 4", &CompileOptions::default())
+            .unwrap()
+            .chunk;
         // 2, 3, 4, *, +
         assert_eq!(chunk.code[6], crate::OpCode::Multiply as u8);
         assert_eq!(chunk.code[7], crate::OpCode::Add as u8);
     }
 
+    #[test]
+    fn binary_precedence_shift_over_bit_and() {
+        // 1 & 2 << 3 = 1 & (2 << 3)
+        let chunk = compile("1 & 2 << 3", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        // 1, 2, 3, <<, &
+        assert_eq!(chunk.code[6], crate::OpCode::ShiftLeft as u8);
+        assert_eq!(chunk.code[7], crate::OpCode::BitAnd as u8);
+    }
+
     #[test]
     fn binary_left_associativity() {
         // 1 - 2 - 3 = (1 - 2) - 3 = -4, not 1 - (2 - 3) = 2
-        let chunk = compile("1 - 2 - 3").unwrap();
+        let chunk = compile("1 - 2 - 3", &CompileOptions::default())
+            .unwrap()
+            .chunk;
         // 1, 2, -, 3, -
         assert_eq!(chunk.code[4], crate::OpCode::Subtract as u8);
         assert_eq!(chunk.code[7], crate::OpCode::Subtract as u8);
     }
 
+    // 18.4 - Literals and comparison
+    #[test]
+    fn literal_true_emits_true() {
+        let chunk = compile("firmeza", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(chunk.code[0], crate::OpCode::True as u8);
+        assert!(chunk.constants.is_empty());
+    }
+
+    #[test]
+    fn literal_false_emits_false() {
+        let chunk = compile("treta", &CompileOptions::default()).unwrap().chunk;
+        assert_eq!(chunk.code[0], crate::OpCode::False as u8);
+    }
+
+    #[test]
+    fn literal_nil_emits_nil() {
+        let chunk = compile("nadaNão", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(chunk.code[0], crate::OpCode::Nil as u8);
+    }
+
+    #[test]
+    fn unary_bang_emits_not() {
+        let chunk = compile("!firmeza", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(
+            chunk.code,
+            vec![
+                crate::OpCode::True as u8,
+                crate::OpCode::Not as u8,
+                crate::OpCode::Return as u8
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_equal_emits_equal() {
+        let chunk = compile("1 == 2", &CompileOptions::default()).unwrap().chunk;
+        assert_eq!(chunk.code[4], crate::OpCode::Equal as u8);
+    }
+
+    #[test]
+    fn binary_not_equal_emits_equal_not() {
+        let chunk = compile("1 != 2", &CompileOptions::default()).unwrap().chunk;
+        assert_eq!(chunk.code[4], crate::OpCode::Equal as u8);
+        assert_eq!(chunk.code[5], crate::OpCode::Not as u8);
+    }
+
+    #[test]
+    fn binary_comparisons_emit_their_opcodes() {
+        for (source, op) in [
+            ("1 > 2", crate::OpCode::Greater),
+            ("1 >= 2", crate::OpCode::GreaterEqual),
+            ("1 < 2", crate::OpCode::Less),
+            ("1 <= 2", crate::OpCode::LessEqual),
+        ] {
+            let chunk = compile(source, &CompileOptions::default()).unwrap().chunk;
+            assert_eq!(chunk.code[4], op as u8, "for {}", source);
+        }
+    }
+
+    #[test]
+    fn comparison_binds_tighter_than_equality() {
+        // 1 < 2 == firmeza -> (1 < 2) == firmeza
+        let chunk = compile("1 < 2 == firmeza", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(chunk.code[4], crate::OpCode::Less as u8);
+        assert_eq!(chunk.code[5], crate::OpCode::True as u8);
+        assert_eq!(chunk.code[6], crate::OpCode::Equal as u8);
+    }
+
+    #[test]
+    fn term_binds_tighter_than_comparison() {
+        // 1 + 2 > 2 -> (1 + 2) > 2
+        let chunk = compile("1 + 2 > 2", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(chunk.code[4], crate::OpCode::Add as u8);
+        assert_eq!(chunk.code[7], crate::OpCode::Greater as u8);
+    }
+
     // Ternary tests (parsing only, no codegen yet)
     #[test]
     fn ternary_missing_colon_errors() {
-        let result = compile("1 ? 2");
+        let result = compile("1 ? 2", &CompileOptions::default());
         assert!(result.is_err());
         let errors = result.unwrap_err();
         match &errors[0] {
@@ -608,20 +1254,361 @@ mod tests {
         }
     }
 
+    // Ternary and comma - disassembly golden tests
+    fn disassemble(source: &str) -> String {
+        crate::disassemble_chunk(
+            &compile(source, &CompileOptions::default()).unwrap().chunk,
+            "code",
+        )
+    }
+
     #[test]
-    fn ternary_not_yet_supported() {
-        let result = compile("1 ? 2 : 3");
-        assert!(result.is_err());
-        let errors = result.unwrap_err();
-        match &errors[0] {
-            mano::ManoError::Parse { message, .. } => {
-                assert!(
-                    message.to_lowercase().contains("ternário"),
-                    "Expected error about ternário, got: {}",
-                    message
-                );
-            }
-            _ => panic!("Expected Parse error"),
+    fn ternary_compiles_with_jumps() {
+        assert_eq!(
+            disassemble("firmeza ? 1 : 2"),
+            "\
+== code ==
+0000 0..7 OP_TRUE
+0001 8..9 OP_JUMP_IF_FALSE    1 -> 10
+0004    | OP_POP
+0005 10..11 OP_CONSTANT         0 '1'
+0007    | OP_JUMP    7 -> 13
+0010 12..13 OP_POP
+0011 14..15 OP_CONSTANT         1 '2'
+0013 15..15 OP_RETURN
+"
+        );
+    }
+
+    #[test]
+    fn ternary_else_branch_is_right_associative() {
+        assert_eq!(
+            disassemble("treta ? 1 : nadaNão ? 2 : 3"),
+            "\
+== code ==
+0000 0..5 OP_FALSE
+0001 6..7 OP_JUMP_IF_FALSE    1 -> 10
+0004    | OP_POP
+0005 8..9 OP_CONSTANT         0 '1'
+0007    | OP_JUMP    7 -> 24
+0010 10..11 OP_POP
+0011 12..20 OP_NIL
+0012 21..22 OP_JUMP_IF_FALSE   12 -> 21
+0015    | OP_POP
+0016 23..24 OP_CONSTANT         1 '2'
+0018    | OP_JUMP   18 -> 24
+0021 25..26 OP_POP
+0022 27..28 OP_CONSTANT         2 '3'
+0024 28..28 OP_RETURN
+"
+        );
+    }
+
+    #[test]
+    fn comma_pops_all_but_last_value() {
+        assert_eq!(
+            disassemble("1, 2, 3"),
+            "\
+== code ==
+0000 0..1 OP_CONSTANT         0 '1'
+0002 1..2 OP_POP
+0003 3..4 OP_CONSTANT         1 '2'
+0005 4..5 OP_POP
+0006 6..7 OP_CONSTANT         2 '3'
+0008 7..7 OP_RETURN
+"
+        );
+    }
+
+    #[test]
+    fn comma_allowed_in_ternary_then_branch() {
+        assert_eq!(
+            disassemble("1 ? 2, 3 : 4"),
+            "\
+== code ==
+0000 0..1 OP_CONSTANT         0 '1'
+0002 2..3 OP_JUMP_IF_FALSE    2 -> 14
+0005    | OP_POP
+0006 4..5 OP_CONSTANT         1 '2'
+0008 5..6 OP_POP
+0009 7..8 OP_CONSTANT         2 '3'
+0011    | OP_JUMP   11 -> 17
+0014 9..10 OP_POP
+0015 11..12 OP_CONSTANT         3 '4'
+0017 12..12 OP_RETURN
+"
+        );
+    }
+
+    #[test]
+    fn ternary_binds_tighter_than_comma() {
+        // 1, 2 ? 3 : 4 -> 1, (2 ? 3 : 4)
+        let chunk = compile("1, 2 ? 3 : 4", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(chunk.code[2], crate::OpCode::Pop as u8);
+        assert_eq!(chunk.code[5], crate::OpCode::JumpIfFalse as u8);
+    }
+
+    #[test]
+    fn ternary_and_comma_evaluate_like_interpreter() {
+        for (source, expected) in [
+            ("firmeza ? 1 : 2", "1\n"),
+            ("treta ? 1 : 2", "2\n"),
+            ("nadaNão ? 1 : 0 ? 2 : 3", "2\n"),
+            ("1, 2, 3", "3\n"),
+            ("(1, treta) ? 3 : 4", "4\n"),
+        ] {
+            let mut output = Vec::new();
+            crate::run(source, &mut output, false).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                expected,
+                "for {}",
+                source
+            );
+        }
+    }
+
+    // Statements and control flow
+    #[test]
+    fn while_loops_jump_back_and_breaks_jump_past_the_exit() {
+        assert_eq!(
+            disassemble("segueOFluxo (treta) saiFora;"),
+            "\
+== code ==
+0000 13..18 OP_FALSE
+0001 18..19 OP_JUMP_IF_FALSE    1 -> 11
+0004    | OP_POP
+0005 20..27 OP_JUMP    5 -> 12
+0008 0..11 OP_LOOP    8 -> 0
+0011 27..28 OP_POP
+"
+        );
+    }
+
+    #[test]
+    fn continue_jumps_back_to_the_condition() {
+        assert_eq!(
+            disassemble("segueOFluxo (treta) segueReto;"),
+            "\
+== code ==
+0000 13..18 OP_FALSE
+0001 18..19 OP_JUMP_IF_FALSE    1 -> 11
+0004    | OP_POP
+0005 0..11 OP_LOOP    5 -> 0
+0008    | OP_LOOP    8 -> 0
+0011 29..30 OP_POP
+"
+        );
+    }
+
+    #[test]
+    fn control_flow_runs_like_the_interpreter() {
+        for source in [
+            "sePá (1 < 2) salve 1; vacilou salve 2;",
+            "sePá (nadaNão) salve 1; salve 2;",
+            "sePá (treta) { salve 1; } vacilou sePá (0) { salve 2; } vacilou salve 3;",
+            "segueOFluxo (firmeza) { salve 1; saiFora; salve 2; } salve 3;",
+            "segueOFluxo (firmeza) { segueOFluxo (firmeza) saiFora; salve 1; saiFora; }",
+            "segueOFluxo (firmeza) { sePá (treta) segueReto; salve 1; saiFora; }",
+            "salve nadaNão ow 2; salve 1 tamoJunto treta; salve treta ow nadaNão;",
+            "salve treta tamoJunto -(1 < 2); salve 1 ow -(1 < 2);",
+            "salve 1 ow 2 tamoJunto 3; 1 + 2;",
+        ] {
+            let mut expected = Vec::new();
+            let errors = mano::Mano::new().run(source, &mut expected);
+            assert!(errors.is_empty(), "{source}: {errors:?}");
+            let mut output = Vec::new();
+            crate::run(source, &mut output, false).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                String::from_utf8(expected).unwrap(),
+                "for {}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn a_trailing_expression_after_statements_is_printed() {
+        let mut output = Vec::new();
+        crate::run("sePá (firmeza) salve 1; 2 ow 3", &mut output, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n2\n");
+    }
+
+    #[test]
+    fn break_outside_a_loop_errors() {
+        let errors = compile("saiFora;", &CompileOptions::default()).unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            ManoError::Parse { message, span } if message.contains("saiFora") && *span == (0..7)
+        ));
+    }
+
+    #[test]
+    fn continue_outside_a_loop_errors() {
+        let errors = compile("segueReto;", &CompileOptions::default()).unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            ManoError::Parse { message, span } if message.contains("segueReto") && *span == (0..9)
+        ));
+    }
+
+    #[test]
+    fn lists_say_they_are_not_in_the_vm_yet() {
+        for (source, span) in [("salve [1, 2];", 6..7), ("salve \"oi\"[0];", 10..11)] {
+            let errors = compile(source, &CompileOptions::default()).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(
+                &errors[0],
+                ManoError::Parse { message, span: at } if message.contains("Lista") && *at == span
+            ));
+        }
+    }
+
+    #[test]
+    fn a_mistake_is_reported_once_and_compiling_goes_on() {
+        let errors = compile("salve 1 salve 2; sePá 3", &CompileOptions::default()).unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(matches!(
+            &errors[1],
+            ManoError::Parse { message, .. } if message.contains("'(' depois do sePá")
+        ));
+    }
+
+    // Warnings and options
+    fn basic() -> CompileOptions {
+        CompileOptions {
+            opt_level: OptLevel::Basic,
+            ..Default::default()
+        }
+    }
+
+    fn warning_messages(output: &CompileOutput) -> Vec<String> {
+        output
+            .warnings
+            .iter()
+            .map(|warning| match warning {
+                ManoError::Warning { message, .. } => message.clone(),
+                other => panic!("Expected Warning, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_ternary_condition_warns() {
+        let output = compile("firmeza ? 1 : 2", &CompileOptions::default()).unwrap();
+        assert_eq!(
+            warning_messages(&output),
+            vec!["Essa condição é sempre firmeza, mano!"]
+        );
+        match &output.warnings[0] {
+            ManoError::Warning { span, .. } => assert_eq!(*span, 0..7),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn falsey_ternary_condition_warns() {
+        let output = compile("nadaNão ? 1 : 2", &CompileOptions::default()).unwrap();
+        assert_eq!(
+            warning_messages(&output),
+            vec!["Essa condição é sempre treta, mano!"]
+        );
+    }
+
+    #[test]
+    fn computed_ternary_condition_does_not_warn() {
+        for source in ["1 < 2 ? 1 : 2", "-1 ? 1 : 2", "!firmeza ? 1 : 2"] {
+            let output = compile(source, &CompileOptions::default()).unwrap();
+            assert!(output.warnings.is_empty(), "for {}", source);
+        }
+    }
+
+    #[test]
+    fn strict_mode_turns_warnings_into_errors() {
+        let options = CompileOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let errors = compile("firmeza ? 1 : 2", &options).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ManoError::Warning { .. }));
+    }
+
+    #[test]
+    fn dialect_aliases_compile_like_keywords() {
+        let options = CompileOptions {
+            dialect: Dialect::Formal,
+            ..Default::default()
+        };
+        let formal = compile("!falso == verdadeiro", &options).unwrap().chunk;
+        let mano = compile("!treta == firmeza", &CompileOptions::default())
+            .unwrap()
+            .chunk;
+        assert_eq!(formal.code, mano.code);
+    }
+
+    #[test]
+    fn compiles_only_vm_and_defined_blocks() {
+        let source = "#seVm\n1 +\n#vacilou\nsalve 9;\n#fim\n#se DOIS\n1 +\n#fim\n1";
+        let chunk = |defines: &[&str]| {
+            let options = CompileOptions {
+                defines: defines.iter().map(|d| d.to_string()).collect(),
+                ..Default::default()
+            };
+            compile(source, &options).unwrap().chunk
+        };
+        assert_eq!(chunk(&[]).constants.len(), 2);
+        assert_eq!(chunk(&["DOIS"]).constants.len(), 3);
+    }
+
+    #[test]
+    fn basic_opt_level_keeps_only_taken_branch() {
+        let chunk = compile("firmeza ? 1 : 2", &basic()).unwrap().chunk;
+        assert_eq!(
+            crate::disassemble_chunk(&chunk, "code"),
+            "\
+== code ==
+0000 10..11 OP_CONSTANT         0 '1'
+0002 15..15 OP_RETURN
+"
+        );
+
+        let chunk = compile("treta ? 1 : 2", &basic()).unwrap().chunk;
+        assert_eq!(chunk.code[0], crate::OpCode::Constant as u8);
+        assert_eq!(chunk.constants[chunk.code[1] as usize], Value::Number(2.0));
+        assert_eq!(chunk.code.len(), 3);
+    }
+
+    #[test]
+    fn basic_opt_level_still_reports_dead_branch_errors() {
+        let errors = compile("firmeza ? 1 : )", &basic()).unwrap_err();
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn basic_opt_level_evaluates_like_none() {
+        for source in [
+            "firmeza ? 1 : 2",
+            "treta ? 1 : 2",
+            "nadaNão ? 1 : 0 ? 2 : 3",
+            "(1, treta) ? 3 : 4",
+            "1 ? 2, 3 : 4",
+        ] {
+            let run = |options: &CompileOptions| {
+                let chunk = compile(source, options).unwrap().chunk;
+                let mut output = Vec::new();
+                crate::run_chunk(&chunk, source, &mut output, false).unwrap();
+                String::from_utf8(output).unwrap()
+            };
+            assert_eq!(
+                run(&basic()),
+                run(&CompileOptions::default()),
+                "for {}",
+                source
+            );
         }
     }
 
@@ -674,6 +1661,34 @@ mod tests {
         assert_eq!(rule.precedence, Precedence::None);
     }
 
+    #[test]
+    fn parse_rule_bang() {
+        let rule = ParseRule::from(TokenType::Bang);
+        assert_eq!(rule.prefix, Some(ParseFn::Unary));
+        assert_eq!(rule.infix, None);
+    }
+
+    #[test]
+    fn parse_rule_equal_equal() {
+        let rule = ParseRule::from(TokenType::EqualEqual);
+        assert_eq!(rule.infix, Some(ParseFn::Binary));
+        assert_eq!(rule.precedence, Precedence::Equality);
+    }
+
+    #[test]
+    fn parse_rule_less() {
+        let rule = ParseRule::from(TokenType::Less);
+        assert_eq!(rule.infix, Some(ParseFn::Binary));
+        assert_eq!(rule.precedence, Precedence::Comparison);
+    }
+
+    #[test]
+    fn parse_rule_true() {
+        let rule = ParseRule::from(TokenType::True);
+        assert_eq!(rule.prefix, Some(ParseFn::Literal));
+        assert_eq!(rule.infix, None);
+    }
+
     #[test]
     fn parse_rule_eof() {
         let rule = ParseRule::from(TokenType::Eof);
@@ -689,7 +1704,7 @@ mod tests {
         compiler.advance();
         compiler.advance(); // previous = 42
         ParseFn::Number.call(&mut compiler);
-        assert_eq!(compiler.chunk.constants[0], 42.0);
+        assert_eq!(compiler.chunk.constants[0], Value::Number(42.0));
     }
 
     #[test]
@@ -710,7 +1725,7 @@ mod tests {
         compiler.advance();
         compiler.advance(); // previous = (
         ParseFn::Grouping.call(&mut compiler);
-        assert_eq!(compiler.chunk.constants[0], 42.0);
+        assert_eq!(compiler.chunk.constants[0], Value::Number(42.0));
     }
 
     #[test]
@@ -734,8 +1749,18 @@ mod tests {
     }
 
     #[test]
-    fn precedence_next_assignment_is_or() {
-        assert_eq!(Precedence::Assignment.next(), Precedence::Or);
+    fn precedence_next_assignment_is_comma() {
+        assert_eq!(Precedence::Assignment.next(), Precedence::Comma);
+    }
+
+    #[test]
+    fn precedence_next_comma_is_ternary() {
+        assert_eq!(Precedence::Comma.next(), Precedence::Ternary);
+    }
+
+    #[test]
+    fn precedence_next_ternary_is_or() {
+        assert_eq!(Precedence::Ternary.next(), Precedence::Or);
     }
 
     #[test]
@@ -754,8 +1779,16 @@ mod tests {
     }
 
     #[test]
-    fn precedence_next_comparison_is_term() {
-        assert_eq!(Precedence::Comparison.next(), Precedence::Term);
+    fn precedence_next_comparison_is_bit_or() {
+        assert_eq!(Precedence::Comparison.next(), Precedence::BitOr);
+    }
+
+    #[test]
+    fn precedence_bitwise_levels_lead_to_term() {
+        assert_eq!(Precedence::BitOr.next(), Precedence::BitXor);
+        assert_eq!(Precedence::BitXor.next(), Precedence::BitAnd);
+        assert_eq!(Precedence::BitAnd.next(), Precedence::Shift);
+        assert_eq!(Precedence::Shift.next(), Precedence::Term);
     }
 
     #[test]
@@ -1,4 +1,5 @@
-use crate::{Chunk, OpCode};
+use crate::chunk::Span;
+use crate::{Chunk, OpCode, OperandKind};
 
 pub fn disassemble_chunk(chunk: &Chunk, name: &str) -> String {
     let mut output = format!("== {} ==\n", name);
@@ -11,76 +12,119 @@ pub fn disassemble_chunk(chunk: &Chunk, name: &str) -> String {
     output
 }
 
+/// The operand of a decoded instruction, already resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    None,
+    /// Index into the chunk's constants.
+    Constant(usize),
+    /// Offset the jump or loop lands on.
+    Target(usize),
+}
+
+/// One instruction of a chunk, for tools that want more than the text listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub offset: usize,
+    /// Source the instruction was compiled from.
+    pub span: Span,
+    /// `None` for a byte that isn't an opcode.
+    pub opcode: Option<OpCode>,
+    /// The raw opcode byte.
+    pub byte: u8,
+    pub operand: Operand,
+}
+
+/// Decode the instruction at `offset`, returning it and the next offset.
+pub fn decode_instruction(chunk: &Chunk, offset: usize) -> (Instruction, usize) {
+    let byte = chunk.code[offset];
+    let opcode = OpCode::from_byte(byte);
+    let operand_kind = opcode.map_or(OperandKind::None, |opcode| opcode.info().operand);
+    let next_offset = offset + 1 + operand_kind.size();
+
+    let operand = match operand_kind {
+        OperandKind::None => Operand::None,
+        OperandKind::Constant => Operand::Constant(chunk.code[offset + 1] as usize),
+        OperandKind::ConstantLong => Operand::Constant(
+            chunk.code[offset + 1] as usize
+                | (chunk.code[offset + 2] as usize) << 8
+                | (chunk.code[offset + 3] as usize) << 16,
+        ),
+        OperandKind::Jump | OperandKind::Loop => {
+            let jump = (chunk.code[offset + 1] as usize) << 8 | chunk.code[offset + 2] as usize;
+            Operand::Target(if operand_kind == OperandKind::Loop {
+                next_offset - jump
+            } else {
+                next_offset + jump
+            })
+        }
+    };
+    let instruction = Instruction {
+        offset,
+        span: chunk.get_span(offset),
+        opcode,
+        byte,
+        operand,
+    };
+    (instruction, next_offset)
+}
+
+/// Decode every instruction in the chunk, in order.
+pub fn decode_chunk(chunk: &Chunk) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let (instruction, next_offset) = decode_instruction(chunk, offset);
+        instructions.push(instruction);
+        offset = next_offset;
+    }
+    instructions
+}
+
 pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> (String, usize) {
-    let span = chunk.get_span(offset);
-    let span_str = if offset > 0 && chunk.get_span(offset - 1) == span {
+    let (instruction, next_offset) = decode_instruction(chunk, offset);
+    let span = &instruction.span;
+    let span_str = if offset > 0 && chunk.get_span(offset - 1) == *span {
         "   |".to_string()
     } else {
         format!("{}..{}", span.start, span.end)
     };
 
-    let byte = chunk.code[offset];
-    match byte {
-        b if b == OpCode::Return as u8 => (
-            format!("{:04} {} OP_RETURN\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Negate as u8 => (
-            format!("{:04} {} OP_NEGATE\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Add as u8 => (format!("{:04} {} OP_ADD\n", offset, span_str), offset + 1),
-        b if b == OpCode::Subtract as u8 => (
-            format!("{:04} {} OP_SUBTRACT\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Multiply as u8 => (
-            format!("{:04} {} OP_MULTIPLY\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Divide as u8 => (
-            format!("{:04} {} OP_DIVIDE\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Modulo as u8 => (
-            format!("{:04} {} OP_MODULO\n", offset, span_str),
-            offset + 1,
-        ),
-        b if b == OpCode::Constant as u8 => {
-            let constant_idx = chunk.code[offset + 1];
-            let value = chunk.constants[constant_idx as usize];
-            (
-                format!(
-                    "{:04} {} OP_CONSTANT {:>9} '{}'\n",
-                    offset, span_str, constant_idx, value
-                ),
-                offset + 2,
+    let Some(opcode) = instruction.opcode else {
+        return (
+            format!(
+                "{:04} {} Unknown opcode {}\n",
+                offset, span_str, instruction.byte
+            ),
+            next_offset,
+        );
+    };
+    let name = opcode.info().name;
+
+    let line = match instruction.operand {
+        Operand::None => format!("{:04} {} {}\n", offset, span_str, name),
+        Operand::Constant(constant_idx) => {
+            let value = &chunk.constants[constant_idx];
+            format!(
+                "{:04} {} {} {:>9} '{}'\n",
+                offset, span_str, name, constant_idx, value
             )
         }
-        b if b == OpCode::ConstantLong as u8 => {
-            let constant_idx = chunk.code[offset + 1] as usize
-                | (chunk.code[offset + 2] as usize) << 8
-                | (chunk.code[offset + 3] as usize) << 16;
-            let value = chunk.constants[constant_idx];
-            (
-                format!(
-                    "{:04} {} OP_CONSTANT_LONG {:>9} '{}'\n",
-                    offset, span_str, constant_idx, value
-                ),
-                offset + 4,
+        Operand::Target(target) => {
+            format!(
+                "{:04} {} {} {:>4} -> {}\n",
+                offset, span_str, name, offset, target
             )
         }
-        _ => (
-            format!("{:04} {} Unknown opcode {}\n", offset, span_str, byte),
-            offset + 1,
-        ),
-    }
+    };
+    (line, next_offset)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::OpCode;
+    use crate::value::Value;
 
     #[test]
     fn disassemble_empty_chunk() {
@@ -124,7 +168,7 @@ mod tests {
     #[test]
     fn disassemble_instruction_constant() {
         let mut chunk = Chunk::new();
-        let constant_idx = chunk.add_constant(1.2);
+        let constant_idx = chunk.add_constant(Value::Number(1.2));
         chunk.write(OpCode::Constant.into(), 0..0);
         chunk.write(constant_idx as u8, 0..0);
 
@@ -171,10 +215,10 @@ mod tests {
         let mut chunk = Chunk::new();
         // Add 256 constants
         for i in 0..256 {
-            chunk.add_constant(i as f64);
+            chunk.add_constant(Value::Number(i as f64));
         }
         // Write constant at index 256
-        chunk.write_constant(999.0, 0..0);
+        chunk.write_constant(Value::Number(999.0), 0..0);
 
         let (output, next_offset) = disassemble_instruction(&chunk, 0);
 
@@ -247,4 +291,180 @@ mod tests {
         assert_eq!(output, "0000 0..0 OP_MODULO\n");
         assert_eq!(next_offset, 1);
     }
+
+    #[test]
+    fn disassemble_instruction_nil() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Nil.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_NIL\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_true() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::True.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_TRUE\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_false() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::False.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_FALSE\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_not() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Not.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_NOT\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_equal() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Equal.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_EQUAL\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_greater() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Greater.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_GREATER\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_less() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Less.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_LESS\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_greater_equal() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::GreaterEqual.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_GREATER_EQUAL\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_less_equal() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::LessEqual.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_LESS_EQUAL\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_pop() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Pop.into(), 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_POP\n");
+        assert_eq!(next_offset, 1);
+    }
+
+    #[test]
+    fn disassemble_instruction_jump_shows_target() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Jump.into(), 0..0);
+        chunk.write(0x01, 0..0);
+        chunk.write(0x02, 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        // 0x0102 = 258 bytes past the end of the instruction
+        assert_eq!(output, "0000 0..0 OP_JUMP    0 -> 261\n");
+        assert_eq!(next_offset, 3);
+    }
+
+    #[test]
+    fn disassemble_instruction_jump_if_false() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::JumpIfFalse.into(), 0..0);
+        chunk.write(0x00, 0..0);
+        chunk.write(0x04, 0..0);
+
+        let (output, next_offset) = disassemble_instruction(&chunk, 0);
+
+        assert_eq!(output, "0000 0..0 OP_JUMP_IF_FALSE    0 -> 7\n");
+        assert_eq!(next_offset, 3);
+    }
+
+    #[test]
+    fn decode_chunk_resolves_operands() {
+        let mut chunk = Chunk::new();
+        chunk.write_constant(Value::Number(1.0), 0..1);
+        chunk.write(OpCode::Jump.into(), 2..3);
+        chunk.write(0x00, 2..3);
+        chunk.write(0x01, 2..3);
+        chunk.write(0xFF, 4..5);
+
+        let instructions = decode_chunk(&chunk);
+
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction {
+                    offset: 0,
+                    span: 0..1,
+                    opcode: Some(OpCode::Constant),
+                    byte: OpCode::Constant.into(),
+                    operand: Operand::Constant(0),
+                },
+                Instruction {
+                    offset: 2,
+                    span: 2..3,
+                    opcode: Some(OpCode::Jump),
+                    byte: OpCode::Jump.into(),
+                    operand: Operand::Target(6),
+                },
+                Instruction {
+                    offset: 5,
+                    span: 4..5,
+                    opcode: None,
+                    byte: 0xFF,
+                    operand: Operand::None,
+                },
+            ]
+        );
+    }
 }
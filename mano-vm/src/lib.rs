@@ -4,7 +4,9 @@ mod chunk;
 mod compiler;
 mod debug;
 mod opcode;
+mod trace;
 mod value;
+mod verify;
 mod vm;
 
 use std::io::Write;
@@ -12,9 +14,15 @@ use std::io::Write;
 use mano::ManoError;
 
 pub use chunk::Chunk;
-pub use compiler::compile;
-pub use debug::{disassemble_chunk, disassemble_instruction};
-pub use opcode::OpCode;
+pub use compiler::{CompileOptions, CompileOutput, CompileResult, OptLevel, compile};
+pub use debug::{
+    Instruction, Operand, decode_chunk, decode_instruction, disassemble_chunk,
+    disassemble_instruction,
+};
+pub use opcode::{OP_INFO, OpCode, OpInfo, OperandKind};
+pub use trace::Trace;
+pub use value::Value;
+pub use verify::{StackError, verify_stack};
 pub use vm::{InterpretResult, VM};
 
 /// Run mano source code.
@@ -22,12 +30,25 @@ pub use vm::{InterpretResult, VM};
 /// Compiles the source to bytecode and executes it in the VM.
 /// When trace is enabled, dumps the compiled chunk before execution.
 pub fn run<W: Write>(source: &str, output: &mut W, trace: bool) -> Result<(), Vec<ManoError>> {
-    let chunk = compile(source)?;
+    let compiled = compile(source, &CompileOptions::default())?;
+    run_chunk(&compiled.chunk, source, output, trace)
+}
+
+/// Run an already compiled chunk.
+///
+/// `source` is the code the chunk was compiled from, used for error locations.
+pub fn run_chunk<W: Write>(
+    chunk: &Chunk,
+    source: &str,
+    output: &mut W,
+    trace: bool,
+) -> Result<(), Vec<ManoError>> {
     if trace {
-        write!(output, "{}", disassemble_chunk(&chunk, "code")).unwrap();
+        write!(output, "{}", disassemble_chunk(chunk, "code")).unwrap();
     }
-    let mut vm = VM::new(&chunk, output);
+    let mut vm = VM::new(chunk, output);
     vm.set_trace(trace);
+    vm.set_source(source);
     vm.interpret()
 }
 
@@ -43,4 +64,50 @@ mod tests {
         assert!(output_str.contains("== code =="));
         assert!(output_str.contains("OP_CONSTANT"));
     }
+
+    #[test]
+    fn run_evaluates_comparisons_and_truthiness() {
+        let mut output = Vec::new();
+        run("!(5 - 4 > 3 * 2 == !nadaNão)", &mut output, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "firmeza\n");
+    }
+
+    #[test]
+    fn run_interpolates_strings_like_the_interpreter() {
+        let source = "salve \"oi \" + \"mano\"; \"{1 + 2} {firmeza}{\"!\"}\"";
+        let mut output = Vec::new();
+        run(source, &mut output, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "oi mano\n3 firmeza!\n");
+    }
+
+    #[test]
+    fn run_points_errors_inside_interpolations_at_the_expression() {
+        let source = "\"total: {1 - treta}\"";
+        let errors = run(source, &mut Vec::new(), false).unwrap_err();
+        match &errors[0] {
+            ManoError::Runtime { message, span } => {
+                assert!(message.contains("número"));
+                assert!(span.start > source.find('{').unwrap());
+                assert!(span.end <= source.find('}').unwrap());
+            }
+            other => panic!("expected Runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn run_evaluates_bitwise_operators_like_the_interpreter() {
+        let mut output = Vec::new();
+        run(
+            "(6 & 3) + (6 | 3) * 10 + (6 ^ 3) * 100 + (-16 >> 2) + (1 << 10)",
+            &mut output,
+            false,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1592\n");
+
+        let errors = run("1 << 64", &mut Vec::new(), false).unwrap_err();
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("0 a 63"))
+        );
+    }
 }
@@ -20,6 +20,180 @@ pub enum OpCode {
     Divide = 7,
     /// Modulo top two values on the stack.
     Modulo = 8,
+    /// Push `nadaNão` onto the stack.
+    Nil = 9,
+    /// Push `firmeza` onto the stack.
+    True = 10,
+    /// Push `treta` onto the stack.
+    False = 11,
+    /// Replace the top value with its logical negation.
+    Not = 12,
+    /// Compare top two values for equality.
+    Equal = 13,
+    /// Check if the second value is greater than the top one.
+    Greater = 14,
+    /// Check if the second value is less than the top one.
+    Less = 15,
+    /// Check if the second value is greater than or equal to the top one.
+    /// Not `Less` + `Not`, so NaN compares like it does in the interpreter.
+    GreaterEqual = 16,
+    /// Check if the second value is less than or equal to the top one.
+    LessEqual = 17,
+    /// Discard the top value on the stack.
+    Pop = 18,
+    /// Jump forward unconditionally (16-bit offset).
+    Jump = 19,
+    /// Jump forward if the top value is falsy, leaving it on the stack (16-bit offset).
+    JumpIfFalse = 20,
+    /// Bitwise and of the top two values (`&`).
+    BitAnd = 21,
+    /// Bitwise or of the top two values (`|`).
+    BitOr = 22,
+    /// Bitwise xor of the top two values (`^`).
+    BitXor = 23,
+    /// Shift the second value left by the top one (`<<`).
+    ShiftLeft = 24,
+    /// Shift the second value right by the top one, keeping the sign (`>>`).
+    ShiftRight = 25,
+    /// Print the top value on the stack and pop it (`salve`).
+    Print = 26,
+    /// Jump backward unconditionally (16-bit offset), to the start of a loop.
+    Loop = 27,
+    /// Replace the top value with its text, as `salve` writes it (for `"oi {nome}"`).
+    Stringify = 28,
+}
+
+/// Kind of operand bytes following an opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    /// No operand.
+    None,
+    /// 1-byte constant pool index.
+    Constant,
+    /// 24-bit little-endian constant pool index.
+    ConstantLong,
+    /// 16-bit big-endian forward jump offset.
+    Jump,
+    /// 16-bit big-endian backward jump offset.
+    Loop,
+}
+
+impl OperandKind {
+    /// Number of operand bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Constant => 1,
+            Self::ConstantLong => 3,
+            Self::Jump | Self::Loop => 2,
+        }
+    }
+}
+
+/// Static description of an opcode, shared by the disassembler, the
+/// compiler and the VM's stack checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpInfo {
+    pub opcode: OpCode,
+    /// Name shown in disassembly.
+    pub name: &'static str,
+    pub operand: OperandKind,
+    /// Values the instruction needs on the stack.
+    pub pops: u8,
+    /// Values it leaves on the stack.
+    pub pushes: u8,
+}
+
+impl OpInfo {
+    /// Net change in stack depth after the instruction runs.
+    pub fn stack_effect(&self) -> isize {
+        self.pushes as isize - self.pops as isize
+    }
+}
+
+const fn info(
+    opcode: OpCode,
+    name: &'static str,
+    operand: OperandKind,
+    pops: u8,
+    pushes: u8,
+) -> OpInfo {
+    OpInfo {
+        opcode,
+        name,
+        operand,
+        pops,
+        pushes,
+    }
+}
+
+/// Metadata for every opcode, indexed by its byte value.
+pub const OP_INFO: &[OpInfo] = &[
+    info(OpCode::Return, "OP_RETURN", OperandKind::None, 1, 0),
+    info(OpCode::Constant, "OP_CONSTANT", OperandKind::Constant, 0, 1),
+    info(
+        OpCode::ConstantLong,
+        "OP_CONSTANT_LONG",
+        OperandKind::ConstantLong,
+        0,
+        1,
+    ),
+    info(OpCode::Negate, "OP_NEGATE", OperandKind::None, 1, 1),
+    info(OpCode::Add, "OP_ADD", OperandKind::None, 2, 1),
+    info(OpCode::Subtract, "OP_SUBTRACT", OperandKind::None, 2, 1),
+    info(OpCode::Multiply, "OP_MULTIPLY", OperandKind::None, 2, 1),
+    info(OpCode::Divide, "OP_DIVIDE", OperandKind::None, 2, 1),
+    info(OpCode::Modulo, "OP_MODULO", OperandKind::None, 2, 1),
+    info(OpCode::Nil, "OP_NIL", OperandKind::None, 0, 1),
+    info(OpCode::True, "OP_TRUE", OperandKind::None, 0, 1),
+    info(OpCode::False, "OP_FALSE", OperandKind::None, 0, 1),
+    info(OpCode::Not, "OP_NOT", OperandKind::None, 1, 1),
+    info(OpCode::Equal, "OP_EQUAL", OperandKind::None, 2, 1),
+    info(OpCode::Greater, "OP_GREATER", OperandKind::None, 2, 1),
+    info(OpCode::Less, "OP_LESS", OperandKind::None, 2, 1),
+    info(
+        OpCode::GreaterEqual,
+        "OP_GREATER_EQUAL",
+        OperandKind::None,
+        2,
+        1,
+    ),
+    info(OpCode::LessEqual, "OP_LESS_EQUAL", OperandKind::None, 2, 1),
+    info(OpCode::Pop, "OP_POP", OperandKind::None, 1, 0),
+    info(OpCode::Jump, "OP_JUMP", OperandKind::Jump, 0, 0),
+    info(
+        OpCode::JumpIfFalse,
+        "OP_JUMP_IF_FALSE",
+        OperandKind::Jump,
+        1,
+        1,
+    ),
+    info(OpCode::BitAnd, "OP_BIT_AND", OperandKind::None, 2, 1),
+    info(OpCode::BitOr, "OP_BIT_OR", OperandKind::None, 2, 1),
+    info(OpCode::BitXor, "OP_BIT_XOR", OperandKind::None, 2, 1),
+    info(OpCode::ShiftLeft, "OP_SHIFT_LEFT", OperandKind::None, 2, 1),
+    info(
+        OpCode::ShiftRight,
+        "OP_SHIFT_RIGHT",
+        OperandKind::None,
+        2,
+        1,
+    ),
+    info(OpCode::Print, "OP_PRINT", OperandKind::None, 1, 0),
+    info(OpCode::Loop, "OP_LOOP", OperandKind::Loop, 0, 0),
+    info(OpCode::Stringify, "OP_STRINGIFY", OperandKind::None, 1, 1),
+];
+
+impl OpCode {
+    /// Metadata for this opcode.
+    pub fn info(self) -> &'static OpInfo {
+        &OP_INFO[self as usize]
+    }
+
+    /// Decode a byte, returning `None` for unknown opcodes.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        OP_INFO.get(byte as usize).map(|info| info.opcode)
+    }
 }
 
 impl From<u8> for OpCode {
@@ -34,6 +208,26 @@ impl From<u8> for OpCode {
             6 => OpCode::Multiply,
             7 => OpCode::Divide,
             8 => OpCode::Modulo,
+            9 => OpCode::Nil,
+            10 => OpCode::True,
+            11 => OpCode::False,
+            12 => OpCode::Not,
+            13 => OpCode::Equal,
+            14 => OpCode::Greater,
+            15 => OpCode::Less,
+            16 => OpCode::GreaterEqual,
+            17 => OpCode::LessEqual,
+            18 => OpCode::Pop,
+            19 => OpCode::Jump,
+            20 => OpCode::JumpIfFalse,
+            21 => OpCode::BitAnd,
+            22 => OpCode::BitOr,
+            23 => OpCode::BitXor,
+            24 => OpCode::ShiftLeft,
+            25 => OpCode::ShiftRight,
+            26 => OpCode::Print,
+            27 => OpCode::Loop,
+            28 => OpCode::Stringify,
             _ => panic!("Unknown opcode: {}", byte),
         }
     }
@@ -149,4 +343,189 @@ mod tests {
     fn opcode_from_byte_eight_is_modulo() {
         assert_eq!(OpCode::from(8), OpCode::Modulo);
     }
+
+    #[test]
+    fn opcode_nil_has_value_nine() {
+        assert_eq!(OpCode::Nil as u8, 9);
+    }
+
+    #[test]
+    fn opcode_from_byte_nine_is_nil() {
+        assert_eq!(OpCode::from(9), OpCode::Nil);
+    }
+
+    #[test]
+    fn opcode_true_has_value_ten() {
+        assert_eq!(OpCode::True as u8, 10);
+    }
+
+    #[test]
+    fn opcode_from_byte_ten_is_true() {
+        assert_eq!(OpCode::from(10), OpCode::True);
+    }
+
+    #[test]
+    fn opcode_false_has_value_eleven() {
+        assert_eq!(OpCode::False as u8, 11);
+    }
+
+    #[test]
+    fn opcode_from_byte_eleven_is_false() {
+        assert_eq!(OpCode::from(11), OpCode::False);
+    }
+
+    #[test]
+    fn opcode_not_has_value_twelve() {
+        assert_eq!(OpCode::Not as u8, 12);
+    }
+
+    #[test]
+    fn opcode_from_byte_twelve_is_not() {
+        assert_eq!(OpCode::from(12), OpCode::Not);
+    }
+
+    #[test]
+    fn opcode_equal_has_value_thirteen() {
+        assert_eq!(OpCode::Equal as u8, 13);
+    }
+
+    #[test]
+    fn opcode_from_byte_thirteen_is_equal() {
+        assert_eq!(OpCode::from(13), OpCode::Equal);
+    }
+
+    #[test]
+    fn opcode_greater_has_value_fourteen() {
+        assert_eq!(OpCode::Greater as u8, 14);
+    }
+
+    #[test]
+    fn opcode_from_byte_fourteen_is_greater() {
+        assert_eq!(OpCode::from(14), OpCode::Greater);
+    }
+
+    #[test]
+    fn opcode_less_has_value_fifteen() {
+        assert_eq!(OpCode::Less as u8, 15);
+    }
+
+    #[test]
+    fn opcode_from_byte_fifteen_is_less() {
+        assert_eq!(OpCode::from(15), OpCode::Less);
+    }
+
+    #[test]
+    fn opcode_greater_equal_has_value_sixteen() {
+        assert_eq!(OpCode::GreaterEqual as u8, 16);
+    }
+
+    #[test]
+    fn opcode_from_byte_sixteen_is_greater_equal() {
+        assert_eq!(OpCode::from(16), OpCode::GreaterEqual);
+    }
+
+    #[test]
+    fn opcode_less_equal_has_value_seventeen() {
+        assert_eq!(OpCode::LessEqual as u8, 17);
+    }
+
+    #[test]
+    fn opcode_from_byte_seventeen_is_less_equal() {
+        assert_eq!(OpCode::from(17), OpCode::LessEqual);
+    }
+
+    #[test]
+    fn opcode_pop_has_value_eighteen() {
+        assert_eq!(OpCode::Pop as u8, 18);
+    }
+
+    #[test]
+    fn opcode_from_byte_eighteen_is_pop() {
+        assert_eq!(OpCode::from(18), OpCode::Pop);
+    }
+
+    #[test]
+    fn opcode_jump_has_value_nineteen() {
+        assert_eq!(OpCode::Jump as u8, 19);
+    }
+
+    #[test]
+    fn opcode_from_byte_nineteen_is_jump() {
+        assert_eq!(OpCode::from(19), OpCode::Jump);
+    }
+
+    #[test]
+    fn opcode_jump_if_false_has_value_twenty() {
+        assert_eq!(OpCode::JumpIfFalse as u8, 20);
+    }
+
+    #[test]
+    fn opcode_from_byte_twenty_is_jump_if_false() {
+        assert_eq!(OpCode::from(20), OpCode::JumpIfFalse);
+    }
+
+    #[test]
+    fn opcode_print_has_value_twenty_six() {
+        assert_eq!(OpCode::Print as u8, 26);
+        assert_eq!(OpCode::from(26), OpCode::Print);
+    }
+
+    #[test]
+    fn opcode_loop_has_value_twenty_seven() {
+        assert_eq!(OpCode::Loop as u8, 27);
+        assert_eq!(OpCode::from(27), OpCode::Loop);
+    }
+
+    #[test]
+    fn opcode_stringify_has_value_twenty_eight() {
+        assert_eq!(OpCode::Stringify as u8, 28);
+        assert_eq!(OpCode::from(28), OpCode::Stringify);
+    }
+
+    #[test]
+    fn op_info_is_indexed_by_opcode_byte() {
+        for (byte, info) in OP_INFO.iter().enumerate() {
+            assert_eq!(info.opcode as usize, byte, "{} is out of place", info.name);
+        }
+    }
+
+    #[test]
+    fn from_byte_agrees_with_from() {
+        for info in OP_INFO {
+            let byte = info.opcode as u8;
+            assert_eq!(OpCode::from_byte(byte), Some(OpCode::from(byte)));
+        }
+        assert_eq!(OpCode::from_byte(OP_INFO.len() as u8), None);
+    }
+
+    #[test]
+    fn op_info_names_are_unique() {
+        let mut names: Vec<_> = OP_INFO.iter().map(|info| info.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), OP_INFO.len());
+    }
+
+    #[test]
+    fn from_byte_unknown_is_none() {
+        assert_eq!(OpCode::from_byte(255), None);
+    }
+
+    #[test]
+    fn info_describes_operands() {
+        assert_eq!(OpCode::Constant.info().operand.size(), 1);
+        assert_eq!(OpCode::ConstantLong.info().operand.size(), 3);
+        assert_eq!(OpCode::Jump.info().operand.size(), 2);
+        assert_eq!(OpCode::Loop.info().operand.size(), 2);
+        assert_eq!(OpCode::Add.info().operand.size(), 0);
+    }
+
+    #[test]
+    fn info_stack_effects() {
+        assert_eq!(OpCode::Constant.info().stack_effect(), 1);
+        assert_eq!(OpCode::Add.info().stack_effect(), -1);
+        assert_eq!(OpCode::Negate.info().stack_effect(), 0);
+        assert_eq!(OpCode::JumpIfFalse.info().stack_effect(), 0);
+        assert_eq!(OpCode::Add.info().pops, 2);
+    }
 }
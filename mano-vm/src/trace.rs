@@ -0,0 +1,92 @@
+//! Trace - which events the VM writes out while it runs
+
+use std::str::FromStr;
+
+/// What the VM traces, each part on its own so a script's calls can be
+/// watched without a line per instruction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// Entering and returning from the script, with the value returned.
+    pub calls: bool,
+    /// The stack before each instruction.
+    pub stack: bool,
+    /// Each instruction as it runs.
+    pub ops: bool,
+}
+
+impl Trace {
+    /// Everything, what `--debug` shows.
+    pub const ALL: Trace = Trace {
+        calls: true,
+        stack: true,
+        ops: true,
+    };
+
+    /// Whether anything is traced at all.
+    pub fn is_on(&self) -> bool {
+        self.calls || self.stack || self.ops
+    }
+}
+
+impl FromStr for Trace {
+    type Err = String;
+
+    /// Parses a comma-separated list like `calls,stack`.
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        let mut trace = Trace::default();
+        for part in list.split(',').map(str::trim) {
+            match part {
+                "calls" => trace.calls = true,
+                "stack" => trace.stack = true,
+                "ops" => trace.ops = true,
+                _ => {
+                    return Err(format!(
+                        "Não sei rastrear '{}', mano! Tem calls, stack e ops.",
+                        part
+                    ));
+                }
+            }
+        }
+        Ok(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_single_kind() {
+        assert_eq!(
+            "calls".parse(),
+            Ok(Trace {
+                calls: true,
+                ..Trace::default()
+            })
+        );
+    }
+
+    #[test]
+    fn parses_a_list_of_kinds() {
+        assert_eq!(
+            "stack, ops".parse(),
+            Ok(Trace {
+                stack: true,
+                ops: true,
+                ..Trace::default()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_kinds() {
+        let err = "calls,tudo".parse::<Trace>().unwrap_err();
+        assert!(err.contains("'tudo'"));
+    }
+
+    #[test]
+    fn default_traces_nothing() {
+        assert!(!Trace::default().is_on());
+        assert!(Trace::ALL.is_on());
+    }
+}
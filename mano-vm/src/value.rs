@@ -1,2 +1,89 @@
+use std::fmt;
+use std::rc::Rc;
+
 /// Runtime value type for the mano VM.
-pub type Value = f64;
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Nil,
+    Number(f64),
+    /// Texts are shared, copying one onto the stack doesn't copy the text
+    String(Rc<str>),
+}
+
+impl Value {
+    /// Same truthiness as the interpreter: only `nadaNão` and `treta` are falsy.
+    pub fn is_falsey(&self) -> bool {
+        matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    /// What kind of value this is, named like the interpreter's errors do
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "lógico",
+            Value::Nil => "nadaNão",
+            Value::Number(_) => "número",
+            Value::String(_) => "texto",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(true) => write!(f, "firmeza"),
+            Value::Bool(false) => write!(f, "treta"),
+            Value::Nil => write!(f, "nadaNão"),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nil_is_falsey() {
+        assert!(Value::Nil.is_falsey());
+    }
+
+    #[test]
+    fn false_is_falsey() {
+        assert!(Value::Bool(false).is_falsey());
+    }
+
+    #[test]
+    fn true_is_truthy() {
+        assert!(!Value::Bool(true).is_falsey());
+    }
+
+    #[test]
+    fn zero_is_truthy() {
+        assert!(!Value::Number(0.0).is_falsey());
+    }
+
+    #[test]
+    fn display_matches_interpreter() {
+        assert_eq!(Value::Bool(true).to_string(), "firmeza");
+        assert_eq!(Value::Bool(false).to_string(), "treta");
+        assert_eq!(Value::Nil.to_string(), "nadaNão");
+        assert_eq!(Value::Number(2.0).to_string(), "2");
+        assert_eq!(Value::Number(1.5).to_string(), "1.5");
+        assert_eq!(Value::String("oi".into()).to_string(), "oi");
+    }
+
+    #[test]
+    fn values_of_different_types_are_not_equal() {
+        assert_ne!(Value::Nil, Value::Bool(false));
+        assert_ne!(Value::Number(0.0), Value::Bool(false));
+        assert_ne!(Value::String("1".into()), Value::Number(1.0));
+    }
+
+    #[test]
+    fn texts_are_equal_by_content() {
+        assert_eq!(Value::String("oi".into()), Value::String("oi".into()));
+        assert!(!Value::String("".into()).is_falsey());
+    }
+}
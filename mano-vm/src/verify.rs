@@ -0,0 +1,222 @@
+//! Stack-effect verification - simulates stack depth over a chunk using the
+//! opcode table, so codegen bugs show up at compile time instead of as stack
+//! corruption at runtime.
+
+use std::fmt;
+
+use crate::{Chunk, OpCode, OperandKind};
+
+/// A stack imbalance found in a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackError {
+    /// Offset of the offending instruction.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}: {}", self.offset, self.message)
+    }
+}
+
+/// Check every path through the chunk keeps the stack balanced.
+///
+/// Each instruction must be reached with the same depth from every path,
+/// never pop more than is on the stack, and `OP_RETURN` must find exactly
+/// the one value it returns. A script that ends in statements runs off the
+/// end of its chunk instead, with nothing left on the stack. Returns the
+/// maximum depth reached.
+pub fn verify_stack(chunk: &Chunk) -> Result<usize, StackError> {
+    let code = &chunk.code;
+    let mut depths: Vec<Option<usize>> = vec![None; code.len()];
+    let mut worklist = vec![(0, 0)];
+    let mut max_depth = 0;
+
+    while let Some((offset, depth)) = worklist.pop() {
+        if offset == code.len() && depth == 0 {
+            continue;
+        }
+        if offset >= code.len() {
+            return Err(StackError {
+                offset,
+                message: format!(
+                    "execution runs past the end of the chunk with {} values on the stack",
+                    depth
+                ),
+            });
+        }
+        match depths[offset] {
+            Some(seen) if seen == depth => continue,
+            Some(seen) => {
+                return Err(StackError {
+                    offset,
+                    message: format!("reached with depth {} and {}", seen, depth),
+                });
+            }
+            None => depths[offset] = Some(depth),
+        }
+
+        let Some(opcode) = OpCode::from_byte(code[offset]) else {
+            return Err(StackError {
+                offset,
+                message: format!("unknown opcode {}", code[offset]),
+            });
+        };
+        let info = opcode.info();
+
+        if (info.pops as usize) > depth {
+            return Err(StackError {
+                offset,
+                message: format!("{} underflows a stack of depth {}", info.name, depth),
+            });
+        }
+        let after = depth - info.pops as usize + info.pushes as usize;
+        max_depth = max_depth.max(after);
+
+        let next = offset + 1 + info.operand.size();
+        match opcode {
+            OpCode::Return => {
+                if depth != 1 {
+                    return Err(StackError {
+                        offset,
+                        message: format!("OP_RETURN with {} values on the stack", depth),
+                    });
+                }
+            }
+            OpCode::Loop => {
+                let jump = (code[offset + 1] as usize) << 8 | code[offset + 2] as usize;
+                worklist.push((next - jump, after));
+            }
+            _ if info.operand == OperandKind::Jump => {
+                let jump = (code[offset + 1] as usize) << 8 | code[offset + 2] as usize;
+                worklist.push((next + jump, after));
+                if opcode != OpCode::Jump {
+                    worklist.push((next, after));
+                }
+            }
+            _ => worklist.push((next, after)),
+        }
+    }
+
+    Ok(max_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    fn chunk(build: impl FnOnce(&mut Chunk)) -> Chunk {
+        let mut chunk = Chunk::new();
+        build(&mut chunk);
+        chunk
+    }
+
+    #[test]
+    fn balanced_chunk_passes() {
+        let chunk = chunk(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(2.0), 0..0);
+            c.write(OpCode::Add.into(), 0..0);
+            c.write(OpCode::Return.into(), 0..0);
+        });
+        assert_eq!(verify_stack(&chunk), Ok(2));
+    }
+
+    #[test]
+    fn underflow_is_reported() {
+        let chunk = chunk(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Add.into(), 0..0);
+            c.write(OpCode::Return.into(), 0..0);
+        });
+        let err = verify_stack(&chunk).unwrap_err();
+        assert_eq!(err.offset, 2);
+        assert!(err.message.contains("OP_ADD"));
+    }
+
+    #[test]
+    fn leftover_values_at_return_are_reported() {
+        let chunk = chunk(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Return.into(), 0..0);
+        });
+        let err = verify_stack(&chunk).unwrap_err();
+        assert!(err.message.contains("2 values"));
+    }
+
+    #[test]
+    fn missing_return_is_reported() {
+        let chunk = chunk(|c| c.write(OpCode::Nil.into(), 0..0));
+        let err = verify_stack(&chunk).unwrap_err();
+        assert!(err.message.contains("past the end"));
+    }
+
+    #[test]
+    fn running_off_the_end_with_an_empty_stack_passes() {
+        let chunk = chunk(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Print.into(), 0..0);
+        });
+        assert_eq!(verify_stack(&chunk), Ok(1));
+    }
+
+    #[test]
+    fn loops_that_leave_values_behind_are_reported() {
+        // Each time around pushes one more nadaNão
+        let chunk = chunk(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Loop.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(4, 0..0);
+        });
+        let err = verify_stack(&chunk).unwrap_err();
+        assert_eq!(err.offset, 0);
+        assert!(err.message.contains("depth 0 and 1"), "got: {}", err);
+    }
+
+    #[test]
+    fn branches_with_different_depths_are_reported() {
+        // firmeza ? <nothing> : 1 -> then branch forgets to push
+        let chunk = chunk(|c| {
+            c.write(OpCode::True.into(), 0..0);
+            c.write(OpCode::JumpIfFalse.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(4, 0..0);
+            c.write(OpCode::Pop.into(), 0..0);
+            c.write(OpCode::Jump.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(3, 0..0);
+            c.write(OpCode::Pop.into(), 0..0);
+            c.write(OpCode::True.into(), 0..0);
+            c.write(OpCode::Return.into(), 0..0);
+            c.write(OpCode::Return.into(), 0..0);
+        });
+        let err = verify_stack(&chunk).unwrap_err();
+        assert!(err.message.contains("depth"), "got: {}", err);
+    }
+
+    #[test]
+    fn unknown_opcode_is_reported() {
+        let chunk = chunk(|c| c.write(0xFF, 0..0));
+        let err = verify_stack(&chunk).unwrap_err();
+        assert_eq!(err.to_string(), "0000: unknown opcode 255");
+    }
+
+    #[test]
+    fn compiled_loops_pass() {
+        let source = "segueOFluxo (firmeza) { sePá (1 tamoJunto 2) saiFora; vacilou salve 3; }";
+        let chunk = crate::compile(source, &Default::default()).unwrap().chunk;
+        assert!(verify_stack(&chunk).is_ok());
+    }
+
+    #[test]
+    fn compiled_ternary_passes() {
+        let chunk = crate::compile("firmeza ? 1, 2 : treta ? 3 : 4", &Default::default())
+            .unwrap()
+            .chunk;
+        assert!(verify_stack(&chunk).is_ok());
+    }
+}
@@ -1,8 +1,11 @@
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::{Chunk, OpCode, disassemble_instruction};
+use crate::value::Value;
+use crate::{Chunk, OpCode, Trace, disassemble_instruction};
 
-use mano::ManoError;
+use mano::{LineIndex, ManoError, SandboxPolicy, TokenType};
 
 pub type InterpretResult = Result<(), Vec<ManoError>>;
 
@@ -10,8 +13,12 @@ pub struct VM<'a, W: Write> {
     chunk: &'a Chunk,
     ip: usize,
     output: &'a mut W,
-    trace: bool,
-    stack: Vec<f64>,
+    trace: Trace,
+    source: Option<&'a str>,
+    stack: Vec<Value>,
+    policy: SandboxPolicy,
+    /// Set on Ctrl+C, checked each time a loop goes around
+    interrupt: Option<Arc<AtomicBool>>,
 }
 
 impl<'a, W: Write> VM<'a, W> {
@@ -20,23 +27,57 @@ impl<'a, W: Write> VM<'a, W> {
             chunk,
             ip: 0,
             output,
-            trace: false,
+            trace: Trace::default(),
+            source: None,
             stack: Vec::new(),
+            policy: SandboxPolicy::default(),
+            interrupt: None,
         }
     }
 
+    /// What natives may do, same as the interpreter's. The VM has no natives
+    /// yet, so there's nothing for it to check.
+    pub fn set_policy(&mut self, policy: SandboxPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> &SandboxPolicy {
+        &self.policy
+    }
+
+    /// Flag that stops the script the next time a loop goes around, like the
+    /// interpreter stops at its next statement
+    pub fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    /// Trace everything or nothing, see [`Self::set_trace_options`].
     pub fn set_trace(&mut self, trace: bool) {
+        self.trace = if trace { Trace::ALL } else { Trace::default() };
+    }
+
+    /// Pick which events to trace.
+    pub fn set_trace_options(&mut self, trace: Trace) {
         self.trace = trace;
     }
 
-    pub fn push(&mut self, value: f64) {
+    /// Source the chunk was compiled from, used for line numbers in stack traces.
+    pub fn set_source(&mut self, source: &'a str) {
+        self.source = Some(source);
+    }
+
+    pub fn push(&mut self, value: Value) {
         self.stack.push(value);
     }
 
-    pub fn pop(&mut self) -> f64 {
+    pub fn pop(&mut self) -> Value {
         self.stack.pop().expect("Stack underflow")
     }
 
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
     pub fn trace_stack(&mut self) {
         write!(self.output, "          ").unwrap();
         for value in &self.stack {
@@ -46,20 +87,64 @@ impl<'a, W: Write> VM<'a, W> {
     }
 
     pub fn interpret(&mut self) -> InterpretResult {
-        if self.trace {
+        if self.trace.is_on() {
             writeln!(self.output, "== trace ==").unwrap();
         }
+        if self.trace.calls {
+            writeln!(self.output, "-> script").unwrap();
+        }
         self.run()
     }
 
+    /// Traces the script returning, with the value it returns if any
+    fn trace_return(&mut self) {
+        if !self.trace.calls {
+            return;
+        }
+        match self.stack.last() {
+            Some(value) => writeln!(self.output, "<- script = {value}").unwrap(),
+            None => writeln!(self.output, "<- script").unwrap(),
+        }
+    }
+
     fn run(&mut self) -> InterpretResult {
+        // Stack depth predicted by the opcode table, checked before the next instruction
+        #[cfg(debug_assertions)]
+        let mut expected_depth: Option<(usize, &'static str)> = None;
+
         loop {
-            if self.trace {
+            #[cfg(debug_assertions)]
+            if let Some((depth, name)) = expected_depth {
+                debug_assert_eq!(
+                    self.stack.len(),
+                    depth,
+                    "{} left the stack at the wrong depth",
+                    name
+                );
+            }
+
+            // A script that ends in statements runs off the end of its chunk
+            if self.ip == self.chunk.code.len() {
+                self.trace_return();
+                return Ok(());
+            }
+
+            if self.trace.stack {
                 self.trace_stack();
+            }
+            if self.trace.ops {
                 let (line, _) = disassemble_instruction(self.chunk, self.ip);
                 write!(self.output, "{line}").unwrap();
             }
             let byte = self.read_byte();
+
+            #[cfg(debug_assertions)]
+            {
+                let info = OpCode::from(byte).info();
+                let depth = self.stack.len() as isize + info.stack_effect();
+                expected_depth = Some((depth.max(0) as usize, info.name));
+            }
+
             match byte {
                 b if b == OpCode::Constant as u8 => {
                     let constant = self.read_constant();
@@ -69,38 +154,114 @@ impl<'a, W: Write> VM<'a, W> {
                     let constant = self.read_constant_long();
                     self.push(constant);
                 }
+                b if b == OpCode::Nil as u8 => self.push(Value::Nil),
+                b if b == OpCode::True as u8 => self.push(Value::Bool(true)),
+                b if b == OpCode::False as u8 => self.push(Value::Bool(false)),
                 b if b == OpCode::Negate as u8 => {
+                    let value = match self.peek(0) {
+                        Value::Number(value) => *value,
+                        other => {
+                            let message =
+                                format!("Só dá pra negar número, tio! Veio {}.", other.kind_name());
+                            return self.runtime_error(&message);
+                        }
+                    };
+                    self.pop();
+                    self.push(Value::Number(-value));
+                }
+                b if b == OpCode::Not as u8 => {
                     let value = self.pop();
-                    self.push(-value);
+                    self.push(Value::Bool(value.is_falsey()));
                 }
-                b if b == OpCode::Add as u8 => {
+                b if b == OpCode::Equal as u8 => {
                     let b = self.pop();
                     let a = self.pop();
-                    self.push(a + b);
+                    self.push(Value::Bool(a == b));
+                }
+                b if b == OpCode::Add as u8 => {
+                    let result = match (self.peek(1), self.peek(0)) {
+                        (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                        (Value::String(a), Value::String(b)) => {
+                            Value::String(format!("{a}{b}").into())
+                        }
+                        (a, b) => {
+                            let message = format!(
+                                "Só dá pra somar número com número ou texto com texto, chapa! Veio {} com {}.",
+                                a.kind_name(),
+                                b.kind_name()
+                            );
+                            return self.runtime_error(&message);
+                        }
+                    };
+                    self.binary_result(result);
                 }
                 b if b == OpCode::Subtract as u8 => {
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.push(a - b);
+                    let (a, b) = self.number_operands()?;
+                    self.binary_result(Value::Number(a - b));
                 }
                 b if b == OpCode::Multiply as u8 => {
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.push(a * b);
+                    let (a, b) = self.number_operands()?;
+                    self.binary_result(Value::Number(a * b));
                 }
                 b if b == OpCode::Divide as u8 => {
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.push(a / b);
+                    let (a, b) = self.number_operands()?;
+                    self.binary_result(Value::Number(a / b));
                 }
                 b if b == OpCode::Modulo as u8 => {
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.push(a % b);
+                    let (a, b) = self.number_operands()?;
+                    self.binary_result(Value::Number(a % b));
+                }
+                b if b == OpCode::Greater as u8 => {
+                    let (a, b) = self.compared_operands()?;
+                    self.binary_result(Value::Bool(a > b));
+                }
+                b if b == OpCode::GreaterEqual as u8 => {
+                    let (a, b) = self.compared_operands()?;
+                    self.binary_result(Value::Bool(a >= b));
+                }
+                b if b == OpCode::Less as u8 => {
+                    let (a, b) = self.compared_operands()?;
+                    self.binary_result(Value::Bool(a < b));
+                }
+                b if b == OpCode::LessEqual as u8 => {
+                    let (a, b) = self.compared_operands()?;
+                    self.binary_result(Value::Bool(a <= b));
+                }
+                b if b == OpCode::BitAnd as u8 => self.bitwise(TokenType::Ampersand)?,
+                b if b == OpCode::BitOr as u8 => self.bitwise(TokenType::Pipe)?,
+                b if b == OpCode::BitXor as u8 => self.bitwise(TokenType::Caret)?,
+                b if b == OpCode::ShiftLeft as u8 => self.bitwise(TokenType::LessLess)?,
+                b if b == OpCode::ShiftRight as u8 => self.bitwise(TokenType::GreaterGreater)?,
+                b if b == OpCode::Pop as u8 => {
+                    self.pop();
+                }
+                b if b == OpCode::Jump as u8 => {
+                    let offset = self.read_short();
+                    self.ip += offset;
+                }
+                b if b == OpCode::JumpIfFalse as u8 => {
+                    let offset = self.read_short();
+                    if self.peek(0).is_falsey() {
+                        self.ip += offset;
+                    }
+                }
+                b if b == OpCode::Loop as u8 => {
+                    if self.interrupted() {
+                        return Err(vec![ManoError::Interrupted {
+                            span: self.chunk.get_span(self.ip - 1),
+                        }]);
+                    }
+                    let offset = self.read_short();
+                    self.ip -= offset;
+                }
+                b if b == OpCode::Stringify as u8 => {
+                    let text = self.pop().to_string();
+                    self.push(Value::String(text.into()));
                 }
+                b if b == OpCode::Print as u8 => self.print()?,
                 b if b == OpCode::Return as u8 => {
-                    let value = self.pop();
-                    writeln!(self.output, "{value}").unwrap();
+                    self.trace_return();
+                    self.print()?;
                     return Ok(());
                 }
                 _ => unreachable!("Unknown opcode: {}", byte),
@@ -108,23 +269,117 @@ impl<'a, W: Write> VM<'a, W> {
         }
     }
 
+    /// Pops the top value and writes it on its own line
+    fn print(&mut self) -> InterpretResult {
+        let value = self.pop();
+        writeln!(self.output, "{value}").map_err(|source| {
+            vec![ManoError::Output {
+                source,
+                span: self.chunk.get_span(self.ip - 1),
+            }]
+        })
+    }
+
+    /// Whether Ctrl+C was pressed, clearing it for the next run
+    fn interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|flag| flag.swap(false, Ordering::SeqCst))
+    }
+
     fn read_byte(&mut self) -> u8 {
         let byte = self.chunk.code[self.ip];
         self.ip += 1;
         byte
     }
 
-    fn read_constant(&mut self) -> f64 {
+    /// Both operands of a binary op, left first; leaves them on the stack
+    fn number_operands(&mut self) -> Result<(f64, f64), Vec<ManoError>> {
+        match (self.peek(1), self.peek(0)) {
+            (Value::Number(a), Value::Number(b)) => Ok((*a, *b)),
+            (a, b) => self.runtime_error(&format!(
+                "Os dois lados precisam ser número, irmão! Veio {} com {}.",
+                a.kind_name(),
+                b.kind_name()
+            )),
+        }
+    }
+
+    /// Both operands of `<`, `>`, `<=` or `>=`, like [`Self::number_operands`]
+    fn compared_operands(&mut self) -> Result<(f64, f64), Vec<ManoError>> {
+        match (self.peek(1), self.peek(0)) {
+            (Value::Number(a), Value::Number(b)) => Ok((*a, *b)),
+            (a, b) => self.runtime_error(&format!(
+                "Não dá pra comparar {} com {}, mano!",
+                a.kind_name(),
+                b.kind_name()
+            )),
+        }
+    }
+
+    /// A bitwise op, with the same rules as the interpreter
+    fn bitwise(&mut self, operator: TokenType) -> Result<(), Vec<ManoError>> {
+        let (a, b) = self.number_operands()?;
+        match mano::bitwise::apply(operator, a, b) {
+            Ok(n) => {
+                self.binary_result(Value::Number(n));
+                Ok(())
+            }
+            Err(message) => self.runtime_error(&message),
+        }
+    }
+
+    /// Replace the two operands with the result
+    fn binary_result(&mut self, value: Value) {
+        self.pop();
+        self.pop();
+        self.push(value);
+    }
+
+    fn runtime_error<T>(&mut self, message: &str) -> Result<T, Vec<ManoError>> {
+        let span = self.chunk.get_span(self.ip - 1);
+        let trace = self.stack_trace();
+        self.stack.clear();
+        Err(vec![ManoError::Runtime {
+            message: format!("{message}\n{trace}"),
+            span,
+        }])
+    }
+
+    /// One line per active call frame, innermost first.
+    ///
+    /// There are no function calls in the VM yet, so the only frame is the
+    /// top-level script.
+    fn stack_trace(&self) -> String {
+        let span = self.chunk.get_span(self.ip - 1);
+        let location = match self.source {
+            Some(source) => format!(
+                "linha {}",
+                LineIndex::new(source).line_col(span.start).line + 1
+            ),
+            None => format!("byte {}", span.start),
+        };
+        format!("[{location}] no script")
+    }
+
+    /// 16-bit big-endian operand
+    fn read_short(&mut self) -> usize {
+        let high = self.read_byte() as usize;
+        let low = self.read_byte() as usize;
+        (high << 8) | low
+    }
+
+    fn read_constant(&mut self) -> Value {
         let index = self.read_byte() as usize;
-        self.chunk.constants[index]
+        self.chunk.constants[index].clone()
     }
 
-    fn read_constant_long(&mut self) -> f64 {
+    fn read_constant_long(&mut self) -> Value {
         let b0 = self.read_byte() as usize;
         let b1 = self.read_byte() as usize;
         let b2 = self.read_byte() as usize;
         let index = b0 | (b1 << 8) | (b2 << 16);
-        self.chunk.constants[index]
+        self.chunk.constants[index].clone()
     }
 }
 
@@ -143,7 +398,7 @@ mod tests {
     #[test]
     fn vm_interpret_returns_ok() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(0.0, 0..0);
+        chunk.write_constant(Value::Number(0.0), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -153,7 +408,7 @@ mod tests {
     #[test]
     fn vm_return_pops_and_prints() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -166,9 +421,9 @@ mod tests {
         let mut chunk = Chunk::new();
         // Fill up 256 constants to force OP_CONSTANT_LONG
         for i in 0..256 {
-            chunk.add_constant(i as f64);
+            chunk.add_constant(Value::Number(i as f64));
         }
-        chunk.write_constant(999.0, 0..0);
+        chunk.write_constant(Value::Number(999.0), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -179,7 +434,7 @@ mod tests {
     #[test]
     fn vm_trace_prints_instructions() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -193,7 +448,7 @@ mod tests {
     #[test]
     fn vm_trace_prints_header() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -203,15 +458,51 @@ mod tests {
         assert!(out.starts_with("== trace ==\n"));
     }
 
+    #[test]
+    fn vm_trace_calls_shows_only_entering_and_returning() {
+        let mut chunk = Chunk::new();
+        chunk.write_constant(Value::Number(1.2), 0..0);
+        chunk.write(crate::OpCode::Return.into(), 0..0);
+        let mut output = Vec::new();
+        let mut vm = VM::new(&chunk, &mut output);
+        vm.set_trace_options(Trace {
+            calls: true,
+            ..Trace::default()
+        });
+        vm.interpret().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "== trace ==\n-> script\n<- script = 1.2\n1.2\n"
+        );
+    }
+
+    #[test]
+    fn vm_trace_ops_skips_the_stack() {
+        let mut chunk = Chunk::new();
+        chunk.write_constant(Value::Number(1.2), 0..0);
+        chunk.write(crate::OpCode::Pop.into(), 0..0);
+        let mut output = Vec::new();
+        let mut vm = VM::new(&chunk, &mut output);
+        vm.set_trace_options(Trace {
+            ops: true,
+            ..Trace::default()
+        });
+        vm.interpret().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "== trace ==\n0000 0..0 OP_CONSTANT         0 '1.2'\n0002    | OP_POP\n"
+        );
+    }
+
     #[test]
     fn vm_push_and_pop() {
         let chunk = Chunk::new();
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
-        vm.push(1.2);
-        vm.push(3.4);
-        assert_eq!(vm.pop(), 3.4);
-        assert_eq!(vm.pop(), 1.2);
+        vm.push(Value::Number(1.2));
+        vm.push(Value::Number(3.4));
+        assert_eq!(vm.pop(), Value::Number(3.4));
+        assert_eq!(vm.pop(), Value::Number(1.2));
     }
 
     #[test]
@@ -229,8 +520,8 @@ mod tests {
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
         vm.set_trace(true);
-        vm.push(1.2);
-        vm.push(3.4);
+        vm.push(Value::Number(1.2));
+        vm.push(Value::Number(3.4));
         vm.trace_stack();
         assert_eq!(
             String::from_utf8(output).unwrap(),
@@ -241,36 +532,36 @@ mod tests {
     #[test]
     fn vm_constant_pushes_to_stack() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
-        chunk.write_constant(3.4, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
+        chunk.write_constant(Value::Number(3.4), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
         let _ = vm.interpret();
         // Return popped 3.4, 1.2 remains
-        assert_eq!(vm.stack, vec![1.2]);
+        assert_eq!(vm.stack, vec![Value::Number(1.2)]);
     }
 
     #[test]
     fn vm_constant_long_pushes_to_stack() {
         let mut chunk = Chunk::new();
         for i in 0..256 {
-            chunk.add_constant(i as f64);
+            chunk.add_constant(Value::Number(i as f64));
         }
-        chunk.write_constant(888.0, 0..0);
-        chunk.write_constant(999.0, 0..0);
+        chunk.write_constant(Value::Number(888.0), 0..0);
+        chunk.write_constant(Value::Number(999.0), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
         let _ = vm.interpret();
         // Return popped 999.0, 888.0 remains
-        assert_eq!(vm.stack, vec![888.0]);
+        assert_eq!(vm.stack, vec![Value::Number(888.0)]);
     }
 
     #[test]
     fn vm_run_traces_stack_before_instruction() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
         let mut vm = VM::new(&chunk, &mut output);
@@ -286,7 +577,7 @@ mod tests {
     #[test]
     fn vm_negate_negates_top_of_stack() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(3.4, 0..0);
+        chunk.write_constant(Value::Number(3.4), 0..0);
         chunk.write(crate::OpCode::Negate.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -298,8 +589,8 @@ mod tests {
     #[test]
     fn vm_add_adds_top_two_values() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(1.2, 0..0);
-        chunk.write_constant(3.4, 0..0);
+        chunk.write_constant(Value::Number(1.2), 0..0);
+        chunk.write_constant(Value::Number(3.4), 0..0);
         chunk.write(crate::OpCode::Add.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -311,8 +602,8 @@ mod tests {
     #[test]
     fn vm_subtract_subtracts_top_two_values() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(5.0, 0..0);
-        chunk.write_constant(3.0, 0..0);
+        chunk.write_constant(Value::Number(5.0), 0..0);
+        chunk.write_constant(Value::Number(3.0), 0..0);
         chunk.write(crate::OpCode::Subtract.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -324,8 +615,8 @@ mod tests {
     #[test]
     fn vm_multiply_multiplies_top_two_values() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(3.0, 0..0);
-        chunk.write_constant(4.0, 0..0);
+        chunk.write_constant(Value::Number(3.0), 0..0);
+        chunk.write_constant(Value::Number(4.0), 0..0);
         chunk.write(crate::OpCode::Multiply.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -337,8 +628,8 @@ mod tests {
     #[test]
     fn vm_divide_divides_top_two_values() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(10.0, 0..0);
-        chunk.write_constant(4.0, 0..0);
+        chunk.write_constant(Value::Number(10.0), 0..0);
+        chunk.write_constant(Value::Number(4.0), 0..0);
         chunk.write(crate::OpCode::Divide.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -350,8 +641,8 @@ mod tests {
     #[test]
     fn vm_modulo_computes_remainder() {
         let mut chunk = Chunk::new();
-        chunk.write_constant(10.0, 0..0);
-        chunk.write_constant(3.0, 0..0);
+        chunk.write_constant(Value::Number(10.0), 0..0);
+        chunk.write_constant(Value::Number(3.0), 0..0);
         chunk.write(crate::OpCode::Modulo.into(), 0..0);
         chunk.write(crate::OpCode::Return.into(), 0..0);
         let mut output = Vec::new();
@@ -359,4 +650,305 @@ mod tests {
         let _ = vm.interpret();
         assert_eq!(String::from_utf8(output).unwrap(), "1\n");
     }
+
+    /// Runs a chunk built by `build` followed by OP_RETURN
+    fn run_ops(build: impl FnOnce(&mut Chunk)) -> (InterpretResult, String) {
+        let mut chunk = Chunk::new();
+        build(&mut chunk);
+        chunk.write(OpCode::Return.into(), 0..0);
+        let mut output = Vec::new();
+        let result = VM::new(&chunk, &mut output).interpret();
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    fn runtime_message(result: InterpretResult) -> String {
+        match result {
+            Err(errors) => match &errors[0] {
+                ManoError::Runtime { message, .. } => message.clone(),
+                other => panic!("Expected Runtime error, got {:?}", other),
+            },
+            Ok(()) => panic!("Expected runtime error"),
+        }
+    }
+
+    #[test]
+    fn vm_nil_pushes_nil() {
+        let (_, out) = run_ops(|c| c.write(OpCode::Nil.into(), 0..0));
+        assert_eq!(out, "nadaNão\n");
+    }
+
+    #[test]
+    fn vm_true_pushes_firmeza() {
+        let (_, out) = run_ops(|c| c.write(OpCode::True.into(), 0..0));
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_false_pushes_treta() {
+        let (_, out) = run_ops(|c| c.write(OpCode::False.into(), 0..0));
+        assert_eq!(out, "treta\n");
+    }
+
+    #[test]
+    fn vm_not_nil_is_true() {
+        let (_, out) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Not.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_not_false_is_true() {
+        let (_, out) = run_ops(|c| {
+            c.write(OpCode::False.into(), 0..0);
+            c.write(OpCode::Not.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_not_zero_is_false() {
+        // Like the interpreter, only nil and false are falsy
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(0.0), 0..0);
+            c.write(OpCode::Not.into(), 0..0);
+        });
+        assert_eq!(out, "treta\n");
+    }
+
+    #[test]
+    fn vm_equal_compares_numbers() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Equal.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_equal_different_types_is_false() {
+        let (_, out) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::False.into(), 0..0);
+            c.write(OpCode::Equal.into(), 0..0);
+        });
+        assert_eq!(out, "treta\n");
+    }
+
+    #[test]
+    fn vm_equal_nil_nil_is_true() {
+        let (_, out) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Equal.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_greater_compares_numbers() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(2.0), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Greater.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_less_compares_numbers() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(2.0), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Less.into(), 0..0);
+        });
+        assert_eq!(out, "treta\n");
+    }
+
+    #[test]
+    fn vm_greater_equal_includes_equal() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::GreaterEqual.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_less_equal_includes_equal() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::LessEqual.into(), 0..0);
+        });
+        assert_eq!(out, "firmeza\n");
+    }
+
+    #[test]
+    fn vm_greater_equal_with_nan_is_false() {
+        // Same as the interpreter, which compares with >= directly
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(f64::NAN), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::GreaterEqual.into(), 0..0);
+        });
+        assert_eq!(out, "treta\n");
+    }
+
+    #[test]
+    fn vm_comparison_requires_numbers() {
+        let (result, _) = run_ops(|c| {
+            c.write(OpCode::True.into(), 0..0);
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Greater.into(), 3..4);
+        });
+        assert!(
+            runtime_message(result).starts_with("Não dá pra comparar lógico com número, mano!")
+        );
+    }
+
+    #[test]
+    fn vm_negate_requires_number() {
+        let (result, _) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::Negate.into(), 0..0);
+        });
+        assert!(runtime_message(result).starts_with("Só dá pra negar número, tio!"));
+    }
+
+    #[test]
+    fn vm_add_requires_numbers() {
+        let (result, _) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::False.into(), 0..0);
+            c.write(OpCode::Add.into(), 0..0);
+        });
+        assert!(runtime_message(result).contains("somar"));
+    }
+
+    #[test]
+    fn vm_runtime_error_uses_instruction_span() {
+        let (result, _) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..3);
+            c.write(OpCode::Negate.into(), 4..5);
+        });
+        match result {
+            Err(errors) => match &errors[0] {
+                ManoError::Runtime { span, .. } => assert_eq!(*span, 4..5),
+                _ => panic!("Expected Runtime error"),
+            },
+            Ok(()) => panic!("Expected runtime error"),
+        }
+    }
+
+    #[test]
+    fn vm_pop_discards_top_value() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(2.0), 0..0);
+            c.write(OpCode::Pop.into(), 0..0);
+        });
+        assert_eq!(out, "1\n");
+    }
+
+    #[test]
+    fn vm_jump_skips_instructions() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::Jump.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(2, 0..0);
+            c.write_constant(Value::Number(2.0), 0..0);
+        });
+        assert_eq!(out, "1\n");
+    }
+
+    #[test]
+    fn vm_jump_if_false_jumps_on_falsy_and_keeps_condition() {
+        let (_, out) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..0);
+            c.write(OpCode::JumpIfFalse.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(1, 0..0);
+            c.write(OpCode::Pop.into(), 0..0);
+        });
+        assert_eq!(out, "nadaNão\n");
+    }
+
+    #[test]
+    fn vm_jump_if_false_falls_through_on_truthy() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write(OpCode::True.into(), 0..0);
+            c.write(OpCode::JumpIfFalse.into(), 0..0);
+            c.write(0, 0..0);
+            c.write(1, 0..0);
+            c.write(OpCode::Pop.into(), 0..0);
+        });
+        assert_eq!(out, "1\n");
+    }
+
+    #[test]
+    fn vm_print_writes_and_pops_the_top_value() {
+        let (_, out) = run_ops(|c| {
+            c.write_constant(Value::Number(1.0), 0..0);
+            c.write_constant(Value::Number(2.0), 0..0);
+            c.write(OpCode::Print.into(), 0..0);
+        });
+        assert_eq!(out, "2\n1\n");
+    }
+
+    #[test]
+    fn vm_runs_off_the_end_of_a_chunk_without_return() {
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::True.into(), 0..0);
+        chunk.write(OpCode::Print.into(), 0..0);
+        let mut output = Vec::new();
+        VM::new(&chunk, &mut output).interpret().unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "firmeza\n");
+    }
+
+    #[test]
+    fn vm_loop_stops_when_interrupted_and_clears_the_flag() {
+        // segueOFluxo (firmeza) {}
+        let mut chunk = Chunk::new();
+        chunk.write(OpCode::Nil.into(), 0..0);
+        chunk.write(OpCode::Pop.into(), 0..0);
+        chunk.write(OpCode::Loop.into(), 2..13);
+        chunk.write(0, 2..13);
+        chunk.write(5, 2..13);
+        let flag = Arc::new(AtomicBool::new(true));
+        let mut output = Vec::new();
+        let mut vm = VM::new(&chunk, &mut output);
+        vm.set_interrupt(Arc::clone(&flag));
+        let errors = vm.interpret().unwrap_err();
+        assert!(matches!(errors[0], ManoError::Interrupted { ref span } if *span == (2..13)));
+        assert!(!flag.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn vm_runtime_error_appends_stack_trace() {
+        let (result, _) = run_ops(|c| {
+            c.write(OpCode::Nil.into(), 0..3);
+            c.write(OpCode::Negate.into(), 4..5);
+        });
+        assert_eq!(
+            runtime_message(result),
+            "Só dá pra negar número, tio! Veio nadaNão.\n[byte 4] no script"
+        );
+    }
+
+    #[test]
+    fn vm_stack_trace_uses_line_numbers_from_source() {
+        let source = "1 +\n-nadaNão";
+        let chunk = crate::compile(source, &Default::default()).unwrap().chunk;
+        let mut output = Vec::new();
+        let mut vm = VM::new(&chunk, &mut output);
+        vm.set_source(source);
+        let message = runtime_message(vm.interpret());
+        assert!(message.ends_with("\n[linha 2] no script"), "got: {message}");
+    }
 }
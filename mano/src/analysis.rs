@@ -0,0 +1,203 @@
+use crate::ast::{Span, Stmt};
+use crate::error::ManoError;
+use crate::resolver::{Resolutions, Resolver};
+use crate::scope::{Declaration, ScopeTree};
+
+/// Points at a declaration: the scope it lives in and its position there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeclarationId {
+    pub scope: usize,
+    pub index: usize,
+}
+
+/// A name used in an expression (read or assigned)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+    /// The declaration it binds to, `None` for names declared nowhere (natives,
+    /// typos, or globals defined in another run)
+    pub declaration: Option<DeclarationId>,
+}
+
+/// Everything the resolver learns about a program.
+///
+/// Unlike running the program, analysis never stops at the first problem:
+/// resolution errors are collected alongside the binding facts so editors can
+/// still navigate code that doesn't compile yet.
+#[derive(Debug)]
+pub struct Analysis {
+    scopes: ScopeTree,
+    resolutions: Resolutions,
+    references: Vec<Reference>,
+    errors: Vec<ManoError>,
+}
+
+impl Analysis {
+    pub fn new(statements: &[Stmt]) -> Self {
+        Resolver::new().analyze(statements)
+    }
+
+    pub(crate) fn from_parts(
+        scopes: ScopeTree,
+        resolutions: Resolutions,
+        references: Vec<Reference>,
+        errors: Vec<ManoError>,
+    ) -> Self {
+        let mut analysis = Self {
+            scopes,
+            resolutions,
+            references,
+            errors,
+        };
+        analysis.link_globals();
+        analysis
+    }
+
+    pub fn scopes(&self) -> &ScopeTree {
+        &self.scopes
+    }
+
+    /// Local bindings by reference span, as `(distance, slot)`: how many
+    /// scopes to walk up and the variable's index there. Globals are absent.
+    pub fn resolutions(&self) -> &Resolutions {
+        &self.resolutions
+    }
+
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    /// Resolution errors and lints (unused variables, `toma` outside a fita, ...)
+    pub fn errors(&self) -> &[ManoError] {
+        &self.errors
+    }
+
+    pub fn declaration(&self, id: DeclarationId) -> &Declaration {
+        &self.scopes.scopes()[id.scope].declarations[id.index]
+    }
+
+    /// The declaration named or referenced at the offset
+    pub fn declaration_at(&self, offset: usize) -> Option<DeclarationId> {
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        self.declarations()
+            .find(|&id| contains(&self.declaration(id).span))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|reference| contains(&reference.span))
+                    .and_then(|reference| reference.declaration)
+            })
+    }
+
+    /// References that bind to the declaration, in source order
+    pub fn references_to(&self, id: DeclarationId) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.declaration == Some(id))
+    }
+
+    fn declarations(&self) -> impl Iterator<Item = DeclarationId> + '_ {
+        self.scopes
+            .scopes()
+            .iter()
+            .enumerate()
+            .flat_map(|(scope, s)| {
+                (0..s.declarations.len()).map(move |index| DeclarationId { scope, index })
+            })
+    }
+
+    /// Globals are looked up by name at runtime, so a reference binds to the
+    /// last global declared before it, or the first one for late-bound uses
+    fn link_globals(&mut self) {
+        let globals = &self.scopes.scopes()[0].declarations;
+        for reference in self.references.iter_mut() {
+            if reference.declaration.is_some() {
+                continue;
+            }
+            let same_name = || {
+                globals
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, declaration)| declaration.name == reference.name)
+            };
+            reference.declaration = same_name()
+                .rfind(|(_, declaration)| declaration.span.start <= reference.span.start)
+                .or_else(|| same_name().next())
+                .map(|(index, _)| DeclarationId { scope: 0, index });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Scanner};
+
+    fn analyze(source: &str) -> Analysis {
+        let tokens: Vec<_> = Scanner::new(source).filter_map(|r| r.ok()).collect();
+        let statements = Parser::new(tokens).parse().unwrap_or_default();
+        Analysis::new(&statements)
+    }
+
+    fn reference_spans(analysis: &Analysis, id: DeclarationId) -> Vec<Span> {
+        analysis
+            .references_to(id)
+            .map(|reference| reference.span.clone())
+            .collect()
+    }
+
+    #[test]
+    fn links_global_references_to_declaration() {
+        let source = "seLiga x = 1; salve x; x = 2;";
+        let analysis = analyze(source);
+        let id = analysis.declaration_at(7).unwrap();
+        assert_eq!(analysis.declaration(id).name, "x");
+        assert_eq!(reference_spans(&analysis, id), vec![20..21, 23..24]);
+    }
+
+    #[test]
+    fn shadowed_locals_link_to_their_own_declaration() {
+        let source = "seLiga x = 1; { seLiga x = 2; salve x; } salve x;";
+        let analysis = analyze(source);
+        let inner = analysis.declaration_at(source.find("salve x;").unwrap() + 6);
+        assert_eq!(analysis.declaration(inner.unwrap()).span, 23..24);
+        let outer = analysis.declaration_at(source.rfind('x').unwrap()).unwrap();
+        assert_eq!(analysis.declaration(outer).span, 7..8);
+    }
+
+    #[test]
+    fn local_references_have_resolutions() {
+        let source = "olhaEssaFita f(a) { toma a; }";
+        let analysis = analyze(source);
+        let use_span = 25..26;
+        assert_eq!(analysis.resolutions().get(&use_span), Some(&(0, 0)));
+    }
+
+    #[test]
+    fn late_bound_global_links_to_first_declaration() {
+        let source = "olhaEssaFita f() { toma g(); } olhaEssaFita g() { toma 1; }";
+        let analysis = analyze(source);
+        let id = analysis
+            .declaration_at(source.find("g()").unwrap())
+            .unwrap();
+        assert_eq!(
+            analysis.declaration(id).span.start,
+            source.rfind('g').unwrap()
+        );
+    }
+
+    #[test]
+    fn undeclared_names_have_no_declaration() {
+        let analysis = analyze("salve fazTeuCorre();");
+        assert_eq!(analysis.references().len(), 1);
+        assert!(analysis.references()[0].declaration.is_none());
+    }
+
+    #[test]
+    fn analysis_keeps_errors_and_bindings() {
+        let analysis = analyze("{ seLiga x = 1; } salve y;");
+        assert_eq!(analysis.errors().len(), 1);
+        assert_eq!(analysis.references().len(), 1);
+    }
+}
@@ -29,6 +29,8 @@ pub enum Expr {
     },
     Assign {
         name: Token,
+        /// The `+` of `x += 1`; `None` for a plain `=`
+        operator: Option<Token>,
         value: Box<Expr>,
     },
     Logical {
@@ -52,6 +54,29 @@ pub enum Expr {
     Set {
         object: Box<Expr>,
         name: Token,
+        /// The `+` of `obj.campo += 1`; `None` for a plain `=`
+        operator: Option<Token>,
+        value: Box<Expr>,
+    },
+    /// `[1, 2, 3]`
+    ListLiteral {
+        /// The closing `]`
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
+    /// `lista[0]`
+    Index {
+        object: Box<Expr>,
+        /// The closing `]`
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    IndexSet {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        /// The `+` of `lista[0] += 1`; `None` for a plain `=`
+        operator: Option<Token>,
         value: Box<Expr>,
     },
     This {
@@ -64,6 +89,10 @@ pub enum Expr {
     Interpolation {
         parts: Vec<InterpolationPart>,
     },
+    /// Placeholder for an expression that failed to parse
+    Error {
+        span: Span,
+    },
 }
 
 /// A part of an interpolated string
@@ -105,9 +134,17 @@ pub enum Stmt {
     While {
         condition: Expr,
         body: Box<Stmt>,
+        /// The increment of a `seVira`, run after the body even when it
+        /// stops early with `segueReto`
+        increment: Option<Box<Stmt>>,
         span: Span,
     },
     Break {
+        keyword: Token,
+        span: Span,
+    },
+    Continue {
+        keyword: Token,
         span: Span,
     },
     Else {
@@ -120,6 +157,10 @@ pub enum Stmt {
         body: Vec<Stmt>,
         is_static: bool,
         is_getter: bool,
+        /// A `memoizado` getter, computed once per instance until a field changes
+        is_memoized: bool,
+        /// The last parameter is `...nome`, taking the arguments left as a lista
+        is_variadic: bool,
         span: Span,
     },
     Return {
@@ -133,6 +174,40 @@ pub enum Stmt {
         methods: Vec<Stmt>,
         span: Span,
     },
+    /// `opcoes Cor { Vermelho, Verde }`: a bagulho whose only paradas are its
+    /// options, each one frozen
+    Enum {
+        name: Token,
+        variants: Vec<Token>,
+        span: Span,
+    },
+    /// `chama "util.mano";`: runs another file once, declaring its globals
+    Import {
+        keyword: Token,
+        /// The string token with the module's path
+        path: Token,
+        span: Span,
+    },
+    /// `tentaAi { ... } seDeuRuim (erro) { ... }`: runs the handler with
+    /// what went wrong when the body fails
+    Try {
+        body: Box<Stmt>,
+        /// The variable the handler gets the error in
+        name: Token,
+        handler: Box<Stmt>,
+        span: Span,
+    },
+    /// `joga valor;`: stops with an error carrying the value
+    Throw {
+        keyword: Token,
+        value: Expr,
+        span: Span,
+    },
+    /// Placeholder for a statement that failed to parse, so tools keep the
+    /// statements around it
+    Error {
+        span: Span,
+    },
 }
 
 impl Stmt {
@@ -150,7 +225,17 @@ impl Stmt {
                 }
                 children
             }
-            Stmt::While { body, .. } | Stmt::Else { body, .. } => vec![body.as_ref()],
+            Stmt::While {
+                body, increment, ..
+            } => {
+                let mut children = vec![body.as_ref()];
+                if let Some(increment) = increment {
+                    children.push(increment.as_ref());
+                }
+                children
+            }
+            Stmt::Else { body, .. } => vec![body.as_ref()],
+            Stmt::Try { body, handler, .. } => vec![body.as_ref(), handler.as_ref()],
             _ => vec![],
         }
     }
@@ -190,10 +275,16 @@ impl Stmt {
             | Stmt::If { span, .. }
             | Stmt::While { span, .. }
             | Stmt::Break { span, .. }
+            | Stmt::Continue { span, .. }
             | Stmt::Else { span, .. }
             | Stmt::Function { span, .. }
             | Stmt::Return { span, .. }
-            | Stmt::Class { span, .. } => span.clone(),
+            | Stmt::Class { span, .. }
+            | Stmt::Enum { span, .. }
+            | Stmt::Import { span, .. }
+            | Stmt::Try { span, .. }
+            | Stmt::Throw { span, .. }
+            | Stmt::Error { span } => span.clone(),
         }
     }
 }
@@ -243,15 +334,24 @@ impl Stmt {
         Stmt::While {
             condition,
             body: Box::new(body),
+            increment: None,
             span: 0..0,
         }
     }
 
-    pub fn break_stmt() -> Self {
-        Stmt::Break { span: 0..0 }
+    pub fn break_stmt(keyword: Token) -> Self {
+        Stmt::Break {
+            keyword,
+            span: 0..0,
+        }
     }
 }
 
+/// The operator of a compound assignment, empty for a plain `=`
+fn lexeme(operator: &Option<Token>) -> &str {
+    operator.as_ref().map_or("", |operator| &operator.lexeme)
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -269,7 +369,11 @@ impl fmt::Display for Expr {
             Expr::Literal { value } => write!(f, "{}", value),
             Expr::Grouping { expression } => write!(f, "(group {})", expression),
             Expr::Variable { name } => write!(f, "{}", name.lexeme),
-            Expr::Assign { name, value } => write!(f, "(= {} {})", name.lexeme, value),
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => write!(f, "({}= {} {})", lexeme(operator), name.lexeme, value),
             Expr::Logical {
                 left,
                 operator,
@@ -295,8 +399,31 @@ impl fmt::Display for Expr {
             Expr::Set {
                 object,
                 name,
+                operator,
+                value,
+            } => write!(
+                f,
+                "({}.{} {}= {})",
+                object,
+                name.lexeme,
+                lexeme(operator),
+                value
+            ),
+            Expr::ListLiteral { elements, .. } => {
+                write!(f, "(list")?;
+                for element in elements {
+                    write!(f, " {}", element)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Index { object, index, .. } => write!(f, "{}[{}]", object, index),
+            Expr::IndexSet {
+                object,
+                index,
+                operator,
                 value,
-            } => write!(f, "({}.{} = {})", object, name.lexeme, value),
+                ..
+            } => write!(f, "({}[{}] {}= {})", object, index, lexeme(operator), value),
             Expr::This { .. } => write!(f, "oCara"),
             Expr::Super { method, .. } => write!(f, "mestre.{}", method.lexeme),
             Expr::Interpolation { parts } => {
@@ -309,6 +436,7 @@ impl fmt::Display for Expr {
                 }
                 write!(f, ")")
             }
+            Expr::Error { .. } => write!(f, "(error)"),
         }
     }
 }
@@ -321,7 +449,7 @@ mod tests {
     fn make_token(token_type: TokenType, lexeme: &str) -> Token {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             literal: None,
             span: 0..lexeme.len(),
         }
@@ -419,12 +547,12 @@ mod tests {
         let expr = Expr::Variable {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "x".to_string(),
+                lexeme: "x".into(),
                 literal: None,
                 span: 0..1,
             },
         };
-        assert!(matches!(expr, Expr::Variable { name } if name.lexeme == "x"));
+        assert!(matches!(expr, Expr::Variable { name } if &*name.lexeme == "x"));
     }
 
     #[test]
@@ -432,7 +560,7 @@ mod tests {
         let expr = Expr::Variable {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "meuMano".to_string(),
+                lexeme: "meuMano".into(),
                 literal: None,
                 span: 0..7,
             },
@@ -445,15 +573,16 @@ mod tests {
         let expr = Expr::Assign {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "x".to_string(),
+                lexeme: "x".into(),
                 literal: None,
                 span: 0..1,
             },
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(42.0),
             }),
         };
-        assert!(matches!(expr, Expr::Assign { name, .. } if name.lexeme == "x"));
+        assert!(matches!(expr, Expr::Assign { name, .. } if &*name.lexeme == "x"));
     }
 
     #[test]
@@ -461,10 +590,11 @@ mod tests {
         let expr = Expr::Assign {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "x".to_string(),
+                lexeme: "x".into(),
                 literal: None,
                 span: 0..1,
             },
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(42.0),
             }),
@@ -562,7 +692,7 @@ mod tests {
         let var = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "x".to_string(),
+                lexeme: "x".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -581,7 +711,7 @@ mod tests {
             condition: Expr::Literal {
                 value: Literal::Bool(true),
             },
-            then_branch: Box::new(Stmt::break_stmt()),
+            then_branch: Box::new(Stmt::break_stmt(make_token(TokenType::Break, "saiFora"))),
             else_branch: None,
             span: 50..60,
         };
@@ -591,14 +721,40 @@ mod tests {
             condition: Expr::Literal {
                 value: Literal::Bool(true),
             },
-            body: Box::new(Stmt::break_stmt()),
+            body: Box::new(Stmt::break_stmt(make_token(TokenType::Break, "saiFora"))),
+            increment: None,
             span: 70..80,
         };
         assert_eq!(while_stmt.span(), 70..80);
 
-        let break_stmt = Stmt::Break { span: 90..95 };
+        let break_stmt = Stmt::Break {
+            keyword: make_token(TokenType::Break, "saiFora"),
+            span: 90..95,
+        };
         assert_eq!(break_stmt.span(), 90..95);
 
+        let continue_stmt = Stmt::Continue {
+            keyword: make_token(TokenType::Continue, "segueReto"),
+            span: 96..106,
+        };
+        assert_eq!(continue_stmt.span(), 96..106);
+
+        let import_stmt = Stmt::Import {
+            keyword: make_token(TokenType::Import, "chama"),
+            path: make_token(TokenType::String, "\"util\""),
+            span: 107..121,
+        };
+        assert_eq!(import_stmt.span(), 107..121);
+
+        let throw_stmt = Stmt::Throw {
+            keyword: make_token(TokenType::Throw, "joga"),
+            value: Expr::Literal {
+                value: Literal::Nil,
+            },
+            span: 122..135,
+        };
+        assert_eq!(throw_stmt.span(), 122..135);
+
         let else_stmt = Stmt::Else {
             body: Box::new(Stmt::print(Expr::Literal {
                 value: Literal::Nil,
@@ -615,7 +771,7 @@ mod tests {
         });
         assert!(print.children().is_empty());
 
-        let break_stmt = Stmt::break_stmt();
+        let break_stmt = Stmt::break_stmt(make_token(TokenType::Break, "saiFora"));
         assert!(break_stmt.children().is_empty());
     }
 
@@ -689,11 +845,33 @@ mod tests {
         assert_eq!(children[0].span(), 50..60);
     }
 
+    #[test]
+    fn stmt_children_of_a_for_loop_include_the_increment() {
+        let increment = Stmt::Expression {
+            expression: Expr::Literal {
+                value: Literal::Nil,
+            },
+            span: 70..80,
+        };
+        let while_stmt = Stmt::While {
+            condition: Expr::Literal {
+                value: Literal::Bool(true),
+            },
+            body: Box::new(Stmt::block(vec![])),
+            increment: Some(Box::new(increment)),
+            span: 0..90,
+        };
+
+        let children = while_stmt.children();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].span(), 70..80);
+    }
+
     #[test]
     fn stmt_var_declaration_returns_name_and_initializer() {
         let name = Token {
             token_type: TokenType::Identifier,
-            lexeme: "meuMano".to_string(),
+            lexeme: "meuMano".into(),
             literal: None,
             span: 7..14,
         };
@@ -707,7 +885,7 @@ mod tests {
         };
 
         let (n, i) = var.var_declaration().expect("should return Some for Var");
-        assert_eq!(n.lexeme, "meuMano");
+        assert_eq!(&*n.lexeme, "meuMano");
         assert_eq!(n.span, 7..14);
         assert!(matches!(
             i,
@@ -743,7 +921,7 @@ mod tests {
             Expr::Literal {
                 value: Literal::Bool(true),
             },
-            Stmt::break_stmt(),
+            Stmt::break_stmt(make_token(TokenType::Break, "saiFora")),
             None,
         );
         assert!(stmt.var_declaration().is_none());
@@ -755,21 +933,21 @@ mod tests {
             Expr::Literal {
                 value: Literal::Bool(true),
             },
-            Stmt::break_stmt(),
+            Stmt::break_stmt(make_token(TokenType::Break, "saiFora")),
         );
         assert!(stmt.var_declaration().is_none());
     }
 
     #[test]
     fn stmt_var_declaration_returns_none_for_break() {
-        let stmt = Stmt::break_stmt();
+        let stmt = Stmt::break_stmt(make_token(TokenType::Break, "saiFora"));
         assert!(stmt.var_declaration().is_none());
     }
 
     #[test]
     fn stmt_var_declaration_returns_none_for_else() {
         let stmt = Stmt::Else {
-            body: Box::new(Stmt::break_stmt()),
+            body: Box::new(Stmt::break_stmt(make_token(TokenType::Break, "saiFora"))),
             span: 0..10,
         };
         assert!(stmt.var_declaration().is_none());
@@ -816,6 +994,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         assert!(matches!(stmt, Stmt::Function { params, .. } if params.len() == 1));
@@ -839,13 +1019,15 @@ mod tests {
             body: body.clone(),
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
 
         let (n, p, b) = stmt
             .function_declaration()
             .expect("should return Some for Function");
-        assert_eq!(n.lexeme, "soma");
+        assert_eq!(&*n.lexeme, "soma");
         assert_eq!(p.len(), 2);
         assert_eq!(b.len(), 1);
     }
@@ -879,6 +1061,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 10..20,
         };
         let stmt = Stmt::Class {
@@ -888,7 +1072,7 @@ mod tests {
             span: 0..30,
         };
         let (decl_name, methods) = stmt.class_declaration().unwrap();
-        assert_eq!(decl_name.lexeme, "Pessoa");
+        assert_eq!(&*decl_name.lexeme, "Pessoa");
         assert_eq!(methods.len(), 1);
     }
 
@@ -900,6 +1084,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..10,
         };
         assert!(stmt.class_declaration().is_none());
@@ -919,6 +1105,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 10..50,
         };
         assert_eq!(stmt.span(), 10..50);
@@ -977,6 +1165,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 20..30,
         };
         let class = Stmt::Class {
@@ -1011,7 +1201,7 @@ mod tests {
         } = &class
         {
             if let Expr::Variable { name } = sc.as_ref() {
-                assert_eq!(name.lexeme, "Pai");
+                assert_eq!(&*name.lexeme, "Pai");
             } else {
                 panic!("Expected Variable expression for superclass");
             }
@@ -1049,6 +1239,7 @@ mod tests {
                 name: make_token(TokenType::Identifier, "pessoa"),
             }),
             name: make_token(TokenType::Identifier, "nome"),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::String("João".to_string()),
             }),
@@ -1063,6 +1254,7 @@ mod tests {
                 name: make_token(TokenType::Identifier, "pessoa"),
             }),
             name: make_token(TokenType::Identifier, "nome"),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::String("João".to_string()),
             }),
@@ -0,0 +1,71 @@
+//! Bitwise operators: `&`, `|`, `^`, `<<` and `>>`
+//!
+//! Numbers are f64, so operands are truncated toward zero and worked on as
+//! 64-bit two's complement integers. Shared by the interpreter and the VM so
+//! both agree on every edge.
+
+use crate::token::TokenType;
+
+/// The largest whole number an f64 holds exactly
+pub(crate) const MAX_EXACT: i128 = 1 << 53;
+
+fn operand(n: f64) -> Result<i128, String> {
+    if !n.is_finite() || n.trunc().abs() > MAX_EXACT as f64 {
+        return Err(format!(
+            "Operação de bit só rola com inteiro até 2^53, não com {}!",
+            n
+        ));
+    }
+    Ok(n.trunc() as i128)
+}
+
+/// Applies a bitwise operator, or says why it can't
+pub fn apply(operator: TokenType, a: f64, b: f64) -> Result<f64, String> {
+    let (a, b) = (operand(a)?, operand(b)?);
+    let result = match operator {
+        TokenType::Ampersand => a & b,
+        TokenType::Pipe => a | b,
+        TokenType::Caret => a ^ b,
+        TokenType::LessLess | TokenType::GreaterGreater => {
+            if !(0..64).contains(&b) {
+                return Err(format!("Deslocamento tem que ser de 0 a 63, não {}!", b));
+            }
+            match operator {
+                TokenType::LessLess => a << b,
+                _ => a >> b,
+            }
+        }
+        _ => unreachable!("bitwise::apply called with {:?}", operator),
+    };
+    if result.abs() > MAX_EXACT {
+        return Err("O resultado passou de 2^53, aí deixa de ser exato, mano!".to_string());
+    }
+    Ok(result as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_operands_toward_zero() {
+        assert_eq!(apply(TokenType::Ampersand, 6.9, 3.0), Ok(2.0));
+        assert_eq!(apply(TokenType::Pipe, -1.5, 0.0), Ok(-1.0));
+        assert_eq!(apply(TokenType::Caret, 5.0, 1.0), Ok(4.0));
+    }
+
+    #[test]
+    fn shifts_keep_the_sign() {
+        assert_eq!(apply(TokenType::LessLess, 1.0, 10.0), Ok(1024.0));
+        assert_eq!(apply(TokenType::GreaterGreater, -8.0, 1.0), Ok(-4.0));
+        assert!(apply(TokenType::LessLess, 1.0, 64.0).is_err());
+        assert!(apply(TokenType::GreaterGreater, 1.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn refuses_what_would_not_stay_exact() {
+        assert!(apply(TokenType::LessLess, 1.0, 54.0).is_err());
+        assert!(apply(TokenType::Pipe, f64::NAN, 1.0).is_err());
+        assert!(apply(TokenType::Ampersand, 1e300, 1.0).is_err());
+    }
+}
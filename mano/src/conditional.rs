@@ -0,0 +1,233 @@
+//! Conditional blocks, for code that only one backend can run yet
+//!
+//! ```text
+//! #seVm
+//! 1 + 2
+//! #vacilou
+//! salve 1 + 2;
+//! #fim
+//! #se DEBUG
+//! salve "depurando";
+//! #fim
+//! ```
+//!
+//! Lines left out are blanked instead of removed, so spans still point at the
+//! original source. Directives are read from the scanner's tokens, so a line
+//! starting with `#` inside a string or a comment is left alone.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::error::ManoError;
+use crate::scanner::Scanner;
+use crate::token::TokenType;
+
+/// What `#seVm` and `#se NOME` blocks are checked against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Conditions {
+    /// Whether the bytecode VM runs the code, for `#seVm`
+    pub vm: bool,
+    /// Flags passed with `--define`, for `#se NOME`
+    pub defines: Vec<String>,
+}
+
+enum Directive<'a> {
+    If(bool),
+    Else,
+    End,
+    Invalid(&'a str),
+}
+
+struct Block {
+    kept: bool,
+    seen_else: bool,
+    span: Range<usize>,
+}
+
+impl Conditions {
+    /// The source with the blocks that don't apply blanked out, along with
+    /// every directive line
+    pub fn select<'a>(&self, source: &'a str) -> Result<Cow<'a, str>, Vec<ManoError>> {
+        if !source.contains('#') {
+            return Ok(Cow::Borrowed(source));
+        }
+
+        let mut selected = String::with_capacity(source.len());
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut errors = Vec::new();
+        let mut offset = 0;
+        // The scanner makes each directive line a comment starting with `#`
+        let mut directives = Scanner::with_comments(source)
+            .filter_map(Result::ok)
+            .filter(|token| token.token_type == TokenType::Comment && token.lexeme.starts_with('#'))
+            .peekable();
+
+        for line in source.split_inclusive('\n') {
+            offset += line.len();
+            let Some(token) = directives.next_if(|token| token.span.start < offset) else {
+                if blocks.iter().all(|block| block.kept) {
+                    selected.push_str(line);
+                } else {
+                    blank(line, &mut selected);
+                }
+                continue;
+            };
+            let text = token.lexeme.trim_end();
+            let span = token.span.start..token.span.start + text.len();
+            let directive = self.directive(&text[1..]);
+            blank(line, &mut selected);
+
+            match directive {
+                Directive::If(kept) => blocks.push(Block {
+                    kept,
+                    seen_else: false,
+                    span,
+                }),
+                Directive::Else => match blocks.last_mut() {
+                    Some(block) if !block.seen_else => {
+                        block.kept = !block.kept;
+                        block.seen_else = true;
+                    }
+                    Some(_) => errors.push(error("Dois #vacilou no mesmo #se, mano!", span)),
+                    None => errors.push(error("#vacilou sem #se, mano!", span)),
+                },
+                Directive::End => {
+                    if blocks.pop().is_none() {
+                        errors.push(error("#fim sem #se, mano!", span));
+                    }
+                }
+                Directive::Invalid(name) => errors.push(error(
+                    &format!(
+                        "Diretiva '#{}' não existe, mano! Tem #seVm, #se NOME, #vacilou e #fim.",
+                        name
+                    ),
+                    span,
+                )),
+            }
+        }
+
+        for block in blocks {
+            errors.push(error("Faltou o #fim desse #se, mano!", block.span));
+        }
+
+        if errors.is_empty() {
+            Ok(Cow::Owned(selected))
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn directive<'a>(&self, text: &'a str) -> Directive<'a> {
+        let mut words = text.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let argument = words.next();
+        if words.next().is_some() {
+            return Directive::Invalid(text);
+        }
+        match (name, argument) {
+            ("seVm", None) => Directive::If(self.vm),
+            ("se", Some(flag)) => Directive::If(self.defines.iter().any(|d| d == flag)),
+            ("vacilou", None) => Directive::Else,
+            ("fim", None) => Directive::End,
+            _ => Directive::Invalid(text),
+        }
+    }
+}
+
+/// Spaces in place of the line, one per byte, keeping the line break
+fn blank(line: &str, selected: &mut String) {
+    for c in line.chars() {
+        if c == '\n' || c == '\r' {
+            selected.push(c);
+        } else {
+            selected.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+}
+
+fn error(message: &str, span: Range<usize>) -> ManoError {
+    ManoError::Parse {
+        message: message.to_string(),
+        span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(source: &str, vm: bool, defines: &[&str]) -> String {
+        let conditions = Conditions {
+            vm,
+            defines: defines.iter().map(|d| d.to_string()).collect(),
+        };
+        conditions.select(source).unwrap().into_owned()
+    }
+
+    #[test]
+    fn keeps_the_branch_for_the_backend() {
+        let source = "#seVm\n1\n#vacilou\n2\n#fim\n3";
+        assert_eq!(select(source, true, &[]), "     \n1\n        \n \n    \n3");
+        assert_eq!(select(source, false, &[]), "     \n \n        \n2\n    \n3");
+    }
+
+    #[test]
+    fn checks_defines_and_nests() {
+        let source = "#se A\n#se B\nab\n#fim\na\n#fim\n";
+        assert_eq!(select(source, false, &["A"]).trim(), "a");
+        assert_eq!(
+            select(source, false, &["A", "B"])
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["ab", "a"]
+        );
+        assert_eq!(select(source, false, &["B"]).trim(), "");
+    }
+
+    #[test]
+    fn blanked_lines_keep_their_byte_length() {
+        let source = "#se X\nseLiga ação = 1;\n#fim\nsalve 1;";
+        let selected = select(source, false, &[]);
+        assert_eq!(selected.len(), source.len());
+        assert_eq!(selected.find("salve"), source.find("salve"));
+    }
+
+    #[test]
+    fn leaves_sources_without_directives_alone() {
+        let conditions = Conditions::default();
+        assert!(matches!(
+            conditions.select("salve 1;"),
+            Ok(Cow::Borrowed(_))
+        ));
+        let script = "#!/usr/bin/env mano\nsalve 1;";
+        assert_eq!(conditions.select(script).unwrap(), script);
+    }
+
+    #[test]
+    fn ignores_hashes_in_strings_and_comments() {
+        let conditions = Conditions::default();
+        let string = "salve \"itens:\n#1 primeiro\n#fim\n\";";
+        assert_eq!(conditions.select(string).unwrap(), string);
+        let comment = "/* notas\n  # nota\n#se X\n*/\nsalve 1;";
+        assert_eq!(conditions.select(comment).unwrap(), comment);
+    }
+
+    #[test]
+    fn reports_unbalanced_and_unknown_directives() {
+        let errors = Conditions::default()
+            .select("#fim\n#seila\n  #se\n#se A\n#vacilou\n#vacilou\n")
+            .unwrap_err();
+        let messages: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                ManoError::Parse { message, span } => (message.as_str(), span.clone()),
+                _ => panic!("expected a parse error"),
+            })
+            .collect();
+        assert_eq!(messages[0], ("#fim sem #se, mano!", 0..4));
+        assert!(messages[1].0.contains("'#seila'"));
+        assert_eq!(messages[2].1, 14..17);
+        assert_eq!(messages[3].0, "Dois #vacilou no mesmo #se, mano!");
+        assert_eq!(messages[4], ("Faltou o #fim desse #se, mano!", 18..23));
+    }
+}
@@ -0,0 +1,163 @@
+//! Branches a constant condition never lets run, like the `vacilou` of
+//! `sePá (firmeza) { ... }` or the body of `segueOFluxo (treta) { ... }`.
+//!
+//! The code is fine, it just does nothing, so the LSP fades it out instead of
+//! underlining it.
+
+use crate::ast::{Expr, Span, Stmt};
+use crate::token::{Literal, TokenType};
+
+/// Code that never runs, with what its condition always is
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadBranch {
+    pub span: Span,
+    /// `true` when the condition is always firmeza, `false` when always treta
+    pub always: bool,
+}
+
+impl DeadBranch {
+    pub fn message(&self) -> String {
+        let always = if self.always { "firmeza" } else { "treta" };
+        format!(
+            "Isso aqui nunca roda, mano! A condição é sempre {}.",
+            always
+        )
+    }
+}
+
+/// Whether an expression is always truthy or always falsey, when that's known
+/// without running it
+pub fn constant_truthiness(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Literal { value } => Some(!matches!(value, Literal::Nil | Literal::Bool(false))),
+        Expr::Grouping { expression } => constant_truthiness(expression),
+        Expr::Unary { operator, right } if operator.token_type == TokenType::Bang => {
+            constant_truthiness(right).map(|truthy| !truthy)
+        }
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => match (operator.token_type, constant_truthiness(left)?) {
+            // Short-circuits without looking at the right side
+            (TokenType::Or, true) => Some(true),
+            (TokenType::And, false) => Some(false),
+            _ => constant_truthiness(right),
+        },
+        _ => None,
+    }
+}
+
+/// Every branch in `statements` that can't run, inside fitas and methods too.
+/// A dead branch inside another one isn't listed again.
+pub fn dead_branches(statements: &[Stmt]) -> Vec<DeadBranch> {
+    let mut dead = Vec::new();
+    for stmt in statements {
+        collect(stmt, &mut dead);
+    }
+    dead
+}
+
+fn collect(stmt: &Stmt, dead: &mut Vec<DeadBranch>) {
+    match stmt {
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => match constant_truthiness(condition) {
+            Some(true) => {
+                collect(then_branch, dead);
+                if let Some(else_branch) = else_branch {
+                    dead.push(DeadBranch {
+                        span: else_branch.span(),
+                        always: true,
+                    });
+                }
+            }
+            Some(false) => {
+                dead.push(DeadBranch {
+                    span: then_branch.span(),
+                    always: false,
+                });
+                if let Some(else_branch) = else_branch {
+                    collect(else_branch, dead);
+                }
+            }
+            None => {
+                for child in stmt.children() {
+                    collect(child, dead);
+                }
+            }
+        },
+        Stmt::While {
+            condition, body, ..
+        } if constant_truthiness(condition) == Some(false) => dead.push(DeadBranch {
+            span: body.span(),
+            always: false,
+        }),
+        Stmt::Function { body, .. } => body.iter().for_each(|stmt| collect(stmt, dead)),
+        Stmt::Class { methods, .. } => methods.iter().for_each(|method| collect(method, dead)),
+        _ => {
+            for child in stmt.children() {
+                collect(child, dead);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn dead(source: &str) -> Vec<&str> {
+        dead_branches(&parse(source).unwrap())
+            .into_iter()
+            .map(|branch| &source[branch.span])
+            .collect()
+    }
+
+    fn truthiness(source: &str) -> Option<bool> {
+        match parse(source).unwrap().as_slice() {
+            [Stmt::Expression { expression, .. }] => constant_truthiness(expression),
+            other => panic!("Expected one expression: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn literals_and_what_is_built_from_them_are_constant() {
+        assert_eq!(truthiness("firmeza;"), Some(true));
+        assert_eq!(truthiness("0;"), Some(true));
+        assert_eq!(truthiness("\"\";"), Some(true));
+        assert_eq!(truthiness("nadaNão;"), Some(false));
+        assert_eq!(truthiness("!(treta);"), Some(true));
+        assert_eq!(truthiness("firmeza ow x;"), Some(true));
+        assert_eq!(truthiness("treta tamoJunto x;"), Some(false));
+        assert_eq!(truthiness("firmeza tamoJunto nadaNão;"), Some(false));
+        assert_eq!(truthiness("firmeza tamoJunto x;"), None);
+        assert_eq!(truthiness("x;"), None);
+    }
+
+    #[test]
+    fn finds_the_branch_that_never_runs() {
+        assert_eq!(
+            dead("sePá (firmeza) { salve 1; } vacilou { salve 2; }"),
+            ["vacilou { salve 2; }"]
+        );
+        assert_eq!(
+            dead("sePá (treta) { salve 1; } vacilou { salve 2; }"),
+            ["{ salve 1; }"]
+        );
+        assert_eq!(dead("segueOFluxo (treta) { salve 1; }"), ["{ salve 1; }"]);
+        assert!(dead("segueOFluxo (firmeza) { saiFora; }").is_empty());
+        assert!(dead("sePá (x) { salve 1; } vacilou { salve 2; }").is_empty());
+    }
+
+    #[test]
+    fn looks_inside_fitas_and_methods_but_not_inside_dead_code() {
+        let source = "olhaEssaFita f() { sePá (treta) { sePá (treta) salve 1; } }\n\
+                      bagulho B { m() { sePá (nadaNão) salve 2; } }";
+        assert_eq!(dead(source), ["{ sePá (treta) salve 1; }", "salve 2;"]);
+    }
+}
@@ -0,0 +1,174 @@
+//! Alternate keyword sets.
+//!
+//! A dialect mostly adds aliases: the scanner turns them into the same token
+//! types as the mano keywords, so nothing after it knows which words were
+//! used. Mano keywords keep working in every dialect. Lox also renames a
+//! couple of identifiers (`init`, `clock`) to their mano names.
+
+use std::fmt;
+use std::str::FromStr;
+
+use phf::phf_map;
+
+use crate::token::TokenType;
+
+/// Keywords for people who'd rather not use slang
+pub static FORMAL_KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
+    "capture" => TokenType::Catch,
+    "classe" => TokenType::Class,
+    "continue" => TokenType::Continue,
+    "e" => TokenType::And,
+    "enquanto" => TokenType::While,
+    "este" => TokenType::This,
+    "falso" => TokenType::False,
+    "funcao" => TokenType::Fun,
+    "importe" => TokenType::Import,
+    "imprima" => TokenType::Print,
+    "lance" => TokenType::Throw,
+    "nulo" => TokenType::Nil,
+    "ou" => TokenType::Or,
+    "para" => TokenType::For,
+    "pare" => TokenType::Break,
+    "retorne" => TokenType::Return,
+    "se" => TokenType::If,
+    "senao" => TokenType::Else,
+    "super" => TokenType::Super,
+    "tente" => TokenType::Try,
+    "variavel" => TokenType::Var,
+    "verdadeiro" => TokenType::True,
+};
+
+/// The keywords of Lox, the language from Crafting Interpreters
+pub static LOX_KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
+    "and" => TokenType::And,
+    "class" => TokenType::Class,
+    "else" => TokenType::Else,
+    "false" => TokenType::False,
+    "for" => TokenType::For,
+    "fun" => TokenType::Fun,
+    "if" => TokenType::If,
+    "nil" => TokenType::Nil,
+    "or" => TokenType::Or,
+    "print" => TokenType::Print,
+    "return" => TokenType::Return,
+    "super" => TokenType::Super,
+    "this" => TokenType::This,
+    "true" => TokenType::True,
+    "var" => TokenType::Var,
+    "while" => TokenType::While,
+};
+
+/// Lox names with a mano name of their own: the initializer and the clock native
+static LOX_NAMES: phf::Map<&'static str, &'static str> = phf_map! {
+    "init" => "bora",
+    "clock" => "fazTeuCorre",
+};
+
+static NO_NAMES: phf::Map<&'static str, &'static str> = phf_map! {};
+
+static NO_ALIASES: phf::Map<&'static str, TokenType> = phf_map! {};
+
+/// Prefix of the comment that picks a dialect, e.g. `// dialeto: formal`
+const PRAGMA: &str = "// dialeto:";
+
+/// Which keyword aliases the scanner accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    #[default]
+    Mano,
+    /// Formal Portuguese: `imprima`, `variavel`, `se`/`senao`...
+    Formal,
+    /// Lox itself, to run the book's programs unchanged
+    Lox,
+}
+
+impl Dialect {
+    /// Words this dialect adds on top of the mano keywords
+    pub fn aliases(self) -> &'static phf::Map<&'static str, TokenType> {
+        match self {
+            Dialect::Mano => &NO_ALIASES,
+            Dialect::Formal => &FORMAL_KEYWORDS,
+            Dialect::Lox => &LOX_KEYWORDS,
+        }
+    }
+
+    /// The mano name an identifier of this dialect stands for, like Lox's `init`
+    /// for `bora`
+    pub fn identifier(self, name: &str) -> Option<&'static str> {
+        let names = match self {
+            Dialect::Lox => &LOX_NAMES,
+            Dialect::Mano | Dialect::Formal => &NO_NAMES,
+        };
+        names.get(name).copied()
+    }
+
+    /// The dialect a `// dialeto: nome` comment on the first line asks for
+    /// (the second line when the first is a shebang)
+    pub fn from_pragma(source: &str) -> Option<Dialect> {
+        let mut lines = source.lines();
+        let mut line = lines.next()?;
+        if line.starts_with("#!") {
+            line = lines.next()?;
+        }
+        line.trim().strip_prefix(PRAGMA)?.trim().parse().ok()
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "mano" => Ok(Dialect::Mano),
+            "formal" => Ok(Dialect::Formal),
+            "lox" => Ok(Dialect::Lox),
+            _ => Err(format!(
+                "Dialeto '{}' não existe, mano! Tem mano, formal e lox.",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dialect::Mano => write!(f, "mano"),
+            Dialect::Formal => write!(f, "formal"),
+            Dialect::Lox => write!(f, "lox"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_pragma_from_first_line() {
+        assert_eq!(
+            Dialect::from_pragma("// dialeto: formal\nimprima 1;"),
+            Some(Dialect::Formal)
+        );
+        assert_eq!(
+            Dialect::from_pragma("#!/usr/bin/env mano\n// dialeto: formal\n"),
+            Some(Dialect::Formal)
+        );
+    }
+
+    #[test]
+    fn ignores_pragma_past_the_first_line_or_unknown() {
+        assert_eq!(Dialect::from_pragma("salve 1;\n// dialeto: formal"), None);
+        assert_eq!(Dialect::from_pragma("// dialeto: klingon"), None);
+        assert_eq!(Dialect::from_pragma("// dialeto: lox"), Some(Dialect::Lox));
+        assert_eq!(Dialect::from_pragma(""), None);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("formal".parse(), Ok(Dialect::Formal));
+        assert_eq!("lox".parse(), Ok(Dialect::Lox));
+        assert!("klingon".parse::<Dialect>().is_err());
+        assert_eq!(Dialect::Formal.to_string(), "formal");
+    }
+}
@@ -0,0 +1,717 @@
+//! Comparing programs by their syntax trees instead of their text.
+//!
+//! Formatting, comments and spans never count as changes. Declarations are
+//! matched by name, and a declaration that only differs by the names it
+//! declares (consistently) is a rename, not a rewrite.
+
+use std::fmt;
+
+use crate::ast::{Expr, InterpolationPart, Span, Stmt};
+use crate::scope::DeclarationKind;
+use crate::token::Token;
+
+/// A semantic change between two versions of a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstChange {
+    Added {
+        kind: DeclarationKind,
+        name: String,
+        span: Span,
+    },
+    Removed {
+        kind: DeclarationKind,
+        name: String,
+        span: Span,
+    },
+    /// A fita now takes a different number of lances
+    ArityChanged {
+        name: String,
+        from: usize,
+        to: usize,
+        span: Span,
+    },
+    /// Same declaration, different code inside
+    BodyChanged {
+        kind: DeclarationKind,
+        name: String,
+        span: Span,
+    },
+    /// A name replaced everywhere it was used, `within` the declaration it
+    /// happened in (`None` for top-level names)
+    Renamed {
+        from: String,
+        to: String,
+        within: Option<String>,
+        span: Span,
+    },
+    /// Top-level code outside any declaration changed
+    StatementsChanged,
+}
+
+impl fmt::Display for AstChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstChange::Added { kind, name, .. } => write!(f, "+ {} '{}'", label(*kind), name),
+            AstChange::Removed { kind, name, .. } => write!(f, "- {} '{}'", label(*kind), name),
+            AstChange::ArityChanged { name, from, to, .. } => {
+                write!(f, "~ fita '{}' foi de {} pra {} lances", name, from, to)
+            }
+            AstChange::BodyChanged { kind, name, .. } => {
+                write!(f, "~ {} '{}' mudou por dentro", label(*kind), name)
+            }
+            AstChange::Renamed {
+                from, to, within, ..
+            } => {
+                write!(f, "→ '{}' virou '{}'", from, to)?;
+                if let Some(within) = within {
+                    write!(f, " em '{}'", within)?;
+                }
+                Ok(())
+            }
+            AstChange::StatementsChanged => write!(f, "~ o código solto mudou"),
+        }
+    }
+}
+
+/// Whether two assignments are both plain or use the same compound operator
+fn same_operator(a: &Option<Token>, b: &Option<Token>) -> bool {
+    a.as_ref().map(|a| a.token_type) == b.as_ref().map(|b| b.token_type)
+}
+
+fn label(kind: DeclarationKind) -> &'static str {
+    match kind {
+        DeclarationKind::Variable => "variável",
+        DeclarationKind::Function => "fita",
+        DeclarationKind::Class => "bagulho",
+        DeclarationKind::Parameter => "lance",
+    }
+}
+
+/// True when both programs parse to the same tree, ignoring spans
+pub fn same_ast(old: &[Stmt], new: &[Stmt]) -> bool {
+    let mut matcher = Matcher::default();
+    matcher.stmts(old, new) && matcher.renames().next().is_none()
+}
+
+/// Semantic changes from `old` to `new`, top-level declarations first, in the
+/// order they appear in `new`
+pub fn diff_ast(old: &[Stmt], new: &[Stmt]) -> Vec<AstChange> {
+    let old_declarations: Vec<_> = old.iter().filter_map(declaration).collect();
+    let new_declarations: Vec<_> = new.iter().filter_map(declaration).collect();
+    let mut changes = Vec::new();
+    let mut removed: Vec<_> = old_declarations
+        .iter()
+        .filter(|(_, name, _)| !new_declarations.iter().any(|(_, n, _)| n == name))
+        .collect();
+
+    for (kind, name, stmt) in &new_declarations {
+        match old_declarations.iter().find(|(_, n, _)| n == name) {
+            Some((_, _, old_stmt)) => compare(old_stmt, stmt, name, &mut changes),
+            None => {
+                // A removed declaration with the same code under a new name was renamed
+                let renamed = removed.iter().position(|(old_kind, old_name, old_stmt)| {
+                    let mut matcher = Matcher::default();
+                    old_kind == kind
+                        && matcher.matches(old_stmt, stmt)
+                        && matcher
+                            .renames()
+                            .any(|(from, to)| from == old_name && to == name)
+                });
+                match renamed {
+                    Some(index) => {
+                        let (_, old_name, _) = removed.remove(index);
+                        changes.push(AstChange::Renamed {
+                            from: old_name.clone(),
+                            to: name.clone(),
+                            within: None,
+                            span: stmt.span(),
+                        });
+                    }
+                    None => changes.push(AstChange::Added {
+                        kind: *kind,
+                        name: name.clone(),
+                        span: stmt.span(),
+                    }),
+                }
+            }
+        }
+    }
+
+    for (kind, name, stmt) in removed {
+        changes.push(AstChange::Removed {
+            kind: *kind,
+            name: name.clone(),
+            span: stmt.span(),
+        });
+    }
+
+    let loose = |statements: &[Stmt]| -> Vec<Stmt> {
+        statements
+            .iter()
+            .filter(|stmt| declaration(stmt).is_none())
+            .cloned()
+            .collect()
+    };
+    if !same_ast(&loose(old), &loose(new)) {
+        changes.push(AstChange::StatementsChanged);
+    }
+
+    changes
+}
+
+fn declaration(stmt: &Stmt) -> Option<(DeclarationKind, String, &Stmt)> {
+    match stmt {
+        Stmt::Var { name, .. } => Some((DeclarationKind::Variable, name.lexeme.to_string(), stmt)),
+        Stmt::Function { name, .. } => {
+            Some((DeclarationKind::Function, name.lexeme.to_string(), stmt))
+        }
+        Stmt::Class { name, .. } | Stmt::Enum { name, .. } => {
+            Some((DeclarationKind::Class, name.lexeme.to_string(), stmt))
+        }
+        _ => None,
+    }
+}
+
+/// Changes between two declarations with the same name
+fn compare(old: &Stmt, new: &Stmt, name: &str, changes: &mut Vec<AstChange>) {
+    match (old, new) {
+        (Stmt::Function { params: from, .. }, Stmt::Function { params: to, .. })
+            if from.len() != to.len() =>
+        {
+            changes.push(AstChange::ArityChanged {
+                name: name.to_string(),
+                from: from.len(),
+                to: to.len(),
+                span: new.span(),
+            });
+        }
+        (
+            Stmt::Class {
+                superclass: old_superclass,
+                methods: old_methods,
+                ..
+            },
+            Stmt::Class {
+                superclass: new_superclass,
+                methods: new_methods,
+                ..
+            },
+        ) if Matcher::default().option(old_superclass, new_superclass) => {
+            // Methods are compared one by one, like top-level fitas
+            let qualified = |method: &str| format!("{}.{}", name, method);
+            for change in diff_ast(old_methods, new_methods) {
+                changes.push(match change {
+                    AstChange::Added { kind, name, span } => AstChange::Added {
+                        kind,
+                        name: qualified(&name),
+                        span,
+                    },
+                    AstChange::Removed { kind, name, span } => AstChange::Removed {
+                        kind,
+                        name: qualified(&name),
+                        span,
+                    },
+                    AstChange::ArityChanged {
+                        name,
+                        from,
+                        to,
+                        span,
+                    } => AstChange::ArityChanged {
+                        name: qualified(&name),
+                        from,
+                        to,
+                        span,
+                    },
+                    AstChange::BodyChanged { kind, name, span } => AstChange::BodyChanged {
+                        kind,
+                        name: qualified(&name),
+                        span,
+                    },
+                    AstChange::Renamed {
+                        from,
+                        to,
+                        within,
+                        span,
+                    } => AstChange::Renamed {
+                        within: Some(within.map_or_else(|| name.to_string(), |m| qualified(&m))),
+                        from,
+                        to,
+                        span,
+                    },
+                    AstChange::StatementsChanged => AstChange::StatementsChanged,
+                });
+            }
+        }
+        _ => {
+            let mut matcher = Matcher::default();
+            if matcher.matches(old, new) {
+                for (from, to) in matcher.renames() {
+                    changes.push(AstChange::Renamed {
+                        from: from.clone(),
+                        to: to.clone(),
+                        within: Some(name.to_string()),
+                        span: new.span(),
+                    });
+                }
+            } else if let Some((kind, _, _)) = declaration(new) {
+                changes.push(AstChange::BodyChanged {
+                    kind,
+                    name: name.to_string(),
+                    span: new.span(),
+                });
+            }
+        }
+    }
+}
+
+/// Walks two trees side by side, allowing names to differ only when they
+/// were renamed consistently: each old name maps to exactly one new name
+#[derive(Default)]
+struct Matcher {
+    names: Vec<(String, String)>,
+    /// Names declared in the trees, the only ones a rename may change: a
+    /// different global or fita is different code
+    declared: Vec<(String, String)>,
+}
+
+impl Matcher {
+    /// Whether `new` is `old` with only its own declarations renamed
+    fn matches(&mut self, old: &Stmt, new: &Stmt) -> bool {
+        self.stmt(old, new) && self.renames().all(|rename| self.declared.contains(rename))
+    }
+
+    /// Names that changed, in the order they were first seen
+    fn renames(&self) -> impl Iterator<Item = &(String, String)> {
+        self.names.iter().filter(|(from, to)| from != to)
+    }
+
+    fn name(&mut self, old: &Token, new: &Token) -> bool {
+        let (from, to) = (&*old.lexeme, &*new.lexeme);
+        match self.names.iter().find(|(f, t)| f == from || t == to) {
+            Some((f, t)) => f == from && t == to,
+            None => {
+                self.names.push((from.to_string(), to.to_string()));
+                true
+            }
+        }
+    }
+
+    /// Like [`Self::name`], for the name a declaration introduces
+    fn declare(&mut self, old: &Token, new: &Token) -> bool {
+        self.declared
+            .push((old.lexeme.to_string(), new.lexeme.to_string()));
+        self.name(old, new)
+    }
+
+    fn declare_all(&mut self, old: &[Token], new: &[Token]) -> bool {
+        old.len() == new.len() && old.iter().zip(new).all(|(a, b)| self.declare(a, b))
+    }
+
+    fn stmts(&mut self, old: &[Stmt], new: &[Stmt]) -> bool {
+        old.len() == new.len() && old.iter().zip(new).all(|(a, b)| self.stmt(a, b))
+    }
+
+    fn exprs(&mut self, old: &[Expr], new: &[Expr]) -> bool {
+        old.len() == new.len() && old.iter().zip(new).all(|(a, b)| self.expr(a, b))
+    }
+
+    fn option<T: Matchable>(&mut self, old: &Option<T>, new: &Option<T>) -> bool {
+        match (old, new) {
+            (Some(a), Some(b)) => T::matches(self, a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn stmt(&mut self, old: &Stmt, new: &Stmt) -> bool {
+        match (old, new) {
+            (Stmt::Expression { expression: a, .. }, Stmt::Expression { expression: b, .. })
+            | (Stmt::Print { expression: a, .. }, Stmt::Print { expression: b, .. }) => {
+                self.expr(a, b)
+            }
+            (
+                Stmt::Var {
+                    name: a,
+                    initializer: x,
+                    ..
+                },
+                Stmt::Var {
+                    name: b,
+                    initializer: y,
+                    ..
+                },
+            ) => self.declare(a, b) && self.option(x, y),
+            (Stmt::Block { statements: a, .. }, Stmt::Block { statements: b, .. }) => {
+                self.stmts(a, b)
+            }
+            (
+                Stmt::If {
+                    condition: a,
+                    then_branch: x,
+                    else_branch: p,
+                    ..
+                },
+                Stmt::If {
+                    condition: b,
+                    then_branch: y,
+                    else_branch: q,
+                    ..
+                },
+            ) => self.expr(a, b) && self.stmt(x, y) && self.option(p, q),
+            (
+                Stmt::While {
+                    condition: a,
+                    body: x,
+                    increment: p,
+                    ..
+                },
+                Stmt::While {
+                    condition: b,
+                    body: y,
+                    increment: q,
+                    ..
+                },
+            ) => self.expr(a, b) && self.stmt(x, y) && self.option(p, q),
+            (Stmt::Break { .. }, Stmt::Break { .. })
+            | (Stmt::Continue { .. }, Stmt::Continue { .. }) => true,
+            (Stmt::Else { body: a, .. }, Stmt::Else { body: b, .. }) => self.stmt(a, b),
+            (
+                Stmt::Function {
+                    name: a,
+                    params: x,
+                    body: p,
+                    is_static: s,
+                    is_getter: g,
+                    is_variadic: v,
+                    ..
+                },
+                Stmt::Function {
+                    name: b,
+                    params: y,
+                    body: q,
+                    is_static: t,
+                    is_getter: h,
+                    is_variadic: w,
+                    ..
+                },
+            ) => {
+                s == t
+                    && g == h
+                    && v == w
+                    && self.declare(a, b)
+                    && self.declare_all(x, y)
+                    && self.stmts(p, q)
+            }
+            (Stmt::Return { value: a, .. }, Stmt::Return { value: b, .. }) => self.option(a, b),
+            (
+                Stmt::Class {
+                    name: a,
+                    superclass: x,
+                    methods: p,
+                    ..
+                },
+                Stmt::Class {
+                    name: b,
+                    superclass: y,
+                    methods: q,
+                    ..
+                },
+            ) => self.declare(a, b) && self.option(x, y) && self.stmts(p, q),
+            (
+                Stmt::Enum {
+                    name: a,
+                    variants: x,
+                    ..
+                },
+                Stmt::Enum {
+                    name: b,
+                    variants: y,
+                    ..
+                },
+            ) => self.declare(a, b) && self.declare_all(x, y),
+            (Stmt::Import { path: a, .. }, Stmt::Import { path: b, .. }) => a.literal == b.literal,
+            (
+                Stmt::Try {
+                    body: x,
+                    name: a,
+                    handler: p,
+                    ..
+                },
+                Stmt::Try {
+                    body: y,
+                    name: b,
+                    handler: q,
+                    ..
+                },
+            ) => self.stmt(x, y) && self.declare(a, b) && self.stmt(p, q),
+            (Stmt::Throw { value: a, .. }, Stmt::Throw { value: b, .. }) => self.expr(a, b),
+            (Stmt::Error { .. }, Stmt::Error { .. }) => true,
+            _ => false,
+        }
+    }
+
+    fn expr(&mut self, old: &Expr, new: &Expr) -> bool {
+        match (old, new) {
+            (
+                Expr::Binary {
+                    left: a,
+                    operator: o,
+                    right: x,
+                },
+                Expr::Binary {
+                    left: b,
+                    operator: p,
+                    right: y,
+                },
+            )
+            | (
+                Expr::Logical {
+                    left: a,
+                    operator: o,
+                    right: x,
+                },
+                Expr::Logical {
+                    left: b,
+                    operator: p,
+                    right: y,
+                },
+            ) => o.token_type == p.token_type && self.expr(a, b) && self.expr(x, y),
+            (
+                Expr::Ternary {
+                    condition: a,
+                    then_branch: x,
+                    else_branch: p,
+                },
+                Expr::Ternary {
+                    condition: b,
+                    then_branch: y,
+                    else_branch: q,
+                },
+            ) => self.expr(a, b) && self.expr(x, y) && self.expr(p, q),
+            (
+                Expr::Unary {
+                    operator: o,
+                    right: a,
+                },
+                Expr::Unary {
+                    operator: p,
+                    right: b,
+                },
+            ) => o.token_type == p.token_type && self.expr(a, b),
+            (Expr::Literal { value: a }, Expr::Literal { value: b }) => a == b,
+            (Expr::Grouping { expression: a }, Expr::Grouping { expression: b }) => self.expr(a, b),
+            (Expr::Variable { name: a }, Expr::Variable { name: b }) => self.name(a, b),
+            (
+                Expr::Assign {
+                    name: a,
+                    operator: o,
+                    value: x,
+                },
+                Expr::Assign {
+                    name: b,
+                    operator: p,
+                    value: y,
+                },
+            ) => same_operator(o, p) && self.name(a, b) && self.expr(x, y),
+            (
+                Expr::Call {
+                    callee: a,
+                    arguments: x,
+                    ..
+                },
+                Expr::Call {
+                    callee: b,
+                    arguments: y,
+                    ..
+                },
+            ) => self.expr(a, b) && self.exprs(x, y),
+            (Expr::Lambda { params: a, body: x }, Expr::Lambda { params: b, body: y }) => {
+                self.declare_all(a, b) && self.stmts(x, y)
+            }
+            (Expr::Get { object: a, name: x }, Expr::Get { object: b, name: y }) => {
+                self.expr(a, b) && self.name(x, y)
+            }
+            (
+                Expr::Set {
+                    object: a,
+                    name: x,
+                    operator: o,
+                    value: p,
+                },
+                Expr::Set {
+                    object: b,
+                    name: y,
+                    operator: r,
+                    value: q,
+                },
+            ) => same_operator(o, r) && self.expr(a, b) && self.name(x, y) && self.expr(p, q),
+            (Expr::ListLiteral { elements: a, .. }, Expr::ListLiteral { elements: b, .. }) => {
+                self.exprs(a, b)
+            }
+            (
+                Expr::Index {
+                    object: a,
+                    index: x,
+                    ..
+                },
+                Expr::Index {
+                    object: b,
+                    index: y,
+                    ..
+                },
+            ) => self.expr(a, b) && self.expr(x, y),
+            (
+                Expr::IndexSet {
+                    object: a,
+                    index: x,
+                    operator: o,
+                    value: p,
+                    ..
+                },
+                Expr::IndexSet {
+                    object: b,
+                    index: y,
+                    operator: r,
+                    value: q,
+                    ..
+                },
+            ) => same_operator(o, r) && self.expr(a, b) && self.expr(x, y) && self.expr(p, q),
+            (Expr::This { .. }, Expr::This { .. }) => true,
+            (Expr::Super { method: a, .. }, Expr::Super { method: b, .. }) => self.name(a, b),
+            (Expr::Interpolation { parts: a }, Expr::Interpolation { parts: b }) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|parts| match parts {
+                        (InterpolationPart::Str(x), InterpolationPart::Str(y)) => x == y,
+                        (InterpolationPart::Expr(x), InterpolationPart::Expr(y)) => self.expr(x, y),
+                        _ => false,
+                    })
+            }
+            (Expr::Error { .. }, Expr::Error { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Lets [`Matcher::option`] compare optional expressions and statements alike
+trait Matchable {
+    fn matches(matcher: &mut Matcher, old: &Self, new: &Self) -> bool;
+}
+
+impl Matchable for Expr {
+    fn matches(matcher: &mut Matcher, old: &Self, new: &Self) -> bool {
+        matcher.expr(old, new)
+    }
+}
+
+impl Matchable for Box<Expr> {
+    fn matches(matcher: &mut Matcher, old: &Self, new: &Self) -> bool {
+        matcher.expr(old, new)
+    }
+}
+
+impl Matchable for Box<Stmt> {
+    fn matches(matcher: &mut Matcher, old: &Self, new: &Self) -> bool {
+        matcher.stmt(old, new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        let old = parse(old).unwrap();
+        let new = parse(new).unwrap();
+        diff_ast(&old, &new)
+            .iter()
+            .map(|change| change.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn formatting_is_not_a_change() {
+        let old = "olhaEssaFita soma(a, b) { toma a + b; }";
+        let new = "// soma\nolhaEssaFita soma(a,b)\n{\n    toma a+b;\n}\n";
+        assert!(changes(old, new).is_empty());
+        assert!(same_ast(&parse(old).unwrap(), &parse(new).unwrap()));
+    }
+
+    #[test]
+    fn reports_added_and_removed_declarations() {
+        let old = "olhaEssaFita a() {} bagulho B {}";
+        let new = "olhaEssaFita a() {} seLiga c = 1;";
+        assert_eq!(changes(old, new), vec!["+ variável 'c'", "- bagulho 'B'"]);
+    }
+
+    #[test]
+    fn reports_changed_arity() {
+        let changes = changes("olhaEssaFita f(a) {}", "olhaEssaFita f(a, b) {}");
+        assert_eq!(changes, vec!["~ fita 'f' foi de 1 pra 2 lances"]);
+    }
+
+    #[test]
+    fn consistent_renames_are_renames() {
+        let old = "olhaEssaFita f(n) { seLiga x = n; toma x * 2; }";
+        let new = "olhaEssaFita f(n) { seLiga dobro = n; toma dobro * 2; }";
+        assert_eq!(changes(old, new), vec!["→ 'x' virou 'dobro' em 'f'"]);
+    }
+
+    #[test]
+    fn renamed_top_level_fita() {
+        let old = "olhaEssaFita fat(n) { toma n < 2 ? 1 : n * fat(n - 1); }";
+        let new = "olhaEssaFita fatorial(n) { toma n < 2 ? 1 : n * fatorial(n - 1); }";
+        assert_eq!(changes(old, new), vec!["→ 'fat' virou 'fatorial'"]);
+    }
+
+    #[test]
+    fn inconsistent_rename_changes_the_body() {
+        let old = "olhaEssaFita f(a, b) { toma a - b; }";
+        let new = "olhaEssaFita f(a, b) { toma a - a; }";
+        assert_eq!(changes(old, new), vec!["~ fita 'f' mudou por dentro"]);
+        assert!(!same_ast(&parse(old).unwrap(), &parse(new).unwrap()));
+    }
+
+    #[test]
+    fn calling_another_fita_changes_the_body() {
+        let old = "olhaEssaFita f(a, b) { toma soma(a, b); }";
+        let new = "olhaEssaFita f(a, b) { toma sub(a, b); }";
+        assert_eq!(changes(old, new), vec!["~ fita 'f' mudou por dentro"]);
+        let old = "olhaEssaFita f() { toma limite; } olhaEssaFita limite() {}";
+        let new = "olhaEssaFita g() { toma teto; } olhaEssaFita limite() {}";
+        assert_eq!(changes(old, new), vec!["+ fita 'g'", "- fita 'f'"]);
+    }
+
+    #[test]
+    fn methods_are_compared_by_name() {
+        let old = "bagulho Bicho { falar() { salve 1; } andar() {} }";
+        let new = "bagulho Bicho { falar() { salve 2; } correr() {} }";
+        assert_eq!(
+            changes(old, new),
+            vec![
+                "~ fita 'Bicho.falar' mudou por dentro",
+                "→ 'andar' virou 'correr' em 'Bicho'"
+            ]
+        );
+    }
+
+    #[test]
+    fn loose_statements_are_compared_as_a_whole() {
+        assert_eq!(
+            changes("salve 1;", "salve 2;"),
+            vec!["~ o código solto mudou"]
+        );
+        assert!(changes("salve 1;", "salve   1 ;").is_empty());
+        assert_eq!(
+            changes("chama \"a\";", "chama \"b\";"),
+            vec!["~ o código solto mudou"]
+        );
+    }
+
+    #[test]
+    fn renaming_the_caught_error_is_a_rename() {
+        let old = "olhaEssaFita f() { tentaAi { joga 1; } seDeuRuim (e) { salve e; } }";
+        let new = "olhaEssaFita f() { tentaAi { joga 1; } seDeuRuim (erro) { salve erro; } }";
+        assert_eq!(changes(old, new), vec!["→ 'e' virou 'erro' em 'f'"]);
+    }
+}
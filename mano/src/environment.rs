@@ -4,8 +4,60 @@ use std::ops::Range;
 use std::rc::Rc;
 
 use crate::error::ManoError;
-use crate::value::Value;
+use crate::value::{Function, Value};
+
+/// What a name in the environment holds, for kinded completions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Class,
+    Native,
+    /// A field set on an instance
+    Field,
+    /// A method reached through an instance or class
+    Method,
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            SymbolKind::Variable => "variável",
+            SymbolKind::Function => "fita",
+            SymbolKind::Class => "bagulho",
+            SymbolKind::Native => "fita nativa",
+            SymbolKind::Field => "esquema",
+            SymbolKind::Method => "método",
+        };
+        write!(f, "{}", label)
+    }
+}
 
+/// A name defined in the environment and what kind of value it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+impl SymbolKind {
+    pub(crate) fn of(value: Option<&Value>) -> Self {
+        match value {
+            Some(Value::Function(function)) => match function.as_ref() {
+                Function::Mano(_) => SymbolKind::Function,
+                Function::Native(_) => SymbolKind::Native,
+            },
+            Some(Value::Class(_)) => SymbolKind::Class,
+            _ => SymbolKind::Variable,
+        }
+    }
+}
+
+/// One scope: globals by name, locals by the slot the resolver gave them.
+///
+/// Scopes are shared (`Rc<RefCell<..>>`) rather than kept in a flat stack
+/// because a fita keeps the scope it was declared in alive after the call
+/// that made it returns, and nothing else would free those frames.
 #[derive(Debug, Default)]
 pub struct Environment {
     values: HashMap<String, Option<Value>>,
@@ -131,6 +183,53 @@ impl Environment {
 
         names
     }
+
+    /// Names and values defined by name in this scope (globals), sorted by name
+    pub(crate) fn named_values(&self) -> Vec<(String, Option<Value>)> {
+        let mut values: Vec<_> = self
+            .values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        values
+    }
+
+    /// Every name visible from this environment with its kind, innermost
+    /// scope first. Shadowed names only show up once.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self
+            .slot_names
+            .iter()
+            .zip(&self.slots)
+            .map(|(name, value)| Symbol {
+                name: name.clone(),
+                kind: SymbolKind::of(value.as_ref()),
+            })
+            .collect();
+
+        let mut globals: Vec<Symbol> = self
+            .values
+            .iter()
+            .map(|(name, value)| Symbol {
+                name: name.clone(),
+                kind: SymbolKind::of(value.as_ref()),
+            })
+            .collect();
+        // HashMap order is random, keep the output stable
+        globals.sort_by(|a, b| a.name.cmp(&b.name));
+        symbols.extend(globals);
+
+        if let Some(enclosing) = &self.enclosing {
+            for symbol in enclosing.borrow().symbols() {
+                if !symbols.iter().any(|s| s.name == symbol.name) {
+                    symbols.push(symbol);
+                }
+            }
+        }
+
+        symbols
+    }
 }
 
 #[cfg(test)]
@@ -271,6 +370,44 @@ mod tests {
         assert_eq!(names.len(), 2);
     }
 
+    #[test]
+    fn symbols_report_kinds_across_scopes() {
+        use crate::value::NativeFunction;
+
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        let native = NativeFunction {
+            name: "relogio".to_string(),
+            arity: 0,
+            func: Rc::new(|_| Ok(Value::Literal(Literal::Nil))),
+        };
+        outer.borrow_mut().define(
+            "relogio".to_string(),
+            Value::Function(Rc::new(Function::Native(native))),
+        );
+        outer.borrow_mut().define("x".to_string(), num(1.0));
+
+        let mut inner = Environment::with_enclosing(Rc::clone(&outer));
+        inner.define_at_slot("x".to_string(), str("sombra"));
+        inner.define_uninitialized_at_slot("vazio".to_string());
+
+        let symbols = inner.symbols();
+        let kind_of = |name: &str| symbols.iter().find(|s| s.name == name).map(|s| s.kind);
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(kind_of("relogio"), Some(SymbolKind::Native));
+        assert_eq!(kind_of("x"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("vazio"), Some(SymbolKind::Variable));
+        // Inner scope first
+        assert_eq!(symbols[0].name, "x");
+    }
+
+    #[test]
+    fn symbol_kind_displays_mano_terms() {
+        assert_eq!(SymbolKind::Function.to_string(), "fita");
+        assert_eq!(SymbolKind::Class.to_string(), "bagulho");
+        assert_eq!(SymbolKind::Native.to_string(), "fita nativa");
+        assert_eq!(SymbolKind::Variable.to_string(), "variável");
+    }
+
     #[test]
     fn define_uninitialized_at_slot_creates_none_slot() {
         let mut env = Environment::new();
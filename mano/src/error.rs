@@ -1,6 +1,7 @@
 use std::ops::Range;
 use thiserror::Error;
 
+use crate::line_index::{LineIndex, SourceLocation};
 use crate::value::Value;
 
 #[derive(Debug, Error)]
@@ -20,16 +21,204 @@ pub enum ManoError {
     #[error("Pô, mano! Erro de escopo!")]
     Resolution { message: String, span: Range<usize> },
 
+    /// Not fatal on its own, only an error in strict mode
+    #[error("Se liga, mano!")]
+    Warning { message: String, span: Range<usize> },
+
+    /// Writing what a `salve` printed failed, like when whoever reads the
+    /// output went away (`mano script.mano | head -1`)
+    #[error("Não deu pra escrever a saída, mano!")]
+    Output {
+        source: std::io::Error,
+        span: Range<usize>,
+    },
+
+    /// Code bigger than the size limit, refused before it's all read in
+    #[error("Arquivo grande demais, mano! O limite é {limit} bytes.")]
+    TooLarge { limit: u64 },
+
+    /// Stopped from outside, like with Ctrl+C, before the statement at `span`
+    #[error("Parou, mano!")]
+    Interrupted { span: Range<usize> },
+
+    /// A bug in mano itself, not in the user's code
+    #[error("Deu pau aqui dentro, a culpa não é tua!")]
+    Internal(String),
+
+    /// An error that also points at other places, like the declaration of
+    /// the fita a call got wrong, and may say how to fix it or which calls
+    /// it happened inside of
+    #[error("{error}")]
+    Detailed {
+        error: Box<ManoError>,
+        related: Vec<Related>,
+        help: Option<String>,
+        /// The calls running when it happened, the innermost first
+        stack: Vec<Frame>,
+    },
+
     #[error("")]
     Break,
 
+    #[error("")]
+    Continue,
+
     #[error("")]
     Return(Value),
 
+    /// A `joga` on its way to a `seDeuRuim`, with the value it threw
+    #[error("")]
+    Throw { value: Value, span: Range<usize> },
+
     #[error("")]
     ScriptFailed, // Script errors already reported, just signal failure
 }
 
+/// Another place an error points at, with what it has to do with the error
+#[derive(Debug, Clone, PartialEq)]
+pub struct Related {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// A call an error happened inside of
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The fita that was called
+    pub name: String,
+    /// Where it was called from
+    pub span: Range<usize>,
+}
+
+impl ManoError {
+    /// The error, also pointing at `span`
+    pub fn with_related(self, span: Range<usize>, message: impl Into<String>) -> ManoError {
+        self.detailed(|related, _, _| {
+            related.push(Related {
+                span,
+                message: message.into(),
+            })
+        })
+    }
+
+    /// The error, with a tip on how to fix it
+    pub fn with_help(self, help: impl Into<String>) -> ManoError {
+        self.detailed(|_, tip, _| *tip = Some(help.into()))
+    }
+
+    /// The error, with the calls it happened inside of
+    pub fn with_stack(self, frames: Vec<Frame>) -> ManoError {
+        self.detailed(|_, _, stack| *stack = frames)
+    }
+
+    fn detailed(
+        self,
+        change: impl FnOnce(&mut Vec<Related>, &mut Option<String>, &mut Vec<Frame>),
+    ) -> ManoError {
+        let (error, mut related, mut help, mut stack) = match self {
+            ManoError::Detailed {
+                error,
+                related,
+                help,
+                stack,
+            } => (error, related, help, stack),
+            error => (Box::new(error), Vec::new(), None, Vec::new()),
+        };
+        change(&mut related, &mut help, &mut stack);
+        ManoError::Detailed {
+            error,
+            related,
+            help,
+            stack,
+        }
+    }
+
+    /// The error itself, without the other places it points at
+    pub fn primary(&self) -> &ManoError {
+        match self {
+            ManoError::Detailed { error, .. } => error,
+            error => error,
+        }
+    }
+
+    /// The other places the error points at
+    pub fn related(&self) -> &[Related] {
+        match self {
+            ManoError::Detailed { related, .. } => related,
+            _ => &[],
+        }
+    }
+
+    /// How to fix the error, if there's a tip for it
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            ManoError::Detailed { help, .. } => help.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The calls the error happened inside of, the innermost first
+    pub fn stack(&self) -> &[Frame] {
+        match self {
+            ManoError::Detailed { stack, .. } => stack,
+            _ => &[],
+        }
+    }
+
+    /// Byte span of the code the error points at, if it points at any
+    pub fn span(&self) -> Option<&Range<usize>> {
+        match self {
+            ManoError::Detailed { error, .. } => error.span(),
+            ManoError::Scan { span, .. }
+            | ManoError::Parse { span, .. }
+            | ManoError::Runtime { span, .. }
+            | ManoError::Resolution { span, .. }
+            | ManoError::Warning { span, .. }
+            | ManoError::Output { span, .. }
+            | ManoError::Interrupted { span }
+            | ManoError::Throw { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    /// A `joga` nothing caught, as the runtime error it ends up being
+    pub fn uncaught(self) -> ManoError {
+        match self {
+            ManoError::Throw { value, span } => ManoError::Runtime {
+                message: format!("Jogaram {} e ninguém segurou, mano!", value),
+                span,
+            },
+            ManoError::Detailed {
+                error,
+                related,
+                help,
+                stack,
+            } => ManoError::Detailed {
+                error: Box::new(error.uncaught()),
+                related,
+                help,
+                stack,
+            },
+            error => error,
+        }
+    }
+
+    /// Whether this is output that couldn't be written because its reader
+    /// closed the pipe, which scripts should stop on quietly
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(
+            self.primary(),
+            ManoError::Output { source, .. } if source.kind() == std::io::ErrorKind::BrokenPipe
+        )
+    }
+
+    /// Line, column and length of the error in the indexed source, so tools
+    /// don't have to do the offset math themselves
+    pub fn locate(&self, index: &LineIndex) -> Option<SourceLocation> {
+        self.span().map(|span| index.locate(span))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,6 +238,46 @@ mod tests {
         assert_eq!(mano_err.to_string(), "Pô, véi! Cadê o arquivo?");
     }
 
+    #[test]
+    fn too_large_error_says_the_limit() {
+        let error = ManoError::TooLarge { limit: 1024 };
+        assert_eq!(
+            error.to_string(),
+            "Arquivo grande demais, mano! O limite é 1024 bytes."
+        );
+        assert_eq!(error.span(), None);
+    }
+
+    #[test]
+    fn uncaught_throw_becomes_a_runtime_error() {
+        let thrown = ManoError::Throw {
+            value: Value::Literal(crate::token::Literal::String("vish".to_string())),
+            span: 0..11,
+        };
+        assert_eq!(thrown.span(), Some(&(0..11)));
+        assert!(matches!(
+            thrown.uncaught(),
+            ManoError::Runtime { message, span }
+                if message == "Jogaram vish e ninguém segurou, mano!" && span == (0..11)
+        ));
+    }
+
+    #[test]
+    fn output_error_knows_a_broken_pipe() {
+        let closed = ManoError::Output {
+            source: Error::new(ErrorKind::BrokenPipe, "broken pipe"),
+            span: 0..8,
+        };
+        assert!(closed.is_broken_pipe());
+        assert_eq!(closed.span(), Some(&(0..8)));
+        let full = ManoError::Output {
+            source: Error::new(ErrorKind::StorageFull, "disk full"),
+            span: 0..8,
+        };
+        assert!(!full.is_broken_pipe());
+        assert!(!ManoError::Io(Error::new(ErrorKind::BrokenPipe, "broken pipe")).is_broken_pipe());
+    }
+
     #[test]
     fn scan_error_roasts_user() {
         let err = ManoError::Scan {
@@ -84,4 +313,76 @@ mod tests {
         };
         assert_eq!(err.to_string(), "Pô, mano! Erro de escopo!");
     }
+
+    #[test]
+    fn warning_tips_user_off() {
+        let err = ManoError::Warning {
+            message: "Essa condição é sempre firmeza".to_string(),
+            span: 0..7,
+        };
+        assert_eq!(err.to_string(), "Se liga, mano!");
+    }
+
+    #[test]
+    fn related_spans_keep_the_primary_error() {
+        let err = ManoError::Runtime {
+            message: "Essa fita espera 1 argumentos, mas tu passou 2, maluco!".to_string(),
+            span: 30..31,
+        }
+        .with_related(0..5, "declarada aqui")
+        .with_help("passa só um lance")
+        .with_related(10..12, "e aqui");
+        assert_eq!(err.to_string(), "Deu ruim na execução, brother!");
+        assert_eq!(err.span(), Some(&(30..31)));
+        assert!(matches!(err.primary(), ManoError::Runtime { .. }));
+        assert_eq!(err.help(), Some("passa só um lance"));
+        assert_eq!(
+            err.related(),
+            [
+                Related {
+                    span: 0..5,
+                    message: "declarada aqui".to_string()
+                },
+                Related {
+                    span: 10..12,
+                    message: "e aqui".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn stack_is_kept_with_the_other_details() {
+        let frame = Frame {
+            name: "dividir".to_string(),
+            span: 20..22,
+        };
+        let err = ManoError::Runtime {
+            message: "Dividir por zero?".to_string(),
+            span: 5..6,
+        }
+        .with_stack(vec![frame.clone()])
+        .with_help("confere o divisor");
+        assert_eq!(err.stack(), [frame]);
+        assert_eq!(err.help(), Some("confere o divisor"));
+        assert_eq!(err.span(), Some(&(5..6)));
+        assert!(ManoError::Internal("bug".to_string()).stack().is_empty());
+    }
+
+    #[test]
+    fn locates_errors_with_a_span() {
+        let index = LineIndex::new("seLiga x = 1;\nsalve y;").named("a.mano");
+        let err = ManoError::Runtime {
+            message: "Cadê 'y'?".to_string(),
+            span: 20..21,
+        };
+        let location = err.locate(&index).unwrap();
+        assert_eq!((location.line, location.col, location.len), (1, 6, 1));
+        assert_eq!(location.name.as_deref(), Some("a.mano"));
+        assert!(
+            ManoError::Internal("bug".to_string())
+                .locate(&index)
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,263 @@
+//! Source formatting: re-indents code by nesting and tidies whitespace.
+//!
+//! Works on the lossless token stream, so comments survive and text inside
+//! strings that span lines is never touched.
+
+use crate::diff::same_ast;
+use crate::error::ManoError;
+use crate::scanner::Scanner;
+use crate::source_map::SourceMap;
+use crate::token::{Token, TokenType};
+
+/// How to indent formatted code
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub use_tabs: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+        }
+    }
+}
+
+/// Formats the source.
+///
+/// Lines inside an open `{`, `(` or `[` are indented one level past the line
+/// that opened it, so several openers on one line only add one level, and a
+/// line starting with closers sits at the level of the line that opened the
+/// first one. Trailing whitespace is dropped, runs of blank lines become one
+/// and the file ends with a newline. Only scan errors stop it, so
+/// half-written code can still be formatted.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, Vec<ManoError>> {
+    format_with_map(source, options).map(SourceMap::into_text)
+}
+
+/// Like [`format`], also telling where each piece of the formatted code was in
+/// the source, so spans in it can be mapped back
+pub fn format_with_map(source: &str, options: &FormatOptions) -> Result<SourceMap, Vec<ManoError>> {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for result in Scanner::with_trivia(source) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let unit = if options.use_tabs {
+        "\t".to_string()
+    } else {
+        " ".repeat(options.indent_width)
+    };
+    // A token other than whitespace that runs over `offset`, like a string spanning lines
+    let spans_over = |tokens: &[Token], offset: usize| {
+        tokens.iter().any(|token| {
+            token.token_type != TokenType::Whitespace
+                && token.span.start < offset
+                && offset < token.span.end
+        })
+    };
+
+    let mut output = SourceMap::new();
+    // For each delimiter still open, the level of the line it's on
+    let mut open: Vec<usize> = Vec::new();
+    // The level of the last line written
+    let mut level = 0;
+    let mut next = 0;
+    let mut line_start = 0;
+    // A blank line to keep, written once more code follows it
+    let mut blank_line = false;
+
+    for line in source.split_inclusive('\n') {
+        let line_end = line_start + line.trim_end_matches('\n').len();
+
+        // Nesting at the start of the line, from every token that ended
+        // before it, all on the last line written
+        while next < tokens.len() && tokens[next].span.end <= line_start {
+            match tokens[next].token_type {
+                TokenType::LeftBrace | TokenType::LeftParen | TokenType::LeftBracket => {
+                    open.push(level)
+                }
+                TokenType::RightBrace | TokenType::RightParen | TokenType::RightBracket => {
+                    open.pop();
+                }
+                _ => {}
+            }
+            next += 1;
+        }
+
+        let rest = &tokens[next..];
+        let text_start = line_start;
+        let text = &source[line_start..line_end];
+        line_start += line.len();
+
+        if std::mem::take(&mut blank_line) && !text.trim().is_empty() {
+            output.push_generated("\n");
+        }
+
+        if spans_over(rest, line_end - text.len()) {
+            // The middle of a multi-line string or comment: leave it as written
+            output.push_original(text, text_start);
+            output.push_generated("\n");
+            continue;
+        }
+
+        let content = if spans_over(rest, line_end) {
+            text.trim_start()
+        } else {
+            text.trim()
+        };
+        if content.is_empty() {
+            blank_line = !output.text().is_empty();
+            continue;
+        }
+
+        // Closing delimiters at the start of the line sit at their opener's level
+        let closing = rest
+            .iter()
+            .skip_while(|token| token.token_type == TokenType::Whitespace)
+            .take_while(|token| {
+                matches!(
+                    token.token_type,
+                    TokenType::RightBrace
+                        | TokenType::RightParen
+                        | TokenType::RightBracket
+                        | TokenType::Whitespace
+                ) && token.span.start < line_end
+            })
+            .filter(|token| token.token_type != TokenType::Whitespace)
+            .count();
+        level = match closing {
+            0 => open.last().map_or(0, |opener| opener + 1),
+            _ => open
+                .len()
+                .checked_sub(closing)
+                .map_or(0, |first| open[first]),
+        };
+        output.push_generated(&unit.repeat(level));
+        output.push_original(content, text_start + text.len() - text.trim_start().len());
+        output.push_generated("\n");
+    }
+
+    Ok(output)
+}
+
+/// Formats the source, refusing to return code that parses differently.
+///
+/// The source must parse; its errors are returned as is. If the formatted
+/// code doesn't parse to the same tree (spans aside) it's a formatter bug and
+/// comes back as a [`ManoError::Internal`] instead of the broken code.
+pub fn format_checked(source: &str, options: &FormatOptions) -> Result<String, Vec<ManoError>> {
+    let before = crate::parse(source)?;
+    let formatted = format(source, options)?;
+    match crate::parse(&formatted) {
+        Ok(after) if same_ast(&before, &after) => Ok(formatted),
+        _ => Err(vec![ManoError::Internal(
+            "A formatação ia mudar o que o código faz, então deixei quieto.".to_string(),
+        )]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(source: &str) -> String {
+        format(source, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn indents_blocks() {
+        let source = "olhaEssaFita f(n) {\nsePá (n) {\nsalve n;\n}\n}\n";
+        assert_eq!(
+            formatted(source),
+            "olhaEssaFita f(n) {\n    sePá (n) {\n        salve n;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn indents_one_level_per_line() {
+        let source = "trata(olhaEssaFita () {\nsalve 1;\n});\n";
+        assert_eq!(
+            formatted(source),
+            "trata(olhaEssaFita () {\n    salve 1;\n});\n"
+        );
+        let source = "seLiga x = [[\n1,\n[2,\n3]\n]];\n";
+        assert_eq!(
+            formatted(source),
+            "seLiga x = [[\n    1,\n    [2,\n        3]\n]];\n"
+        );
+    }
+
+    #[test]
+    fn tidies_whitespace_and_blank_lines() {
+        let source = "\n\nsalve 1;   \n\n\n\nsalve 2;\t\n\n";
+        assert_eq!(formatted(source), "salve 1;\n\nsalve 2;\n");
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "{\n// oi\n   /* bloco */ salve 1;\n}";
+        assert_eq!(
+            formatted(source),
+            "{\n    // oi\n    /* bloco */ salve 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn leaves_multi_line_strings_alone() {
+        let source = "{\nsalve \"linha  \n   continua\";\n}\n";
+        assert_eq!(
+            formatted(source),
+            "{\n    salve \"linha  \n   continua\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn maps_formatted_spans_back_to_the_source() {
+        let source = "{\n\n\n   salve oi;\n}";
+        let map = format_with_map(source, &FormatOptions::default()).unwrap();
+        assert_eq!(map.text(), "{\n\n    salve oi;\n}\n");
+        let formatted = map.text().find("oi").unwrap();
+        assert_eq!(
+            map.span(&(formatted..formatted + 2)),
+            source.find("oi").unwrap()..source.find("oi").unwrap() + 2
+        );
+    }
+
+    #[test]
+    fn uses_tabs_when_asked() {
+        let options = FormatOptions {
+            indent_width: 2,
+            use_tabs: true,
+        };
+        assert_eq!(
+            format("{\nsalve 1;\n}", &options).unwrap(),
+            "{\n\tsalve 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_checked_returns_same_program() {
+        let source = "bagulho B {\nfalar() { toma \"oi {1 + 2}\"; }\n}\n";
+        let options = FormatOptions::default();
+        assert_eq!(
+            format_checked(source, &options).unwrap(),
+            format(source, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_checked_rejects_code_that_does_not_parse() {
+        let errors = format_checked("salve (1;", &FormatOptions::default()).unwrap_err();
+        assert!(matches!(errors[0], ManoError::Parse { .. }));
+    }
+}
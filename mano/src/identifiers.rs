@@ -0,0 +1,91 @@
+//! Identifiers that look like something they're not.
+
+use std::collections::{HashMap, HashSet};
+
+use unicode_security::{MixedScript, skeleton};
+
+use crate::error::ManoError;
+use crate::scanner::Scanner;
+use crate::token::TokenType;
+
+/// Warns about names that mix alphabets, like a Cyrillic `о` in an otherwise
+/// Latin name, and about different names that look the same. Each name is
+/// only reported where it first shows up.
+pub fn identifier_warnings(source: &str) -> Vec<ManoError> {
+    let mut warnings = Vec::new();
+    let mut warned = HashSet::new();
+    // What each name looks like, for the first name that looked that way
+    let mut looks: HashMap<String, String> = HashMap::new();
+
+    for token in Scanner::new(source).filter_map(Result::ok) {
+        if token.token_type != TokenType::Identifier || warned.contains(&*token.lexeme) {
+            continue;
+        }
+        let name = &*token.lexeme;
+        let message = if !name.is_single_script() {
+            Some(format!(
+                "'{}' mistura alfabetos, mano! Tem letra aí que só parece.",
+                name
+            ))
+        } else {
+            match looks.get(&skeleton(name).collect::<String>()) {
+                Some(other) if other != name => Some(format!(
+                    "'{}' parece '{}', mas é outro nome, mano!",
+                    name, other
+                )),
+                Some(_) => None,
+                None => {
+                    looks.insert(skeleton(name).collect(), name.to_string());
+                    None
+                }
+            }
+        };
+        if let Some(message) = message {
+            warned.insert(name.to_string());
+            warnings.push(ManoError::Warning {
+                message,
+                span: token.span,
+            });
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        identifier_warnings(source)
+            .into_iter()
+            .map(|w| match w {
+                ManoError::Warning { message, .. } => message,
+                other => panic!("expected a warning, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn warns_about_mixed_alphabets_once() {
+        // The 'о' is Cyrillic
+        let warnings = messages("seLiga nоme = 1; salve nоme;");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("mistura alfabetos"));
+    }
+
+    #[test]
+    fn warns_about_names_that_look_alike() {
+        let warnings = messages("seLiga valor1 = 1; seLiga valorl = 2; salve valorl;");
+        assert_eq!(
+            warnings,
+            ["'valorl' parece 'valor1', mas é outro nome, mano!"]
+        );
+    }
+
+    #[test]
+    fn accepts_accents_emoji_and_single_scripts() {
+        assert!(
+            messages("seLiga ação = 1; seLiga 🔥 = 2; seLiga имя = 3; seLiga 名前 = 4;").is_empty()
+        );
+    }
+}
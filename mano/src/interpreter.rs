@@ -1,38 +1,365 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::io::Write;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::SystemTime;
 
 use crate::INITIALIZER_NAME;
 use crate::ast::{Expr, InterpolationPart, Stmt};
-use crate::environment::Environment;
-use crate::error::ManoError;
+use crate::bitwise::MAX_EXACT;
+use crate::environment::{Environment, Symbol, SymbolKind};
+use crate::error::{Frame, ManoError};
+use crate::module::{self, Load, Modules};
+use crate::random::Random;
 use crate::resolver::Resolutions;
-use crate::token::{Literal, TokenType};
-use crate::value::{Class, Function, Instance, ManoFunction, NativeFunction, Value};
+use crate::sandbox::{SandboxPolicy, denied};
+use crate::token::{Literal, Token, TokenType};
+use crate::value::{Class, Function, Instance, ManoFunction, NativeFunction, Value, Variants};
 
 pub struct Interpreter {
     environment: Rc<RefCell<Environment>>,
     globals: Rc<RefCell<Environment>>,
-    resolutions: Resolutions,
+    /// Bindings of the code running now; fitas carry the ones they were declared with
+    resolutions: Rc<Resolutions>,
+    /// Add the operand values to operator errors (`--explica`)
+    explain: bool,
+    /// Let `+` join texto with a number, lógico or nadaNão (`--junta-texto`)
+    text_coercion: bool,
+    /// How many expressions are being evaluated one inside the other, to
+    /// know the outermost one
+    depth: usize,
+    /// Roughly where the stack was at the outermost expression
+    stack_base: usize,
+    /// How much stack evaluating may use before stopping with an error,
+    /// unchecked when `None`
+    stack_budget: Option<usize>,
+    /// What the natives are allowed to do
+    policy: SandboxPolicy,
+    /// Set from outside (Ctrl+C) to stop before the next statement
+    interrupt: Option<Arc<AtomicBool>>,
+    /// Files loaded with `chama`
+    modules: Modules,
+    /// The fitas running now, by name (`None` for lambdas) with where each
+    /// was called, the innermost last
+    call_stack: Vec<(Option<Rc<str>>, Range<usize>)>,
+}
+
+/// Stack for the thread the interpreter runs on, see
+/// [`Interpreter::set_stack_size`]
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Stack a fita call takes, rounded up from what a recursive call in an
+/// expression measured in debug builds, about 58 KiB. Release builds take
+/// about 5 KiB. Each level of an expression nested in another takes about
+/// 22 KiB and 1.5 KiB.
+const CALL_STACK_BYTES: usize = 64 * 1024;
+
+/// Most fita calls that can run one inside the other before running stops
+/// with an error, as many as fit in [`STACK_SIZE`] in any build
+pub const MAX_CALL_DEPTH: usize = STACK_SIZE / CALL_STACK_BYTES;
+
+/// Roughly where the stack is now, to measure how much of it is in use
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Adds what each operand held to an operator's runtime error
+fn explained(error: ManoError, operands: &[(&str, &Value)]) -> ManoError {
+    let (message, span) = match error {
+        ManoError::Runtime { message, span } => (message, span),
+        ManoError::Detailed {
+            error,
+            related,
+            help,
+            stack,
+        } => {
+            return ManoError::Detailed {
+                error: Box::new(explained(*error, operands)),
+                related,
+                help,
+                stack,
+            };
+        }
+        other => return other,
+    };
+    let values: Vec<String> = operands
+        .iter()
+        .map(|(side, value)| format!("{} era {}", side, describe(value)))
+        .collect();
+    ManoError::Runtime {
+        message: format!("{} ({})", message, values.join(", ")),
+        span,
+    }
+}
+
+/// The number written in `text`, spaces around it allowed. Only digits, signs,
+/// a point and an exponent count, and it has to fit, so `"inf"`, `"NaN"` and
+/// `"1e400"` aren't numbers.
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if !text
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'))
+    {
+        return None;
+    }
+    text.parse().ok().filter(|n: &f64| n.is_finite())
+}
+
+/// A value as a beginner would read it: `"mano" (texto)`, `3 (número)`
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Literal(Literal::String(s)) => format!("\"{}\" (texto)", s),
+        Value::Literal(Literal::Nil) => value.to_string(),
+        _ => format!("{} ({})", value, value.kind_name()),
+    }
+}
+
+/// The method `object.name(...)` would call, when it's a plain method of an
+/// instance: fields shadow methods and getters run on access, so those go the
+/// slow way.
+fn invoked_method(object: &Value, name: &Token) -> Option<(Rc<Instance>, Rc<Function>)> {
+    let Value::Instance(instance) = object else {
+        return None;
+    };
+    if instance.fields.borrow().contains_key(&*name.lexeme) {
+        return None;
+    }
+    let method = instance.class.find_method(&name.lexeme)?;
+    match method.as_ref() {
+        Function::Mano(func) if !func.is_getter => Some((Rc::clone(instance), method)),
+        _ => None,
+    }
+}
+
+/// The token of an expression that errors point at, when it has one
+fn located_by(expr: &Expr) -> Option<&Token> {
+    match expr {
+        Expr::Binary { operator, .. }
+        | Expr::Logical { operator, .. }
+        | Expr::Unary { operator, .. } => Some(operator),
+        Expr::Call { paren, .. } => Some(paren),
+        Expr::ListLiteral { bracket, .. }
+        | Expr::Index { bracket, .. }
+        | Expr::IndexSet { bracket, .. } => Some(bracket),
+        Expr::Variable { name }
+        | Expr::Assign { name, .. }
+        | Expr::Get { name, .. }
+        | Expr::Set { name, .. } => Some(name),
+        Expr::This { keyword } | Expr::Super { keyword, .. } => Some(keyword),
+        _ => None,
+    }
+}
+
+/// Where `lista[index]` is in a list of `len` items
+fn list_slot(len: usize, index: &Value, bracket: &Token) -> Result<usize, ManoError> {
+    match index {
+        Value::Literal(Literal::Number(n)) if is_whole(*n) && *n >= 0.0 && (*n as usize) < len => {
+            Ok(*n as usize)
+        }
+        Value::Literal(Literal::Number(_)) if len == 0 => Err(ManoError::Runtime {
+            message: format!("A lista tá vazia, mano! Não tem item {} aí.", index),
+            span: bracket.span.clone(),
+        }),
+        Value::Literal(Literal::Number(_)) => Err(ManoError::Runtime {
+            message: format!("Não tem item {} aí, mano! Vai de 0 a {}.", index, len - 1),
+            span: bracket.span.clone(),
+        }),
+        other => Err(ManoError::Runtime {
+            message: format!("Índice de lista é número, não {}!", describe(other)),
+            span: bracket.span.clone(),
+        }),
+    }
+}
+
+/// `lista.adiciona`, `lista.tira` and `lista.tamanho`. The fitas come bound
+/// to the list, so `seLiga põe = lista.adiciona;` keeps adding to it.
+fn list_property(list: Rc<RefCell<Vec<Value>>>, name: &Token) -> Result<Value, ManoError> {
+    let native = match &*name.lexeme {
+        "tamanho" => return Ok(Value::Literal(Literal::Number(list.borrow().len() as f64))),
+        "adiciona" => NativeFunction {
+            name: "adiciona".to_string(),
+            arity: 1,
+            func: Rc::new(move |args| {
+                list.borrow_mut().push(args[0].clone());
+                Ok(Value::Literal(Literal::Nil))
+            }),
+        },
+        "tira" => NativeFunction {
+            name: "tira".to_string(),
+            arity: 0,
+            func: Rc::new(move |_| {
+                list.borrow_mut().pop().ok_or_else(|| ManoError::Runtime {
+                    message: "A lista tá vazia, mano! Não tem o que tirar.".to_string(),
+                    span: 0..0,
+                })
+            }),
+        },
+        _ => {
+            return Err(ManoError::Runtime {
+                message: format!(
+                    "Eita, '{}' não existe na lista! Tem adiciona, tira e tamanho.",
+                    name.lexeme
+                ),
+                span: name.span.clone(),
+            });
+        }
+    };
+    Ok(Value::Function(Rc::new(Function::Native(native))))
+}
+
+/// The error for `[]` on something that isn't a list
+fn not_a_list(value: &Value, bracket: &Token) -> ManoError {
+    ManoError::Runtime {
+        message: format!("Só dá pra usar [] em lista, não {}!", describe(value)),
+        span: bracket.span.clone(),
+    }
+}
+
+/// The system shell, set to run `command`
+fn shell(command: &str) -> std::process::Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Whether a number is whole and small enough to count on exactly
+fn is_whole(n: f64) -> bool {
+    n.fract() == 0.0 && n.abs() <= MAX_EXACT as f64
+}
+
+/// A whole-number argument of an integer native
+fn whole_arg(native: &str, value: &Value) -> Result<i128, ManoError> {
+    match value {
+        Value::Literal(Literal::Number(n)) if is_whole(*n) => Ok(*n as i128),
+        other => Err(ManoError::Runtime {
+            message: format!("{} quer número inteiro, não {}!", native, describe(other)),
+            span: 0..0,
+        }),
+    }
+}
+
+/// An integer native's result, unless it's too big to stay exact
+fn whole_result(native: &str, n: i128) -> Result<Value, ManoError> {
+    if n.abs() > MAX_EXACT {
+        return Err(ManoError::Runtime {
+            message: format!(
+                "{} passou de 2^53, aí a conta deixa de ser exata, mano!",
+                native
+            ),
+            span: 0..0,
+        });
+    }
+    Ok(Value::Literal(Literal::Number(n as f64)))
+}
+
+/// The modulus for `multiplicaMod` and `potenciaMod`, which has to be positive
+fn modulus_arg(native: &str, value: &Value) -> Result<i128, ManoError> {
+    match whole_arg(native, value)? {
+        m if m > 0 => Ok(m),
+        _ => Err(ManoError::Runtime {
+            message: format!("{} quer um módulo maior que zero, chapa!", native),
+            span: 0..0,
+        }),
+    }
+}
+
+/// A line from `input` without its line break, or `nadaNão` once it's over
+fn read_line(input: &mut dyn BufRead) -> Result<Value, ManoError> {
+    let mut line = String::new();
+    let read = input.read_line(&mut line).map_err(|e| ManoError::Runtime {
+        message: format!("Não rolou ler a entrada: {}", e),
+        span: 0..0,
+    })?;
+    if read == 0 {
+        return Ok(Value::Literal(Literal::Nil));
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Value::Literal(Literal::String(line)))
+}
+
+/// The value of option `name` in command-line arguments: the text after
+/// `--name` (or in `--name=text`), `firmeza` for a `--name` flag with no
+/// value, or `nadaNão` when it's not there. Nothing after `--` is an option.
+fn option_value(args: &[String], name: &str) -> Value {
+    let flag = format!("--{}", name);
+    let mut args = args.iter().take_while(|arg| *arg != "--").peekable();
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg
+            .strip_prefix(&flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Value::Literal(Literal::String(value.to_string()));
+        }
+        if *arg == flag {
+            return match args.next_if(|next| !next.starts_with("--")) {
+                Some(value) => Value::Literal(Literal::String(value.clone())),
+                None => Value::Literal(Literal::Bool(true)),
+            };
+        }
+    }
+    Value::Literal(Literal::Nil)
+}
+
+fn broken_syntax(span: &Range<usize>) -> ManoError {
+    ManoError::Runtime {
+        message: "Esse trecho tá quebrado, mano! Arruma a sintaxe antes de rodar.".to_string(),
+        span: span.clone(),
+    }
+}
+
+/// What a `seDeuRuim` gets for an error: the value a `joga` threw, or the
+/// message of a runtime error. Control flow, a Ctrl+C and output that can't
+/// be written go on past it.
+fn caught(error: ManoError) -> Result<Value, ManoError> {
+    match error.primary() {
+        ManoError::Throw { value, .. } => Ok(value.clone()),
+        ManoError::Runtime { message, .. } => Ok(Value::Literal(Literal::String(message.clone()))),
+        _ => Err(error),
+    }
 }
 
 impl Interpreter {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_policy(SandboxPolicy::default())
+    }
+
+    pub fn with_policy(policy: SandboxPolicy) -> Self {
         let environment = Rc::new(RefCell::new(Environment::new()));
 
         // Register native function: fazTeuCorre (clock)
+        let clock = policy.clone();
         let faz_teu_corre = NativeFunction {
             name: "fazTeuCorre".to_string(),
             arity: 0,
-            func: |_| {
-                let time = SystemTime::now()
+            func: Rc::new(move |_| {
+                let now = SystemTime::now()
                     .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs_f64();
+                    .unwrap();
+                let time = clock
+                    .clock_seconds(now)
+                    .ok_or_else(|| denied("fazTeuCorre"))?;
                 Ok(Value::Literal(Literal::Number(time)))
-            },
+            }),
         };
         environment.borrow_mut().define(
             "fazTeuCorre".to_string(),
@@ -43,37 +370,661 @@ impl Interpreter {
         let vira_texto = NativeFunction {
             name: "viraTexto".to_string(),
             arity: 1,
-            func: |args| {
+            func: Rc::new(|args| {
                 let value = &args[0];
                 Ok(Value::Literal(Literal::String(format!("{}", value))))
-            },
+            }),
         };
         environment.borrow_mut().define(
             "viraTexto".to_string(),
             Value::Function(Rc::new(Function::Native(vira_texto))),
         );
 
-        Self {
+        // Register native function: queTipoE(x), the kind of value x is, named
+        // like the type errors name it
+        let que_tipo_e = NativeFunction {
+            name: "queTipoE".to_string(),
+            arity: 1,
+            func: Rc::new(|args| {
+                Ok(Value::Literal(Literal::String(
+                    args[0].kind_name().to_string(),
+                )))
+            }),
+        };
+        environment.borrow_mut().define(
+            "queTipoE".to_string(),
+            Value::Function(Rc::new(Function::Native(que_tipo_e))),
+        );
+
+        // Register conversion natives: textoPraNumero(texto), nadaNão when the
+        // texto isn't a number, and numeroPraTexto(n)
+        let texto_pra_numero = NativeFunction {
+            name: "textoPraNumero".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Literal(Literal::String(text)) => Ok(Value::Literal(
+                    parse_number(text).map_or(Literal::Nil, Literal::Number),
+                )),
+                other => Err(ManoError::Runtime {
+                    message: format!("textoPraNumero quer texto, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        let numero_pra_texto = NativeFunction {
+            name: "numeroPraTexto".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Literal(Literal::Number(n)) => {
+                    Ok(Value::Literal(Literal::String(n.to_string())))
+                }
+                other => Err(ManoError::Runtime {
+                    message: format!("numeroPraTexto quer número, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        for native in [texto_pra_numero, numero_pra_texto] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register native functions: aleatorio() and aleatorioEntre(a, b),
+        // sharing one generator so a seed fixes the whole sequence
+        let random = Rc::new(RefCell::new(
+            policy.seed.map_or_else(Random::from_time, Random::new),
+        ));
+        let generator = Rc::clone(&random);
+        let aleatorio = NativeFunction {
+            name: "aleatorio".to_string(),
+            arity: 0,
+            func: Rc::new(move |_| {
+                Ok(Value::Literal(Literal::Number(
+                    generator.borrow_mut().next_f64(),
+                )))
+            }),
+        };
+        let aleatorio_entre = NativeFunction {
+            name: "aleatorioEntre".to_string(),
+            arity: 2,
+            func: Rc::new(move |args| {
+                let (low, high) = match (&args[0], &args[1]) {
+                    (
+                        Value::Literal(Literal::Number(low)),
+                        Value::Literal(Literal::Number(high)),
+                    ) if is_whole(*low) && is_whole(*high) => (*low as i64, *high as i64),
+                    _ => {
+                        return Err(ManoError::Runtime {
+                            message: "aleatorioEntre quer dois números inteiros, mano!".to_string(),
+                            span: 0..0,
+                        });
+                    }
+                };
+                if low > high {
+                    return Err(ManoError::Runtime {
+                        message: format!(
+                            "Não tem número entre {} e {}, o primeiro tem que ser o menor!",
+                            low, high
+                        ),
+                        span: 0..0,
+                    });
+                }
+                let n = random.borrow_mut().between(low, high);
+                Ok(Value::Literal(Literal::Number(n as f64)))
+            }),
+        };
+        for native in [aleatorio, aleatorio_entre] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register native functions for bytes: viraBytes, tamanho and pegaByte,
+        // plus leBytes and escreveBytes for binary files
+        let vira_bytes = NativeFunction {
+            name: "viraBytes".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Literal(Literal::String(text)) => {
+                    Ok(Value::Literal(Literal::Bytes(text.as_bytes().to_vec())))
+                }
+                bytes @ Value::Literal(Literal::Bytes(_)) => Ok(bytes.clone()),
+                other => Err(ManoError::Runtime {
+                    message: format!("viraBytes quer um texto, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        let tamanho = NativeFunction {
+            name: "tamanho".to_string(),
+            arity: 1,
+            func: Rc::new(|args| {
+                let length = match &args[0] {
+                    Value::Literal(Literal::String(text)) => text.chars().count(),
+                    Value::Literal(Literal::Bytes(bytes)) => bytes.len(),
+                    Value::List(list) => list.borrow().len(),
+                    other => {
+                        return Err(ManoError::Runtime {
+                            message: format!(
+                                "tamanho é só pra texto, bytes ou lista, não {}!",
+                                describe(other)
+                            ),
+                            span: 0..0,
+                        });
+                    }
+                };
+                Ok(Value::Literal(Literal::Number(length as f64)))
+            }),
+        };
+        let pega_byte = NativeFunction {
+            name: "pegaByte".to_string(),
+            arity: 2,
+            func: Rc::new(|args| {
+                let Value::Literal(Literal::Bytes(bytes)) = &args[0] else {
+                    return Err(ManoError::Runtime {
+                        message: format!("pegaByte quer bytes, não {}!", describe(&args[0])),
+                        span: 0..0,
+                    });
+                };
+                match &args[1] {
+                    Value::Literal(Literal::Number(index))
+                        if is_whole(*index) && *index >= 0.0 && (*index as usize) < bytes.len() =>
+                    {
+                        Ok(Value::Literal(Literal::Number(
+                            bytes[*index as usize] as f64,
+                        )))
+                    }
+                    index => Err(ManoError::Runtime {
+                        message: format!(
+                            "Não tem byte {} aí, mano! Vai de 0 a {}.",
+                            index,
+                            bytes.len() as f64 - 1.0
+                        ),
+                        span: 0..0,
+                    }),
+                }
+            }),
+        };
+        let allowed = policy.fs;
+        let le_bytes = NativeFunction {
+            name: "leBytes".to_string(),
+            arity: 1,
+            func: Rc::new(move |args| {
+                if !allowed {
+                    return Err(denied("leBytes"));
+                }
+                let Value::Literal(Literal::String(path)) = &args[0] else {
+                    return Err(ManoError::Runtime {
+                        message: format!(
+                            "leBytes quer o caminho em texto, não {}!",
+                            describe(&args[0])
+                        ),
+                        span: 0..0,
+                    });
+                };
+                let bytes = std::fs::read(path).map_err(|e| ManoError::Runtime {
+                    message: format!("Não rolou ler '{}': {}", path, e),
+                    span: 0..0,
+                })?;
+                Ok(Value::Literal(Literal::Bytes(bytes)))
+            }),
+        };
+        let escreve_bytes = NativeFunction {
+            name: "escreveBytes".to_string(),
+            arity: 2,
+            func: Rc::new(move |args| {
+                if !allowed {
+                    return Err(denied("escreveBytes"));
+                }
+                let (Value::Literal(Literal::String(path)), Value::Literal(Literal::Bytes(bytes))) =
+                    (&args[0], &args[1])
+                else {
+                    return Err(ManoError::Runtime {
+                        message: "escreveBytes quer o caminho em texto e os bytes, mano!"
+                            .to_string(),
+                        span: 0..0,
+                    });
+                };
+                std::fs::write(path, bytes).map_err(|e| ManoError::Runtime {
+                    message: format!("Não rolou escrever '{}': {}", path, e),
+                    span: 0..0,
+                })?;
+                Ok(Value::Literal(Literal::Nil))
+            }),
+        };
+        for native in [vira_bytes, tamanho, pega_byte, le_bytes, escreve_bytes] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register native functions for weak references: refFraca(parada)
+        // and pegaRef(ref), which gives the parada back or nadaNão once it's gone
+        let ref_fraca = NativeFunction {
+            name: "refFraca".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Instance(instance) => Ok(Value::Weak(Rc::downgrade(instance))),
+                weak @ Value::Weak(_) => Ok(weak.clone()),
+                other => Err(ManoError::Runtime {
+                    message: format!("refFraca é só pra parada, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        let pega_ref = NativeFunction {
+            name: "pegaRef".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Weak(weak) => Ok(weak
+                    .upgrade()
+                    .map_or(Value::Literal(Literal::Nil), Value::Instance)),
+                other => Err(ManoError::Runtime {
+                    message: format!("pegaRef quer uma ref fraca, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        for native in [ref_fraca, pega_ref] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register esqueceCampo(parada, nome), which drops a field so a method
+        // it shadowed shows again, and says whether the field was there
+        let esquece_campo = NativeFunction {
+            name: "esqueceCampo".to_string(),
+            arity: 2,
+            func: Rc::new(|args| match (&args[0], &args[1]) {
+                (Value::Instance(instance), Value::Literal(Literal::String(name))) => {
+                    if instance.frozen.get() {
+                        return Err(ManoError::Runtime {
+                            message: format!(
+                                "Essa parada tá congelada, mano! Não dá pra esquecer o '{}'.",
+                                name
+                            ),
+                            span: 0..0,
+                        });
+                    }
+                    let removed = instance.fields.borrow_mut().remove(name).is_some();
+                    if removed {
+                        // Memoized getters may depend on the field
+                        instance.memos.borrow_mut().clear();
+                    }
+                    Ok(Value::Literal(Literal::Bool(removed)))
+                }
+                (Value::Instance(_), other) => Err(ManoError::Runtime {
+                    message: format!(
+                        "esqueceCampo quer o nome do campo em texto, não {}!",
+                        describe(other)
+                    ),
+                    span: 0..0,
+                }),
+                (other, _) => Err(ManoError::Runtime {
+                    message: format!("esqueceCampo é só pra parada, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        // Register congela(parada), which stops its fields from changing and
+        // gives it back, for constants built from bagulhos
+        let congela = NativeFunction {
+            name: "congela".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Instance(instance) => {
+                    instance.frozen.set(true);
+                    Ok(args[0].clone())
+                }
+                other => Err(ManoError::Runtime {
+                    message: format!("congela é só pra parada, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        for native in [esquece_campo, congela] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register integer natives, for exact arithmetic on whole numbers:
+        // inteiro, divInteira, multiplicaMod and potenciaMod
+        let inteiro = NativeFunction {
+            name: "inteiro".to_string(),
+            arity: 1,
+            func: Rc::new(|args| match &args[0] {
+                Value::Literal(Literal::Number(n)) if n.is_finite() => {
+                    whole_result("inteiro", n.trunc() as i128)
+                }
+                Value::Literal(Literal::String(text)) => match text.trim().parse::<i128>() {
+                    Ok(n) => whole_result("inteiro", n),
+                    Err(_) => Err(ManoError::Runtime {
+                        message: format!("'{}' não é número inteiro, mano!", text),
+                        span: 0..0,
+                    }),
+                },
+                other => Err(ManoError::Runtime {
+                    message: format!("inteiro quer número ou texto, não {}!", describe(other)),
+                    span: 0..0,
+                }),
+            }),
+        };
+        let div_inteira = NativeFunction {
+            name: "divInteira".to_string(),
+            arity: 2,
+            func: Rc::new(|args| {
+                let a = whole_arg("divInteira", &args[0])?;
+                let b = whole_arg("divInteira", &args[1])?;
+                if b == 0 {
+                    return Err(ManoError::Runtime {
+                        message: "Dividir por zero não dá, mano!".to_string(),
+                        span: 0..0,
+                    });
+                }
+                // Rounds down: divInteira(-7, 2) is -4
+                let quotient = a / b;
+                let rounds_up = a % b != 0 && (a < 0) != (b < 0);
+                whole_result("divInteira", quotient - i128::from(rounds_up))
+            }),
+        };
+        let multiplica_mod = NativeFunction {
+            name: "multiplicaMod".to_string(),
+            arity: 3,
+            func: Rc::new(|args| {
+                let a = whole_arg("multiplicaMod", &args[0])?;
+                let b = whole_arg("multiplicaMod", &args[1])?;
+                let m = modulus_arg("multiplicaMod", &args[2])?;
+                whole_result("multiplicaMod", (a * b).rem_euclid(m))
+            }),
+        };
+        let potencia_mod = NativeFunction {
+            name: "potenciaMod".to_string(),
+            arity: 3,
+            func: Rc::new(|args| {
+                let mut base = whole_arg("potenciaMod", &args[0])?;
+                let mut exponent = whole_arg("potenciaMod", &args[1])?;
+                let m = modulus_arg("potenciaMod", &args[2])?;
+                if exponent < 0 {
+                    return Err(ManoError::Runtime {
+                        message: "potenciaMod quer expoente que não seja negativo, chapa!"
+                            .to_string(),
+                        span: 0..0,
+                    });
+                }
+                base = base.rem_euclid(m);
+                let mut result = 1 % m;
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        result = result * base % m;
+                    }
+                    base = base * base % m;
+                    exponent >>= 1;
+                }
+                whole_result("potenciaMod", result)
+            }),
+        };
+        for native in [inteiro, div_inteira, multiplica_mod, potencia_mod] {
+            environment.borrow_mut().define(
+                native.name.clone(),
+                Value::Function(Rc::new(Function::Native(native))),
+            );
+        }
+
+        // Register native function: rodaComando(cmd) - runs a shell command,
+        // returning a Resultado with its saida, erro and status
+        let allowed = policy.process;
+        let resultado = Rc::new(Class {
+            name: "Resultado".to_string(),
+            superclass: None,
+            methods: HashMap::new(),
+            static_methods: HashMap::new(),
+            variants: Variants::default(),
+        });
+        let roda_comando = NativeFunction {
+            name: "rodaComando".to_string(),
+            arity: 1,
+            func: Rc::new(move |args| {
+                if !allowed {
+                    return Err(denied("rodaComando"));
+                }
+                let Value::Literal(Literal::String(command)) = &args[0] else {
+                    return Err(ManoError::Runtime {
+                        message: format!(
+                            "rodaComando quer o comando em texto, não {}!",
+                            describe(&args[0])
+                        ),
+                        span: 0..0,
+                    });
+                };
+                let output = shell(command).output().map_err(|e| ManoError::Runtime {
+                    message: format!("Não rolou rodar '{}': {}", command, e),
+                    span: 0..0,
+                })?;
+                let text = |bytes: &[u8]| {
+                    Value::Literal(Literal::String(String::from_utf8_lossy(bytes).into_owned()))
+                };
+                let status = output
+                    .status
+                    .code()
+                    .map_or(Literal::Nil, |code| Literal::Number(code as f64));
+                let fields = HashMap::from([
+                    ("saida".to_string(), text(&output.stdout)),
+                    ("erro".to_string(), text(&output.stderr)),
+                    ("status".to_string(), Value::Literal(status)),
+                ]);
+                Ok(Value::Instance(Rc::new(Instance {
+                    class: Rc::clone(&resultado),
+                    fields: RefCell::new(fields),
+                    memos: RefCell::default(),
+                    frozen: Cell::default(),
+                })))
+            }),
+        };
+        environment.borrow_mut().define(
+            "rodaComando".to_string(),
+            Value::Function(Rc::new(Function::Native(roda_comando))),
+        );
+
+        let mut interpreter = Self {
             globals: Rc::clone(&environment),
             environment,
-            resolutions: Resolutions::new(),
-        }
+            resolutions: Rc::default(),
+            explain: false,
+            text_coercion: false,
+            depth: 0,
+            stack_base: 0,
+            stack_budget: None,
+            policy,
+            interrupt: None,
+            modules: Modules::default(),
+            call_stack: Vec::new(),
+        };
+        interpreter.set_args(Vec::new());
+        interpreter.define_le_ai(|| read_line(&mut io::stdin().lock()));
+        interpreter
     }
 
     pub fn variable_names(&self) -> Vec<String> {
         self.environment.borrow().variable_names()
     }
 
+    /// Global names and their current values, sorted by name
+    pub fn globals(&self) -> Vec<(String, Option<Value>)> {
+        self.globals.borrow().named_values()
+    }
+
+    /// Fields and methods reachable with `name.` for the value currently bound
+    /// to `name`: an instance's fields and its class's methods, or a class's
+    /// static methods. Empty for anything else.
+    pub fn members(&self, name: &str) -> Vec<Symbol> {
+        let Ok(value) = self.environment.borrow().get(name, 0..0) else {
+            return Vec::new();
+        };
+
+        let mut members: Vec<Symbol> = Vec::new();
+        let mut push = |name: &str, kind: SymbolKind| {
+            if !members.iter().any(|member| member.name == name) {
+                members.push(Symbol {
+                    name: name.to_string(),
+                    kind,
+                });
+            }
+        };
+
+        let mut class = match &value {
+            Value::Instance(instance) => {
+                let fields = instance.fields.borrow();
+                let mut names: Vec<_> = fields.keys().collect();
+                names.sort();
+                for field in names {
+                    push(field, SymbolKind::Field);
+                }
+                Some(Rc::clone(&instance.class))
+            }
+            Value::Class(class) => {
+                for variant in class.variants.names() {
+                    push(&variant, SymbolKind::Field);
+                }
+                Some(Rc::clone(class))
+            }
+            _ => None,
+        };
+
+        // Walk up the superclass chain; subclass methods shadow the parent's
+        while let Some(current) = class {
+            let methods = match &value {
+                Value::Instance(_) => &current.methods,
+                _ => &current.static_methods,
+            };
+            let mut names: Vec<_> = methods
+                .keys()
+                .filter(|method| method.as_str() != INITIALIZER_NAME)
+                .collect();
+            names.sort();
+            for method in names {
+                push(method, SymbolKind::Method);
+            }
+            class = current.superclass.clone();
+        }
+
+        members
+    }
+
+    /// Names in scope with their kinds, optionally leaving out native fitas
+    pub fn symbols(&self, include_natives: bool) -> Vec<Symbol> {
+        let mut symbols = self.environment.borrow().symbols();
+        if !include_natives {
+            symbols.retain(|symbol| symbol.kind != SymbolKind::Native);
+        }
+        symbols
+    }
+
+    pub fn set_explain(&mut self, explain: bool) {
+        self.explain = explain;
+    }
+
+    /// Stop deep code with an error before it overflows a stack of `size`
+    /// bytes, the one the interpreter runs on. Some of it is left for what
+    /// runs past the check, like natives and building the error.
+    pub fn set_stack_size(&mut self, size: usize) {
+        self.stack_budget = Some(size - size / 8);
+    }
+
+    pub fn set_text_coercion(&mut self, text_coercion: bool) {
+        self.text_coercion = text_coercion;
+    }
+
+    pub fn text_coercion(&self) -> bool {
+        self.text_coercion
+    }
+
+    /// The arguments the script was run with, for `pegaOpcao`
+    pub fn set_args(&mut self, args: Vec<String>) {
+        // Register native function: pegaOpcao(nome) - `--nome valor` from the arguments
+        let allowed = self.policy.env;
+        let pega_opcao = NativeFunction {
+            name: "pegaOpcao".to_string(),
+            arity: 1,
+            func: Rc::new(move |values| match &values[0] {
+                _ if !allowed => Err(denied("pegaOpcao")),
+                Value::Literal(Literal::String(name)) => Ok(option_value(&args, name)),
+                other => Err(ManoError::Runtime {
+                    message: format!(
+                        "pegaOpcao quer o nome da opção em texto, não {}!",
+                        describe(other)
+                    ),
+                    span: 0..0,
+                }),
+            }),
+        };
+        self.globals.borrow_mut().define(
+            "pegaOpcao".to_string(),
+            Value::Function(Rc::new(Function::Native(pega_opcao))),
+        );
+    }
+
+    /// Where `leAi` reads lines from, instead of stdin
+    pub fn set_input(&mut self, input: Box<dyn BufRead>) {
+        let input = RefCell::new(input);
+        self.define_le_ai(move || read_line(&mut **input.borrow_mut()));
+    }
+
+    /// Register native function: leAi() - the next line of input, `nadaNão` at the end
+    fn define_le_ai(&mut self, read: impl Fn() -> Result<Value, ManoError> + 'static) {
+        let allowed = self.policy.input;
+        let le_ai = NativeFunction {
+            name: "leAi".to_string(),
+            arity: 0,
+            func: Rc::new(move |_| {
+                if !allowed {
+                    return Err(denied("leAi"));
+                }
+                read()
+            }),
+        };
+        self.globals.borrow_mut().define(
+            "leAi".to_string(),
+            Value::Function(Rc::new(Function::Native(le_ai))),
+        );
+    }
+
+    /// Where the paths in the main script's `chama`s start from
+    pub fn set_module_dir(&mut self, dir: &Path) {
+        self.modules.set_base(dir);
+    }
+
     pub fn set_resolutions(&mut self, resolutions: Resolutions) {
-        self.resolutions = resolutions;
+        self.resolutions = Rc::new(resolutions);
+    }
+
+    /// Stop with [`ManoError::Interrupted`] at the next statement once `flag`
+    /// is set, clearing it so the next run goes on normally
+    pub fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
     }
 
     pub fn execute(&mut self, stmt: &Stmt, output: &mut dyn Write) -> Result<(), ManoError> {
+        if let Some(flag) = &self.interrupt
+            && flag.load(Ordering::Relaxed)
+        {
+            flag.store(false, Ordering::Relaxed);
+            return Err(ManoError::Interrupted { span: stmt.span() });
+        }
         match stmt {
-            Stmt::Print { expression, .. } => {
+            Stmt::Print { expression, span } => {
                 let value = self.interpret(expression, output)?;
-                writeln!(output, "{}", value)?;
-                Ok(())
+                writeln!(output, "{}", value).map_err(|source| ManoError::Output {
+                    source,
+                    span: span.clone(),
+                })
             }
             Stmt::Expression { expression, .. } => {
                 self.interpret(expression, output)?;
@@ -88,21 +1039,21 @@ impl Interpreter {
                         let value = self.interpret(expr, output)?;
                         self.environment
                             .borrow_mut()
-                            .define(name.lexeme.clone(), value);
+                            .define(name.lexeme.to_string(), value);
                     } else {
                         self.environment
                             .borrow_mut()
-                            .define_uninitialized(name.lexeme.clone());
+                            .define_uninitialized(name.lexeme.to_string());
                     }
                 } else if let Some(expr) = initializer {
                     let value = self.interpret(expr, output)?;
                     self.environment
                         .borrow_mut()
-                        .define_at_slot(name.lexeme.clone(), value);
+                        .define_at_slot(name.lexeme.to_string(), value);
                 } else {
                     self.environment
                         .borrow_mut()
-                        .define_uninitialized_at_slot(name.lexeme.clone());
+                        .define_uninitialized_at_slot(name.lexeme.to_string());
                 }
                 Ok(())
             }
@@ -123,7 +1074,10 @@ impl Interpreter {
                 }
             }
             Stmt::While {
-                condition, body, ..
+                condition,
+                body,
+                increment,
+                ..
             } => {
                 loop {
                     let condition_value = self.interpret(condition, output)?;
@@ -131,20 +1085,48 @@ impl Interpreter {
                         break;
                     }
                     match self.execute(body, output) {
-                        Ok(()) => {}
+                        Ok(()) | Err(ManoError::Continue) => {}
                         Err(ManoError::Break) => break,
                         Err(e) => return Err(e),
                     }
+                    if let Some(increment) = increment {
+                        self.execute(increment, output)?;
+                    }
                 }
                 Ok(())
             }
             Stmt::Break { .. } => Err(ManoError::Break),
+            Stmt::Continue { .. } => Err(ManoError::Continue),
             Stmt::Else { body, .. } => self.execute(body, output),
+            // Parse errors stop a run before execution, so this only happens
+            // when someone hands us a broken tree directly
+            Stmt::Error { span } => Err(broken_syntax(span)),
+            Stmt::Import { path, .. } => self.import(path, output),
+            Stmt::Try {
+                body,
+                name,
+                handler,
+                ..
+            } => match self.execute(body, output) {
+                Err(error) => {
+                    let value = caught(error)?;
+                    self.handle(name, value, handler, output)
+                }
+                done => done,
+            },
+            Stmt::Throw { value, span, .. } => {
+                let value = self.interpret(value, output)?;
+                Err(ManoError::Throw {
+                    value,
+                    span: span.clone(),
+                })
+            }
             Stmt::Function {
                 name,
                 params,
                 body,
                 is_getter,
+                is_variadic,
                 ..
             } => {
                 let function = ManoFunction {
@@ -153,6 +1135,11 @@ impl Interpreter {
                     body: body.clone(),
                     closure: Rc::clone(&self.environment),
                     is_getter: *is_getter,
+                    is_memoized: false,
+                    is_variadic: *is_variadic,
+                    is_initializer: false,
+                    resolutions: Rc::clone(&self.resolutions),
+                    receiver: None,
                 };
                 let value = Value::Function(Rc::new(Function::Mano(function)));
 
@@ -160,11 +1147,11 @@ impl Interpreter {
                 if Rc::ptr_eq(&self.environment, &self.globals) {
                     self.environment
                         .borrow_mut()
-                        .define(name.lexeme.clone(), value);
+                        .define(name.lexeme.to_string(), value);
                 } else {
                     self.environment
                         .borrow_mut()
-                        .define_at_slot(name.lexeme.clone(), value);
+                        .define_at_slot(name.lexeme.to_string(), value);
                 }
                 Ok(())
             }
@@ -175,6 +1162,37 @@ impl Interpreter {
                 };
                 Err(ManoError::Return(return_value))
             }
+            Stmt::Enum { name, variants, .. } => {
+                let class = Rc::new(Class {
+                    name: name.lexeme.to_string(),
+                    superclass: None,
+                    methods: HashMap::new(),
+                    static_methods: HashMap::new(),
+                    variants: Variants::default(),
+                });
+                for (ordem, variant) in variants.iter().enumerate() {
+                    let fields = HashMap::from([
+                        (
+                            "nome".to_string(),
+                            Value::Literal(Literal::String(variant.lexeme.to_string())),
+                        ),
+                        (
+                            "ordem".to_string(),
+                            Value::Literal(Literal::Number(ordem as f64)),
+                        ),
+                    ]);
+                    class.variants.push(Rc::new(Instance {
+                        class: Rc::clone(&class),
+                        fields: RefCell::new(fields),
+                        memos: RefCell::default(),
+                        frozen: Cell::new(true),
+                    }));
+                }
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), Value::Class(class));
+                Ok(())
+            }
             Stmt::Class {
                 name,
                 superclass,
@@ -191,6 +1209,12 @@ impl Interpreter {
 
                     let value = self.interpret(superclass_expr, output)?;
                     match value {
+                        Value::Class(class) if !class.variants.is_empty() => {
+                            return Err(ManoError::Runtime {
+                                message: "Opções não podem ser coroa, mano!".to_string(),
+                                span: superclass_span,
+                            });
+                        }
                         Value::Class(class) => Some(class),
                         _ => {
                             return Err(ManoError::Runtime {
@@ -206,7 +1230,7 @@ impl Interpreter {
                 // Define class name (with nil initially)
                 self.environment
                     .borrow_mut()
-                    .define(name.lexeme.clone(), Value::Literal(Literal::Nil));
+                    .define(name.lexeme.to_string(), Value::Literal(Literal::Nil));
 
                 // If there's a superclass, create an environment with "mestre"
                 // that will be the closure for all methods
@@ -230,6 +1254,8 @@ impl Interpreter {
                         body,
                         is_static,
                         is_getter,
+                        is_memoized,
+                        is_variadic,
                         ..
                     } = method
                     {
@@ -239,25 +1265,31 @@ impl Interpreter {
                             body: body.clone(),
                             closure: Rc::clone(&method_closure),
                             is_getter: *is_getter,
+                            is_memoized: *is_memoized,
+                            is_variadic: *is_variadic,
+                            is_initializer: !*is_static && &*method_name.lexeme == INITIALIZER_NAME,
+                            resolutions: Rc::clone(&self.resolutions),
+                            receiver: None,
                         };
                         if *is_static {
                             static_method_map.insert(
-                                method_name.lexeme.clone(),
+                                method_name.lexeme.to_string(),
                                 Rc::new(Function::Mano(function)),
                             );
                         } else {
                             method_map.insert(
-                                method_name.lexeme.clone(),
+                                method_name.lexeme.to_string(),
                                 Rc::new(Function::Mano(function)),
                             );
                         }
                     }
                 }
                 let class = Class {
-                    name: name.lexeme.clone(),
+                    name: name.lexeme.to_string(),
                     superclass: superclass_value,
                     methods: method_map,
                     static_methods: static_method_map,
+                    variants: Variants::default(),
                 };
 
                 // Assign the class value
@@ -289,19 +1321,65 @@ impl Interpreter {
         result
     }
 
+    /// Runs a `seDeuRuim` block with what went wrong in `name`
+    fn handle(
+        &mut self,
+        name: &Token,
+        value: Value,
+        handler: &Stmt,
+        output: &mut dyn Write,
+    ) -> Result<(), ManoError> {
+        let previous = Rc::clone(&self.environment);
+        self.environment = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(
+            &previous,
+        ))));
+        self.environment
+            .borrow_mut()
+            .define_at_slot(name.lexeme.to_string(), value);
+
+        let result = self.execute(handler, output);
+
+        self.environment = previous;
+        result
+    }
+
     pub fn interpret(&mut self, expr: &Expr, output: &mut dyn Write) -> Result<Value, ManoError> {
+        // Stacks grow down, so what's in use is how far below the base this is.
+        // Checked where there's a token to point at; the rest nest at most as
+        // deep as the parser lets them.
+        if self.depth == 0 {
+            self.stack_base = stack_position();
+        } else if let Some(budget) = self.stack_budget
+            && self.stack_base.saturating_sub(stack_position()) > budget
+            && let Some(token) = located_by(expr)
+        {
+            return Err(ManoError::Runtime {
+                message: "Afundou demais, mano! Tanta chamada e conta uma dentro da outra encheu a pilha.".to_string(),
+                span: token.span.clone(),
+            });
+        }
+        self.depth += 1;
+        let result = self.evaluate(expr, output);
+        self.depth -= 1;
+        result
+    }
+
+    fn evaluate(&mut self, expr: &Expr, output: &mut dyn Write) -> Result<Value, ManoError> {
         match expr {
             Expr::Literal { value } => Ok(Value::Literal(value.clone())),
             Expr::Grouping { expression } => self.interpret(expression, output),
             Expr::Unary { operator, right } => {
                 let right_val = self.interpret(right, output)?;
-                match operator.token_type {
+                let result = match operator.token_type {
                     TokenType::Minus => match right_val {
                         Value::Literal(Literal::Number(n)) => {
                             Ok(Value::Literal(Literal::Number(-n)))
                         }
-                        _ => Err(ManoError::Runtime {
-                            message: "Só dá pra negar número, tio!".to_string(),
+                        ref other => Err(ManoError::Runtime {
+                            message: format!(
+                                "Só dá pra negar número, tio! Veio {}.",
+                                other.kind_name()
+                            ),
                             span: operator.span.clone(),
                         }),
                     },
@@ -309,7 +1387,11 @@ impl Interpreter {
                         Ok(Value::Literal(Literal::Bool(!self.is_truthy(&right_val))))
                     }
                     _ => unreachable!(),
+                };
+                if self.explain {
+                    return result.map_err(|error| explained(error, &[("valor", &right_val)]));
                 }
+                result
             }
             Expr::Binary {
                 left,
@@ -318,58 +1400,7 @@ impl Interpreter {
             } => {
                 let left_val = self.interpret(left, output)?;
                 let right_val = self.interpret(right, output)?;
-
-                match operator.token_type {
-                    TokenType::Minus | TokenType::Slash | TokenType::Star | TokenType::Percent => {
-                        let (a, b) =
-                            self.require_numbers(&left_val, &right_val, operator.span.clone())?;
-                        match operator.token_type {
-                            TokenType::Minus => Ok(Value::Literal(Literal::Number(a - b))),
-                            TokenType::Slash => Ok(Value::Literal(Literal::Number(a / b))),
-                            TokenType::Star => Ok(Value::Literal(Literal::Number(a * b))),
-                            TokenType::Percent => Ok(Value::Literal(Literal::Number(a % b))),
-                            _ => unreachable!(),
-                        }
-                    }
-                    TokenType::Plus => match (&left_val, &right_val) {
-                        (
-                            Value::Literal(Literal::Number(a)),
-                            Value::Literal(Literal::Number(b)),
-                        ) => Ok(Value::Literal(Literal::Number(a + b))),
-                        (
-                            Value::Literal(Literal::String(a)),
-                            Value::Literal(Literal::String(b)),
-                        ) => Ok(Value::Literal(Literal::String(format!("{}{}", a, b)))),
-                        _ => Err(ManoError::Runtime {
-                            message: "Só dá pra somar número com número ou texto com texto, chapa!"
-                                .to_string(),
-                            span: operator.span.clone(),
-                        }),
-                    },
-                    TokenType::Greater
-                    | TokenType::GreaterEqual
-                    | TokenType::Less
-                    | TokenType::LessEqual => {
-                        let (a, b) =
-                            self.require_numbers(&left_val, &right_val, operator.span.clone())?;
-                        let result = match operator.token_type {
-                            TokenType::Greater => a > b,
-                            TokenType::GreaterEqual => a >= b,
-                            TokenType::Less => a < b,
-                            TokenType::LessEqual => a <= b,
-                            _ => unreachable!(),
-                        };
-                        Ok(Value::Literal(Literal::Bool(result)))
-                    }
-                    TokenType::EqualEqual => Ok(Value::Literal(Literal::Bool(
-                        self.is_equal(&left_val, &right_val),
-                    ))),
-                    TokenType::BangEqual => Ok(Value::Literal(Literal::Bool(
-                        !self.is_equal(&left_val, &right_val),
-                    ))),
-                    TokenType::Comma => Ok(right_val),
-                    _ => unreachable!(),
-                }
+                self.binary(&left_val, operator, &right_val)
             }
             Expr::Ternary {
                 condition,
@@ -397,8 +1428,22 @@ impl Interpreter {
                     self.globals.borrow().get(&name.lexeme, name.span.clone())
                 }
             }
-            Expr::Assign { name, value } => {
-                let val = self.interpret(value, output)?;
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => {
+                // `x += v` reads x before evaluating v, like `obj.campo += v`
+                let current = match operator {
+                    Some(_) => {
+                        Some(self.interpret(&Expr::Variable { name: name.clone() }, output)?)
+                    }
+                    None => None,
+                };
+                let mut val = self.interpret(value, output)?;
+                if let (Some(operator), Some(current)) = (operator, current) {
+                    val = self.binary(&current, operator, &val)?;
+                }
                 if let Some(&(distance, slot)) = self.resolutions.get(&name.span) {
                     self.environment
                         .borrow_mut()
@@ -438,35 +1483,31 @@ impl Interpreter {
                 paren,
                 arguments,
             } => {
-                let callee_val = self.interpret(callee, output)?;
+                let callee_val = match callee.as_ref() {
+                    Expr::Get { object, name } => {
+                        let object_value = self.interpret(object, output)?;
+                        // `p.falar()`: call the method with oCara directly
+                        // instead of binding a new fita first
+                        if let Some((instance, method)) = invoked_method(&object_value, name) {
+                            let Function::Mano(func) = method.as_ref() else {
+                                unreachable!("Native functions are never class methods")
+                            };
+                            let args = self.arguments(arguments, output)?;
+                            self.check_arity(func, args.len(), paren)?;
+                            return self.call_method(func, instance, args, &paren.span, output);
+                        }
+                        self.get_property(object_value, name, output)?
+                    }
+                    _ => self.interpret(callee, output)?,
+                };
 
-                let mut args = Vec::new();
-                for arg in arguments {
-                    args.push(self.interpret(arg, output)?);
-                }
+                let args = self.arguments(arguments, output)?;
 
                 match callee_val {
                     Value::Function(func) => match func.as_ref() {
                         Function::Mano(mano_func) => {
-                            if args.len() != mano_func.params.len() {
-                                return Err(ManoError::Runtime {
-                                    message: format!(
-                                        "Essa fita espera {} argumentos, mas tu passou {}, maluco!",
-                                        mano_func.params.len(),
-                                        args.len()
-                                    ),
-                                    span: paren.span.clone(),
-                                });
-                            }
-                            // Clone to avoid borrowing issues
-                            let func_clone = ManoFunction {
-                                name: mano_func.name.clone(),
-                                params: mano_func.params.clone(),
-                                body: mano_func.body.clone(),
-                                closure: Rc::clone(&mano_func.closure),
-                                is_getter: mano_func.is_getter,
-                            };
-                            self.call_mano_function(&func_clone, args, output)
+                            self.check_arity(mano_func, args.len(), paren)?;
+                            self.call_mano_function(mano_func, args, &paren.span, output)
                         }
                         Function::Native(native_func) => {
                             if args.len() != native_func.arity {
@@ -479,14 +1520,39 @@ impl Interpreter {
                                     span: paren.span.clone(),
                                 });
                             }
-                            (native_func.func)(&args)
+                            // Natives don't know where they were called from
+                            (native_func.func)(&args).map_err(|error| match error {
+                                ManoError::Runtime { message, .. } => ManoError::Runtime {
+                                    message,
+                                    span: paren.span.clone(),
+                                },
+                                error => error,
+                            })
                         }
                     },
                     Value::Class(class) => {
+                        if !class.variants.is_empty() {
+                            return Err(ManoError::Runtime {
+                                message: format!(
+                                    "Não dá pra criar opção nova de '{}', mano! Tem só {}.",
+                                    class.name,
+                                    class
+                                        .variants
+                                        .names()
+                                        .iter()
+                                        .map(|variant| format!("{}.{}", class.name, variant))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ),
+                                span: paren.span.clone(),
+                            });
+                        }
                         // Create the instance first
                         let instance = Rc::new(Instance {
                             class: Rc::clone(&class),
                             fields: RefCell::new(HashMap::new()),
+                            memos: RefCell::default(),
+                            frozen: Cell::default(),
                         });
 
                         // Look for initializer (bora)
@@ -494,19 +1560,20 @@ impl Interpreter {
                             match initializer.as_ref() {
                                 Function::Mano(func) => {
                                     // Check arity
-                                    if args.len() != func.params.len() {
-                                        return Err(ManoError::Runtime {
-                                            message: format!(
+                                    if !func.accepts(args.len()) {
+                                        return Err(self.arity_error(
+                                            format!(
                                                 "Esse bagulho espera {} lances, mas tu passou {}, mano!",
-                                                func.params.len(),
+                                                func.expected_args(),
                                                 args.len()
                                             ),
-                                            span: paren.span.clone(),
-                                        });
+                                            paren,
+                                            func,
+                                        ));
                                     }
                                     // Bind and call bora
                                     let bound = func.bind(Rc::clone(&instance));
-                                    self.call_mano_function(&bound, args, output)?;
+                                    self.call_mano_function(&bound, args, &paren.span, output)?;
                                 }
                                 Function::Native(_) => {
                                     // Native initializers shouldn't happen
@@ -524,87 +1591,75 @@ impl Interpreter {
                                 });
                             }
                         }
-
-                        Ok(Value::Instance(instance))
-                    }
-                    _ => Err(ManoError::Runtime {
-                        message: "Só dá pra chamar fita, chapa!".to_string(),
-                        span: paren.span.clone(),
-                    }),
-                }
-            }
-            Expr::Lambda { params, body } => {
-                let func = ManoFunction {
-                    name: None,
-                    params: params.clone(),
-                    body: body.clone(),
-                    closure: Rc::clone(&self.environment),
-                    is_getter: false,
-                };
-                Ok(Value::Function(Rc::new(Function::Mano(func))))
-            }
-            Expr::Get { object, name } => {
-                let object_value = self.interpret(object, output)?;
-                match object_value {
-                    Value::Instance(instance) => {
-                        // First check fields
-                        if let Some(value) = instance.fields.borrow().get(&name.lexeme) {
-                            return Ok(value.clone());
-                        }
-
-                        // Then check methods on the class (and superclass chain)
-                        // Note: static methods are NOT accessible on instances
-                        let method = instance.class.find_method(&name.lexeme);
-                        if let Some(method) = method {
-                            if let Function::Mano(func) = method.as_ref() {
-                                let bound = func.bind(Rc::clone(&instance));
-                                // If it's a getter, auto-invoke it
-                                if func.is_getter {
-                                    return self.call_mano_function(&bound, vec![], output);
-                                }
-                                return Ok(Value::Function(Rc::new(Function::Mano(bound))));
-                            }
-                            return Ok(Value::Function(method));
-                        }
-
-                        Err(ManoError::Runtime {
-                            message: format!("Eita, '{}' não existe nessa parada!", name.lexeme),
-                            span: name.span.clone(),
-                        })
-                    }
-                    Value::Class(class) => {
-                        // Static methods are accessible on class itself (and superclass chain)
-                        if let Some(method) = class.find_static_method(&name.lexeme) {
-                            return Ok(Value::Function(method));
-                        }
-
-                        Err(ManoError::Runtime {
-                            message: format!(
-                                "Eita, '{}' não é fita estática do bagulho {}!",
-                                name.lexeme, class.name
-                            ),
-                            span: name.span.clone(),
-                        })
+
+                        Ok(Value::Instance(instance))
                     }
                     _ => Err(ManoError::Runtime {
-                        message: "Só parada tem esquema, chapa!".to_string(),
-                        span: name.span.clone(),
+                        message: "Só dá pra chamar fita, chapa!".to_string(),
+                        span: paren.span.clone(),
                     }),
                 }
             }
+            Expr::Lambda { params, body } => {
+                let func = ManoFunction {
+                    name: None,
+                    params: params.clone(),
+                    body: body.clone(),
+                    closure: Rc::clone(&self.environment),
+                    is_getter: false,
+                    is_memoized: false,
+                    is_variadic: false,
+                    is_initializer: false,
+                    resolutions: Rc::clone(&self.resolutions),
+                    receiver: None,
+                };
+                Ok(Value::Function(Rc::new(Function::Mano(func))))
+            }
+            Expr::Get { object, name } => {
+                let object_value = self.interpret(object, output)?;
+                self.get_property(object_value, name, output)
+            }
             Expr::Set {
                 object,
                 name,
+                operator,
                 value,
             } => {
                 let object_value = self.interpret(object, output)?;
                 match object_value {
                     Value::Instance(instance) => {
-                        let val = self.interpret(value, output)?;
+                        if let Some(error) = self.getter_assignment_error(&instance, name) {
+                            return Err(error);
+                        }
+                        if instance.frozen.get() {
+                            return Err(ManoError::Runtime {
+                                message: format!(
+                                    "Essa parada de '{}' tá congelada, mano! Não dá pra mudar o '{}'.",
+                                    instance.class.name, name.lexeme
+                                ),
+                                span: name.span.clone(),
+                            });
+                        }
+                        // The receiver is evaluated once, before the current
+                        // value is read and before the right side runs
+                        let current = match operator {
+                            Some(_) => Some(self.get_property(
+                                Value::Instance(Rc::clone(&instance)),
+                                name,
+                                output,
+                            )?),
+                            None => None,
+                        };
+                        let mut val = self.interpret(value, output)?;
+                        if let (Some(operator), Some(current)) = (operator, current) {
+                            val = self.binary(&current, operator, &val)?;
+                        }
                         instance
                             .fields
                             .borrow_mut()
-                            .insert(name.lexeme.clone(), val.clone());
+                            .insert(name.lexeme.to_string(), val.clone());
+                        // Memoized getters may depend on the field
+                        instance.memos.borrow_mut().clear();
                         Ok(val)
                     }
                     _ => Err(ManoError::Runtime {
@@ -613,6 +1668,58 @@ impl Interpreter {
                     }),
                 }
             }
+            Expr::ListLiteral { elements, .. } => {
+                let mut items = Vec::with_capacity(elements.len());
+                for element in elements {
+                    items.push(self.interpret(element, output)?);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(items))))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object_value = self.interpret(object, output)?;
+                let index_value = self.interpret(index, output)?;
+                let Value::List(list) = object_value else {
+                    return Err(not_a_list(&object_value, bracket));
+                };
+                let items = list.borrow();
+                let slot = list_slot(items.len(), &index_value, bracket)?;
+                Ok(items[slot].clone())
+            }
+            Expr::IndexSet {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            } => {
+                let object_value = self.interpret(object, output)?;
+                let index_value = self.interpret(index, output)?;
+                let Value::List(list) = object_value else {
+                    return Err(not_a_list(&object_value, bracket));
+                };
+                // Like `obj.campo += 1`: the current item is read before the
+                // right side runs
+                let current = match operator {
+                    Some(_) => {
+                        let items = list.borrow();
+                        Some(items[list_slot(items.len(), &index_value, bracket)?].clone())
+                    }
+                    None => None,
+                };
+                let mut val = self.interpret(value, output)?;
+                if let (Some(operator), Some(current)) = (operator, current) {
+                    val = self.binary(&current, operator, &val)?;
+                }
+                // Checked again, the right side may have changed the list
+                let mut items = list.borrow_mut();
+                let slot = list_slot(items.len(), &index_value, bracket)?;
+                items[slot] = val.clone();
+                Ok(val)
+            }
             Expr::This { keyword } => {
                 // Look up "oCara" using resolution - same as Variable
                 if let Some(&(distance, slot)) = self.resolutions.get(&keyword.span) {
@@ -686,27 +1793,340 @@ impl Interpreter {
                 }
                 Ok(Value::Literal(Literal::String(result)))
             }
+            Expr::Error { span } => Err(broken_syntax(span)),
+        }
+    }
+
+    /// Reads a field or method off an instance, or a static method off a class
+    ///
+    /// On an instance a field wins over a method of the same name, and a
+    /// method of the class over one it inherits. Both backends look things up
+    /// in this order; the programs in `mano-cli/tests/lox/field` check it.
+    fn get_property(
+        &mut self,
+        object_value: Value,
+        name: &Token,
+        output: &mut dyn Write,
+    ) -> Result<Value, ManoError> {
+        match object_value {
+            Value::Instance(instance) => {
+                // First check fields
+                if let Some(value) = instance.fields.borrow().get(&*name.lexeme) {
+                    return Ok(value.clone());
+                }
+
+                // Then check methods on the class (and superclass chain)
+                // Note: static methods are NOT accessible on instances
+                let method = instance.class.find_method(&name.lexeme);
+                if let Some(method) = method {
+                    if let Function::Mano(func) = method.as_ref() {
+                        let bound = func.bind(Rc::clone(&instance));
+                        // If it's a getter, auto-invoke it
+                        if func.is_getter {
+                            if func.is_memoized
+                                && let Some(value) = instance.memos.borrow().get(&*name.lexeme)
+                            {
+                                return Ok(value.clone());
+                            }
+                            let value =
+                                self.call_mano_function(&bound, vec![], &name.span, output)?;
+                            if func.is_memoized {
+                                instance
+                                    .memos
+                                    .borrow_mut()
+                                    .insert(name.lexeme.to_string(), value.clone());
+                            }
+                            return Ok(value);
+                        }
+                        return Ok(Value::Function(Rc::new(Function::Mano(bound))));
+                    }
+                    return Ok(Value::Function(method));
+                }
+
+                Err(ManoError::Runtime {
+                    message: format!("Eita, '{}' não existe nessa parada!", name.lexeme),
+                    span: name.span.clone(),
+                })
+            }
+            Value::Class(class) => {
+                if let Some(variant) = class.variants.get(&name.lexeme) {
+                    return Ok(Value::Instance(variant));
+                }
+                // Static methods are accessible on class itself (and superclass chain)
+                if let Some(method) = class.find_static_method(&name.lexeme) {
+                    return Ok(Value::Function(method));
+                }
+
+                let message = if class.variants.is_empty() {
+                    format!(
+                        "Eita, '{}' não é fita estática do bagulho {}!",
+                        name.lexeme, class.name
+                    )
+                } else {
+                    format!(
+                        "Eita, '{}' não tá nas opções de {}! Tem {}.",
+                        name.lexeme,
+                        class.name,
+                        class.variants.names().join(", ")
+                    )
+                };
+                Err(ManoError::Runtime {
+                    message,
+                    span: name.span.clone(),
+                })
+            }
+            Value::List(list) => list_property(list, name),
+            _ => Err(ManoError::Runtime {
+                message: "Só parada tem esquema, chapa!".to_string(),
+                span: name.span.clone(),
+            }),
+        }
+    }
+
+    /// Fails when `func` can't take `count` arguments
+    fn check_arity(
+        &self,
+        func: &ManoFunction,
+        count: usize,
+        paren: &Token,
+    ) -> Result<(), ManoError> {
+        if func.accepts(count) {
+            return Ok(());
+        }
+        Err(self.arity_error(
+            format!(
+                "Essa fita espera {} argumentos, mas tu passou {}, maluco!",
+                func.expected_args(),
+                count
+            ),
+            paren,
+            func,
+        ))
+    }
+
+    /// The error for a call with the wrong number of lances, also pointing at
+    /// the fita's declaration when it's in the same code as the call
+    fn arity_error(&self, message: String, paren: &Token, func: &ManoFunction) -> ManoError {
+        let error = ManoError::Runtime {
+            message,
+            span: paren.span.clone(),
+        };
+        self.with_declaration(error, func, "declarada aqui")
+    }
+
+    /// The error, also pointing at where the fita was declared when that's in
+    /// the code that's running
+    fn with_declaration(&self, error: ManoError, func: &ManoFunction, label: &str) -> ManoError {
+        match &func.name {
+            Some(name) if Rc::ptr_eq(&func.resolutions, &self.resolutions) => {
+                error.with_related(name.span.clone(), label)
+            }
+            _ => error,
+        }
+    }
+
+    /// The error for `parada.nome = valor` when `nome` is a getter, which
+    /// would otherwise be hidden by the new field for good
+    fn getter_assignment_error(&self, instance: &Instance, name: &Token) -> Option<ManoError> {
+        let method = instance.class.find_method(&name.lexeme)?;
+        let Function::Mano(func) = method.as_ref() else {
+            return None;
+        };
+        if !func.is_getter {
+            return None;
+        }
+        let error = ManoError::Runtime {
+            message: format!(
+                "'{}' é getter do bagulho '{}', mano! Dá pra ler, mas não dá pra mudar.",
+                name.lexeme, instance.class.name
+            ),
+            span: name.span.clone(),
+        };
+        Some(
+            self.with_declaration(error, func, "o getter tá aqui")
+                .with_help(format!(
+                    "Guarda o valor num campo com outro nome, tipo oCara._{}, e faz o getter tomar ele.",
+                    name.lexeme
+                )),
+        )
+    }
+
+    /// Calls a method with `instance` as oCara, without binding it first
+    fn call_method(
+        &mut self,
+        func: &ManoFunction,
+        instance: Rc<Instance>,
+        args: Vec<Value>,
+        call_site: &Range<usize>,
+        output: &mut dyn Write,
+    ) -> Result<Value, ManoError> {
+        // Same scope bind() would create, so resolved distances still line up
+        let mut this_env = Environment::with_enclosing(Rc::clone(&func.closure));
+        this_env.define_at_slot("oCara".to_string(), Value::Instance(instance));
+        self.call_in(
+            func,
+            Rc::new(RefCell::new(this_env)),
+            args,
+            call_site,
+            output,
+        )
+    }
+
+    /// Runs the module a `chama` names, unless it already ran
+    fn import(&mut self, path: &Token, output: &mut dyn Write) -> Result<(), ManoError> {
+        let at_path = |message: String| ManoError::Runtime {
+            message,
+            span: path.span.clone(),
+        };
+        if !self.policy.fs {
+            return Err(match denied("chama") {
+                ManoError::Runtime { message, .. } => at_path(message),
+                error => error,
+            });
+        }
+        let Some(Literal::String(name)) = &path.literal else {
+            return Err(broken_syntax(&path.span));
+        };
+        let file = self
+            .modules
+            .locate(name)
+            .map_err(|e| at_path(format!("Não achei o módulo '{}', mano: {}", name, e)))?;
+        match self.modules.enter(file).map_err(at_path)? {
+            Load::Run(file) => {
+                let result = self.run_module(&file, path, output);
+                self.modules.leave(result.is_err());
+                result
+            }
+            Load::Cached => Ok(()),
         }
     }
 
+    /// Runs a module's code in the globals, like the main script's, with
+    /// its errors moved to the `chama`
+    fn run_module(
+        &mut self,
+        file: &Path,
+        path: &Token,
+        output: &mut dyn Write,
+    ) -> Result<(), ManoError> {
+        let name = module::file_name(file);
+        let source = std::fs::read_to_string(file).map_err(|e| ManoError::Runtime {
+            message: format!("Não rolou ler o módulo '{}': {}", name, e),
+            span: path.span.clone(),
+        })?;
+        let inside = |error| module::in_module(error, &name, &source, path.span.clone());
+        let statements = crate::parse(&source).map_err(|mut errors| inside(errors.remove(0)))?;
+        let resolutions = crate::resolver::Resolver::new()
+            .with_text_coercion(self.text_coercion)
+            .resolve(&statements)
+            .map_err(|mut errors| inside(errors.remove(0)))?;
+
+        let resolutions = std::mem::replace(&mut self.resolutions, Rc::new(resolutions));
+        let environment = std::mem::replace(&mut self.environment, Rc::clone(&self.globals));
+        let result = statements
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt, output));
+        self.resolutions = resolutions;
+        self.environment = environment;
+        result.map_err(inside)
+    }
+
     fn call_mano_function(
         &mut self,
         func: &ManoFunction,
         args: Vec<Value>,
+        call_site: &Range<usize>,
+        output: &mut dyn Write,
+    ) -> Result<Value, ManoError> {
+        self.call_in(func, Rc::clone(&func.closure), args, call_site, output)
+    }
+
+    /// Runs `func` called at `call_site`, keeping it on the call stack while
+    /// it runs so a runtime error from inside it knows the calls it's in
+    fn call_in(
+        &mut self,
+        func: &ManoFunction,
+        closure: Rc<RefCell<Environment>>,
+        args: Vec<Value>,
+        call_site: &Range<usize>,
+        output: &mut dyn Write,
+    ) -> Result<Value, ManoError> {
+        // The calls around this one add themselves to the error's stack
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(ManoError::Runtime {
+                message: format!(
+                    "Afundou demais, mano! Passou de {} chamadas uma dentro da outra.",
+                    MAX_CALL_DEPTH
+                ),
+                span: call_site.clone(),
+            });
+        }
+        self.call_stack.push((
+            func.name.as_ref().map(|name| Rc::clone(&name.lexeme)),
+            call_site.clone(),
+        ));
+        let result = self.run_body(func, closure, args, output);
+        let result = match result {
+            // The innermost call sees the error first, with every call still on the stack
+            Err(error)
+                if matches!(
+                    error.primary(),
+                    ManoError::Runtime { .. } | ManoError::Throw { .. }
+                ) && error.stack().is_empty() =>
+            {
+                let stack = self
+                    .call_stack
+                    .iter()
+                    .rev()
+                    .map(|(name, span)| Frame {
+                        name: name.as_deref().unwrap_or("fita sem nome").to_string(),
+                        span: span.clone(),
+                    })
+                    .collect();
+                Err(error.with_stack(stack))
+            }
+            result => result,
+        };
+        self.call_stack.pop();
+        result
+    }
+
+    /// Runs the body of `func` in a new scope inside `closure`
+    fn run_body(
+        &mut self,
+        func: &ManoFunction,
+        closure: Rc<RefCell<Environment>>,
+        mut args: Vec<Value>,
         output: &mut dyn Write,
     ) -> Result<Value, ManoError> {
+        // `bora` gives back oCara even when called again as `p.bora(...)`
+        let this = if func.is_initializer {
+            closure.borrow().get_at(0, 0)
+        } else {
+            None
+        };
         let previous = Rc::clone(&self.environment);
+        // The body may come from an earlier run, whose spans mean different things
+        let previous_resolutions =
+            std::mem::replace(&mut self.resolutions, Rc::clone(&func.resolutions));
 
         // Create new environment with closure as enclosing
-        self.environment = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(
-            &func.closure,
-        ))));
+        self.environment = Rc::new(RefCell::new(Environment::with_enclosing(closure)));
 
         // Bind parameters to arguments (function scope is always local, use slots)
-        for (param, arg) in func.params.iter().zip(args.into_iter()) {
+        let rest = func
+            .is_variadic
+            .then(|| args.split_off(func.params.len() - 1));
+        for (param, arg) in func.params.iter().zip(args) {
             self.environment
                 .borrow_mut()
-                .define_at_slot(param.lexeme.clone(), arg);
+                .define_at_slot(param.lexeme.to_string(), arg);
+        }
+        if let (Some(rest), Some(param)) = (rest, func.params.last()) {
+            self.environment.borrow_mut().define_at_slot(
+                param.lexeme.to_string(),
+                Value::List(Rc::new(RefCell::new(rest))),
+            );
         }
 
         // Execute body
@@ -720,6 +2140,7 @@ impl Interpreter {
                 }
                 Err(e) => {
                     self.environment = previous;
+                    self.resolutions = previous_resolutions;
                     return Err(e);
                 }
             }
@@ -727,8 +2148,21 @@ impl Interpreter {
 
         // Restore environment
         self.environment = previous;
+        self.resolutions = previous_resolutions;
+
+        Ok(this.unwrap_or(return_value))
+    }
 
-        Ok(return_value)
+    /// Evaluates call arguments, left to right
+    fn arguments(
+        &mut self,
+        arguments: &[Expr],
+        output: &mut dyn Write,
+    ) -> Result<Vec<Value>, ManoError> {
+        arguments
+            .iter()
+            .map(|arg| self.interpret(arg, output))
+            .collect()
     }
 
     fn is_truthy(&self, value: &Value) -> bool {
@@ -739,21 +2173,148 @@ impl Interpreter {
         }
     }
 
+    /// Applies a binary operator to values already evaluated
+    fn binary(
+        &self,
+        left_val: &Value,
+        operator: &Token,
+        right_val: &Value,
+    ) -> Result<Value, ManoError> {
+        let result = match operator.token_type {
+            // Errors fall through to the end, so --explica adds the values
+            TokenType::Minus | TokenType::Slash | TokenType::Star | TokenType::Percent => self
+                .require_numbers(left_val, right_val, operator)
+                .map(|(a, b)| {
+                    let n = match operator.token_type {
+                        TokenType::Minus => a - b,
+                        TokenType::Slash => a / b,
+                        TokenType::Star => a * b,
+                        TokenType::Percent => a % b,
+                        _ => unreachable!(),
+                    };
+                    Value::Literal(Literal::Number(n))
+                }),
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => self
+                .require_numbers(left_val, right_val, operator)
+                .and_then(|(a, b)| {
+                    crate::bitwise::apply(operator.token_type, a, b)
+                        .map(|n| Value::Literal(Literal::Number(n)))
+                        .map_err(|message| ManoError::Runtime {
+                            message,
+                            span: operator.span.clone(),
+                        })
+                }),
+            TokenType::Plus => match (left_val, right_val) {
+                (Value::Literal(Literal::Number(a)), Value::Literal(Literal::Number(b))) => {
+                    Ok(Value::Literal(Literal::Number(a + b)))
+                }
+                (Value::Literal(Literal::String(a)), Value::Literal(Literal::String(b))) => {
+                    Ok(Value::Literal(Literal::String(format!("{}{}", a, b))))
+                }
+                (Value::Literal(Literal::Bytes(a)), Value::Literal(Literal::Bytes(b))) => {
+                    Ok(Value::Literal(Literal::Bytes([a.as_slice(), b].concat())))
+                }
+                (Value::Literal(Literal::String(a)), Value::Literal(other))
+                    if self.text_coercion && other.joins_text() =>
+                {
+                    Ok(Value::Literal(Literal::String(format!("{}{}", a, other))))
+                }
+                (Value::Literal(other), Value::Literal(Literal::String(b)))
+                    if self.text_coercion && other.joins_text() =>
+                {
+                    Ok(Value::Literal(Literal::String(format!("{}{}", other, b))))
+                }
+                _ => {
+                    let error = ManoError::Runtime {
+                        message: format!(
+                            "Só dá pra somar número com número ou texto com texto, chapa! Veio {} com {}.",
+                            left_val.kind_name(),
+                            right_val.kind_name()
+                        ),
+                        span: operator.span.clone(),
+                    };
+                    let would_join = match (left_val, right_val) {
+                        (Value::Literal(Literal::String(_)), Value::Literal(other))
+                        | (Value::Literal(other), Value::Literal(Literal::String(_))) => {
+                            other.joins_text()
+                        }
+                        _ => false,
+                    };
+                    if would_join {
+                        Err(error.with_help(
+                            "Vira o outro lado com numeroPraTexto ou viraTexto, ou roda com --junta-texto.",
+                        ))
+                    } else {
+                        Err(error)
+                    }
+                }
+            },
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => {
+                self.require_numbers(left_val, right_val, operator)
+                    .map(|(a, b)| {
+                        let result = match operator.token_type {
+                            TokenType::Greater => a > b,
+                            TokenType::GreaterEqual => a >= b,
+                            TokenType::Less => a < b,
+                            TokenType::LessEqual => a <= b,
+                            _ => unreachable!(),
+                        };
+                        Value::Literal(Literal::Bool(result))
+                    })
+            }
+            TokenType::EqualEqual => Ok(Value::Literal(Literal::Bool(
+                self.is_equal(left_val, right_val),
+            ))),
+            TokenType::BangEqual => Ok(Value::Literal(Literal::Bool(
+                !self.is_equal(left_val, right_val),
+            ))),
+            TokenType::Comma => Ok(right_val.clone()),
+            _ => unreachable!(),
+        };
+        if self.explain {
+            let operands = [("esquerda", left_val), ("direita", right_val)];
+            return result.map_err(|error| explained(error, &operands));
+        }
+        result
+    }
+
+    /// Both sides of an operator that only works on numbers, or an error
+    /// naming what each side was
     fn require_numbers(
         &self,
         left: &Value,
         right: &Value,
-        span: std::ops::Range<usize>,
+        operator: &Token,
     ) -> Result<(f64, f64), ManoError> {
-        match (left, right) {
-            (Value::Literal(Literal::Number(a)), Value::Literal(Literal::Number(b))) => {
-                Ok((*a, *b))
-            }
-            _ => Err(ManoError::Runtime {
-                message: "Os dois lados precisam ser número, irmão!".to_string(),
-                span,
-            }),
+        if let (Value::Literal(Literal::Number(a)), Value::Literal(Literal::Number(b))) =
+            (left, right)
+        {
+            return Ok((*a, *b));
         }
+        let (left, right) = (left.kind_name(), right.kind_name());
+        let message = match operator.token_type {
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => {
+                format!("Não dá pra comparar {} com {}, mano!", left, right)
+            }
+            _ => format!(
+                "Os dois lados precisam ser número, irmão! Veio {} com {}.",
+                left, right
+            ),
+        };
+        Err(ManoError::Runtime {
+            message,
+            span: operator.span.clone(),
+        })
     }
 
     fn is_equal(&self, a: &Value, b: &Value) -> bool {
@@ -861,7 +2422,7 @@ mod tests {
     ) -> crate::token::Token {
         crate::token::Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             literal: None,
             span: start..start + lexeme.len(),
         }
@@ -893,6 +2454,58 @@ mod tests {
         assert!(matches!(result, Err(ManoError::Runtime { .. })));
     }
 
+    #[test]
+    fn explain_mode_names_the_operand() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_explain(true);
+        let expr = Expr::Unary {
+            operator: make_token(crate::token::TokenType::Minus, "-", 3),
+            right: Box::new(Expr::Literal {
+                value: Literal::Bool(true),
+            }),
+        };
+        match eval(&mut interpreter, &expr) {
+            Err(ManoError::Runtime { message, .. }) => {
+                assert!(
+                    message.ends_with("(valor era firmeza (lógico))"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn explain_mode_names_the_operands_of_number_operators() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_explain(true);
+        for (token_type, lexeme) in [
+            (TokenType::Minus, "-"),
+            (TokenType::Less, "<"),
+            (TokenType::Ampersand, "&"),
+        ] {
+            let expr = Expr::Binary {
+                left: Box::new(Expr::Literal {
+                    value: Literal::String("a".to_string()),
+                }),
+                operator: make_token(token_type, lexeme, 4),
+                right: Box::new(Expr::Literal {
+                    value: Literal::Number(1.0),
+                }),
+            };
+            match eval(&mut interpreter, &expr) {
+                Err(ManoError::Runtime { message, .. }) => assert!(
+                    message.ends_with("(esquerda era \"a\" (texto), direita era 1 (número))"),
+                    "{}: {}",
+                    lexeme,
+                    message
+                ),
+                other => panic!("expected runtime error for {}, got {:?}", lexeme, other),
+            }
+        }
+    }
+
     #[test]
     fn evaluates_unary_bang_on_false() {
         let mut interpreter = Interpreter::new();
@@ -1073,8 +2686,8 @@ mod tests {
                 value: Literal::Number(42.0),
             }),
         };
-        let result = eval(&mut interpreter, &expr);
-        assert!(matches!(result, Err(ManoError::Runtime { .. })));
+        let error = eval(&mut interpreter, &expr).unwrap_err();
+        assert!(matches!(error.primary(), ManoError::Runtime { .. }));
     }
 
     // === binary comparison ===
@@ -1404,8 +3017,8 @@ mod tests {
             }),
         });
         let mut output = Vec::new();
-        let result = interpreter.execute(&stmt, &mut output);
-        assert!(matches!(result, Err(ManoError::Runtime { .. })));
+        let error = interpreter.execute(&stmt, &mut output).unwrap_err();
+        assert!(matches!(error.primary(), ManoError::Runtime { .. }));
     }
 
     // === variable declaration ===
@@ -1450,6 +3063,7 @@ mod tests {
         // x = 42;
         let assign_stmt = Stmt::expression(Expr::Assign {
             name: make_token(crate::token::TokenType::Identifier, "x", 0),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(42.0),
             }),
@@ -1502,7 +3116,7 @@ mod tests {
                 Stmt::Var {
                     name: crate::token::Token {
                         token_type: crate::token::TokenType::Identifier,
-                        lexeme: "x".to_string(),
+                        lexeme: "x".into(),
                         literal: None,
                         span: 0..1,
                     },
@@ -1513,10 +3127,11 @@ mod tests {
                     expression: Expr::Assign {
                         name: crate::token::Token {
                             token_type: crate::token::TokenType::Identifier,
-                            lexeme: "x".to_string(),
+                            lexeme: "x".into(),
                             literal: None,
                             span: 10..11,
                         },
+                        operator: None,
                         value: Box::new(Expr::Literal {
                             value: crate::token::Literal::Number(42.0),
                         }),
@@ -1527,7 +3142,7 @@ mod tests {
                     expression: Expr::Variable {
                         name: crate::token::Token {
                             token_type: crate::token::TokenType::Identifier,
-                            lexeme: "x".to_string(),
+                            lexeme: "x".into(),
                             literal: None,
                             span: 20..21,
                         },
@@ -1557,6 +3172,7 @@ mod tests {
         // x = 42;
         let assign_stmt = Stmt::expression(Expr::Assign {
             name: make_token(crate::token::TokenType::Identifier, "x", 0),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(42.0),
             }),
@@ -1699,6 +3315,7 @@ mod tests {
         // { x = 99; }
         let block = Stmt::block(vec![Stmt::expression(Expr::Assign {
             name: make_token(crate::token::TokenType::Identifier, "x", 2),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(99.0),
             }),
@@ -1964,6 +3581,7 @@ mod tests {
                 }),
                 Stmt::expression(Expr::Assign {
                     name: make_token(crate::token::TokenType::Identifier, "x", 0),
+                    operator: None,
                     value: Box::new(Expr::Binary {
                         left: Box::new(Expr::Variable {
                             name: make_token(crate::token::TokenType::Identifier, "x", 0),
@@ -2033,11 +3651,12 @@ mod tests {
                             value: Literal::Number(2.0),
                         }),
                     },
-                    Stmt::break_stmt(),
+                    Stmt::break_stmt(make_token(crate::token::TokenType::Break, "saiFora", 0)),
                     None,
                 ),
                 Stmt::expression(Expr::Assign {
                     name: make_token(crate::token::TokenType::Identifier, "i", 0),
+                    operator: None,
                     value: Box::new(Expr::Binary {
                         left: Box::new(Expr::Variable {
                             name: make_token(crate::token::TokenType::Identifier, "i", 0),
@@ -2068,7 +3687,7 @@ mod tests {
                 Stmt::print(Expr::Literal {
                     value: Literal::Number(1.0),
                 }),
-                Stmt::break_stmt(),
+                Stmt::break_stmt(make_token(crate::token::TokenType::Break, "saiFora", 0)),
                 Stmt::print(Expr::Literal {
                     value: Literal::Number(2.0),
                 }),
@@ -2132,6 +3751,8 @@ mod tests {
             })],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2144,6 +3765,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn symbols_include_natives_unless_filtered() {
+        let interpreter = Interpreter::new();
+        let with_natives = interpreter.symbols(true);
+        assert!(with_natives.contains(&Symbol {
+            name: "fazTeuCorre".to_string(),
+            kind: SymbolKind::Native,
+        }));
+        assert!(interpreter.symbols(false).is_empty());
+    }
+
+    fn run_source(interpreter: &mut Interpreter, source: &str) {
+        let tokens: Vec<_> = crate::Scanner::new(source).filter_map(|r| r.ok()).collect();
+        let statements = crate::Parser::new(tokens).parse().unwrap();
+        let mut output = Vec::new();
+        for stmt in &statements {
+            interpreter.execute(stmt, &mut output).unwrap();
+        }
+    }
+
+    #[test]
+    fn members_of_instance_include_fields_and_inherited_methods() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "bagulho A { falar() {} bora() {} } \
+             bagulho B < A { correr() {} falar() {} } \
+             seLiga b = B(); b.nome = 1;",
+        );
+        let members = interpreter.members("b");
+        let names: Vec<_> = members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["nome", "correr", "falar"]);
+        assert_eq!(members[0].kind, SymbolKind::Field);
+        assert_eq!(members[1].kind, SymbolKind::Method);
+    }
+
+    #[test]
+    fn members_of_class_are_static_methods() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "bagulho Conta { bagulho soma(a, b) { toma a + b; } dobro() {} }",
+        );
+        let names: Vec<_> = interpreter
+            .members("Conta")
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, vec!["soma"]);
+    }
+
+    #[test]
+    fn members_of_plain_values_are_empty() {
+        let mut interpreter = Interpreter::new();
+        run_source(&mut interpreter, "seLiga x = 1;");
+        assert!(interpreter.members("x").is_empty());
+        assert!(interpreter.members("naoExiste").is_empty());
+    }
+
     #[test]
     fn function_call_executes_body() {
         let mut interpreter = Interpreter::new();
@@ -2158,6 +3838,8 @@ mod tests {
             })],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2190,6 +3872,8 @@ mod tests {
             })],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
 
@@ -2232,6 +3916,8 @@ mod tests {
             body: vec![],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2247,7 +3933,10 @@ mod tests {
             }],
         };
         let result = eval(&mut interpreter, &call_expr);
-        assert!(matches!(result, Err(ManoError::Runtime { .. })));
+        // It also points at where soma was declared
+        let error = result.unwrap_err();
+        assert!(matches!(error.primary(), ManoError::Runtime { .. }));
+        assert_eq!(error.related().len(), 1);
     }
 
     #[test]
@@ -2352,6 +4041,69 @@ mod tests {
         assert!(matches!(result, Err(ManoError::Runtime { .. })));
     }
 
+    // === pegaOpcao native function ===
+
+    #[test]
+    fn option_value_reads_values_flags_and_missing_options() {
+        let args: Vec<String> = ["--saida", "x.txt", "--seco", "--nivel=3", "--", "--depois"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let text = |s: &str| Value::Literal(Literal::String(s.to_string()));
+        assert_eq!(option_value(&args, "saida"), text("x.txt"));
+        assert_eq!(
+            option_value(&args, "seco"),
+            Value::Literal(Literal::Bool(true))
+        );
+        assert_eq!(option_value(&args, "nivel"), text("3"));
+        assert_eq!(option_value(&args, "depois"), Value::Literal(Literal::Nil));
+        assert_eq!(option_value(&args, "sai"), Value::Literal(Literal::Nil));
+    }
+
+    #[test]
+    fn pega_opcao_reads_the_script_arguments() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_args(vec!["--nome".to_string(), "Zé".to_string()]);
+        let tokens: Vec<_> =
+            crate::Scanner::new("salve pegaOpcao(\"nome\"); salve pegaOpcao(\"idade\");")
+                .filter_map(|r| r.ok())
+                .collect();
+        let mut output = Vec::new();
+        for stmt in &crate::Parser::new(tokens).parse().unwrap() {
+            interpreter.execute(stmt, &mut output).unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "Zé\nnadaNão\n");
+    }
+
+    #[test]
+    fn pega_opcao_errors_at_the_call_for_non_text_names() {
+        let mut interpreter = Interpreter::new();
+        let call_expr = Expr::Call {
+            callee: Box::new(Expr::Variable {
+                name: make_token(TokenType::Identifier, "pegaOpcao", 0),
+            }),
+            paren: make_token(TokenType::RightParen, ")", 12),
+            arguments: vec![Expr::Literal {
+                value: Literal::Number(1.0),
+            }],
+        };
+        let Err(ManoError::Runtime { span, .. }) = eval(&mut interpreter, &call_expr) else {
+            panic!("Expected a runtime error");
+        };
+        assert_eq!(span.start, 12);
+    }
+
+    // === leAi native function ===
+
+    #[test]
+    fn read_line_drops_the_line_break_and_gives_nada_nao_at_the_end() {
+        let mut input: &[u8] = b"oi\r\nmano";
+        let text = |s: &str| Value::Literal(Literal::String(s.to_string()));
+        assert_eq!(read_line(&mut input).unwrap(), text("oi"));
+        assert_eq!(read_line(&mut input).unwrap(), text("mano"));
+        assert_eq!(read_line(&mut input).unwrap(), Value::Literal(Literal::Nil));
+    }
+
     // === viraTexto native function ===
 
     #[test]
@@ -2434,6 +4186,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2466,6 +4220,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..20,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2505,6 +4261,8 @@ mod tests {
             ],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2621,6 +4379,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         };
         interpreter.execute(&func_stmt, &mut output).unwrap();
@@ -2707,6 +4467,7 @@ mod tests {
         // Assign expression - NO resolution (global variable)
         let assign_expr = Expr::Assign {
             name: make_token(TokenType::Identifier, "x", 0),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(99.0),
             }),
@@ -2802,6 +4563,7 @@ mod tests {
         // Assign expression at span 0..5 assigns to x
         let assign_expr = Expr::Assign {
             name: make_token(TokenType::Identifier, "x", 0),
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::Number(99.0),
             }),
@@ -2830,7 +4592,7 @@ mod tests {
         let stmt = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 8..14,
             },
@@ -2856,7 +4618,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Vazio".to_string(),
+                lexeme: "Vazio".into(),
                 literal: None,
                 span: 8..13,
             },
@@ -2871,7 +4633,7 @@ mod tests {
             expression: Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Vazio".to_string(),
+                    lexeme: "Vazio".into(),
                     literal: None,
                     span: 22..27,
                 },
@@ -2892,7 +4654,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 8..14,
             },
@@ -2900,7 +4662,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 17..22,
                 },
@@ -2908,6 +4670,8 @@ mod tests {
                 body: vec![],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 17..30,
             }],
             span: 0..32,
@@ -2931,7 +4695,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -2946,14 +4710,14 @@ mod tests {
             callee: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pessoa".to_string(),
+                    lexeme: "Pessoa".into(),
                     literal: None,
                     span: 0..6,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 7..8,
             },
@@ -2973,7 +4737,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -2988,14 +4752,14 @@ mod tests {
             callee: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pessoa".to_string(),
+                    lexeme: "Pessoa".into(),
                     literal: None,
                     span: 0..6,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 10..11,
             },
@@ -3029,7 +4793,7 @@ mod tests {
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nome".to_string(),
+                lexeme: "nome".into(),
                 literal: None,
                 span: 3..7,
             },
@@ -3047,7 +4811,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3061,7 +4825,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -3069,14 +4833,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -3091,17 +4855,18 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nome".to_string(),
+                lexeme: "nome".into(),
                 literal: None,
                 span: 2..6,
             },
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::String("João".to_string()),
             }),
@@ -3120,10 +4885,11 @@ mod tests {
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nome".to_string(),
+                lexeme: "nome".into(),
                 literal: None,
                 span: 3..7,
             },
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::String("João".to_string()),
             }),
@@ -3141,7 +4907,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3155,7 +4921,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -3163,14 +4929,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -3185,14 +4951,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "undefined".to_string(),
+                lexeme: "undefined".into(),
                 literal: None,
                 span: 2..11,
             },
@@ -3210,7 +4976,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3224,7 +4990,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -3232,14 +4998,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -3254,17 +5020,18 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nome".to_string(),
+                lexeme: "nome".into(),
                 literal: None,
                 span: 2..6,
             },
+            operator: None,
             value: Box::new(Expr::Literal {
                 value: Literal::String("João".to_string()),
             }),
@@ -3276,14 +5043,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nome".to_string(),
+                lexeme: "nome".into(),
                 literal: None,
                 span: 2..6,
             },
@@ -3301,7 +5068,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3309,7 +5076,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 10..15,
                 },
@@ -3317,7 +5084,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 20..24,
                     },
@@ -3328,6 +5095,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..35,
             }],
             span: 0..40,
@@ -3338,7 +5107,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -3346,14 +5115,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -3368,14 +5137,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "falar".to_string(),
+                lexeme: "falar".into(),
                 literal: None,
                 span: 2..7,
             },
@@ -3393,7 +5162,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3401,7 +5170,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 10..15,
                 },
@@ -3409,6 +5178,8 @@ mod tests {
                 body: vec![],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..20,
             }],
             span: 0..25,
@@ -3419,7 +5190,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 0..1,
             },
@@ -3427,14 +5198,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -3449,14 +5220,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 0..1,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "falar".to_string(),
+                lexeme: "falar".into(),
                 literal: None,
                 span: 2..7,
             },
@@ -3490,10 +5261,13 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Rc::new(Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         });
 
         // Set up environment with oCara defined at slot 0
@@ -3506,7 +5280,7 @@ mod tests {
         let this_expr = Expr::This {
             keyword: Token {
                 token_type: TokenType::This,
-                lexeme: "oCara".to_string(),
+                lexeme: "oCara".into(),
                 literal: None,
                 span: 0..5,
             },
@@ -3528,10 +5302,13 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Rc::new(Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         });
         interpreter
             .globals
@@ -3542,7 +5319,7 @@ mod tests {
         let this_expr = Expr::This {
             keyword: Token {
                 token_type: TokenType::This,
-                lexeme: "oCara".to_string(),
+                lexeme: "oCara".into(),
                 literal: None,
                 span: 100..105, // Different span, not in resolutions
             },
@@ -3562,7 +5339,7 @@ mod tests {
         let this_expr = Expr::This {
             keyword: Token {
                 token_type: TokenType::This,
-                lexeme: "oCara".to_string(),
+                lexeme: "oCara".into(),
                 literal: None,
                 span: 0..5,
             },
@@ -3592,7 +5369,7 @@ mod tests {
         let native_fn = Rc::new(Function::Native(NativeFunction {
             name: "nativeMethod".to_string(),
             arity: 0,
-            func: |_| Ok(Value::Literal(Literal::Number(42.0))),
+            func: Rc::new(|_| Ok(Value::Literal(Literal::Number(42.0)))),
         }));
 
         let mut methods = HashMap::new();
@@ -3603,10 +5380,13 @@ mod tests {
             superclass: None,
             methods,
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Rc::new(Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         });
 
         // Manually set up the object to be the instance using slot-based storage
@@ -3619,14 +5399,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "obj".to_string(),
+                    lexeme: "obj".into(),
                     literal: None,
                     span: 0..3,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "nativeMethod".to_string(),
+                lexeme: "nativeMethod".into(),
                 literal: None,
                 span: 4..16,
             },
@@ -3651,7 +5431,7 @@ mod tests {
         let native_bora = NativeFunction {
             name: "bora".to_string(),
             arity: 0,
-            func: |_| Ok(Value::Literal(Literal::Nil)),
+            func: Rc::new(|_| Ok(Value::Literal(Literal::Nil))),
         };
 
         let mut methods = HashMap::new();
@@ -3662,6 +5442,7 @@ mod tests {
             superclass: None,
             methods,
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
 
         // Store the class in environment
@@ -3675,14 +5456,14 @@ mod tests {
             callee: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "TestClass".to_string(),
+                    lexeme: "TestClass".into(),
                     literal: None,
                     span: 0..9,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 10..11,
             },
@@ -3709,7 +5490,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Math".to_string(),
+                lexeme: "Math".into(),
                 literal: None,
                 span: 0..4,
             },
@@ -3717,20 +5498,20 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "soma".to_string(),
+                    lexeme: "soma".into(),
                     literal: None,
                     span: 10..14,
                 },
                 params: vec![
                     Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "a".to_string(),
+                        lexeme: "a".into(),
                         literal: None,
                         span: 15..16,
                     },
                     Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "b".to_string(),
+                        lexeme: "b".into(),
                         literal: None,
                         span: 18..19,
                     },
@@ -3738,7 +5519,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 25..29,
                     },
@@ -3746,21 +5527,21 @@ mod tests {
                         left: Box::new(Expr::Variable {
                             name: Token {
                                 token_type: TokenType::Identifier,
-                                lexeme: "a".to_string(),
+                                lexeme: "a".into(),
                                 literal: None,
                                 span: 30..31,
                             },
                         }),
                         operator: Token {
                             token_type: TokenType::Plus,
-                            lexeme: "+".to_string(),
+                            lexeme: "+".into(),
                             literal: None,
                             span: 32..33,
                         },
                         right: Box::new(Expr::Variable {
                             name: Token {
                                 token_type: TokenType::Identifier,
-                                lexeme: "b".to_string(),
+                                lexeme: "b".into(),
                                 literal: None,
                                 span: 34..35,
                             },
@@ -3770,14 +5551,15 @@ mod tests {
                 }],
                 is_static: true,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..40,
             }],
             span: 0..45,
         };
-        interpreter.execute(&class_decl, &mut output).unwrap();
-
         // Set up resolutions for params a and b (distance 0, slots 0 and 1)
         interpreter.set_resolutions([(30..31, (0, 0)), (34..35, (0, 1))].into_iter().collect());
+        interpreter.execute(&class_decl, &mut output).unwrap();
 
         // Math.soma(1, 2)
         let call_expr = Expr::Call {
@@ -3785,21 +5567,21 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Math".to_string(),
+                        lexeme: "Math".into(),
                         literal: None,
                         span: 50..54,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "soma".to_string(),
+                    lexeme: "soma".into(),
                     literal: None,
                     span: 55..59,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 65..66,
             },
@@ -3828,7 +5610,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Math".to_string(),
+                lexeme: "Math".into(),
                 literal: None,
                 span: 0..4,
             },
@@ -3836,7 +5618,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "soma".to_string(),
+                    lexeme: "soma".into(),
                     literal: None,
                     span: 10..14,
                 },
@@ -3844,6 +5626,8 @@ mod tests {
                 body: vec![],
                 is_static: true,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..20,
             }],
             span: 0..25,
@@ -3854,7 +5638,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "m".to_string(),
+                lexeme: "m".into(),
                 literal: None,
                 span: 30..31,
             },
@@ -3862,14 +5646,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Math".to_string(),
+                        lexeme: "Math".into(),
                         literal: None,
                         span: 34..38,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 40..41,
                 },
@@ -3885,21 +5669,21 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "m".to_string(),
+                        lexeme: "m".into(),
                         literal: None,
                         span: 50..51,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "soma".to_string(),
+                    lexeme: "soma".into(),
                     literal: None,
                     span: 52..56,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 58..59,
             },
@@ -3926,7 +5710,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 0..6,
             },
@@ -3934,7 +5718,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "idade".to_string(),
+                    lexeme: "idade".into(),
                     literal: None,
                     span: 10..15,
                 },
@@ -3942,7 +5726,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 20..24,
                     },
@@ -3953,6 +5737,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: true,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..30,
             }],
             span: 0..35,
@@ -3963,7 +5749,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "p".to_string(),
+                lexeme: "p".into(),
                 literal: None,
                 span: 40..41,
             },
@@ -3971,14 +5757,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pessoa".to_string(),
+                        lexeme: "Pessoa".into(),
                         literal: None,
                         span: 44..50,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 52..53,
                 },
@@ -3993,14 +5779,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "p".to_string(),
+                    lexeme: "p".into(),
                     literal: None,
                     span: 60..61,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "idade".to_string(),
+                lexeme: "idade".into(),
                 literal: None,
                 span: 62..67,
             },
@@ -4020,7 +5806,7 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Math".to_string(),
+                lexeme: "Math".into(),
                 literal: None,
                 span: 0..4,
             },
@@ -4028,7 +5814,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "soma".to_string(),
+                    lexeme: "soma".into(),
                     literal: None,
                     span: 10..14,
                 },
@@ -4036,6 +5822,8 @@ mod tests {
                 body: vec![],
                 is_static: true,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..20,
             }],
             span: 0..25,
@@ -4047,14 +5835,14 @@ mod tests {
             object: Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Math".to_string(),
+                    lexeme: "Math".into(),
                     literal: None,
                     span: 30..34,
                 },
             }),
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "multiplica".to_string(),
+                lexeme: "multiplica".into(),
                 literal: None,
                 span: 35..45,
             },
@@ -4081,7 +5869,7 @@ mod tests {
         let parent_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pai".to_string(),
+                lexeme: "Pai".into(),
                 literal: None,
                 span: 0..3,
             },
@@ -4095,14 +5883,14 @@ mod tests {
         let child_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Filho".to_string(),
+                lexeme: "Filho".into(),
                 literal: None,
                 span: 15..20,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 23..26,
                 },
@@ -4133,7 +5921,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "notAClass".to_string(),
+                lexeme: "notAClass".into(),
                 literal: None,
                 span: 0..9,
             },
@@ -4148,14 +5936,14 @@ mod tests {
         let class_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Foo".to_string(),
+                lexeme: "Foo".into(),
                 literal: None,
                 span: 25..28,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "notAClass".to_string(),
+                    lexeme: "notAClass".into(),
                     literal: None,
                     span: 31..40,
                 },
@@ -4184,7 +5972,7 @@ mod tests {
         let parent_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pai".to_string(),
+                lexeme: "Pai".into(),
                 literal: None,
                 span: 0..3,
             },
@@ -4192,7 +5980,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 10..15,
                 },
@@ -4200,7 +5988,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 20..24,
                     },
@@ -4211,6 +5999,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..40,
             }],
             span: 0..45,
@@ -4221,14 +6011,14 @@ mod tests {
         let child_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Filho".to_string(),
+                lexeme: "Filho".into(),
                 literal: None,
                 span: 50..55,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 58..61,
                 },
@@ -4242,7 +6032,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "f".to_string(),
+                lexeme: "f".into(),
                 literal: None,
                 span: 75..76,
             },
@@ -4250,14 +6040,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Filho".to_string(),
+                        lexeme: "Filho".into(),
                         literal: None,
                         span: 79..84,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 86..87,
                 },
@@ -4273,21 +6063,21 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "f".to_string(),
+                        lexeme: "f".into(),
                         literal: None,
                         span: 90..91,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 92..97,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 99..100,
             },
@@ -4314,7 +6104,7 @@ mod tests {
         let parent_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pai".to_string(),
+                lexeme: "Pai".into(),
                 literal: None,
                 span: 0..3,
             },
@@ -4322,7 +6112,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 10..15,
                 },
@@ -4330,7 +6120,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 20..24,
                     },
@@ -4341,6 +6131,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..40,
             }],
             span: 0..45,
@@ -4351,14 +6143,14 @@ mod tests {
         let child_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Filho".to_string(),
+                lexeme: "Filho".into(),
                 literal: None,
                 span: 50..55,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 58..61,
                 },
@@ -4366,7 +6158,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(), // Same method name = override
+                    lexeme: "falar".into(), // Same method name = override
                     literal: None,
                     span: 70..75,
                 },
@@ -4374,7 +6166,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 80..84,
                     },
@@ -4385,6 +6177,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 70..100,
             }],
             span: 50..105,
@@ -4395,7 +6189,7 @@ mod tests {
         let var_decl = Stmt::Var {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "f".to_string(),
+                lexeme: "f".into(),
                 literal: None,
                 span: 110..111,
             },
@@ -4403,14 +6197,14 @@ mod tests {
                 callee: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Filho".to_string(),
+                        lexeme: "Filho".into(),
                         literal: None,
                         span: 114..119,
                     },
                 }),
                 paren: Token {
                     token_type: TokenType::RightParen,
-                    lexeme: ")".to_string(),
+                    lexeme: ")".into(),
                     literal: None,
                     span: 121..122,
                 },
@@ -4426,21 +6220,21 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "f".to_string(),
+                        lexeme: "f".into(),
                         literal: None,
                         span: 125..126,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 127..132,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 134..135,
             },
@@ -4467,7 +6261,7 @@ mod tests {
         let parent_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pai".to_string(),
+                lexeme: "Pai".into(),
                 literal: None,
                 span: 0..3,
             },
@@ -4475,7 +6269,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "cumprimentar".to_string(),
+                    lexeme: "cumprimentar".into(),
                     literal: None,
                     span: 10..22,
                 },
@@ -4483,7 +6277,7 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 30..34,
                     },
@@ -4494,6 +6288,8 @@ mod tests {
                 }],
                 is_static: true, // Static method!
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 10..50,
             }],
             span: 0..55,
@@ -4504,14 +6300,14 @@ mod tests {
         let child_decl = Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Filho".to_string(),
+                lexeme: "Filho".into(),
                 literal: None,
                 span: 60..65,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 68..71,
                 },
@@ -4527,21 +6323,21 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Filho".to_string(),
+                        lexeme: "Filho".into(),
                         literal: None,
                         span: 85..90,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "cumprimentar".to_string(),
+                    lexeme: "cumprimentar".into(),
                     literal: None,
                     span: 91..103,
                 },
             }),
             paren: Token {
                 token_type: TokenType::RightParen,
-                lexeme: ")".to_string(),
+                lexeme: ")".into(),
                 literal: None,
                 span: 105..106,
             },
@@ -1,31 +1,155 @@
+mod analysis;
 mod ast;
+pub mod bitwise;
+mod conditional;
+mod dead_branches;
+mod dialect;
+mod diff;
 mod environment;
 mod error;
+mod format;
+mod identifiers;
 mod interpreter;
+mod line_index;
+mod module;
 mod parser;
+mod random;
+mod redeclarations;
 mod resolver;
 mod runner;
+mod sandbox;
 mod scanner;
+mod scope;
+mod session;
+mod source_map;
 mod token;
 mod value;
 
-use std::io::Write;
-
-pub use ast::{Expr, Stmt};
-pub use error::ManoError;
-pub use parser::Parser;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+pub use analysis::{Analysis, DeclarationId, Reference};
+pub use ast::{Expr, InterpolationPart, Span, Stmt};
+pub use conditional::Conditions;
+pub use dead_branches::{DeadBranch, constant_truthiness, dead_branches};
+pub use dialect::{Dialect, FORMAL_KEYWORDS, LOX_KEYWORDS};
+pub use diff::{AstChange, diff_ast, same_ast};
+pub use environment::{Symbol, SymbolKind};
+pub use error::{Frame, ManoError, Related};
+pub use format::{FormatOptions, format, format_checked, format_with_map};
+pub use identifiers::identifier_warnings;
+pub use interpreter::{MAX_CALL_DEPTH, STACK_SIZE};
+pub use line_index::{LineCol, LineIndex, SourceLocation, word_at};
+pub use module::locate_module;
+pub use parser::{MAX_NESTING, Parser};
+pub use redeclarations::redeclaration_warnings;
+pub use resolver::Resolutions;
 pub use runner::Runner;
+pub use sandbox::SandboxPolicy;
 pub use scanner::{KEYWORDS, Scanner, is_identifier_char};
+pub use scope::{Declaration, DeclarationKind, Scope, ScopeTree};
+pub use source_map::SourceMap;
 pub use token::{Literal, Token, TokenType};
 
 /// Native functions available in the interpreter
-pub const NATIVE_FUNCTIONS: &[&str] = &["fazTeuCorre"];
+pub const NATIVE_FUNCTIONS: &[&str] = &[
+    "fazTeuCorre",
+    "pegaOpcao",
+    "leAi",
+    "aleatorio",
+    "aleatorioEntre",
+    "rodaComando",
+    "viraBytes",
+    "tamanho",
+    "pegaByte",
+    "leBytes",
+    "escreveBytes",
+    "inteiro",
+    "divInteira",
+    "multiplicaMod",
+    "potenciaMod",
+    "refFraca",
+    "pegaRef",
+    "esqueceCampo",
+    "congela",
+    "queTipoE",
+    "textoPraNumero",
+    "numeroPraTexto",
+];
+
+/// Fitas written in mano that every interpreter loads before the user's code
+pub const PRELUDE: &str = include_str!("prelude.mano");
+
+/// The names [`PRELUDE`] declares
+pub const PRELUDE_NAMES: &[&str] = &[
+    "maior",
+    "menor",
+    "absoluto",
+    "limita",
+    "repete",
+    "alinhaDireita",
+    "alinhaEsquerda",
+    "mapeia",
+    "filtra",
+    "junta",
+];
 
 /// Name of the initializer method (constructor) - called automatically on instantiation
 pub const INITIALIZER_NAME: &str = "bora";
 
+/// Runs the resolver's static checks (unused variables, `toma` outside a
+/// fita, ...) without executing anything.
+pub fn check(statements: &[Stmt]) -> Vec<ManoError> {
+    match resolver::Resolver::new().resolve(statements) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors,
+    }
+}
+
+/// Scans and parses the source, returning every scan or parse error found
+pub fn parse(source: &str) -> Result<Vec<Stmt>, Vec<ManoError>> {
+    parse_with_dialect(source, Dialect::default())
+}
+
+/// Like [`parse`], accepting the dialect's keywords unless the source has a pragma
+pub fn parse_with_dialect(source: &str, dialect: Dialect) -> Result<Vec<Stmt>, Vec<ManoError>> {
+    let mut errors = Vec::new();
+    let mut tokens = Vec::new();
+    for result in scanner::Scanner::new(source).with_dialect(dialect) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut parser = parser::Parser::new(tokens);
+    let statements = parser.parse().unwrap();
+    errors.extend(parser.take_errors());
+
+    if errors.is_empty() {
+        Ok(statements)
+    } else {
+        Err(errors)
+    }
+}
+
 pub struct Mano {
     interpreter: interpreter::Interpreter,
+    /// Top-level fitas and bagulhos that ran, for saving the session
+    declarations: Vec<session::SavedDeclaration>,
+    dialect: Dialect,
+    /// Flags for `#se NOME` blocks
+    defines: Vec<String>,
+    /// Whether the [`PRELUDE`] was loaded
+    prelude: bool,
+    /// Whether warnings stop the code from running
+    strict: bool,
 }
 
 impl Default for Mano {
@@ -36,54 +160,200 @@ impl Default for Mano {
 
 impl Mano {
     pub fn new() -> Self {
+        Self::new_with_policy(SandboxPolicy::default())
+    }
+
+    /// An interpreter whose natives only do what the policy allows
+    pub fn new_with_policy(policy: SandboxPolicy) -> Self {
+        let mut mano = Self::without_prelude(policy);
+        let statements = parse(PRELUDE).expect("the prelude parses");
+        let resolutions = resolver::Resolver::new()
+            .resolve(&statements)
+            .expect("the prelude resolves");
+        let errors = mano.execute(&statements, resolutions, None, std::io::sink());
+        assert!(errors.is_empty(), "the prelude runs: {:?}", errors);
+        mano.prelude = true;
+        mano
+    }
+
+    /// Like [`Self::new_with_policy`], without the [`PRELUDE`]'s fitas
+    pub fn without_prelude(policy: SandboxPolicy) -> Self {
         Self {
-            interpreter: interpreter::Interpreter::new(),
+            interpreter: interpreter::Interpreter::with_policy(policy),
+            declarations: Vec::new(),
+            dialect: Dialect::default(),
+            defines: Vec::new(),
+            prelude: false,
+            strict: false,
         }
     }
 
-    pub fn variable_names(&self) -> Vec<String> {
-        self.interpreter.variable_names()
+    /// A prelude fita the user hasn't declared over
+    fn is_prelude_fita(&self, name: &str) -> bool {
+        self.prelude
+            && PRELUDE_NAMES.contains(&name)
+            && !self.declarations.iter().any(|d| d.name == name)
     }
 
-    pub fn run<O: Write>(&mut self, source: &str, mut stdout: O) -> Vec<ManoError> {
-        let mut errors = Vec::new();
-        let scanner = scanner::Scanner::new(source);
+    /// Keywords to accept besides mano's, for sources without a `// dialeto:` pragma
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.dialect = dialect;
+    }
 
-        let mut tokens = Vec::new();
-        for result in scanner {
-            match result {
-                Ok(token) => tokens.push(token),
-                Err(e) => errors.push(e),
-            }
-        }
+    /// Flags that turn on `#se NOME` blocks, from `--define NOME`
+    pub fn set_defines(&mut self, defines: Vec<String>) {
+        self.defines = defines;
+    }
 
-        if !errors.is_empty() {
-            return errors;
-        }
+    /// Refuse to run code with warnings, like a fita declared twice in the
+    /// same source. Declaring it again in a later run still replaces it.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Stop the code that's running at its next statement when `flag` gets
+    /// set, e.g. from a Ctrl+C handler
+    pub fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.interpreter.set_interrupt(flag);
+    }
+
+    /// Make operator errors say what values they got, e.g.
+    /// `esquerda era "mano" (texto), direita era 3 (número)`
+    pub fn set_explain(&mut self, explain: bool) {
+        self.interpreter.set_explain(explain);
+    }
+
+    /// Stop deep code with an error before it overflows a stack of `size`
+    /// bytes, the one the interpreter runs on. Without it only
+    /// [`MAX_CALL_DEPTH`] is checked, so run on a thread with [`STACK_SIZE`]
+    /// and pass that.
+    pub fn set_stack_size(&mut self, size: usize) {
+        self.interpreter.set_stack_size(size);
+    }
 
-        let mut parser = parser::Parser::new(tokens);
-        let statements = parser.parse().unwrap();
+    /// Let `+` join texto with a number, lógico or nadaNão, so
+    /// `"idade: " + 30` is `"idade: 30"` instead of an error
+    pub fn set_text_coercion(&mut self, text_coercion: bool) {
+        self.interpreter.set_text_coercion(text_coercion);
+    }
+
+    /// Arguments for the script, read with `pegaOpcao("nome")` from `--nome valor`
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.interpreter.set_args(args);
+    }
 
-        errors.extend(parser.take_errors());
+    /// Where `leAi` reads lines from, like a file or a test's canned input,
+    /// instead of stdin
+    pub fn set_input<R: Read + 'static>(&mut self, input: R) {
+        self.interpreter.set_input(Box::new(BufReader::new(input)));
+    }
 
-        if !errors.is_empty() {
-            return errors;
+    /// The script's directory, where its `chama` paths start from instead
+    /// of the working directory
+    pub fn set_script_dir(&mut self, dir: &Path) {
+        self.interpreter.set_module_dir(dir);
+    }
+
+    pub fn variable_names(&self) -> Vec<String> {
+        self.interpreter.variable_names()
+    }
+
+    /// Names in scope; leaving out natives also leaves out the prelude's fitas
+    pub fn symbols(&self, include_natives: bool) -> Vec<Symbol> {
+        let mut symbols = self.interpreter.symbols(include_natives);
+        if !include_natives {
+            symbols.retain(|symbol| !self.is_prelude_fita(&symbol.name));
         }
+        symbols
+    }
 
-        // Resolve variable bindings
-        let resolver = resolver::Resolver::new();
-        let resolutions = match resolver.resolve(&statements) {
-            Ok(r) => r,
-            Err(errs) => {
-                return errs;
+    pub fn members(&self, name: &str) -> Vec<Symbol> {
+        self.interpreter.members(name)
+    }
+
+    /// The global state as a script that recreates it when run
+    pub fn save_session(&self) -> String {
+        let mut globals = self.interpreter.globals();
+        globals.retain(|(name, _)| !self.is_prelude_fita(name));
+        session::save(&globals, &self.declarations)
+    }
+
+    fn remember_declaration(&mut self, stmt: &Stmt, source: &str) {
+        let name = match stmt {
+            Stmt::Function { name, .. } | Stmt::Class { name, .. } | Stmt::Enum { name, .. } => {
+                &*name.lexeme
             }
+            _ => return,
         };
+        self.declarations
+            .retain(|declaration| declaration.name != name);
+        self.declarations.push(session::SavedDeclaration {
+            name: name.to_string(),
+            source: source[stmt.span()].to_string(),
+        });
+    }
 
+    pub fn run<O: Write>(&mut self, source: &str, stdout: O) -> Vec<ManoError> {
+        let conditions = Conditions {
+            vm: false,
+            defines: self.defines.clone(),
+        };
+        let source = match conditions.select(source) {
+            Ok(source) => source,
+            Err(errors) => return errors,
+        };
+        let statements = match parse_with_dialect(&source, self.dialect) {
+            Ok(statements) => statements,
+            Err(errors) => return errors,
+        };
+        if self.strict {
+            let warnings = redeclaration_warnings(&statements);
+            if !warnings.is_empty() {
+                return warnings;
+            }
+        }
+        let resolutions = match resolver::Resolver::new()
+            .with_text_coercion(self.interpreter.text_coercion())
+            .resolve(&statements)
+        {
+            Ok(resolutions) => resolutions,
+            Err(errors) => return errors,
+        };
+        self.execute(&statements, resolutions, Some(&source), stdout)
+    }
+
+    fn execute<O: Write>(
+        &mut self,
+        statements: &[Stmt],
+        resolutions: Resolutions,
+        source: Option<&str>,
+        mut stdout: O,
+    ) -> Vec<ManoError> {
+        let mut errors = Vec::new();
         self.interpreter.set_resolutions(resolutions);
 
-        for stmt in &statements {
-            if let Err(e) = self.interpreter.execute(stmt, &mut stdout) {
-                errors.push(e);
+        for stmt in statements {
+            match self.interpreter.execute(stmt, &mut stdout) {
+                Ok(()) => {
+                    if let Some(source) = source {
+                        self.remember_declaration(stmt, source);
+                    }
+                }
+                // The resolver rejects these, but never let control flow escape to the caller
+                Err(ManoError::Return(_)) => errors.push(ManoError::Resolution {
+                    message: resolver::RETURN_OUTSIDE_FUNCTION.to_string(),
+                    span: stmt.span(),
+                }),
+                Err(e) if matches!(e.primary(), ManoError::Throw { .. }) => {
+                    errors.push(e.uncaught())
+                }
+                // With the output gone nothing else gets printed either, and
+                // an interrupted script is done
+                Err(e @ (ManoError::Output { .. } | ManoError::Interrupted { .. })) => {
+                    errors.push(e);
+                    break;
+                }
+                Err(e) => errors.push(e),
             }
         }
 
@@ -101,15 +371,1188 @@ impl Runner for Mano {
         }
     }
 
+    fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+        self.set_interrupt(flag);
+    }
+
     fn variable_names(&self) -> Vec<String> {
         self.variable_names()
     }
+
+    fn symbols(&self, include_natives: bool) -> Vec<Symbol> {
+        self.symbols(include_natives)
+    }
+
+    fn members(&self, name: &str) -> Vec<Symbol> {
+        self.members(name)
+    }
+
+    fn save_session(&self) -> Result<String, String> {
+        Ok(self.save_session())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn check_reports_resolution_errors_without_running() {
+        let tokens: Vec<_> = Scanner::new("{ seLiga x = 1; }")
+            .filter_map(|r| r.ok())
+            .collect();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let errors = check(&statements);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], ManoError::Resolution { .. }));
+    }
+
+    #[test]
+    fn policy_blocks_natives_it_does_not_allow() {
+        let mut mano = Mano::new_with_policy(SandboxPolicy::deny_all());
+        for source in ["fazTeuCorre();", "pegaOpcao(\"nome\");", "leAi();"] {
+            let errors = mano.run(source, Vec::new());
+            let [ManoError::Runtime { message, span }] = errors.as_slice() else {
+                panic!("Expected a runtime error for {}: {:?}", source, errors);
+            };
+            assert!(message.contains("sandbox"), "{}", message);
+            assert_eq!(&source[span.clone()], ")");
+        }
+
+        // Only what's denied is blocked
+        let mut mano = Mano::new_with_policy(SandboxPolicy {
+            env: false,
+            ..SandboxPolicy::default()
+        });
+        assert!(mano.run("fazTeuCorre();", Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn le_ai_reads_lines_from_the_input() {
+        let mut mano = Mano::new();
+        mano.set_input("Zé\nRua 1\n".as_bytes());
+        let mut output = Vec::new();
+        let source = "seLiga nome = leAi(); salve \"oi, {nome}!\"; leAi(); salve leAi();";
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "oi, Zé!\nnadaNão\n");
+    }
+
+    /// A directory with the given modules in it, relative paths as names
+    fn modules(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in files {
+            let file = dir.path().join(name);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn chama_runs_a_module_once_and_shares_its_globals() {
+        let dir = modules(&[
+            (
+                "lib/util.mano",
+                "salve \"carregou\"; olhaEssaFita dobro(n) { toma n * 2; } chama \"outro\";",
+            ),
+            ("lib/outro.mano", "seLiga oi = \"oi\";"),
+        ]);
+        let mut mano = Mano::new();
+        mano.set_script_dir(dir.path());
+        let mut output = Vec::new();
+
+        let source = "chama \"lib/util\"; chama \"lib/util.mano\"; salve dobro(2); salve oi;";
+        let errors = mano.run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(output).unwrap(), "carregou\n4\noi\n");
+    }
+
+    #[test]
+    fn chama_reports_cycles_and_errors_inside_modules() {
+        let dir = modules(&[
+            ("a.mano", "chama \"b\";"),
+            ("b.mano", "chama \"a\";"),
+            ("ruim.mano", "salve 1;\nsalve naoExiste;"),
+        ]);
+        let mut mano = Mano::new();
+        mano.set_script_dir(dir.path());
+
+        for (source, expected) in [
+            ("chama \"a\";", "a.mano → b.mano → a.mano"),
+            ("chama \"ruim\";", "Deu ruim no módulo ruim.mano:2:7"),
+            ("chama \"sumiu\";", "Não achei o módulo 'sumiu'"),
+        ] {
+            let errors = mano.run(source, Vec::new());
+            let [ManoError::Runtime { message, span }] = errors.as_slice() else {
+                panic!("Expected a runtime error for {}: {:?}", source, errors);
+            };
+            assert!(message.contains(expected), "{}", message);
+            assert_eq!(span.start, 6);
+        }
+
+        let mut mano = Mano::new_with_policy(SandboxPolicy::deny_all());
+        let errors = mano.run("chama \"a\";", Vec::new());
+        assert!(matches!(
+            errors.as_slice(),
+            [ManoError::Runtime { message, .. }] if message.contains("sandbox")
+        ));
+    }
+
+    #[test]
+    fn rest_parameters_take_the_arguments_left_as_a_lista() {
+        let mut output = Vec::new();
+        let errors = Mano::new().run(
+            "olhaEssaFita junta(sep, ...partes) { salve partes; toma sep; }\n\
+             junta(\",\"); junta(\"-\", 1, 2, 3);\n\
+             bagulho Soma { bora(...nums) { oCara.total = tamanho(nums); } }\n\
+             salve Soma(4, 5).total; salve junta;",
+            &mut output,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[]\n[1, 2, 3]\n2\n<fita junta/1+>\n"
+        );
+
+        let errors = Mano::new().run(
+            "olhaEssaFita f(a, ...resto) { toma [a, resto]; }\nf();",
+            &mut Vec::new(),
+        );
+        assert!(
+            matches!(
+                errors[0].primary(),
+                ManoError::Runtime { message, .. }
+                    if message == "Essa fita espera pelo menos 1 argumentos, mas tu passou 0, maluco!"
+            ),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn tenta_ai_catches_thrown_values_and_runtime_errors() {
+        let source = r#"
+            olhaEssaFita dobra(n) { toma n * 2; }
+            tentaAi { joga 42; } seDeuRuim (erro) { salve erro + 1; }
+            tentaAi { dobra(1, 2); } seDeuRuim (erro) { salve erro; }
+            olhaEssaFita fundo() { tentaAi { joga "lá"; } seDeuRuim (_e) { joga "de novo"; } }
+            tentaAi { fundo(); salve "nunca"; } seDeuRuim (erro) { salve erro; }
+            seVira (seLiga i = 0; i < 3; i = i + 1) {
+                tentaAi { sePá (i == 1) saiFora; } seDeuRuim (_e) {}
+                salve i;
+            }
+        "#;
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "43\nEssa fita espera 1 argumentos, mas tu passou 2, maluco!\nde novo\n0\n"
+        );
+    }
+
+    #[test]
+    fn uncaught_joga_is_a_runtime_error_at_the_joga() {
+        let source = "olhaEssaFita f() { joga \"vish\"; }\nf();\nsalve 1;";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        let [error] = errors.as_slice() else {
+            panic!("Expected one error: {:?}", errors);
+        };
+        let ManoError::Runtime { message, span } = error.primary() else {
+            panic!("Expected a runtime error: {:?}", error);
+        };
+        assert_eq!(message, "Jogaram vish e ninguém segurou, mano!");
+        assert_eq!(&source[span.clone()], "joga \"vish\";");
+        let [frame] = error.stack() else {
+            panic!("Expected the call to f: {:?}", error);
+        };
+        assert_eq!(
+            (frame.name.as_str(), &source[frame.span.clone()]),
+            ("f", ")")
+        );
+        // Like any runtime error, the statements after it still run
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n");
+    }
+
+    #[test]
+    fn runtime_errors_carry_the_calls_they_happened_in() {
+        let source = "olhaEssaFita dividir(n) { toma n / \"zero\"; }\n\
+                      olhaEssaFita calcular() { toma dividir(1); }\n\
+                      calcular();";
+        let errors = Mano::new().run(source, &mut Vec::new());
+        let [error] = errors.as_slice() else {
+            panic!("Expected one error: {:?}", errors);
+        };
+        assert!(matches!(error.primary(), ManoError::Runtime { .. }));
+        let calls: Vec<(&str, &str)> = error
+            .stack()
+            .iter()
+            .map(|frame| (frame.name.as_str(), &source[frame.span.clone()]))
+            .collect();
+        assert_eq!(calls, [("dividir", ")"), ("calcular", ")")]);
+
+        // Caught errors don't keep a stack around, and later ones start fresh
+        let errors = Mano::new().run(
+            "olhaEssaFita f(a, b) { tentaAi { a / b; } seDeuRuim (_e) {} toma -b; }\nf(1, \"b\");",
+            &mut Vec::new(),
+        );
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].stack().len(), 1);
+    }
+
+    #[test]
+    fn random_natives_follow_the_seed() {
+        let run = |seed| {
+            let mut output = Vec::new();
+            let mut mano = Mano::new_with_policy(SandboxPolicy {
+                seed: Some(seed),
+                ..SandboxPolicy::default()
+            });
+            let source = "salve aleatorio(); seVira (seLiga i = 0; i < 5; i = i + 1) salve aleatorioEntre(1, 6);";
+            assert!(mano.run(source, &mut output).is_empty());
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+        for line in run(7).lines().skip(1) {
+            assert!(["1", "2", "3", "4", "5", "6"].contains(&line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn aleatorio_entre_wants_whole_numbers_in_order() {
+        for source in [
+            "aleatorioEntre(1.5, 3);",
+            "aleatorioEntre(\"1\", 3);",
+            "aleatorioEntre(3, 1);",
+        ] {
+            let errors = Mano::new().run(source, Vec::new());
+            assert!(
+                matches!(errors.as_slice(), [ManoError::Runtime { .. }]),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn roda_comando_returns_output_and_status_when_allowed() {
+        let source = "seLiga r = rodaComando(\"echo oi; echo ruim >&2; exit 3\"); salve r.saida + r.erro; salve r.status;";
+        let errors = Mano::new().run(source, Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("sandbox"))
+        );
+
+        let mut output = Vec::new();
+        let mut mano = Mano::new_with_policy(SandboxPolicy {
+            process: true,
+            ..SandboxPolicy::default()
+        });
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "oi\nruim\n\n3\n");
+    }
+
+    #[test]
+    fn interpolation_errors_point_at_the_expression_inside() {
+        let source = "seLiga n = 2; salve \"total: {n - treta}\";";
+        let errors = Mano::new().run(source, Vec::new());
+        match errors.as_slice() {
+            [ManoError::Runtime { span, .. }] => {
+                assert!(span.start > source.find('{').unwrap());
+                assert!(span.end <= source.find('}').unwrap());
+            }
+            other => panic!("expected one Runtime error, got {:?}", other),
+        }
+
+        let mut output = Vec::new();
+        assert!(
+            Mano::new()
+                .run("salve \"{2 * 3} e {\"oi\"}!\";", &mut output)
+                .is_empty()
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), "6 e oi!\n");
+    }
+
+    #[test]
+    fn bytes_natives_index_measure_and_concatenate() {
+        let source = r#"seLiga b = viraBytes("aí") + b"\x00";
+                        salve b; salve tamanho(b); salve tamanho("aí"); salve pegaByte(b, 1);"#;
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<bytes 4: 61 c3 ad 00>\n4\n2\n195\n"
+        );
+
+        let errors = Mano::new().run("pegaByte(b\"a\", 1);", Vec::new());
+        assert!(
+            matches!(errors.as_slice(), [ManoError::Runtime { message, .. }] if message.contains("0 a 0"))
+        );
+    }
+
+    #[test]
+    fn bytes_files_round_trip_unless_the_sandbox_denies_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dados.bin").display().to_string();
+        let source = format!(
+            "escreveBytes(\"{0}\", b\"\\xff\\x00\"); salve leBytes(\"{0}\");",
+            path
+        );
+        let mut output = Vec::new();
+        assert!(Mano::new().run(&source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "<bytes 2: ff 00>\n");
+
+        let errors = Mano::new_with_policy(SandboxPolicy::deny_all()).run(&source, Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("sandbox"))
+        );
+    }
+
+    #[test]
+    fn integer_natives_stay_exact() {
+        let source = r#"salve inteiro(-3.9); salve inteiro(" 42 ");
+                        salve divInteira(7, 2); salve divInteira(-7, 2);
+                        salve multiplicaMod(9007199254740991, 9007199254740991, 1000000007);
+                        salve potenciaMod(2, 100, 1000000007); salve potenciaMod(5, 0, 1);"#;
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "-3\n42\n3\n-4\n104380609\n976371285\n0\n"
+        );
+
+        for source in [
+            "inteiro(\"4.5\");",
+            "inteiro(99999999999999999999);",
+            "divInteira(1.5, 1);",
+            "divInteira(1, 0);",
+            "multiplicaMod(2, 3, 0);",
+            "potenciaMod(2, -1, 7);",
+        ] {
+            let errors = Mano::new().run(source, Vec::new());
+            assert!(
+                matches!(errors.as_slice(), [ManoError::Runtime { .. }]),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn conversion_natives_go_between_texto_and_numero() {
+        let source = r#"salve textoPraNumero(" 42 ") + 1; salve textoPraNumero("-1.5e2");
+                        salve textoPraNumero("42 reais"); salve textoPraNumero("inf");
+                        salve textoPraNumero("1e400");
+                        salve numeroPraTexto(1.5) + "!";"#;
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "43\n-150\nnadaNão\nnadaNão\nnadaNão\n1.5!\n"
+        );
+
+        for source in ["textoPraNumero(42);", "numeroPraTexto(\"42\");"] {
+            let errors = Mano::new().run(source, Vec::new());
+            assert!(
+                matches!(errors.as_slice(), [ManoError::Runtime { .. }]),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn text_coercion_lets_plus_join_texto_with_other_values() {
+        let source = r#"salve "idade: " + 30; olhaEssaFita f(x) { toma x + " anos"; }
+                        salve f(firmeza); salve f(nadaNão);"#;
+        let errors = Mano::new().run(source, Vec::new());
+        assert!(matches!(errors.as_slice(), [ManoError::Resolution { .. }]));
+
+        let errors = Mano::new().run("olhaEssaFita f(x) { toma x + \"!\"; } f(1);", Vec::new());
+        assert!(errors[0].help().unwrap().contains("--junta-texto"));
+
+        let mut mano = Mano::new();
+        mano.set_text_coercion(true);
+        let mut output = Vec::new();
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "idade: 30\nfirmeza anos\nnadaNão anos\n"
+        );
+
+        // Still only texto with the plain values
+        let errors = mano.run(
+            "olhaEssaFita f(x) { toma \"l: \" + x; } f([1]);",
+            Vec::new(),
+        );
+        assert!(matches!(errors[0].primary(), ManoError::Runtime { .. }));
+    }
+
+    #[test]
+    fn bitwise_operators_work_on_truncated_numbers() {
+        let source =
+            "salve 6 & 3; salve 6 | 3; salve 6 ^ 3; salve 1 << 4; salve -16 >> 2; salve 7.9 & 5;
+                      salve 10 & 1 == 0;";
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2\n7\n5\n16\n-4\n5\nfirmeza\n"
+        );
+
+        let errors = Mano::new().run("seLiga n = 64; salve 1 << n;", Vec::new());
+        assert!(
+            matches!(errors.as_slice(), [ManoError::Runtime { message, span }] if message.contains("0 a 63") && *span == (23..25))
+        );
+        let errors = Mano::new().run("salve \"a\" & 1;", Vec::new());
+        assert!(matches!(errors.as_slice(), [ManoError::Resolution { .. }]));
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_the_receiver_once() {
+        let source = "
+            seLiga log = \"\";
+            olhaEssaFita anota(s, v) { log = log + s; toma v; }
+            bagulho Caixa {}
+            seLiga c = Caixa(); c.n = 1;
+            seLiga d = Caixa(); d.c = c;
+            salve anota(\"a\", d).c.n += anota(\"b\", 10);
+            salve log; salve c.n;
+            d.c.s = \"oi\"; d.c.s += \" mano\"; salve c.s;
+            seLiga x = 10; x -= 1; x *= 2; x /= 3; x %= 4; salve x;
+            seLiga y = 1; y += (y = 5); salve y;";
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "11\nab\n11\noi mano\n2\n6\n"
+        );
+
+        let errors = Mano::new().run(
+            "bagulho C {} seLiga c = C(); c.nome = \"a\"; c.nome -= 1;",
+            Vec::new(),
+        );
+        assert!(matches!(errors.as_slice(), [ManoError::Runtime { .. }]));
+    }
+
+    #[test]
+    fn calling_bora_again_reinitializes_and_gives_back_the_parada() {
+        let mut mano = Mano::new();
+        let mut output = Vec::new();
+        let code = "bagulho P { bora(n) { oCara.n = n; } }\n\
+                    seLiga p = P(1);\n\
+                    salve p.bora(2).n;\n\
+                    seLiga de_novo = p.bora;\n\
+                    de_novo(3);\n\
+                    salve p.n;";
+        assert!(mano.run(code, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "2\n3\n");
+    }
+
+    #[test]
+    fn bora_cannot_be_static_or_a_getter() {
+        let mut mano = Mano::new();
+        let errors = mano.run("bagulho P { bagulho bora() {} }", &mut Vec::new());
+        assert!(
+            matches!(&errors[..], [ManoError::Resolution { message, .. }] if message.contains("estático"))
+        );
+        let errors = mano.run("bagulho Q { bora { oCara.n = 1; } }", &mut Vec::new());
+        assert!(
+            matches!(&errors[..], [ManoError::Resolution { message, .. }] if message.contains("getter"))
+        );
+    }
+
+    #[test]
+    fn enum_options_are_unique_frozen_paradas() {
+        let source = "
+            opcoes Cor { Vermelho, Verde }
+            seLiga c = Cor.Verde;
+            salve c; salve c.nome; salve c.ordem;
+            salve c == Cor.Verde; salve c == Cor.Vermelho;
+            sePá (Cor.Vermelho != c) salve \"diferente\";";
+        let mut mano = Mano::new();
+        let mut output = Vec::new();
+        let errors = mano.run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Cor.Verde\nVerde\n1\nfirmeza\ntreta\ndiferente\n"
+        );
+
+        for (code, expected) in [
+            ("Cor();", "Tem só Cor.Vermelho, Cor.Verde"),
+            (
+                "Cor.Azul;",
+                "'Azul' não tá nas opções de Cor! Tem Vermelho, Verde.",
+            ),
+            ("Cor.Verde.nome = \"azul\";", "congelada"),
+            ("bagulho Tom < Cor {}", "não podem ser coroa"),
+        ] {
+            let errors = mano.run(code, &mut Vec::new());
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. } if message.contains(expected)),
+                "for {code}: {:?}",
+                errors
+            );
+        }
+        assert!(
+            mano.members("Cor")
+                .iter()
+                .any(|member| member.name == "Vermelho")
+        );
+    }
+
+    #[test]
+    fn duplicate_enum_options_are_errors() {
+        let errors = Mano::new().run("opcoes Cor { Azul, Verde, Azul }", &mut Vec::new());
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].primary(),
+            ManoError::Resolution { message, .. } if message.contains("'Azul' já tá nas opções de 'Cor'")
+        ));
+        assert_eq!(errors[0].related()[0].span, 13..17);
+    }
+
+    #[test]
+    fn duplicate_methods_are_errors_pointing_at_the_first() {
+        let mut mano = Mano::new();
+        let code = "bagulho P {\n  falar() {}\n  falar() {}\n  bagulho falar() {}\n}";
+        let errors = mano.run(code, &mut Vec::new());
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].primary(),
+            ManoError::Resolution { message, .. } if message.contains("método de instância 'falar'")
+        ));
+        assert_eq!(errors[0].related()[0].span, 14..19);
+
+        // A getter counts as an instance method; a static one is apart
+        let code = "bagulho Q { bagulho a() {} bagulho a() {} a { toma 1; } a() {} }";
+        let errors = mano.run(code, &mut Vec::new());
+        assert_eq!(errors.len(), 2);
+        assert!(
+            matches!(errors[0].primary(), ManoError::Resolution { message, .. } if message.contains("estático"))
+        );
+    }
+
+    #[test]
+    fn memoized_getter_runs_once_until_a_field_changes() {
+        let source = "
+            seLiga contas = 0;
+            bagulho Quadrado {
+                bora(lado) { oCara.lado = lado; }
+                memoizado area { contas = contas + 1; toma oCara.lado * oCara.lado; }
+            }
+            seLiga q = Quadrado(3);
+            salve q.area; salve q.area; salve contas;
+            q.lado = 4;
+            salve q.area; salve q.area; salve contas;
+            salve Quadrado(5).area; salve contas;";
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "9\n9\n1\n16\n16\n2\n25\n3\n"
+        );
+    }
+
+    #[test]
+    fn forgotten_field_uncovers_the_method_it_shadowed() {
+        let source = "
+            seLiga contas = 0;
+            bagulho Caixa {
+                oi() { toma \"método\"; }
+                memoizado total { contas = contas + 1; toma contas; }
+            }
+            seLiga c = Caixa();
+            c.oi = \"campo\";
+            salve c.oi;
+            salve c.total;
+            salve esqueceCampo(c, \"oi\");
+            salve c.oi();
+            salve c.total;
+            salve esqueceCampo(c, \"oi\");";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "campo\n1\nfirmeza\nmétodo\n2\ntreta\n"
+        );
+    }
+
+    #[test]
+    fn esquece_campo_needs_a_parada_and_a_name() {
+        let mut mano = Mano::new();
+        let errors = mano.run("esqueceCampo(1, \"x\");", &mut Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("só pra parada"))
+        );
+        let errors = mano.run("bagulho B {} esqueceCampo(B(), 1);", &mut Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("em texto"))
+        );
+    }
+
+    #[test]
+    fn bound_methods_keep_their_parada_and_compare_by_method() {
+        let source = "
+            bagulho Pessoa {
+                bora(nome) { oCara.nome = nome; }
+                falar() { toma \"oi, \" + oCara.nome; }
+                andar() {}
+            }
+            bagulho Aluno < Pessoa {}
+            seLiga p = Pessoa(\"Ana\");
+            seLiga a = Aluno();
+            a.nome = \"Bia\";
+            seLiga f = p.falar;
+            p.nome = \"Ana Maria\";
+            salve f();
+            salve p.falar == p.falar;
+            salve f == p.falar;
+            salve p.falar == p.andar;
+            salve p.falar == Pessoa(\"Ana\").falar;
+            salve a.falar == a.falar;
+            salve a.falar == p.falar;";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "oi, Ana Maria\nfirmeza\nfirmeza\ntreta\ntreta\nfirmeza\ntreta\n"
+        );
+    }
+
+    #[test]
+    fn type_errors_name_the_kinds_of_both_sides() {
+        let mut mano = Mano::new();
+        for (code, expected) in [
+            (
+                "olhaEssaFita minhaFita() {} minhaFita > 3;",
+                "Não dá pra comparar fita com número, mano!",
+            ),
+            (
+                "bagulho B {} 1 <= B;",
+                "Não dá pra comparar número com bagulho",
+            ),
+            ("seLiga l = []; l * 2;", "Veio lista com número."),
+            ("seLiga t = \"oi\"; t + 1;", "Veio texto com número."),
+            ("seLiga t = nadaNão; -t;", "Veio nadaNão."),
+        ] {
+            let errors = mano.run(code, &mut Vec::new());
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. } if message.contains(expected)),
+                "for {code}: {:?}",
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn que_tipo_e_names_kinds_like_the_errors_do() {
+        let source = "
+            bagulho B {}
+            olhaEssaFita f() {}
+            seLiga tipos = [1, \"oi\", b\"oi\", firmeza, nadaNão, f, B, B(), refFraca(B()), []];
+            salve junta(mapeia(tipos, queTipoE), \", \");";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "número, texto, bytes, lógico, nadaNão, fita, bagulho, parada, ref fraca, lista\n"
+        );
+
+        let errors = Mano::new().run("tamanho(1);", &mut Vec::new());
+        assert!(
+            matches!(errors[0].primary(), ManoError::Runtime { message, .. } if message.ends_with("não 1 (número)!"))
+        );
+    }
+
+    #[test]
+    fn failed_writes_point_at_the_salve() {
+        struct ClosedPipe;
+        impl std::io::Write for ClosedPipe {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let source = "seLiga x = 1;\nsalve x;\nsalve 2;";
+        let errors = Mano::new().run(source, &mut ClosedPipe);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].is_broken_pipe());
+        assert_eq!(&source[errors[0].span().unwrap().clone()], "salve x;");
+    }
+
+    #[test]
+    fn interrupt_stops_at_the_next_statement_and_clears_itself() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut mano = Mano::new();
+        mano.set_interrupt(Arc::clone(&flag));
+
+        let setter = Arc::clone(&flag);
+        let ctrl_c = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            setter.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        let source = "salve \"antes\";\nsegueOFluxo (firmeza) {}\nsalve \"depois\";";
+        let mut output = Vec::new();
+        let errors = mano.run(source, &mut output);
+        ctrl_c.join().unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ManoError::Interrupted { .. }));
+        assert_eq!(String::from_utf8(output).unwrap(), "antes\n");
+        assert!(!flag.load(std::sync::atomic::Ordering::Relaxed));
+
+        let mut output = Vec::new();
+        assert!(mano.run("salve 1;", &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n");
+    }
+
+    #[test]
+    fn lists_are_indexed_changed_and_shared() {
+        let source = "
+            seLiga l = [1, \"oi\", [2, 3],];
+            seLiga m = l;
+            m[0] += 10;
+            l.adiciona(nadaNão);
+            salve l;
+            salve l[2][1];
+            salve l.tamanho + tamanho(m);
+            salve l.tira();
+            salve l == m;
+            salve [1] == [1];";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[11, \"oi\", [2, 3], nadaNão]\n3\n8\nnadaNão\nfirmeza\ntreta\n"
+        );
+    }
+
+    #[test]
+    fn list_errors_point_at_the_bracket() {
+        let mut mano = Mano::new();
+        for (code, expected) in [
+            ("seLiga l = [1, 2]; l[2];", "Vai de 0 a 1"),
+            ("[][0] = 1;", "tá vazia"),
+            ("[1][\"0\"];", "Índice de lista é número"),
+            ("seLiga n = 1; n[0];", "Só dá pra usar [] em lista"),
+            ("[].tira();", "Não tem o que tirar"),
+            ("[].push(1);", "Tem adiciona, tira e tamanho"),
+        ] {
+            let errors = mano.run(code, &mut Vec::new());
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. } if message.contains(expected)),
+                "for {code}: {:?}",
+                errors
+            );
+        }
+        let source = "seLiga l = [1];\nl[5];";
+        let errors = mano.run(source, &mut Vec::new());
+        let ManoError::Runtime { span, .. } = errors[0].primary() else {
+            panic!("expected a runtime error, got {:?}", errors[0]);
+        };
+        assert_eq!(&source[span.clone()], "]");
+    }
+
+    #[test]
+    fn a_list_inside_itself_prints_once() {
+        let mut output = Vec::new();
+        let errors = Mano::new().run("seLiga l = [1]; l.adiciona(l); salve l;", &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(output).unwrap(), "[1, [...]]\n");
+    }
+
+    #[test]
+    fn frozen_parada_keeps_its_fields() {
+        let source = "
+            bagulho Cor { bora(nome) { oCara.nome = nome; } }
+            seLiga azul = congela(Cor(\"azul\"));
+            salve azul.nome;";
+        let mut mano = Mano::new();
+        let mut output = Vec::new();
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "azul\n");
+
+        for code in [
+            "azul.nome = \"verde\";",
+            "azul.nova = 1;",
+            "azul.bora(\"verde\");",
+        ] {
+            let errors = mano.run(code, &mut Vec::new());
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. } if message.contains("congelada")),
+                "for {code}: {:?}",
+                errors
+            );
+        }
+        let errors = mano.run("esqueceCampo(azul, \"nome\");", &mut Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("congelada"))
+        );
+        let errors = mano.run("congela(1);", &mut Vec::new());
+        assert!(
+            matches!(&errors[0], ManoError::Runtime { message, .. } if message.contains("só pra parada"))
+        );
+
+        let mut output = Vec::new();
+        assert!(
+            mano.run("salve azul.nome; salve Cor(\"rosa\").nome;", &mut output)
+                .is_empty()
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), "azul\nrosa\n");
+    }
+
+    #[test]
+    fn assigning_to_a_getter_says_it_is_a_getter() {
+        let source = "
+            bagulho Pessoa { idade { toma 42; } }
+            bagulho Aluno < Pessoa {}
+            seLiga p = Aluno();
+            p.idade += 1;";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert_eq!(errors.len(), 1);
+        let ManoError::Runtime { message, span } = errors[0].primary() else {
+            panic!("expected a runtime error, got {:?}", errors[0]);
+        };
+        assert!(
+            message.contains("'idade' é getter do bagulho 'Aluno'"),
+            "got: {message}"
+        );
+        assert_eq!(&source[span.clone()], "idade");
+        assert_eq!(errors[0].related()[0].message, "o getter tá aqui");
+        assert_eq!(
+            errors[0].related()[0].span,
+            source.find("idade").map(|i| i..i + 5).unwrap()
+        );
+        assert!(errors[0].help().unwrap().contains("oCara._idade"));
+    }
+
+    #[test]
+    fn weak_references_do_not_keep_instances_alive() {
+        let source = "
+            bagulho Caixa {}
+            seLiga fraca = nadaNão;
+            olhaEssaFita usa() {
+                seLiga c = Caixa();
+                fraca = refFraca(c);
+                salve pegaRef(fraca);
+            }
+            usa();
+            salve fraca; salve pegaRef(fraca);
+            seLiga forte = Caixa();
+            salve refFraca(forte) == refFraca(forte); salve pegaRef(refFraca(forte));";
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<parada Caixa>\n<ref fraca sumiu>\nnadaNão\nfirmeza\n<parada Caixa>\n"
+        );
+
+        let errors = Mano::new().run("refFraca(1);", Vec::new());
+        assert!(matches!(errors.as_slice(), [ManoError::Runtime { .. }]));
+    }
+
+    #[test]
+    fn prelude_declares_exactly_its_public_names() {
+        let statements = parse(PRELUDE).unwrap();
+        let mut declared: Vec<_> = statements
+            .iter()
+            .filter_map(|stmt| stmt.function_declaration())
+            .map(|(name, _, _)| name.lexeme.to_string())
+            .collect();
+        declared.sort();
+        let mut names = PRELUDE_NAMES.to_vec();
+        names.sort();
+        assert_eq!(declared, names);
+        assert!(check(&statements).is_empty());
+
+        let has = |mano: &Mano, name: &str| mano.symbols(true).iter().any(|s| s.name == name);
+        let mano = Mano::new();
+        assert!(PRELUDE_NAMES.iter().all(|name| has(&mano, name)));
+        // Kept out of listings of the user's names and out of saved sessions
+        assert!(mano.symbols(false).is_empty());
+        assert!(!mano.save_session().contains("maior"));
+        let bare = Mano::without_prelude(SandboxPolicy::default());
+        assert!(!PRELUDE_NAMES.iter().any(|name| has(&bare, name)));
+    }
+
+    #[test]
+    fn prelude_fitas_work() {
+        let source = "salve maior(2, 5); salve menor(2, 5); salve absoluto(-3); salve limita(12, 0, 10);
+                      salve \"[\" + alinhaDireita(\"oi\", 4) + \"|\" + alinhaEsquerda(\"oi\", 4) + \"]\";
+                      salve repete(\"ab\", 3);
+                      salve junta(filtra(mapeia([1, 2, 3], olhaEssaFita (n) { toma n * 2; }),
+                                         olhaEssaFita (n) { toma n > 2; }), \", \");";
+        let mut output = Vec::new();
+        assert!(Mano::new().run(source, &mut output).is_empty());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "5\n2\n3\n10\n[  oi|oi  ]\nababab\n4, 6\n"
+        );
+    }
+
+    #[test]
+    fn runs_only_the_interpreter_and_defined_blocks() {
+        let source = "#seVm\nsalve 1;\n#vacilou\nsalve 2;\n#fim\n#se DEBUG\nsalve 3;\n#fim\n";
+        let mut mano = Mano::new();
+        let mut output = Vec::new();
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "2\n");
+
+        mano.set_defines(vec!["DEBUG".to_string()]);
+        let mut output = Vec::new();
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "2\n3\n");
+
+        let errors = mano.run("#se DEBUG\nsalve 1;", &mut Vec::new());
+        assert!(matches!(&errors[0], ManoError::Parse { span, .. } if *span == (0..9)));
+    }
+
+    #[test]
+    fn runs_formal_dialect() {
+        let source = "funcao dobro(n) { retorne n * 2; }
+                      variavel x = verdadeiro ou falso;
+                      se (x) imprima dobro(21); senao imprima nulo;";
+        let mut output = Vec::new();
+        let mut mano = Mano::new();
+        mano.set_dialect(Dialect::Formal);
+        assert!(mano.run(source, &mut output).is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "42\n");
+
+        // Without the dialect those are just names
+        assert!(!Mano::new().run(source, Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn runs_lox_programs_unchanged() {
+        let source = "
+            class Doughnut {
+              init(flavor) { this.flavor = flavor; }
+              cook() { return \"Fry \" + this.flavor; }
+            }
+            class Cruller < Doughnut {
+              init(flavor) { super.init(flavor); }
+              cook() { return super.cook() + \"!\"; }
+            }
+            fun counter() {
+              var i = 0;
+              fun count() { i = i + 1; return i; }
+              return count;
+            }
+            var c = counter();
+            c();
+            print c();
+            for (var i = 0; i < 2 and true; i = i + 1) print i;
+            print Cruller(\"glaze\").cook();
+            print clock() > 0 or nil;";
+        let mut output = Vec::new();
+        let mut mano = Mano::new();
+        mano.set_dialect(Dialect::Lox);
+        let errors = mano.run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2\n0\n1\nFry glaze!\nfirmeza\n"
+        );
+    }
+
+    #[test]
+    fn names_match_however_their_accents_were_typed() {
+        let mut output = Vec::new();
+        let errors = Mano::new().run("seLiga ação = 1; salve ac\u{327}a\u{303}o;", &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(output, b"1\n");
+    }
+
+    #[test]
+    fn invoked_methods_keep_their_receiver() {
+        let source = "
+            bagulho Mano {
+                bora(nome) { oCara.nome = nome; }
+                falar(oi) { toma oi + \", \" + oCara.nome; }
+                grito { toma oCara.falar(\"ÔÔ\"); }
+            }
+            seLiga p = Mano(\"Zé\");
+            seLiga q = Mano(\"Tião\");
+            salve p.falar(\"salve\");
+            seLiga guardada = p.falar;
+            salve guardada(\"e aí\");
+            salve q.grito;
+            q.falar = olhaEssaFita (oi) { toma oi + \"!\"; };
+            salve q.falar(\"campo\");";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "salve, Zé\ne aí, Zé\nÔÔ, Tião\ncampo!\n"
+        );
+    }
+
+    /// Runs `test` on a thread with the stack the CLI gives the interpreter
+    fn with_stack(test: impl FnOnce() + Send + 'static) {
+        let thread = std::thread::Builder::new().stack_size(STACK_SIZE);
+        thread.spawn(test).unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn deep_code_stops_with_an_error_before_the_stack_runs_out() {
+        with_stack(|| {
+            let mut mano = Mano::new();
+            // Much less than the thread has, so it runs out in release builds too
+            mano.set_stack_size(8 * 1024 * 1024);
+            let chain = vec!["1"; 10_000].join(" + ");
+            let errors = mano.run(&format!("salve {};", chain), Vec::new());
+            assert!(matches!(&errors[..], [ManoError::Runtime { message, .. }]
+                if message.contains("encheu a pilha")));
+
+            let errors = mano.run("olhaEssaFita f(n) { toma f(n + 1); } f(0);", Vec::new());
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. }
+                if message.contains("Afundou demais"))
+            );
+        });
+    }
+
+    #[test]
+    fn calls_stop_at_max_call_depth() {
+        with_stack(|| {
+            let mut mano = Mano::new();
+            mano.set_stack_size(STACK_SIZE);
+            let mut output = Vec::new();
+            let source = format!(
+                "olhaEssaFita f(n) {{ sePá (n == 0) toma 0; toma f(n - 1); }}
+                 salve f({}); f({});",
+                MAX_CALL_DEPTH - 1,
+                MAX_CALL_DEPTH
+            );
+            let errors = mano.run(&source, &mut output);
+            assert_eq!(output, b"0\n");
+            assert!(
+                matches!(errors[0].primary(), ManoError::Runtime { message, .. }
+                if message.contains(&format!("Passou de {} chamadas", MAX_CALL_DEPTH)))
+            );
+        });
+    }
+
+    #[test]
+    fn deep_but_legal_recursion_runs() {
+        with_stack(|| {
+            let mut mano = Mano::new();
+            mano.set_stack_size(STACK_SIZE);
+            let mut output = Vec::new();
+            let source = "olhaEssaFita soma(n) { sePá (n == 0) toma 0; toma n + soma(n - 1); }
+                          salve soma(2000);";
+            let errors = mano.run(source, &mut output);
+            assert!(errors.is_empty(), "{:?}", errors);
+            assert_eq!(output, b"2001000\n");
+        });
+    }
+
+    fn assert_return_outside_function(source: &str) {
+        let mut mano = Mano::new();
+        let mut stdout = Vec::new();
+        let errors = mano.run(source, &mut stdout);
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ManoError::Resolution { message, .. } => assert!(message.contains("Toma sem fita")),
+            other => panic!("Expected Resolution error, got {:?}", other),
+        }
+        assert!(stdout.is_empty(), "nothing should run");
+    }
+
+    #[test]
+    fn run_rejects_return_at_top_level() {
+        assert_return_outside_function("salve 1; toma 2;");
+    }
+
+    #[test]
+    fn run_rejects_return_in_top_level_block() {
+        assert_return_outside_function("salve 1; { toma; }");
+    }
+
+    #[test]
+    fn run_rejects_return_in_top_level_control_flow() {
+        assert_return_outside_function("sePá (firmeza) { segueOFluxo (treta) toma 1; }");
+    }
+
+    #[test]
+    fn run_allows_return_in_function_declared_in_block() {
+        let mut mano = Mano::new();
+        let mut stdout = Vec::new();
+        let errors = mano.run(
+            "{ olhaEssaFita f() { { toma 1; } } salve f(); }",
+            &mut stdout,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(stdout).unwrap(), "1\n");
+    }
+
+    #[test]
+    fn run_rejects_break_in_lambda_inside_loop() {
+        let mut mano = Mano::new();
+        let mut stdout = Vec::new();
+        let source = "segueOFluxo (firmeza) { seLiga f = olhaEssaFita () { saiFora; }; f(); }";
+        let errors = mano.run(source, &mut stdout);
+        assert_eq!(errors.len(), 1);
+        let start = source.find("saiFora").unwrap();
+        match &errors[0] {
+            ManoError::Resolution { message, span } => {
+                assert!(message.contains("saiFora fora de um loop"));
+                assert_eq!(span, &(start..start + "saiFora".len()));
+            }
+            other => panic!("Expected Resolution error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn segue_reto_skips_to_the_next_iteration() {
+        let source =
+            "seVira (seLiga i = 0; i < 5; i = i + 1) { sePá (i % 2 == 0) segueReto; salve i; }
+                      seLiga j = 0;
+                      segueOFluxo (j < 3) { j = j + 1; sePá (j == 2) segueReto; salve j * 10; }";
+        let mut output = Vec::new();
+        let errors = Mano::new().run(source, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n3\n10\n30\n");
+    }
+
+    #[test]
+    fn segue_reto_outside_a_loop_is_an_error() {
+        let source = "segueOFluxo (firmeza) { seLiga f = olhaEssaFita () { segueReto; }; f(); }";
+        let errors = Mano::new().run(source, Vec::new());
+        let start = source.find("segueReto").unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [ManoError::Resolution { message, span }]
+                if message.contains("segueReto fora de um loop")
+                    && span == &(start..start + "segueReto".len())
+        ));
+    }
+
+    #[test]
+    fn fitas_keep_working_in_later_runs() {
+        let mut mano = Mano::new();
+        let errors = mano.run("olhaEssaFita dobro(n) { toma n * 2; }", Vec::new());
+        assert!(errors.is_empty());
+        let mut stdout = Vec::new();
+        // `n` was resolved by the first run, not this one
+        let errors = mano.run("seLiga x = 1; salve dobro(21) + x;", &mut stdout);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(stdout).unwrap(), "43\n");
+    }
+
     #[test]
     fn initializer_name_is_bora() {
         assert_eq!(INITIALIZER_NAME, "bora");
@@ -268,7 +1711,42 @@ mod tests {
         // Two vars with same name in same scope (use underscore prefix to avoid unused var error)
         let errors = mano.run("{ seLiga _x = 1; seLiga _x = 2; }", &mut stdout);
         assert_eq!(errors.len(), 1);
-        assert!(matches!(&errors[0], ManoError::Resolution { .. }));
+        assert!(matches!(errors[0].primary(), ManoError::Resolution { .. }));
+    }
+
+    #[test]
+    fn redeclared_fita_replaces_the_first() {
+        let mut mano = Mano::new();
+        let mut stdout = Vec::new();
+        let code =
+            "olhaEssaFita f() { toma 1; } salve f(); olhaEssaFita f() { toma 2; } salve f();";
+        let errors = mano.run(code, &mut stdout);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(stdout).unwrap(), "1\n2\n");
+    }
+
+    #[test]
+    fn strict_mode_refuses_redeclarations_in_one_run() {
+        let mut mano = Mano::new();
+        mano.set_strict(true);
+        let mut stdout = Vec::new();
+        let errors = mano.run(
+            "bagulho B {} salve 1; bagulho B { oi() { toma 2; } }",
+            &mut stdout,
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].primary(), ManoError::Warning { .. }));
+        assert!(stdout.is_empty());
+
+        // Like the REPL, a later run declaring it again replaces it
+        let errors = mano.run("bagulho B {}", &mut stdout);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let errors = mano.run(
+            "bagulho B { oi() { toma 2; } } salve B().oi();",
+            &mut stdout,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(String::from_utf8(stdout).unwrap(), "2\n");
     }
 
     // === closure semantics tests ===
@@ -430,6 +1908,22 @@ mod tests {
         assert_eq!(output.trim(), "<parada Carro>");
     }
 
+    #[test]
+    fn arity_errors_point_at_the_declaration() {
+        let mut mano = Mano::new();
+        let code =
+            "olhaEssaFita soma(a, b) { toma a + b; }\nsoma(1);\nbagulho P { bora(_x) {} }\nP();";
+        let errors = mano.run(code, &mut Vec::new());
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0].primary(), ManoError::Runtime { .. }));
+        assert_eq!(errors[0].related()[0].span, 13..17);
+        assert_eq!(&code[errors[1].related()[0].span.clone()], "bora");
+
+        // Declared in an earlier run, whose spans point into other code
+        let errors = mano.run("soma(1, 2, 3);", &mut Vec::new());
+        assert!(matches!(errors[0], ManoError::Runtime { .. }));
+    }
+
     #[test]
     fn instance_creation_with_arguments_errors() {
         let mut mano = Mano::new();
@@ -762,7 +2256,7 @@ mod tests {
         let errors = mano.run(code, &mut stdout);
         assert!(!errors.is_empty());
         assert!(errors.iter().any(
-            |e| matches!(e, ManoError::Runtime { message, .. } if message.contains("não existe no mestre"))
+            |e| matches!(e.primary(), ManoError::Runtime { message, .. } if message.contains("não existe no mestre"))
         ));
     }
 }
@@ -0,0 +1,438 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::scanner::is_identifier_char;
+
+/// A zero-based line and column pair, ordered by position in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// Where a span sits in a named source: zero-based line and byte column, and
+/// its length in bytes.
+///
+/// Displays as `name:line:col`, counting from 1 like compilers do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub name: Option<String>,
+    pub line: u32,
+    pub col: u32,
+    pub len: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{}:", name)?;
+        }
+        write!(f, "{}:{}", self.line + 1, self.col + 1)
+    }
+}
+
+/// Maps byte offsets to line/column positions and back.
+///
+/// Built once per source so tools (error reports, the LSP) don't each rescan
+/// for newlines. Columns come in two flavours: bytes, and UTF-16 code units as
+/// required by the LSP.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    name: Option<&'a str>,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            name: None,
+            line_starts,
+        }
+    }
+
+    /// Names the source, usually its file path, for [`Self::locate`]
+    pub fn named(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn name(&self) -> Option<&'a str> {
+        self.name
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte offset where a line starts
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+
+    /// Text of a line without its trailing newline
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line)?;
+        Some(self.source[start..self.line_end(line)].trim_end_matches('\r'))
+    }
+
+    /// Line and byte column of a byte offset (clamped to the source)
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = self.clamp(offset);
+        let line = self.line_of(offset);
+        LineCol {
+            line: line as u32,
+            col: (offset - self.line_starts[line]) as u32,
+        }
+    }
+
+    /// Line and UTF-16 column of a byte offset (clamped to the source)
+    pub fn line_col_utf16(&self, offset: usize) -> LineCol {
+        let offset = self.clamp(offset);
+        let line = self.line_of(offset);
+        let col = self.source[self.line_starts[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        LineCol {
+            line: line as u32,
+            col: col as u32,
+        }
+    }
+
+    /// Byte offset of a line and byte column; out-of-range positions are clamped
+    pub fn offset(&self, pos: LineCol) -> usize {
+        let Some(&start) = self.line_starts.get(pos.line as usize) else {
+            return self.source.len();
+        };
+        let end = self.line_end(pos.line as usize);
+        self.clamp((start + pos.col as usize).min(end))
+    }
+
+    /// Byte offset of a line and UTF-16 column; out-of-range positions are clamped
+    pub fn offset_utf16(&self, pos: LineCol) -> usize {
+        let Some(&start) = self.line_starts.get(pos.line as usize) else {
+            return self.source.len();
+        };
+        let end = self.line_end(pos.line as usize);
+        let mut units = 0;
+        for (i, c) in self.source[start..end].char_indices() {
+            if units >= pos.col as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+
+    /// Number of chars before a byte offset, for tools that index by char
+    pub fn char_offset(&self, offset: usize) -> usize {
+        self.source[..self.clamp(offset)].chars().count()
+    }
+
+    /// Converts a byte span into a char span
+    pub fn char_span(&self, span: &Range<usize>) -> Range<usize> {
+        self.char_offset(span.start)..self.char_offset(span.end)
+    }
+
+    /// Where a byte span starts, and how long it is once clamped to the source
+    pub fn locate(&self, span: &Range<usize>) -> SourceLocation {
+        let start = self.clamp(span.start);
+        let LineCol { line, col } = self.line_col(start);
+        SourceLocation {
+            name: self.name.map(str::to_string),
+            line,
+            col,
+            len: self.clamp(span.end).saturating_sub(start),
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// End of a line's content, excluding the newline
+    fn line_end(&self, line: usize) -> usize {
+        self.line_starts
+            .get(line + 1)
+            .map_or(self.source.len(), |next| next - 1)
+    }
+
+    fn clamp(&self, offset: usize) -> usize {
+        snap(self.source, offset)
+    }
+}
+
+/// Byte span of the identifier around an offset, empty when there is none.
+///
+/// The offset is clamped to the source and snapped back to a char boundary,
+/// so the span is always safe to slice with.
+pub fn word_at(source: &str, offset: usize) -> Range<usize> {
+    let offset = snap(source, offset);
+    let start = source[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_identifier_char(c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = source[offset..]
+        .char_indices()
+        .find(|&(_, c)| !is_identifier_char(c))
+        .map_or(source.len(), |(i, _)| offset + i);
+    start..end
+}
+
+/// Clamps to the source and snaps back to a char boundary
+fn snap(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lc(line: u32, col: u32) -> LineCol {
+        LineCol { line, col }
+    }
+
+    #[test]
+    fn empty_source_has_one_line() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line_col(0), lc(0, 0));
+    }
+
+    #[test]
+    fn offset_in_first_line() {
+        let index = LineIndex::new("hello world");
+        assert_eq!(index.line_col(6), lc(0, 6));
+    }
+
+    #[test]
+    fn offset_after_newline_is_next_line() {
+        let index = LineIndex::new("hello\nworld");
+        assert_eq!(index.line_col(5), lc(0, 5));
+        assert_eq!(index.line_col(6), lc(1, 0));
+        assert_eq!(index.line_col(8), lc(1, 2));
+    }
+
+    #[test]
+    fn offset_past_end_is_clamped() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.line_col(100), lc(1, 2));
+    }
+
+    #[test]
+    fn trailing_newline_starts_empty_line() {
+        let index = LineIndex::new("salve 1;\n");
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.line_col(9), lc(1, 0));
+        assert_eq!(index.line(1), Some(""));
+    }
+
+    #[test]
+    fn line_returns_text_without_newline() {
+        let index = LineIndex::new("um\r\ndois\ntrês");
+        assert_eq!(index.line(0), Some("um"));
+        assert_eq!(index.line(1), Some("dois"));
+        assert_eq!(index.line(2), Some("três"));
+        assert_eq!(index.line(3), None);
+    }
+
+    #[test]
+    fn line_start_returns_byte_offset() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.line_start(1), Some(3));
+        assert_eq!(index.line_start(2), None);
+    }
+
+    #[test]
+    fn utf16_column_counts_code_units() {
+        // 'é' is 2 bytes / 1 unit, '🔥' is 4 bytes / 2 units
+        let index = LineIndex::new("é🔥x");
+        assert_eq!(index.line_col(6), lc(0, 6));
+        assert_eq!(index.line_col_utf16(6), lc(0, 3));
+    }
+
+    #[test]
+    fn offset_inside_char_snaps_to_boundary() {
+        let index = LineIndex::new("é");
+        assert_eq!(index.line_col(1), lc(0, 0));
+    }
+
+    #[test]
+    fn offset_round_trips_byte_column() {
+        let index = LineIndex::new("hello\nworld");
+        assert_eq!(index.offset(lc(1, 2)), 8);
+        assert_eq!(index.offset(lc(1, 99)), 11);
+        assert_eq!(index.offset(lc(9, 0)), 11);
+    }
+
+    #[test]
+    fn offset_utf16_round_trips() {
+        let source = "seLiga 🔥 = 1;\nsalve 🔥;";
+        let index = LineIndex::new(source);
+        for (offset, _) in source.char_indices() {
+            assert_eq!(index.offset_utf16(index.line_col_utf16(offset)), offset);
+        }
+    }
+
+    #[test]
+    fn offset_utf16_past_line_end_stops_at_newline() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.offset_utf16(lc(0, 10)), 2);
+    }
+
+    #[test]
+    fn char_span_converts_bytes_to_chars() {
+        let index = LineIndex::new("é = 1");
+        assert_eq!(index.char_span(&(2..3)), 1..2);
+    }
+
+    #[test]
+    fn locate_names_the_source_and_clamps_the_span() {
+        let index = LineIndex::new("salve 1;\nsalve x;").named("oi.mano");
+        let location = index.locate(&(15..16));
+        assert_eq!(
+            location,
+            SourceLocation {
+                name: Some("oi.mano".to_string()),
+                line: 1,
+                col: 6,
+                len: 1,
+            }
+        );
+        assert_eq!(location.to_string(), "oi.mano:2:7");
+        assert_eq!(index.locate(&(15..99)).len, 2);
+        assert_eq!(LineIndex::new("x").locate(&(0..1)).to_string(), "1:1");
+    }
+
+    #[test]
+    fn word_at_finds_identifier_around_offset() {
+        let source = "salve meuNome🔥;";
+        assert_eq!(word_at(source, 8), 6..17);
+        assert_eq!(word_at(source, 17), 6..17);
+        assert_eq!(word_at(source, 15), 6..17);
+        assert_eq!(word_at("a + b", 2), 2..2);
+        assert_eq!(word_at("ab", 99), 0..2);
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Sources mixing ASCII, multi-byte chars, astral chars and line breaks
+        fn source() -> impl Strategy<Value = String> {
+            proptest::collection::vec(
+                prop_oneof![
+                    Just("a"),
+                    Just("_"),
+                    Just(" "),
+                    Just(";"),
+                    Just("\n"),
+                    Just("\r\n"),
+                    Just("é"),
+                    Just("e\u{301}"),
+                    Just("ã"),
+                    Just("🔥"),
+                    Just("𝔸"),
+                ],
+                0..40,
+            )
+            .prop_map(|parts| parts.concat())
+        }
+
+        fn boundaries(source: &str) -> Vec<usize> {
+            source
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([source.len()])
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn byte_columns_round_trip(source in source()) {
+                let index = LineIndex::new(&source);
+                for offset in boundaries(&source) {
+                    prop_assert_eq!(index.offset(index.line_col(offset)), offset);
+                }
+            }
+
+            #[test]
+            fn utf16_columns_round_trip(source in source()) {
+                let index = LineIndex::new(&source);
+                for offset in boundaries(&source) {
+                    prop_assert_eq!(index.offset_utf16(index.line_col_utf16(offset)), offset);
+                }
+            }
+
+            #[test]
+            fn positions_grow_with_offsets(source in source(), a in 0usize..200, b in 0usize..200) {
+                let index = LineIndex::new(&source);
+                let (low, high) = (a.min(b), a.max(b));
+                prop_assert!(index.line_col(low) <= index.line_col(high));
+                prop_assert!(index.line_col_utf16(low) <= index.line_col_utf16(high));
+                prop_assert!(index.char_offset(low) <= index.char_offset(high));
+            }
+
+            #[test]
+            fn any_position_maps_to_a_char_boundary(
+                source in source(),
+                line in 0u32..12,
+                col in 0u32..60,
+            ) {
+                let index = LineIndex::new(&source);
+                let pos = LineCol { line, col };
+                for offset in [index.offset(pos), index.offset_utf16(pos)] {
+                    prop_assert!(offset <= source.len());
+                    prop_assert!(source.is_char_boundary(offset));
+                }
+            }
+
+            #[test]
+            fn utf16_columns_grow_along_a_line(source in source(), line in 0u32..12, col in 0u32..60) {
+                let index = LineIndex::new(&source);
+                let here = index.offset_utf16(LineCol { line, col });
+                let next = index.offset_utf16(LineCol { line, col: col + 1 });
+                prop_assert!(here <= next);
+            }
+
+            #[test]
+            fn char_spans_count_chars(source in source(), a in 0usize..200, b in 0usize..200) {
+                let index = LineIndex::new(&source);
+                let span = index.char_span(&(a.min(b)..a.max(b)));
+                let start = snap(&source, a.min(b));
+                let end = snap(&source, a.max(b));
+                prop_assert_eq!(span.start, source[..start].chars().count());
+                prop_assert_eq!(span.len(), source[start..end].chars().count());
+            }
+
+            #[test]
+            fn word_at_spans_only_identifier_chars(source in source(), offset in 0usize..200) {
+                let span = word_at(&source, offset);
+                let offset = snap(&source, offset);
+                prop_assert!(span.start <= offset && offset <= span.end);
+                prop_assert!(source[span.clone()].chars().all(is_identifier_char));
+                if let Some(c) = source[..span.start].chars().next_back() {
+                    prop_assert!(!is_identifier_char(c));
+                }
+                if let Some(c) = source[span.end..].chars().next() {
+                    prop_assert!(!is_identifier_char(c));
+                }
+            }
+        }
+    }
+}
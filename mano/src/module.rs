@@ -0,0 +1,273 @@
+//! Modules: other files a script runs with `chama`, each one only once
+
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::ManoError;
+use crate::line_index::LineIndex;
+
+/// Where `mano pacote` installs a project's packages
+const PACKAGES_DIR: &str = ".mano_modules";
+
+/// The modules an interpreter ran, and the chain of the ones running now
+#[derive(Debug, Default)]
+pub(crate) struct Modules {
+    /// Where the main script's `chama` paths start from, the working
+    /// directory when `None`
+    base: Option<PathBuf>,
+    /// Every module that ran or is running, by canonical path
+    loaded: HashSet<PathBuf>,
+    /// The modules running one inside the other, the innermost last
+    loading: Vec<PathBuf>,
+}
+
+/// What `chama` has to do with a module
+#[derive(Debug, PartialEq)]
+pub(crate) enum Load {
+    /// Run it, it's the first time
+    Run(PathBuf),
+    /// Nothing, it already ran
+    Cached,
+}
+
+impl Modules {
+    pub fn set_base(&mut self, dir: &Path) {
+        self.base = Some(dir.to_path_buf());
+    }
+
+    /// The file a `chama` names, relative to the file it's in
+    pub fn locate(&self, path: &str) -> io::Result<PathBuf> {
+        let dir = match self.loading.last() {
+            Some(module) => module.parent(),
+            None => self.base.as_deref(),
+        };
+        locate_module(dir, path)
+    }
+
+    /// Starts running `file` unless it already ran. A module that's still
+    /// running would never finish, so that's an error with the chain of
+    /// modules that got back to it.
+    pub fn enter(&mut self, file: PathBuf) -> Result<Load, String> {
+        if let Some(start) = self.loading.iter().position(|module| *module == file) {
+            let chain: Vec<String> = self.loading[start..]
+                .iter()
+                .chain([&file])
+                .map(|module| file_name(module))
+                .collect();
+            return Err(format!(
+                "Um módulo chama o outro em círculo, mano: {}",
+                chain.join(" → ")
+            ));
+        }
+        if !self.loaded.insert(file.clone()) {
+            return Ok(Load::Cached);
+        }
+        self.loading.push(file.clone());
+        Ok(Load::Run(file))
+    }
+
+    /// Done running the innermost module. One that failed is forgotten, so
+    /// the next `chama` tries it again.
+    pub fn leave(&mut self, failed: bool) {
+        if let Some(file) = self.loading.pop()
+            && failed
+        {
+            self.loaded.remove(&file);
+        }
+    }
+}
+
+/// The file `chama "path"` loads from a file in `dir`, the working directory
+/// when `None`: `path` relative to `dir`, with `.mano` added when it has no
+/// extension. A path that isn't there and doesn't start with `.` or `/` is
+/// looked up as a package, in the `.mano_modules` of `dir` or of the nearest
+/// folder above it, where `chama "fitas"` loads `fitas/fitas.mano`.
+pub fn locate_module(dir: Option<&Path>, path: &str) -> io::Result<PathBuf> {
+    let dir = dir.unwrap_or(Path::new(""));
+    let not_found = match with_extension(dir.join(path)).canonicalize() {
+        Ok(file) => return Ok(file),
+        Err(e) => e,
+    };
+    if !matches!(
+        Path::new(path).components().next(),
+        Some(Component::Normal(_))
+    ) {
+        return Err(not_found);
+    }
+    let start = if dir.as_os_str().is_empty() {
+        std::env::current_dir()
+    } else {
+        std::path::absolute(dir)
+    };
+    let Ok(start) = start else {
+        return Err(not_found);
+    };
+    start
+        .ancestors()
+        .find_map(|folder| {
+            let mut package = folder.join(PACKAGES_DIR).join(path);
+            if package.is_dir() {
+                let name = package.file_name()?.to_owned();
+                package.push(name);
+            }
+            with_extension(package).canonicalize().ok()
+        })
+        .ok_or(not_found)
+}
+
+fn with_extension(mut file: PathBuf) -> PathBuf {
+    if file.extension().is_none() {
+        file.set_extension("mano");
+    }
+    file
+}
+
+/// How errors name a module
+pub(crate) fn file_name(file: &Path) -> String {
+    file.file_name()
+        .unwrap_or(file.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// An error from inside a module, moved to the `chama` at `span` since its
+/// own span points into the module's source
+pub(crate) fn in_module(
+    error: ManoError,
+    name: &str,
+    source: &str,
+    span: Range<usize>,
+) -> ManoError {
+    match error {
+        // Still a Ctrl+C or a closed output, only somewhere else
+        ManoError::Interrupted { .. } => return ManoError::Interrupted { span },
+        ManoError::Output { source: cause, .. } => {
+            return ManoError::Output {
+                source: cause,
+                span,
+            };
+        }
+        _ => {}
+    }
+    let error = error.uncaught();
+    let location = error
+        .locate(&LineIndex::new(source).named(name))
+        .map_or_else(|| name.to_string(), |location| location.to_string());
+    let message = match error.primary() {
+        ManoError::Scan { message, .. }
+        | ManoError::Parse { message, .. }
+        | ManoError::Runtime { message, .. }
+        | ManoError::Resolution { message, .. }
+        | ManoError::Warning { message, .. } => message.clone(),
+        other => other.to_string(),
+    };
+    ManoError::Runtime {
+        message: format!("Deu ruim no módulo {}: {}", location, message),
+        span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("util.mano"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn locate_adds_the_extension_and_starts_from_the_base() {
+        let dir = dir();
+        let mut modules = Modules::default();
+        modules.set_base(dir.path());
+
+        let file = modules.locate("util").unwrap();
+        assert_eq!(file, dir.path().join("util.mano").canonicalize().unwrap());
+        assert!(modules.locate("nada").is_err());
+    }
+
+    #[test]
+    fn locate_falls_back_to_installed_packages() {
+        let dir = dir();
+        let fitas = dir.path().join(".mano_modules/fitas");
+        std::fs::create_dir_all(fitas.join("sub")).unwrap();
+        std::fs::write(fitas.join("fitas.mano"), "").unwrap();
+        std::fs::write(fitas.join("sub/oi.mano"), "").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let src = dir.path().join("src");
+
+        let file = locate_module(Some(&src), "fitas").unwrap();
+        assert_eq!(file, fitas.join("fitas.mano").canonicalize().unwrap());
+        let file = locate_module(Some(&src), "fitas/sub/oi").unwrap();
+        assert_eq!(file, fitas.join("sub/oi.mano").canonicalize().unwrap());
+        // Files next to the script win, and explicit paths aren't packages
+        assert_eq!(
+            locate_module(Some(dir.path()), "util").unwrap(),
+            dir.path().join("util.mano").canonicalize().unwrap()
+        );
+        assert!(locate_module(Some(&src), "./fitas").is_err());
+        assert!(locate_module(Some(&src), "nada").is_err());
+    }
+
+    #[test]
+    fn a_module_runs_once() {
+        let dir = dir();
+        let mut modules = Modules::default();
+        modules.set_base(dir.path());
+        let file = modules.locate("util.mano").unwrap();
+
+        assert_eq!(modules.enter(file.clone()), Ok(Load::Run(file.clone())));
+        modules.leave(false);
+        assert_eq!(modules.enter(file), Ok(Load::Cached));
+    }
+
+    #[test]
+    fn a_failed_module_runs_again() {
+        let dir = dir();
+        let mut modules = Modules::default();
+        modules.set_base(dir.path());
+        let file = modules.locate("util.mano").unwrap();
+
+        modules.enter(file.clone()).unwrap();
+        modules.leave(true);
+        assert_eq!(modules.enter(file.clone()), Ok(Load::Run(file)));
+    }
+
+    #[test]
+    fn a_cycle_names_the_modules_in_it() {
+        let dir = dir();
+        std::fs::write(dir.path().join("outro.mano"), "").unwrap();
+        let mut modules = Modules::default();
+        modules.set_base(dir.path());
+        let util = modules.locate("util.mano").unwrap();
+        modules.enter(util.clone()).unwrap();
+        let outro = modules.locate("outro.mano").unwrap();
+        modules.enter(outro).unwrap();
+
+        let err = modules.enter(util).unwrap_err();
+        assert!(
+            err.ends_with("util.mano → outro.mano → util.mano"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn in_module_says_where_in_the_module() {
+        let error = ManoError::Runtime {
+            message: "Vish!".to_string(),
+            span: 9..10,
+        };
+        let error = in_module(error, "util.mano", "salve 1;\nx;", 6..17);
+
+        assert!(matches!(
+            error,
+            ManoError::Runtime { message, span }
+                if message == "Deu ruim no módulo util.mano:2:1: Vish!" && span == (6..17)
+        ));
+    }
+}
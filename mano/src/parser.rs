@@ -2,11 +2,29 @@ use crate::ast::{Expr, InterpolationPart, Stmt};
 use crate::error::ManoError;
 use crate::token::{Literal, Token, TokenType};
 
+/// How deep statements and expressions can nest before the parser gives up,
+/// well before its recursion could overflow the stack
+pub const MAX_NESTING: usize = 200;
+
+/// Marks a getter whose value is cached on the instance
+const MEMOIZED_MODIFIER: &str = "memoizado";
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     errors: Vec<ManoError>,
-    loop_depth: usize,
+    /// Set when an error was already reported for the current declaration,
+    /// so the errors it causes further along aren't reported again
+    recovering: bool,
+    /// Statements and expressions open around the current token
+    depth: usize,
+    max_nesting: usize,
+    /// Set when the nesting limit was hit, so the blocks and parens left open
+    /// don't each report an error until the top-level declaration ends
+    too_deep: bool,
+    /// Blocks whose `}` hasn't been reached, so recovering from an error
+    /// stops at the end of the block instead of swallowing it
+    open_blocks: usize,
 }
 
 impl Parser {
@@ -15,14 +33,28 @@ impl Parser {
             tokens,
             current: 0,
             errors: Vec::new(),
-            loop_depth: 0,
+            recovering: false,
+            depth: 0,
+            max_nesting: MAX_NESTING,
+            too_deep: false,
+            open_blocks: 0,
         }
     }
 
+    /// Sets how deep code can nest, instead of [`MAX_NESTING`]
+    pub fn with_max_nesting(mut self, max_nesting: usize) -> Self {
+        self.max_nesting = max_nesting;
+        self
+    }
+
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ManoError> {
         let mut statements = Vec::new();
         while !self.is_at_end() {
             if let Some(stmt) = self.declaration() {
+                // What follows code nested too deep is often more of it
+                if !matches!(stmt, Stmt::Error { .. }) {
+                    self.too_deep = false;
+                }
                 statements.push(stmt);
             }
         }
@@ -36,28 +68,43 @@ impl Parser {
     fn declaration(&mut self) -> Option<Stmt> {
         // Check for named function: olhaEssaFita followed by identifier
         // Lambda expressions (olhaEssaFita followed by '(') are handled as expression statements
+        let start = self.peek().span.start;
+        let depth = self.depth;
+        // Declarations nest (fita bodies), so each one tracks its own recovery
+        let enclosing_recovering = std::mem::take(&mut self.recovering);
         let is_named_function = self.check(&TokenType::Fun)
             && self
                 .peek_next()
                 .is_some_and(|t| t.token_type == TokenType::Identifier);
 
-        let result = if is_named_function {
-            self.advance(); // consume 'olhaEssaFita'
-            self.function_declaration()
-        } else if self.match_types(&[TokenType::Class]) {
-            self.class_declaration()
-        } else if self.match_types(&[TokenType::Var]) {
-            self.var_declaration()
-        } else {
-            self.statement()
-        };
+        let result = self.enter().and_then(|()| {
+            if is_named_function {
+                self.advance(); // consume 'olhaEssaFita'
+                self.function_declaration()
+            } else if self.match_types(&[TokenType::Class]) {
+                self.class_declaration()
+            } else if self.match_types(&[TokenType::Enum]) {
+                self.enum_declaration()
+            } else if self.match_types(&[TokenType::Var]) {
+                self.var_declaration()
+            } else {
+                self.statement()
+            }
+        });
+        // Errors skip the code that would have closed what was opened
+        self.depth = depth;
 
+        let recovering = std::mem::replace(&mut self.recovering, enclosing_recovering);
         match result {
             Ok(stmt) => Some(stmt),
             Err(e) => {
-                self.errors.push(e);
+                if !recovering && !self.too_deep {
+                    self.errors.push(e);
+                }
                 self.synchronize();
-                None
+                // Keep a placeholder so tools still see where the broken code was
+                let end = self.previous().span.end.max(start);
+                Some(Stmt::Error { span: start..end })
             }
         }
     }
@@ -77,7 +124,32 @@ impl Parser {
             "Cadê o '(' depois do nome da fita, maluco?",
         )?;
 
+        let (params, is_variadic) = self.parameters()?;
+        self.consume(
+            TokenType::LeftBrace,
+            "Cadê o '{' antes do corpo da fita, tio?",
+        )?;
+
+        let body = self.block_statements()?;
+        let end = self.previous().span.end;
+
+        Ok(Stmt::Function {
+            name,
+            params,
+            body,
+            is_static,
+            is_getter: false,
+            is_memoized: false,
+            is_variadic,
+            span: start..end,
+        })
+    }
+
+    /// The parameters of a fita up to the `)`, and whether the last one is a
+    /// `...nome` that takes the arguments left over
+    fn parameters(&mut self) -> Result<(Vec<Token>, bool), ManoError> {
         let mut params = Vec::new();
+        let mut is_variadic = false;
         if !self.check(&TokenType::RightParen) {
             loop {
                 if params.len() >= 255 {
@@ -86,6 +158,7 @@ impl Parser {
                         span: self.peek().span.clone(),
                     });
                 }
+                is_variadic = self.match_types(&[TokenType::DotDotDot]);
                 params.push(
                     self.consume(TokenType::Identifier, "Cadê o nome do parâmetro, parça?")?
                         .clone(),
@@ -93,6 +166,12 @@ impl Parser {
                 if !self.match_types(&[TokenType::Comma]) {
                     break;
                 }
+                if is_variadic {
+                    self.errors.push(ManoError::Parse {
+                        message: "O parâmetro com '...' tem que ser o último, mano!".to_string(),
+                        span: self.previous().span.clone(),
+                    });
+                }
             }
         }
 
@@ -100,63 +179,42 @@ impl Parser {
             TokenType::RightParen,
             "Cadê o ')' depois dos parâmetros, chapa?",
         )?;
-        self.consume(
-            TokenType::LeftBrace,
-            "Cadê o '{' antes do corpo da fita, tio?",
-        )?;
-
-        let body = self.block_statements()?;
-        let end = self.previous().span.end;
-
-        Ok(Stmt::Function {
-            name,
-            params,
-            body,
-            is_static,
-            is_getter: false,
-            span: start..end,
-        })
+        Ok((params, is_variadic))
     }
 
     /// Parse a method inside a class - can be regular method or getter (no parens)
     fn method(&mut self, start: usize, is_static: bool) -> Result<Stmt, ManoError> {
+        // `memoizado area { ... }` keeps the getter's value until a field
+        // changes. Only a modifier when a name follows, so it's still a
+        // fine method name.
+        let memoizado = (&*self.peek().lexeme == MEMOIZED_MODIFIER
+            && self.peek_next().map(|next| next.token_type) == Some(TokenType::Identifier))
+        .then(|| self.advance().clone());
+
         let name = self
             .consume(TokenType::Identifier, "Cadê o nome da fita, tio?")?
             .clone();
 
         // Check if it's a getter (no parentheses - directly to body)
         let is_getter = self.check(&TokenType::LeftBrace);
+        if let Some(memoizado) = &memoizado
+            && (!is_getter || is_static)
+        {
+            self.errors.push(ManoError::Parse {
+                message: "Só getter de instância pode ser memoizado, mano!".to_string(),
+                span: memoizado.span.clone(),
+            });
+        }
 
-        let mut params = Vec::new();
-        if !is_getter {
+        let (params, is_variadic) = if is_getter {
+            (Vec::new(), false)
+        } else {
             self.consume(
                 TokenType::LeftParen,
                 "Cadê o '(' depois do nome da fita, maluco?",
             )?;
-
-            if !self.check(&TokenType::RightParen) {
-                loop {
-                    if params.len() >= 255 {
-                        self.errors.push(ManoError::Parse {
-                            message: "Não pode ter mais de 255 parâmetros, véi!".to_string(),
-                            span: self.peek().span.clone(),
-                        });
-                    }
-                    params.push(
-                        self.consume(TokenType::Identifier, "Cadê o nome do parâmetro, parça?")?
-                            .clone(),
-                    );
-                    if !self.match_types(&[TokenType::Comma]) {
-                        break;
-                    }
-                }
-            }
-
-            self.consume(
-                TokenType::RightParen,
-                "Cadê o ')' depois dos parâmetros, chapa?",
-            )?;
-        }
+            self.parameters()?
+        };
 
         self.consume(
             TokenType::LeftBrace,
@@ -172,6 +230,8 @@ impl Parser {
             body,
             is_static,
             is_getter,
+            is_memoized: is_getter && !is_static && memoizado.is_some(),
+            is_variadic,
             span: start..end,
         })
     }
@@ -218,14 +278,48 @@ impl Parser {
         })
     }
 
+    fn enum_declaration(&mut self) -> Result<Stmt, ManoError> {
+        let start = self.previous().span.start;
+        let name = self
+            .consume(TokenType::Identifier, "Cadê o nome das opções, tio?")?
+            .clone();
+        self.consume(TokenType::LeftBrace, "Cadê o '{' antes das opções, mano?")?;
+
+        let mut variants = Vec::new();
+        loop {
+            variants.push(
+                self.consume(TokenType::Identifier, "Cadê o nome da opção, tio?")?
+                    .clone(),
+            );
+            // A comma after the last option is fine
+            if !self.match_types(&[TokenType::Comma]) || self.check(&TokenType::RightBrace) {
+                break;
+            }
+        }
+
+        self.consume(
+            TokenType::RightBrace,
+            "Esperava '}' no final das opções, véi!",
+        )?;
+        let end = self.previous().span.end;
+
+        Ok(Stmt::Enum {
+            name,
+            variants,
+            span: start..end,
+        })
+    }
+
     fn block_statements(&mut self) -> Result<Vec<Stmt>, ManoError> {
         let mut statements = Vec::new();
 
+        self.open_blocks += 1;
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
             if let Some(stmt) = self.declaration() {
                 statements.push(stmt);
             }
         }
+        self.open_blocks -= 1;
 
         self.consume(
             TokenType::RightBrace,
@@ -260,35 +354,155 @@ impl Parser {
     }
 
     fn statement(&mut self) -> Result<Stmt, ManoError> {
+        self.enter()?;
+        let statement = self.statement_inner();
+        self.depth -= 1;
+        statement
+    }
+
+    fn statement_inner(&mut self) -> Result<Stmt, ManoError> {
         if self.match_types(&[TokenType::Break]) {
             self.break_statement()
+        } else if self.match_types(&[TokenType::Continue]) {
+            self.continue_statement()
         } else if self.match_types(&[TokenType::For]) {
             self.for_statement()
+        } else if self.match_types(&[TokenType::Import]) {
+            self.import_statement()
         } else if self.match_types(&[TokenType::If]) {
             self.if_statement()
         } else if self.match_types(&[TokenType::Return]) {
             self.return_statement()
+        } else if self.match_types(&[TokenType::Throw]) {
+            self.throw_statement()
+        } else if self.match_types(&[TokenType::Try]) {
+            self.try_statement()
         } else if self.match_types(&[TokenType::While]) {
             self.while_statement()
         } else if self.match_types(&[TokenType::LeftBrace]) {
             self.block()
         } else if self.match_types(&[TokenType::Print]) {
             self.print_statement()
+        } else if self.is_para_loop() {
+            // Parsed as the loop it was meant to be, so the error is the only one
+            let para = self.advance().clone();
+            self.errors.push(
+                ManoError::Parse {
+                    message: "Em mano o loop é seVira, mano! O 'para' só vale no dialeto formal."
+                        .to_string(),
+                    span: para.span,
+                }
+                .with_help("Troca por seVira, ou começa o arquivo com // dialeto: formal"),
+            );
+            self.for_statement()
         } else {
             self.expression_statement()
         }
     }
 
+    /// Whether the code is `para (seLiga ...` or `para (;`, a for loop with
+    /// the formal dialect's keyword, which would only fail as a call to `para`
+    fn is_para_loop(&self) -> bool {
+        self.check(&TokenType::Identifier)
+            && &*self.peek().lexeme == "para"
+            && self
+                .peek_next()
+                .is_some_and(|t| t.token_type == TokenType::LeftParen)
+            && self
+                .tokens
+                .get(self.current + 2)
+                .is_some_and(|t| matches!(t.token_type, TokenType::Var | TokenType::Semicolon))
+    }
+
     fn break_statement(&mut self) -> Result<Stmt, ManoError> {
         let keyword = self.previous().clone();
-        if self.loop_depth == 0 {
-            return Err(ManoError::Parse {
-                message: "Não pode dar saiFora fora de um loop, mano!".to_string(),
-                span: keyword.span,
-            });
-        }
+        let start = keyword.span.start;
         self.consume(TokenType::Semicolon, "Cadê o ';' depois do saiFora, véi?")?;
-        Ok(Stmt::Break { span: 0..0 })
+        let end = self.previous().span.end;
+        Ok(Stmt::Break {
+            keyword,
+            span: start..end,
+        })
+    }
+
+    fn continue_statement(&mut self) -> Result<Stmt, ManoError> {
+        let keyword = self.previous().clone();
+        let start = keyword.span.start;
+        self.consume(TokenType::Semicolon, "Cadê o ';' depois do segueReto, véi?")?;
+        let end = self.previous().span.end;
+        Ok(Stmt::Continue {
+            keyword,
+            span: start..end,
+        })
+    }
+
+    fn import_statement(&mut self) -> Result<Stmt, ManoError> {
+        let keyword = self.previous().clone();
+        let start = keyword.span.start;
+        let path = self
+            .consume(
+                TokenType::String,
+                "Cadê o caminho do arquivo depois do chama, mano? Tipo chama \"util.mano\";",
+            )?
+            .clone();
+        self.consume(TokenType::Semicolon, "Cadê o ';' depois do chama, véi?")?;
+        let end = self.previous().span.end;
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            span: start..end,
+        })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, ManoError> {
+        let keyword = self.previous().clone();
+        let start = keyword.span.start;
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Cadê o ';' depois do joga, véi?")?;
+        let end = self.previous().span.end;
+        Ok(Stmt::Throw {
+            keyword,
+            value,
+            span: start..end,
+        })
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, ManoError> {
+        let start = self.previous().span.start;
+        self.consume(TokenType::LeftBrace, "Cadê o '{' depois do tentaAi, mano?")?;
+        let body = Box::new(self.block()?);
+
+        self.consume(
+            TokenType::Catch,
+            "Cadê o seDeuRuim depois do tentaAi, mano? Tipo seDeuRuim (erro) { ... }",
+        )?;
+        self.consume(
+            TokenType::LeftParen,
+            "Cadê o '(' depois do seDeuRuim, mano?",
+        )?;
+        let name = self
+            .consume(
+                TokenType::Identifier,
+                "Cadê o nome do erro no seDeuRuim, parça?",
+            )?
+            .clone();
+        self.consume(
+            TokenType::RightParen,
+            "Cadê o ')' depois do nome do erro, véi?",
+        )?;
+        self.consume(
+            TokenType::LeftBrace,
+            "Cadê o '{' depois do seDeuRuim, mano?",
+        )?;
+        let handler = Box::new(self.block()?);
+        let end = self.previous().span.end;
+
+        Ok(Stmt::Try {
+            body,
+            name,
+            handler,
+            span: start..end,
+        })
     }
 
     fn return_statement(&mut self) -> Result<Stmt, ManoError> {
@@ -344,31 +558,20 @@ impl Parser {
         };
         self.consume(TokenType::RightParen, "Cadê o ')' depois do seVira, mano?")?;
 
-        // Body (inside loop context for break)
-        self.loop_depth += 1;
-        let body_result = self.statement();
-        self.loop_depth -= 1;
-        let mut body = body_result?;
+        let body = self.statement()?;
         let end = self.previous().span.end;
 
-        // Desugar: add increment to end of body
-        if let Some(inc) = increment {
-            body = Stmt::Block {
-                statements: vec![
-                    body,
-                    Stmt::Expression {
-                        expression: inc,
-                        span: 0..0,
-                    },
-                ],
+        // Desugar: wrap in while, which runs the increment after the body
+        let increment = increment.map(|inc| {
+            Box::new(Stmt::Expression {
+                expression: inc,
                 span: 0..0,
-            };
-        }
-
-        // Desugar: wrap in while
-        body = Stmt::While {
+            })
+        });
+        let mut body = Stmt::While {
             condition,
             body: Box::new(body),
+            increment,
             span: 0..0,
         };
 
@@ -392,15 +595,13 @@ impl Parser {
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, "Cadê o ')' depois da condição, véi?")?;
 
-        self.loop_depth += 1;
-        let body_result = self.statement();
-        self.loop_depth -= 1;
-        let body = Box::new(body_result?);
+        let body = Box::new(self.statement()?);
         let end = self.previous().span.end;
 
         Ok(Stmt::While {
             condition,
             body,
+            increment: None,
             span: start..end,
         })
     }
@@ -437,11 +638,13 @@ impl Parser {
         let start = self.previous().span.start;
         let mut statements = Vec::new();
 
+        self.open_blocks += 1;
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
             if let Some(stmt) = self.declaration() {
                 statements.push(stmt);
             }
         }
+        self.open_blocks -= 1;
 
         let closing = self.consume(
             TokenType::RightBrace,
@@ -477,19 +680,33 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, ManoError> {
-        self.assignment()
+        self.enter()?;
+        let expr = self.assignment();
+        self.depth -= 1;
+        expr
     }
 
     fn assignment(&mut self) -> Result<Expr, ManoError> {
         let expr = self.comma()?;
 
-        if self.match_types(&[TokenType::Equal]) {
+        if self.match_types(&[
+            TokenType::Equal,
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
             let equals = self.previous().clone();
+            let operator = compound_operator(&equals);
+            self.enter()?;
             let value = self.assignment()?;
+            self.depth -= 1;
 
             if let Expr::Variable { name } = expr {
                 return Ok(Expr::Assign {
                     name,
+                    operator,
                     value: Box::new(value),
                 });
             }
@@ -498,6 +715,22 @@ impl Parser {
                 return Ok(Expr::Set {
                     object,
                     name,
+                    operator,
+                    value: Box::new(value),
+                });
+            }
+
+            if let Expr::Index {
+                object,
+                bracket,
+                index,
+            } = expr
+            {
+                return Ok(Expr::IndexSet {
+                    object,
+                    bracket,
+                    index,
+                    operator,
                     value: Box::new(value),
                 });
             }
@@ -533,7 +766,9 @@ impl Parser {
         if self.match_types(&[TokenType::Question]) {
             let then_branch = self.expression()?;
             self.consume(TokenType::Colon, "Cadê o ':' do ternário, chapa?")?;
+            self.enter()?;
             let else_branch = self.ternary()?;
+            self.depth -= 1;
             return Ok(Expr::Ternary {
                 condition: Box::new(expr),
                 then_branch: Box::new(then_branch),
@@ -593,7 +828,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expr, ManoError> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while self.match_types(&[
             TokenType::Greater,
@@ -601,6 +836,72 @@ impl Parser {
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
+            let operator = self.previous().clone();
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // Bitwise operators bind tighter than comparisons, so `x & 1 == 0`
+    // needs no parentheses: | below ^ below & below << >>
+    fn bit_or(&mut self) -> Result<Expr, ManoError> {
+        let mut expr = self.bit_xor()?;
+
+        while self.match_types(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expr, ManoError> {
+        let mut expr = self.bit_and()?;
+
+        while self.match_types(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, ManoError> {
+        let mut expr = self.shift()?;
+
+        while self.match_types(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expr, ManoError> {
+        let mut expr = self.term()?;
+
+        while self.match_types(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous().clone();
             let right = self.term()?;
             expr = Expr::Binary {
@@ -648,7 +949,9 @@ impl Parser {
     fn unary(&mut self) -> Result<Expr, ManoError> {
         if self.match_types(&[TokenType::Bang, TokenType::Minus]) {
             let operator = self.previous().clone();
+            self.enter()?;
             let right = self.unary()?;
+            self.depth -= 1;
             return Ok(Expr::Unary {
                 operator,
                 right: Box::new(right),
@@ -674,6 +977,21 @@ impl Parser {
                     object: Box::new(expr),
                     name,
                 };
+            } else if self.match_types(&[TokenType::LeftBracket]) {
+                self.enter()?;
+                let index = self.ternary()?;
+                self.depth -= 1;
+                let bracket = self
+                    .consume(
+                        TokenType::RightBracket,
+                        "Cadê o ']' depois do índice, mano?",
+                    )?
+                    .clone();
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -693,7 +1011,9 @@ impl Parser {
                         span: self.peek().span.clone(),
                     });
                 }
+                self.enter()?;
                 arguments.push(self.ternary()?);
+                self.depth -= 1;
                 if !self.match_types(&[TokenType::Comma]) {
                     break;
                 }
@@ -714,6 +1034,44 @@ impl Parser {
         })
     }
 
+    /// The items of a `[1, 2, 3]` after its `[`; a trailing comma is fine
+    fn list_literal(&mut self) -> Result<Expr, ManoError> {
+        let mut elements = Vec::new();
+
+        while !self.check(&TokenType::RightBracket) {
+            self.enter()?;
+            elements.push(self.ternary()?);
+            self.depth -= 1;
+            if !self.match_types(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        let bracket = self
+            .consume(TokenType::RightBracket, "Cadê o ']' da lista, chapa?")?
+            .clone();
+        Ok(Expr::ListLiteral { bracket, elements })
+    }
+
+    /// Opens one more level of nesting, failing past the limit
+    fn enter(&mut self) -> Result<(), ManoError> {
+        if self.depth < self.max_nesting {
+            self.depth += 1;
+            return Ok(());
+        }
+        let span = self.peek().span.clone();
+        let error = || ManoError::Parse {
+            message: "Expressão aninhada demais, mano!".to_string(),
+            span: span.clone(),
+        };
+        // Reported once here; the declarations it unwinds through stay quiet
+        if !self.too_deep {
+            self.too_deep = true;
+            self.errors.push(error());
+        }
+        Err(error())
+    }
+
     fn match_types(&mut self, types: &[TokenType]) -> bool {
         for t in types {
             if self.check(t) {
@@ -745,12 +1103,16 @@ impl Parser {
                     value: Literal::Nil,
                 })
             }
-            TokenType::Number | TokenType::String => {
+            TokenType::Number | TokenType::String | TokenType::Bytes => {
                 let value = token.literal.clone().unwrap();
                 self.advance();
                 Ok(Expr::Literal { value })
             }
             TokenType::StringStart => self.interpolated_string(),
+            TokenType::LeftBracket => {
+                self.advance();
+                self.list_literal()
+            }
             TokenType::LeftParen => {
                 self.advance();
                 let expr = self.expression()?;
@@ -785,10 +1147,19 @@ impl Parser {
                     .clone();
                 Ok(Expr::Super { keyword, method })
             }
-            _ => Err(ManoError::Parse {
-                message: "Cadê a expressão, jão?".to_string(),
-                span: token.span.clone(),
-            }),
+            _ => {
+                // Report and keep going with a placeholder, leaving the token
+                // for the caller (`* 3` still parses as a binary expression)
+                let span = token.span.clone();
+                if !self.recovering {
+                    self.errors.push(ManoError::Parse {
+                        message: "Cadê a expressão, jão?".to_string(),
+                        span: span.clone(),
+                    });
+                    self.recovering = true;
+                }
+                Ok(Expr::Error { span })
+            }
         }
     }
 
@@ -883,22 +1254,33 @@ impl Parser {
     }
 
     fn synchronize(&mut self) {
+        // The `}` of the block we're in ends the broken statement too, unless
+        // the blocks are nested too deep to be worth keeping
+        let closes_block = |parser: &Self| {
+            parser.open_blocks > 0 && !parser.too_deep && parser.check(&TokenType::RightBrace)
+        };
+        if closes_block(self) {
+            return;
+        }
         self.advance();
 
         while !self.is_at_end() {
-            if self.previous().token_type == TokenType::Semicolon {
+            if self.previous().token_type == TokenType::Semicolon || closes_block(self) {
                 return;
             }
 
             match self.peek().token_type {
                 TokenType::Class
+                | TokenType::Enum
                 | TokenType::Fun
                 | TokenType::Var
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => return,
+                | TokenType::Return
+                | TokenType::Try
+                | TokenType::Throw => return,
                 _ => {}
             }
 
@@ -943,6 +1325,25 @@ impl Parser {
     }
 }
 
+/// The binary operator a compound assignment like `+=` applies, spanning the
+/// whole `+=`; `None` for a plain `=`
+fn compound_operator(token: &Token) -> Option<Token> {
+    let token_type = match token.token_type {
+        TokenType::PlusEqual => TokenType::Plus,
+        TokenType::MinusEqual => TokenType::Minus,
+        TokenType::StarEqual => TokenType::Star,
+        TokenType::SlashEqual => TokenType::Slash,
+        TokenType::PercentEqual => TokenType::Percent,
+        _ => return None,
+    };
+    Some(Token {
+        token_type,
+        lexeme: token.lexeme[..1].into(),
+        literal: None,
+        span: token.span.clone(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -952,7 +1353,7 @@ mod tests {
     fn make_token(token_type: TokenType, lexeme: &str, literal: Option<Literal>) -> Token {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             literal,
             span: 0..lexeme.len(),
         }
@@ -1525,7 +1926,7 @@ mod tests {
             Stmt::Var {
                 name, initializer, ..
             } => {
-                assert_eq!(name.lexeme, "x");
+                assert_eq!(&*name.lexeme, "x");
                 assert!(initializer.is_some());
             }
             _ => panic!("expected Var statement"),
@@ -1548,7 +1949,7 @@ mod tests {
             Stmt::Var {
                 name, initializer, ..
             } => {
-                assert_eq!(name.lexeme, "x");
+                assert_eq!(&*name.lexeme, "x");
                 assert!(initializer.is_none());
             }
             _ => panic!("expected Var statement"),
@@ -1572,7 +1973,7 @@ mod tests {
         assert_eq!(stmts.len(), 1);
         match &stmts[0] {
             Stmt::Expression { expression, .. } => {
-                assert!(matches!(expression, Expr::Assign { name, .. } if name.lexeme == "x"));
+                assert!(matches!(expression, Expr::Assign { name, .. } if &*name.lexeme == "x"));
             }
             _ => panic!("expected expression statement"),
         }
@@ -1601,7 +2002,7 @@ mod tests {
             Stmt::Expression { expression, .. } => {
                 assert!(matches!(
                     expression,
-                    Expr::Set { name, .. } if name.lexeme == "nome"
+                    Expr::Set { name, .. } if &*name.lexeme == "nome"
                 ));
             }
             _ => panic!("expected expression statement"),
@@ -1634,11 +2035,11 @@ mod tests {
                 // Outer Set (cidade)
                 match expression {
                     Expr::Set { object, name, .. } => {
-                        assert_eq!(name.lexeme, "cidade");
+                        assert_eq!(&*name.lexeme, "cidade");
                         // Inner Get (pessoa.endereco)
                         assert!(matches!(
                             object.as_ref(),
-                            Expr::Get { name, .. } if name.lexeme == "endereco"
+                            Expr::Get { name, .. } if &*name.lexeme == "endereco"
                         ));
                     }
                     _ => panic!("expected Set expression"),
@@ -1674,7 +2075,7 @@ mod tests {
                 // Set expression
                 match expression {
                     Expr::Set { object, name, .. } => {
-                        assert_eq!(name.lexeme, "nome");
+                        assert_eq!(&*name.lexeme, "nome");
                         // Object should be a Call
                         assert!(matches!(object.as_ref(), Expr::Call { .. }));
                     }
@@ -1696,7 +2097,7 @@ mod tests {
         assert_eq!(stmts.len(), 1);
         match &stmts[0] {
             Stmt::Expression { expression, .. } => {
-                assert!(matches!(expression, Expr::Variable { name } if name.lexeme == "x"));
+                assert!(matches!(expression, Expr::Variable { name } if &*name.lexeme == "x"));
             }
             _ => panic!("expected expression statement"),
         }
@@ -1723,8 +2124,63 @@ mod tests {
         // Should still parse the second statement
         assert!(result.is_ok());
         let stmts = result.unwrap();
-        assert_eq!(stmts.len(), 1); // Only the valid statement
-        assert!(matches!(stmts[0], Stmt::Print { .. }));
+        assert_eq!(stmts.len(), 2); // A placeholder for the broken one, then the valid one
+        assert!(matches!(stmts[0], Stmt::Error { .. }));
+        assert!(matches!(stmts[1], Stmt::Print { .. }));
+    }
+
+    #[test]
+    fn missing_expression_becomes_error_placeholder() {
+        // seLiga x = ;
+        let tokens = vec![
+            make_token(TokenType::Var, "seLiga", None),
+            make_token(TokenType::Identifier, "x", None),
+            make_token(TokenType::Equal, "=", None),
+            semi(),
+            eof(),
+        ];
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        assert_eq!(parser.take_errors().len(), 1);
+        match &stmts[0] {
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                assert_eq!(&*name.lexeme, "x");
+                assert!(matches!(initializer, Some(Expr::Error { .. })));
+            }
+            _ => panic!("expected Var with an error initializer"),
+        }
+    }
+
+    #[test]
+    fn broken_statement_inside_function_keeps_the_function() {
+        // olhaEssaFita f() { salve ); salve 1; }
+        let tokens = vec![
+            make_token(TokenType::Fun, "olhaEssaFita", None),
+            make_token(TokenType::Identifier, "f", None),
+            make_token(TokenType::LeftParen, "(", None),
+            make_token(TokenType::RightParen, ")", None),
+            make_token(TokenType::LeftBrace, "{", None),
+            make_token(TokenType::Print, "salve", None),
+            make_token(TokenType::RightParen, ")", None),
+            semi(),
+            make_token(TokenType::Print, "salve", None),
+            make_token(TokenType::Number, "1", Some(Literal::Number(1.0))),
+            semi(),
+            make_token(TokenType::RightBrace, "}", None),
+            eof(),
+        ];
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        assert_eq!(parser.take_errors().len(), 1);
+        match &stmts[0] {
+            Stmt::Function { body, .. } => {
+                assert!(matches!(body[0], Stmt::Error { .. }));
+                assert!(matches!(body[1], Stmt::Print { .. }));
+            }
+            _ => panic!("expected Function"),
+        }
     }
 
     // === statements ===
@@ -2051,6 +2507,101 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bitwise_operators_bind_between_comparison_and_term() {
+        let source = "1 | 2 ^ 3 & 4 << 5 + 6 == 7 < 8 | 9;";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        match &stmts[0] {
+            Stmt::Expression { expression, .. } => {
+                assert_eq!(
+                    expression.to_string(),
+                    "(== (| 1 (^ 2 (& 3 (<< 4 (+ 5 6))))) (< 7 (| 8 9)))"
+                );
+            }
+            _ => panic!("expected expression statement"),
+        }
+    }
+
+    #[test]
+    fn para_loop_points_at_sevira() {
+        let source = "para (seLiga i = 0; i < 3; i = i + 1) { salve i; } para(1);";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        let errors = parser.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].primary(),
+            ManoError::Parse { message, span } if message.contains("seVira") && *span == (0..4)
+        ));
+        assert!(errors[0].help().unwrap().contains("dialeto: formal"));
+        // The loop still parses, and `para(1)` is just a call
+        assert_eq!(stmts.len(), 2);
+        assert!(matches!(stmts[0], Stmt::Block { .. } | Stmt::While { .. }));
+        assert!(matches!(stmts[1], Stmt::Expression { .. }));
+    }
+
+    #[test]
+    fn compound_assignment_keeps_its_operator() {
+        let source = "x += 1; a.b.c *= 2; a.b.c = x -= 3; 1 += 2;";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        let printed: Vec<_> = stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Expression { expression, .. } => Some(expression.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            printed,
+            vec!["(+= x 1)", "(a.b.c *= 2)", "(a.b.c = (-= x 3))"]
+        );
+        assert_eq!(parser.take_errors().len(), 1);
+    }
+
+    #[test]
+    fn parses_lists_indexing_and_index_assignment() {
+        let source = "[]; [1, [2], 3,]; l[0][i + 1]; f()[0] += 2; l[0] = [1];";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        let printed: Vec<_> = stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Expression { expression, .. } => Some(expression.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            printed,
+            vec![
+                "(list)",
+                "(list 1 (list 2) 3)",
+                "l[0][(+ i 1)]",
+                "((call f)[0] += 2)",
+                "(l[0] = (list 1))"
+            ]
+        );
+        assert!(parser.take_errors().is_empty());
+    }
+
+    #[test]
+    fn unclosed_list_asks_for_the_bracket() {
+        let tokens = crate::Scanner::new("[1, 2;")
+            .filter_map(Result::ok)
+            .collect();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let errors = parser.take_errors();
+        assert!(matches!(
+            errors[0].primary(),
+            ManoError::Parse { message, .. } if message.contains("']' da lista")
+        ));
+    }
+
     #[test]
     fn or_is_left_associative() {
         // a ow b ow c -> (a ow b) ow c
@@ -2138,7 +2689,8 @@ mod tests {
     #[test]
     fn parses_for_with_all_clauses() {
         // seVira (seLiga i = 0; i < 3; i = i + 1) salve i;
-        // Desugars to: { seLiga i = 0; segueOFluxo (i < 3) { salve i; i = i + 1; } }
+        // Desugars to: { seLiga i = 0; segueOFluxo (i < 3) salve i; } with
+        // `i = i + 1` as the loop's increment
         let tokens = vec![
             make_token(TokenType::For, "seVira", None),
             make_token(TokenType::LeftParen, "(", None),
@@ -2170,7 +2722,21 @@ mod tests {
             Stmt::Block { statements, .. } => {
                 assert_eq!(statements.len(), 2);
                 assert!(matches!(&statements[0], Stmt::Var { .. }));
-                assert!(matches!(&statements[1], Stmt::While { .. }));
+                match &statements[1] {
+                    Stmt::While {
+                        body, increment, ..
+                    } => {
+                        assert!(matches!(body.as_ref(), Stmt::Print { .. }));
+                        assert!(matches!(
+                            increment.as_deref(),
+                            Some(Stmt::Expression {
+                                expression: Expr::Assign { .. },
+                                ..
+                            })
+                        ));
+                    }
+                    _ => panic!("expected While"),
+                }
             }
             _ => panic!("expected Block statement (desugared for)"),
         }
@@ -2345,14 +2911,126 @@ mod tests {
     }
 
     #[test]
-    fn break_outside_loop_is_error() {
-        // saiFora; (not in a loop)
+    fn parses_break_outside_loop() {
+        // saiFora; (loop context is checked by the resolver)
         let tokens = vec![make_token(TokenType::Break, "saiFora", None), semi(), eof()];
         let mut parser = Parser::new(tokens);
-        parser.parse().unwrap();
+        let stmts = parser.parse().unwrap();
+        assert!(parser.take_errors().is_empty());
+        assert!(matches!(&stmts[0], Stmt::Break { keyword, .. } if &*keyword.lexeme == "saiFora"));
+    }
+
+    #[test]
+    fn parses_continue_in_while() {
+        // segueOFluxo (firmeza) segueReto;
+        let tokens = vec![
+            make_token(TokenType::While, "segueOFluxo", None),
+            make_token(TokenType::LeftParen, "(", None),
+            make_token(TokenType::True, "firmeza", None),
+            make_token(TokenType::RightParen, ")", None),
+            make_token(TokenType::Continue, "segueReto", None),
+            semi(),
+            eof(),
+        ];
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        match &stmts[0] {
+            Stmt::While { body, .. } => {
+                assert!(
+                    matches!(body.as_ref(), Stmt::Continue { keyword, .. } if &*keyword.lexeme == "segueReto")
+                );
+            }
+            _ => panic!("expected While"),
+        }
+    }
+
+    #[test]
+    fn parses_import() {
+        // chama "util.mano";
+        let tokens = vec![
+            make_token(TokenType::Import, "chama", None),
+            make_token(
+                TokenType::String,
+                "\"util.mano\"",
+                Some(Literal::String("util.mano".to_string())),
+            ),
+            semi(),
+            eof(),
+        ];
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        assert!(matches!(
+            &stmts[0],
+            Stmt::Import { path, .. }
+                if path.literal == Some(Literal::String("util.mano".to_string()))
+        ));
+    }
+
+    #[test]
+    fn import_without_a_path_errors() {
+        // chama util;
+        let tokens = vec![
+            make_token(TokenType::Import, "chama", None),
+            make_token(TokenType::Identifier, "util", None),
+            semi(),
+            eof(),
+        ];
+        let mut parser = Parser::new(tokens);
+        let _ = parser.parse();
         let errors = parser.take_errors();
-        assert_eq!(errors.len(), 1);
-        assert!(matches!(&errors[0], ManoError::Parse { .. }));
+        assert!(
+            matches!(errors[0].primary(), ManoError::Parse { message, .. } if message.contains("caminho"))
+        );
+    }
+
+    #[test]
+    fn parses_try_and_throw() {
+        let source = "tentaAi { joga \"vish\"; } seDeuRuim (erro) { salve erro; }";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        let Stmt::Try {
+            body,
+            name,
+            handler,
+            span,
+        } = &stmts[0]
+        else {
+            panic!("expected tentaAi");
+        };
+        assert_eq!(&*name.lexeme, "erro");
+        assert_eq!(*span, 0..source.len());
+        assert!(matches!(
+            body.children()[..],
+            [Stmt::Throw {
+                value: Expr::Literal { .. },
+                ..
+            }]
+        ));
+        assert!(matches!(handler.children()[..], [Stmt::Print { .. }]));
+    }
+
+    #[test]
+    fn try_without_catch_errors() {
+        let source = "tentaAi { salve 1; } salve 2;";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let _ = parser.parse();
+        let errors = parser.take_errors();
+        assert!(
+            matches!(errors[0].primary(), ManoError::Parse { message, .. } if message.contains("seDeuRuim"))
+        );
+    }
+
+    #[test]
+    fn continue_without_semicolon_errors() {
+        // segueReto
+        let tokens = vec![make_token(TokenType::Continue, "segueReto", None), eof()];
+        let mut parser = Parser::new(tokens);
+        let _ = parser.parse();
+        let errors = parser.take_errors();
+        assert!(
+            matches!(errors[0].primary(), ManoError::Parse { message, .. } if message.contains("segueReto"))
+        );
     }
 
     #[test]
@@ -2390,7 +3068,7 @@ mod tests {
     ) -> Token {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             literal,
             span: start..start + lexeme.len(),
         }
@@ -2726,7 +3404,7 @@ mod tests {
             Stmt::Expression { expression, .. } => {
                 assert!(matches!(
                     expression,
-                    Expr::Get { name, .. } if name.lexeme == "nome"
+                    Expr::Get { name, .. } if &*name.lexeme == "nome"
                 ));
             }
             _ => panic!("expected expression statement"),
@@ -2753,11 +3431,11 @@ mod tests {
                 // Outer Get (cidade)
                 match expression {
                     Expr::Get { object, name } => {
-                        assert_eq!(name.lexeme, "cidade");
+                        assert_eq!(&*name.lexeme, "cidade");
                         // Inner Get (endereco)
                         assert!(matches!(
                             object.as_ref(),
-                            Expr::Get { name, .. } if name.lexeme == "endereco"
+                            Expr::Get { name, .. } if &*name.lexeme == "endereco"
                         ));
                     }
                     _ => panic!("expected Get expression"),
@@ -2787,7 +3465,7 @@ mod tests {
                 // Outer Get
                 match expression {
                     Expr::Get { object, name } => {
-                        assert_eq!(name.lexeme, "nome");
+                        assert_eq!(&*name.lexeme, "nome");
                         // Inner Call
                         assert!(matches!(object.as_ref(), Expr::Call { .. }));
                     }
@@ -2822,7 +3500,7 @@ mod tests {
             Stmt::Function {
                 name, params, body, ..
             } => {
-                assert_eq!(name.lexeme, "cumprimentar");
+                assert_eq!(&*name.lexeme, "cumprimentar");
                 assert!(params.is_empty());
                 assert_eq!(body.len(), 1);
             }
@@ -2855,16 +3533,53 @@ mod tests {
             Stmt::Function {
                 name, params, body, ..
             } => {
-                assert_eq!(name.lexeme, "soma");
+                assert_eq!(&*name.lexeme, "soma");
                 assert_eq!(params.len(), 2);
-                assert_eq!(params[0].lexeme, "a");
-                assert_eq!(params[1].lexeme, "b");
+                assert_eq!(&*params[0].lexeme, "a");
+                assert_eq!(&*params[1].lexeme, "b");
                 assert_eq!(body.len(), 1);
             }
             _ => panic!("expected Function statement"),
         }
     }
 
+    #[test]
+    fn parses_a_rest_parameter_last() {
+        let source = "olhaEssaFita soma(base, ...nums) {} bagulho B { m(...xs) {} }";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        let Stmt::Function {
+            params,
+            is_variadic,
+            ..
+        } = &stmts[0]
+        else {
+            panic!("expected Function statement");
+        };
+        assert!(is_variadic);
+        assert_eq!(&*params[1].lexeme, "nums");
+        let Stmt::Class { methods, .. } = &stmts[1] else {
+            panic!("expected Class statement");
+        };
+        assert!(matches!(
+            &methods[0],
+            Stmt::Function {
+                is_variadic: true,
+                ..
+            }
+        ));
+
+        let source = "olhaEssaFita f(...xs, y) {}";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let errors = parser.take_errors();
+        assert!(matches!(
+            errors.as_slice(),
+            [ManoError::Parse { message, span }] if message.contains("último") && span == &(20..21)
+        ));
+    }
+
     #[test]
     fn error_on_function_missing_name() {
         // olhaEssaFita () {}
@@ -3137,7 +3852,7 @@ mod tests {
         assert_eq!(statements.len(), 1);
         match &statements[0] {
             Stmt::Class { name, methods, .. } => {
-                assert_eq!(name.lexeme, "Vazio");
+                assert_eq!(&*name.lexeme, "Vazio");
                 assert_eq!(methods.len(), 0);
             }
             _ => panic!("Expected Class statement"),
@@ -3166,11 +3881,11 @@ mod tests {
         assert_eq!(statements.len(), 1);
         match &statements[0] {
             Stmt::Class { name, methods, .. } => {
-                assert_eq!(name.lexeme, "Pessoa");
+                assert_eq!(&*name.lexeme, "Pessoa");
                 assert_eq!(methods.len(), 1);
                 match &methods[0] {
                     Stmt::Function { name, .. } => {
-                        assert_eq!(name.lexeme, "falar");
+                        assert_eq!(&*name.lexeme, "falar");
                     }
                     _ => panic!("Expected Function in methods"),
                 }
@@ -3179,6 +3894,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_enum_options() {
+        let source = "opcoes Cor { Vermelho, Verde, } opcoes Vazio {}";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
+        let Stmt::Enum {
+            name,
+            variants,
+            span,
+        } = &stmts[0]
+        else {
+            panic!("expected an enum, got {:?}", stmts[0]);
+        };
+        assert_eq!(&*name.lexeme, "Cor");
+        let names: Vec<_> = variants.iter().map(|v| &*v.lexeme).collect();
+        assert_eq!(names, ["Vermelho", "Verde"]);
+        assert_eq!(&source[span.clone()], "opcoes Cor { Vermelho, Verde, }");
+        // At least one option
+        let errors = parser.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(
+            matches!(&errors[0], ManoError::Parse { message, .. } if message.contains("nome da opção"))
+        );
+    }
+
     #[test]
     fn parses_class_with_superclass() {
         // bagulho Filho < Pai {}
@@ -3203,12 +3944,12 @@ mod tests {
                 methods,
                 ..
             } => {
-                assert_eq!(name.lexeme, "Filho");
+                assert_eq!(&*name.lexeme, "Filho");
                 assert_eq!(methods.len(), 0);
                 assert!(superclass.is_some());
                 if let Some(sc) = superclass {
                     if let Expr::Variable { name } = sc.as_ref() {
-                        assert_eq!(name.lexeme, "Pai");
+                        assert_eq!(&*name.lexeme, "Pai");
                     } else {
                         panic!("Expected Variable expression for superclass");
                     }
@@ -3303,7 +4044,7 @@ mod tests {
                     Stmt::Function {
                         name, is_static, ..
                     } => {
-                        assert_eq!(name.lexeme, "criar");
+                        assert_eq!(&*name.lexeme, "criar");
                         assert!(is_static, "method should be static");
                     }
                     _ => panic!("expected function"),
@@ -3343,7 +4084,7 @@ mod tests {
                         params,
                         ..
                     } => {
-                        assert_eq!(name.lexeme, "idade");
+                        assert_eq!(&*name.lexeme, "idade");
                         assert!(is_getter, "method should be a getter");
                         assert!(params.is_empty(), "getter should have no params");
                     }
@@ -3374,8 +4115,8 @@ mod tests {
                 match expression {
                     Expr::Call { callee, .. } => match callee.as_ref() {
                         Expr::Super { keyword, method } => {
-                            assert_eq!(keyword.lexeme, "mestre");
-                            assert_eq!(method.lexeme, "cozinhar");
+                            assert_eq!(&*keyword.lexeme, "mestre");
+                            assert_eq!(&*method.lexeme, "cozinhar");
                         }
                         _ => panic!("expected Super expression as callee"),
                     },
@@ -3447,7 +4188,7 @@ mod tests {
                     assert_eq!(parts.len(), 3);
                     assert!(matches!(&parts[0], InterpolationPart::Str(s) if s == "Olá, "));
                     assert!(
-                        matches!(&parts[1], InterpolationPart::Expr(e) if matches!(e.as_ref(), Expr::Variable { name } if name.lexeme == "nome"))
+                        matches!(&parts[1], InterpolationPart::Expr(e) if matches!(e.as_ref(), Expr::Variable { name } if &*name.lexeme == "nome"))
                     );
                     assert!(matches!(&parts[2], InterpolationPart::Str(s) if s == "!"));
                 }
@@ -3573,4 +4314,158 @@ mod tests {
             panic!("Expected Parse error");
         }
     }
+
+    #[test]
+    fn memoizado_marks_instance_getters_only() {
+        let source =
+            "bagulho C { memoizado area { toma 1; } memoizado() { toma 2; } memoizado f() {} }";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+        let Stmt::Class { methods, .. } = &statements[0] else {
+            panic!("expected a class, got {:?}", statements[0]);
+        };
+        let flags: Vec<_> = methods
+            .iter()
+            .map(|method| match method {
+                Stmt::Function {
+                    name, is_memoized, ..
+                } => (name.lexeme.to_string(), *is_memoized),
+                other => panic!("expected a method, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                ("area".to_string(), true),
+                ("memoizado".to_string(), false),
+                ("f".to_string(), false),
+            ]
+        );
+        let errors = parser.take_errors();
+        assert!(
+            matches!(errors.as_slice(), [ManoError::Parse { message, .. }] if message.contains("Só getter"))
+        );
+    }
+
+    // === nesting ===
+
+    #[test]
+    fn broken_statement_does_not_swallow_its_block() {
+        let source = "bagulho P { falar() { oCara. } outra() { toma 1; } } salve 2;";
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+
+        assert_eq!(parser.take_errors().len(), 1);
+        assert_eq!(statements.len(), 2);
+        let Stmt::Class { methods, .. } = &statements[0] else {
+            panic!("expected the class to survive, got {:?}", statements[0]);
+        };
+        assert_eq!(methods.len(), 2);
+        assert!(matches!(statements[1], Stmt::Print { .. }));
+    }
+
+    fn parse_errors(source: &str, max_nesting: usize) -> Vec<ManoError> {
+        let tokens = crate::Scanner::new(source).filter_map(Result::ok).collect();
+        let mut parser = Parser::new(tokens).with_max_nesting(max_nesting);
+        parser.parse().unwrap();
+        parser.take_errors()
+    }
+
+    fn is_too_deep(error: &ManoError) -> bool {
+        matches!(error, ManoError::Parse { message, .. } if message.contains("aninhada demais"))
+    }
+
+    /// Runs `test` with room for [`MAX_NESTING`] levels in debug builds, whose
+    /// frames are several times bigger than release ones
+    fn with_debug_stack(test: impl FnOnce() + Send + 'static) {
+        std::thread::Builder::new()
+            .stack_size(32 * 1024 * 1024)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn reports_code_nested_too_deep_once() {
+        with_debug_stack(reports_each_pattern_once);
+    }
+
+    fn reports_each_pattern_once() {
+        let patterns = [
+            ("salve ", "(", "1", ")", ";"),
+            ("", "{", "", "}", ""),
+            ("salve ", "-", "1", "", ";"),
+            ("salve ", "!", "1", "", ";"),
+            ("", "f(", "1", ")", ";"),
+            ("", "a = ", "1", "", ";"),
+            ("", "sePá (1) ", "salve 1;", "", ""),
+            ("salve ", "1 ? 1 : ", "1", "", ";"),
+            ("", "olhaEssaFita f() { ", "", "}", ""),
+            ("", "segueOFluxo (1) ", "salve 1;", "", ""),
+        ];
+        for (before, open, middle, close, after) in patterns {
+            let n = 50_000;
+            let source = format!(
+                "{before}{}{middle}{}{after}",
+                open.repeat(n),
+                close.repeat(n)
+            );
+            let errors = parse_errors(&source, MAX_NESTING);
+            assert_eq!(errors.len(), 1, "{open}: {errors:?}");
+            assert!(is_too_deep(&errors[0]), "{open}: {errors:?}");
+        }
+    }
+
+    #[test]
+    fn nesting_limit_is_configurable() {
+        assert!(parse_errors("salve ((1));", 10).is_empty());
+        assert!(is_too_deep(&parse_errors("salve ((1));", 3)[0]));
+        // Only the declaration that went too deep is lost
+        let errors = parse_errors("salve ((((1)))); salve 1; salve (1 +);", 4);
+        assert_eq!(errors.len(), 2);
+        assert!(is_too_deep(&errors[0]));
+    }
+
+    #[test]
+    fn random_nesting_never_overflows() {
+        with_debug_stack(|| {
+            let pieces = [
+                "(",
+                ")",
+                "{",
+                "}",
+                "-",
+                "!",
+                "f(",
+                ",",
+                "a = ",
+                "1 ? ",
+                " : ",
+                "sePá (1) ",
+                "olhaEssaFita (x) {",
+                "salve ",
+                "1",
+                ";",
+                "\"{",
+                "}\"",
+                ".b",
+                "oCara",
+            ];
+            // Small xorshift, so failures reproduce
+            let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+            for _ in 0..100 {
+                let mut source = String::new();
+                for _ in 0..5_000 {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    source.push_str(pieces[(state % pieces.len() as u64) as usize]);
+                }
+                parse_errors(&source, MAX_NESTING);
+            }
+        });
+    }
 }
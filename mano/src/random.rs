@@ -0,0 +1,71 @@
+//! Random numbers for `aleatorio` and `aleatorioEntre`
+//!
+//! A small splitmix64 generator, so the same seed gives the same numbers on
+//! every machine.
+
+use std::time::SystemTime;
+
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded from the clock, for runs that don't ask for a seed
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |now| now.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..1`
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64's mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A whole number from `low` to `high`, both included
+    pub fn between(&mut self, low: i64, high: i64) -> i64 {
+        let count = high.abs_diff(low) + 1;
+        low.wrapping_add((self.next_u64() % count) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_f64(), b.next_f64());
+        }
+        assert_ne!(Random::new(1).next_f64(), Random::new(2).next_f64());
+    }
+
+    #[test]
+    fn stays_in_range() {
+        let mut random = Random::new(7);
+        for _ in 0..1000 {
+            let n = random.next_f64();
+            assert!((0.0..1.0).contains(&n));
+            let n = random.between(-2, 3);
+            assert!((-2..=3).contains(&n));
+        }
+        assert_eq!(random.between(5, 5), 5);
+    }
+}
@@ -0,0 +1,87 @@
+//! Fitas and bagulhos declared twice at the top of a script.
+//!
+//! The second declaration replaces the first, in the interpreter and the VM
+//! alike. That's what the REPL is for (each input runs on its own, so typing a
+//! fita again fixes it), but in a script the first one was never meant to go
+//! away: the LSP warns about it, and strict mode refuses to run it on either
+//! backend.
+
+use std::collections::HashMap;
+
+use crate::ast::Stmt;
+use crate::error::ManoError;
+use crate::token::Token;
+
+/// Warns about every top-level fita or bagulho whose name an earlier one
+/// already took, pointing back at the first
+pub fn redeclaration_warnings(statements: &[Stmt]) -> Vec<ManoError> {
+    let mut warnings = Vec::new();
+    let mut declared: HashMap<&str, (&Token, &str)> = HashMap::new();
+
+    for stmt in statements {
+        let (name, kind) = match stmt {
+            Stmt::Function { name, .. } => (name, "uma fita"),
+            Stmt::Class { name, .. } => (name, "um bagulho"),
+            Stmt::Enum { name, .. } => (name, "umas opções"),
+            _ => continue,
+        };
+        match declared.get(&*name.lexeme) {
+            Some((first, first_kind)) => warnings.push(
+                ManoError::Warning {
+                    message: format!(
+                        "'{}' já é {} lá em cima, mano! Essa declaração substitui a outra.",
+                        name.lexeme, first_kind
+                    ),
+                    span: name.span.clone(),
+                }
+                .with_related(first.span.clone(), "a primeira tá aqui"),
+            ),
+            None => {
+                declared.insert(&name.lexeme, (name, kind));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn warnings(source: &str) -> Vec<ManoError> {
+        redeclaration_warnings(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn warns_at_the_second_declaration_pointing_at_the_first() {
+        let source = "olhaEssaFita f() {} olhaEssaFita f() {} olhaEssaFita f() {}";
+        let warnings = warnings(source);
+        assert_eq!(warnings.len(), 2);
+        let ManoError::Warning { message, span } = warnings[0].primary() else {
+            panic!("expected a warning, got {:?}", warnings[0]);
+        };
+        assert!(message.contains("'f' já é uma fita"), "got: {message}");
+        assert_eq!(&source[span.clone()], "f");
+        assert_eq!(span.start, 33);
+        assert_eq!(warnings[0].related()[0].span, 13..14);
+        assert_eq!(warnings[1].related()[0].span, 13..14);
+    }
+
+    #[test]
+    fn fitas_and_bagulhos_share_names() {
+        let warnings = warnings("bagulho Coisa {} olhaEssaFita Coisa() {}");
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0].primary(),
+            ManoError::Warning { message, .. } if message.contains("já é um bagulho")
+        ));
+    }
+
+    #[test]
+    fn only_looks_at_the_top_level() {
+        let source = "olhaEssaFita f() {} { olhaEssaFita f() {} } \
+                      bagulho B { f() {} } olhaEssaFita g() { olhaEssaFita f() {} }";
+        assert!(warnings(source).is_empty());
+    }
+}
@@ -1,13 +1,27 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 
 use crate::INITIALIZER_NAME;
+use crate::analysis::{Analysis, DeclarationId, Reference};
 use crate::ast::{Expr, InterpolationPart, Span, Stmt};
 use crate::error::ManoError;
+use crate::scope::{Declaration, DeclarationKind, ScopeTree};
 use crate::token::{Literal, Token, TokenType};
 
+pub(crate) const RETURN_OUTSIDE_FUNCTION: &str =
+    "Toma sem fita? Só pode dar toma dentro de uma função, tio!";
+const BREAK_OUTSIDE_LOOP: &str = "Não pode dar saiFora fora de um loop, mano!";
+const CONTINUE_OUTSIDE_LOOP: &str = "Não pode dar segueReto fora de um loop, mano!";
+const NESTED_IMPORT: &str = "O chama é só no topo do arquivo, mano, fora de bloco e de fita!";
+const STATIC_INITIALIZER: &str =
+    "O bora monta cada parada, não dá pra ele ser do bagulho (estático), mano!";
+const GETTER_INITIALIZER: &str = "O bora recebe lances, não dá pra ele ser getter, mano!";
+
 /// Maps expression spans to their resolved (distance, slot) pair
 /// - distance: how many scopes to walk up
 /// - slot: index within that scope's variable array
+///
+/// Only locals are resolved; globals are looked up by name at runtime.
 pub type Resolutions = HashMap<Span, (usize, usize)>;
 
 /// Tracks function context for validation (return statements)
@@ -35,6 +49,8 @@ struct VarInfo {
     used: bool,
     span: Span,
     slot: usize,
+    /// Where it was declared in the scope tree (`None` for oCara and mestre)
+    declaration: Option<DeclarationId>,
 }
 
 pub struct Resolver {
@@ -46,8 +62,18 @@ pub struct Resolver {
     current_function: FunctionType,
     /// Current class context
     current_class: ClassType,
+    /// Whether we're inside a loop body of the current function
+    in_loop: bool,
     /// Accumulated errors
     errors: Vec<ManoError>,
+    /// Every scope seen so far, for tooling
+    scope_tree: ScopeTree,
+    /// Index of the current scope in the scope tree
+    current_scope: usize,
+    /// Every name used in an expression, for tooling
+    references: Vec<Reference>,
+    /// Whether `+` may join texto with a number, lógico or nadaNão
+    text_coercion: bool,
 }
 
 impl Resolver {
@@ -57,10 +83,21 @@ impl Resolver {
             resolutions: HashMap::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            in_loop: false,
             errors: Vec::new(),
+            scope_tree: ScopeTree::default(),
+            current_scope: 0,
+            references: Vec::new(),
+            text_coercion: false,
         }
     }
 
+    /// Accept `"idade: " + 30`, for code run with text coercion on
+    pub fn with_text_coercion(mut self, text_coercion: bool) -> Self {
+        self.text_coercion = text_coercion;
+        self
+    }
+
     /// Main entry point - resolve all statements
     pub fn resolve(mut self, statements: &[Stmt]) -> Result<Resolutions, Vec<ManoError>> {
         for stmt in statements {
@@ -73,11 +110,40 @@ impl Resolver {
         }
     }
 
-    fn begin_scope(&mut self) {
+    /// Resolve all statements only to collect their scopes, ignoring errors
+    pub(crate) fn scope_tree(mut self, statements: &[Stmt]) -> ScopeTree {
+        for stmt in statements {
+            self.resolve_stmt(stmt);
+        }
+        self.scope_tree
+    }
+
+    /// Resolve all statements, keeping both the binding facts and the errors
+    pub(crate) fn analyze(mut self, statements: &[Stmt]) -> Analysis {
+        for stmt in statements {
+            self.resolve_stmt(stmt);
+        }
+        Analysis::from_parts(
+            self.scope_tree,
+            self.resolutions,
+            self.references,
+            self.errors,
+        )
+    }
+
+    fn begin_scope(&mut self, span: Span) {
+        self.scopes.push(HashMap::new());
+        self.current_scope = self.scope_tree.push(span, self.current_scope, false);
+    }
+
+    /// A scope for a fita's parameters and body
+    fn begin_function_scope(&mut self, span: Span) {
         self.scopes.push(HashMap::new());
+        self.current_scope = self.scope_tree.push(span, self.current_scope, true);
     }
 
     fn end_scope(&mut self) {
+        self.current_scope = self.scope_tree.parent(self.current_scope);
         if let Some(scope) = self.scopes.pop() {
             for (name, info) in scope {
                 // Variables starting with _ are intentionally unused (like Rust)
@@ -94,26 +160,73 @@ impl Resolver {
         }
     }
 
-    fn declare(&mut self, name: &Token) {
+    /// Two methods with the same name would silently keep only the last one.
+    /// Static methods and instance methods (getters included) are apart, so
+    /// one of each with the same name is fine.
+    fn check_duplicate_methods(&mut self, class: &Token, methods: &[Stmt]) {
+        let mut seen: HashMap<(bool, &str), &Token> = HashMap::new();
+        for method in methods {
+            let Stmt::Function {
+                name, is_static, ..
+            } = method
+            else {
+                continue;
+            };
+            match seen.entry((*is_static, &name.lexeme)) {
+                Entry::Occupied(first) => {
+                    let kind = if *is_static {
+                        "método estático"
+                    } else {
+                        "método de instância"
+                    };
+                    let error = ManoError::Resolution {
+                        message: format!(
+                            "O bagulho '{}' já tem um {} '{}', mano! Só o último ia valer.",
+                            class.lexeme, kind, name.lexeme
+                        ),
+                        span: name.span.clone(),
+                    };
+                    self.errors
+                        .push(error.with_related(first.get().span.clone(), "o primeiro tá aqui"));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(name);
+                }
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &Token, kind: DeclarationKind) {
+        let declaration = self.scope_tree.declare(
+            self.current_scope,
+            Declaration {
+                name: name.lexeme.to_string(),
+                kind,
+                span: name.span.clone(),
+            },
+        );
         if let Some(scope) = self.scopes.last_mut() {
-            if scope.contains_key(&name.lexeme) {
-                self.errors.push(ManoError::Resolution {
+            if let Some(previous) = scope.get(&*name.lexeme) {
+                let error = ManoError::Resolution {
                     message: format!(
                         "Já tem uma '{}' aqui, chapa! Tá querendo confundir o corre?",
                         name.lexeme
                     ),
                     span: name.span.clone(),
-                });
+                };
+                self.errors
+                    .push(error.with_related(previous.span.clone(), "a primeira tá aqui"));
             }
             // Assign slot index based on current scope size
             let slot = scope.len();
             scope.insert(
-                name.lexeme.clone(),
+                name.lexeme.to_string(),
                 VarInfo {
                     defined: false,
                     used: false,
                     span: name.span.clone(),
                     slot,
+                    declaration: Some(declaration),
                 },
             );
         }
@@ -121,32 +234,46 @@ impl Resolver {
 
     fn define(&mut self, name: &Token) {
         if let Some(scope) = self.scopes.last_mut()
-            && let Some(info) = scope.get_mut(&name.lexeme)
+            && let Some(info) = scope.get_mut(&*name.lexeme)
         {
             info.defined = true;
         }
     }
 
     fn resolve_local(&mut self, name: &Token) {
+        // Dialects spell oCara and mestre differently, their scopes don't
+        let key = match name.token_type {
+            TokenType::This => "oCara",
+            TokenType::Super => "mestre",
+            _ => &name.lexeme,
+        };
         let len = self.scopes.len();
+        let mut declaration = None;
         for i in 0..len {
             let scope_idx = len - 1 - i;
-            if self.scopes[scope_idx].contains_key(&name.lexeme) {
-                // Mark variable as used and get its slot
-                if let Some(info) = self.scopes[scope_idx].get_mut(&name.lexeme) {
-                    info.used = true;
-                    self.resolutions.insert(name.span.clone(), (i, info.slot));
-                }
-                return;
+            // Mark variable as used and get its slot
+            if let Some(info) = self.scopes[scope_idx].get_mut(key) {
+                info.used = true;
+                self.resolutions.insert(name.span.clone(), (i, info.slot));
+                declaration = info.declaration;
+                break;
             }
         }
         // Not found = global variable (looked up dynamically at runtime)
+
+        if name.token_type == TokenType::Identifier {
+            self.references.push(Reference {
+                name: name.lexeme.to_string(),
+                span: name.span.clone(),
+                declaration,
+            });
+        }
     }
 
     fn resolve_stmt(&mut self, stmt: &Stmt) {
         match stmt {
-            Stmt::Block { statements, .. } => {
-                self.begin_scope();
+            Stmt::Block { statements, span } => {
+                self.begin_scope(span.clone());
                 for s in statements {
                     self.resolve_stmt(s);
                 }
@@ -155,7 +282,7 @@ impl Resolver {
             Stmt::Var {
                 name, initializer, ..
             } => {
-                self.declare(name);
+                self.declare(name, DeclarationKind::Variable);
                 if let Some(init) = initializer {
                     self.resolve_expr_checking_self_ref(init, name);
                 }
@@ -165,17 +292,20 @@ impl Resolver {
                 self.resolve_expr(expression);
             }
             Stmt::Function {
-                name, params, body, ..
+                name,
+                params,
+                body,
+                span,
+                ..
             } => {
-                self.declare(name);
+                self.declare(name, DeclarationKind::Function);
                 self.define(name);
-                self.resolve_function(params, body, FunctionType::Function);
+                self.resolve_function(params, body, FunctionType::Function, span.clone());
             }
             Stmt::Return { keyword, value, .. } => {
                 if self.current_function == FunctionType::None {
                     self.errors.push(ManoError::Resolution {
-                        message: "Toma sem fita? Só pode dar toma dentro de uma função, tio!"
-                            .to_string(),
+                        message: RETURN_OUTSIDE_FUNCTION.to_string(),
                         span: keyword.span.clone(),
                     });
                 }
@@ -203,10 +333,18 @@ impl Resolver {
                 }
             }
             Stmt::While {
-                condition, body, ..
+                condition,
+                body,
+                increment,
+                ..
             } => {
                 self.resolve_expr(condition);
+                let enclosing_loop = std::mem::replace(&mut self.in_loop, true);
                 self.resolve_stmt(body);
+                self.in_loop = enclosing_loop;
+                if let Some(increment) = increment {
+                    self.resolve_stmt(increment);
+                }
             }
             Stmt::Expression { expression, .. } => {
                 self.resolve_expr(expression);
@@ -214,14 +352,74 @@ impl Resolver {
             Stmt::Else { body, .. } => {
                 self.resolve_stmt(body);
             }
-            Stmt::Break { .. } => {}
+            Stmt::Error { .. } => {}
+            Stmt::Break { keyword, .. } => {
+                if !self.in_loop {
+                    self.errors.push(ManoError::Resolution {
+                        message: BREAK_OUTSIDE_LOOP.to_string(),
+                        span: keyword.span.clone(),
+                    });
+                }
+            }
+            Stmt::Continue { keyword, .. } => {
+                if !self.in_loop {
+                    self.errors.push(ManoError::Resolution {
+                        message: CONTINUE_OUTSIDE_LOOP.to_string(),
+                        span: keyword.span.clone(),
+                    });
+                }
+            }
+            Stmt::Import { keyword, .. } => {
+                // The module's globals are only there once it ran, so it
+                // can't depend on where the chama is
+                if !self.scopes.is_empty() {
+                    self.errors.push(ManoError::Resolution {
+                        message: NESTED_IMPORT.to_string(),
+                        span: keyword.span.clone(),
+                    });
+                }
+            }
+            Stmt::Try {
+                body,
+                name,
+                handler,
+                ..
+            } => {
+                self.resolve_stmt(body);
+                // The error gets a scope of its own around the handler's block
+                self.begin_scope(handler.span());
+                self.declare(name, DeclarationKind::Variable);
+                self.define(name);
+                self.resolve_stmt(handler);
+                self.end_scope();
+            }
+            Stmt::Throw { value, .. } => {
+                self.resolve_expr(value);
+            }
+            Stmt::Enum { name, variants, .. } => {
+                self.declare(name, DeclarationKind::Class);
+                self.define(name);
+                for (i, variant) in variants.iter().enumerate() {
+                    if let Some(first) = variants[..i].iter().find(|v| v.lexeme == variant.lexeme) {
+                        let error = ManoError::Resolution {
+                            message: format!(
+                                "'{}' já tá nas opções de '{}', mano!",
+                                variant.lexeme, name.lexeme
+                            ),
+                            span: variant.span.clone(),
+                        };
+                        self.errors
+                            .push(error.with_related(first.span.clone(), "a primeira tá aqui"));
+                    }
+                }
+            }
             Stmt::Class {
                 name,
                 superclass,
                 methods,
-                ..
+                span: class_span,
             } => {
-                self.declare(name);
+                self.declare(name, DeclarationKind::Class);
                 self.define(name);
 
                 let enclosing_class = self.current_class;
@@ -244,7 +442,7 @@ impl Resolver {
                     self.current_class = ClassType::Subclass;
 
                     // Create a scope for "mestre" that wraps oCara and methods
-                    self.begin_scope();
+                    self.begin_scope(class_span.clone());
                     if let Some(scope) = self.scopes.last_mut() {
                         scope.insert(
                             "mestre".to_string(),
@@ -253,6 +451,7 @@ impl Resolver {
                                 used: true, // Don't warn about unused mestre
                                 span: name.span.clone(),
                                 slot: 0,
+                                declaration: None,
                             },
                         );
                     }
@@ -261,7 +460,7 @@ impl Resolver {
                 }
 
                 // Create a scope for "oCara" that wraps all methods
-                self.begin_scope();
+                self.begin_scope(class_span.clone());
                 // Define "oCara" in this scope (slot 0, first in scope)
                 if let Some(scope) = self.scopes.last_mut() {
                     scope.insert(
@@ -271,20 +470,37 @@ impl Resolver {
                             used: true, // Don't warn about unused oCara
                             span: name.span.clone(),
                             slot: 0,
+                            declaration: None,
                         },
                     );
                 }
 
+                self.check_duplicate_methods(name, methods);
+
                 for method in methods {
                     if let Stmt::Function {
                         name,
                         params,
                         body,
                         is_static,
+                        is_getter,
+                        span,
                         ..
                     } = method
                     {
-                        let fn_type = if name.lexeme == INITIALIZER_NAME {
+                        let is_initializer = &*name.lexeme == INITIALIZER_NAME;
+                        if is_initializer && *is_static {
+                            self.errors.push(ManoError::Resolution {
+                                message: STATIC_INITIALIZER.to_string(),
+                                span: name.span.clone(),
+                            });
+                        } else if is_initializer && *is_getter {
+                            self.errors.push(ManoError::Resolution {
+                                message: GETTER_INITIALIZER.to_string(),
+                                span: name.span.clone(),
+                            });
+                        }
+                        let fn_type = if is_initializer && !*is_static {
                             FunctionType::Initializer
                         } else {
                             FunctionType::Method
@@ -296,7 +512,7 @@ impl Resolver {
                             self.current_class = ClassType::StaticMethod;
                         }
 
-                        self.resolve_function(params, body, fn_type);
+                        self.resolve_function(params, body, fn_type, span.clone());
 
                         if *is_static {
                             self.current_class = saved_class;
@@ -316,13 +532,21 @@ impl Resolver {
         }
     }
 
-    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], fn_type: FunctionType) {
+    fn resolve_function(
+        &mut self,
+        params: &[Token],
+        body: &[Stmt],
+        fn_type: FunctionType,
+        span: Span,
+    ) {
         let enclosing_function = self.current_function;
         self.current_function = fn_type;
+        // A loop around the function doesn't make saiFora valid in its body
+        let enclosing_loop = std::mem::replace(&mut self.in_loop, false);
 
-        self.begin_scope();
+        self.begin_function_scope(span);
         for param in params {
-            self.declare(param);
+            self.declare(param, DeclarationKind::Parameter);
             self.define(param);
         }
         for stmt in body {
@@ -331,6 +555,7 @@ impl Resolver {
         self.end_scope();
 
         self.current_function = enclosing_function;
+        self.in_loop = enclosing_loop;
     }
 
     fn resolve_expr(&mut self, expr: &Expr) {
@@ -348,7 +573,7 @@ impl Resolver {
                 if let Some(decl) = declaring
                     && decl.lexeme == name.lexeme
                     && let Some(scope) = self.scopes.last()
-                    && scope.get(&name.lexeme).is_some_and(|info| !info.defined)
+                    && scope.get(&*name.lexeme).is_some_and(|info| !info.defined)
                 {
                     self.errors.push(ManoError::Resolution {
                         message: format!(
@@ -360,7 +585,7 @@ impl Resolver {
                 }
                 self.resolve_local(name);
             }
-            Expr::Assign { name, value } => {
+            Expr::Assign { name, value, .. } => {
                 self.resolve_expr(value);
                 self.resolve_local(name);
             }
@@ -402,7 +627,14 @@ impl Resolver {
                 }
             }
             Expr::Lambda { params, body } => {
-                self.resolve_function(params, body, FunctionType::Function);
+                // Lambdas carry no span, so cover their params and body
+                let start = params
+                    .first()
+                    .map(|param| param.span.start)
+                    .or_else(|| body.first().map(|stmt| stmt.span().start))
+                    .unwrap_or(0);
+                let end = body.last().map_or(start, |stmt| stmt.span().end);
+                self.resolve_function(params, body, FunctionType::Function, start..end);
             }
             Expr::Literal { .. } => {}
             Expr::Get { object, .. } => {
@@ -412,6 +644,25 @@ impl Resolver {
                 self.resolve_expr(value);
                 self.resolve_expr(object);
             }
+            Expr::ListLiteral { elements, .. } => {
+                for element in elements {
+                    self.resolve_expr(element);
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            }
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+                self.resolve_expr(value);
+            }
             Expr::This { keyword } => {
                 if self.current_class == ClassType::None {
                     self.errors.push(ManoError::Resolution {
@@ -450,6 +701,7 @@ impl Resolver {
                     }
                 }
             }
+            Expr::Error { .. } => {}
         }
     }
 
@@ -463,7 +715,7 @@ impl Resolver {
             self.errors.push(ManoError::Resolution {
                 message: format!(
                     "E aí, chapa! Menos unário só funciona com número, não com {}!",
-                    Self::literal_type_name(value)
+                    value.kind_name()
                 ),
                 span: operator.span.clone(),
             });
@@ -480,40 +732,46 @@ impl Resolver {
         };
 
         match operator.token_type {
-            // Arithmetic: -, *, /, % require numbers
-            TokenType::Minus | TokenType::Star | TokenType::Slash | TokenType::Percent => {
+            // Arithmetic and bitwise: -, *, /, %, &, |, ^, <<, >> require numbers
+            TokenType::Minus
+            | TokenType::Star
+            | TokenType::Slash
+            | TokenType::Percent
+            | TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater
                 if !matches!(left_lit, Literal::Number(_))
-                    || !matches!(right_lit, Literal::Number(_))
-                {
-                    self.errors.push(ManoError::Resolution {
-                        message: format!(
-                            "Ô, parceiro! '{}' só funciona com números, não com {} e {}!",
-                            operator.lexeme,
-                            Self::literal_type_name(left_lit),
-                            Self::literal_type_name(right_lit)
-                        ),
-                        span: operator.span.clone(),
-                    });
-                }
+                    || !matches!(right_lit, Literal::Number(_)) =>
+            {
+                self.errors.push(ManoError::Resolution {
+                    message: format!(
+                        "Ô, parceiro! '{}' só funciona com números, não com {} e {}!",
+                        operator.lexeme,
+                        left_lit.kind_name(),
+                        right_lit.kind_name()
+                    ),
+                    span: operator.span.clone(),
+                });
             }
             // Comparison: <, >, <=, >= require numbers
             TokenType::Less
             | TokenType::Greater
             | TokenType::LessEqual
-            | TokenType::GreaterEqual => {
+            | TokenType::GreaterEqual
                 if !matches!(left_lit, Literal::Number(_))
-                    || !matches!(right_lit, Literal::Number(_))
-                {
-                    self.errors.push(ManoError::Resolution {
-                        message: format!(
-                            "Pô, mano! Comparação '{}' só rola com números, não com {} e {}!",
-                            operator.lexeme,
-                            Self::literal_type_name(left_lit),
-                            Self::literal_type_name(right_lit)
-                        ),
-                        span: operator.span.clone(),
-                    });
-                }
+                    || !matches!(right_lit, Literal::Number(_)) =>
+            {
+                self.errors.push(ManoError::Resolution {
+                    message: format!(
+                        "Pô, mano! Comparação '{}' só rola com números, não com {} e {}!",
+                        operator.lexeme,
+                        left_lit.kind_name(),
+                        right_lit.kind_name()
+                    ),
+                    span: operator.span.clone(),
+                });
             }
             // Plus: either both numbers or both strings
             TokenType::Plus => {
@@ -521,13 +779,22 @@ impl Resolver {
                     && matches!(right_lit, Literal::Number(_));
                 let both_strings = matches!(left_lit, Literal::String(_))
                     && matches!(right_lit, Literal::String(_));
+                let both_bytes =
+                    matches!(left_lit, Literal::Bytes(_)) && matches!(right_lit, Literal::Bytes(_));
+                let joins_text = self.text_coercion
+                    && match (left_lit, right_lit) {
+                        (Literal::String(_), other) | (other, Literal::String(_)) => {
+                            other.joins_text()
+                        }
+                        _ => false,
+                    };
 
-                if !both_numbers && !both_strings {
+                if !both_numbers && !both_strings && !both_bytes && !joins_text {
                     self.errors.push(ManoError::Resolution {
                         message: format!(
                             "Aí não dá, mano! '+' só funciona com dois números ou duas strings, não com {} e {}!",
-                            Self::literal_type_name(left_lit),
-                            Self::literal_type_name(right_lit)
+                            left_lit.kind_name(),
+                            right_lit.kind_name()
                         ),
                         span: operator.span.clone(),
                     });
@@ -537,16 +804,6 @@ impl Resolver {
             _ => {}
         }
     }
-
-    /// Get human-readable type name for a literal
-    fn literal_type_name(lit: &Literal) -> &'static str {
-        match lit {
-            Literal::Number(_) => "número",
-            Literal::String(_) => "string",
-            Literal::Bool(_) => "booleano",
-            Literal::Nil => "nadaNão",
-        }
-    }
 }
 
 #[cfg(test)]
@@ -558,7 +815,7 @@ mod tests {
     fn make_token(lexeme: &str, span: Span) -> Token {
         Token {
             token_type: TokenType::Identifier,
-            lexeme: lexeme.to_string(),
+            lexeme: lexeme.into(),
             literal: None,
             span,
         }
@@ -718,11 +975,13 @@ mod tests {
         assert!(result.is_err());
         let errors = result.unwrap_err();
         assert_eq!(errors.len(), 1);
-        if let ManoError::Resolution { message, .. } = &errors[0] {
+        if let ManoError::Resolution { message, .. } = errors[0].primary() {
             assert!(message.contains("Já tem uma"));
         } else {
             panic!("Expected Resolution error");
         }
+        // Also pointing at the first `_a`
+        assert_eq!(errors[0].related()[0].span, 10..12);
     }
 
     #[test]
@@ -759,7 +1018,7 @@ mod tests {
         let stmts = vec![Stmt::Return {
             keyword: Token {
                 token_type: TokenType::Return,
-                lexeme: "toma".to_string(),
+                lexeme: "toma".into(),
                 literal: None,
                 span: 0..4,
             },
@@ -790,7 +1049,7 @@ mod tests {
             body: vec![Stmt::Return {
                 keyword: Token {
                     token_type: TokenType::Return,
-                    lexeme: "toma".to_string(),
+                    lexeme: "toma".into(),
                     literal: None,
                     span: 25..29,
                 },
@@ -801,6 +1060,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..40,
         }];
 
@@ -823,6 +1084,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..40,
         }];
 
@@ -859,11 +1122,15 @@ mod tests {
                     }],
                     is_static: false,
                     is_getter: false,
+                    is_memoized: false,
+                    is_variadic: false,
                     span: 45..90,
                 },
             ],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..100,
         }];
 
@@ -899,7 +1166,7 @@ mod tests {
                                 }),
                                 operator: Token {
                                     token_type: TokenType::Plus,
-                                    lexeme: "+".to_string(),
+                                    lexeme: "+".into(),
                                     literal: None,
                                     span: 62..63,
                                 },
@@ -980,6 +1247,7 @@ mod tests {
                         statements: vec![],
                         span: 35..40,
                     }),
+                    increment: None,
                     span: 20..45,
                 },
             ],
@@ -1073,9 +1341,13 @@ mod tests {
                     value: Literal::Bool(true),
                 },
                 body: Box::new(Stmt::Block {
-                    statements: vec![Stmt::Break { span: 30..37 }],
+                    statements: vec![Stmt::Break {
+                        keyword: make_token("saiFora", 30..37),
+                        span: 30..38,
+                    }],
                     span: 25..40,
                 }),
+                increment: None,
                 span: 10..45,
             }],
             span: 0..50,
@@ -1085,6 +1357,149 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn resolver_errors_on_continue_outside_loop() {
+        // segueReto;
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::Continue {
+            keyword: make_token("segueReto", 0..9),
+            span: 0..10,
+        }];
+
+        let errors = resolver.resolve(&stmts).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ManoError::Resolution { message, span }]
+                if message.contains("segueReto fora de um loop") && span == &(0..9)
+        ));
+    }
+
+    #[test]
+    fn resolver_errors_on_import_inside_a_block() {
+        // { chama "util"; }
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::Block {
+            statements: vec![Stmt::Import {
+                keyword: make_token("chama", 2..7),
+                path: make_token("\"util\"", 8..14),
+                span: 2..15,
+            }],
+            span: 0..17,
+        }];
+
+        let errors = resolver.resolve(&stmts).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ManoError::Resolution { message, span }]
+                if message.contains("topo do arquivo") && span == &(2..7)
+        ));
+    }
+
+    #[test]
+    fn resolver_scopes_the_error_to_the_handler() {
+        // tentaAi {} seDeuRuim (erro) { salve erro; }
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::Try {
+            body: Box::new(Stmt::Block {
+                statements: vec![],
+                span: 8..10,
+            }),
+            name: make_token("erro", 22..26),
+            handler: Box::new(Stmt::Block {
+                statements: vec![Stmt::Print {
+                    expression: Expr::Variable {
+                        name: make_token("erro", 36..40),
+                    },
+                    span: 30..41,
+                }],
+                span: 28..43,
+            }),
+            span: 0..43,
+        }];
+
+        let resolutions = resolver.resolve(&stmts).unwrap();
+        // From inside the handler's block, one scope up
+        assert_eq!(resolutions.get(&(36..40)), Some(&(1, 0)));
+    }
+
+    #[test]
+    fn resolver_errors_on_unused_error_in_handler() {
+        // tentaAi {} seDeuRuim (erro) {}
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::Try {
+            body: Box::new(Stmt::Block {
+                statements: vec![],
+                span: 8..10,
+            }),
+            name: make_token("erro", 22..26),
+            handler: Box::new(Stmt::Block {
+                statements: vec![],
+                span: 28..30,
+            }),
+            span: 0..30,
+        }];
+
+        let errors = resolver.resolve(&stmts).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ManoError::Resolution { message, span }]
+                if message.contains("'erro' nunca foi usada") && span == &(22..26)
+        ));
+    }
+
+    #[test]
+    fn resolver_errors_on_break_outside_loop() {
+        // saiFora;
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::Break {
+            keyword: make_token("saiFora", 0..7),
+            span: 0..8,
+        }];
+
+        let errors = resolver.resolve(&stmts).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ManoError::Resolution { message, span } => {
+                assert!(message.contains("saiFora fora de um loop"));
+                assert_eq!(span, &(0..7));
+            }
+            _ => panic!("Expected Resolution error"),
+        }
+    }
+
+    #[test]
+    fn resolver_errors_on_break_in_function_inside_loop() {
+        // segueOFluxo (firmeza) { olhaEssaFita _f() { saiFora; } }
+        let resolver = Resolver::new();
+        let stmts = vec![Stmt::While {
+            condition: Expr::Literal {
+                value: Literal::Bool(true),
+            },
+            body: Box::new(Stmt::Block {
+                statements: vec![Stmt::Function {
+                    name: make_token("_f", 37..39),
+                    params: vec![],
+                    body: vec![Stmt::Break {
+                        keyword: make_token("saiFora", 44..51),
+                        span: 44..52,
+                    }],
+                    is_static: false,
+                    is_getter: false,
+                    is_memoized: false,
+                    is_variadic: false,
+                    span: 24..54,
+                }],
+                span: 22..56,
+            }),
+            increment: None,
+            span: 0..56,
+        }];
+
+        let errors = resolver.resolve(&stmts).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], ManoError::Resolution { span, .. } if span == &(44..51)));
+    }
+
     #[test]
     fn resolver_resolves_logical_expression() {
         // { seLiga x = firmeza; seLiga _y = x tamoJunto firmeza; }
@@ -1106,7 +1521,7 @@ mod tests {
                         }),
                         operator: Token {
                             token_type: TokenType::And,
-                            lexeme: "tamoJunto".to_string(),
+                            lexeme: "tamoJunto".into(),
                             literal: None,
                             span: 42..51,
                         },
@@ -1295,7 +1710,7 @@ mod tests {
             expression: Expr::Unary {
                 operator: Token {
                     token_type: TokenType::Minus,
-                    lexeme: "-".to_string(),
+                    lexeme: "-".into(),
                     literal: None,
                     span: 0..1,
                 },
@@ -1321,7 +1736,7 @@ mod tests {
             expression: Expr::Unary {
                 operator: Token {
                     token_type: TokenType::Minus,
-                    lexeme: "-".to_string(),
+                    lexeme: "-".into(),
                     literal: None,
                     span: 0..1,
                 },
@@ -1344,7 +1759,7 @@ mod tests {
             expression: Expr::Unary {
                 operator: Token {
                     token_type: TokenType::Minus,
-                    lexeme: "-".to_string(),
+                    lexeme: "-".into(),
                     literal: None,
                     span: 0..1,
                 },
@@ -1367,7 +1782,7 @@ mod tests {
             expression: Expr::Unary {
                 operator: Token {
                     token_type: TokenType::Minus,
-                    lexeme: "-".to_string(),
+                    lexeme: "-".into(),
                     literal: None,
                     span: 0..1,
                 },
@@ -1393,7 +1808,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Minus,
-                    lexeme: "-".to_string(),
+                    lexeme: "-".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -1419,7 +1834,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Plus,
-                    lexeme: "+".to_string(),
+                    lexeme: "+".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1445,7 +1860,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Less,
-                    lexeme: "<".to_string(),
+                    lexeme: "<".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1471,7 +1886,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Greater,
-                    lexeme: ">".to_string(),
+                    lexeme: ">".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1497,7 +1912,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::LessEqual,
-                    lexeme: "<=".to_string(),
+                    lexeme: "<=".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1523,7 +1938,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::GreaterEqual,
-                    lexeme: ">=".to_string(),
+                    lexeme: ">=".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1549,7 +1964,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Plus,
-                    lexeme: "+".to_string(),
+                    lexeme: "+".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1575,7 +1990,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Plus,
-                    lexeme: "+".to_string(),
+                    lexeme: "+".into(),
                     literal: None,
                     span: 7..8,
                 },
@@ -1601,7 +2016,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Less,
-                    lexeme: "<".to_string(),
+                    lexeme: "<".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1627,7 +2042,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::Greater,
-                    lexeme: ">".to_string(),
+                    lexeme: ">".into(),
                     literal: None,
                     span: 2..3,
                 },
@@ -1653,7 +2068,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::LessEqual,
-                    lexeme: "<=".to_string(),
+                    lexeme: "<=".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1679,7 +2094,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::GreaterEqual,
-                    lexeme: ">=".to_string(),
+                    lexeme: ">=".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1705,7 +2120,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::EqualEqual,
-                    lexeme: "==".to_string(),
+                    lexeme: "==".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1731,7 +2146,7 @@ mod tests {
                 }),
                 operator: Token {
                     token_type: TokenType::BangEqual,
-                    lexeme: "!=".to_string(),
+                    lexeme: "!=".into(),
                     literal: None,
                     span: 2..4,
                 },
@@ -1753,7 +2168,7 @@ mod tests {
         let stmts = vec![Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 8..14,
             },
@@ -1761,13 +2176,13 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 17..22,
                 },
                 params: vec![Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "msg".to_string(),
+                    lexeme: "msg".into(),
                     literal: None,
                     span: 23..26,
                 }],
@@ -1775,7 +2190,7 @@ mod tests {
                     expression: Expr::Variable {
                         name: Token {
                             token_type: TokenType::Identifier,
-                            lexeme: "msg".to_string(),
+                            lexeme: "msg".into(),
                             literal: None,
                             span: 35..38,
                         },
@@ -1784,6 +2199,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 17..42,
             }],
             span: 0..44,
@@ -1799,13 +2216,13 @@ mod tests {
     #[test]
     fn resolver_errors_on_duplicate_class_in_same_scope() {
         let mut resolver = Resolver::new();
-        resolver.begin_scope();
+        resolver.begin_scope(0..0);
 
         let stmts = vec![
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pessoa".to_string(),
+                    lexeme: "Pessoa".into(),
                     literal: None,
                     span: 8..14,
                 },
@@ -1816,7 +2233,7 @@ mod tests {
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pessoa".to_string(),
+                    lexeme: "Pessoa".into(),
                     literal: None,
                     span: 26..32,
                 },
@@ -1831,7 +2248,7 @@ mod tests {
         let errors = result.unwrap_err();
         assert_eq!(errors.len(), 1);
         assert!(
-            matches!(&errors[0], ManoError::Resolution { message, .. } if message.contains("Já tem uma"))
+            matches!(errors[0].primary(), ManoError::Resolution { message, .. } if message.contains("Já tem uma"))
         );
     }
 
@@ -1844,14 +2261,14 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "pessoa".to_string(),
+                        lexeme: "pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "nome".to_string(),
+                    lexeme: "nome".into(),
                     literal: None,
                     span: 7..11,
                 },
@@ -1872,17 +2289,18 @@ mod tests {
                 object: Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "pessoa".to_string(),
+                        lexeme: "pessoa".into(),
                         literal: None,
                         span: 0..6,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "nome".to_string(),
+                    lexeme: "nome".into(),
                     literal: None,
                     span: 7..11,
                 },
+                operator: None,
                 value: Box::new(Expr::Literal {
                     value: Literal::String("João".to_string()),
                 }),
@@ -1904,21 +2322,21 @@ mod tests {
                     object: Box::new(Expr::Variable {
                         name: Token {
                             token_type: TokenType::Identifier,
-                            lexeme: "pessoa".to_string(),
+                            lexeme: "pessoa".into(),
                             literal: None,
                             span: 0..6,
                         },
                     }),
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "endereco".to_string(),
+                        lexeme: "endereco".into(),
                         literal: None,
                         span: 7..15,
                     },
                 }),
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "cidade".to_string(),
+                    lexeme: "cidade".into(),
                     literal: None,
                     span: 16..22,
                 },
@@ -1937,7 +2355,7 @@ mod tests {
         let stmts = vec![Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 8..14,
             },
@@ -1945,7 +2363,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "getOCara".to_string(),
+                    lexeme: "getOCara".into(),
                     literal: None,
                     span: 17..25,
                 },
@@ -1953,14 +2371,14 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 30..34,
                     },
                     value: Some(Expr::This {
                         keyword: Token {
                             token_type: TokenType::This,
-                            lexeme: "oCara".to_string(),
+                            lexeme: "oCara".into(),
                             literal: None,
                             span: 35..40,
                         },
@@ -1969,6 +2387,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 17..45,
             }],
             span: 0..50,
@@ -1986,7 +2406,7 @@ mod tests {
             expression: Expr::This {
                 keyword: Token {
                     token_type: TokenType::This,
-                    lexeme: "oCara".to_string(),
+                    lexeme: "oCara".into(),
                     literal: None,
                     span: 0..5,
                 },
@@ -2009,7 +2429,7 @@ mod tests {
         let stmts = vec![Stmt::Function {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "teste".to_string(),
+                lexeme: "teste".into(),
                 literal: None,
                 span: 0..5,
             },
@@ -2018,7 +2438,7 @@ mod tests {
                 expression: Expr::This {
                     keyword: Token {
                         token_type: TokenType::This,
-                        lexeme: "oCara".to_string(),
+                        lexeme: "oCara".into(),
                         literal: None,
                         span: 20..25,
                     },
@@ -2027,6 +2447,8 @@ mod tests {
             }],
             is_static: false,
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
             span: 0..30,
         }];
 
@@ -2048,7 +2470,7 @@ mod tests {
         let stmts = vec![Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Pessoa".to_string(),
+                lexeme: "Pessoa".into(),
                 literal: None,
                 span: 8..14,
             },
@@ -2056,7 +2478,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "teste".to_string(),
+                    lexeme: "teste".into(),
                     literal: None,
                     span: 24..29,
                 },
@@ -2064,14 +2486,14 @@ mod tests {
                 body: vec![Stmt::Return {
                     keyword: Token {
                         token_type: TokenType::Return,
-                        lexeme: "toma".to_string(),
+                        lexeme: "toma".into(),
                         literal: None,
                         span: 35..39,
                     },
                     value: Some(Expr::This {
                         keyword: Token {
                             token_type: TokenType::This,
-                            lexeme: "oCara".to_string(),
+                            lexeme: "oCara".into(),
                             literal: None,
                             span: 40..45,
                         },
@@ -2080,6 +2502,8 @@ mod tests {
                 }],
                 is_static: true,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 24..50,
             }],
             span: 0..55,
@@ -2100,14 +2524,14 @@ mod tests {
         let stmts = vec![Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Foo".to_string(),
+                lexeme: "Foo".into(),
                 literal: None,
                 span: 8..11,
             },
             superclass: Some(Box::new(Expr::Variable {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Foo".to_string(),
+                    lexeme: "Foo".into(),
                     literal: None,
                     span: 14..17,
                 },
@@ -2138,13 +2562,13 @@ mod tests {
             expression: Expr::Super {
                 keyword: Token {
                     token_type: TokenType::Super,
-                    lexeme: "mestre".to_string(),
+                    lexeme: "mestre".into(),
                     literal: None,
                     span: 0..6,
                 },
                 method: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "falar".to_string(),
+                    lexeme: "falar".into(),
                     literal: None,
                     span: 7..12,
                 },
@@ -2167,7 +2591,7 @@ mod tests {
         let stmts = vec![Stmt::Class {
             name: Token {
                 token_type: TokenType::Identifier,
-                lexeme: "Foo".to_string(),
+                lexeme: "Foo".into(),
                 literal: None,
                 span: 8..11,
             },
@@ -2175,7 +2599,7 @@ mod tests {
             methods: vec![Stmt::Function {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "test".to_string(),
+                    lexeme: "test".into(),
                     literal: None,
                     span: 14..18,
                 },
@@ -2184,13 +2608,13 @@ mod tests {
                     expression: Expr::Super {
                         keyword: Token {
                             token_type: TokenType::Super,
-                            lexeme: "mestre".to_string(),
+                            lexeme: "mestre".into(),
                             literal: None,
                             span: 24..30,
                         },
                         method: Token {
                             token_type: TokenType::Identifier,
-                            lexeme: "bar".to_string(),
+                            lexeme: "bar".into(),
                             literal: None,
                             span: 31..34,
                         },
@@ -2199,6 +2623,8 @@ mod tests {
                 }],
                 is_static: false,
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
                 span: 14..40,
             }],
             span: 0..45,
@@ -2223,7 +2649,7 @@ mod tests {
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 8..11,
                 },
@@ -2234,14 +2660,14 @@ mod tests {
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Filho".to_string(),
+                    lexeme: "Filho".into(),
                     literal: None,
                     span: 23..28,
                 },
                 superclass: Some(Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pai".to_string(),
+                        lexeme: "Pai".into(),
                         literal: None,
                         span: 31..34,
                     },
@@ -2249,7 +2675,7 @@ mod tests {
                 methods: vec![Stmt::Function {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "test".to_string(),
+                        lexeme: "test".into(),
                         literal: None,
                         span: 37..41,
                     },
@@ -2258,13 +2684,13 @@ mod tests {
                         expression: Expr::Super {
                             keyword: Token {
                                 token_type: TokenType::Super,
-                                lexeme: "mestre".to_string(),
+                                lexeme: "mestre".into(),
                                 literal: None,
                                 span: 47..53,
                             },
                             method: Token {
                                 token_type: TokenType::Identifier,
-                                lexeme: "foo".to_string(),
+                                lexeme: "foo".into(),
                                 literal: None,
                                 span: 54..57,
                             },
@@ -2273,6 +2699,8 @@ mod tests {
                     }],
                     is_static: false,
                     is_getter: false,
+                    is_memoized: false,
+                    is_variadic: false,
                     span: 37..63,
                 }],
                 span: 15..66,
@@ -2296,7 +2724,7 @@ mod tests {
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Pai".to_string(),
+                    lexeme: "Pai".into(),
                     literal: None,
                     span: 8..11,
                 },
@@ -2307,14 +2735,14 @@ mod tests {
             Stmt::Class {
                 name: Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "Filho".to_string(),
+                    lexeme: "Filho".into(),
                     literal: None,
                     span: 23..28,
                 },
                 superclass: Some(Box::new(Expr::Variable {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "Pai".to_string(),
+                        lexeme: "Pai".into(),
                         literal: None,
                         span: 31..34,
                     },
@@ -2322,7 +2750,7 @@ mod tests {
                 methods: vec![Stmt::Function {
                     name: Token {
                         token_type: TokenType::Identifier,
-                        lexeme: "test".to_string(),
+                        lexeme: "test".into(),
                         literal: None,
                         span: 37..41,
                     },
@@ -2331,13 +2759,13 @@ mod tests {
                         expression: Expr::Super {
                             keyword: Token {
                                 token_type: TokenType::Super,
-                                lexeme: "mestre".to_string(),
+                                lexeme: "mestre".into(),
                                 literal: None,
                                 span: 47..53,
                             },
                             method: Token {
                                 token_type: TokenType::Identifier,
-                                lexeme: "foo".to_string(),
+                                lexeme: "foo".into(),
                                 literal: None,
                                 span: 54..57,
                             },
@@ -2346,6 +2774,8 @@ mod tests {
                     }],
                     is_static: false,
                     is_getter: false,
+                    is_memoized: false,
+                    is_variadic: false,
                     span: 37..63,
                 }],
                 span: 15..66,
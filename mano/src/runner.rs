@@ -1,8 +1,10 @@
 //! Runner trait for unified interpreter/VM execution
 
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use crate::ManoError;
+use crate::{ManoError, Symbol, SymbolKind};
 
 /// Trait for running mano source code.
 ///
@@ -14,11 +16,37 @@ pub trait Runner {
     /// Returns `Ok(())` on success, or a vector of errors on failure.
     fn run<W: Write>(&mut self, source: &str, stdout: W) -> Result<(), Vec<ManoError>>;
 
+    /// Stop running at the next statement when `flag` gets set, e.g. by a
+    /// Ctrl+C handler, with [`ManoError::Interrupted`]. The flag is cleared
+    /// once that happens. Backends that can't be interrupted ignore it.
+    fn set_interrupt(&mut self, _flag: Arc<AtomicBool>) {}
+
     /// Get the names of all variables currently defined in the environment.
     ///
     /// Used for REPL autocompletion.
     fn variable_names(&self) -> Vec<String>;
 
+    /// Names in scope with their kinds, across the whole scope chain.
+    ///
+    /// Used for kinded REPL completions. Native fitas are left out unless
+    /// `include_natives` is set. Defaults to every variable name as a
+    /// `Variable`, for backends that can't tell kinds apart.
+    fn symbols(&self, _include_natives: bool) -> Vec<Symbol> {
+        self.variable_names()
+            .into_iter()
+            .map(|name| Symbol {
+                name,
+                kind: SymbolKind::Variable,
+            })
+            .collect()
+    }
+
+    /// Fields and methods of the value bound to `name`, for completing `name.`
+    /// in the REPL. Backends without objects return nothing.
+    fn members(&self, _name: &str) -> Vec<Symbol> {
+        Vec::new()
+    }
+
     /// Whether the REPL should auto-print expressions.
     ///
     /// Returns true by default. The VM returns false since it only handles
@@ -26,6 +54,21 @@ pub trait Runner {
     fn supports_auto_print(&self) -> bool {
         true
     }
+
+    /// Disassemble compiled bytecode, for the REPL `:desmonta` command.
+    ///
+    /// `name` selects a function; `None` means the last code that ran.
+    /// Only the VM has bytecode, so the default is an error.
+    fn disassemble(&self, _name: Option<&str>) -> Result<String, String> {
+        Err("Só dá pra desmontar no modo --vm, mano!".to_string())
+    }
+
+    /// The global state as a script that recreates it, for `:salvaSessao`.
+    ///
+    /// Loading a session is just running that script.
+    fn save_session(&self) -> Result<String, String> {
+        Err("Só dá pra salvar sessão no interpretador, mano!".to_string())
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +101,28 @@ mod tests {
         assert_eq!(output_str.trim(), "42");
     }
 
+    #[test]
+    fn mano_runner_cannot_disassemble() {
+        let mano = Mano::new();
+        assert!(Runner::disassemble(&mano, None).is_err());
+    }
+
+    #[test]
+    fn mano_runner_symbols_have_kinds() {
+        let mut mano = Mano::new();
+        let _ = Runner::run(
+            &mut mano,
+            "seLiga x = 1; olhaEssaFita f() {} bagulho B {}",
+            &mut Vec::new(),
+        );
+        let symbols = Runner::symbols(&mano, false);
+        let kind_of = |name: &str| symbols.iter().find(|s| s.name == name).map(|s| s.kind);
+        assert_eq!(kind_of("x"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("f"), Some(SymbolKind::Function));
+        assert_eq!(kind_of("B"), Some(SymbolKind::Class));
+        assert_eq!(kind_of("fazTeuCorre"), None);
+    }
+
     #[test]
     fn mano_runner_variable_names_returns_defined_vars() {
         let mut mano = Mano::new();
@@ -0,0 +1,111 @@
+//! What side effects a program may have, declared in one place by whoever
+//! embeds mano instead of one setter per native
+
+use std::time::Duration;
+
+use crate::error::ManoError;
+
+/// The capabilities natives check before touching the world outside the
+/// program. The default allows everything but running other programs;
+/// [`SandboxPolicy::deny_all`] allows nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxPolicy {
+    /// Reading and writing files
+    pub fs: bool,
+    /// Talking to the network
+    pub net: bool,
+    /// Reading the environment: variables and the script's arguments
+    pub env: bool,
+    /// Running other programs
+    pub process: bool,
+    /// Reading what the user types, with `leAi`
+    pub input: bool,
+    /// How finely `fazTeuCorre` tells the time, e.g. whole seconds so timing
+    /// can't leak much; `None` hides the clock
+    pub clock: Option<Duration>,
+    /// Seed for `aleatorio` and `aleatorioEntre`, so runs repeat the same
+    /// numbers; `None` seeds from the clock
+    pub seed: Option<u64>,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            fs: true,
+            net: true,
+            env: true,
+            process: false,
+            input: true,
+            clock: Some(Duration::ZERO),
+            seed: None,
+        }
+    }
+}
+
+impl SandboxPolicy {
+    /// No files, network, environment, processes, input or clock
+    pub fn deny_all() -> Self {
+        Self {
+            fs: false,
+            net: false,
+            env: false,
+            process: false,
+            input: false,
+            clock: None,
+            seed: None,
+        }
+    }
+
+    /// Seconds since the epoch, rounded down to the clock granularity
+    pub(crate) fn clock_seconds(&self, now: Duration) -> Option<f64> {
+        let granularity = self.clock?;
+        if granularity.is_zero() {
+            return Some(now.as_secs_f64());
+        }
+        let nanos = now.as_nanos() / granularity.as_nanos() * granularity.as_nanos();
+        Some(nanos as f64 / 1e9)
+    }
+}
+
+/// The error for a native the policy doesn't allow. The interpreter puts it
+/// at the call.
+pub(crate) fn denied(native: &str) -> ManoError {
+    ManoError::Runtime {
+        message: format!("A sandbox não deixa usar {}, mano!", native),
+        span: 0..0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_is_rounded_down_to_the_granularity() {
+        let now = Duration::from_millis(12_345);
+        let policy = |clock| SandboxPolicy {
+            clock,
+            ..SandboxPolicy::default()
+        };
+        assert_eq!(
+            policy(Some(Duration::ZERO)).clock_seconds(now),
+            Some(now.as_secs_f64())
+        );
+        assert_eq!(
+            policy(Some(Duration::from_secs(1))).clock_seconds(now),
+            Some(12.0)
+        );
+        assert_eq!(
+            policy(Some(Duration::from_millis(100))).clock_seconds(now),
+            Some(12.3)
+        );
+        // Years of milliseconds don't overflow
+        let years = Duration::from_secs(60 * 365 * 24 * 3600);
+        assert!(
+            policy(Some(Duration::from_millis(1)))
+                .clock_seconds(years)
+                .is_some()
+        );
+        assert_eq!(policy(None).clock_seconds(now), None);
+    }
+}
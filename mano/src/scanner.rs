@@ -1,6 +1,10 @@
+use crate::dialect::Dialect;
 use crate::error::ManoError;
 use crate::token::{Literal, Token, TokenType};
 use phf::phf_map;
+use std::borrow::Cow;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
 use unicode_properties::UnicodeEmoji;
 
 /// Check if a character can start an identifier
@@ -10,26 +14,32 @@ pub fn is_identifier_start(c: char) -> bool {
 
 /// Check if a character can continue an identifier
 pub fn is_identifier_char(c: char) -> bool {
-    c.is_alphanumeric() || c == '_' || c.is_emoji_char()
+    c.is_alphanumeric() || c == '_' || c.is_emoji_char() || is_combining_mark(c)
 }
 
 /// All mano keywords with their token types (compile-time perfect hash map)
 pub static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "bagulho" => TokenType::Class,
+    "chama" => TokenType::Import,
     "firmeza" => TokenType::True,
+    "joga" => TokenType::Throw,
     "mestre" => TokenType::Super,
     "nadaNão" => TokenType::Nil,
     "oCara" => TokenType::This,
     "oiSumida" => TokenType::Print,
     "olhaEssaFita" => TokenType::Fun,
+    "opcoes" => TokenType::Enum,
     "ow" => TokenType::Or,
     "saiFora" => TokenType::Break,
     "salve" => TokenType::Print,
+    "seDeuRuim" => TokenType::Catch,
     "seLiga" => TokenType::Var,
     "sePá" => TokenType::If,
     "seVira" => TokenType::For,
     "segueOFluxo" => TokenType::While,
+    "segueReto" => TokenType::Continue,
     "tamoJunto" => TokenType::And,
+    "tentaAi" => TokenType::Try,
     "toma" => TokenType::Return,
     "treta" => TokenType::False,
     "vacilou" => TokenType::Else,
@@ -40,31 +50,72 @@ pub struct Scanner<'a> {
     start: usize,
     current: usize,
     include_comments: bool,
+    include_whitespace: bool,
     /// Stack of brace depths for nested string interpolations.
     /// Each entry represents an interpolated string we're inside.
     /// The value is the brace nesting depth within that interpolation.
     interpolation_stack: Vec<usize>,
+    /// Extra keywords accepted, from a `// dialeto:` pragma or [`Self::with_dialect`]
+    dialect: Dialect,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(source: &'a str) -> Self {
+        let start = Self::shebang_len(source);
         Self {
             source,
-            start: 0,
-            current: 0,
+            start,
+            current: start,
             include_comments: false,
+            include_whitespace: false,
             interpolation_stack: Vec::new(),
+            dialect: Dialect::from_pragma(source).unwrap_or_default(),
         }
     }
 
     /// Create a scanner that includes comment tokens (for highlighting)
     pub fn with_comments(source: &'a str) -> Self {
+        let start = Self::shebang_len(source);
+        Self {
+            source,
+            start,
+            current: start,
+            include_comments: true,
+            include_whitespace: false,
+            interpolation_stack: Vec::new(),
+            dialect: Dialect::from_pragma(source).unwrap_or_default(),
+        }
+    }
+
+    /// Create a lossless scanner that also emits whitespace and comment tokens.
+    ///
+    /// Concatenating every lexeme reproduces the source exactly (a leading
+    /// shebang line comes out as a `Comment`), which is what the formatter and
+    /// codemod tooling need.
+    pub fn with_trivia(source: &'a str) -> Self {
         Self {
             source,
             start: 0,
             current: 0,
             include_comments: true,
+            include_whitespace: true,
             interpolation_stack: Vec::new(),
+            dialect: Dialect::from_pragma(source).unwrap_or_default(),
+        }
+    }
+
+    /// Accept the dialect's keywords, unless the source picks another with a pragma
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = Dialect::from_pragma(self.source).unwrap_or(dialect);
+        self
+    }
+
+    /// Length of a leading `#!` line, so scripts can be run as executables
+    fn shebang_len(source: &str) -> usize {
+        if source.starts_with("#!") {
+            source.find('\n').unwrap_or(source.len())
+        } else {
+            0
         }
     }
 }
@@ -83,18 +134,35 @@ impl<'a> Iterator for Scanner<'a> {
                 self.current += 1;
                 return Some(Ok(Token {
                     token_type: TokenType::Eof,
-                    lexeme: String::new(),
+                    lexeme: "".into(),
                     literal: None,
                     span,
                 }));
             }
 
             self.start = self.current;
+
+            if self.start == 0 && self.source.starts_with("#!") {
+                // Only reachable in trivia mode, other modes skip the shebang up front
+                while self.peek() != Some('\n') && !self.is_at_end() {
+                    self.advance();
+                }
+                return Some(Ok(self.add_token(TokenType::Comment)));
+            }
+
             let c = self.advance();
 
             match c {
                 // Whitespace
-                ' ' | '\r' | '\t' | '\n' => continue,
+                ' ' | '\r' | '\t' | '\n' => {
+                    if self.include_whitespace {
+                        while matches!(self.peek(), Some(' ' | '\r' | '\t' | '\n')) {
+                            self.advance();
+                        }
+                        return Some(Ok(self.add_token(TokenType::Whitespace)));
+                    }
+                    continue;
+                }
                 // Single-character tokens
                 '(' => return Some(Ok(self.add_token(TokenType::LeftParen))),
                 ')' => return Some(Ok(self.add_token(TokenType::RightParen))),
@@ -118,10 +186,35 @@ impl<'a> Iterator for Scanner<'a> {
                     }
                     return Some(Ok(self.add_token(TokenType::RightBrace)));
                 }
+                '[' => return Some(Ok(self.add_token(TokenType::LeftBracket))),
+                ']' => return Some(Ok(self.add_token(TokenType::RightBracket))),
                 ',' => return Some(Ok(self.add_token(TokenType::Comma))),
-                '.' => return Some(Ok(self.add_token(TokenType::Dot))),
-                '-' => return Some(Ok(self.add_token(TokenType::Minus))),
-                '+' => return Some(Ok(self.add_token(TokenType::Plus))),
+                '.' => {
+                    let token_type = if self.peek() == Some('.') && self.peek_next() == Some('.') {
+                        self.advance();
+                        self.advance();
+                        TokenType::DotDotDot
+                    } else {
+                        TokenType::Dot
+                    };
+                    return Some(Ok(self.add_token(token_type)));
+                }
+                '-' => {
+                    let token_type = if self.match_char('=') {
+                        TokenType::MinusEqual
+                    } else {
+                        TokenType::Minus
+                    };
+                    return Some(Ok(self.add_token(token_type)));
+                }
+                '+' => {
+                    let token_type = if self.match_char('=') {
+                        TokenType::PlusEqual
+                    } else {
+                        TokenType::Plus
+                    };
+                    return Some(Ok(self.add_token(token_type)));
+                }
                 ';' => return Some(Ok(self.add_token(TokenType::Semicolon))),
                 '?' => return Some(Ok(self.add_token(TokenType::Question))),
                 ':' => return Some(Ok(self.add_token(TokenType::Colon))),
@@ -145,12 +238,31 @@ impl<'a> Iterator for Scanner<'a> {
                             return Some(Ok(self.add_token(TokenType::Comment)));
                         }
                         continue;
+                    } else if self.match_char('=') {
+                        return Some(Ok(self.add_token(TokenType::SlashEqual)));
                     } else {
                         return Some(Ok(self.add_token(TokenType::Slash)));
                     }
                 }
-                '*' => return Some(Ok(self.add_token(TokenType::Star))),
-                '%' => return Some(Ok(self.add_token(TokenType::Percent))),
+                '*' => {
+                    let token_type = if self.match_char('=') {
+                        TokenType::StarEqual
+                    } else {
+                        TokenType::Star
+                    };
+                    return Some(Ok(self.add_token(token_type)));
+                }
+                '%' => {
+                    let token_type = if self.match_char('=') {
+                        TokenType::PercentEqual
+                    } else {
+                        TokenType::Percent
+                    };
+                    return Some(Ok(self.add_token(token_type)));
+                }
+                '&' => return Some(Ok(self.add_token(TokenType::Ampersand))),
+                '|' => return Some(Ok(self.add_token(TokenType::Pipe))),
+                '^' => return Some(Ok(self.add_token(TokenType::Caret))),
                 '!' => {
                     let token_type = if self.match_char('=') {
                         TokenType::BangEqual
@@ -170,6 +282,8 @@ impl<'a> Iterator for Scanner<'a> {
                 '<' => {
                     let token_type = if self.match_char('=') {
                         TokenType::LessEqual
+                    } else if self.match_char('<') {
+                        TokenType::LessLess
                     } else {
                         TokenType::Less
                     };
@@ -178,12 +292,29 @@ impl<'a> Iterator for Scanner<'a> {
                 '>' => {
                     let token_type = if self.match_char('=') {
                         TokenType::GreaterEqual
+                    } else if self.match_char('>') {
+                        TokenType::GreaterGreater
                     } else {
                         TokenType::Greater
                     };
                     return Some(Ok(self.add_token(token_type)));
                 }
                 '"' => return Some(self.string()),
+                'b' if self.peek() == Some('"') => {
+                    self.advance(); // consume the opening quote
+                    return Some(self.bytes());
+                }
+                // `#seVm`, `#se NOME`, `#vacilou`, `#fim`: already applied by
+                // `Conditions::select`, so tools just see a comment
+                '#' if self.starts_line() && self.peek().is_some_and(char::is_alphabetic) => {
+                    while self.peek() != Some('\n') && !self.is_at_end() {
+                        self.advance();
+                    }
+                    if self.include_comments {
+                        return Some(Ok(self.add_token(TokenType::Comment)));
+                    }
+                    continue;
+                }
                 c if c.is_ascii_digit() => return Some(Ok(self.number())),
                 c if is_identifier_start(c) => {
                     return Some(Ok(self.identifier()));
@@ -200,6 +331,15 @@ impl<'a> Iterator for Scanner<'a> {
 }
 
 impl<'a> Scanner<'a> {
+    /// Whether only indentation comes before the current token on its line
+    fn starts_line(&self) -> bool {
+        self.source[..self.start]
+            .chars()
+            .rev()
+            .take_while(|&c| c != '\n')
+            .all(|c| c == ' ' || c == '\t')
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
@@ -226,7 +366,7 @@ impl<'a> Scanner<'a> {
     fn add_token(&self, token_type: TokenType) -> Token {
         Token {
             token_type,
-            lexeme: self.source[self.start..self.current].to_string(),
+            lexeme: self.source[self.start..self.current].into(),
             literal: None,
             span: self.start..self.current,
         }
@@ -235,7 +375,7 @@ impl<'a> Scanner<'a> {
     fn add_token_with_literal(&self, token_type: TokenType, literal: Literal) -> Token {
         Token {
             token_type,
-            lexeme: self.source[self.start..self.current].to_string(),
+            lexeme: self.source[self.start..self.current].into(),
             literal: Some(literal),
             span: self.start..self.current,
         }
@@ -252,13 +392,31 @@ impl<'a> Scanner<'a> {
             self.advance();
         }
 
-        let text = &self.source[self.start..self.current];
-        let token_type = Self::keyword(text).unwrap_or(TokenType::Identifier);
-        self.add_token(token_type)
-    }
-
-    fn keyword(text: &str) -> Option<TokenType> {
-        KEYWORDS.get(text).copied()
+        let source = &self.source[self.start..self.current];
+        // `sePá` typed as `a` plus a combining accent is still `sePá`
+        let text: Cow<str> = if is_nfc(source) {
+            Cow::Borrowed(source)
+        } else {
+            Cow::Owned(source.nfc().collect())
+        };
+        let token_type = KEYWORDS
+            .get(&text)
+            .or_else(|| self.dialect.aliases().get(&text))
+            .copied()
+            .unwrap_or(TokenType::Identifier);
+        let mut token = self.add_token(token_type);
+        // Lossless scans keep the source text as is
+        if self.include_whitespace {
+            return token;
+        }
+        if token_type == TokenType::Identifier
+            && let Some(name) = self.dialect.identifier(&text)
+        {
+            token.lexeme = name.into();
+        } else if let Cow::Owned(name) = text {
+            token.lexeme = name.into();
+        }
+        token
     }
 
     fn number(&mut self) -> Token {
@@ -311,7 +469,7 @@ impl<'a> Scanner<'a> {
                     self.interpolation_stack.push(0);
                     return Ok(Token {
                         token_type: TokenType::StringStart,
-                        lexeme: self.source[self.start..self.current].to_string(),
+                        lexeme: self.source[self.start..self.current].into(),
                         literal: Some(Literal::String(value)),
                         span: self.start..self.current,
                     });
@@ -330,6 +488,62 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    /// A `b"..."` literal: the text as UTF-8, with `\xHH`, `\n`, `\t`, `\r`,
+    /// `\0`, `\\` and `\"` escapes for the bytes that can't be typed
+    fn bytes(&mut self) -> Result<Token, ManoError> {
+        let mut bytes = Vec::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(ManoError::Scan {
+                    message: "Fechou os bytes não, maluco!".to_string(),
+                    span: self.start..self.current,
+                });
+            };
+            let escape_start = self.current;
+            self.advance();
+            match c {
+                '"' => {
+                    return Ok(self.add_token_with_literal(TokenType::Bytes, Literal::Bytes(bytes)));
+                }
+                '\\' => {
+                    let escape_end = self.current + self.peek().map_or(0, char::len_utf8);
+                    let (byte, len) = match self.peek() {
+                        Some('n') => (Some(b'\n'), 1),
+                        Some('t') => (Some(b'\t'), 1),
+                        Some('r') => (Some(b'\r'), 1),
+                        Some('0') => (Some(0), 1),
+                        Some('\\') => (Some(b'\\'), 1),
+                        Some('"') => (Some(b'"'), 1),
+                        Some('x') => {
+                            let digits = self.source.get(self.current + 1..self.current + 3);
+                            let byte = digits
+                                .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+                                .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+                            (byte, 3)
+                        }
+                        _ => (None, 0),
+                    };
+                    let Some(byte) = byte else {
+                        // Skip the rest of the literal so scanning goes on after it
+                        while !matches!(self.peek(), Some('"') | None) {
+                            self.advance();
+                        }
+                        self.match_char('"');
+                        return Err(ManoError::Scan {
+                            message: "Escape zoado nos bytes, mano! Usa \\xHH, \\n, \\t, \\r, \\0, \\\\ ou \\\"".to_string(),
+                            span: escape_start..escape_end,
+                        });
+                    };
+                    for _ in 0..len {
+                        self.advance();
+                    }
+                    bytes.push(byte);
+                }
+                c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+    }
+
     /// Continue scanning an interpolated string after a closing }
     fn interpolated_string_continue(&mut self) -> Result<Token, ManoError> {
         let content_start = self.current; // Position after the }
@@ -349,7 +563,7 @@ impl<'a> Scanner<'a> {
                     self.advance();
                     return Ok(Token {
                         token_type: TokenType::StringEnd,
-                        lexeme: self.source[token_start..self.current].to_string(),
+                        lexeme: self.source[token_start..self.current].into(),
                         literal: Some(Literal::String(value)),
                         span: token_start..self.current,
                     });
@@ -367,7 +581,7 @@ impl<'a> Scanner<'a> {
                     self.interpolation_stack.push(0);
                     return Ok(Token {
                         token_type: TokenType::StringMiddle,
-                        lexeme: self.source[token_start..self.current].to_string(),
+                        lexeme: self.source[token_start..self.current].into(),
                         literal: Some(Literal::String(value)),
                         span: token_start..self.current,
                     });
@@ -428,7 +642,7 @@ mod tests {
         let mut scanner = Scanner::new("(");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::LeftParen);
-        assert_eq!(token.lexeme, "(");
+        assert_eq!(&*token.lexeme, "(");
     }
 
     #[test]
@@ -455,12 +669,12 @@ mod tests {
 
         let keyword = scanner.next().unwrap().unwrap();
         assert_eq!(keyword.token_type, TokenType::If);
-        assert_eq!(keyword.lexeme, "sePá");
+        assert_eq!(&*keyword.lexeme, "sePá");
         assert_eq!(keyword.span, 0..5); // 5 bytes for "sePá"
 
         let ident = scanner.next().unwrap().unwrap();
         assert_eq!(ident.token_type, TokenType::Identifier);
-        assert_eq!(ident.lexeme, "x");
+        assert_eq!(&*ident.lexeme, "x");
         assert_eq!(ident.span, 6..7); // "x" starts at byte 6
 
         // Verify we can slice back to the lexeme
@@ -468,6 +682,30 @@ mod tests {
         assert_eq!(&source[ident.span.clone()], "x");
     }
 
+    #[test]
+    fn skips_shebang_line() {
+        let source = "#!/usr/bin/env mano\nsalve 1;";
+        let mut scanner = Scanner::new(source);
+        let token = scanner.next().unwrap().unwrap();
+        assert_eq!(token.token_type, TokenType::Print);
+        assert_eq!(token.span, 20..25);
+    }
+
+    #[test]
+    fn shebang_only_source_returns_eof() {
+        let mut scanner = Scanner::new("#!/usr/bin/env mano");
+        let token = scanner.next().unwrap().unwrap();
+        assert_eq!(token.token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn shebang_must_be_at_very_start() {
+        let mut scanner = Scanner::new(" #!mano");
+        let token = scanner.next().unwrap().unwrap();
+        assert_ne!(token.token_type, TokenType::Eof);
+        assert_eq!(token.span.start, 1);
+    }
+
     #[test]
     fn is_at_end_returns_false_when_not_at_end() {
         let scanner = Scanner::new("(");
@@ -503,6 +741,23 @@ mod tests {
         assert_eq!(third.unwrap().token_type, TokenType::RightParen);
     }
 
+    #[test]
+    fn scans_brackets() {
+        let types: Vec<_> = Scanner::new("l[0]")
+            .map(|token| token.unwrap().token_type)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::Identifier,
+                TokenType::LeftBracket,
+                TokenType::Number,
+                TokenType::RightBracket,
+                TokenType::Eof
+            ]
+        );
+    }
+
     #[test]
     fn scans_all_single_char_tokens() {
         let mut scanner = Scanner::new("(){},.-+;?:*/");
@@ -558,7 +813,45 @@ mod tests {
         let mut scanner = Scanner::new("%");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::Percent);
-        assert_eq!(token.lexeme, "%");
+        assert_eq!(&*token.lexeme, "%");
+    }
+
+    #[test]
+    fn scans_bitwise_operators() {
+        let types: Vec<_> = Scanner::new("& | ^ << >> <= >=")
+            .map(|token| token.unwrap().token_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Ampersand,
+                TokenType::Pipe,
+                TokenType::Caret,
+                TokenType::LessLess,
+                TokenType::GreaterGreater,
+                TokenType::LessEqual,
+                TokenType::GreaterEqual,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn scans_compound_assignment_operators() {
+        let types: Vec<_> = Scanner::new("+= -= *= /= %= // fim")
+            .map(|token| token.unwrap().token_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::PlusEqual,
+                TokenType::MinusEqual,
+                TokenType::StarEqual,
+                TokenType::SlashEqual,
+                TokenType::PercentEqual,
+                TokenType::Eof,
+            ]
+        );
     }
 
     #[test]
@@ -566,7 +859,7 @@ mod tests {
         let mut scanner = Scanner::new("=");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::Equal);
-        assert_eq!(token.lexeme, "=");
+        assert_eq!(&*token.lexeme, "=");
     }
 
     #[test]
@@ -574,7 +867,7 @@ mod tests {
         let mut scanner = Scanner::new("<");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::Less);
-        assert_eq!(token.lexeme, "<");
+        assert_eq!(&*token.lexeme, "<");
     }
 
     #[test]
@@ -582,7 +875,7 @@ mod tests {
         let mut scanner = Scanner::new(">");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::Greater);
-        assert_eq!(token.lexeme, ">");
+        assert_eq!(&*token.lexeme, ">");
     }
 
     #[test]
@@ -590,7 +883,7 @@ mod tests {
         let mut scanner = Scanner::new("!");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::Bang);
-        assert_eq!(token.lexeme, "!");
+        assert_eq!(&*token.lexeme, "!");
     }
 
     #[test]
@@ -598,7 +891,7 @@ mod tests {
         let mut scanner = Scanner::new("!=");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::BangEqual);
-        assert_eq!(token.lexeme, "!=");
+        assert_eq!(&*token.lexeme, "!=");
     }
 
     #[test]
@@ -606,7 +899,7 @@ mod tests {
         let mut scanner = Scanner::new("==");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::EqualEqual);
-        assert_eq!(token.lexeme, "==");
+        assert_eq!(&*token.lexeme, "==");
     }
 
     #[test]
@@ -614,7 +907,7 @@ mod tests {
         let mut scanner = Scanner::new("<=");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::LessEqual);
-        assert_eq!(token.lexeme, "<=");
+        assert_eq!(&*token.lexeme, "<=");
     }
 
     #[test]
@@ -622,7 +915,7 @@ mod tests {
         let mut scanner = Scanner::new(">=");
         let token = scanner.next().unwrap().unwrap();
         assert_eq!(token.token_type, TokenType::GreaterEqual);
-        assert_eq!(token.lexeme, ">=");
+        assert_eq!(&*token.lexeme, ">=");
     }
 
     #[test]
@@ -680,6 +973,17 @@ mod tests {
         assert_eq!(second.span, 23..24); // ")" at position 23 (len of "( // this is a comment\n" is 24)
     }
 
+    #[test]
+    fn directive_lines_are_comments() {
+        let types: Vec<_> = Scanner::with_comments("  #seVm\n1 # 2")
+            .map(|token| token.map(|t| t.token_type))
+            .collect();
+        assert!(matches!(types[0], Ok(TokenType::Comment)));
+        assert!(matches!(types[1], Ok(TokenType::Number)));
+        // Only at the start of a line
+        assert!(!matches!(types[2], Ok(TokenType::Comment)));
+    }
+
     #[test]
     fn comment_at_end_of_file() {
         let mut scanner = Scanner::new("( // comment");
@@ -699,7 +1003,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::String);
-        assert_eq!(token.lexeme, "\"mano\"");
+        assert_eq!(&*token.lexeme, "\"mano\"");
         assert_eq!(token.literal, Some(Literal::String("mano".to_string())));
     }
 
@@ -711,13 +1015,41 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::String);
-        assert_eq!(token.lexeme, "\"e aí mano, beleza?\"");
+        assert_eq!(&*token.lexeme, "\"e aí mano, beleza?\"");
         assert_eq!(
             token.literal,
             Some(Literal::String("e aí mano, beleza?".to_string()))
         );
     }
 
+    #[test]
+    fn scans_bytes_literal_with_escapes() {
+        use crate::token::Literal;
+
+        let mut scanner = Scanner::new(r#"b"aí\x00\xFF\n\"" bala"#);
+        let token = scanner.next().unwrap().unwrap();
+
+        assert_eq!(token.token_type, TokenType::Bytes);
+        assert_eq!(
+            token.literal,
+            Some(Literal::Bytes(vec![b'a', 0xc3, 0xad, 0, 0xff, b'\n', b'"']))
+        );
+        // A plain `b` is still an identifier
+        let token = scanner.next().unwrap().unwrap();
+        assert_eq!(token.token_type, TokenType::Identifier);
+    }
+
+    #[test]
+    fn bad_bytes_escape_skips_the_literal() {
+        let mut scanner = Scanner::new(r#"b"\xZZ oi" 1"#);
+        let Some(Err(ManoError::Scan { span, .. })) = scanner.next() else {
+            panic!("Expected Scan error");
+        };
+        assert_eq!(span, 2..4);
+        let token = scanner.next().unwrap().unwrap();
+        assert_eq!(token.token_type, TokenType::Number);
+    }
+
     #[test]
     fn unterminated_string_returns_error() {
         let mut scanner = Scanner::new("\"esqueceu de fechar");
@@ -770,7 +1102,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Number);
-        assert_eq!(token.lexeme, "1234");
+        assert_eq!(&*token.lexeme, "1234");
         assert_eq!(token.literal, Some(Literal::Number(1234.0)));
     }
 
@@ -780,7 +1112,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Number);
-        assert_eq!(token.lexeme, "12.34");
+        assert_eq!(&*token.lexeme, "12.34");
         assert_eq!(token.literal, Some(Literal::Number(12.34)));
     }
 
@@ -797,6 +1129,24 @@ mod tests {
         assert_eq!(dot.token_type, TokenType::Dot);
     }
 
+    #[test]
+    fn three_dots_are_one_token() {
+        let types: Vec<_> = Scanner::new("...a.. b")
+            .map(|token| token.unwrap().token_type)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::DotDotDot,
+                TokenType::Identifier,
+                TokenType::Dot,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+    }
+
     #[test]
     fn leading_dot_is_not_decimal() {
         // ".1234" should be dot followed by number 1234
@@ -816,7 +1166,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Identifier);
-        assert_eq!(token.lexeme, "meuNome");
+        assert_eq!(&*token.lexeme, "meuNome");
     }
 
     #[test]
@@ -825,7 +1175,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Identifier);
-        assert_eq!(token.lexeme, "_meu_nome_123");
+        assert_eq!(&*token.lexeme, "_meu_nome_123");
     }
 
     #[test]
@@ -834,7 +1184,23 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Identifier);
-        assert_eq!(token.lexeme, "variável");
+        assert_eq!(&*token.lexeme, "variável");
+    }
+
+    #[test]
+    fn normalizes_identifiers_to_nfc() {
+        // 'a' followed by a combining acute accent
+        let decomposed = "varia\u{301}vel";
+        let token = Scanner::new(decomposed).next().unwrap().unwrap();
+        assert_eq!(&*token.lexeme, "variável");
+        assert_eq!(token.span, 0..decomposed.len());
+
+        let keyword = Scanner::new("sePa\u{301}").next().unwrap().unwrap();
+        assert_eq!(keyword.token_type, TokenType::If);
+
+        // Lossless scans keep the text as written
+        let token = Scanner::with_trivia(decomposed).next().unwrap().unwrap();
+        assert_eq!(&*token.lexeme, decomposed);
     }
 
     #[test]
@@ -843,7 +1209,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Identifier);
-        assert_eq!(token.lexeme, "🔥");
+        assert_eq!(&*token.lexeme, "🔥");
     }
 
     #[test]
@@ -852,7 +1218,7 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Identifier);
-        assert_eq!(token.lexeme, "var🚀test");
+        assert_eq!(&*token.lexeme, "var🚀test");
     }
 
     #[test]
@@ -862,7 +1228,49 @@ mod tests {
         let token = scanner.next().unwrap().unwrap();
 
         assert_eq!(token.token_type, TokenType::Var);
-        assert_eq!(token.lexeme, "seLiga");
+        assert_eq!(&*token.lexeme, "seLiga");
+    }
+
+    #[test]
+    fn formal_aliases_only_in_formal_dialect() {
+        let types = |scanner: Scanner| -> Vec<TokenType> {
+            scanner.map(|t| t.unwrap().token_type).collect()
+        };
+        assert_eq!(
+            types(Scanner::new("imprima").with_dialect(Dialect::Formal)),
+            vec![TokenType::Print, TokenType::Eof]
+        );
+        assert_eq!(
+            types(Scanner::new("imprima")),
+            vec![TokenType::Identifier, TokenType::Eof]
+        );
+    }
+
+    #[test]
+    fn lox_dialect_renames_init_to_the_initializer() {
+        let tokens: Vec<_> = Scanner::new("class A { init() {} }")
+            .with_dialect(Dialect::Lox)
+            .map(|t| t.unwrap())
+            .collect();
+        assert_eq!(tokens[0].token_type, TokenType::Class);
+        assert_eq!(&*tokens[3].lexeme, crate::INITIALIZER_NAME);
+        assert_eq!(tokens[3].span, 10..14);
+    }
+
+    #[test]
+    fn pragma_picks_dialect_over_the_configured_one() {
+        let source = "// dialeto: mano\nse";
+        let tokens: Vec<_> = Scanner::new(source)
+            .with_dialect(Dialect::Formal)
+            .map(|t| t.unwrap().token_type)
+            .collect();
+        assert_eq!(tokens, vec![TokenType::Identifier, TokenType::Eof]);
+
+        let token = Scanner::new("// dialeto: formal\nse")
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.token_type, TokenType::If);
     }
 
     #[test]
@@ -951,7 +1359,7 @@ mod tests {
 
         assert_eq!(tokens.len(), 2); // Comment, Eof
         assert_eq!(tokens[0].token_type, TokenType::Comment);
-        assert_eq!(tokens[0].lexeme, "// comentário");
+        assert_eq!(&*tokens[0].lexeme, "// comentário");
     }
 
     #[test]
@@ -961,7 +1369,7 @@ mod tests {
 
         assert_eq!(tokens.len(), 2); // Comment, Eof
         assert_eq!(tokens[0].token_type, TokenType::Comment);
-        assert_eq!(tokens[0].lexeme, "/* bloco */");
+        assert_eq!(&*tokens[0].lexeme, "/* bloco */");
     }
 
     #[test]
@@ -981,7 +1389,54 @@ mod tests {
 
         assert_eq!(tokens.len(), 2); // Comment, Eof
         assert_eq!(tokens[0].token_type, TokenType::Comment);
-        assert_eq!(tokens[0].lexeme, "/* linha 1\nlinha 2 */");
+        assert_eq!(&*tokens[0].lexeme, "/* linha 1\nlinha 2 */");
+    }
+
+    fn reconstruct(source: &str) -> String {
+        Scanner::with_trivia(source)
+            .map(|t| t.unwrap().lexeme.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn with_trivia_emits_whitespace_tokens() {
+        let tokens: Vec<_> = Scanner::with_trivia("salve  \n 1;")
+            .map(|t| t.unwrap())
+            .collect();
+        assert_eq!(tokens[1].token_type, TokenType::Whitespace);
+        assert_eq!(&*tokens[1].lexeme, "  \n ");
+        assert_eq!(tokens[1].span, 5..9);
+    }
+
+    #[test]
+    fn with_trivia_emits_comments() {
+        let tokens: Vec<_> = Scanner::with_trivia("1; // oi")
+            .map(|t| t.unwrap())
+            .collect();
+        assert!(tokens.iter().any(|t| t.token_type == TokenType::Comment));
+    }
+
+    #[test]
+    fn with_trivia_reconstructs_source_exactly() {
+        let source = "// topo\nseLiga x = 1;\r\n\t/* bloco\n */ salve \"oi {x + 1} e {{ {x}\";\n";
+        assert_eq!(reconstruct(source), source);
+    }
+
+    #[test]
+    fn with_trivia_keeps_shebang_as_comment() {
+        let source = "#!/usr/bin/env mano\nsalve 1;";
+        let first = Scanner::with_trivia(source).next().unwrap().unwrap();
+        assert_eq!(first.token_type, TokenType::Comment);
+        assert_eq!(&*first.lexeme, "#!/usr/bin/env mano");
+        assert_eq!(reconstruct(source), source);
+    }
+
+    #[test]
+    fn without_trivia_skips_whitespace() {
+        let tokens: Vec<_> = Scanner::with_comments("salve  1;")
+            .map(|t| t.unwrap())
+            .collect();
+        assert!(tokens.iter().all(|t| t.token_type != TokenType::Whitespace));
     }
 
     #[test]
@@ -1035,7 +1490,7 @@ mod tests {
         );
 
         assert_eq!(tokens[1].token_type, TokenType::Identifier);
-        assert_eq!(tokens[1].lexeme, "nome");
+        assert_eq!(&*tokens[1].lexeme, "nome");
 
         assert_eq!(tokens[2].token_type, TokenType::StringEnd);
         assert_eq!(tokens[2].literal, Some(Literal::String("!".to_string())));
@@ -1055,7 +1510,7 @@ mod tests {
         );
 
         assert_eq!(tokens[1].token_type, TokenType::Identifier);
-        assert_eq!(tokens[1].lexeme, "nome");
+        assert_eq!(&*tokens[1].lexeme, "nome");
 
         assert_eq!(tokens[2].token_type, TokenType::StringMiddle);
         assert_eq!(
@@ -1064,7 +1519,7 @@ mod tests {
         );
 
         assert_eq!(tokens[3].token_type, TokenType::Identifier);
-        assert_eq!(tokens[3].lexeme, "idade");
+        assert_eq!(&*tokens[3].lexeme, "idade");
 
         assert_eq!(tokens[4].token_type, TokenType::StringEnd);
         assert_eq!(
@@ -1080,7 +1535,7 @@ mod tests {
 
         assert_eq!(tokens[0].token_type, TokenType::StringStart);
         assert_eq!(tokens[1].token_type, TokenType::Identifier);
-        assert_eq!(tokens[1].lexeme, "x");
+        assert_eq!(&*tokens[1].lexeme, "x");
         assert_eq!(tokens[2].token_type, TokenType::Star);
         assert_eq!(tokens[3].token_type, TokenType::Number);
         assert_eq!(tokens[4].token_type, TokenType::StringEnd);
@@ -0,0 +1,211 @@
+use crate::analysis::DeclarationId;
+use crate::ast::{Span, Stmt};
+use crate::resolver::Resolver;
+
+/// What kind of name a declaration introduces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Variable,
+    Function,
+    Class,
+    Parameter,
+}
+
+/// A name declared in a scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// Span of the declared name
+    pub span: Span,
+}
+
+/// A lexical scope: the source it covers and the names declared directly in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub span: Span,
+    pub parent: Option<usize>,
+    pub declarations: Vec<Declaration>,
+    /// Whether this is a fita's scope, whose body runs only when it's called
+    pub function: bool,
+}
+
+/// Every scope the resolver walks through, from the global scope down.
+///
+/// Built for tools (the LSP) that need to know which names are visible at a
+/// given offset. Scope 0 is the global scope and covers the whole source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeTree {
+    scopes: Vec<Scope>,
+}
+
+impl Default for ScopeTree {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope {
+                span: 0..usize::MAX,
+                parent: None,
+                declarations: Vec::new(),
+                function: false,
+            }],
+        }
+    }
+}
+
+impl ScopeTree {
+    /// Resolves the statements and collects their scopes, ignoring resolution errors
+    pub fn build(statements: &[Stmt]) -> Self {
+        Resolver::new().scope_tree(statements)
+    }
+
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    pub(crate) fn push(&mut self, span: Span, parent: usize, function: bool) -> usize {
+        self.scopes.push(Scope {
+            span,
+            parent: Some(parent),
+            declarations: Vec::new(),
+            function,
+        });
+        self.scopes.len() - 1
+    }
+
+    pub(crate) fn parent(&self, scope: usize) -> usize {
+        self.scopes[scope].parent.unwrap_or(0)
+    }
+
+    pub(crate) fn declare(&mut self, scope: usize, declaration: Declaration) -> DeclarationId {
+        self.scopes[scope].declarations.push(declaration);
+        DeclarationId {
+            scope,
+            index: self.scopes[scope].declarations.len() - 1,
+        }
+    }
+
+    /// Innermost scope containing the offset
+    pub fn scope_at(&self, offset: usize) -> usize {
+        let depth = |mut scope: usize| {
+            let mut depth = 0;
+            while let Some(parent) = self.scopes[scope].parent {
+                scope = parent;
+                depth += 1;
+            }
+            depth
+        };
+        (0..self.scopes.len())
+            .filter(|&scope| self.scopes[scope].span.contains(&offset))
+            .max_by_key(|&scope| depth(scope))
+            .unwrap_or(0)
+    }
+
+    /// Names visible at the offset, innermost first, with shadowed names left out.
+    ///
+    /// Locals must be declared before the offset. Globals too when the offset
+    /// is outside any function, but inside one they are looked up when the
+    /// function runs, so later globals are visible there.
+    pub fn visible_at(&self, offset: usize) -> Vec<&Declaration> {
+        let mut visible: Vec<&Declaration> = Vec::new();
+        let mut in_function = false;
+        let mut scope = Some(self.scope_at(offset));
+
+        while let Some(index) = scope {
+            let current = &self.scopes[index];
+            in_function |= current.function;
+            let late_bound = current.parent.is_none() && in_function;
+            for declaration in &current.declarations {
+                let declared = late_bound || declaration.span.start < offset;
+                if declared && !visible.iter().any(|seen| seen.name == declaration.name) {
+                    visible.push(declaration);
+                }
+            }
+            scope = current.parent;
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Scanner};
+
+    fn tree(source: &str) -> ScopeTree {
+        let tokens: Vec<_> = Scanner::new(source).filter_map(|r| r.ok()).collect();
+        let statements = Parser::new(tokens).parse().unwrap_or_default();
+        ScopeTree::build(&statements)
+    }
+
+    fn visible_names(source: &str, offset: usize) -> Vec<String> {
+        tree(source)
+            .visible_at(offset)
+            .into_iter()
+            .map(|declaration| declaration.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn globals_are_visible_after_declaration() {
+        let source = "seLiga a = 1; seLiga b = 2;";
+        assert_eq!(visible_names(source, 14), vec!["a"]);
+        assert_eq!(visible_names(source, source.len()), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn block_locals_are_not_visible_outside() {
+        let source = "{ seLiga dentro = 1; } ";
+        assert_eq!(visible_names(source, 20), vec!["dentro"]);
+        assert!(visible_names(source, source.len()).is_empty());
+    }
+
+    #[test]
+    fn function_params_and_locals_are_visible_in_body() {
+        let source = "olhaEssaFita soma(a, b) { seLiga c = a + b; toma c; }";
+        let names = visible_names(source, source.find("toma").unwrap());
+        assert_eq!(names, vec!["a", "b", "c", "soma"]);
+    }
+
+    #[test]
+    fn later_globals_are_visible_inside_functions() {
+        let source = "olhaEssaFita f() { salve 1; } seLiga depois = 1;";
+        let names = visible_names(source, source.find("salve").unwrap());
+        assert!(names.contains(&"depois".to_string()));
+    }
+
+    #[test]
+    fn later_globals_are_not_visible_in_top_level_blocks() {
+        let source = "{ salve 1; } seLiga depois = 1;";
+        let names = visible_names(source, source.find("salve").unwrap());
+        assert!(names.is_empty(), "{names:?}");
+    }
+
+    #[test]
+    fn inner_declaration_shadows_outer() {
+        let source = "seLiga x = 1; { seLiga x = 2; salve x; }";
+        let tree = tree(source);
+        let visible = tree.visible_at(source.find("salve").unwrap());
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].span, 23..24);
+    }
+
+    #[test]
+    fn declarations_record_their_kind() {
+        let tree = tree("bagulho Bicho {} olhaEssaFita f(p) { seLiga _v = p; }");
+        let kinds: Vec<_> = tree
+            .scopes()
+            .iter()
+            .flat_map(|scope| scope.declarations.iter().map(|d| d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DeclarationKind::Class,
+                DeclarationKind::Function,
+                DeclarationKind::Parameter,
+                DeclarationKind::Variable
+            ]
+        );
+    }
+}
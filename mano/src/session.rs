@@ -0,0 +1,176 @@
+//! Saving REPL sessions as mano source.
+//!
+//! A session file is a plain script: loading it is just running it. Literals
+//! become `seLiga` declarations and fitas/bagulhos are written back from the
+//! source they were declared with.
+
+use std::fmt::Write;
+
+use crate::token::Literal;
+use crate::value::{Function, Value};
+
+const HEADER: &str = "// Sessão do mano, carrega com :carregaSessao";
+
+/// Source text of a top-level `olhaEssaFita`/`bagulho` declaration
+#[derive(Debug, Clone)]
+pub(crate) struct SavedDeclaration {
+    pub name: String,
+    pub source: String,
+}
+
+/// Writes the globals back as a script.
+///
+/// `globals` must be sorted by name; `declarations` are in the order they ran,
+/// so subclasses come after their parents.
+pub(crate) fn save(
+    globals: &[(String, Option<Value>)],
+    declarations: &[SavedDeclaration],
+) -> String {
+    let mut variables = String::new();
+    let mut aliases = String::new();
+    let mut skipped = String::new();
+
+    for (name, value) in globals {
+        let declared = |source_name: &str| declarations.iter().any(|d| d.name == source_name);
+        let Some(value) = value else {
+            writeln!(variables, "seLiga {};", name).unwrap();
+            continue;
+        };
+        match value {
+            Value::Literal(literal) => match literal_source(literal) {
+                Some(source) => writeln!(variables, "seLiga {} = {};", name, source).unwrap(),
+                None => writeln!(skipped, "// '{}' ficou de fora: {}", name, literal).unwrap(),
+            },
+            Value::Function(function) if matches!(function.as_ref(), Function::Native(_)) => {}
+            // Written back from its declaration below
+            _ if declares(value, name) && declared(name) => {}
+            // Another name for a declared fita or bagulho
+            _ if declared_name(value).is_some_and(declared) => {
+                let original = declared_name(value).unwrap();
+                writeln!(aliases, "seLiga {} = {};", name, original).unwrap()
+            }
+            // An option of a declared `opcoes` prints as the code that reads it
+            Value::Instance(instance)
+                if instance.variant_name().is_some() && declared(&instance.class.name) =>
+            {
+                writeln!(aliases, "seLiga {} = {};", name, value).unwrap()
+            }
+            _ => writeln!(skipped, "// '{}' ficou de fora: {}", name, value).unwrap(),
+        }
+    }
+
+    let mut script = format!("{}\n", HEADER);
+    script.push_str(&variables);
+    for declaration in declarations {
+        // Only what's still bound to its own name; rebound names were handled above
+        let still_bound = globals.iter().any(|(name, value)| {
+            *name == declaration.name && matches!(value, Some(v) if declares(v, name))
+        });
+        if still_bound {
+            writeln!(script, "{}", declaration.source).unwrap();
+        }
+    }
+    script.push_str(&aliases);
+    script.push_str(&skipped);
+    script
+}
+
+fn declares(value: &Value, name: &str) -> bool {
+    declared_name(value) == Some(name)
+}
+
+/// Name a fita or bagulho was declared with
+fn declared_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::Function(function) => match function.as_ref() {
+            Function::Mano(f) => f.name.as_ref().map(|fita| &*fita.lexeme),
+            Function::Native(_) => None,
+        },
+        Value::Class(class) => Some(&class.name),
+        _ => None,
+    }
+}
+
+/// How to write a literal back, `None` when it can't be read back as the same value
+fn literal_source(literal: &Literal) -> Option<String> {
+    match literal {
+        Literal::Number(n) if !n.is_finite() => None,
+        // Strings keep their escapes as written, so these can't round-trip
+        Literal::String(s) if s.contains(['"', '{', '}', '\\']) => None,
+        Literal::String(s) => Some(format!("\"{}\"", s)),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mano;
+
+    fn saved(source: &str) -> String {
+        let mut mano = Mano::new();
+        let errors = mano.run(source, Vec::new());
+        assert!(errors.is_empty(), "{:?}", errors);
+        mano.save_session()
+    }
+
+    fn reload(script: &str, check: &str) -> String {
+        let mut mano = Mano::new();
+        let errors = mano.run(script, Vec::new());
+        assert!(errors.is_empty(), "{:?}\n{}", errors, script);
+        let mut output = Vec::new();
+        let errors = mano.run(check, &mut output);
+        assert!(errors.is_empty(), "{:?}", errors);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn saves_literals_as_declarations() {
+        let script = saved("seLiga a = 1.5; seLiga b = \"oi\"; seLiga c = firmeza; seLiga d;");
+        assert!(script.contains("seLiga a = 1.5;"));
+        assert!(script.contains("seLiga b = \"oi\";"));
+        assert!(script.contains("seLiga c = firmeza;"));
+        assert!(script.contains("seLiga d;"));
+        assert!(script.starts_with(HEADER));
+    }
+
+    #[test]
+    fn saves_current_value_not_initial_one() {
+        let script = saved("seLiga x = 1; x = x + 41;");
+        assert!(script.contains("seLiga x = 42;"));
+    }
+
+    #[test]
+    fn enum_options_round_trip() {
+        let script = saved("opcoes Cor { Azul, Verde } seLiga favorita = Cor.Verde;");
+        assert!(script.contains("seLiga favorita = Cor.Verde;"));
+        assert_eq!(reload(&script, "salve favorita == Cor.Verde;"), "firmeza\n");
+    }
+
+    #[test]
+    fn fitas_and_bagulhos_round_trip() {
+        let script = saved(
+            "bagulho Bicho { falar() { toma \"...\"; } }
+             bagulho Cachorro < Bicho { falar() { toma \"au\"; } }
+             olhaEssaFita dobro(n) { toma n * 2; }
+             seLiga vezes2 = dobro;",
+        );
+        let output = reload(&script, "salve Cachorro().falar(); salve vezes2(21);");
+        assert_eq!(output, "au\n42\n");
+    }
+
+    #[test]
+    fn rebound_names_drop_their_old_declaration() {
+        let script = saved("olhaEssaFita f() {} f = 3;");
+        assert!(!script.contains("olhaEssaFita"));
+        assert!(script.contains("seLiga f = 3;"));
+    }
+
+    #[test]
+    fn unsaveable_values_are_noted() {
+        let script = saved("bagulho B {} seLiga b = B(); seLiga l = olhaEssaFita () {};");
+        assert!(script.contains("// 'b' ficou de fora: <parada B>"));
+        assert!(script.contains("// 'l' ficou de fora: <fita anônima/0>"));
+        assert!(!script.contains("fazTeuCorre"));
+    }
+}
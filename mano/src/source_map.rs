@@ -0,0 +1,213 @@
+//! Offsets in generated code mapped back to the code the user typed
+//!
+//! The REPL wraps input (`1 + 2` runs as `salve 1 + 2;`) and the formatter
+//! moves code around, so errors from the code that ran point at text the user
+//! never saw. Whoever generates the code builds a [`SourceMap`] along with it
+//! and maps the spans back before reporting them.
+
+use std::ops::Range;
+
+use crate::error::{Frame, ManoError, Related};
+
+/// A piece of generated text copied verbatim from the original
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    generated: usize,
+    original: usize,
+    len: usize,
+}
+
+/// Generated text together with where its pieces came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The map for code that wasn't changed at all
+    pub fn identity(source: &str) -> Self {
+        let mut map = Self::new();
+        map.push_original(source, 0);
+        map
+    }
+
+    /// Adds text that isn't in the original, like the `salve ` of a wrapped input
+    pub fn push_generated(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Adds text copied from the original, found there at `original`
+    pub fn push_original(&mut self, text: &str, original: usize) {
+        if text.is_empty() {
+            return;
+        }
+        self.segments.push(Segment {
+            generated: self.text.len(),
+            original,
+            len: text.len(),
+        });
+        self.text.push_str(text);
+    }
+
+    /// Adds code generated from a piece of the original found at `original`
+    pub fn push_map(&mut self, map: &SourceMap, original: usize) {
+        for segment in &map.segments {
+            self.segments.push(Segment {
+                generated: self.text.len() + segment.generated,
+                original: original + segment.original,
+                len: segment.len,
+            });
+        }
+        self.text.push_str(&map.text);
+    }
+
+    /// The generated code
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    /// Where an offset of the generated code was in the original. Offsets in
+    /// generated text go to the end of the original text before them, or the
+    /// start of the one after when there's none before.
+    pub fn offset(&self, offset: usize) -> usize {
+        let mut before = None;
+        for segment in &self.segments {
+            if offset < segment.generated {
+                return before.unwrap_or(segment.original);
+            }
+            if offset <= segment.generated + segment.len {
+                return segment.original + (offset - segment.generated);
+            }
+            before = Some(segment.original + segment.len);
+        }
+        before.unwrap_or(0)
+    }
+
+    /// Where a span of the generated code was in the original
+    pub fn span(&self, span: &Range<usize>) -> Range<usize> {
+        let start = self.offset(span.start);
+        let end = self.offset(span.end).max(start);
+        start..end
+    }
+
+    /// The error, pointing at the original instead of the generated code
+    pub fn error(&self, error: ManoError) -> ManoError {
+        match error {
+            ManoError::Scan { message, span } => ManoError::Scan {
+                message,
+                span: self.span(&span),
+            },
+            ManoError::Parse { message, span } => ManoError::Parse {
+                message,
+                span: self.span(&span),
+            },
+            ManoError::Runtime { message, span } => ManoError::Runtime {
+                message,
+                span: self.span(&span),
+            },
+            ManoError::Resolution { message, span } => ManoError::Resolution {
+                message,
+                span: self.span(&span),
+            },
+            ManoError::Warning { message, span } => ManoError::Warning {
+                message,
+                span: self.span(&span),
+            },
+            ManoError::Output { source, span } => ManoError::Output {
+                source,
+                span: self.span(&span),
+            },
+            ManoError::Interrupted { span } => ManoError::Interrupted {
+                span: self.span(&span),
+            },
+            ManoError::Detailed {
+                error,
+                related,
+                help,
+                stack,
+            } => ManoError::Detailed {
+                error: Box::new(self.error(*error)),
+                related: related
+                    .into_iter()
+                    .map(|related| Related {
+                        span: self.span(&related.span),
+                        ..related
+                    })
+                    .collect(),
+                help,
+                stack: stack
+                    .into_iter()
+                    .map(|frame| Frame {
+                        span: self.span(&frame.span),
+                        ..frame
+                    })
+                    .collect(),
+            },
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_copied_text_back_and_snaps_generated_text() {
+        // "  1 + oi" wrapped as "salve 1 + oi;"
+        let mut map = SourceMap::new();
+        map.push_generated("salve ");
+        map.push_original("1 + oi", 2);
+        map.push_generated(";");
+        assert_eq!(map.text(), "salve 1 + oi;");
+
+        assert_eq!(map.span(&(10..12)), 6..8);
+        // The `salve` goes to where the input starts, the `;` to where it ends
+        assert_eq!(map.span(&(0..5)), 2..2);
+        assert_eq!(map.span(&(12..13)), 8..8);
+        assert_eq!(map.span(&(0..13)), 2..8);
+    }
+
+    #[test]
+    fn maps_errors_keeping_the_message() {
+        let mut map = SourceMap::new();
+        map.push_generated("seLiga _1 = ");
+        map.push_original("oi", 0);
+        let error = map.error(ManoError::Runtime {
+            message: "Variável 'oi' não existe".to_string(),
+            span: 12..14,
+        });
+        assert!(matches!(
+            error,
+            ManoError::Runtime { message, span } if message.contains("oi") && span == (0..2)
+        ));
+    }
+
+    #[test]
+    fn nested_maps_shift_to_where_their_piece_was() {
+        let mut inner = SourceMap::new();
+        inner.push_generated("salve ");
+        inner.push_original("x", 0);
+        let mut map = SourceMap::new();
+        map.push_original("a; ", 0);
+        map.push_map(&inner, 3);
+        assert_eq!(map.text(), "a; salve x");
+        assert_eq!(map.span(&(9..10)), 3..4);
+    }
+
+    #[test]
+    fn identity_changes_nothing() {
+        let map = SourceMap::identity("salve 1;");
+        assert_eq!(map.text(), "salve 1;");
+        assert_eq!(map.span(&(6..7)), 6..7);
+    }
+}
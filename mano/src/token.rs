@@ -5,6 +5,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -15,6 +17,9 @@ pub enum TokenType {
     Slash,
     Star,
     Percent,
+    Ampersand,
+    Pipe,
+    Caret,
 
     // One or two character tokens
     Bang,
@@ -25,6 +30,14 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    DotDotDot, // ...rest, the last parameter of a fita
 
     // Literals
     String,
@@ -32,29 +45,37 @@ pub enum TokenType {
     StringMiddle, // Middle part of interpolated string (between } and {)
     StringEnd,    // Last part of interpolated string (after last })
     Number,
+    Bytes, // b"..."
     Identifier,
 
-    // Comments (used for highlighting, skipped by parser)
+    // Trivia (used for highlighting and lossless tooling, skipped by parser)
     Comment,
+    Whitespace,
 
     // Keywords
-    And,    // tamoJunto
-    Class,  // bagulho
-    Else,   // vacilou
-    False,  // treta
-    Fun,    // olhaEssaFita
-    For,    // seVira
-    If,     // sePá
-    Nil,    // nadaNão
-    Or,     // ow
-    Print,  // salve, oiSumida
-    Return, // toma
-    Super,  // mestre
-    This,   // oCara
-    True,   // firmeza
-    Var,    // seLiga
-    While,  // segueOFluxo
-    Break,  // saiFora
+    And,      // tamoJunto
+    Class,    // bagulho
+    Else,     // vacilou
+    False,    // treta
+    Fun,      // olhaEssaFita
+    For,      // seVira
+    If,       // sePá
+    Nil,      // nadaNão
+    Or,       // ow
+    Print,    // salve, oiSumida
+    Return,   // toma
+    Super,    // mestre
+    This,     // oCara
+    True,     // firmeza
+    Var,      // seLiga
+    While,    // segueOFluxo
+    Break,    // saiFora
+    Continue, // segueReto
+    Enum,     // opcoes
+    Import,   // chama
+    Try,      // tentaAi
+    Catch,    // seDeuRuim
+    Throw,    // joga
 
     Eof,
 }
@@ -63,16 +84,22 @@ pub enum TokenType {
 pub enum Literal {
     Number(f64),
     String(String),
+    Bytes(Vec<u8>),
     Bool(bool),
     Nil,
 }
 
+/// How many bytes are shown before the preview is cut short
+const BYTES_PREVIEW: usize = 16;
+
 use std::ops::Range;
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    /// Shared, so cloning a token into the tree doesn't copy its text
+    pub lexeme: Rc<str>,
     pub literal: Option<Literal>,
     pub span: Range<usize>,
 }
@@ -86,11 +113,44 @@ impl std::fmt::Display for Token {
     }
 }
 
+impl Literal {
+    /// What kind of value this is, as errors name it
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Literal::Number(_) => "número",
+            Literal::String(_) => "texto",
+            Literal::Bytes(_) => "bytes",
+            Literal::Bool(_) => "lógico",
+            Literal::Nil => "nadaNão",
+        }
+    }
+
+    /// Whether `+` turns this into texto when the other side is texto, with
+    /// text coercion on
+    pub fn joins_text(&self) -> bool {
+        matches!(self, Literal::Number(_) | Literal::Bool(_) | Literal::Nil)
+    }
+}
+
 impl std::fmt::Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::Number(n) => write!(f, "{}", n),
             Literal::String(s) => write!(f, "{}", s),
+            Literal::Bytes(bytes) => {
+                // A hex preview: <bytes 3: 6f 69 0a>
+                write!(f, "<bytes {}", bytes.len())?;
+                if !bytes.is_empty() {
+                    write!(f, ":")?;
+                }
+                for byte in bytes.iter().take(BYTES_PREVIEW) {
+                    write!(f, " {:02x}", byte)?;
+                }
+                if bytes.len() > BYTES_PREVIEW {
+                    write!(f, " …")?;
+                }
+                write!(f, ">")
+            }
             Literal::Bool(true) => write!(f, "firmeza"),
             Literal::Bool(false) => write!(f, "treta"),
             Literal::Nil => write!(f, "nadaNão"),
@@ -106,7 +166,7 @@ mod tests {
     fn token_display_without_literal() {
         let token = Token {
             token_type: TokenType::LeftParen,
-            lexeme: "(".to_string(),
+            lexeme: "(".into(),
             literal: None,
             span: 0..1,
         };
@@ -117,13 +177,26 @@ mod tests {
     fn token_display_with_number() {
         let token = Token {
             token_type: TokenType::LeftParen,
-            lexeme: "42".to_string(),
+            lexeme: "42".into(),
             literal: Some(Literal::Number(42.0)),
             span: 0..2,
         };
         assert_eq!(token.to_string(), "LeftParen 42 42");
     }
 
+    #[test]
+    fn literal_bytes_displays_hex_preview() {
+        assert_eq!(Literal::Bytes(vec![]).to_string(), "<bytes 0>");
+        assert_eq!(
+            Literal::Bytes(b"oi\n".to_vec()).to_string(),
+            "<bytes 3: 6f 69 0a>"
+        );
+        assert_eq!(
+            Literal::Bytes(vec![0xff; 20]).to_string(),
+            format!("<bytes 20:{} …>", " ff".repeat(16))
+        );
+    }
+
     #[test]
     fn literal_bool_true_displays_correctly() {
         let value = Literal::Bool(true);
@@ -146,7 +219,7 @@ mod tests {
     fn token_has_span() {
         let token = Token {
             token_type: TokenType::Number,
-            lexeme: "42".to_string(),
+            lexeme: "42".into(),
             literal: Some(Literal::Number(42.0)),
             span: 0..2,
         };
@@ -1,10 +1,11 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 
 use crate::ast::Stmt;
 use crate::environment::Environment;
+use crate::resolver::Resolutions;
 use crate::token::{Literal, Token};
 
 #[derive(Debug, Clone)]
@@ -13,6 +14,12 @@ pub enum Value {
     Function(Rc<Function>),
     Class(Rc<Class>),
     Instance(Rc<Instance>),
+    /// From `refFraca(parada)`: doesn't keep the instance alive, so a cycle
+    /// through it (a closure stored on the instance it captures) gets freed
+    Weak(Weak<Instance>),
+    /// `[1, 2, 3]`, shared like a parada: changing it through one variable
+    /// shows up in every other one
+    List(Rc<RefCell<Vec<Value>>>),
 }
 
 #[derive(Debug)]
@@ -32,10 +39,13 @@ impl Function {
     }
 }
 
+/// A fita raiz's code: gets the arguments, already checked against the arity
+pub type NativeFn = Rc<dyn Fn(&[Value]) -> Result<Value, crate::error::ManoError>>;
+
 pub struct NativeFunction {
     pub name: String,
     pub arity: usize,
-    pub func: fn(&[Value]) -> Result<Value, crate::error::ManoError>,
+    pub func: NativeFn,
 }
 
 impl fmt::Debug for NativeFunction {
@@ -54,21 +64,66 @@ pub struct ManoFunction {
     pub body: Vec<Stmt>,
     pub closure: Rc<RefCell<Environment>>,
     pub is_getter: bool,
+    pub is_memoized: bool,
+    /// The last parameter takes every argument after the others as a lista
+    pub is_variadic: bool,
+    /// A class's `bora`, which gives back oCara however it's called
+    pub is_initializer: bool,
+    /// Bindings from the run that declared it, since spans are only unique per run
+    pub resolutions: Rc<Resolutions>,
+    /// The parada a method was bound to, `None` for a fita that isn't bound
+    pub receiver: Option<Rc<Instance>>,
 }
 
 impl ManoFunction {
     pub fn bind(&self, instance: Rc<Instance>) -> ManoFunction {
         let mut env = Environment::with_enclosing(Rc::clone(&self.closure));
         // Use slot-based storage for resolution to work
-        env.define_at_slot("oCara".to_string(), Value::Instance(instance));
+        env.define_at_slot("oCara".to_string(), Value::Instance(Rc::clone(&instance)));
         ManoFunction {
             name: self.name.clone(),
             params: self.params.clone(),
             body: self.body.clone(),
             closure: Rc::new(RefCell::new(env)),
             is_getter: self.is_getter,
+            is_memoized: self.is_memoized,
+            is_variadic: self.is_variadic,
+            is_initializer: self.is_initializer,
+            resolutions: Rc::clone(&self.resolutions),
+            receiver: Some(instance),
+        }
+    }
+
+    /// Whether a call with `count` arguments fills the parameters
+    pub fn accepts(&self, count: usize) -> bool {
+        if self.is_variadic {
+            count >= self.params.len() - 1
+        } else {
+            count == self.params.len()
+        }
+    }
+
+    /// How many arguments it takes, as an arity error says it
+    pub fn expected_args(&self) -> String {
+        if self.is_variadic {
+            format!("pelo menos {}", self.params.len() - 1)
+        } else {
+            self.params.len().to_string()
         }
     }
+
+    /// Whether both are the same method bound to the same parada. Each
+    /// `p.falar` binds again, so that's what `p.falar == p.falar` compares.
+    pub fn same_bound_method(&self, other: &ManoFunction) -> bool {
+        let (Some(a), Some(b)) = (&self.receiver, &other.receiver) else {
+            return false;
+        };
+        // A declaration is known by its name's span in the run that declared it
+        Rc::ptr_eq(a, b)
+            && Rc::ptr_eq(&self.resolutions, &other.resolutions)
+            && self.name.as_ref().map(|name| &name.span)
+                == other.name.as_ref().map(|name| &name.span)
+    }
 }
 
 #[derive(Debug)]
@@ -77,6 +132,48 @@ pub struct Class {
     pub superclass: Option<Rc<Class>>,
     pub methods: HashMap<String, Rc<Function>>,
     pub static_methods: HashMap<String, Rc<Function>>,
+    /// The options of an `opcoes` declaration; empty for a plain bagulho
+    pub variants: Variants,
+}
+
+/// The paradas of an `opcoes` declaration, in order, each one frozen with its
+/// `nome` and `ordem`. They point back at their class, so that cycle lives as
+/// long as the declaration.
+#[derive(Default)]
+pub struct Variants(RefCell<Vec<Rc<Instance>>>);
+
+impl Variants {
+    pub fn push(&self, variant: Rc<Instance>) {
+        self.0.borrow_mut().push(variant);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    /// The option called `name`
+    pub fn get(&self, name: &str) -> Option<Rc<Instance>> {
+        self.0
+            .borrow()
+            .iter()
+            .find(|variant| variant.variant_name().as_deref() == Some(name))
+            .cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0
+            .borrow()
+            .iter()
+            .filter_map(|variant| variant.variant_name())
+            .collect()
+    }
+}
+
+impl fmt::Debug for Variants {
+    // The variants' classes are the class being printed, so only their names
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
 }
 
 impl Class {
@@ -115,6 +212,39 @@ impl Class {
 pub struct Instance {
     pub class: Rc<Class>,
     pub fields: RefCell<HashMap<String, Value>>,
+    /// Values of `memoizado` getters, dropped whenever a field is written or
+    /// forgotten
+    pub memos: RefCell<HashMap<String, Value>>,
+    /// Set by `congela(parada)`: its fields can't change anymore
+    pub frozen: Cell<bool>,
+}
+
+impl Instance {
+    /// The option's name, when this is one of the paradas of an `opcoes`
+    pub fn variant_name(&self) -> Option<String> {
+        if self.class.variants.is_empty() {
+            return None;
+        }
+        match self.fields.borrow().get("nome") {
+            Some(Value::Literal(Literal::String(name))) => Some(name.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    /// What kind of value this is, as errors and `queTipoE` name it:
+    /// `número`, `fita`...
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Value::Literal(literal) => literal.kind_name(),
+            Value::Function(_) => "fita",
+            Value::Class(_) => "bagulho",
+            Value::Instance(_) => "parada",
+            Value::Weak(_) => "ref fraca",
+            Value::List(_) => "lista",
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -123,19 +253,58 @@ impl fmt::Display for Value {
             Value::Literal(lit) => write!(f, "{}", lit),
             Value::Function(func) => write!(f, "{}", func),
             Value::Class(class) => write!(f, "<bagulho {}>", class.name),
-            Value::Instance(instance) => write!(f, "<parada {}>", instance.class.name),
+            Value::Instance(instance) => match instance.variant_name() {
+                Some(variant) => write!(f, "{}.{}", instance.class.name, variant),
+                None => write!(f, "<parada {}>", instance.class.name),
+            },
+            Value::Weak(weak) => match weak.upgrade() {
+                Some(instance) => write!(f, "<ref fraca {}>", instance.class.name),
+                None => write!(f, "<ref fraca sumiu>"),
+            },
+            Value::List(list) => write_list(list, f, &mut Vec::new()),
         }
     }
 }
 
+/// Writes `[1, "oi", [2]]`, with texts in quotes so `["1"]` and `[1]` look
+/// different. A list inside itself is written `[...]` instead of forever.
+fn write_list(
+    list: &Rc<RefCell<Vec<Value>>>,
+    f: &mut fmt::Formatter<'_>,
+    open: &mut Vec<*const RefCell<Vec<Value>>>,
+) -> fmt::Result {
+    if open.contains(&Rc::as_ptr(list)) {
+        return write!(f, "[...]");
+    }
+    open.push(Rc::as_ptr(list));
+    write!(f, "[")?;
+    for (i, item) in list.borrow().iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        match item {
+            Value::List(inner) => write_list(inner, f, open)?,
+            Value::Literal(Literal::String(s)) => write!(f, "\"{}\"", s)?,
+            other => write!(f, "{}", other)?,
+        }
+    }
+    open.pop();
+    write!(f, "]")
+}
+
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Function::Mano(func) => match &func.name {
-                Some(name) => write!(f, "<fita caseira {}>", name.lexeme),
-                None => write!(f, "<fita caseira lambda>"),
-            },
-            Function::Native(func) => write!(f, "<fita raiz {}>", func.name),
+            Function::Mano(func) => {
+                let name = func.name.as_ref().map_or("anônima", |name| &name.lexeme);
+                if func.is_variadic {
+                    // Takes at least the parameters before the `...` one
+                    write!(f, "<fita {}/{}+>", name, func.params.len() - 1)
+                } else {
+                    write!(f, "<fita {}/{}>", name, func.params.len())
+                }
+            }
+            Function::Native(func) => write!(f, "<fita raiz {}/{}>", func.name, func.arity),
         }
     }
 }
@@ -150,8 +319,18 @@ impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Literal(a), Value::Literal(b)) => a == b,
-            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Function(a), Value::Function(b)) => {
+                Rc::ptr_eq(a, b)
+                    || matches!(
+                        (a.as_ref(), b.as_ref()),
+                        (Function::Mano(a), Function::Mano(b)) if a.same_bound_method(b)
+                    )
+            }
             (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            (Value::Weak(a), Value::Weak(b)) => a.ptr_eq(b),
+            // The same list, not just the same items, like paradas
+            (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -160,7 +339,7 @@ impl PartialEq for Value {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
 
     use crate::environment::Environment;
@@ -177,30 +356,45 @@ mod tests {
         let func = ManoFunction {
             name: Some(Token {
                 token_type: TokenType::Identifier,
-                lexeme: "cumprimentar".to_string(),
+                lexeme: "cumprimentar".into(),
                 literal: None,
                 span: 0..12,
             }),
-            params: vec![],
+            params: vec![Token {
+                token_type: TokenType::Identifier,
+                lexeme: "nome".into(),
+                literal: None,
+                span: 13..17,
+            }],
             body: vec![],
             closure: Rc::new(RefCell::new(Environment::new())),
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
+            is_initializer: false,
+            resolutions: Rc::default(),
+            receiver: None,
         };
         let value = Value::Function(Rc::new(Function::Mano(func)));
-        assert_eq!(value.to_string(), "<fita caseira cumprimentar>");
+        assert_eq!(value.to_string(), "<fita cumprimentar/1>");
     }
 
     #[test]
-    fn lambda_displays_as_fita_lambda() {
+    fn lambda_displays_as_fita_anonima() {
         let func = ManoFunction {
             name: None,
             params: vec![],
             body: vec![],
             closure: Rc::new(RefCell::new(Environment::new())),
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
+            is_initializer: false,
+            resolutions: Rc::default(),
+            receiver: None,
         };
         let value = Value::Function(Rc::new(Function::Mano(func)));
-        assert_eq!(value.to_string(), "<fita caseira lambda>");
+        assert_eq!(value.to_string(), "<fita anônima/0>");
     }
 
     #[test]
@@ -208,10 +402,10 @@ mod tests {
         let func = NativeFunction {
             name: "fazTeuCorre".to_string(),
             arity: 0,
-            func: |_| Ok(Value::Literal(Literal::Number(0.0))),
+            func: Rc::new(|_| Ok(Value::Literal(Literal::Number(0.0)))),
         };
         let value = Value::Function(Rc::new(Function::Native(func)));
-        assert_eq!(value.to_string(), "<fita raiz fazTeuCorre>");
+        assert_eq!(value.to_string(), "<fita raiz fazTeuCorre/0>");
     }
 
     #[test]
@@ -226,7 +420,7 @@ mod tests {
         let func = Rc::new(Function::Mano(ManoFunction {
             name: Some(Token {
                 token_type: TokenType::Identifier,
-                lexeme: "test".to_string(),
+                lexeme: "test".into(),
                 literal: None,
                 span: 0..4,
             }),
@@ -234,6 +428,11 @@ mod tests {
             body: vec![],
             closure: Rc::new(RefCell::new(Environment::new())),
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
+            is_initializer: false,
+            resolutions: Rc::default(),
+            receiver: None,
         }));
         let a = Value::Function(Rc::clone(&func));
         let b = Value::Function(Rc::clone(&func));
@@ -253,7 +452,7 @@ mod tests {
             Rc::new(Function::Mano(ManoFunction {
                 name: Some(Token {
                     token_type: TokenType::Identifier,
-                    lexeme: "test".to_string(),
+                    lexeme: "test".into(),
                     literal: None,
                     span: 0..4,
                 }),
@@ -261,6 +460,11 @@ mod tests {
                 body: vec![],
                 closure: Rc::new(RefCell::new(Environment::new())),
                 is_getter: false,
+                is_memoized: false,
+                is_variadic: false,
+                is_initializer: false,
+                resolutions: Rc::default(),
+                receiver: None,
             }))
         };
         let a = Value::Function(make_func());
@@ -277,6 +481,11 @@ mod tests {
             body: vec![],
             closure: Rc::new(RefCell::new(Environment::new())),
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
+            is_initializer: false,
+            resolutions: Rc::default(),
+            receiver: None,
         })));
         assert_ne!(literal, func);
     }
@@ -286,7 +495,7 @@ mod tests {
         let func = NativeFunction {
             name: "fazTeuCorre".to_string(),
             arity: 0,
-            func: |_| Ok(Value::Literal(Literal::Number(0.0))),
+            func: Rc::new(|_| Ok(Value::Literal(Literal::Number(0.0)))),
         };
         let debug_str = format!("{:?}", func);
         assert!(debug_str.contains("fazTeuCorre"));
@@ -300,6 +509,7 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         };
         let value = Value::Class(Rc::new(class));
         assert_eq!(value.to_string(), "<bagulho Pessoa>");
@@ -312,6 +522,7 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let a = Value::Class(Rc::clone(&class));
         let b = Value::Class(Rc::clone(&class));
@@ -326,6 +537,7 @@ mod tests {
                 superclass: None,
                 methods: HashMap::new(),
                 static_methods: HashMap::new(),
+                variants: Variants::default(),
             })
         };
         let a = Value::Class(make_class());
@@ -340,12 +552,14 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let child = Class {
             name: "Filho".to_string(),
             superclass: Some(Rc::clone(&parent)),
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         };
         assert!(child.superclass.is_some());
         assert_eq!(child.superclass.unwrap().name, "Pai");
@@ -358,10 +572,13 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         };
         let value = Value::Instance(Rc::new(instance));
         assert_eq!(value.to_string(), "<parada Pessoa>");
@@ -374,10 +591,13 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         };
         assert!(instance.fields.borrow().is_empty());
     }
@@ -389,10 +609,13 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         };
         instance.fields.borrow_mut().insert(
             "nome".to_string(),
@@ -412,16 +635,19 @@ mod tests {
             superclass: None,
             methods: HashMap::new(),
             static_methods: HashMap::new(),
+            variants: Variants::default(),
         });
         let instance = Rc::new(Instance {
             class: Rc::clone(&class),
             fields: RefCell::new(HashMap::new()),
+            memos: RefCell::default(),
+            frozen: Cell::default(),
         });
 
         let func = ManoFunction {
             name: Some(Token {
                 token_type: TokenType::Identifier,
-                lexeme: "falar".to_string(),
+                lexeme: "falar".into(),
                 literal: None,
                 span: 0..5,
             }),
@@ -429,6 +655,11 @@ mod tests {
             body: vec![],
             closure: Rc::new(RefCell::new(Environment::new())),
             is_getter: false,
+            is_memoized: false,
+            is_variadic: false,
+            is_initializer: false,
+            resolutions: Rc::default(),
+            receiver: None,
         };
 
         let bound = func.bind(Rc::clone(&instance));